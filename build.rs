@@ -0,0 +1,4 @@
+// Build lại khi migrations thay đổi, vì `sqlx::migrate!` embed thư mục `migrations/` lúc compile
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+}
@@ -0,0 +1,3 @@
+CREATE TYPE "public"."duplicate_policy" AS ENUM('allow', 'collapse', 'reject');--> statement-breakpoint
+ALTER TABLE "conversations" ADD COLUMN "duplicate_policy" "duplicate_policy" DEFAULT 'collapse' NOT NULL;--> statement-breakpoint
+ALTER TABLE "messages" ADD COLUMN "repeat_count" integer DEFAULT 1 NOT NULL;
@@ -0,0 +1,13 @@
+CREATE TABLE "client_metadata" (
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    "user_id" uuid NOT NULL,
+    "message_id" uuid,
+    "session_id" uuid,
+    "app_version" varchar(32),
+    "platform" varchar(32),
+    "created_at" timestamptz NOT NULL DEFAULT NOW(),
+    CONSTRAINT "client_metadata_user_id_users_id_fk" FOREIGN KEY ("user_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action,
+    CONSTRAINT "client_metadata_message_id_messages_id_fk" FOREIGN KEY ("message_id") REFERENCES "public"."messages"("id") ON DELETE cascade ON UPDATE no action
+);--> statement-breakpoint
+CREATE INDEX "idx_client_metadata_user_id" ON "client_metadata" USING btree ("user_id","created_at");--> statement-breakpoint
+CREATE INDEX "idx_client_metadata_message_id" ON "client_metadata" USING btree ("message_id");
@@ -0,0 +1,3 @@
+ALTER TABLE "users" ADD COLUMN "email_verified" boolean DEFAULT false NOT NULL;--> statement-breakpoint
+-- Tài khoản đã tồn tại trước khi có xác thực email được coi là đã xác thực
+UPDATE "users" SET "email_verified" = true;
@@ -0,0 +1,20 @@
+CREATE TYPE "public"."history_visibility" AS ENUM('full', 'since_joined');--> statement-breakpoint
+CREATE TYPE "public"."group_creation_policy" AS ENUM('everyone', 'admins');--> statement-breakpoint
+CREATE TABLE "conversation_defaults" (
+	"id" smallint PRIMARY KEY DEFAULT 1 NOT NULL,
+	"history_visibility" "history_visibility" DEFAULT 'full' NOT NULL,
+	"message_ttl_seconds" integer,
+	"group_creation" "group_creation_policy" DEFAULT 'everyone' NOT NULL,
+	"max_group_size" integer DEFAULT 100 NOT NULL,
+	"allow_history_override" boolean DEFAULT true NOT NULL,
+	"allow_ttl_override" boolean DEFAULT true NOT NULL,
+	"updated_at" timestamptz DEFAULT now() NOT NULL,
+	CONSTRAINT "conversation_defaults_singleton" CHECK ("conversation_defaults"."id" = 1),
+	CONSTRAINT "conversation_defaults_ttl_positive" CHECK ("conversation_defaults"."message_ttl_seconds" IS NULL OR "conversation_defaults"."message_ttl_seconds" > 0),
+	CONSTRAINT "conversation_defaults_group_size" CHECK ("conversation_defaults"."max_group_size" >= 2)
+);
+--> statement-breakpoint
+INSERT INTO "conversation_defaults" ("id") VALUES (1);--> statement-breakpoint
+ALTER TABLE "conversations" ADD COLUMN "history_visibility" "history_visibility" DEFAULT 'full' NOT NULL;--> statement-breakpoint
+ALTER TABLE "conversations" ADD COLUMN "message_ttl_seconds" integer;--> statement-breakpoint
+ALTER TABLE "conversations" ADD CONSTRAINT "conversations_ttl_positive" CHECK ("conversations"."message_ttl_seconds" IS NULL OR "conversations"."message_ttl_seconds" > 0);
@@ -0,0 +1,11 @@
+CREATE TABLE "oauth_accounts" (
+	"provider" varchar(32) NOT NULL,
+	"provider_user_id" varchar(255) NOT NULL,
+	"user_id" uuid NOT NULL,
+	"email" varchar(255) NOT NULL,
+	"created_at" timestamptz DEFAULT now() NOT NULL,
+	CONSTRAINT "oauth_accounts_provider_provider_user_id_pk" PRIMARY KEY("provider","provider_user_id")
+);
+--> statement-breakpoint
+ALTER TABLE "oauth_accounts" ADD CONSTRAINT "oauth_accounts_user_id_users_id_fk" FOREIGN KEY ("user_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+CREATE INDEX "idx_oauth_accounts_user" ON "oauth_accounts" USING btree ("user_id");
@@ -0,0 +1,2 @@
+ALTER TABLE "users" ADD COLUMN "banned_at" timestamptz;--> statement-breakpoint
+ALTER TABLE "users" ADD COLUMN "ban_reason" varchar(500);
@@ -0,0 +1,5 @@
+CREATE TYPE "public"."participant_status" AS ENUM('active', 'pending');--> statement-breakpoint
+ALTER TABLE "participants" ADD COLUMN "status" "participant_status" DEFAULT 'active' NOT NULL;--> statement-breakpoint
+ALTER TABLE "participants" ADD COLUMN "invited_by" uuid;--> statement-breakpoint
+ALTER TABLE "participants" ADD CONSTRAINT "participants_invited_by_users_id_fk" FOREIGN KEY ("invited_by") REFERENCES "public"."users"("id") ON DELETE set null ON UPDATE no action;--> statement-breakpoint
+CREATE INDEX "idx_participants_user_pending" ON "participants" USING btree ("user_id") WHERE "participants"."status" = 'pending' AND "participants"."deleted_at" is null;
@@ -0,0 +1,21 @@
+CREATE TYPE "public"."report_target_type" AS ENUM('message', 'user');--> statement-breakpoint
+CREATE TYPE "public"."report_status" AS ENUM('open', 'resolved', 'dismissed');--> statement-breakpoint
+CREATE TABLE "reports" (
+	"id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+	"reporter_id" uuid NOT NULL,
+	"target_type" "report_target_type" NOT NULL,
+	"target_id" uuid NOT NULL,
+	"reason" varchar(500) NOT NULL,
+	"status" "report_status" DEFAULT 'open' NOT NULL,
+	"resolved_by" uuid,
+	"resolution_note" varchar(500),
+	"created_at" timestamptz DEFAULT now() NOT NULL,
+	"resolved_at" timestamptz,
+	CONSTRAINT "reports_reporter_target_unique" UNIQUE("reporter_id","target_type","target_id")
+);
+--> statement-breakpoint
+ALTER TABLE "reports" ADD CONSTRAINT "reports_reporter_id_users_id_fk" FOREIGN KEY ("reporter_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "reports" ADD CONSTRAINT "reports_resolved_by_users_id_fk" FOREIGN KEY ("resolved_by") REFERENCES "public"."users"("id") ON DELETE set null ON UPDATE no action;--> statement-breakpoint
+CREATE INDEX "idx_reports_target" ON "reports" USING btree ("target_type","target_id");--> statement-breakpoint
+CREATE INDEX "idx_reports_status_created" ON "reports" USING btree ("status","created_at");--> statement-breakpoint
+ALTER TABLE "messages" ADD COLUMN "hidden_at" timestamptz;
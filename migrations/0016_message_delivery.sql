@@ -0,0 +1,3 @@
+ALTER TABLE "participants" ADD COLUMN "last_delivered_message_id" uuid;--> statement-breakpoint
+ALTER TABLE "participants" ADD COLUMN "last_delivered_at" timestamptz;--> statement-breakpoint
+ALTER TABLE "participants" ADD CONSTRAINT "participants_last_delivered_message_id_messages_id_fk" FOREIGN KEY ("last_delivered_message_id") REFERENCES "public"."messages"("id") ON DELETE set null ON UPDATE no action;
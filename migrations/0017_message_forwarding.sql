@@ -0,0 +1,2 @@
+ALTER TABLE "messages" ADD COLUMN "forwarded_from" uuid;--> statement-breakpoint
+ALTER TABLE "messages" ADD CONSTRAINT "messages_forwarded_from_messages_id_fk" FOREIGN KEY ("forwarded_from") REFERENCES "public"."messages"("id") ON DELETE set null ON UPDATE no action;
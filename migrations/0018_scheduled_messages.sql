@@ -0,0 +1,19 @@
+CREATE TYPE "public"."scheduled_message_status" AS ENUM('pending', 'sent', 'cancelled', 'failed');--> statement-breakpoint
+CREATE TABLE "scheduled_messages" (
+	"id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+	"conversation_id" uuid NOT NULL,
+	"sender_id" uuid NOT NULL,
+	"content" text NOT NULL,
+	"scheduled_at" timestamptz NOT NULL,
+	"status" "scheduled_message_status" DEFAULT 'pending' NOT NULL,
+	"message_id" uuid,
+	"error" text,
+	"created_at" timestamptz DEFAULT now() NOT NULL,
+	"updated_at" timestamptz DEFAULT now() NOT NULL
+);
+--> statement-breakpoint
+ALTER TABLE "scheduled_messages" ADD CONSTRAINT "scheduled_messages_conversation_id_conversations_id_fk" FOREIGN KEY ("conversation_id") REFERENCES "public"."conversations"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "scheduled_messages" ADD CONSTRAINT "scheduled_messages_sender_id_users_id_fk" FOREIGN KEY ("sender_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "scheduled_messages" ADD CONSTRAINT "scheduled_messages_message_id_messages_id_fk" FOREIGN KEY ("message_id") REFERENCES "public"."messages"("id") ON DELETE set null ON UPDATE no action;--> statement-breakpoint
+CREATE INDEX "idx_scheduled_messages_due" ON "scheduled_messages" USING btree ("scheduled_at") WHERE "status" = 'pending';--> statement-breakpoint
+CREATE INDEX "idx_scheduled_messages_conversation_sender" ON "scheduled_messages" USING btree ("conversation_id","sender_id","scheduled_at");
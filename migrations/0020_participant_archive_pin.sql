@@ -0,0 +1,3 @@
+ALTER TABLE "participants" ADD COLUMN "is_archived" boolean DEFAULT false NOT NULL;--> statement-breakpoint
+ALTER TABLE "participants" ADD COLUMN "is_pinned" boolean DEFAULT false NOT NULL;--> statement-breakpoint
+ALTER TABLE "participants" ADD COLUMN "pinned_at" timestamptz;
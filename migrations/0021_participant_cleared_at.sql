@@ -0,0 +1 @@
+ALTER TABLE "participants" ADD COLUMN "cleared_at" timestamptz;
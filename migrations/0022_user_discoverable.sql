@@ -0,0 +1,3 @@
+ALTER TABLE "users" ADD COLUMN "discoverable" boolean DEFAULT true NOT NULL;--> statement-breakpoint
+CREATE INDEX "idx_user_email_hash" ON "users" USING btree (encode(sha256(lower("email")::bytea), 'hex'));--> statement-breakpoint
+CREATE INDEX "idx_user_phone_hash" ON "users" USING btree (encode(sha256(regexp_replace("phone", '[^0-9+]', '', 'g')::bytea), 'hex')) WHERE "phone" IS NOT NULL;
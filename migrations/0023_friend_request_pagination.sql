@@ -0,0 +1,3 @@
+CREATE INDEX "idx_friend_requests_to_user_created" ON "friend_requests" USING btree ("to_user_id","created_at" DESC);--> statement-breakpoint
+CREATE INDEX "idx_friend_requests_from_user_created" ON "friend_requests" USING btree ("from_user_id","created_at" DESC);--> statement-breakpoint
+CREATE INDEX "idx_friend_requests_created" ON "friend_requests" USING btree ("created_at");
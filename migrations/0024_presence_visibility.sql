@@ -0,0 +1,2 @@
+CREATE TYPE "public"."presence_visibility" AS ENUM('everyone', 'friends', 'nobody');--> statement-breakpoint
+ALTER TABLE "users" ADD COLUMN "presence_visibility" "presence_visibility" DEFAULT 'everyone' NOT NULL;
@@ -0,0 +1,5 @@
+CREATE TABLE "event_outbox" (
+	"id" bigserial PRIMARY KEY NOT NULL,
+	"payload" jsonb NOT NULL,
+	"created_at" timestamptz DEFAULT now() NOT NULL
+);
@@ -0,0 +1,4 @@
+ALTER TABLE "conversation_defaults" ADD COLUMN "message_retention_days" integer;--> statement-breakpoint
+ALTER TABLE "conversation_defaults" ADD CONSTRAINT "conversation_defaults_retention_positive" CHECK ("conversation_defaults"."message_retention_days" IS NULL OR "conversation_defaults"."message_retention_days" > 0);--> statement-breakpoint
+ALTER TABLE "participants" DROP CONSTRAINT "participants_last_seen_message_id_messages_id_fk";--> statement-breakpoint
+ALTER TABLE "participants" ADD CONSTRAINT "participants_last_seen_message_id_messages_id_fk" FOREIGN KEY ("last_seen_message_id") REFERENCES "public"."messages"("id") ON DELETE set null ON UPDATE no action;
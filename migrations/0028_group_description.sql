@@ -0,0 +1 @@
+ALTER TABLE "group_conversations" ADD COLUMN "description" varchar(1000);
@@ -0,0 +1,26 @@
+ALTER TYPE "public"."user_role" ADD VALUE 'BOT';--> statement-breakpoint
+CREATE TYPE "public"."bot_scope" AS ENUM('send_messages', 'read_messages');--> statement-breakpoint
+CREATE TABLE "bots" (
+	"user_id" uuid PRIMARY KEY NOT NULL,
+	"owner_id" uuid NOT NULL,
+	"created_at" timestamptz DEFAULT now() NOT NULL
+);
+--> statement-breakpoint
+CREATE TABLE "bot_api_keys" (
+	"id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+	"bot_id" uuid NOT NULL,
+	"name" varchar(100) NOT NULL,
+	"key_prefix" varchar(16) NOT NULL,
+	"key_hash" varchar(64) NOT NULL,
+	"scopes" "bot_scope"[] NOT NULL,
+	"created_at" timestamptz DEFAULT now() NOT NULL,
+	"last_used_at" timestamptz,
+	"revoked_at" timestamptz,
+	CONSTRAINT "bot_api_keys_key_hash_unique" UNIQUE("key_hash")
+);
+--> statement-breakpoint
+ALTER TABLE "bots" ADD CONSTRAINT "bots_user_id_users_id_fk" FOREIGN KEY ("user_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "bots" ADD CONSTRAINT "bots_owner_id_users_id_fk" FOREIGN KEY ("owner_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "bot_api_keys" ADD CONSTRAINT "bot_api_keys_bot_id_bots_user_id_fk" FOREIGN KEY ("bot_id") REFERENCES "public"."bots"("user_id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+CREATE INDEX "idx_bots_owner" ON "bots" USING btree ("owner_id");--> statement-breakpoint
+CREATE INDEX "idx_bot_api_keys_bot" ON "bot_api_keys" USING btree ("bot_id");
@@ -0,0 +1,31 @@
+CREATE TYPE "public"."webhook_delivery_status" AS ENUM('pending', 'delivered', 'failed');--> statement-breakpoint
+CREATE TABLE "conversation_webhooks" (
+	"id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+	"conversation_id" uuid NOT NULL,
+	"url" text NOT NULL,
+	"secret" varchar(64) NOT NULL,
+	"created_by" uuid,
+	"created_at" timestamptz DEFAULT now() NOT NULL
+);
+--> statement-breakpoint
+CREATE TABLE "webhook_deliveries" (
+	"id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+	"webhook_id" uuid NOT NULL,
+	"message_id" uuid NOT NULL,
+	"payload" jsonb NOT NULL,
+	"status" "webhook_delivery_status" DEFAULT 'pending' NOT NULL,
+	"attempts" integer DEFAULT 0 NOT NULL,
+	"next_attempt_at" timestamptz DEFAULT now() NOT NULL,
+	"response_status" integer,
+	"last_error" varchar(500),
+	"created_at" timestamptz DEFAULT now() NOT NULL,
+	"delivered_at" timestamptz
+);
+--> statement-breakpoint
+ALTER TABLE "conversation_webhooks" ADD CONSTRAINT "conversation_webhooks_conversation_id_conversations_id_fk" FOREIGN KEY ("conversation_id") REFERENCES "public"."conversations"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "conversation_webhooks" ADD CONSTRAINT "conversation_webhooks_created_by_users_id_fk" FOREIGN KEY ("created_by") REFERENCES "public"."users"("id") ON DELETE set null ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "webhook_deliveries" ADD CONSTRAINT "webhook_deliveries_webhook_id_conversation_webhooks_id_fk" FOREIGN KEY ("webhook_id") REFERENCES "public"."conversation_webhooks"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "webhook_deliveries" ADD CONSTRAINT "webhook_deliveries_message_id_messages_id_fk" FOREIGN KEY ("message_id") REFERENCES "public"."messages"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+CREATE INDEX "idx_conversation_webhooks_conversation" ON "conversation_webhooks" USING btree ("conversation_id");--> statement-breakpoint
+CREATE INDEX "idx_webhook_deliveries_due" ON "webhook_deliveries" USING btree ("next_attempt_at") WHERE "webhook_deliveries"."status" = 'pending';--> statement-breakpoint
+CREATE INDEX "idx_webhook_deliveries_webhook_created" ON "webhook_deliveries" USING btree ("webhook_id","created_at" DESC);
@@ -0,0 +1,20 @@
+CREATE TABLE "incoming_webhooks" (
+	"id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+	"conversation_id" uuid NOT NULL,
+	"name" varchar(100) NOT NULL,
+	"token_prefix" varchar(16) NOT NULL,
+	"token_hash" varchar(64) NOT NULL,
+	"created_by" uuid NOT NULL,
+	"created_at" timestamptz DEFAULT now() NOT NULL,
+	"last_used_at" timestamptz,
+	"revoked_at" timestamptz,
+	CONSTRAINT "incoming_webhooks_token_hash_unique" UNIQUE("token_hash")
+);
+--> statement-breakpoint
+ALTER TABLE "messages" ADD COLUMN "incoming_webhook_id" uuid;--> statement-breakpoint
+ALTER TABLE "messages" ADD COLUMN "sender_name_override" varchar(80);--> statement-breakpoint
+ALTER TABLE "messages" ADD COLUMN "sender_avatar_override" text;--> statement-breakpoint
+ALTER TABLE "incoming_webhooks" ADD CONSTRAINT "incoming_webhooks_conversation_id_conversations_id_fk" FOREIGN KEY ("conversation_id") REFERENCES "public"."conversations"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "incoming_webhooks" ADD CONSTRAINT "incoming_webhooks_created_by_users_id_fk" FOREIGN KEY ("created_by") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "messages" ADD CONSTRAINT "messages_incoming_webhook_id_incoming_webhooks_id_fk" FOREIGN KEY ("incoming_webhook_id") REFERENCES "public"."incoming_webhooks"("id") ON DELETE set null ON UPDATE no action;--> statement-breakpoint
+CREATE INDEX "idx_incoming_webhooks_conversation" ON "incoming_webhooks" USING btree ("conversation_id");
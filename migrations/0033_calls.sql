@@ -0,0 +1,23 @@
+CREATE TYPE "public"."call_type" AS ENUM('audio', 'video');--> statement-breakpoint
+CREATE TYPE "public"."call_status" AS ENUM('ringing', 'ongoing', 'ended', 'missed', 'declined');--> statement-breakpoint
+CREATE TABLE "calls" (
+	"id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+	"conversation_id" uuid NOT NULL,
+	"caller_id" uuid NOT NULL,
+	"callee_id" uuid NOT NULL,
+	"type" "call_type" NOT NULL,
+	"status" "call_status" DEFAULT 'ringing' NOT NULL,
+	"created_at" timestamptz DEFAULT now() NOT NULL,
+	"answered_at" timestamptz,
+	"ended_at" timestamptz,
+	"ended_by" uuid,
+	"duration_secs" integer
+);
+--> statement-breakpoint
+ALTER TABLE "calls" ADD CONSTRAINT "calls_conversation_id_conversations_id_fk" FOREIGN KEY ("conversation_id") REFERENCES "public"."conversations"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "calls" ADD CONSTRAINT "calls_caller_id_users_id_fk" FOREIGN KEY ("caller_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "calls" ADD CONSTRAINT "calls_callee_id_users_id_fk" FOREIGN KEY ("callee_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "calls" ADD CONSTRAINT "calls_ended_by_users_id_fk" FOREIGN KEY ("ended_by") REFERENCES "public"."users"("id") ON DELETE set null ON UPDATE no action;--> statement-breakpoint
+CREATE INDEX "idx_calls_caller_created" ON "calls" USING btree ("caller_id","created_at" DESC);--> statement-breakpoint
+CREATE INDEX "idx_calls_callee_created" ON "calls" USING btree ("callee_id","created_at" DESC);--> statement-breakpoint
+CREATE INDEX "idx_calls_ringing" ON "calls" USING btree ("created_at") WHERE "calls"."status" = 'ringing';
@@ -0,0 +1,27 @@
+ALTER TYPE "public"."user_role" ADD VALUE 'GUEST';--> statement-breakpoint
+CREATE TABLE "guest_invites" (
+	"id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+	"conversation_id" uuid NOT NULL,
+	"token_prefix" varchar(16) NOT NULL,
+	"token_hash" varchar(64) NOT NULL,
+	"created_by" uuid NOT NULL,
+	"expires_at" timestamptz NOT NULL,
+	"created_at" timestamptz DEFAULT now() NOT NULL,
+	"revoked_at" timestamptz,
+	CONSTRAINT "guest_invites_token_hash_unique" UNIQUE("token_hash")
+);
+--> statement-breakpoint
+CREATE TABLE "guests" (
+	"user_id" uuid PRIMARY KEY NOT NULL,
+	"conversation_id" uuid NOT NULL,
+	"invite_id" uuid,
+	"created_at" timestamptz DEFAULT now() NOT NULL
+);
+--> statement-breakpoint
+ALTER TABLE "guest_invites" ADD CONSTRAINT "guest_invites_conversation_id_conversations_id_fk" FOREIGN KEY ("conversation_id") REFERENCES "public"."conversations"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "guest_invites" ADD CONSTRAINT "guest_invites_created_by_users_id_fk" FOREIGN KEY ("created_by") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "guests" ADD CONSTRAINT "guests_user_id_users_id_fk" FOREIGN KEY ("user_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "guests" ADD CONSTRAINT "guests_conversation_id_conversations_id_fk" FOREIGN KEY ("conversation_id") REFERENCES "public"."conversations"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "guests" ADD CONSTRAINT "guests_invite_id_guest_invites_id_fk" FOREIGN KEY ("invite_id") REFERENCES "public"."guest_invites"("id") ON DELETE set null ON UPDATE no action;--> statement-breakpoint
+CREATE INDEX "idx_guest_invites_conversation" ON "guest_invites" USING btree ("conversation_id");--> statement-breakpoint
+CREATE INDEX "idx_guests_conversation" ON "guests" USING btree ("conversation_id");
@@ -0,0 +1,23 @@
+ALTER TYPE "public"."message_type" ADD VALUE 'encrypted';--> statement-breakpoint
+CREATE TYPE "public"."ciphertext_kind" AS ENUM('prekey', 'message');--> statement-breakpoint
+ALTER TABLE "messages" ADD COLUMN "ciphertext" text;--> statement-breakpoint
+ALTER TABLE "messages" ADD COLUMN "ciphertext_kind" "ciphertext_kind";--> statement-breakpoint
+CREATE TABLE "identity_keys" (
+	"user_id" uuid PRIMARY KEY NOT NULL,
+	"identity_key" text NOT NULL,
+	"signed_prekey_id" integer NOT NULL,
+	"signed_prekey" text NOT NULL,
+	"signed_prekey_signature" text NOT NULL,
+	"updated_at" timestamptz DEFAULT now() NOT NULL
+);
+--> statement-breakpoint
+CREATE TABLE "one_time_prekeys" (
+	"user_id" uuid NOT NULL,
+	"key_id" integer NOT NULL,
+	"public_key" text NOT NULL,
+	"created_at" timestamptz DEFAULT now() NOT NULL,
+	CONSTRAINT "one_time_prekeys_user_id_key_id_pk" PRIMARY KEY("user_id","key_id")
+);
+--> statement-breakpoint
+ALTER TABLE "identity_keys" ADD CONSTRAINT "identity_keys_user_id_users_id_fk" FOREIGN KEY ("user_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "one_time_prekeys" ADD CONSTRAINT "one_time_prekeys_user_id_users_id_fk" FOREIGN KEY ("user_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;
@@ -0,0 +1,11 @@
+CREATE TABLE "hidden_messages" (
+	"user_id" uuid NOT NULL,
+	"message_id" uuid NOT NULL,
+	"created_at" timestamptz DEFAULT now() NOT NULL,
+	CONSTRAINT "hidden_messages_user_id_message_id_pk" PRIMARY KEY("user_id","message_id")
+);
+--> statement-breakpoint
+ALTER TABLE "hidden_messages" ADD CONSTRAINT "hidden_messages_user_id_users_id_fk" FOREIGN KEY ("user_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "hidden_messages" ADD CONSTRAINT "hidden_messages_message_id_messages_id_fk" FOREIGN KEY ("message_id") REFERENCES "public"."messages"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+DROP INDEX "idx_message_conversation";--> statement-breakpoint
+CREATE INDEX "idx_message_conversation" ON "messages" USING btree ("conversation_id","created_at" DESC NULLS LAST);
@@ -0,0 +1,20 @@
+CREATE TABLE "announcements" (
+	"id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+	"title" varchar(200) NOT NULL,
+	"content" text NOT NULL,
+	"created_by" uuid,
+	"created_at" timestamptz DEFAULT now() NOT NULL,
+	"expires_at" timestamptz
+);
+--> statement-breakpoint
+CREATE TABLE "announcement_acks" (
+	"announcement_id" uuid NOT NULL,
+	"user_id" uuid NOT NULL,
+	"acknowledged_at" timestamptz DEFAULT now() NOT NULL,
+	CONSTRAINT "announcement_acks_announcement_id_user_id_pk" PRIMARY KEY("announcement_id","user_id")
+);
+--> statement-breakpoint
+ALTER TABLE "announcements" ADD CONSTRAINT "announcements_created_by_users_id_fk" FOREIGN KEY ("created_by") REFERENCES "public"."users"("id") ON DELETE set null ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "announcement_acks" ADD CONSTRAINT "announcement_acks_announcement_id_announcements_id_fk" FOREIGN KEY ("announcement_id") REFERENCES "public"."announcements"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "announcement_acks" ADD CONSTRAINT "announcement_acks_user_id_users_id_fk" FOREIGN KEY ("user_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+CREATE INDEX "idx_announcements_created_at" ON "announcements" USING btree ("created_at" DESC NULLS LAST);
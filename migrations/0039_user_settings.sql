@@ -0,0 +1,12 @@
+CREATE TYPE "public"."field_visibility" AS ENUM('everyone', 'friends', 'nobody');--> statement-breakpoint
+CREATE TYPE "public"."friend_request_policy" AS ENUM('everyone', 'friends_of_friends', 'nobody');--> statement-breakpoint
+CREATE TABLE "user_settings" (
+	"user_id" uuid PRIMARY KEY NOT NULL,
+	"email_visibility" "field_visibility" DEFAULT 'everyone' NOT NULL,
+	"phone_visibility" "field_visibility" DEFAULT 'everyone' NOT NULL,
+	"bio_visibility" "field_visibility" DEFAULT 'everyone' NOT NULL,
+	"friend_request_policy" "friend_request_policy" DEFAULT 'everyone' NOT NULL,
+	"updated_at" timestamptz DEFAULT now() NOT NULL
+);
+--> statement-breakpoint
+ALTER TABLE "user_settings" ADD CONSTRAINT "user_settings_user_id_users_id_fk" FOREIGN KEY ("user_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;
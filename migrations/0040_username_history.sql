@@ -0,0 +1,10 @@
+CREATE TABLE "username_history" (
+	"id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+	"user_id" uuid NOT NULL,
+	"username" varchar(255) NOT NULL,
+	"changed_at" timestamptz DEFAULT now() NOT NULL
+);
+--> statement-breakpoint
+ALTER TABLE "username_history" ADD CONSTRAINT "username_history_user_id_users_id_fk" FOREIGN KEY ("user_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+CREATE INDEX "idx_username_history_username" ON "username_history" USING btree (lower("username"),"changed_at");--> statement-breakpoint
+CREATE INDEX "idx_username_history_user" ON "username_history" USING btree ("user_id","changed_at");
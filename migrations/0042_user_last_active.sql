@@ -0,0 +1,3 @@
+ALTER TABLE "users" ADD COLUMN "last_active_at" timestamptz;--> statement-breakpoint
+ALTER TABLE "users" ADD COLUMN "inactive_flagged_at" timestamptz;--> statement-breakpoint
+CREATE INDEX "idx_user_last_active" ON "users" USING btree (COALESCE("last_active_at", "created_at")) WHERE "users"."deleted_at" is null;
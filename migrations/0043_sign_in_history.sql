@@ -0,0 +1,11 @@
+CREATE TABLE "sign_in_history" (
+	"id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+	"user_id" uuid NOT NULL,
+	"ip_address" varchar(64) NOT NULL,
+	"user_agent" text,
+	"created_at" timestamptz DEFAULT now() NOT NULL
+);
+--> statement-breakpoint
+ALTER TABLE "sign_in_history" ADD CONSTRAINT "sign_in_history_user_id_users_id_fk" FOREIGN KEY ("user_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+CREATE INDEX "idx_sign_in_history_user" ON "sign_in_history" USING btree ("user_id","created_at");--> statement-breakpoint
+CREATE INDEX "idx_sign_in_history_user_ip" ON "sign_in_history" USING btree ("user_id","ip_address");
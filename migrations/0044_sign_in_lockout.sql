@@ -0,0 +1,3 @@
+CREATE TYPE "public"."sign_in_outcome" AS ENUM('success', 'locked');--> statement-breakpoint
+ALTER TABLE "sign_in_history" ADD COLUMN "outcome" "sign_in_outcome" DEFAULT 'success' NOT NULL;--> statement-breakpoint
+ALTER TABLE "sign_in_history" ALTER COLUMN "ip_address" DROP NOT NULL;
@@ -0,0 +1,29 @@
+-- Messages cũ được chuyển sang "messages_archive" (partition theo tháng của created_at).
+-- Khi thêm cột vào "messages", thêm cùng cột vào "messages_archive" và tạo lại view "messages_all".
+CREATE TABLE "messages_archive" (LIKE "messages" INCLUDING DEFAULTS) PARTITION BY RANGE ("created_at");--> statement-breakpoint
+ALTER TABLE "messages_archive" ADD CONSTRAINT "messages_archive_id_created_at_pk" PRIMARY KEY("id","created_at");--> statement-breakpoint
+ALTER TABLE "messages_archive" ADD CONSTRAINT "messages_archive_conversation_id_conversations_id_fk" FOREIGN KEY ("conversation_id") REFERENCES "public"."conversations"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "messages_archive" ADD CONSTRAINT "messages_archive_sender_id_users_id_fk" FOREIGN KEY ("sender_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+CREATE INDEX "idx_messages_archive_conversation" ON "messages_archive" USING btree ("conversation_id","created_at" DESC NULLS LAST);--> statement-breakpoint
+CREATE INDEX "idx_message_created_at" ON "messages" USING btree ("created_at");--> statement-breakpoint
+-- Trạng thái ẩn của user được giữ lại khi message chuyển sang archive
+ALTER TABLE "hidden_messages" DROP CONSTRAINT "hidden_messages_message_id_messages_id_fk";--> statement-breakpoint
+CREATE VIEW "messages_all" AS
+    SELECT * FROM "messages"
+    UNION ALL
+    SELECT * FROM "messages_archive";--> statement-breakpoint
+-- Tạo partition tháng (UTC) chứa `month` nếu chưa có
+CREATE FUNCTION "create_messages_archive_partition"("month" timestamptz) RETURNS void AS $$
+DECLARE
+    start_at timestamptz := date_trunc('month', "month", 'UTC');
+    end_at timestamptz := start_at + interval '1 month';
+    partition_name text := 'messages_archive_' || to_char(start_at AT TIME ZONE 'UTC', 'YYYY_MM');
+BEGIN
+    EXECUTE format(
+        'CREATE TABLE IF NOT EXISTS %I PARTITION OF "messages_archive" FOR VALUES FROM (%L) TO (%L)',
+        partition_name,
+        start_at,
+        end_at
+    );
+END;
+$$ LANGUAGE plpgsql;
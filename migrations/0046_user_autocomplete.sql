@@ -0,0 +1,3 @@
+CREATE EXTENSION IF NOT EXISTS "pg_trgm";--> statement-breakpoint
+CREATE INDEX "idx_user_username_prefix" ON "users" USING btree (lower("username") text_pattern_ops) WHERE "users"."deleted_at" IS NULL AND "users"."deactivated_at" IS NULL;--> statement-breakpoint
+CREATE INDEX "idx_user_display_name_trgm" ON "users" USING gin (lower("display_name") gin_trgm_ops) WHERE "users"."deleted_at" IS NULL AND "users"."deactivated_at" IS NULL;
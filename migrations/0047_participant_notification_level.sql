@@ -0,0 +1,2 @@
+CREATE TYPE "public"."notification_level" AS ENUM('all', 'mentions', 'nothing');--> statement-breakpoint
+ALTER TABLE "participants" ADD COLUMN "notification_level" "notification_level" DEFAULT 'all' NOT NULL;
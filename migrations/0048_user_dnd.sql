@@ -0,0 +1,14 @@
+ALTER TABLE "user_settings" ADD COLUMN "dnd_start" time;--> statement-breakpoint
+ALTER TABLE "user_settings" ADD COLUMN "dnd_end" time;--> statement-breakpoint
+ALTER TABLE "user_settings" ADD COLUMN "dnd_timezone" text;--> statement-breakpoint
+ALTER TABLE "user_settings" ADD COLUMN "dnd_show_badge" boolean DEFAULT false NOT NULL;--> statement-breakpoint
+-- Giờ hiện tại theo "tz" nằm trong khung [start_at, end_at), khung qua nửa đêm khi start_at > end_at
+CREATE FUNCTION "dnd_active"("start_at" time, "end_at" time, "tz" text) RETURNS boolean AS $$
+    SELECT CASE
+        WHEN "start_at" IS NULL OR "end_at" IS NULL OR "tz" IS NULL THEN false
+        WHEN "start_at" < "end_at" THEN
+            (NOW() AT TIME ZONE "tz")::time >= "start_at" AND (NOW() AT TIME ZONE "tz")::time < "end_at"
+        ELSE
+            (NOW() AT TIME ZONE "tz")::time >= "start_at" OR (NOW() AT TIME ZONE "tz")::time < "end_at"
+    END;
+$$ LANGUAGE sql STABLE;
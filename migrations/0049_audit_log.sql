@@ -0,0 +1,26 @@
+CREATE TYPE "public"."audit_action" AS ENUM('new_sign_in_ip');--> statement-breakpoint
+CREATE TABLE "audit_log" (
+	"id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+	"user_id" uuid,
+	"action" "audit_action" NOT NULL,
+	"ip_address" varchar(64),
+	"user_agent" text,
+	"created_at" timestamptz DEFAULT now() NOT NULL
+);
+--> statement-breakpoint
+CREATE TYPE "public"."notification_kind" AS ENUM('new_sign_in');--> statement-breakpoint
+CREATE TABLE "notifications" (
+	"id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+	"user_id" uuid NOT NULL,
+	"kind" "notification_kind" NOT NULL,
+	"title" varchar(200) NOT NULL,
+	"body" text NOT NULL,
+	"read_at" timestamptz,
+	"created_at" timestamptz DEFAULT now() NOT NULL
+);
+--> statement-breakpoint
+ALTER TABLE "audit_log" ADD CONSTRAINT "audit_log_user_id_users_id_fk" FOREIGN KEY ("user_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+ALTER TABLE "notifications" ADD CONSTRAINT "notifications_user_id_users_id_fk" FOREIGN KEY ("user_id") REFERENCES "public"."users"("id") ON DELETE cascade ON UPDATE no action;--> statement-breakpoint
+CREATE INDEX "idx_audit_log_user" ON "audit_log" USING btree ("user_id","created_at");--> statement-breakpoint
+CREATE INDEX "idx_audit_log_created_at" ON "audit_log" USING btree ("created_at" DESC NULLS LAST);--> statement-breakpoint
+CREATE INDEX "idx_notifications_user" ON "notifications" USING btree ("user_id","created_at");
@@ -0,0 +1,2 @@
+ALTER TYPE "public"."audit_action" ADD VALUE 'sign_in_locked';--> statement-breakpoint
+ALTER TYPE "public"."notification_kind" ADD VALUE 'account_locked';
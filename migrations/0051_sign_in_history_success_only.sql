@@ -0,0 +1,6 @@
+INSERT INTO "audit_log" ("user_id", "action", "ip_address", "user_agent", "created_at")
+SELECT "user_id", 'sign_in_locked', "ip_address", "user_agent", "created_at"
+FROM "sign_in_history" WHERE "outcome" = 'locked';--> statement-breakpoint
+DELETE FROM "sign_in_history" WHERE "outcome" = 'locked';--> statement-breakpoint
+ALTER TABLE "sign_in_history" DROP COLUMN "outcome";--> statement-breakpoint
+DROP TYPE "public"."sign_in_outcome";
@@ -0,0 +1,3 @@
+ALTER TABLE "guest_invites" ADD COLUMN "max_uses" integer DEFAULT 1 NOT NULL;--> statement-breakpoint
+ALTER TABLE "guest_invites" ADD COLUMN "use_count" integer DEFAULT 0 NOT NULL;--> statement-breakpoint
+UPDATE "guest_invites" gi SET "use_count" = (SELECT count(*) FROM "guests" g WHERE g."invite_id" = gi."id");
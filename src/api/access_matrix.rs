@@ -0,0 +1,1014 @@
+/// Authorization Matrix
+///
+/// Registry khai báo mức truy cập mong đợi của từng HTTP handler. Test suite:
+/// - Duyệt mọi operation trong OpenAPI spec (`ApiDoc`, sinh từ `#[utoipa::path]` của các
+///   handlers được mount) và yêu cầu mỗi operation phải có mặt trong registry (endpoint mới
+///   bắt buộc khai báo access level); ngược lại mỗi route trong registry phải được document
+/// - Gọi từng route trên app thật (`api_routes` mount dưới `/api/v1`) với anonymous,
+///   token giả mạo, user, admin, bot (API key) và guest, kiểm tra authentication/authorization
+///   middleware
+/// - Gọi từng route `Access::Member` với user không thuộc group (services dùng repository
+///   mocks), kiểm tra membership check của service / middleware
+use std::{collections::BTreeSet, sync::Arc};
+
+use actix::Actor;
+use actix_web::{
+    http::{Method, StatusCode},
+    test as actix_test, web, App,
+};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use uuid::Uuid;
+
+use crate::{
+    api::{
+        error,
+        version::{self, ApiVersion},
+    },
+    configs::{
+        cache::MemoryCache,
+        uow::{MemoryUnitOfWork, UnitOfWork},
+    },
+    constants::init_test_env,
+    middlewares::{ApiKeyResolver, TokenRevocation, API_KEY_HEADER},
+    modules::{
+        bot::schema::BotScope,
+        conversation::{
+            repository::ConversationRepository,
+            repository_mock::ConversationRepositoryMock,
+            service::{ConversationService, ConversationServiceDeps},
+        },
+        guest::{
+            model::InsertGuest, repository::GuestRepository, schema::GuestInviteEntity,
+            service::GuestService,
+        },
+        message::{
+            command::CommandRegistry,
+            repository_mock::MessageRepositoryMock,
+            service::{DirectMessagePolicy, MessageService, MessageServiceDeps},
+        },
+        notification::queue::PushQueue,
+        report::{moderation::ContentFilter, repository_pg::ReportRepositoryPg},
+        user::{repository_mock::UserRepositoryMock, schema::UserRole},
+        webhook::{
+            delivery::WebhookQueue,
+            model::{ClaimedDelivery, GroupAccess},
+            repository::WebhookRepository,
+            schema::{IncomingWebhookEntity, WebhookDeliveryEntity, WebhookEntity},
+            service::WebhookService,
+        },
+        websocket::{
+            dispatcher::EventOutbox,
+            message::{SenderInfo, SenderResolver},
+            server::WebSocketServer,
+        },
+    },
+    utils::{Claims, TypeClaims},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Access {
+    /// Không cần token
+    Public,
+    /// Bất kỳ user đã đăng nhập (User hoặc Admin)
+    Authenticated,
+    /// User đã đăng nhập và là member của conversation (kiểm tra trong service/middleware)
+    Member,
+    /// Chỉ Admin
+    Admin,
+    /// Chỉ bot principal (API key), JWT của user / admin bị từ chối
+    Bot,
+    /// Chỉ guest principal (token của guest), JWT của user / admin bị từ chối
+    Guest,
+}
+
+struct RouteSpec {
+    /// `<module>::<handler>`
+    handler: &'static str,
+    method: Method,
+    /// Path mẫu dưới `/api/v1`, path params đã được điền giá trị hợp lệ
+    path: &'static str,
+    access: Access,
+}
+
+const ID: &str = "0190a5b4-0000-7000-8000-000000000001";
+
+fn route(handler: &'static str, method: Method, path: &'static str, access: Access) -> RouteSpec {
+    RouteSpec { handler, method, path, access }
+}
+
+fn registry() -> Vec<RouteSpec> {
+    use Access::*;
+
+    vec![
+        // oauth
+        route("oauth::oauth_start", Method::GET, "/auth/oauth/google/start", Public),
+        route("oauth::oauth_callback", Method::GET, "/auth/oauth/google/callback", Public),
+        // auth
+        route("user::sign_up", Method::POST, "/auth/signup", Public),
+        route("user::sign_in", Method::POST, "/auth/signin", Public),
+        route("user::sign_out", Method::GET, "/auth/signout", Public),
+        route("user::refresh", Method::POST, "/auth/refresh", Public),
+        route("user::forgot_password", Method::POST, "/auth/forgot-password", Public),
+        route("user::reset_password", Method::POST, "/auth/reset-password", Public),
+        route("user::verify_email", Method::GET, "/auth/verify-email", Public),
+        route("user::resend_verification", Method::POST, "/auth/resend-verification", Public),
+        route("user::confirm_sign_in", Method::POST, "/auth/confirm-sign-in", Public),
+        route("user::change_password", Method::POST, "/auth/change-password", Authenticated),
+        // users
+        route("user::get_profile", Method::GET, "/users/profile", Authenticated),
+        route("user::get_user", Method::GET, "/users/{id}", Authenticated),
+        route("user::update_user", Method::PATCH, "/users/{id}", Authenticated),
+        route("user::upload_avatar", Method::POST, "/users/me/avatar", Authenticated),
+        route("user::delete_user", Method::DELETE, "/users/{id}", Authenticated),
+        route("user::deactivate_user", Method::POST, "/users/me/deactivate", Authenticated),
+        route("user::search_users", Method::GET, "/users/search", Authenticated),
+        route("user::autocomplete_users", Method::GET, "/users/autocomplete", Authenticated),
+        route("user::check_username", Method::GET, "/users/username-available", Authenticated),
+        route("user::lookup_contacts", Method::POST, "/users/lookup", Authenticated),
+        route("user::get_presence", Method::POST, "/users/presence", Authenticated),
+        route(
+            "user::get_presence_settings",
+            Method::GET,
+            "/users/settings/presence",
+            Authenticated,
+        ),
+        route(
+            "user::update_presence_settings",
+            Method::PUT,
+            "/users/settings/presence",
+            Authenticated,
+        ),
+        route("user::get_settings", Method::GET, "/users/me/settings", Authenticated),
+        route("user::update_settings", Method::PATCH, "/users/me/settings", Authenticated),
+        route("user::get_dnd", Method::GET, "/users/me/dnd", Authenticated),
+        route("user::set_dnd", Method::PUT, "/users/me/dnd", Authenticated),
+        route("user::clear_dnd", Method::DELETE, "/users/me/dnd", Authenticated),
+        route("user::list_sign_ins", Method::GET, "/users/me/sign-ins", Authenticated),
+        // friends
+        route("friend::send_friend_request", Method::POST, "/friends/requests", Authenticated),
+        route(
+            "friend::accept_friend_request",
+            Method::POST,
+            "/friends/requests/{id}/accept",
+            Authenticated,
+        ),
+        route(
+            "friend::decline_friend_request",
+            Method::POST,
+            "/friends/requests/{id}/decline",
+            Authenticated,
+        ),
+        route(
+            "friend::cancel_friend_request",
+            Method::DELETE,
+            "/friends/requests/{id}",
+            Authenticated,
+        ),
+        route("friend::list_friends", Method::GET, "/friends/", Authenticated),
+        route("friend::list_friend_requests", Method::GET, "/friends/requests", Authenticated),
+        route(
+            "friend::list_friend_suggestions",
+            Method::GET,
+            "/friends/suggestions",
+            Authenticated,
+        ),
+        route("friend::list_blocked_users", Method::GET, "/friends/blocks", Authenticated),
+        route("friend::block_user", Method::POST, "/friends/blocks/{id}", Authenticated),
+        route("friend::unblock_user", Method::DELETE, "/friends/blocks/{id}", Authenticated),
+        route("friend::remove_friend", Method::DELETE, "/friends/{id}", Authenticated),
+        // conversations
+        route("conversation::get_conversations", Method::GET, "/conversations", Authenticated),
+        route("conversation::create_conversation", Method::POST, "/conversations", Authenticated),
+        route("conversation::get_messages", Method::GET, "/conversations/{id}/messages", Member),
+        route(
+            "conversation::get_messages_around",
+            Method::GET,
+            "/conversations/{id}/messages/around/{id}",
+            Member,
+        ),
+        route("conversation::export_messages", Method::GET, "/conversations/{id}/export", Member),
+        route(
+            "conversation::mark_as_seen",
+            Method::POST,
+            "/conversations/{id}/mark-as-seen",
+            Member,
+        ),
+        route(
+            "conversation::update_duplicate_policy",
+            Method::PUT,
+            "/conversations/{id}/duplicate-policy",
+            Member,
+        ),
+        route(
+            "conversation::update_conversation_settings",
+            Method::PUT,
+            "/conversations/{id}/settings",
+            Member,
+        ),
+        route("conversation::mute_conversation", Method::PUT, "/conversations/{id}/mute", Member),
+        route("conversation::delete_conversation", Method::DELETE, "/conversations/{id}", Member),
+        route("conversation::update_group", Method::PATCH, "/conversations/{id}", Member),
+        route(
+            "conversation::archive_conversation",
+            Method::PUT,
+            "/conversations/{id}/archive",
+            Member,
+        ),
+        route("conversation::pin_conversation", Method::PUT, "/conversations/{id}/pin", Member),
+        route(
+            "conversation::update_notification_level",
+            Method::PATCH,
+            "/conversations/{id}/notification-level",
+            Member,
+        ),
+        route("conversation::get_draft", Method::GET, "/conversations/{id}/draft", Member),
+        route("conversation::save_draft", Method::PUT, "/conversations/{id}/draft", Member),
+        route("conversation::add_members", Method::POST, "/conversations/{id}/members", Member),
+        route("webhook::create_webhook", Method::POST, "/conversations/{id}/webhooks", Member),
+        route("webhook::list_webhooks", Method::GET, "/conversations/{id}/webhooks", Member),
+        route(
+            "webhook::delete_webhook",
+            Method::DELETE,
+            "/conversations/{id}/webhooks/{id}",
+            Member,
+        ),
+        route(
+            "webhook::create_incoming_webhook",
+            Method::POST,
+            "/conversations/{id}/incoming-webhooks",
+            Member,
+        ),
+        route(
+            "webhook::list_incoming_webhooks",
+            Method::GET,
+            "/conversations/{id}/incoming-webhooks",
+            Member,
+        ),
+        route(
+            "webhook::revoke_incoming_webhook",
+            Method::DELETE,
+            "/conversations/{id}/incoming-webhooks/{id}",
+            Member,
+        ),
+        route("webhook::post_incoming_webhook", Method::POST, "/webhooks/whin_test", Public),
+        route("conversation::get_invites", Method::GET, "/conversations/invites", Authenticated),
+        route(
+            "conversation::accept_invite",
+            Method::POST,
+            "/conversations/invites/{id}",
+            Authenticated,
+        ),
+        route(
+            "conversation::decline_invite",
+            Method::DELETE,
+            "/conversations/invites/{id}",
+            Authenticated,
+        ),
+        // messages
+        route("message::send_direct_message", Method::POST, "/messages/direct/", Authenticated),
+        route(
+            "message::send_encrypted_message",
+            Method::POST,
+            "/messages/direct/encrypted",
+            Authenticated,
+        ),
+        route("message::send_group_message", Method::POST, "/messages/group/", Member),
+        route("message::delete_message", Method::DELETE, "/messages/{id}", Authenticated),
+        route("message::edit_message", Method::PATCH, "/messages/{id}", Authenticated),
+        route("message::forward_message", Method::POST, "/messages/{id}/forward", Authenticated),
+        route(
+            "message::translate_message",
+            Method::POST,
+            "/messages/{id}/translate",
+            Authenticated,
+        ),
+        route("message::broadcast_message", Method::POST, "/messages/broadcast", Authenticated),
+        route("message::schedule_message", Method::POST, "/messages/scheduled", Member),
+        route("message::get_scheduled_messages", Method::GET, "/messages/scheduled", Member),
+        route(
+            "message::cancel_scheduled_message",
+            Method::DELETE,
+            "/messages/scheduled/{id}",
+            Authenticated,
+        ),
+        // reports
+        route("report::create_report", Method::POST, "/reports", Authenticated),
+        // devices
+        route("notification::register_device", Method::POST, "/devices", Authenticated),
+        route("notification::list_devices", Method::GET, "/devices", Authenticated),
+        route("notification::remove_device", Method::DELETE, "/devices/{id}", Authenticated),
+        // notifications
+        route("notification::list_notifications", Method::GET, "/notifications", Authenticated),
+        route(
+            "notification::mark_notification_read",
+            Method::POST,
+            "/notifications/{id}/read",
+            Authenticated,
+        ),
+        // bots
+        route("bot::create_bot", Method::POST, "/bots", Authenticated),
+        route("bot::list_bots", Method::GET, "/bots", Authenticated),
+        route("bot::delete_bot", Method::DELETE, "/bots/{id}", Authenticated),
+        route("bot::create_api_key", Method::POST, "/bots/{id}/keys", Authenticated),
+        route("bot::list_api_keys", Method::GET, "/bots/{id}/keys", Authenticated),
+        route("bot::revoke_api_key", Method::DELETE, "/bots/{id}/keys/{id}", Authenticated),
+        route("bot::send_message", Method::POST, "/bot/conversations/{id}/messages", Bot),
+        route("bot::get_messages", Method::GET, "/bot/conversations/{id}/messages", Bot),
+        // guests
+        route(
+            "guest::create_guest_invite",
+            Method::POST,
+            "/conversations/{id}/guest-invites",
+            Member,
+        ),
+        route(
+            "guest::list_guest_invites",
+            Method::GET,
+            "/conversations/{id}/guest-invites",
+            Member,
+        ),
+        route(
+            "guest::revoke_guest_invite",
+            Method::DELETE,
+            "/conversations/{id}/guest-invites/{id}",
+            Member,
+        ),
+        route("guest::guest_sign_in", Method::POST, "/guests/session", Public),
+        route("guest::send_message", Method::POST, "/guest/messages", Guest),
+        route("guest::get_messages", Method::GET, "/guest/messages", Guest),
+        // calls
+        route("call::get_call_history", Method::GET, "/calls", Authenticated),
+        // keys
+        route("keys::upload_key_bundle", Method::PUT, "/keys", Authenticated),
+        route("keys::upload_prekeys", Method::POST, "/keys/prekeys", Authenticated),
+        route("keys::get_prekey_count", Method::GET, "/keys/prekeys/count", Authenticated),
+        route("keys::get_key_bundle", Method::GET, "/keys/{id}", Authenticated),
+        // announcements
+        route("announcement::get_announcements", Method::GET, "/announcements", Authenticated),
+        route(
+            "announcement::acknowledge_announcement",
+            Method::POST,
+            "/announcements/{id}/ack",
+            Authenticated,
+        ),
+        // search
+        route("search::search", Method::GET, "/search", Authenticated),
+        // events (long-poll)
+        route("event::poll_events", Method::GET, "/events/poll", Authenticated),
+        // files
+        route("file_upload::upload_file", Method::POST, "/upload", Authenticated),
+        route("file_upload::get_file", Method::GET, "/{id}", Authenticated),
+        route("file_upload::delete_file", Method::DELETE, "/{id}", Authenticated),
+        // admin
+        route("user::list_users", Method::GET, "/admin/users", Admin),
+        route("user::merge_accounts", Method::POST, "/admin/users/merge", Admin),
+        route("user::ban_user", Method::POST, "/admin/users/{id}/ban", Admin),
+        route("user::unban_user", Method::DELETE, "/admin/users/{id}/ban", Admin),
+        route("user::force_sign_out", Method::POST, "/admin/users/{id}/signout", Admin),
+        route("user::platform_stats", Method::GET, "/admin/stats", Admin),
+        route(
+            "conversation::get_conversation_defaults",
+            Method::GET,
+            "/admin/conversation-defaults",
+            Admin,
+        ),
+        route(
+            "conversation::update_conversation_defaults",
+            Method::PUT,
+            "/admin/conversation-defaults",
+            Admin,
+        ),
+        route(
+            "conversation::reconcile_unread_counts",
+            Method::POST,
+            "/admin/unread-counts/reconcile",
+            Admin,
+        ),
+        route("report::list_reports", Method::GET, "/admin/reports", Admin),
+        route("report::get_report", Method::GET, "/admin/reports/{id}", Admin),
+        route("report::resolve_report", Method::POST, "/admin/reports/{id}/resolve", Admin),
+        route("announcement::create_announcement", Method::POST, "/admin/announcements", Admin),
+        route("announcement::list_announcements", Method::GET, "/admin/announcements", Admin),
+        route(
+            "message::get_message_client_metadata",
+            Method::GET,
+            "/admin/client-metadata/messages/{id}",
+            Admin,
+        ),
+        route(
+            "message::get_user_client_metadata",
+            Method::GET,
+            "/admin/client-metadata/users/{id}",
+            Admin,
+        ),
+        route("audit::list_audit_log", Method::GET, "/admin/audit-log", Admin),
+    ]
+}
+
+/// Stub revocation: không có token nào bị revoke (không cần Redis)
+struct NeverRevoked;
+
+#[async_trait::async_trait]
+impl TokenRevocation for NeverRevoked {
+    async fn is_token_revoked(&self, _claims: &Claims) -> Result<bool, error::SystemError> {
+        Ok(false)
+    }
+
+    async fn revoke_all_tokens(&self, _user_id: Uuid) -> Result<(), error::SystemError> {
+        Ok(())
+    }
+}
+
+/// Stub API key: mọi key đều thuộc một bot có đủ scopes (không cần database)
+struct AnyApiKey;
+
+#[async_trait::async_trait]
+impl ApiKeyResolver for AnyApiKey {
+    async fn resolve_api_key(&self, _key: &str) -> Result<Option<Claims>, error::SystemError> {
+        Ok(Some(
+            Claims::new(&Uuid::now_v7(), &UserRole::Bot, 0)
+                .with_scopes(vec![BotScope::SendMessages, BotScope::ReadMessages]),
+        ))
+    }
+}
+
+fn claims_for(role: UserRole) -> Claims {
+    Claims::new(&Uuid::now_v7(), &role, 300)
+        .with_jti(Uuid::now_v7())
+        .with_type(TypeClaims::AccessToken)
+}
+
+fn token_for(role: UserRole) -> String {
+    claims_for(role).encode().expect("failed to encode test token")
+}
+
+/// Token ký bằng secret không có trong keyring của server
+fn forged_token_for(role: UserRole) -> String {
+    let key = jsonwebtoken::EncodingKey::from_secret(b"not-the-server-secret");
+    jsonwebtoken::encode(&jsonwebtoken::Header::default(), &claims_for(role), &key)
+        .expect("failed to encode test token")
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Caller {
+    Anonymous,
+    Forged,
+    User,
+    Admin,
+    Bot,
+    Guest,
+}
+
+/// Middleware được coi là cho qua khi không trả về 401/403 và route tồn tại (khác 404)
+fn passes(status: StatusCode) -> bool {
+    !matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::NOT_FOUND)
+}
+
+fn allowed(access: Access, caller: Caller) -> bool {
+    match (access, caller) {
+        (Access::Public, _) => true,
+        (_, Caller::Anonymous | Caller::Forged) => false,
+        (Access::Bot, caller) => matches!(caller, Caller::Bot),
+        (_, Caller::Bot) => false,
+        (Access::Guest, caller) => matches!(caller, Caller::Guest),
+        (_, Caller::Guest) => false,
+        (Access::Authenticated | Access::Member, Caller::User | Caller::Admin) => true,
+        (Access::Admin, Caller::Admin) => true,
+        (Access::Admin, Caller::User) => false,
+    }
+}
+
+#[actix_web::test]
+async fn every_route_enforces_expected_access() {
+    init_test_env();
+
+    let app = actix_test::init_service(
+        App::new()
+            .app_data(web::Data::from(Arc::new(NeverRevoked) as Arc<dyn TokenRevocation>))
+            .app_data(web::Data::from(Arc::new(AnyApiKey) as Arc<dyn ApiKeyResolver>))
+            .service(version::mount(ApiVersion::v1(), crate::app::api_routes)),
+    )
+    .await;
+
+    let user_token = token_for(UserRole::User);
+    let admin_token = token_for(UserRole::Admin);
+    let forged_token = forged_token_for(UserRole::Admin);
+    let guest_token = Claims::new(&Uuid::now_v7(), &UserRole::Guest, 300)
+        .with_jti(Uuid::now_v7())
+        .with_type(TypeClaims::AccessToken)
+        .with_conversation(ID.parse().expect("invalid test conversation id"))
+        .encode()
+        .expect("failed to encode test token");
+
+    let mut failures = Vec::new();
+
+    for spec in registry() {
+        let uri = format!("/api/v1{}", spec.path.replace("{id}", ID));
+
+        for caller in [
+            Caller::Anonymous,
+            Caller::Forged,
+            Caller::User,
+            Caller::Admin,
+            Caller::Bot,
+            Caller::Guest,
+        ] {
+            let mut req = actix_test::TestRequest::default().method(spec.method.clone()).uri(&uri);
+            let token = match caller {
+                Caller::Anonymous | Caller::Bot => None,
+                Caller::Forged => Some(&forged_token),
+                Caller::User => Some(&user_token),
+                Caller::Admin => Some(&admin_token),
+                Caller::Guest => Some(&guest_token),
+            };
+            if let Some(token) = token {
+                req = req.insert_header(("Authorization", format!("Bearer {token}")));
+            }
+            if let Caller::Bot = caller {
+                req = req.insert_header((API_KEY_HEADER, "bot_access-matrix-test-key"));
+            }
+
+            // Middleware trả lỗi dưới dạng Err, handler/extractor trả lỗi dưới dạng response
+            let status = match actix_test::try_call_service(&app, req.to_request()).await {
+                Ok(res) => res.status(),
+                Err(e) => e.as_response_error().status_code(),
+            };
+
+            let expected = allowed(spec.access, caller);
+            let ok = if expected {
+                passes(status)
+            } else {
+                matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+            };
+
+            if !ok {
+                failures.push(format!(
+                    "{} {} {} as {:?}: got {}, expected {}",
+                    spec.handler,
+                    spec.method,
+                    uri,
+                    caller,
+                    status,
+                    if expected { "access" } else { "401/403" },
+                ));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "authorization matrix violations:\n{}", failures.join("\n"));
+}
+
+#[actix_web::test]
+async fn non_access_tokens_are_rejected() {
+    init_test_env();
+
+    let app = actix_test::init_service(
+        App::new()
+            .app_data(web::Data::from(Arc::new(NeverRevoked) as Arc<dyn TokenRevocation>))
+            .app_data(web::Data::from(Arc::new(AnyApiKey) as Arc<dyn ApiKeyResolver>))
+            .service(version::mount(ApiVersion::v1(), crate::app::api_routes)),
+    )
+    .await;
+
+    let mut failures = Vec::new();
+
+    for token_type in [
+        TypeClaims::RefreshToken,
+        TypeClaims::PasswordReset,
+        TypeClaims::EmailVerification,
+        TypeClaims::SignInConfirmation,
+    ] {
+        let token = Claims::new(&Uuid::now_v7(), &UserRole::Admin, 300)
+            .with_jti(Uuid::now_v7())
+            .with_type(token_type.clone())
+            .encode()
+            .expect("failed to encode test token");
+
+        for spec in registry().iter().filter(|spec| spec.access != Access::Public) {
+            let uri = format!("/api/v1{}", spec.path.replace("{id}", ID));
+            let req = actix_test::TestRequest::default()
+                .method(spec.method.clone())
+                .uri(&uri)
+                .insert_header(("Authorization", format!("Bearer {token}")))
+                .to_request();
+
+            let status = match actix_test::try_call_service(&app, req).await {
+                Ok(res) => res.status(),
+                Err(e) => e.as_response_error().status_code(),
+            };
+
+            if !matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) {
+                failures.push(format!(
+                    "{} {} {} with {:?} token: got {}",
+                    spec.handler, spec.method, uri, token_type, status,
+                ));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "non-access tokens accepted:\n{}", failures.join("\n"));
+}
+
+/// So khớp theo segment, `{param}` trong spec khớp mọi giá trị
+fn path_matches(documented: &str, concrete: &str) -> bool {
+    let documented: Vec<&str> = documented.split('/').filter(|s| !s.is_empty()).collect();
+    let concrete: Vec<&str> = concrete.split('/').filter(|s| !s.is_empty()).collect();
+    documented.len() == concrete.len()
+        && documented.iter().zip(&concrete).all(|(d, c)| d.starts_with('{') || d == c)
+}
+
+/// Các operations của một path trong spec, kèm HTTP method
+fn operations(
+    item: &utoipa::openapi::PathItem,
+) -> Vec<(Method, &utoipa::openapi::path::Operation)> {
+    [
+        (Method::GET, &item.get),
+        (Method::POST, &item.post),
+        (Method::PUT, &item.put),
+        (Method::PATCH, &item.patch),
+        (Method::DELETE, &item.delete),
+    ]
+    .into_iter()
+    .filter_map(|(method, operation)| operation.as_ref().map(|operation| (method, operation)))
+    .collect()
+}
+
+/// Mọi operation trong OpenAPI spec (sinh từ `#[utoipa::path]` của các handlers được mount)
+/// phải có access level trong registry. `operation_id` mặc định là tên handler
+#[test]
+fn every_documented_operation_is_registered() {
+    use utoipa::OpenApi;
+
+    let spec = crate::api::docs::ApiDoc::openapi();
+    let registry = registry();
+
+    let mut missing = BTreeSet::new();
+    for (path, item) in &spec.paths.paths {
+        for (method, operation) in operations(item) {
+            let handler = operation.operation_id.as_deref().unwrap_or_default();
+            let registered = registry.iter().any(|route| {
+                route.method == method
+                    && route.handler.rsplit("::").next() == Some(handler)
+                    && path_matches(path, &format!("/api/v1{}", route.path))
+            });
+            if !registered {
+                missing.insert(format!("{method} {path} ({handler})"));
+            }
+        }
+    }
+
+    assert!(missing.is_empty(), "operations without an access declaration: {missing:?}");
+}
+
+#[test]
+fn every_route_is_documented() {
+    use utoipa::OpenApi;
+
+    let spec = crate::api::docs::ApiDoc::openapi();
+
+    let undocumented: Vec<String> = registry()
+        .iter()
+        .filter(|route| {
+            let concrete = format!("/api/v1{}", route.path);
+            !spec.paths.paths.iter().any(|(path, item)| {
+                path_matches(path, &concrete)
+                    && operations(item).iter().any(|(method, _)| *method == route.method)
+            })
+        })
+        .map(|route| format!("{} {} ({})", route.method, route.path, route.handler))
+        .collect();
+
+    assert!(undocumented.is_empty(), "routes missing from OpenAPI spec: {undocumented:?}");
+}
+
+/// Stub webhook repository: mọi group tồn tại nhưng caller không phải thành viên
+struct NotAGroupMember;
+
+#[async_trait::async_trait]
+impl WebhookRepository for NotAGroupMember {
+    async fn find_group_access(
+        &self,
+        _conversation_id: &Uuid,
+        _user_id: &Uuid,
+    ) -> Result<Option<GroupAccess>, error::SystemError> {
+        Ok(Some(GroupAccess { created_by: Uuid::now_v7(), is_member: false }))
+    }
+
+    async fn count_by_conversation(&self, _: &Uuid) -> Result<i64, error::SystemError> {
+        unreachable!("membership is checked first")
+    }
+
+    async fn create(
+        &self,
+        _: &Uuid,
+        _: &str,
+        _: &str,
+        _: &Uuid,
+    ) -> Result<WebhookEntity, error::SystemError> {
+        unreachable!("membership is checked first")
+    }
+
+    async fn find_by_conversation(
+        &self,
+        _: &Uuid,
+    ) -> Result<Vec<WebhookEntity>, error::SystemError> {
+        unreachable!("membership is checked first")
+    }
+
+    async fn find_recent_deliveries(
+        &self,
+        _: &[Uuid],
+        _: i64,
+    ) -> Result<Vec<WebhookDeliveryEntity>, error::SystemError> {
+        unreachable!("membership is checked first")
+    }
+
+    async fn delete(&self, _: &Uuid, _: &Uuid) -> Result<bool, error::SystemError> {
+        unreachable!("membership is checked first")
+    }
+
+    async fn claim_due(&self, _: i64, _: i64) -> Result<Vec<ClaimedDelivery>, error::SystemError> {
+        unreachable!("delivery worker is not running")
+    }
+
+    async fn mark_delivered(&self, _: &Uuid, _: i32) -> Result<(), error::SystemError> {
+        unreachable!("delivery worker is not running")
+    }
+
+    async fn mark_failed(
+        &self,
+        _: &Uuid,
+        _: Option<i32>,
+        _: &str,
+        _: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<(), error::SystemError> {
+        unreachable!("delivery worker is not running")
+    }
+
+    async fn count_incoming_by_conversation(&self, _: &Uuid) -> Result<i64, error::SystemError> {
+        unreachable!("membership is checked first")
+    }
+
+    async fn create_incoming(
+        &self,
+        _: &Uuid,
+        _: &str,
+        _: &str,
+        _: &str,
+        _: &Uuid,
+    ) -> Result<IncomingWebhookEntity, error::SystemError> {
+        unreachable!("membership is checked first")
+    }
+
+    async fn find_incoming_by_conversation(
+        &self,
+        _: &Uuid,
+    ) -> Result<Vec<IncomingWebhookEntity>, error::SystemError> {
+        unreachable!("membership is checked first")
+    }
+
+    async fn revoke_incoming(&self, _: &Uuid, _: &Uuid) -> Result<bool, error::SystemError> {
+        unreachable!("membership is checked first")
+    }
+
+    async fn authenticate_incoming(
+        &self,
+        _: &str,
+    ) -> Result<Option<IncomingWebhookEntity>, error::SystemError> {
+        unreachable!("membership is checked first")
+    }
+}
+
+/// Stub guest repository: caller không phải thành viên của conversation nào
+struct NotAGuestInviter;
+
+#[async_trait::async_trait]
+impl GuestRepository for NotAGuestInviter {
+    async fn is_member(&self, _: &Uuid, _: &Uuid) -> Result<bool, error::SystemError> {
+        Ok(false)
+    }
+
+    async fn create_invite(
+        &self,
+        _: &Uuid,
+        _: &str,
+        _: &str,
+        _: &Uuid,
+        _: i32,
+        _: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<GuestInviteEntity, error::SystemError> {
+        unreachable!("membership is checked first")
+    }
+
+    async fn find_invites(&self, _: &Uuid) -> Result<Vec<GuestInviteEntity>, error::SystemError> {
+        unreachable!("membership is checked first")
+    }
+
+    async fn revoke_invite(
+        &self,
+        _: &Uuid,
+        _: &Uuid,
+    ) -> Result<Option<Vec<Uuid>>, error::SystemError> {
+        unreachable!("membership is checked first")
+    }
+
+    async fn redeem_invite(
+        &self,
+        _: &str,
+        _: &InsertGuest,
+    ) -> Result<Option<Uuid>, error::SystemError> {
+        unreachable!("membership is checked first")
+    }
+}
+
+struct UnknownSenders;
+
+#[async_trait::async_trait]
+impl SenderResolver for UnknownSenders {
+    async fn resolve_sender(&self, user_id: Uuid) -> SenderInfo {
+        SenderInfo::unknown(user_id)
+    }
+}
+
+struct AllowDirect;
+
+#[async_trait::async_trait]
+impl DirectMessagePolicy for AllowDirect {
+    async fn check_direct_message(&self, _: Uuid, _: Uuid) -> Result<(), error::SystemError> {
+        Ok(())
+    }
+}
+
+/// Query string và body hợp lệ của route member-only, để request vượt qua extractors và
+/// tới bước kiểm tra membership. Route nhận conversation qua body / query dùng `conversation_id`
+fn member_payload(handler: &str, conversation_id: Uuid) -> (String, serde_json::Value) {
+    use serde_json::{json, Value};
+
+    let scheduled_at = chrono::Utc::now() + chrono::Duration::hours(1);
+
+    match handler {
+        "conversation::get_messages" => ("?limit=10".into(), Value::Null),
+        "conversation::get_messages_around" => ("?limit=10".into(), Value::Null),
+        "conversation::export_messages" => (String::new(), Value::Null),
+        "conversation::update_duplicate_policy" => (String::new(), json!({ "policy": "reject" })),
+        "conversation::update_conversation_settings" => {
+            (String::new(), json!({ "message_ttl_seconds": null }))
+        }
+        "conversation::mute_conversation" => (String::new(), json!({})),
+        "conversation::update_group" => (String::new(), json!({ "name": "Renamed" })),
+        "conversation::archive_conversation" => (String::new(), json!({ "archived": true })),
+        "conversation::pin_conversation" => (String::new(), json!({ "pinned": true })),
+        "conversation::update_notification_level" => {
+            (String::new(), json!({ "level": "mentions" }))
+        }
+        "conversation::save_draft" => (String::new(), json!({ "content": "draft" })),
+        "conversation::add_members" => (String::new(), json!({ "member_ids": [Uuid::now_v7()] })),
+        "conversation::mark_as_seen"
+        | "conversation::delete_conversation"
+        | "conversation::get_draft"
+        | "webhook::list_webhooks"
+        | "webhook::delete_webhook"
+        | "webhook::list_incoming_webhooks"
+        | "webhook::revoke_incoming_webhook"
+        | "guest::list_guest_invites"
+        | "guest::revoke_guest_invite" => (String::new(), Value::Null),
+        "webhook::create_webhook" => (String::new(), json!({ "url": "https://example.com/hook" })),
+        "webhook::create_incoming_webhook" => (String::new(), json!({ "name": "CI" })),
+        "guest::create_guest_invite" => (String::new(), json!({})),
+        "message::send_group_message" => {
+            (String::new(), json!({ "conversation_id": conversation_id, "content": "hello" }))
+        }
+        "message::schedule_message" => (
+            String::new(),
+            json!({
+                "conversation_id": conversation_id,
+                "content": "hello",
+                "scheduled_at": scheduled_at,
+            }),
+        ),
+        "message::get_scheduled_messages" => {
+            (format!("?conversation_id={conversation_id}"), Value::Null)
+        }
+        other => panic!("member-only route {other} has no payload for the non-member check"),
+    }
+}
+
+/// Non-member gọi mọi route `Access::Member` trên group có thật phải nhận 403 `NOT_A_MEMBER`.
+/// Services dùng repository mocks, không cần database / Redis
+#[actix_web::test]
+async fn member_routes_reject_non_members() {
+    init_test_env();
+
+    let users = UserRepositoryMock::default();
+    let conversations = ConversationRepositoryMock::new(users.clone());
+    let messages = MessageRepositoryMock::new(conversations.clone());
+    // Report repository chỉ được dùng khi nội dung bị flag, pool không bao giờ kết nối
+    let reports =
+        ReportRepositoryPg::new(PgPoolOptions::new().connect_lazy_with(PgConnectOptions::new()));
+    let ws_server = Arc::new(WebSocketServer::new().start());
+    let cache = Arc::new(MemoryCache::default());
+
+    let conversation_svc = ConversationService::with_dependencies(ConversationServiceDeps {
+        conversation_repo: Arc::new(conversations.clone()),
+        participant_repo: Arc::new(conversations.clone()),
+        message_repo: Arc::new(messages.clone()),
+        uow: Arc::new(MemoryUnitOfWork),
+        ws_server: ws_server.clone(),
+        cache: cache.clone(),
+        moderation: ContentFilter::from_env(Arc::new(reports.clone())),
+        senders: Arc::new(UnknownSenders),
+    });
+    let message_svc = MessageService::with_dependencies(MessageServiceDeps {
+        conversation_repo: Arc::new(conversations.clone()),
+        message_repo: Arc::new(messages.clone()),
+        participant_repo: Arc::new(conversations.clone()),
+        last_message_repo: Arc::new(conversations.clone()),
+        uow: Arc::new(MemoryUnitOfWork),
+        cache,
+        ws_server,
+        push_queue: PushQueue::new().0,
+        events: EventOutbox::new(),
+        webhooks: WebhookQueue::new(),
+        moderation: ContentFilter::from_env(Arc::new(reports)),
+        commands: Arc::new(CommandRegistry::with_builtin()),
+        senders: Arc::new(UnknownSenders),
+        direct_policy: Arc::new(AllowDirect),
+        translator: None,
+    });
+
+    let members = [
+        users.insert(UserRepositoryMock::entity("alice", "unused-hash")),
+        users.insert(UserRepositoryMock::entity("bob", "unused-hash")),
+    ];
+    let mut tx = MemoryUnitOfWork.begin().await.expect("failed to begin transaction");
+    let conversation_id = conversations
+        .create_group_conversation("Team", &members, &members[0], &mut tx)
+        .await
+        .expect("failed to create group")
+        .id;
+    let outsider = users.insert(UserRepositoryMock::entity("mallory", "unused-hash"));
+    let token = Claims::new(&outsider, &UserRole::User, 300)
+        .with_jti(Uuid::now_v7())
+        .with_type(TypeClaims::AccessToken)
+        .encode()
+        .expect("failed to encode test token");
+
+    let app = actix_test::init_service(
+        App::new()
+            .app_data(web::Data::from(Arc::new(NeverRevoked) as Arc<dyn TokenRevocation>))
+            .app_data(web::Data::from(Arc::new(AnyApiKey) as Arc<dyn ApiKeyResolver>))
+            .app_data(web::Data::new(conversation_svc))
+            .app_data(web::Data::new(message_svc))
+            .app_data(web::Data::new(WebhookService::with_dependencies(Arc::new(NotAGroupMember))))
+            .app_data(web::Data::new(GuestService::with_dependencies(
+                Arc::new(NotAGuestInviter),
+                Arc::new(NeverRevoked),
+            )))
+            .service(version::mount(ApiVersion::v1(), crate::app::api_routes)),
+    )
+    .await;
+
+    let mut failures = Vec::new();
+
+    for spec in registry().iter().filter(|spec| spec.access == Access::Member) {
+        let (query, body) = member_payload(spec.handler, conversation_id);
+        // `{id}` đầu tiên là conversation, các path params sau (message, webhook, ...) giữ ID mẫu
+        let path = spec.path.replacen("{id}", &conversation_id.to_string(), 1).replace("{id}", ID);
+        let uri = format!("/api/v1{path}{query}");
+
+        let mut req = actix_test::TestRequest::default()
+            .method(spec.method.clone())
+            .uri(&uri)
+            .insert_header(("Authorization", format!("Bearer {token}")));
+        if !body.is_null() {
+            req = req.set_json(&body);
+        }
+
+        let res = match actix_test::try_call_service(&app, req.to_request()).await {
+            Ok(res) => res.into_parts().1,
+            Err(e) => e.error_response(),
+        };
+        let status = res.status();
+        let bytes = actix_web::body::to_bytes(res.into_body()).await.unwrap_or_default();
+        let code = serde_json::from_slice::<serde_json::Value>(&bytes)
+            .ok()
+            .and_then(|body| body.get("code").and_then(|code| code.as_str()).map(str::to_owned));
+
+        if status != StatusCode::FORBIDDEN || code.as_deref() != Some("NOT_A_MEMBER") {
+            failures.push(format!(
+                "{} {} {}: got {} {:?}",
+                spec.handler,
+                spec.method,
+                uri,
+                status,
+                String::from_utf8_lossy(&bytes),
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "member-only routes reachable by non-members:\n{}",
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,121 @@
+/// OpenAPI Documentation
+///
+/// Spec được sinh lúc compile từ `#[utoipa::path]` trên handlers và `ToSchema` trên
+/// models, nên luôn khớp với code. Mỗi module khai báo `*ApiDoc` cạnh hàm configure
+/// routes tương ứng; `ApiDoc` nest chúng theo đúng prefix mà `api_routes` mount dưới
+/// `/api/v1`. Swagger UI phục vụ tại `/api/docs/`, spec JSON tại `/api/docs/openapi.json`.
+use utoipa::{
+    openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::{
+    middlewares::API_KEY_HEADER,
+    modules::{
+        announcement, audit, bot, call, conversation, event, file_upload, friend, guest, keys,
+        message, notification, oauth, report, search, user, webhook,
+    },
+};
+
+pub const DOCS_PATH: &str = "/api/docs";
+pub const SPEC_PATH: &str = "/api/docs/openapi.json";
+
+/// Tên security scheme, phải khớp với `security(("bearer_auth" = []))` bên dưới
+const BEARER_AUTH: &str = "bearer_auth";
+
+/// Tên security scheme của bot principal, dùng trong `security(("api_key" = []))`
+const API_KEY_AUTH: &str = "api_key";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "AppChat API", description = "REST API của AppChat (realtime qua `/ws`)"),
+    nest(
+        (path = "/api/v1/auth/oauth", api = oauth::route::OAuthApiDoc),
+        (path = "/api/v1/auth", api = user::route::AuthApiDoc),
+        (path = "/api/v1/users", api = user::route::UserApiDoc),
+        (path = "/api/v1/friends", api = friend::route::FriendApiDoc),
+        (path = "/api/v1/conversations", api = conversation::route::ConversationApiDoc),
+        (path = "/api/v1/conversations", api = webhook::route::WebhookApiDoc),
+        (path = "/api/v1/webhooks", api = webhook::route::IncomingWebhookApiDoc),
+        (path = "/api/v1/conversations", api = guest::route::GuestInviteApiDoc),
+        (path = "/api/v1/guests", api = guest::route::GuestSessionApiDoc),
+        (path = "/api/v1/guest", api = guest::route::GuestPrincipalApiDoc),
+        (path = "/api/v1/messages", api = message::route::MessageApiDoc),
+        (path = "/api/v1/reports", api = report::route::ReportApiDoc),
+        (path = "/api/v1/devices", api = notification::route::NotificationApiDoc),
+        (path = "/api/v1/notifications", api = notification::route::NotificationEntryApiDoc),
+        (path = "/api/v1/bots", api = bot::route::BotApiDoc),
+        (path = "/api/v1/bot", api = bot::route::BotPrincipalApiDoc),
+        (path = "/api/v1/calls", api = call::route::CallApiDoc),
+        (path = "/api/v1/keys", api = keys::route::KeyApiDoc),
+        (path = "/api/v1/announcements", api = announcement::route::AnnouncementApiDoc),
+        (path = "/api/v1", api = search::route::SearchApiDoc),
+        (path = "/api/v1", api = event::route::EventApiDoc),
+        (path = "/api/v1", api = file_upload::route::FileUploadApiDoc),
+        (path = "/api/v1/admin", api = user::route::AdminApiDoc),
+        (path = "/api/v1/admin/users", api = user::route::AdminUserApiDoc),
+        (
+            path = "/api/v1/admin/conversation-defaults",
+            api = conversation::route::AdminConversationApiDoc
+        ),
+        (
+            path = "/api/v1/admin/unread-counts",
+            api = conversation::route::AdminUnreadCountApiDoc
+        ),
+        (path = "/api/v1/admin/client-metadata", api = message::route::AdminMessageApiDoc),
+        (path = "/api/v1/admin/reports", api = report::route::AdminReportApiDoc),
+        (
+            path = "/api/v1/admin/announcements",
+            api = announcement::route::AdminAnnouncementApiDoc
+        ),
+        (path = "/api/v1/admin/audit-log", api = audit::route::AdminAuditApiDoc)
+    ),
+    modifiers(&BearerAuth),
+    security(("bearer_auth" = [])),
+    tags(
+        (name = "auth", description = "Đăng ký, đăng nhập, tokens và OAuth"),
+        (name = "users", description = "Profile, tìm kiếm và presence"),
+        (name = "friends", description = "Bạn bè và lời mời kết bạn"),
+        (name = "conversations", description = "Conversations, lịch sử tin nhắn và lời mời"),
+        (name = "messages", description = "Gửi, sửa và xóa tin nhắn"),
+        (name = "reports", description = "Report nội dung vi phạm"),
+        (name = "devices", description = "Thiết bị nhận push notification"),
+        (name = "notifications", description = "Notification entries trong app (cảnh báo bảo mật, ...)"),
+        (name = "files", description = "Upload file"),
+        (name = "bots", description = "Bot accounts, API keys và API cho bot (`X-Api-Key`)"),
+        (name = "guests", description = "Lời mời guest và API cho guest (một conversation)"),
+        (name = "calls", description = "Lịch sử cuộc gọi (signaling qua WebSocket)"),
+        (name = "keys", description = "Public keys cho mã hóa đầu cuối (direct chats)"),
+        (name = "announcements", description = "Thông báo toàn hệ thống từ admin"),
+        (name = "search", description = "Tìm kiếm conversations, bạn bè và tin nhắn"),
+        (name = "events", description = "Long-poll events bị lỡ khi mất WebSocket"),
+        (name = "admin", description = "Quản trị (chỉ Admin)")
+    )
+)]
+pub struct ApiDoc;
+
+/// Đăng ký security scheme `Authorization: Bearer <access_token>` và `X-Api-Key` của bot;
+/// routes public override bằng `security(())`
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            BEARER_AUTH,
+            SecurityScheme::Http(
+                HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build(),
+            ),
+        );
+        components.add_security_scheme(
+            API_KEY_AUTH,
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(API_KEY_HEADER))),
+        );
+    }
+}
+
+/// Swagger UI service, mount trước scope `/api` legacy (scope không fall through)
+pub fn swagger_ui() -> SwaggerUi {
+    SwaggerUi::new(format!("{DOCS_PATH}/{{_:.*}}")).url(SPEC_PATH, ApiDoc::openapi())
+}
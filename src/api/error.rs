@@ -4,7 +4,8 @@ use actix_web::{
     HttpResponse, ResponseError,
 };
 use deadpool_redis::{redis::RedisError, CreatePoolError, PoolError};
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::BTreeMap};
+use validator::{ValidationErrors, ValidationErrorsKind};
 
 use crate::ENV;
 
@@ -20,13 +21,72 @@ pub enum Error {
     NotFound(Cow<'static, str>),
     #[error("Conflict: {0}")]
     Conflict(Cow<'static, str>),
+    #[error("Too Many Requests: {0}")]
+    TooManyRequests(Cow<'static, str>),
+    #[error("Payload Too Large: {0}")]
+    PayloadTooLarge(Cow<'static, str>),
+    /// Body / query không qua được validate, kèm lỗi theo từng field
+    #[error("Validation Failed: {0:?}")]
+    ValidationFailed(FieldErrors),
     #[error("Internal Server Error")]
     InternalServer,
+    /// Lỗi kèm error code cụ thể, status code giữ theo lỗi bên trong
+    #[error("{1}")]
+    WithCode(ErrorCode, Box<Error>),
 }
 
-#[derive(serde::Serialize)]
+/// Error code ổn định để client phân nhánh xử lý (message chỉ để hiển thị)
+///
+/// Dùng chung cho REST (`ErrorBody.code`) và WebSocket (`error` event)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    BadRequest,
+    ValidationFailed,
+    Unauthorized,
+    AuthExpired,
+    InvalidToken,
+    TokenRevoked,
+    Forbidden,
+    AccountBanned,
+    AccountDeactivated,
+    AccountLocked,
+    EmailNotVerified,
+    SignInConfirmationRequired,
+    NotAMember,
+    NotFriends,
+    Blocked,
+    NotFound,
+    Conflict,
+    RateLimited,
+    PayloadTooLarge,
+    InternalError,
+    // WebSocket
+    InvalidMessage,
+    MessageTooLarge,
+    InvalidContent,
+    InvalidStatus,
+    AlreadyAuthenticated,
+    SendFailed,
+    ServiceUnavailable,
+    SessionLimitExceeded,
+    ServerAtCapacity,
+    UserBusy,
+}
+
+/// Lỗi validate theo field: tên field (dạng `a.b[0].c` với struct lồng nhau) → các message
+pub type FieldErrors = BTreeMap<String, Vec<String>>;
+
+/// Body chuẩn của response lỗi
+#[derive(serde::Serialize, utoipa::ToSchema)]
 pub struct ErrorBody {
+    pub code: ErrorCode,
     pub message: Cow<'static, str>,
+    /// Chỉ có khi `code` là `VALIDATION_FAILED`, để client gắn lỗi vào đúng field của form
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<FieldErrors>,
 }
 
 impl Error {
@@ -50,20 +110,111 @@ impl Error {
         Self::Conflict(msg.into())
     }
 
+    pub fn too_many_requests(msg: impl Into<Cow<'static, str>>) -> Self {
+        Self::TooManyRequests(msg.into())
+    }
+
+    pub fn payload_too_large(msg: impl Into<Cow<'static, str>>) -> Self {
+        Self::PayloadTooLarge(msg.into())
+    }
+
     pub fn internal_server_error() -> Self {
         Self::InternalServer
     }
+
+    /// Gắn error code cụ thể thay cho code mặc định theo loại lỗi
+    pub fn with_code(self, code: ErrorCode) -> Self {
+        match self {
+            Self::WithCode(_, inner) => Self::WithCode(code, inner),
+            other => Self::WithCode(code, Box::new(other)),
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Error::BadRequest(_) => ErrorCode::BadRequest,
+            Error::Unauthorized(_) => ErrorCode::Unauthorized,
+            Error::Forbidden(_) => ErrorCode::Forbidden,
+            Error::NotFound(_) => ErrorCode::NotFound,
+            Error::Conflict(_) => ErrorCode::Conflict,
+            Error::TooManyRequests(_) => ErrorCode::RateLimited,
+            Error::PayloadTooLarge(_) => ErrorCode::PayloadTooLarge,
+            Error::ValidationFailed(_) => ErrorCode::ValidationFailed,
+            Error::InternalServer => ErrorCode::InternalError,
+            Error::WithCode(code, _) => *code,
+        }
+    }
+
+    fn message(&self) -> Cow<'static, str> {
+        match self {
+            Error::NotFound(msg)
+            | Error::Conflict(msg)
+            | Error::Unauthorized(msg)
+            | Error::BadRequest(msg)
+            | Error::Forbidden(msg)
+            | Error::TooManyRequests(msg)
+            | Error::PayloadTooLarge(msg) => msg.clone(),
+            Error::ValidationFailed(_) => "Validation failed".into(),
+            Error::InternalServer => "Internal Server Error".into(),
+            Error::WithCode(_, inner) => inner.message(),
+        }
+    }
+
+    fn field_errors(&self) -> Option<&FieldErrors> {
+        match self {
+            Error::ValidationFailed(errors) => Some(errors),
+            Error::WithCode(_, inner) => inner.field_errors(),
+            _ => None,
+        }
+    }
+}
+
+impl From<ValidationErrors> for Error {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut fields = FieldErrors::new();
+        collect_field_errors(&errors, None, &mut fields);
+        Error::ValidationFailed(fields)
+    }
+}
+
+/// Làm phẳng lỗi của `validator` (kể cả struct / list lồng nhau) thành `FieldErrors`
+fn collect_field_errors(errors: &ValidationErrors, prefix: Option<&str>, out: &mut FieldErrors) {
+    for (field, kind) in errors.errors() {
+        let path = match prefix {
+            Some(prefix) => format!("{prefix}.{field}"),
+            None => field.to_string(),
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                out.entry(path).or_default().extend(errors.iter().map(|e| match &e.message {
+                    Some(message) => message.to_string(),
+                    None => format!("Invalid value ({})", e.code),
+                }));
+            }
+            ValidationErrorsKind::Struct(inner) => collect_field_errors(inner, Some(&path), out),
+            ValidationErrorsKind::List(items) => {
+                for (index, inner) in items {
+                    collect_field_errors(inner, Some(&format!("{path}[{index}]")), out);
+                }
+            }
+        }
+    }
 }
 
 impl ResponseError for Error {
     fn status_code(&self) -> StatusCode {
-        match *self {
+        match self {
             Error::BadRequest(_) => StatusCode::BAD_REQUEST,
             Error::Unauthorized(_) => StatusCode::UNAUTHORIZED,
             Error::Forbidden(_) => StatusCode::FORBIDDEN,
             Error::NotFound(_) => StatusCode::NOT_FOUND,
             Error::Conflict(_) => StatusCode::CONFLICT,
+            Error::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            Error::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Error::ValidationFailed(_) => StatusCode::BAD_REQUEST,
             Error::InternalServer => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::WithCode(_, inner) => inner.status_code(),
         }
     }
 
@@ -74,18 +225,11 @@ impl ResponseError for Error {
         res.insert_header(header);
         res.insert_header(("Access-Control-Allow-Credentials", "true"));
 
-        match self {
-            // Has Message
-            Error::NotFound(msg)
-            | Error::Conflict(msg)
-            | Error::Unauthorized(msg)
-            | Error::BadRequest(msg)
-            | Error::Forbidden(msg) => res.json(ErrorBody { message: msg.clone() }),
-            // No Message
-            Error::InternalServer => {
-                res.json(ErrorBody { message: "Internal Server Error".into() })
-            }
-        }
+        res.json(ErrorBody {
+            code: self.code(),
+            message: self.message(),
+            errors: self.field_errors().cloned(),
+        })
     }
 }
 
@@ -102,6 +246,8 @@ pub enum SystemError {
     // sqlx errors
     #[error("Database Error : {0}")]
     DatabaseError(Cow<'static, str>),
+    #[error("Migration Error: {0}")]
+    MigrationError(#[from] sqlx::migrate::MigrateError),
     // serde errors
     #[error("JSON Serialization/Deserialization Error")]
     JsonError(#[from] serde_json::Error),
@@ -112,6 +258,9 @@ pub enum SystemError {
     PoolGet(#[from] PoolError),
     #[error("Redis error")]
     RedisError(#[from] RedisError),
+    // http client errors
+    #[error("HTTP client error: {0}")]
+    HttpClient(#[from] reqwest::Error),
     // Custom Errors
     #[error("Bad Request: {0}")]
     BadRequest(Cow<'static, str>),
@@ -123,8 +272,13 @@ pub enum SystemError {
     NotFound(Cow<'static, str>),
     #[error("Database Conflict: {0:?}")]
     Conflict(Option<DbErrorMeta>),
+    #[error("Too Many Requests: {0}")]
+    TooManyRequests(Cow<'static, str>),
     #[error("Internal System Error: {0}")]
     InternalError(Cow<'static, str>),
+    /// Lỗi kèm error code cụ thể, giữ nguyên code khi chuyển sang `Error`
+    #[error("{1}")]
+    WithCode(ErrorCode, Box<SystemError>),
 }
 
 fn conflict_message(meta: &Option<DbErrorMeta>) -> Cow<'static, str> {
@@ -162,6 +316,8 @@ impl From<SystemError> for Error {
             SystemError::Forbidden(msg) => Error::Forbidden(msg),
             SystemError::NotFound(msg) => Error::NotFound(msg),
             SystemError::Conflict(meta) => Error::Conflict(conflict_message(&meta)),
+            SystemError::TooManyRequests(msg) => Error::TooManyRequests(msg),
+            SystemError::WithCode(code, inner) => Error::from(*inner).with_code(code),
             _ => {
                 tracing::error!("Internal Server Error: {:?}", value);
                 Error::InternalServer
@@ -170,6 +326,14 @@ impl From<SystemError> for Error {
     }
 }
 
+/// Body lỗi cho kết quả từng phần (vd. từng người nhận), lỗi hệ thống bị che như response
+impl From<SystemError> for ErrorBody {
+    fn from(value: SystemError) -> Self {
+        let error = Error::from(value);
+        ErrorBody { code: error.code(), message: error.message(), errors: None }
+    }
+}
+
 impl From<sqlx::Error> for SystemError {
     fn from(err: sqlx::Error) -> Self {
         tracing::error!("{:?}", err);
@@ -215,4 +379,44 @@ impl SystemError {
     pub fn internal_error(msg: impl Into<Cow<'static, str>>) -> Self {
         Self::InternalError(msg.into())
     }
+
+    pub fn too_many_requests(msg: impl Into<Cow<'static, str>>) -> Self {
+        Self::TooManyRequests(msg.into())
+    }
+
+    /// Conflict phát hiện trước khi ghi DB, message giống conflict của unique constraint
+    /// trên `field` ("<Field> already exists")
+    pub fn conflict(field: &str) -> Self {
+        Self::Conflict(Some(DbErrorMeta {
+            code: None,
+            constraint: Some(field.to_string()),
+            message: format!("{field} already exists"),
+        }))
+    }
+
+    /// Gắn error code cụ thể thay cho code mặc định theo loại lỗi
+    pub fn with_code(self, code: ErrorCode) -> Self {
+        match self {
+            Self::WithCode(_, inner) => Self::WithCode(code, inner),
+            other => Self::WithCode(code, Box::new(other)),
+        }
+    }
+
+    /// Error code tương ứng (lỗi hệ thống → `INTERNAL_ERROR`)
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            SystemError::JwtError(e) => match e.kind() {
+                jsonwebtoken::errors::ErrorKind::ExpiredSignature => ErrorCode::AuthExpired,
+                _ => ErrorCode::InvalidToken,
+            },
+            SystemError::BadRequest(_) => ErrorCode::BadRequest,
+            SystemError::Unauthorized(_) => ErrorCode::Unauthorized,
+            SystemError::Forbidden(_) => ErrorCode::Forbidden,
+            SystemError::NotFound(_) => ErrorCode::NotFound,
+            SystemError::Conflict(_) => ErrorCode::Conflict,
+            SystemError::TooManyRequests(_) => ErrorCode::RateLimited,
+            SystemError::WithCode(code, _) => *code,
+            _ => ErrorCode::InternalError,
+        }
+    }
 }
@@ -0,0 +1,99 @@
+/// Health Probes
+///
+/// - `GET /healthz` (liveness): process còn phục vụ HTTP, không kiểm tra dependencies
+/// - `GET /readyz` (readiness): ping Postgres (`SELECT 1`), Redis (`PING`) và
+///   WebSocketServer actor; trả về trạng thái từng dependency, 503 nếu có dependency
+///   lỗi hoặc server đang shutdown (load balancer ngừng route traffic mới)
+use std::{collections::BTreeMap, future::Future, time::Duration};
+
+use actix::Addr;
+use actix_web::{get, http::StatusCode, web, HttpResponse};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    api::error,
+    configs::RedisCache,
+    modules::websocket::{events::IsDraining, server::WebSocketServer},
+};
+
+/// Thời gian tối đa cho mỗi dependency check
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize)]
+struct DependencyStatus {
+    status: &'static str,
+    latency_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl DependencyStatus {
+    fn is_up(&self) -> bool {
+        self.status == "up"
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(healthz).service(readyz);
+}
+
+#[get("/healthz")]
+async fn healthz() -> HttpResponse {
+    HttpResponse::Ok().json(json!({ "status": "ok" }))
+}
+
+#[get("/readyz")]
+async fn readyz(
+    db_pool: web::Data<sqlx::PgPool>,
+    redis_cache: web::Data<RedisCache>,
+    ws_server: web::Data<Addr<WebSocketServer>>,
+) -> HttpResponse {
+    let (postgres, redis, websocket) = futures_util::join!(
+        check(async {
+            sqlx::query("SELECT 1").execute(db_pool.get_ref()).await?;
+            Ok(())
+        }),
+        check(redis_cache.ping()),
+        check(async {
+            match ws_server.send(IsDraining).await {
+                Ok(false) => Ok(()),
+                Ok(true) => Err(error::SystemError::internal_error("Server is shutting down")),
+                Err(e) => Err(error::SystemError::internal_error(e.to_string())),
+            }
+        }),
+    );
+
+    let checks =
+        BTreeMap::from([("postgres", postgres), ("redis", redis), ("websocket", websocket)]);
+    let ready = checks.values().all(DependencyStatus::is_up);
+
+    let (status, overall) = if ready {
+        (StatusCode::OK, "ok")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+    };
+    HttpResponse::build(status).json(json!({ "status": overall, "checks": checks }))
+}
+
+/// Chạy một dependency check với timeout, đo latency
+async fn check<F>(fut: F) -> DependencyStatus
+where
+    F: Future<Output = Result<(), error::SystemError>>,
+{
+    let started = std::time::Instant::now();
+    let result = actix_web::rt::time::timeout(CHECK_TIMEOUT, fut).await;
+    let latency_ms = started.elapsed().as_millis();
+
+    let error = match result {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("Timed out after {}ms", CHECK_TIMEOUT.as_millis())),
+    };
+
+    if let Some(e) = &error {
+        tracing::warn!("Readiness check failed: {}", e);
+    }
+
+    DependencyStatus { status: if error.is_none() { "up" } else { "down" }, latency_ms, error }
+}
@@ -0,0 +1,22 @@
+/// JWKS
+///
+/// `GET /.well-known/jwks.json` trả về public keys (RS256 / EdDSA) đang được chấp nhận để
+/// các service khác verify access tokens mà không cần shared secret. Key HS256 không bao
+/// giờ được công bố, keyring chỉ có HS256 thì danh sách rỗng.
+use actix_web::{get, http::header, web, HttpResponse};
+
+use crate::configs::jwt_keys;
+
+/// Thời gian client được cache JWKS (giây), ngắn để key mới sau rotate sớm được nhận
+const JWKS_MAX_AGE: u32 = 300;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(jwks);
+}
+
+#[get("/.well-known/jwks.json")]
+async fn jwks() -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header((header::CACHE_CONTROL, format!("public, max-age={JWKS_MAX_AGE}")))
+        .json(jwt_keys::keyring().jwks())
+}
@@ -0,0 +1,127 @@
+/// Metrics
+///
+/// `GET /metrics` trả về metrics của instance theo Prometheus text format. Endpoint chỉ bật
+/// khi đặt `METRICS_TOKEN` và yêu cầu header `Authorization: Bearer <METRICS_TOKEN>`:
+/// - `ws_outbound_queued_frames`: tổng số frames đang chờ gửi tới WebSocket clients
+/// - `ws_outbound_lag_warnings_total`: số lần queue của một session vượt ngưỡng cảnh báo
+/// - `ws_outbound_lag_disconnects_total`: số clients bị disconnect vì đọc quá chậm
+/// - `unread_count_corrections_total`: số unread counts bị lệch đã được reconcile
+/// - `db_pool_*`, `redis_pool_*`: số connection đang mở / rảnh / tối đa của từng pool
+/// - `db_replica_pool_*`: như `db_pool_*` cho read replica (chỉ có khi đặt `DATABASE_READ_URL`)
+/// - `db_replica_fallbacks_total`: số query đọc phải chạy trên primary vì replica lỗi
+use actix_web::{get, web, HttpRequest, HttpResponse};
+use sqlx::PgPool;
+
+use crate::{
+    api::error,
+    configs::{
+        read_replica::{self, ReadPool},
+        RedisCache,
+    },
+    modules::{conversation::reconcile, websocket::backpressure},
+    utils::hash_token,
+    ENV,
+};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(metrics);
+}
+
+/// So sánh digest thay vì chuỗi token để thời gian so sánh không lộ độ dài prefix khớp
+fn authorize(req: &HttpRequest) -> Result<(), error::Error> {
+    let expected =
+        ENV.metrics_token.as_deref().ok_or_else(|| error::Error::not_found("Not found"))?;
+
+    let provided = req
+        .headers()
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .ok_or_else(|| error::Error::unauthorized("Missing metrics token"))?;
+
+    if hash_token(provided) != hash_token(expected) {
+        return Err(error::Error::unauthorized("Invalid metrics token"));
+    }
+
+    Ok(())
+}
+
+#[get("/metrics")]
+async fn metrics(
+    req: HttpRequest,
+    db_pool: web::Data<PgPool>,
+    read_pool: web::Data<ReadPool>,
+    redis: web::Data<RedisCache>,
+) -> Result<HttpResponse, error::Error> {
+    authorize(&req)?;
+
+    let queue = backpressure::metrics();
+    let redis_status = redis.get_pool().status();
+
+    let mut body = format!(
+        "# HELP ws_outbound_queued_frames Frames waiting in WebSocket outbound queues\n\
+         # TYPE ws_outbound_queued_frames gauge\n\
+         ws_outbound_queued_frames {}\n\
+         # HELP ws_outbound_lag_warnings_total Outbound queues that crossed the warning threshold\n\
+         # TYPE ws_outbound_lag_warnings_total counter\n\
+         ws_outbound_lag_warnings_total {}\n\
+         # HELP ws_outbound_lag_disconnects_total Clients disconnected for a full outbound queue\n\
+         # TYPE ws_outbound_lag_disconnects_total counter\n\
+         ws_outbound_lag_disconnects_total {}\n\
+         # HELP unread_count_corrections_total Participant unread counts fixed by reconciliation\n\
+         # TYPE unread_count_corrections_total counter\n\
+         unread_count_corrections_total {}\n\
+         # HELP db_pool_connections Open Postgres connections\n\
+         # TYPE db_pool_connections gauge\n\
+         db_pool_connections {}\n\
+         # HELP db_pool_idle_connections Idle Postgres connections\n\
+         # TYPE db_pool_idle_connections gauge\n\
+         db_pool_idle_connections {}\n\
+         # HELP db_pool_max_connections Postgres pool size limit\n\
+         # TYPE db_pool_max_connections gauge\n\
+         db_pool_max_connections {}\n\
+         # HELP redis_pool_connections Open Redis connections\n\
+         # TYPE redis_pool_connections gauge\n\
+         redis_pool_connections {}\n\
+         # HELP redis_pool_available_connections Idle Redis connections\n\
+         # TYPE redis_pool_available_connections gauge\n\
+         redis_pool_available_connections {}\n\
+         # HELP redis_pool_waiting Tasks waiting for a Redis connection\n\
+         # TYPE redis_pool_waiting gauge\n\
+         redis_pool_waiting {}\n\
+         # HELP redis_pool_max_connections Redis pool size limit\n\
+         # TYPE redis_pool_max_connections gauge\n\
+         redis_pool_max_connections {}\n",
+        queue.queued_frames,
+        queue.lag_warnings,
+        queue.lag_disconnects,
+        reconcile::corrections(),
+        db_pool.size(),
+        db_pool.num_idle(),
+        db_pool.options().get_max_connections(),
+        redis_status.size,
+        redis_status.available,
+        redis_status.waiting,
+        redis_status.max_size
+    );
+
+    if let Some(replica) = read_pool.replica() {
+        body.push_str(&format!(
+            "# HELP db_replica_pool_connections Open read replica connections\n\
+             # TYPE db_replica_pool_connections gauge\n\
+             db_replica_pool_connections {}\n\
+             # HELP db_replica_pool_idle_connections Idle read replica connections\n\
+             # TYPE db_replica_pool_idle_connections gauge\n\
+             db_replica_pool_idle_connections {}\n\
+             # HELP db_replica_fallbacks_total Read queries sent to the primary because the \
+             replica was unavailable\n\
+             # TYPE db_replica_fallbacks_total counter\n\
+             db_replica_fallbacks_total {}\n",
+            replica.size(),
+            replica.num_idle(),
+            read_replica::fallbacks()
+        ));
+    }
+
+    Ok(HttpResponse::Ok().content_type("text/plain; version=0.0.4").body(body))
+}
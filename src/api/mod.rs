@@ -1,2 +1,11 @@
+pub mod docs;
 pub mod error;
+pub mod health;
+pub mod jwks;
+pub mod metrics;
+pub mod pagination;
 pub mod success;
+pub mod version;
+
+#[cfg(test)]
+mod access_matrix;
@@ -0,0 +1,27 @@
+/// Metadata phân trang keyset dùng chung cho các endpoint danh sách
+///
+/// `next_cursor` tiếp tục theo hướng của request, `prev_cursor` đi ngược lại (ví dụ
+/// messages: request `direction=before` thì `prev_cursor` dùng với `direction=after`).
+#[derive(Debug, Clone, Default, serde::Serialize, utoipa::ToSchema)]
+pub struct PageInfo {
+    /// Còn items tiếp theo `next_cursor` hay không
+    pub has_more: bool,
+    /// Cursor lấy trang tiếp theo, `None` nếu đã hết
+    pub next_cursor: Option<String>,
+    /// Cursor lấy trang ngược lại, `None` ở trang đầu tiên
+    pub prev_cursor: Option<String>,
+    /// Tổng số items (chỉ có khi đếm được rẻ)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+}
+
+impl PageInfo {
+    pub fn new(next_cursor: Option<String>, prev_cursor: Option<String>) -> Self {
+        Self { has_more: next_cursor.is_some(), next_cursor, prev_cursor, total: None }
+    }
+
+    pub fn with_total(mut self, total: i64) -> Self {
+        self.total = Some(total);
+        self
+    }
+}
@@ -2,13 +2,20 @@
 use actix_web::HttpResponse;
 use std::borrow::Cow;
 
-#[derive(serde::Serialize)]
+/// Body chuẩn của response thành công
+#[derive(serde::Serialize, utoipa::ToSchema)]
 pub struct SuccessData<T: serde::Serialize> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<T>,
     pub message: Option<Cow<'static, str>>,
 }
 
+/// Schema OpenAPI của `Success<()>` (chỉ có `message`, không có `data`)
+#[derive(utoipa::ToSchema)]
+pub struct MessageOnly {
+    pub message: Option<Cow<'static, str>>,
+}
+
 pub struct Success<T: serde::Serialize> {
     pub status: actix_web::http::StatusCode,
     pub body: Option<SuccessData<T>>,
@@ -0,0 +1,109 @@
+/// API Versioning
+///
+/// Mỗi version được mount thành một scope riêng (`/api/v1`, ...) dùng chung hàm
+/// configure routes, nên nhiều version có thể chạy song song.
+/// Path legacy `/api` được giữ lại như compatibility shim: vẫn phục vụ đầy đủ routes
+/// nhưng trả thêm header `Deprecation`, `Sunset` và `Link` (successor-version).
+use actix_web::{
+    body::MessageBody,
+    dev::{HttpServiceFactory, ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    middleware::{from_fn, Next},
+    web::{self, ServiceConfig},
+    HttpResponse,
+};
+use futures_util::{future::LocalBoxFuture, FutureExt};
+use std::rc::Rc;
+
+use crate::ENV;
+
+/// Thông tin deprecation của một version
+#[derive(Clone)]
+pub struct Deprecation {
+    /// HTTP-date sau thời điểm này version sẽ bị gỡ
+    pub sunset: String,
+    /// Prefix của version thay thế
+    pub successor: &'static str,
+}
+
+#[derive(Clone)]
+pub struct ApiVersion {
+    pub prefix: &'static str,
+    pub deprecation: Option<Deprecation>,
+}
+
+impl ApiVersion {
+    /// Version hiện tại
+    pub fn v1() -> Self {
+        Self { prefix: "/api/v1", deprecation: None }
+    }
+
+    /// Path không version cũ, giữ lại cho client chưa migrate
+    pub fn legacy() -> Self {
+        Self {
+            prefix: "/api",
+            deprecation: Some(Deprecation {
+                sunset: ENV.api_legacy_sunset.clone(),
+                successor: "/api/v1",
+            }),
+        }
+    }
+}
+
+/// Mount một version với routes được cấu hình bởi `configure`
+pub fn mount<F>(version: ApiVersion, configure: F) -> impl HttpServiceFactory
+where
+    F: FnOnce(&mut ServiceConfig) + 'static,
+{
+    web::scope(version.prefix)
+        .wrap(from_fn(deprecation_headers(version.clone())))
+        .default_service(
+            web::route()
+                .guard(actix_web::guard::Method(actix_web::http::Method::OPTIONS))
+                .to(|| async { HttpResponse::Ok().finish() }),
+        )
+        .configure(configure)
+}
+
+fn deprecation_headers<B>(
+    version: ApiVersion,
+) -> impl Fn(
+    ServiceRequest,
+    Next<B>,
+) -> LocalBoxFuture<'static, Result<ServiceResponse<B>, actix_web::Error>>
+where
+    B: MessageBody + 'static,
+{
+    let version = Rc::new(version);
+    move |req: ServiceRequest, next: Next<B>| {
+        let version = version.clone();
+        async move {
+            let Some(deprecation) = &version.deprecation else {
+                return next.call(req).await;
+            };
+
+            let successor_path = format!(
+                "{}{}",
+                deprecation.successor,
+                req.path().strip_prefix(version.prefix).unwrap_or_default()
+            );
+
+            let mut res = next.call(req).await?;
+            let headers = res.headers_mut();
+
+            headers
+                .insert(HeaderName::from_static("deprecation"), HeaderValue::from_static("true"));
+            if let Ok(sunset) = HeaderValue::from_str(&deprecation.sunset) {
+                headers.insert(HeaderName::from_static("sunset"), sunset);
+            }
+            if let Ok(link) =
+                HeaderValue::from_str(&format!("<{successor_path}>; rel=\"successor-version\""))
+            {
+                headers.insert(HeaderName::from_static("link"), link);
+            }
+
+            Ok(res)
+        }
+        .boxed_local()
+    }
+}
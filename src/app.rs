@@ -0,0 +1,484 @@
+/// Application
+///
+/// Dựng toàn bộ repositories, services và WebSocket server từ Postgres pool + Redis, rồi
+/// lắp `App` của actix-web (middlewares, app data, routes). `serve` và integration tests
+/// dùng chung `AppState::build` + `build_app` nên test chạy đúng wiring của production.
+use std::sync::Arc;
+
+use actix::{Actor, Addr};
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceFactory, ServiceRequest, ServiceResponse},
+    middleware::{from_fn, Logger},
+    web, App,
+};
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::{
+    api::{self, version::ApiVersion},
+    configs::{
+        compression::{compress, skip_compression},
+        cors::{build_cors, CorsConfig},
+        mailer::LogMailer,
+        monitor_pool_saturation,
+        payload::json_config,
+        read_replica::ReadPool,
+        uow::UnitOfWork,
+        RedisCache,
+    },
+    middlewares::{
+        authentication, authorization, ActivityTracker, ApiKeyResolver, TokenRevocation,
+    },
+    modules::{
+        self,
+        announcement::{repository_pg::AnnouncementRepositoryPg, service::AnnouncementService},
+        audit::{repository_pg::AuditLogRepositoryPg, service::AuditService},
+        bot::{repository_pg::BotRepositoryPg, service::BotService},
+        call::{
+            repository_pg::CallRepositoryPg, service::CallService, timeout::run_call_timeout_worker,
+        },
+        conversation::{
+            reconcile::run_unread_reconciliation,
+            repository_pg::{
+                ConversationPgRepository, LastMessagePgRepository, ParticipantPgRepository,
+            },
+            service::{ConversationService, ConversationServiceDeps},
+        },
+        event::service::EventService,
+        file_upload::{repository_pg::FilePgRepository, service::FileUploadService},
+        friend::{
+            cleanup::run_friend_request_cleanup, repository_pg::FriendRepositoryPg,
+            service::FriendService,
+        },
+        guest::{repository_pg::GuestRepositoryPg, service::GuestService},
+        keys::{repository_pg::KeyRepositoryPg, service::KeyService},
+        message::{
+            archive::run_message_archive_worker,
+            command::CommandRegistry,
+            repository_pg::MessageRepositoryPg,
+            retention::run_message_retention_worker,
+            scheduler::run_scheduled_message_worker,
+            service::{MessageService, MessageServiceDeps},
+            translation::translator_from_env,
+        },
+        notification::{
+            model::PushJob,
+            queue::{run_push_worker, PushQueue},
+            repository_pg::{DeviceRepositoryPg, NotificationRepositoryPg},
+            sender::PushSenders,
+            service::NotificationService,
+        },
+        oauth::{repository_pg::OAuthRepositoryPg, service::OAuthService},
+        report::{
+            moderation::ContentFilter, repository_pg::ReportRepositoryPg, service::ReportService,
+        },
+        search::{repository_pg::SearchRepositoryPg, service::SearchService},
+        user::{
+            cache::{run_invalidation_listener, LocalProfileCache},
+            inactivity::run_inactive_account_worker,
+            repository_pg::UserRepositoryPg,
+            schema::UserRole,
+            service::UserService,
+        },
+        webhook::{
+            delivery::{run_webhook_worker, WebhookQueue},
+            repository_pg::WebhookRepositoryPg,
+            service::WebhookService,
+        },
+        websocket::{
+            bridge::{run_fanout_listener, FanoutBridge},
+            dispatcher::{run_event_dispatcher, EventOutbox},
+            event_buffer::EventBuffer,
+            handler::websocket_handler,
+            presence::PresenceService,
+            server::WebSocketServer,
+            socketio::socketio_handler,
+        },
+    },
+    ENV,
+};
+
+#[cfg(test)]
+mod integration;
+
+/// Dependencies dùng chung giữa các workers của HttpServer
+#[derive(Clone)]
+pub struct AppState {
+    pub db_pool: PgPool,
+    pub read_pool: ReadPool,
+    pub redis: RedisCache,
+    pub ws_server: Addr<WebSocketServer>,
+    pub presence_service: PresenceService,
+    pub friend_repo: FriendRepositoryPg,
+    pub user_service: UserService,
+    pub oauth_service: OAuthService,
+    pub friend_service: FriendService,
+    pub bot_service: BotService,
+    pub file_upload_service: FileUploadService,
+    pub conversation_service: ConversationService,
+    pub message_service: MessageService,
+    pub report_service: ReportService,
+    pub announcement_service: AnnouncementService,
+    pub notification_service: NotificationService,
+    pub audit_service: AuditService,
+    pub webhook_service: WebhookService,
+    pub call_service: CallService,
+    pub guest_service: GuestService,
+    pub key_service: KeyService,
+    pub search_service: SearchService,
+    pub event_service: EventService,
+}
+
+/// Phần chỉ dùng một lần để spawn background workers (receiver của các hàng đợi, ...)
+pub struct BackgroundWorkers {
+    fanout_instance_id: Uuid,
+    profile_cache: LocalProfileCache,
+    event_outbox: EventOutbox,
+    webhook_queue: WebhookQueue,
+    webhook_repo: WebhookRepositoryPg,
+    device_repo: DeviceRepositoryPg,
+    push_jobs: mpsc::Receiver<PushJob>,
+}
+
+impl AppState {
+    /// Dựng repositories, services và start WebSocket server (phải gọi trong actix runtime)
+    pub fn build(
+        db_pool: PgPool,
+        read_pool: ReadPool,
+        redis_pool: RedisCache,
+    ) -> (Self, BackgroundWorkers) {
+        let uow: Arc<dyn UnitOfWork> = Arc::new(db_pool.clone());
+        let user_repo = UserRepositoryPg::new(db_pool.clone()).with_read_pool(read_pool.clone());
+        let friend_repo = FriendRepositoryPg::new(db_pool.clone());
+        let presence_service = PresenceService::new(redis_pool.get_pool().clone());
+        let participant_repo = ParticipantPgRepository::new(db_pool.clone());
+        let message_repo =
+            MessageRepositoryPg::new(db_pool.clone()).with_read_pool(read_pool.clone());
+        let conversation_repo =
+            ConversationPgRepository::new(db_pool.clone(), participant_repo.clone())
+                .with_read_pool(read_pool.clone());
+        let last_message_repo = LastMessagePgRepository::default();
+        let file_repo = FilePgRepository::new(db_pool.clone());
+        let report_repo = ReportRepositoryPg::new(db_pool.clone());
+        let fanout_bridge = FanoutBridge::start(redis_pool.get_pool().clone());
+        let event_buffer = EventBuffer::new(redis_pool.get_pool().clone());
+        let ws_server = WebSocketServer::new()
+            .with_bridge(fanout_bridge.clone())
+            .with_event_buffer(event_buffer.clone())
+            .with_limits(ENV.ws_max_sessions_per_user, ENV.ws_max_total_sessions)
+            .start();
+        let profile_cache = LocalProfileCache::default();
+        let content_filter = ContentFilter::from_env(Arc::new(report_repo.clone()));
+        let notification_repo = NotificationRepositoryPg::new(db_pool.clone());
+        let audit_service = AuditService::with_dependencies(
+            Arc::new(AuditLogRepositoryPg::new(db_pool.clone())),
+            Arc::new(notification_repo.clone()),
+        );
+        let user_service = UserService::with_dependencies(
+            Arc::new(user_repo.clone()),
+            Arc::new(redis_pool.clone()),
+            Arc::new(LogMailer),
+            Arc::new(ws_server.clone()),
+            profile_cache.clone(),
+            content_filter.clone(),
+            audit_service.clone(),
+        );
+        let oauth_service = OAuthService::with_dependencies(
+            Arc::new(OAuthRepositoryPg::new(db_pool.clone())),
+            Arc::new(user_repo.clone()),
+            Arc::new(user_service.clone()),
+            Arc::new(redis_pool.clone()),
+        );
+        let friend_service = FriendService::with_dependencies(
+            Arc::new(friend_repo.clone()),
+            Arc::new(user_repo.clone()),
+            uow.clone(),
+            Arc::new(redis_pool.clone()),
+            Arc::new(ws_server.clone()),
+        );
+        let bot_service = BotService::with_dependencies(
+            Arc::new(BotRepositoryPg::new(db_pool.clone())),
+            Arc::new(redis_pool.clone()),
+        );
+        let file_upload_service =
+            FileUploadService::with_defaults(Arc::new(file_repo), uow.clone());
+        let conversation_service =
+            ConversationService::with_dependencies(ConversationServiceDeps {
+                conversation_repo: Arc::new(conversation_repo.clone()),
+                participant_repo: Arc::new(participant_repo.clone()),
+                message_repo: Arc::new(message_repo.clone()),
+                uow: uow.clone(),
+                ws_server: Arc::new(ws_server.clone()),
+                cache: Arc::new(redis_pool.clone()),
+                moderation: content_filter.clone(),
+                senders: Arc::new(user_service.clone()),
+            });
+        let report_service =
+            ReportService::with_dependencies(Arc::new(report_repo), Arc::new(ws_server.clone()));
+        let announcement_service = AnnouncementService::with_dependencies(
+            Arc::new(AnnouncementRepositoryPg::new(db_pool.clone())),
+            Arc::new(ws_server.clone()),
+        );
+        let device_repo = DeviceRepositoryPg::new(db_pool.clone());
+        let notification_service = NotificationService::with_dependencies(
+            Arc::new(device_repo.clone()),
+            Arc::new(notification_repo),
+        );
+        let (push_queue, push_jobs) = PushQueue::new();
+        let event_outbox = EventOutbox::new();
+        let webhook_repo = WebhookRepositoryPg::new(db_pool.clone());
+        let webhook_service = WebhookService::with_dependencies(Arc::new(webhook_repo.clone()));
+        let webhook_queue = WebhookQueue::new();
+        let message_service = MessageService::with_dependencies(MessageServiceDeps {
+            conversation_repo: Arc::new(conversation_repo.clone()),
+            message_repo: Arc::new(message_repo),
+            participant_repo: Arc::new(participant_repo),
+            last_message_repo: Arc::new(last_message_repo),
+            uow,
+            cache: Arc::new(redis_pool.clone()),
+            ws_server: Arc::new(ws_server.clone()),
+            push_queue,
+            events: event_outbox.clone(),
+            webhooks: webhook_queue.clone(),
+            moderation: content_filter,
+            commands: Arc::new(CommandRegistry::with_builtin()),
+            senders: Arc::new(user_service.clone()),
+            direct_policy: Arc::new(friend_service.clone()),
+            translator: translator_from_env(reqwest::Client::new()),
+        });
+        let guest_service = GuestService::with_dependencies(
+            Arc::new(GuestRepositoryPg::new(db_pool.clone())),
+            Arc::new(user_service.clone()),
+        );
+        let key_service =
+            KeyService::with_dependencies(Arc::new(KeyRepositoryPg::new(db_pool.clone())));
+        let search_service =
+            SearchService::with_dependencies(Arc::new(SearchRepositoryPg::new(read_pool.clone())));
+        let event_service = EventService::with_dependencies(event_buffer);
+        let call_service = CallService::with_dependencies(
+            Arc::new(CallRepositoryPg::new(db_pool.clone())),
+            Arc::new(ws_server.clone()),
+            Arc::new(message_service.clone()),
+        );
+
+        let workers = BackgroundWorkers {
+            fanout_instance_id: fanout_bridge.instance_id(),
+            profile_cache,
+            event_outbox,
+            webhook_queue,
+            webhook_repo,
+            device_repo,
+            push_jobs,
+        };
+
+        let state = AppState {
+            db_pool,
+            read_pool,
+            redis: redis_pool,
+            ws_server,
+            presence_service,
+            friend_repo,
+            user_service,
+            oauth_service,
+            friend_service,
+            bot_service,
+            file_upload_service,
+            conversation_service,
+            message_service,
+            report_service,
+            announcement_service,
+            notification_service,
+            audit_service,
+            webhook_service,
+            call_service,
+            guest_service,
+            key_service,
+            search_service,
+            event_service,
+        };
+
+        (state, workers)
+    }
+}
+
+impl BackgroundWorkers {
+    /// Spawn các background workers lên actix runtime hiện tại
+    pub fn spawn(self, state: &AppState) {
+        // Nhận events routing từ các instances khác và deliver tới local WebSocket sessions
+        actix_web::rt::spawn(run_fanout_listener(self.fanout_instance_id, state.ws_server.clone()));
+
+        // Nhận profile invalidation từ mọi instance để evict local cache và notify friends
+        actix_web::rt::spawn(run_invalidation_listener(
+            self.profile_cache,
+            state.friend_repo.clone(),
+            state.ws_server.clone(),
+        ));
+
+        // Route các broadcasts đã commit trong event outbox
+        actix_web::rt::spawn(run_event_dispatcher(
+            self.event_outbox,
+            state.db_pool.clone(),
+            state.ws_server.clone(),
+        ));
+
+        // Gửi outgoing webhooks cho tin nhắn mới, retry với backoff
+        actix_web::rt::spawn(run_webhook_worker(self.webhook_queue, Arc::new(self.webhook_repo)));
+
+        // Gửi push notification cho tin nhắn mới tới participants đang offline
+        actix_web::rt::spawn(run_push_worker(
+            self.push_jobs,
+            Arc::new(self.device_repo),
+            state.presence_service.clone(),
+            PushSenders::from_env(reqwest::Client::new()),
+        ));
+
+        // Gửi tin nhắn hẹn giờ khi tới thời điểm đã đặt
+        actix_web::rt::spawn(run_scheduled_message_worker(state.message_service.clone()));
+
+        // Xóa messages cũ hơn retention policy
+        actix_web::rt::spawn(run_message_retention_worker(state.message_service.clone()));
+
+        // Chuyển messages cũ sang bảng archive
+        if ENV.message_archive_after_months > 0 {
+            actix_web::rt::spawn(run_message_archive_worker(state.message_service.clone()));
+        }
+
+        // Sửa unread counts bị lệch so với messages chưa đọc
+        actix_web::rt::spawn(run_unread_reconciliation(state.conversation_service.clone()));
+
+        // Dọn lời mời kết bạn đã hết hạn
+        actix_web::rt::spawn(run_friend_request_cleanup(state.friend_service.clone()));
+
+        // Đánh dấu nhỡ các cuộc gọi đổ chuông quá lâu không được trả lời
+        actix_web::rt::spawn(run_call_timeout_worker(state.call_service.clone()));
+
+        // Đánh dấu / tạm khóa tài khoản lâu không hoạt động
+        if ENV.inactive_account_days > 0 {
+            actix_web::rt::spawn(run_inactive_account_worker(state.user_service.clone()));
+        }
+
+        // Cảnh báo khi Postgres / Redis pool dùng hết connection
+        actix_web::rt::spawn(monitor_pool_saturation(state.db_pool.clone(), state.redis.clone()));
+    }
+}
+
+/// Routes dùng chung cho tất cả API versions
+pub fn api_routes(cfg: &mut web::ServiceConfig) {
+    cfg.configure(modules::oauth::route::public_api_configure)
+        .configure(modules::user::route::public_api_configure)
+        .configure(modules::webhook::route::public_api_configure)
+        .configure(modules::guest::route::public_api_configure)
+        .service(
+            web::scope("/admin")
+                .wrap(from_fn(authorization(vec![UserRole::Admin])))
+                .wrap(from_fn(authentication))
+                .configure(modules::user::route::admin_configure)
+                .configure(modules::conversation::route::admin_configure)
+                .configure(modules::message::route::admin_configure)
+                .configure(modules::report::route::admin_configure)
+                .configure(modules::announcement::route::admin_configure)
+                .configure(modules::audit::route::admin_configure),
+        )
+        .service(
+            web::scope("/bot")
+                .wrap(from_fn(authorization(vec![UserRole::Bot])))
+                .wrap(from_fn(authentication))
+                .configure(modules::bot::route::bot_configure),
+        )
+        .service(
+            web::scope("/guest")
+                .wrap(from_fn(authorization(vec![UserRole::Guest])))
+                .wrap(from_fn(authentication))
+                .configure(modules::guest::route::guest_configure),
+        )
+        .service(
+            web::scope("")
+                .wrap(from_fn(authorization(vec![UserRole::User, UserRole::Admin])))
+                .wrap(from_fn(authentication))
+                .configure(modules::user::route::configure)
+                .configure(modules::friend::route::configure)
+                .configure(modules::webhook::route::configure)
+                .configure(modules::guest::route::configure)
+                .configure(modules::conversation::route::configure)
+                .configure(modules::message::route::configure)
+                .configure(modules::report::route::configure)
+                .configure(modules::notification::route::configure)
+                .configure(modules::bot::route::configure)
+                .configure(modules::call::route::configure)
+                .configure(modules::keys::route::configure)
+                .configure(modules::announcement::route::configure)
+                .configure(modules::search::route::configure)
+                .configure(modules::event::route::configure)
+                .configure(modules::file_upload::route::configure),
+        );
+}
+
+/// App đầy đủ: middlewares, app data và mọi routes (HTTP API, WebSocket, health, docs)
+pub fn build_app(
+    state: &AppState,
+    cors_config: &CorsConfig,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    let user_service = state.user_service.clone();
+
+    App::new()
+        .wrap(from_fn(skip_compression))
+        .wrap(compress())
+        .wrap(build_cors(cors_config))
+        .wrap(Logger::default())
+        .app_data(json_config(ENV.json_body_limit))
+        .app_data(web::PayloadConfig::new(ENV.json_body_limit))
+        .app_data(web::Data::new(user_service.clone()))
+        .app_data(web::Data::from(Arc::new(user_service.clone()) as Arc<dyn TokenRevocation>))
+        .app_data(web::Data::from(Arc::new(user_service) as Arc<dyn ActivityTracker>))
+        .app_data(web::Data::from(Arc::new(state.bot_service.clone()) as Arc<dyn ApiKeyResolver>))
+        .app_data(web::Data::new(state.bot_service.clone()))
+        .app_data(web::Data::new(state.oauth_service.clone()))
+        .app_data(web::Data::new(state.friend_service.clone()))
+        .app_data(web::Data::new(state.file_upload_service.clone()))
+        .app_data(web::Data::new(state.db_pool.clone()))
+        .app_data(web::Data::new(state.read_pool.clone()))
+        .app_data(web::Data::new(state.conversation_service.clone()))
+        .app_data(web::Data::new(state.message_service.clone()))
+        .app_data(web::Data::new(state.report_service.clone()))
+        .app_data(web::Data::new(state.announcement_service.clone()))
+        .app_data(web::Data::new(state.notification_service.clone()))
+        .app_data(web::Data::new(state.audit_service.clone()))
+        .app_data(web::Data::new(state.webhook_service.clone()))
+        .app_data(web::Data::new(state.call_service.clone()))
+        .app_data(web::Data::new(state.guest_service.clone()))
+        .app_data(web::Data::new(state.key_service.clone()))
+        .app_data(web::Data::new(state.search_service.clone()))
+        .app_data(web::Data::new(state.event_service.clone()))
+        .app_data(web::Data::new(state.ws_server.clone())) // WebSocket server
+        .app_data(web::Data::new(state.presence_service.clone())) // Presence service
+        .app_data(web::Data::new(state.friend_repo.clone())) // Friend repo for WS presence
+        .app_data(web::Data::new(state.redis.clone())) // Redis cho readiness probe
+        // Liveness /healthz và readiness /readyz
+        .configure(api::health::configure)
+        // Prometheus metrics /metrics (bearer token METRICS_TOKEN)
+        .configure(api::metrics::configure)
+        // Public keys để verify JWT /.well-known/jwks.json
+        .configure(api::jwks::configure)
+        // WebSocket endpoint (không cần authentication - auth trong WS handshake)
+        .route("/ws", web::get().to(websocket_handler))
+        // Socket.IO v4 compatible endpoint (cùng protocol, frame format của socket.io)
+        .service(
+            web::resource(["/socket.io", "/socket.io/"]).route(web::get().to(socketio_handler)),
+        )
+        // Swagger UI + OpenAPI spec, mount trước các scope /api
+        .service(web::redirect(api::docs::DOCS_PATH, format!("{}/", api::docs::DOCS_PATH)))
+        .service(api::docs::swagger_ui())
+        // /api/v1 phải được mount trước /api legacy (scope không fall through)
+        .service(api::version::mount(ApiVersion::v1(), api_routes))
+        .service(api::version::mount(ApiVersion::legacy(), api_routes))
+}
@@ -0,0 +1,224 @@
+/// End-to-end Tests
+///
+/// Chạy Postgres và Redis thật bằng testcontainers, apply migrations, dựng app đầy đủ bằng
+/// `AppState::build` + `build_app` (cùng wiring với `serve`) rồi đi qua flow chính bằng
+/// HTTP + WebSocket: signup → signin → kết bạn → tạo conversation → gửi tin nhắn →
+/// nhận `new-message` qua WebSocket.
+///
+/// Cần Docker nên bị `#[ignore]`, chạy riêng bằng:
+/// `cargo test app::integration -- --ignored`
+///
+/// `ENV` là global nên `DATABASE_URL` / `REDIS_URL` phải trỏ tới containers trước khi có
+/// code nào đọc `ENV`, vì vậy không chạy chung process với các test khác.
+use std::time::Duration;
+
+use actix_web::http::StatusCode;
+use awc::{error::WsProtocolError, ws};
+use futures_util::{SinkExt, Stream, StreamExt};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use testcontainers_modules::{
+    postgres::Postgres,
+    redis::Redis,
+    testcontainers::{runners::AsyncRunner, ImageExt},
+};
+use uuid::Uuid;
+
+use crate::{
+    app::{build_app, AppState},
+    configs::{connect_database, cors::CorsConfig, read_replica::ReadPool, RedisCache},
+    ENV,
+};
+
+const PASSWORD: &str = "integration-password";
+
+/// Thời gian tối đa chờ một event WebSocket
+const WS_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn init_env(database_url: &str, redis_url: &str) {
+    std::env::set_var("DATABASE_URL", database_url);
+    std::env::set_var("REDIS_URL", redis_url);
+    std::env::set_var("RUN_MIGRATIONS", "true");
+    if std::env::var("SECRET_KEY").is_err() {
+        std::env::set_var("SECRET_KEY", "integration-test-secret");
+    }
+
+    assert_eq!(
+        ENV.database_url, database_url,
+        "ENV was initialized before the integration test, run it alone: \
+         cargo test app::integration -- --ignored"
+    );
+}
+
+struct TestUser {
+    id: Uuid,
+    token: String,
+}
+
+/// Đăng ký, xác thực email (mail chỉ được log) và đăng nhập
+async fn sign_up(srv: &actix_test::TestServer, db_pool: &PgPool, username: &str) -> TestUser {
+    let mut res = srv
+        .post("/api/v1/auth/signup")
+        .send_json(&json!({
+            "username": username,
+            "email": format!("{username}@example.com"),
+            "password": PASSWORD,
+            "display_name": username,
+        }))
+        .await
+        .expect("signup request failed");
+    assert_eq!(res.status(), StatusCode::CREATED, "signup of {username}");
+    let body: Value = res.json().await.unwrap();
+    let id: Uuid = serde_json::from_value(body["data"]["id"].clone()).unwrap();
+
+    sqlx::query("UPDATE users SET email_verified = true WHERE id = $1")
+        .bind(id)
+        .execute(db_pool)
+        .await
+        .unwrap();
+
+    let mut res = srv
+        .post("/api/v1/auth/signin")
+        .send_json(&json!({ "username": username, "password": PASSWORD }))
+        .await
+        .expect("signin request failed");
+    assert_eq!(res.status(), StatusCode::OK, "signin of {username}");
+    let body: Value = res.json().await.unwrap();
+    let token = body["data"]["access_token"].as_str().expect("missing access_token").to_string();
+
+    TestUser { id, token }
+}
+
+/// POST JSON với token của `user`, trả về `data` của response
+async fn post(
+    srv: &actix_test::TestServer,
+    user: &TestUser,
+    path: &str,
+    body: Value,
+    expected: StatusCode,
+) -> Value {
+    let mut res = srv
+        .post(path)
+        .bearer_auth(&user.token)
+        .send_json(&body)
+        .await
+        .unwrap_or_else(|e| panic!("POST {path} failed: {e}"));
+    let status = res.status();
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(status, expected, "POST {path}: {body}");
+    body["data"].clone()
+}
+
+/// Đợi event có `type` là `event_type`, bỏ qua các events khác (presence, ...)
+async fn next_event(
+    conn: &mut (impl Stream<Item = Result<ws::Frame, WsProtocolError>> + Unpin),
+    event_type: &str,
+) -> Value {
+    let wait = async {
+        while let Some(frame) = conn.next().await {
+            if let ws::Frame::Text(bytes) = frame.expect("WebSocket protocol error") {
+                let event: Value = serde_json::from_slice(&bytes).unwrap();
+                if event["type"] == event_type {
+                    return event;
+                }
+            }
+        }
+        panic!("WebSocket closed before {event_type}");
+    };
+
+    actix_web::rt::time::timeout(WS_TIMEOUT, wait)
+        .await
+        .unwrap_or_else(|_| panic!("Timed out waiting for {event_type}"))
+}
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn chat_flow_end_to_end() {
+    let postgres = Postgres::default()
+        .with_tag("16-alpine")
+        .start()
+        .await
+        .expect("Failed to start Postgres container");
+    let redis = Redis::default().start().await.expect("Failed to start Redis container");
+
+    let database_url = format!(
+        "postgres://postgres:postgres@{}:{}/postgres",
+        postgres.get_host().await.unwrap(),
+        postgres.get_host_port_ipv4(5432).await.unwrap()
+    );
+    let redis_url = format!(
+        "redis://{}:{}/",
+        redis.get_host().await.unwrap(),
+        redis.get_host_port_ipv4(6379).await.unwrap()
+    );
+    init_env(&database_url, &redis_url);
+
+    let db_pool = connect_database().await.expect("Failed to connect to Postgres");
+    let redis_pool = RedisCache::new().await.expect("Failed to connect to Redis");
+    let read_pool = ReadPool::primary_only(db_pool.clone());
+    let (state, workers) = AppState::build(db_pool.clone(), read_pool, redis_pool);
+    workers.spawn(&state);
+
+    let cors_config = CorsConfig::from_env();
+    let mut srv = actix_test::start(move || build_app(&state, &cors_config));
+
+    let alice = sign_up(&srv, &db_pool, "alice").await;
+    let bob = sign_up(&srv, &db_pool, "bob").await;
+
+    // Kết bạn
+    let request = post(
+        &srv,
+        &alice,
+        "/api/v1/friends/requests",
+        json!({ "recipient_id": bob.id }),
+        StatusCode::CREATED,
+    )
+    .await;
+    let request_id = request["id"].as_str().expect("missing friend request id");
+    post(
+        &srv,
+        &bob,
+        &format!("/api/v1/friends/requests/{request_id}/accept"),
+        json!({}),
+        StatusCode::OK,
+    )
+    .await;
+
+    // Conversation 1-1
+    let conversation = post(
+        &srv,
+        &alice,
+        "/api/v1/conversations",
+        json!({ "type": "direct", "name": "", "member_ids": [bob.id] }),
+        StatusCode::OK,
+    )
+    .await;
+    let conversation_id = conversation["conversation_id"].clone();
+
+    // Bob kết nối WebSocket và xác thực
+    let mut conn = srv.ws_at("/ws").await.expect("WebSocket handshake failed");
+    conn.send(ws::Message::Text(json!({ "type": "auth", "token": bob.token }).to_string().into()))
+        .await
+        .unwrap();
+    let auth = next_event(&mut conn, "auth-success").await;
+    assert_eq!(auth["user_id"], json!(bob.id));
+
+    // Alice gửi tin nhắn qua REST, Bob nhận qua WebSocket
+    let sent = post(
+        &srv,
+        &alice,
+        "/api/v1/messages/direct/",
+        json!({
+            "conversation_id": conversation_id,
+            "recipient_id": bob.id,
+            "content": "Hello Bob",
+        }),
+        StatusCode::OK,
+    )
+    .await;
+
+    let event = next_event(&mut conn, "new-message").await;
+    assert_eq!(event["message"]["content"], "Hello Bob");
+    assert_eq!(event["conversation"]["_id"], conversation_id);
+    assert_eq!(event["message"]["id"], sent["id"], "sent: {sent}, received: {event}");
+}
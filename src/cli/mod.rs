@@ -0,0 +1,206 @@
+/// Command Line Interface
+///
+/// Không có command thì chạy server (`serve`). Các command còn lại là tác vụ bảo trì cho
+/// operator, dùng cùng cấu hình (`.env`, `CONFIG_FILE`) với server:
+/// - `migrate`: chạy database migrations rồi thoát (`--migrate` cũ vẫn dùng được)
+/// - `create-admin --username <u> --email <e>`: tạo tài khoản admin đã xác thực email
+/// - `prune-files [--older-than-hours <h>] [--dry-run]`: dọn files không còn được dùng
+/// - `recount-unread`: tính lại unread counts ngay, không chờ task định kỳ của server
+/// - `seed`: sinh dữ liệu giả cho development (xem `seed`)
+use std::sync::Arc;
+
+use clap::{Parser, Subcommand};
+use validator::ValidateEmail;
+
+use seed::SeedOptions;
+
+use crate::{
+    api::error,
+    configs::connect_database,
+    modules::{
+        conversation::{reconcile, repository_pg::ParticipantPgRepository},
+        file_upload::{repository_pg::FilePgRepository, service::FileUploadService},
+        user::{
+            model::InsertUser,
+            repository::UserRepository,
+            repository_pg::UserRepositoryPg,
+            schema::UserRole,
+            service::{normalize_username, username_reservation_cutoff},
+        },
+    },
+    utils::{hash_password, random_token},
+};
+
+pub mod seed;
+
+/// Độ dài mật khẩu được sinh khi `create-admin` không có `--password`
+const GENERATED_PASSWORD_LEN: usize = 20;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "AppChat backend server and maintenance commands")]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Alias cũ của `migrate`
+    #[arg(long, hide = true)]
+    migrate: bool,
+}
+
+impl Cli {
+    /// Command cần chạy, mặc định `serve`
+    pub fn command(self) -> Command {
+        match self.command {
+            Some(command) => command,
+            None if self.migrate => Command::Migrate,
+            None => Command::Serve,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Start the HTTP / WebSocket server (default)
+    Serve,
+    /// Run database migrations and exit
+    Migrate,
+    /// Create an admin account with a verified email
+    CreateAdmin {
+        #[arg(long)]
+        username: String,
+        #[arg(long)]
+        email: String,
+        /// Password of the account, a random one is generated and printed when omitted
+        #[arg(long, env = "ADMIN_PASSWORD", hide_env_values = true)]
+        password: Option<String>,
+    },
+    /// Delete uploaded files no longer referenced and files missing from the database
+    PruneFiles {
+        /// Only prune files older than this many hours
+        #[arg(long, default_value_t = 24)]
+        older_than_hours: i64,
+        /// Report what would be pruned without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Recompute the unread count of every participant
+    RecountUnread,
+    /// Populate the database with fake users, friendships, conversations and messages
+    Seed {
+        #[arg(long, default_value_t = 50)]
+        users: usize,
+        #[arg(long, default_value_t = 5)]
+        friends_per_user: usize,
+        #[arg(long, default_value_t = 10)]
+        groups: usize,
+        #[arg(long, default_value_t = 50)]
+        messages_per_conversation: usize,
+        /// Password shared by every seeded user
+        #[arg(long, default_value = "password")]
+        password: String,
+    },
+}
+
+pub async fn create_admin(
+    username: String,
+    email: String,
+    password: Option<String>,
+) -> Result<(), error::SystemError> {
+    let username = normalize_username(&username)?;
+    if !email.validate_email() {
+        return Err(error::SystemError::bad_request("Invalid email format"));
+    }
+
+    let generated = password.is_none();
+    let password = password.unwrap_or_else(|| random_token(GENERATED_PASSWORD_LEN));
+    if password.chars().count() < 6 {
+        return Err(error::SystemError::bad_request("Password must be at least 6 characters long"));
+    }
+
+    let pool = connect_database().await?;
+    let repo = UserRepositoryPg::new(pool.clone());
+
+    if !repo.is_username_available(&username, None, username_reservation_cutoff()).await? {
+        return Err(error::SystemError::conflict("username"));
+    }
+    if repo.find_by_email(&email).await?.is_some() {
+        return Err(error::SystemError::conflict("email"));
+    }
+
+    let user_id = repo
+        .create(&InsertUser {
+            username: username.clone(),
+            email,
+            hash_password: hash_password(&password)?,
+            display_name: username.clone(),
+        })
+        .await?;
+    repo.set_role(&user_id, &UserRole::Admin).await?;
+    repo.mark_email_verified(&user_id).await?;
+    pool.close().await;
+
+    println!("Created admin {username} ({user_id})");
+    if generated {
+        println!("Generated password: {password}");
+    }
+
+    Ok(())
+}
+
+pub async fn prune_files(older_than_hours: i64, dry_run: bool) -> Result<(), error::SystemError> {
+    if older_than_hours < 0 {
+        return Err(error::SystemError::bad_request("--older-than-hours must not be negative"));
+    }
+
+    let pool = connect_database().await?;
+    let service = FileUploadService::with_defaults(
+        Arc::new(FilePgRepository::new(pool.clone())),
+        Arc::new(pool.clone()),
+    );
+    let report = service.prune_files(chrono::Duration::hours(older_than_hours), dry_run).await;
+    pool.close().await;
+    let report = report?;
+
+    println!(
+        "{} {} unreferenced file(s) and {} orphaned file(s), {} bytes",
+        if dry_run { "Would prune" } else { "Pruned" },
+        report.unreferenced,
+        report.orphaned,
+        report.bytes
+    );
+
+    Ok(())
+}
+
+/// Clients đang kết nối nhận count mới ở lần tải conversations tiếp theo (không có
+/// WebSocket server trong process CLI để gửi `unread-count-corrected`)
+pub async fn recount_unread() -> Result<(), error::SystemError> {
+    let pool = connect_database().await?;
+    let report = reconcile::reconcile_all(
+        &pool,
+        &ParticipantPgRepository::new(pool.clone()),
+        reconcile::BATCH_SIZE,
+        |_| {},
+    )
+    .await;
+    pool.close().await;
+    let report = report?;
+
+    println!("Checked {} participant(s), corrected {}", report.scanned, report.corrected);
+
+    Ok(())
+}
+
+pub async fn seed(options: SeedOptions) -> Result<(), error::SystemError> {
+    let pool = connect_database().await?;
+    let report = seed::seed(&pool, &options).await;
+    pool.close().await;
+    let report = report?;
+
+    println!(
+        "Seeded {} user(s), {} friendship(s), {} conversation(s), {} message(s)",
+        report.users, report.friendships, report.conversations, report.messages
+    );
+
+    Ok(())
+}
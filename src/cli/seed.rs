@@ -0,0 +1,340 @@
+/// Development Seed Data
+///
+/// Sinh users, bạn bè, direct / group conversations và lịch sử tin nhắn giả để phát triển
+/// frontend và load test. Mọi user đã xác thực email và dùng chung mật khẩu `--password`.
+/// Mỗi cặp bạn bè có một direct conversation; tin nhắn rải đều trong `HISTORY_DAYS` ngày
+/// gần nhất và participants đã đọc hết (unread = 0). Mỗi lần chạy dùng một tag ngẫu
+/// nhiên trong username nên có thể seed nhiều lần vào cùng database.
+///
+/// Chỉ dùng cho development: dữ liệu được insert thẳng bằng SQL, bỏ qua business rules
+/// của services (giới hạn bạn bè, privacy...).
+use std::collections::BTreeSet;
+
+use fake::{
+    faker::{
+        company::en::CatchPhrase,
+        lorem::en::Sentence,
+        name::en::{FirstName, LastName},
+    },
+    Fake,
+};
+use rand::{seq::SliceRandom, Rng};
+use uuid::Uuid;
+
+use crate::{api::error, utils::hash_password};
+
+/// Tin nhắn được rải trong khoảng này tính tới hiện tại
+const HISTORY_DAYS: i64 = 30;
+
+/// Số rows mỗi câu INSERT
+const INSERT_BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct SeedOptions {
+    pub users: usize,
+    pub friends_per_user: usize,
+    pub groups: usize,
+    pub messages_per_conversation: usize,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SeedReport {
+    pub users: usize,
+    pub friendships: usize,
+    pub conversations: usize,
+    pub messages: usize,
+}
+
+struct SeedUser {
+    id: Uuid,
+    username: String,
+    email: String,
+    display_name: String,
+}
+
+struct SeedConversation {
+    id: Uuid,
+    /// `None` là direct conversation
+    group: Option<(String, Uuid)>,
+    members: Vec<Uuid>,
+}
+
+struct SeedMessage {
+    id: Uuid,
+    conversation_id: Uuid,
+    sender_id: Uuid,
+    content: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Dữ liệu của một lần seed, sinh xong toàn bộ trước khi ghi database
+struct SeedData {
+    users: Vec<SeedUser>,
+    friendships: Vec<(Uuid, Uuid)>,
+    conversations: Vec<SeedConversation>,
+    messages: Vec<SeedMessage>,
+}
+
+pub async fn seed(
+    pool: &sqlx::PgPool,
+    options: &SeedOptions,
+) -> Result<SeedReport, error::SystemError> {
+    if options.users < 2 {
+        return Err(error::SystemError::bad_request("--users must be at least 2"));
+    }
+
+    let hash_password = hash_password(&options.password)?;
+    let data = generate(options);
+
+    let mut tx = pool.begin().await?;
+    insert_users(&mut tx, &data.users, &hash_password).await?;
+    insert_friendships(&mut tx, &data.friendships).await?;
+    insert_conversations(&mut tx, &data.conversations).await?;
+    insert_messages(&mut tx, &data.messages).await?;
+    mark_conversations_read(&mut tx, &data.conversations).await?;
+    tx.commit().await?;
+
+    Ok(SeedReport {
+        users: data.users.len(),
+        friendships: data.friendships.len(),
+        conversations: data.conversations.len(),
+        messages: data.messages.len(),
+    })
+}
+
+fn generate(options: &SeedOptions) -> SeedData {
+    let mut rng = rand::thread_rng();
+    let tag: String = (0..4).map(|_| rng.gen_range('a'..='z')).collect();
+
+    let users: Vec<SeedUser> = (0..options.users)
+        .map(|i| {
+            let first: String = FirstName().fake_with_rng(&mut rng);
+            let last: String = LastName().fake_with_rng(&mut rng);
+            let username = format!("{}_{}_{tag}{i}", first, last)
+                .to_lowercase()
+                .chars()
+                .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+                .collect::<String>();
+            SeedUser {
+                id: Uuid::now_v7(),
+                email: format!("{username}@example.test"),
+                display_name: format!("{first} {last}"),
+                username,
+            }
+        })
+        .collect();
+    let user_ids: Vec<Uuid> = users.iter().map(|user| user.id).collect();
+
+    // friends.user_a < friends.user_b
+    let mut friendships = BTreeSet::new();
+    for &user_id in &user_ids {
+        for &friend_id in user_ids.choose_multiple(&mut rng, options.friends_per_user + 1) {
+            if friend_id != user_id {
+                friendships.insert((user_id.min(friend_id), user_id.max(friend_id)));
+            }
+        }
+    }
+    let friendships: Vec<(Uuid, Uuid)> = friendships.into_iter().collect();
+
+    let mut conversations: Vec<SeedConversation> = friendships
+        .iter()
+        .map(|&(a, b)| SeedConversation { id: Uuid::now_v7(), group: None, members: vec![a, b] })
+        .collect();
+    for _ in 0..options.groups {
+        let size = rng.gen_range(3..=8).min(user_ids.len());
+        let members: Vec<Uuid> = user_ids.choose_multiple(&mut rng, size).copied().collect();
+        let name: String = CatchPhrase().fake_with_rng(&mut rng);
+        conversations.push(SeedConversation {
+            id: Uuid::now_v7(),
+            group: Some((name, members[0])),
+            members,
+        });
+    }
+
+    let now = chrono::Utc::now();
+    let history = chrono::Duration::days(HISTORY_DAYS).num_seconds();
+    let mut messages = Vec::new();
+    for conversation in &conversations {
+        let mut offsets: Vec<i64> =
+            (0..options.messages_per_conversation).map(|_| rng.gen_range(0..history)).collect();
+        offsets.sort_unstable_by(|a, b| b.cmp(a));
+
+        for offset in offsets {
+            let created_at = now - chrono::Duration::seconds(offset);
+            // id theo thứ tự thời gian như message thật (UUID v7)
+            let timestamp = uuid::Timestamp::from_unix(
+                uuid::NoContext,
+                created_at.timestamp() as u64,
+                created_at.timestamp_subsec_nanos(),
+            );
+            messages.push(SeedMessage {
+                id: Uuid::new_v7(timestamp),
+                conversation_id: conversation.id,
+                sender_id: *conversation.members.choose(&mut rng).unwrap_or(&user_ids[0]),
+                content: Sentence(2..14).fake_with_rng(&mut rng),
+                created_at,
+            });
+        }
+    }
+
+    SeedData { users, friendships, conversations, messages }
+}
+
+async fn insert_users(
+    tx: &mut sqlx::PgConnection,
+    users: &[SeedUser],
+    hash_password: &str,
+) -> Result<(), error::SystemError> {
+    for batch in users.chunks(INSERT_BATCH_SIZE) {
+        sqlx::query(
+            r#"
+            INSERT INTO users (id, username, email, display_name, hash_password, email_verified)
+            SELECT id, username, email, display_name, $5, true
+            FROM UNNEST($1::uuid[], $2::text[], $3::text[], $4::text[])
+                AS u(id, username, email, display_name)
+            "#,
+        )
+        .bind(batch.iter().map(|user| user.id).collect::<Vec<_>>())
+        .bind(batch.iter().map(|user| user.username.clone()).collect::<Vec<_>>())
+        .bind(batch.iter().map(|user| user.email.clone()).collect::<Vec<_>>())
+        .bind(batch.iter().map(|user| user.display_name.clone()).collect::<Vec<_>>())
+        .bind(hash_password)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn insert_friendships(
+    tx: &mut sqlx::PgConnection,
+    friendships: &[(Uuid, Uuid)],
+) -> Result<(), error::SystemError> {
+    for batch in friendships.chunks(INSERT_BATCH_SIZE) {
+        sqlx::query(
+            r#"
+            INSERT INTO friends (user_a, user_b)
+            SELECT * FROM UNNEST($1::uuid[], $2::uuid[])
+            "#,
+        )
+        .bind(batch.iter().map(|&(a, _)| a).collect::<Vec<_>>())
+        .bind(batch.iter().map(|&(_, b)| b).collect::<Vec<_>>())
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn insert_conversations(
+    tx: &mut sqlx::PgConnection,
+    conversations: &[SeedConversation],
+) -> Result<(), error::SystemError> {
+    for batch in conversations.chunks(INSERT_BATCH_SIZE) {
+        let kind = |c: &SeedConversation| if c.group.is_some() { "group" } else { "direct" };
+        sqlx::query(
+            r#"
+            INSERT INTO conversations (id, type)
+            SELECT id, type::conversation_type FROM UNNEST($1::uuid[], $2::text[]) AS c(id, type)
+            "#,
+        )
+        .bind(batch.iter().map(|c| c.id).collect::<Vec<_>>())
+        .bind(batch.iter().map(|c| kind(c).to_string()).collect::<Vec<_>>())
+        .execute(&mut *tx)
+        .await?;
+
+        let groups: Vec<(Uuid, &String, Uuid)> = batch
+            .iter()
+            .filter_map(|c| c.group.as_ref().map(|(name, owner)| (c.id, name, *owner)))
+            .collect();
+        sqlx::query(
+            r#"
+            INSERT INTO group_conversations (conversation_id, name, created_by)
+            SELECT * FROM UNNEST($1::uuid[], $2::text[], $3::uuid[])
+            "#,
+        )
+        .bind(groups.iter().map(|&(id, _, _)| id).collect::<Vec<_>>())
+        .bind(groups.iter().map(|&(_, name, _)| name.clone()).collect::<Vec<_>>())
+        .bind(groups.iter().map(|&(_, _, owner)| owner).collect::<Vec<_>>())
+        .execute(&mut *tx)
+        .await?;
+
+        let members: Vec<(Uuid, Uuid)> =
+            batch.iter().flat_map(|c| c.members.iter().map(|&user_id| (c.id, user_id))).collect();
+        sqlx::query(
+            r#"
+            INSERT INTO participants (conversation_id, user_id)
+            SELECT * FROM UNNEST($1::uuid[], $2::uuid[])
+            "#,
+        )
+        .bind(members.iter().map(|&(id, _)| id).collect::<Vec<_>>())
+        .bind(members.iter().map(|&(_, user_id)| user_id).collect::<Vec<_>>())
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn insert_messages(
+    tx: &mut sqlx::PgConnection,
+    messages: &[SeedMessage],
+) -> Result<(), error::SystemError> {
+    for batch in messages.chunks(INSERT_BATCH_SIZE) {
+        sqlx::query(
+            r#"
+            INSERT INTO messages (id, conversation_id, sender_id, content, created_at, updated_at)
+            SELECT id, conversation_id, sender_id, content, created_at, created_at
+            FROM UNNEST($1::uuid[], $2::uuid[], $3::uuid[], $4::text[], $5::timestamptz[])
+                AS m(id, conversation_id, sender_id, content, created_at)
+            "#,
+        )
+        .bind(batch.iter().map(|m| m.id).collect::<Vec<_>>())
+        .bind(batch.iter().map(|m| m.conversation_id).collect::<Vec<_>>())
+        .bind(batch.iter().map(|m| m.sender_id).collect::<Vec<_>>())
+        .bind(batch.iter().map(|m| m.content.clone()).collect::<Vec<_>>())
+        .bind(batch.iter().map(|m| m.created_at).collect::<Vec<_>>())
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Ghi last message, `updated_at` của conversation và đánh dấu participants đã đọc hết
+async fn mark_conversations_read(
+    tx: &mut sqlx::PgConnection,
+    conversations: &[SeedConversation],
+) -> Result<(), error::SystemError> {
+    let ids: Vec<Uuid> = conversations.iter().map(|c| c.id).collect();
+
+    for batch in ids.chunks(INSERT_BATCH_SIZE) {
+        sqlx::query(
+            r#"
+            WITH latest AS (
+                SELECT DISTINCT ON (conversation_id)
+                    id, conversation_id, sender_id, content, created_at
+                FROM messages
+                WHERE conversation_id = ANY($1)
+                ORDER BY conversation_id, created_at DESC
+            ),
+            last_message AS (
+                INSERT INTO last_messages (id, content, conversation_id, sender_id, created_at)
+                SELECT id, content, conversation_id, sender_id, created_at FROM latest
+            ),
+            conversation AS (
+                UPDATE conversations c SET updated_at = latest.created_at
+                FROM latest WHERE c.id = latest.conversation_id
+            )
+            UPDATE participants p SET last_seen_message_id = latest.id
+            FROM latest WHERE p.conversation_id = latest.conversation_id
+            "#,
+        )
+        .bind(batch)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,264 @@
+/// Cache Backend
+///
+/// Services dùng cache qua `Arc<dyn CacheBackend>` thay vì `RedisCache` trực tiếp để có thể
+/// unit test không cần Redis. Trait làm việc với bytes (object-safe), các helper có kiểu
+/// (`get` / `set` / `set_nx` / `publish`, serialize JSON) nằm trên `dyn CacheBackend`.
+/// Tests dùng `MemoryCache`.
+use deadpool_redis::redis::AsyncCommands;
+
+use crate::{api::error, configs::RedisCache};
+
+#[async_trait::async_trait]
+pub trait CacheBackend: Send + Sync {
+    async fn get_raw(&self, key: &str) -> Result<Option<Vec<u8>>, error::SystemError>;
+
+    async fn set_raw(
+        &self,
+        key: &str,
+        value: Vec<u8>,
+        expiration: usize,
+    ) -> Result<(), error::SystemError>;
+
+    /// SET NX kèm TTL, trả về `true` nếu key chưa tồn tại và đã được set
+    async fn set_nx_raw(
+        &self,
+        key: &str,
+        value: Vec<u8>,
+        expiration: usize,
+    ) -> Result<bool, error::SystemError>;
+
+    /// INCR counter, TTL chỉ được đặt khi counter vừa được tạo (cửa sổ cố định)
+    async fn incr(&self, key: &str, expiration: usize) -> Result<i64, error::SystemError>;
+
+    async fn delete(&self, key: &str) -> Result<(), error::SystemError>;
+
+    async fn exists(&self, key: &str) -> Result<bool, error::SystemError>;
+
+    async fn publish_raw(&self, channel: &str, value: Vec<u8>) -> Result<(), error::SystemError>;
+}
+
+impl dyn CacheBackend {
+    pub async fn get<T>(&self, key: &str) -> Result<Option<T>, error::SystemError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        match self.get_raw(key).await? {
+            Some(v) => Ok(Some(serde_json::from_slice(&v)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn set<T>(
+        &self,
+        key: &str,
+        value: &T,
+        expiration: usize,
+    ) -> Result<(), error::SystemError>
+    where
+        T: serde::Serialize,
+    {
+        self.set_raw(key, serde_json::to_vec(value)?, expiration).await
+    }
+
+    /// SET NX kèm TTL, trả về `true` nếu key chưa tồn tại và đã được set
+    pub async fn set_nx<T>(
+        &self,
+        key: &str,
+        value: &T,
+        expiration: usize,
+    ) -> Result<bool, error::SystemError>
+    where
+        T: serde::Serialize,
+    {
+        self.set_nx_raw(key, serde_json::to_vec(value)?, expiration).await
+    }
+
+    pub async fn publish<T>(&self, channel: &str, value: &T) -> Result<(), error::SystemError>
+    where
+        T: serde::Serialize,
+    {
+        self.publish_raw(channel, serde_json::to_vec(value)?).await
+    }
+}
+
+#[async_trait::async_trait]
+impl CacheBackend for RedisCache {
+    async fn get_raw(&self, key: &str) -> Result<Option<Vec<u8>>, error::SystemError> {
+        let mut conn = self.pool.get().await?;
+        let value: Option<Vec<u8>> = conn.get(key).await?;
+        Ok(value)
+    }
+
+    async fn set_raw(
+        &self,
+        key: &str,
+        value: Vec<u8>,
+        expiration: usize,
+    ) -> Result<(), error::SystemError> {
+        let mut conn = self.pool.get().await?;
+        conn.set_ex::<_, _, ()>(key, value, expiration as u64).await?;
+        Ok(())
+    }
+
+    async fn set_nx_raw(
+        &self,
+        key: &str,
+        value: Vec<u8>,
+        expiration: usize,
+    ) -> Result<bool, error::SystemError> {
+        let mut conn = self.pool.get().await?;
+
+        let set: Option<String> = deadpool_redis::redis::cmd("SET")
+            .arg(key)
+            .arg(value)
+            .arg("NX")
+            .arg("EX")
+            .arg(expiration)
+            .query_async(&mut *conn)
+            .await?;
+
+        Ok(set.is_some())
+    }
+
+    async fn incr(&self, key: &str, expiration: usize) -> Result<i64, error::SystemError> {
+        let mut conn = self.pool.get().await?;
+
+        let count: i64 = conn.incr(key, 1).await?;
+        if count == 1 {
+            conn.expire::<_, ()>(key, expiration as i64).await?;
+        }
+
+        Ok(count)
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), error::SystemError> {
+        let mut conn = self.pool.get().await?;
+        conn.del::<_, ()>(key).await?;
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, error::SystemError> {
+        let mut conn = self.pool.get().await?;
+        let exists: bool = conn.exists(key).await?;
+        Ok(exists)
+    }
+
+    async fn publish_raw(&self, channel: &str, value: Vec<u8>) -> Result<(), error::SystemError> {
+        let mut conn = self.pool.get().await?;
+        conn.publish::<_, _, ()>(channel, value).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub use memory::MemoryCache;
+
+#[cfg(test)]
+mod memory {
+    use std::{
+        collections::HashMap,
+        sync::{Mutex, PoisonError},
+        time::{Duration, Instant},
+    };
+
+    use super::CacheBackend;
+    use crate::api::error;
+
+    /// Cache trong bộ nhớ cho unit tests, hỗ trợ TTL; `publish` chỉ ghi lại message
+    #[derive(Default)]
+    pub struct MemoryCache {
+        entries: Mutex<HashMap<String, (Vec<u8>, Instant)>>,
+        published: Mutex<Vec<(String, Vec<u8>)>>,
+    }
+
+    impl MemoryCache {
+        /// Các messages đã publish theo thứ tự (channel, payload JSON)
+        pub fn published(&self) -> Vec<(String, Vec<u8>)> {
+            self.published.lock().unwrap_or_else(PoisonError::into_inner).clone()
+        }
+
+        fn with_entries<T>(
+            &self,
+            f: impl FnOnce(&mut HashMap<String, (Vec<u8>, Instant)>) -> T,
+        ) -> T {
+            let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+            let now = Instant::now();
+            entries.retain(|_, (_, expires_at)| *expires_at > now);
+            f(&mut entries)
+        }
+    }
+
+    fn expires_at(expiration: usize) -> Instant {
+        Instant::now() + Duration::from_secs(expiration as u64)
+    }
+
+    #[async_trait::async_trait]
+    impl CacheBackend for MemoryCache {
+        async fn get_raw(&self, key: &str) -> Result<Option<Vec<u8>>, error::SystemError> {
+            Ok(self.with_entries(|entries| entries.get(key).map(|(value, _)| value.clone())))
+        }
+
+        async fn set_raw(
+            &self,
+            key: &str,
+            value: Vec<u8>,
+            expiration: usize,
+        ) -> Result<(), error::SystemError> {
+            self.with_entries(|entries| {
+                entries.insert(key.to_string(), (value, expires_at(expiration)));
+            });
+            Ok(())
+        }
+
+        async fn set_nx_raw(
+            &self,
+            key: &str,
+            value: Vec<u8>,
+            expiration: usize,
+        ) -> Result<bool, error::SystemError> {
+            Ok(self.with_entries(|entries| {
+                if entries.contains_key(key) {
+                    return false;
+                }
+                entries.insert(key.to_string(), (value, expires_at(expiration)));
+                true
+            }))
+        }
+
+        async fn incr(&self, key: &str, expiration: usize) -> Result<i64, error::SystemError> {
+            self.with_entries(|entries| {
+                let (value, _) = entries
+                    .entry(key.to_string())
+                    .or_insert_with(|| (b"0".to_vec(), expires_at(expiration)));
+                let count = std::str::from_utf8(value)
+                    .ok()
+                    .and_then(|v| v.parse::<i64>().ok())
+                    .ok_or_else(|| error::SystemError::internal_error("Value is not an integer"))?
+                    + 1;
+                *value = count.to_string().into_bytes();
+                Ok(count)
+            })
+        }
+
+        async fn delete(&self, key: &str) -> Result<(), error::SystemError> {
+            self.with_entries(|entries| entries.remove(key));
+            Ok(())
+        }
+
+        async fn exists(&self, key: &str) -> Result<bool, error::SystemError> {
+            Ok(self.with_entries(|entries| entries.contains_key(key)))
+        }
+
+        async fn publish_raw(
+            &self,
+            channel: &str,
+            value: Vec<u8>,
+        ) -> Result<(), error::SystemError> {
+            self.published
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push((channel.to_string(), value));
+            Ok(())
+        }
+    }
+}
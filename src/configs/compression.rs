@@ -0,0 +1,124 @@
+/// Response Compression
+///
+/// `Compress` của actix nén response theo `Accept-Encoding` của client (gzip, brotli, zstd),
+/// tắt hoàn toàn khi `COMPRESSION_ENABLED=false`. Response không đáng nén được đánh dấu
+/// `Content-Encoding: identity` trước khi tới `Compress` để được gửi nguyên:
+/// - body biết trước kích thước và nhỏ hơn `COMPRESSION_MIN_SIZE` bytes
+/// - content type không phải text / JSON (ảnh, video, archive... vốn đã được nén)
+use actix_web::{
+    body::{BodySize, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{self, HeaderValue},
+    middleware::{Compress, Condition, Next},
+    Error,
+};
+
+use crate::ENV;
+
+/// Middleware nén response, phải wrap bên ngoài `skip_compression`
+pub fn compress() -> Condition<Compress> {
+    Condition::new(ENV.compression_enabled, Compress::default())
+}
+
+/// Text, JSON, NDJSON, XML, JavaScript
+fn is_compressible(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    mime.starts_with("text/")
+        || mime.ends_with("/json")
+        || mime.ends_with("+json")
+        || mime.ends_with("/x-ndjson")
+        || mime.ends_with("/xml")
+        || mime.ends_with("+xml")
+        || mime.ends_with("/javascript")
+}
+
+/// Đánh dấu response nhỏ hoặc không nén được để `Compress` bỏ qua
+pub async fn skip_compression(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let mut res = next.call(req).await?;
+    if !ENV.compression_enabled || res.headers().contains_key(header::CONTENT_ENCODING) {
+        return Ok(res);
+    }
+
+    let too_small = match res.response().body().size() {
+        BodySize::Sized(size) => size < ENV.compression_min_size as u64,
+        BodySize::None => true,
+        BodySize::Stream => false,
+    };
+    let compressible = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(is_compressible);
+
+    if too_small || !compressible {
+        res.headers_mut().insert(header::CONTENT_ENCODING, HeaderValue::from_static("identity"));
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        middleware::from_fn,
+        test as actix_test,
+        web::{self, Bytes},
+        App, HttpResponse,
+    };
+
+    use super::*;
+    use crate::constants::init_test_env;
+
+    /// Gọi `path` với `Accept-Encoding: gzip`, trả về header `Content-Encoding`
+    async fn content_encoding(path: &str) -> Option<String> {
+        init_test_env();
+        let large = "x".repeat(ENV.compression_min_size * 2);
+        let app = actix_test::init_service(
+            App::new()
+                .wrap(from_fn(skip_compression))
+                .wrap(Compress::default())
+                .route("/small", web::get().to(|| async { HttpResponse::Ok().json("ok") }))
+                .route(
+                    "/large",
+                    web::get().to(move || {
+                        let large = large.clone();
+                        async move { HttpResponse::Ok().json(large) }
+                    }),
+                )
+                .route(
+                    "/image",
+                    web::get().to(|| async {
+                        let body = Bytes::from(vec![0u8; ENV.compression_min_size * 2]);
+                        HttpResponse::Ok().content_type("image/png").body(body)
+                    }),
+                ),
+        )
+        .await;
+
+        let req = actix_test::TestRequest::get()
+            .uri(path)
+            .insert_header((header::ACCEPT_ENCODING, "gzip"));
+        let res = actix_test::call_service(&app, req.to_request()).await;
+        res.headers().get(header::CONTENT_ENCODING).map(|value| value.to_str().unwrap().to_string())
+    }
+
+    #[actix_web::test]
+    async fn compresses_only_large_text_responses() {
+        assert_eq!(content_encoding("/large").await.as_deref(), Some("gzip"));
+        assert_eq!(content_encoding("/small").await.as_deref(), Some("identity"));
+        assert_eq!(content_encoding("/image").await.as_deref(), Some("identity"));
+    }
+
+    #[test]
+    fn compressible_content_types() {
+        assert!(is_compressible("application/json"));
+        assert!(is_compressible("application/x-ndjson"));
+        assert!(is_compressible("text/plain; version=0.0.4"));
+        assert!(is_compressible("application/problem+json"));
+        assert!(!is_compressible("image/png"));
+        assert!(!is_compressible("application/zip"));
+        assert!(!is_compressible("application/octet-stream"));
+    }
+}
@@ -0,0 +1,219 @@
+/// CORS Configuration
+///
+/// CORS policy được build từ ENV (`CORS_ALLOWED_ORIGINS`, `CORS_ALLOW_CREDENTIALS`,
+/// `CORS_ALLOWED_METHODS`, `CORS_ALLOWED_HEADERS`, `CORS_MAX_AGE`). Origin hỗ trợ:
+/// - Exact match: `https://chat.example.com`
+/// - Wildcard subdomain: `https://*.example.com` (khớp mọi subdomain, không khớp apex domain)
+/// - `*`: mọi origin (origin được echo lại, không gửi `*`)
+///
+/// Origin không khớp bị từ chối (preflight trả 400, response không có CORS headers).
+/// `CORS_ALLOWED_ORIGINS` là runtime setting: đọc lại khi SIGHUP, áp dụng cho request sau đó.
+use actix_cors::Cors;
+
+use crate::{configs::settings::settings, ENV};
+
+/// Headers client được phép đọc (API versioning / deprecation)
+const EXPOSED_HEADERS: [&str; 3] = ["Deprecation", "Sunset", "Link"];
+
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    pub allowed_origins: Vec<String>,
+    /// Dùng origins của runtime settings hiện tại thay cho `allowed_origins` ở mỗi request
+    pub reload_origins: bool,
+    pub allow_credentials: bool,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    pub max_age: usize,
+}
+
+impl CorsConfig {
+    pub fn from_env() -> Self {
+        Self {
+            allowed_origins: settings().cors_allowed_origins.clone(),
+            reload_origins: true,
+            allow_credentials: ENV.cors_allow_credentials,
+            allowed_methods: ENV.cors_allowed_methods.clone(),
+            allowed_headers: ENV.cors_allowed_headers.clone(),
+            max_age: ENV.cors_max_age,
+        }
+    }
+}
+
+/// Build CORS middleware từ config
+pub fn build_cors(config: &CorsConfig) -> Cors {
+    let allowed_origins = config.allowed_origins.clone();
+    let reload_origins = config.reload_origins;
+
+    let cors = Cors::default()
+        .allowed_origin_fn(move |origin, _req| {
+            origin.to_str().is_ok_and(|origin| {
+                if reload_origins {
+                    matches_any(&settings().cors_allowed_origins, origin)
+                } else {
+                    matches_any(&allowed_origins, origin)
+                }
+            })
+        })
+        .allowed_methods(config.allowed_methods.iter().map(String::as_str))
+        .allowed_headers(config.allowed_headers.iter().map(String::as_str))
+        .expose_headers(EXPOSED_HEADERS)
+        .max_age(config.max_age);
+
+    if config.allow_credentials {
+        cors.supports_credentials()
+    } else {
+        cors
+    }
+}
+
+fn matches_any(patterns: &[String], origin: &str) -> bool {
+    patterns.iter().any(|pattern| origin_matches(pattern.trim_end_matches('/'), origin))
+}
+
+/// Kiểm tra origin có khớp pattern (exact, wildcard subdomain hoặc `*`)
+fn origin_matches(pattern: &str, origin: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+
+    match pattern.split_once("://*.") {
+        Some((scheme, domain)) => origin
+            .strip_prefix(scheme)
+            .and_then(|rest| rest.strip_prefix("://"))
+            .and_then(|host| host.strip_suffix(domain))
+            .is_some_and(|subdomain| subdomain.len() > 1 && subdomain.ends_with('.')),
+        None => pattern.eq_ignore_ascii_case(origin),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::{
+            header::{self, HeaderMap},
+            Method, StatusCode,
+        },
+        test as actix_test, web, App, HttpResponse,
+    };
+
+    use super::*;
+
+    fn config() -> CorsConfig {
+        CorsConfig {
+            allowed_origins: vec![
+                "https://chat.example.com/".to_string(),
+                "https://*.trusted.dev".to_string(),
+            ],
+            reload_origins: false,
+            allow_credentials: true,
+            allowed_methods: vec!["GET".to_string(), "POST".to_string()],
+            allowed_headers: vec!["Authorization".to_string(), "Content-Type".to_string()],
+            max_age: 600,
+        }
+    }
+
+    fn preflight(origin: &str, method: &str) -> actix_test::TestRequest {
+        actix_test::TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri("/ping")
+            .insert_header((header::ORIGIN, origin))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, method))
+    }
+
+    /// Gọi request qua CORS middleware, trả về status và headers
+    /// (middleware trả lỗi dưới dạng Err, khi đó không có headers)
+    async fn call(config: &CorsConfig, req: actix_test::TestRequest) -> (StatusCode, HeaderMap) {
+        let app = actix_test::init_service(
+            App::new()
+                .wrap(build_cors(config))
+                .route("/ping", web::get().to(|| async { HttpResponse::Ok().finish() })),
+        )
+        .await;
+
+        match actix_test::try_call_service(&app, req.to_request()).await {
+            Ok(res) => (res.status(), res.headers().clone()),
+            Err(e) => (e.as_response_error().status_code(), HeaderMap::new()),
+        }
+    }
+
+    #[test]
+    fn origin_patterns() {
+        assert!(origin_matches("https://chat.example.com", "https://chat.example.com"));
+        assert!(!origin_matches("https://chat.example.com", "http://chat.example.com"));
+        assert!(origin_matches("https://*.trusted.dev", "https://app.trusted.dev"));
+        assert!(origin_matches("https://*.trusted.dev", "https://a.b.trusted.dev"));
+        assert!(!origin_matches("https://*.trusted.dev", "https://trusted.dev"));
+        assert!(!origin_matches("https://*.trusted.dev", "https://eviltrusted.dev"));
+        assert!(!origin_matches("https://*.trusted.dev", "http://app.trusted.dev"));
+        assert!(origin_matches("*", "https://anything.example"));
+    }
+
+    #[actix_web::test]
+    async fn preflight_from_allowed_origin_succeeds() {
+        let req = preflight("https://chat.example.com", "POST")
+            .insert_header((header::ACCESS_CONTROL_REQUEST_HEADERS, "authorization"));
+        let (status, headers) = call(&config(), req).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "https://chat.example.com"
+        );
+        assert_eq!(headers.get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS).unwrap(), "true");
+        assert_eq!(headers.get(header::ACCESS_CONTROL_MAX_AGE).unwrap(), "600");
+    }
+
+    #[actix_web::test]
+    async fn preflight_from_wildcard_subdomain_succeeds() {
+        let (status, headers) = call(&config(), preflight("https://app.trusted.dev", "GET")).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "https://app.trusted.dev"
+        );
+    }
+
+    #[actix_web::test]
+    async fn preflight_from_unknown_origin_is_rejected() {
+        for origin in ["https://evil.example", "https://trusted.dev", "https://eviltrusted.dev"] {
+            let (status, headers) = call(&config(), preflight(origin, "GET")).await;
+
+            assert_eq!(status, StatusCode::BAD_REQUEST, "origin {origin}");
+            assert!(!headers.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN), "origin {origin}");
+        }
+    }
+
+    #[actix_web::test]
+    async fn preflight_with_disallowed_method_is_rejected() {
+        let (status, _) = call(&config(), preflight("https://chat.example.com", "DELETE")).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn simple_request_from_unknown_origin_gets_no_cors_headers() {
+        let req = actix_test::TestRequest::get()
+            .uri("/ping")
+            .insert_header((header::ORIGIN, "https://evil.example"));
+        let (_, headers) = call(&config(), req).await;
+
+        assert!(!headers.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[actix_web::test]
+    async fn credentials_header_omitted_when_disabled() {
+        let req = actix_test::TestRequest::get()
+            .uri("/ping")
+            .insert_header((header::ORIGIN, "https://chat.example.com"));
+        let (status, headers) =
+            call(&CorsConfig { allow_credentials: false, ..config() }, req).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "https://chat.example.com"
+        );
+        assert!(!headers.contains_key(header::ACCESS_CONTROL_ALLOW_CREDENTIALS));
+    }
+}
@@ -0,0 +1,173 @@
+/// JWT Signing Keys
+///
+/// Token được ký bằng key hiện tại và mang `kid` của key đó trong header. Token ký bằng các
+/// key trước đó vẫn verify được cho tới khi hết hạn, nên đổi key không đăng xuất mọi
+/// session cùng lúc. Mỗi key có dạng:
+/// - `kid:secret`: HS256 với shared secret (mặc định)
+/// - `kid:RS256:/path/private.pem` hoặc `kid:EdDSA:/path/private.pem`: ký bất đối xứng,
+///   public key được công bố ở `GET /.well-known/jwks.json` để các service khác verify
+///   token mà không cần secret
+///
+/// Key đầu tiên là key ký, các key sau chỉ để verify. Keys được đọc từ:
+/// - `JWT_KEYS_FILE`: mỗi dòng một key, dòng bắt đầu bằng `#` là comment. Gửi SIGHUP để
+///   đọc lại file không cần restart
+/// - `JWT_KEYS`: các keys cách nhau bởi dấu phẩy
+/// - Không cấu hình: `SECRET_KEY` (HS256) với kid `default`
+///
+/// Rotate key khi chạy nhiều instance: thêm key mới vào cuối danh sách (chỉ verify) và
+/// SIGHUP mọi instance, sau đó mới chuyển key mới lên đầu, tránh instance chưa reload
+/// từ chối token đã ký bằng key mới. Bỏ key cũ khi refresh tokens ký bằng nó đã hết hạn.
+use std::sync::{Arc, LazyLock, PoisonError, RwLock};
+
+use jsonwebtoken::{
+    jwk::{Jwk, JwkSet, PublicKeyUse},
+    Algorithm, DecodingKey, EncodingKey,
+};
+
+use crate::{api::error, ENV};
+
+/// kid của `SECRET_KEY` khi không cấu hình keys
+const DEFAULT_KID: &str = "default";
+
+#[derive(Clone)]
+pub struct JwtKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    pub encoding: EncodingKey,
+    pub decoding: DecodingKey,
+    /// Public key dạng JWK, chỉ có ở key bất đối xứng
+    pub jwk: Option<Jwk>,
+}
+
+impl JwtKey {
+    fn hmac(kid: &str, secret: &[u8]) -> Self {
+        JwtKey {
+            kid: kid.to_string(),
+            algorithm: Algorithm::HS256,
+            encoding: EncodingKey::from_secret(secret),
+            decoding: DecodingKey::from_secret(secret),
+            jwk: None,
+        }
+    }
+
+    /// Key bất đối xứng từ file PEM chứa private key, public key được suy ra cho JWKS
+    fn asymmetric(kid: &str, algorithm: Algorithm, path: &str) -> Result<Self, error::SystemError> {
+        let pem = std::fs::read(path)
+            .map_err(|e| error::SystemError::internal_error(format!("Cannot read {path}: {e}")))?;
+        let encoding = match algorithm {
+            Algorithm::RS256 => EncodingKey::from_rsa_pem(&pem)?,
+            _ => EncodingKey::from_ed_pem(&pem)?,
+        };
+
+        let mut jwk = Jwk::from_encoding_key(&encoding, algorithm)?;
+        jwk.common.key_id = Some(kid.to_string());
+        jwk.common.public_key_use = Some(PublicKeyUse::Signature);
+        let decoding = DecodingKey::from_jwk(&jwk)?;
+
+        Ok(JwtKey { kid: kid.to_string(), algorithm, encoding, decoding, jwk: Some(jwk) })
+    }
+}
+
+pub struct JwtKeyring {
+    /// Khác rỗng, key đầu tiên là key ký
+    keys: Vec<JwtKey>,
+}
+
+static KEYRING: LazyLock<RwLock<Arc<JwtKeyring>>> = LazyLock::new(|| {
+    RwLock::new(Arc::new(JwtKeyring::load().expect("Invalid JWT signing keys configuration")))
+});
+
+impl JwtKeyring {
+    fn load() -> Result<Self, error::SystemError> {
+        let keys = match (&ENV.jwt_keys_file, &ENV.jwt_keys) {
+            (Some(path), _) => {
+                let content = std::fs::read_to_string(path).map_err(|e| {
+                    error::SystemError::internal_error(format!("Cannot read {path}: {e}"))
+                })?;
+                parse_keys(content.lines())?
+            }
+            (None, Some(list)) => parse_keys(list.split(','))?,
+            (None, None) => Vec::new(),
+        };
+
+        if keys.is_empty() {
+            return Ok(JwtKeyring {
+                keys: vec![JwtKey::hmac(DEFAULT_KID, ENV.jwt_secret.as_bytes())],
+            });
+        }
+
+        Ok(JwtKeyring { keys })
+    }
+
+    /// Key dùng để ký token mới
+    pub fn current(&self) -> &JwtKey {
+        &self.keys[0]
+    }
+
+    pub fn find(&self, kid: &str) -> Option<&JwtKey> {
+        self.keys.iter().find(|key| key.kid == kid)
+    }
+
+    pub fn keys(&self) -> &[JwtKey] {
+        &self.keys
+    }
+
+    /// Public keys của các key bất đối xứng (JWKS)
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet { keys: self.keys.iter().filter_map(|key| key.jwk.clone()).collect() }
+    }
+}
+
+fn parse_keys<'a>(
+    entries: impl Iterator<Item = &'a str>,
+) -> Result<Vec<JwtKey>, error::SystemError> {
+    let mut keys: Vec<JwtKey> = Vec::new();
+
+    for entry in entries.map(str::trim).filter(|e| !e.is_empty() && !e.starts_with('#')) {
+        let key = parse_key(entry)?;
+        if keys.iter().any(|existing| existing.kid == key.kid) {
+            return Err(error::SystemError::internal_error(format!(
+                "Duplicate JWT kid {}",
+                key.kid
+            )));
+        }
+        keys.push(key);
+    }
+
+    Ok(keys)
+}
+
+fn parse_key(entry: &str) -> Result<JwtKey, error::SystemError> {
+    let (kid, value) = entry
+        .split_once(':')
+        .map(|(kid, value)| (kid.trim(), value.trim()))
+        .filter(|(kid, value)| !kid.is_empty() && !value.is_empty())
+        .ok_or_else(|| {
+            error::SystemError::internal_error(
+                "JWT key must have the form kid:secret or kid:RS256|EdDSA:/path/to/key.pem",
+            )
+        })?;
+
+    match value.split_once(':') {
+        Some(("RS256", path)) => JwtKey::asymmetric(kid, Algorithm::RS256, path.trim()),
+        Some(("EdDSA", path)) => JwtKey::asymmetric(kid, Algorithm::EdDSA, path.trim()),
+        _ => Ok(JwtKey::hmac(kid, value.as_bytes())),
+    }
+}
+
+/// Keys đang dùng
+pub fn keyring() -> Arc<JwtKeyring> {
+    KEYRING.read().unwrap_or_else(PoisonError::into_inner).clone()
+}
+
+/// Đọc lại keys, giữ keys đang dùng nếu cấu hình mới không hợp lệ
+pub fn reload() -> Result<(), error::SystemError> {
+    let keyring = JwtKeyring::load()?;
+    tracing::info!(
+        "JWT keys reloaded: signing with kid {}, {} key(s) accepted",
+        keyring.current().kid,
+        keyring.keys.len()
+    );
+    *KEYRING.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(keyring);
+    Ok(())
+}
@@ -0,0 +1,22 @@
+/// Abstraction gửi email (reset password, xác thực email, ...)
+///
+/// Implementation mặc định `LogMailer` chỉ ghi log nội dung email, phù hợp cho
+/// môi trường development. Production có thể plug SMTP/SES/... bằng cách implement trait.
+use crate::api::error;
+
+#[async_trait::async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), error::SystemError>;
+}
+
+/// Mailer ghi email ra log thay vì gửi thật
+#[derive(Clone, Default)]
+pub struct LogMailer;
+
+#[async_trait::async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), error::SystemError> {
+        tracing::info!(to, subject, "Email: {}", body);
+        Ok(())
+    }
+}
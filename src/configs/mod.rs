@@ -1,19 +1,79 @@
-use deadpool_redis::{redis::AsyncCommands, Runtime};
-use sqlx::{postgres::PgPoolOptions, PgPool};
+use std::{str::FromStr, time::Duration};
+
+use deadpool_redis::Runtime;
+use sqlx::{
+    migrate::Migrator,
+    postgres::{PgConnectOptions, PgPoolOptions},
+    PgPool,
+};
 
 use crate::{api::error, ENV};
 
+pub mod cache;
+pub mod compression;
+pub mod cors;
+pub mod jwt_keys;
+pub mod mailer;
+pub mod payload;
+pub mod read_replica;
+pub mod settings;
+pub mod uow;
+
+/// Migrations trong `migrations/`, được embed vào binary lúc compile
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Tạo Postgres pool, chạy migrations trước khi trả về nếu `RUN_MIGRATIONS=true`
 pub async fn connect_database() -> Result<PgPool, error::SystemError> {
+    let pool = create_pool().await?;
+    if ENV.run_migrations {
+        run_migrations(&pool).await?;
+    }
+    Ok(pool)
+}
+
+/// Chạy migrations rồi đóng pool (CLI `migrate`)
+pub async fn migrate_database() -> Result<(), error::SystemError> {
+    let pool = create_pool().await?;
+    let result = run_migrations(&pool).await;
+    pool.close().await;
+    result
+}
+
+async fn create_pool() -> Result<PgPool, error::SystemError> {
     let database_url = &ENV.database_url;
-    let pool = PgPoolOptions::new()
-        .max_connections(10)
-        .min_connections(5)
-        .acquire_slow_threshold(std::time::Duration::from_secs(3))
-        .connect(database_url)
-        .await?;
+    let pool = pool_options().connect_with(connect_options(database_url)?).await?;
     Ok(pool)
 }
 
+/// Pool options theo Env, dùng chung cho primary và read replica
+fn pool_options() -> PgPoolOptions {
+    let idle_timeout = (ENV.db_idle_timeout > 0).then(|| Duration::from_secs(ENV.db_idle_timeout));
+    PgPoolOptions::new()
+        .max_connections(ENV.db_max_connections)
+        .min_connections(ENV.db_min_connections)
+        .acquire_timeout(Duration::from_secs(ENV.db_acquire_timeout))
+        .idle_timeout(idle_timeout)
+        .acquire_slow_threshold(Duration::from_secs(3))
+}
+
+/// Connect options từ URL, kèm `statement_timeout` nếu `DB_STATEMENT_TIMEOUT` > 0
+fn connect_options(database_url: &str) -> Result<PgConnectOptions, error::SystemError> {
+    let connect_options = PgConnectOptions::from_str(database_url)?;
+    if ENV.db_statement_timeout == 0 {
+        return Ok(connect_options);
+    }
+    let statement_timeout = ENV.db_statement_timeout.to_string();
+    Ok(connect_options.options([("statement_timeout", statement_timeout)]))
+}
+
+/// Apply các migrations chưa chạy (theo bảng `_sqlx_migrations`)
+async fn run_migrations(pool: &PgPool) -> Result<(), error::SystemError> {
+    tracing::info!("Running database migrations");
+    MIGRATOR.run(pool).await.inspect_err(|e| tracing::error!("Migration failed: {}", e))?;
+    tracing::info!("Database schema is up to date");
+    Ok(())
+}
+
 #[derive(Clone)]
 pub struct RedisCache {
     pool: deadpool_redis::Pool,
@@ -22,54 +82,78 @@ pub struct RedisCache {
 impl RedisCache {
     pub async fn new() -> Result<Self, error::SystemError> {
         let mut cfg = deadpool_redis::Config::from_url(&ENV.redis_url);
-        cfg.pool = Some(deadpool_redis::PoolConfig { max_size: 16, ..Default::default() });
+        cfg.pool = Some(deadpool_redis::PoolConfig {
+            max_size: ENV.redis_max_connections,
+            ..Default::default()
+        });
         let pool = cfg.create_pool(Some(Runtime::Tokio1))?;
         Ok(Self { pool })
     }
 
-    pub async fn get<T>(&self, key: &str) -> Result<Option<T>, error::SystemError>
-    where
-        T: serde::de::DeserializeOwned,
-    {
+    /// Kiểm tra kết nối Redis (readiness probe)
+    pub async fn ping(&self) -> Result<(), error::SystemError> {
         let mut conn = self.pool.get().await?;
-
-        let value: Option<Vec<u8>> = conn.get(key).await?;
-
-        match value {
-            Some(v) => {
-                let parsed = serde_json::from_slice(&v)?;
-                Ok(Some(parsed))
-            }
-            None => Ok(None),
-        }
+        deadpool_redis::redis::cmd("PING").query_async::<()>(&mut *conn).await?;
+        Ok(())
     }
 
-    pub async fn set<T>(
-        &self,
-        key: &str,
-        value: &T,
-        expiration: usize,
-    ) -> Result<(), error::SystemError>
-    where
-        T: serde::Serialize,
-    {
-        let mut conn = self.pool.get().await?;
+    /// Expose Redis pool cho PresenceService
+    pub fn get_pool(&self) -> &deadpool_redis::Pool {
+        &self.pool
+    }
+}
 
-        let serialized = serde_json::to_vec(value)?;
+/// Định kỳ kiểm tra Postgres và Redis pool, cảnh báo khi pool đã dùng hết connection
+/// (request phải chờ `DB_ACQUIRE_TIMEOUT` hoặc lỗi). Số liệu chi tiết có ở `/metrics`
+pub async fn monitor_pool_saturation(db_pool: PgPool, redis: RedisCache) {
+    let mut interval = actix_web::rt::time::interval(Duration::from_secs(30));
+    loop {
+        interval.tick().await;
 
-        conn.set_ex::<_, _, ()>(key, serialized, expiration as u64).await?;
+        let db_size = db_pool.size();
+        let db_idle = db_pool.num_idle();
+        if db_size >= ENV.db_max_connections && db_idle == 0 {
+            tracing::warn!(
+                size = db_size,
+                max = ENV.db_max_connections,
+                "Postgres pool saturated, all connections are in use"
+            );
+        }
 
-        Ok(())
+        let redis_status = redis.get_pool().status();
+        if redis_status.size >= redis_status.max_size && redis_status.available == 0 {
+            tracing::warn!(
+                size = redis_status.size,
+                max = redis_status.max_size,
+                waiting = redis_status.waiting,
+                "Redis pool saturated, all connections are in use"
+            );
+        }
     }
+}
 
-    pub async fn delete(&self, key: &str) -> Result<(), error::SystemError> {
-        let mut conn = self.pool.get().await?;
-        conn.del::<_, ()>(key).await?;
-        Ok(())
-    }
+/// Đọc lại JWT keys và runtime settings mỗi khi nhận SIGHUP, phần nào lỗi thì giữ nguyên
+/// cấu hình đang dùng của phần đó
+pub async fn reload_on_sighup() {
+    #[cfg(unix)]
+    {
+        use actix_web::rt::signal::unix::{signal, SignalKind};
 
-    /// Expose Redis pool cho PresenceService
-    pub fn get_pool(&self) -> &deadpool_redis::Pool {
-        &self.pool
+        let mut sighup = match signal(SignalKind::hangup()) {
+            Ok(sighup) => sighup,
+            Err(e) => {
+                tracing::warn!("Failed to listen for SIGHUP: {}", e);
+                return;
+            }
+        };
+
+        while sighup.recv().await.is_some() {
+            if let Err(e) = jwt_keys::reload() {
+                tracing::error!("Failed to reload JWT keys, keeping current keys: {:?}", e);
+            }
+            if let Err(e) = settings::reload() {
+                tracing::error!("Failed to reload settings, keeping current settings: {:?}", e);
+            }
+        }
     }
 }
@@ -0,0 +1,41 @@
+/// Request Body Limits
+///
+/// Giới hạn kích thước body được build từ ENV:
+/// - `JSON_BODY_LIMIT`: áp dụng cho mọi JSON body (`web::Json`, `ValidatedJson`) và body
+///   đọc thô (`web::Bytes`, `String`), mặc định 256 KiB
+/// - `UPLOAD_BODY_LIMIT`: áp dụng cho multipart upload (file, avatar), được kiểm tra khi
+///   đọc stream nên upload quá lớn bị dừng sớm thay vì đọc hết vào memory, mặc định 10 MiB
+///   (runtime setting, đọc lại khi SIGHUP)
+///
+/// Body vượt giới hạn trả về 413 với code `PAYLOAD_TOO_LARGE`; JSON sai định dạng / sai
+/// kiểu trả về 400 kèm message của serde thay vì response mặc định của actix.
+use actix_web::{error::JsonPayloadError, web};
+
+use crate::api::error;
+
+/// `JsonConfig` với giới hạn `limit` bytes và lỗi theo format `ErrorBody`. Đăng ký ở app
+/// với `JSON_BODY_LIMIT`, scope cần giới hạn khác override bằng `app_data` của scope
+pub fn json_config(limit: usize) -> web::JsonConfig {
+    web::JsonConfig::default()
+        .limit(limit)
+        .error_handler(|err, _req| json_payload_error(err).into())
+}
+
+/// Lỗi 413 khi body vượt `limit` bytes
+pub fn payload_too_large(limit: usize) -> error::Error {
+    error::Error::payload_too_large(format!("Request body exceeds the limit of {limit} bytes"))
+}
+
+fn json_payload_error(err: JsonPayloadError) -> error::Error {
+    match err {
+        JsonPayloadError::OverflowKnownLength { limit, .. }
+        | JsonPayloadError::Overflow { limit } => payload_too_large(limit),
+        JsonPayloadError::ContentType => {
+            error::Error::bad_request("Content-Type must be application/json")
+        }
+        JsonPayloadError::Deserialize(e) => {
+            error::Error::bad_request(format!("Invalid JSON body: {e}"))
+        }
+        other => error::Error::bad_request(other.to_string()),
+    }
+}
@@ -0,0 +1,85 @@
+/// Read replica
+///
+/// Nếu đặt `DATABASE_READ_URL`, các query đọc nặng (danh sách conversations, lịch sử
+/// messages, tìm kiếm users) chạy trên read replica qua `ReadPool`. Ghi và transactions
+/// luôn chạy trên primary. Khi replica không lấy được connection, query fallback về
+/// primary và replica bị bỏ qua trong `REPLICA_RETRY_AFTER` trước khi thử lại.
+use std::{
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use sqlx::{pool::PoolConnection, PgPool, Postgres};
+
+use crate::{api::error, ENV};
+
+/// Thời gian tối đa chờ connection từ replica trước khi fallback về primary
+const REPLICA_ACQUIRE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Sau khi replica lỗi, các query đọc chạy trên primary trong khoảng này (giây)
+const REPLICA_RETRY_AFTER: i64 = 30;
+
+/// Số lần query đọc phải fallback về primary vì replica không khả dụng
+static FALLBACKS: AtomicU64 = AtomicU64::new(0);
+
+pub fn fallbacks() -> u64 {
+    FALLBACKS.load(Ordering::Relaxed)
+}
+
+/// Pool cho các query chỉ đọc: replica nếu có cấu hình, ngược lại là primary
+#[derive(Clone)]
+pub struct ReadPool {
+    primary: PgPool,
+    replica: Option<PgPool>,
+    /// Unix timestamp (giây) mà trước đó không dùng replica
+    replica_down_until: Arc<AtomicI64>,
+}
+
+impl ReadPool {
+    /// Mọi query đọc chạy trên primary (không có replica)
+    pub fn primary_only(primary: PgPool) -> Self {
+        Self { primary, replica: None, replica_down_until: Arc::new(AtomicI64::new(0)) }
+    }
+
+    /// Tạo replica pool từ `DATABASE_READ_URL`. Pool connect lazy nên replica chưa sẵn sàng
+    /// lúc khởi động không làm server dừng
+    pub fn from_env(primary: PgPool) -> Result<Self, error::SystemError> {
+        let Some(read_url) = ENV.database_read_url.as_deref() else {
+            return Ok(Self::primary_only(primary));
+        };
+
+        let replica = super::pool_options()
+            .min_connections(0)
+            .acquire_timeout(REPLICA_ACQUIRE_TIMEOUT)
+            .connect_lazy_with(super::connect_options(read_url)?);
+
+        tracing::info!("Routing heavy read queries to DATABASE_READ_URL replica");
+        Ok(Self { replica: Some(replica), ..Self::primary_only(primary) })
+    }
+
+    /// Replica pool (nếu có), dùng cho metrics
+    pub fn replica(&self) -> Option<&PgPool> {
+        self.replica.as_ref()
+    }
+
+    /// Lấy connection để đọc, fallback về primary khi replica lỗi
+    pub async fn acquire(&self) -> Result<PoolConnection<Postgres>, error::SystemError> {
+        let now = chrono::Utc::now().timestamp();
+        if let Some(replica) = &self.replica {
+            if self.replica_down_until.load(Ordering::Relaxed) <= now {
+                match replica.acquire().await {
+                    Ok(conn) => return Ok(conn),
+                    Err(e) => {
+                        tracing::warn!("Read replica unavailable, falling back to primary: {}", e);
+                        self.replica_down_until.store(now + REPLICA_RETRY_AFTER, Ordering::Relaxed);
+                    }
+                }
+            }
+            FALLBACKS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        Ok(self.primary.acquire().await?)
+    }
+}
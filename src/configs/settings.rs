@@ -0,0 +1,93 @@
+/// Runtime Settings
+///
+/// Các cấu hình không ảnh hưởng tới kết nối / bảo mật cốt lõi (rate limits, CORS origins,
+/// upload limit...) được đọc lại khi nhận SIGHUP mà không cần restart. Nguồn giống `Env`:
+/// biến môi trường, file `CONFIG_FILE`, rồi giá trị mặc định. Sửa file rồi SIGHUP để áp
+/// dụng. Cấu hình mới không hợp lệ thì giữ nguyên settings đang dùng.
+use std::sync::{Arc, LazyLock, PoisonError, RwLock};
+
+use crate::{
+    api::error,
+    constants::{source::ConfigSource, split_list},
+    ENV,
+};
+
+#[derive(Debug, Clone)]
+pub struct RuntimeSettings {
+    /// Khoảng thời gian (giây) để phát hiện tin nhắn trùng lặp
+    pub duplicate_window: usize,
+    /// Số tin nhắn trùng lặp tối đa trong cửa sổ trước khi bị từ chối
+    pub duplicate_soft_limit: u32,
+    /// Thời gian chờ (giây) giữa hai lần gửi lại email xác thực / email đặt lại mật khẩu
+    pub verification_resend_cooldown: u64,
+    /// Số lần đăng nhập sai của một username trước khi bị khóa tạm thời, 0 là tắt
+    pub sign_in_max_attempts: i64,
+    /// Số lần đăng nhập sai từ một IP trước khi bị khóa tạm thời, 0 là tắt
+    pub sign_in_ip_max_attempts: i64,
+    /// Thời gian khóa lần đầu (giây), tăng gấp đôi mỗi lần bị khóa tiếp theo
+    pub sign_in_lockout_seconds: u64,
+    /// Thời gian khóa tối đa (giây)
+    pub sign_in_lockout_max_seconds: u64,
+    /// Danh sách phân tách bằng dấu phẩy, hỗ trợ wildcard subdomain (`https://*.example.com`)
+    pub cors_allowed_origins: Vec<String>,
+    /// Kích thước tối đa của request upload file (bytes)
+    pub upload_body_limit: usize,
+}
+
+impl RuntimeSettings {
+    /// `default_origins` là CORS origins khi `CORS_ALLOWED_ORIGINS` không được cấu hình
+    pub fn from_source(source: &ConfigSource, default_origins: &str) -> Result<Self, String> {
+        let settings = RuntimeSettings {
+            duplicate_window: source.parse("DUPLICATE_WINDOW", "30")?,
+            duplicate_soft_limit: source.parse("DUPLICATE_SOFT_LIMIT", "3")?,
+            verification_resend_cooldown: source.parse("VERIFICATION_RESEND_COOLDOWN", "60")?,
+            sign_in_max_attempts: source.parse("SIGN_IN_MAX_ATTEMPTS", "5")?,
+            sign_in_ip_max_attempts: source.parse("SIGN_IN_IP_MAX_ATTEMPTS", "20")?,
+            sign_in_lockout_seconds: source.parse("SIGN_IN_LOCKOUT_SECONDS", "60")?,
+            sign_in_lockout_max_seconds: source.parse("SIGN_IN_LOCKOUT_MAX_SECONDS", "3600")?,
+            cors_allowed_origins: split_list(
+                &source.var("CORS_ALLOWED_ORIGINS").unwrap_or_else(|_| default_origins.to_string()),
+            ),
+            upload_body_limit: source.parse("UPLOAD_BODY_LIMIT", "10485760")?,
+        };
+
+        if settings.duplicate_window == 0 {
+            return Err("DUPLICATE_WINDOW must be greater than 0".to_string());
+        }
+        if settings.sign_in_lockout_max_seconds < settings.sign_in_lockout_seconds {
+            return Err(
+                "SIGN_IN_LOCKOUT_MAX_SECONDS must not be less than SIGN_IN_LOCKOUT_SECONDS"
+                    .to_string(),
+            );
+        }
+        if settings.cors_allowed_origins.is_empty() {
+            return Err("CORS_ALLOWED_ORIGINS must contain at least one origin".to_string());
+        }
+        if settings.upload_body_limit == 0 {
+            return Err("UPLOAD_BODY_LIMIT must be greater than 0".to_string());
+        }
+
+        Ok(settings)
+    }
+
+    fn load() -> Result<Self, String> {
+        Self::from_source(&ConfigSource::load()?, &ENV.frontend_url)
+    }
+}
+
+static SETTINGS: LazyLock<RwLock<Arc<RuntimeSettings>>> = LazyLock::new(|| {
+    RwLock::new(Arc::new(RuntimeSettings::load().unwrap_or_else(|e| panic!("{e}"))))
+});
+
+/// Settings đang dùng
+pub fn settings() -> Arc<RuntimeSettings> {
+    SETTINGS.read().unwrap_or_else(PoisonError::into_inner).clone()
+}
+
+/// Đọc lại settings, giữ settings đang dùng nếu cấu hình mới không hợp lệ
+pub fn reload() -> Result<(), error::SystemError> {
+    let settings = RuntimeSettings::load().map_err(error::SystemError::internal_error)?;
+    tracing::info!("Runtime settings reloaded: {:?}", settings);
+    *SETTINGS.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(settings);
+    Ok(())
+}
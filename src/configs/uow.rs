@@ -0,0 +1,78 @@
+/// Unit of Work
+///
+/// Services mở transaction qua `Arc<dyn UnitOfWork>` và truyền `&mut Transaction` vào các
+/// repository methods cần chạy chung transaction, nên repository traits không phụ thuộc
+/// vào sqlx. Repository Pg lấy connection bằng `Transaction::connection`, các repository
+/// in-memory bỏ qua tham số này. Tests dùng `MemoryUnitOfWork`.
+use sqlx::{pool::PoolConnection, PgConnection, PgPool, Postgres};
+
+use crate::api::error;
+
+/// Phạm vi chạy của repository methods, rollback nếu bị drop trước khi `commit`
+pub struct Transaction(Inner);
+
+enum Inner {
+    Pg(sqlx::Transaction<'static, Postgres>),
+    /// Connection không mở transaction, mỗi statement tự commit
+    Autocommit(PoolConnection<Postgres>),
+    #[cfg(test)]
+    Memory,
+}
+
+impl Transaction {
+    /// Connection Postgres của transaction, lỗi nếu đây là transaction in-memory
+    pub fn connection(&mut self) -> Result<&mut PgConnection, error::SystemError> {
+        match &mut self.0 {
+            Inner::Pg(tx) => Ok(&mut **tx),
+            Inner::Autocommit(conn) => Ok(&mut **conn),
+            #[cfg(test)]
+            Inner::Memory => Err(error::SystemError::internal_error(
+                "In-memory transaction has no database connection",
+            )),
+        }
+    }
+
+    pub async fn commit(self) -> Result<(), error::SystemError> {
+        if let Inner::Pg(tx) = self.0 {
+            tx.commit().await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+pub trait UnitOfWork: Send + Sync {
+    /// Mở transaction mới
+    async fn begin(&self) -> Result<Transaction, error::SystemError>;
+
+    /// Connection không có transaction cho thao tác đơn lẻ (đọc, hoặc ghi một statement)
+    async fn autocommit(&self) -> Result<Transaction, error::SystemError>;
+}
+
+#[async_trait::async_trait]
+impl UnitOfWork for PgPool {
+    async fn begin(&self) -> Result<Transaction, error::SystemError> {
+        Ok(Transaction(Inner::Pg(PgPool::begin(self).await?)))
+    }
+
+    async fn autocommit(&self) -> Result<Transaction, error::SystemError> {
+        Ok(Transaction(Inner::Autocommit(self.acquire().await?)))
+    }
+}
+
+/// Unit of work cho repositories in-memory, transaction không có connection và `commit`
+/// không làm gì (thay đổi của mocks được ghi ngay)
+#[cfg(test)]
+pub struct MemoryUnitOfWork;
+
+#[cfg(test)]
+#[async_trait::async_trait]
+impl UnitOfWork for MemoryUnitOfWork {
+    async fn begin(&self) -> Result<Transaction, error::SystemError> {
+        Ok(Transaction(Inner::Memory))
+    }
+
+    async fn autocommit(&self) -> Result<Transaction, error::SystemError> {
+        Ok(Transaction(Inner::Memory))
+    }
+}
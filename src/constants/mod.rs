@@ -1,55 +1,308 @@
+pub mod source;
+
+use crate::configs::settings::RuntimeSettings;
+use source::ConfigSource;
+
 pub struct Env {
     pub jwt_secret: String,
+    pub jwt_keys: Option<String>,
+    pub jwt_keys_file: Option<String>,
     pub access_token_expiration: u64,
     pub refresh_token_expiration: u64,
+    pub password_reset_expiration: u64,
     pub database_url: String,
+    pub database_read_url: Option<String>,
     pub redis_url: String,
+    pub db_max_connections: u32,
+    pub db_min_connections: u32,
+    pub db_acquire_timeout: u64,
+    pub db_idle_timeout: u64,
+    pub db_statement_timeout: u64,
+    pub redis_max_connections: usize,
     pub frontend_url: String,
     pub ip: String,
     pub port: u16,
+    pub email_verification_expiration: u64,
+    pub username_change_cooldown_days: i64,
+    pub username_reservation_days: i64,
+    pub inactive_account_days: i64,
+    pub message_archive_after_months: u32,
+    pub inactive_account_action: String,
+    pub suspicious_sign_in_action: String,
+    pub sign_in_confirmation_expiration: u64,
+    pub trust_proxy_headers: bool,
+    pub argon2_memory_kib: u32,
+    pub argon2_iterations: u32,
+    pub argon2_parallelism: u32,
+    pub oauth_redirect_base_url: String,
+    pub google_client_id: Option<String>,
+    pub google_client_secret: Option<String>,
+    pub github_client_id: Option<String>,
+    pub github_client_secret: Option<String>,
+    pub api_legacy_sunset: String,
+    pub report_hide_threshold: i64,
+    pub moderation_action: String,
+    pub moderation_wordlist: Vec<String>,
+    pub fcm_project_id: Option<String>,
+    pub fcm_client_email: Option<String>,
+    pub fcm_private_key: Option<String>,
+    pub vapid_public_key: Option<String>,
+    pub vapid_private_key: Option<String>,
+    pub vapid_subject: String,
+    pub translation_provider: Option<String>,
+    pub translation_api_key: Option<String>,
+    pub translation_api_url: Option<String>,
+    pub cors_allow_credentials: bool,
+    pub cors_allowed_methods: Vec<String>,
+    pub cors_allowed_headers: Vec<String>,
+    pub cors_max_age: usize,
+    pub run_migrations: bool,
+    pub ws_max_sessions_per_user: usize,
+    pub ws_max_total_sessions: usize,
+    pub json_body_limit: usize,
+    pub compression_enabled: bool,
+    pub compression_min_size: usize,
+    pub metrics_token: Option<String>,
 }
 
 impl Env {
-    fn new() -> Self {
-        let jwt_secret = std::env::var("SECRET_KEY")
-            .expect("SECRET_KEY must be set in .env file or environment variable");
-
-        let access_token_expiration = std::env::var("ACCESS_TOKEN_EXPIRATION")
-            .unwrap_or_else(|_| "900".to_string())
-            .parse::<u64>()
-            .expect("ACCESS_TOKEN_EXPIRATION must be a valid u64 integer");
-        let refresh_token_expiration = std::env::var("REFRESH_TOKEN_EXPIRATION")
-            .unwrap_or_else(|_| "604800".to_string())
-            .parse::<u64>()
-            .expect("REFRESH_TOKEN_EXPIRATION must be a valid u64 integer");
-
-        let database_url = std::env::var("DATABASE_URL")
-            .expect("DATABASE_URL must be set in .env file or environment variable");
-        let redis_url = std::env::var("REDIS_URL")
-            .expect("REDIS_URL must be set in .env file or environment variable");
+    fn new(source: &ConfigSource) -> Self {
+        let jwt_secret = source
+            .var("SECRET_KEY")
+            .expect("SECRET_KEY must be set in .env file, environment variable or config file");
+        let jwt_keys = source.var("JWT_KEYS").ok();
+        let jwt_keys_file = source.var("JWT_KEYS_FILE").ok();
+
+        let access_token_expiration = source.get::<u64>("ACCESS_TOKEN_EXPIRATION", "900");
+        let refresh_token_expiration = source.get::<u64>("REFRESH_TOKEN_EXPIRATION", "604800");
+        let password_reset_expiration = source.get::<u64>("PASSWORD_RESET_EXPIRATION", "900");
+
+        let database_url = source
+            .var("DATABASE_URL")
+            .expect("DATABASE_URL must be set in .env file, environment variable or config file");
+        let database_read_url = source.var("DATABASE_READ_URL").ok();
+        let redis_url = source
+            .var("REDIS_URL")
+            .expect("REDIS_URL must be set in .env file, environment variable or config file");
+        let db_max_connections = source.get::<u32>("DB_MAX_CONNECTIONS", "10");
+        let db_min_connections = source.get::<u32>("DB_MIN_CONNECTIONS", "5");
+        assert!(
+            db_min_connections <= db_max_connections,
+            "DB_MIN_CONNECTIONS must not be greater than DB_MAX_CONNECTIONS"
+        );
+        // Giây; DB_IDLE_TIMEOUT = 0 thì không đóng connection rảnh
+        let db_acquire_timeout = source.get::<u64>("DB_ACQUIRE_TIMEOUT", "30");
+        let db_idle_timeout = source.get::<u64>("DB_IDLE_TIMEOUT", "600");
+        // Mili giây (`statement_timeout` của Postgres), 0 = không giới hạn
+        let db_statement_timeout = source.get::<u64>("DB_STATEMENT_TIMEOUT", "0");
+        let redis_max_connections = source.get::<usize>("REDIS_MAX_CONNECTIONS", "16");
 
         let frontend_url =
-            std::env::var("FRONTEND_URL").unwrap_or_else(|_| "http://localhost:5173".to_string());
-        let ip = std::env::var("IP").unwrap_or_else(|_| "127.0.0.1".to_string());
-        let port = std::env::var("PORT")
-            .unwrap_or_else(|_| "8080".to_string())
-            .parse::<u16>()
-            .expect("PORT must be a valid u16 integer");
+            source.var("FRONTEND_URL").unwrap_or_else(|_| "http://localhost:5173".to_string());
+        let ip = source.var("IP").unwrap_or_else(|_| "127.0.0.1".to_string());
+        let port = source.get::<u16>("PORT", "8080");
+        let email_verification_expiration =
+            source.get::<u64>("EMAIL_VERIFICATION_EXPIRATION", "86400");
+        let username_change_cooldown_days =
+            source.get::<i64>("USERNAME_CHANGE_COOLDOWN_DAYS", "30");
+        // Username cũ được giữ cho chủ cũ trong khoảng này, người khác chưa đăng ký lại được
+        let username_reservation_days = source.get::<i64>("USERNAME_RESERVATION_DAYS", "30");
+        // 0 = tắt job dọn tài khoản không hoạt động
+        let inactive_account_days = source.get::<i64>("INACTIVE_ACCOUNT_DAYS", "365");
+        let inactive_account_action = source
+            .var("INACTIVE_ACCOUNT_ACTION")
+            .unwrap_or_else(|_| "flag".to_string())
+            .to_lowercase();
+        assert!(
+            matches!(inactive_account_action.as_str(), "flag" | "deactivate"),
+            "INACTIVE_ACCOUNT_ACTION must be one of flag, deactivate"
+        );
+        // 0 = không chuyển messages cũ sang archive
+        let message_archive_after_months = source.get::<u32>("MESSAGE_ARCHIVE_AFTER_MONTHS", "0");
+        let suspicious_sign_in_action = source
+            .var("SUSPICIOUS_SIGN_IN_ACTION")
+            .unwrap_or_else(|_| "notify".to_string())
+            .to_lowercase();
+        assert!(
+            matches!(suspicious_sign_in_action.as_str(), "off" | "notify" | "confirm"),
+            "SUSPICIOUS_SIGN_IN_ACTION must be one of off, notify, confirm"
+        );
+        let sign_in_confirmation_expiration =
+            source.get::<u64>("SIGN_IN_CONFIRMATION_EXPIRATION", "900");
+        let trust_proxy_headers = source.get::<bool>("TRUST_PROXY_HEADERS", "false");
+        let argon2_memory_kib = source.get::<u32>("ARGON2_MEMORY_KIB", "19456");
+        let argon2_iterations = source.get::<u32>("ARGON2_ITERATIONS", "2");
+        let argon2_parallelism = source.get::<u32>("ARGON2_PARALLELISM", "1");
+        let oauth_redirect_base_url = source
+            .var("OAUTH_REDIRECT_BASE_URL")
+            .unwrap_or_else(|_| format!("http://{}:{}/api/v1/auth/oauth", ip, port));
+        let google_client_id = source.var("GOOGLE_CLIENT_ID").ok();
+        let google_client_secret = source.var("GOOGLE_CLIENT_SECRET").ok();
+        let github_client_id = source.var("GITHUB_CLIENT_ID").ok();
+        let github_client_secret = source.var("GITHUB_CLIENT_SECRET").ok();
+        let api_legacy_sunset = source
+            .var("API_LEGACY_SUNSET")
+            .unwrap_or_else(|_| "Thu, 31 Dec 2026 23:59:59 GMT".to_string());
+        let report_hide_threshold = source.get::<i64>("REPORT_HIDE_THRESHOLD", "3");
+        let moderation_action =
+            source.var("MODERATION_ACTION").unwrap_or_else(|_| "flag".to_string()).to_lowercase();
+        assert!(
+            matches!(moderation_action.as_str(), "reject" | "mask" | "flag"),
+            "MODERATION_ACTION must be one of reject, mask, flag"
+        );
+        // Rỗng thì dùng wordlist mặc định của content filter
+        let moderation_wordlist =
+            split_list(&source.var("MODERATION_WORDLIST").unwrap_or_default());
+        let fcm_project_id = source.var("FCM_PROJECT_ID").ok();
+        let fcm_client_email = source.var("FCM_CLIENT_EMAIL").ok();
+        // PEM trong .env thường được viết trên 1 dòng với `\n`
+        let fcm_private_key = source.var("FCM_PRIVATE_KEY").ok().map(|k| k.replace("\\n", "\n"));
+        let vapid_public_key = source.var("VAPID_PUBLIC_KEY").ok();
+        let vapid_private_key =
+            source.var("VAPID_PRIVATE_KEY").ok().map(|k| k.replace("\\n", "\n"));
+        let vapid_subject =
+            source.var("VAPID_SUBJECT").unwrap_or_else(|_| "mailto:admin@localhost".to_string());
+        // Không đặt thì tắt dịch tin nhắn
+        let translation_provider = source
+            .var("TRANSLATION_PROVIDER")
+            .ok()
+            .map(|provider| provider.to_lowercase())
+            .filter(|provider| !provider.is_empty());
+        assert!(
+            translation_provider
+                .as_deref()
+                .is_none_or(|provider| matches!(provider, "deepl" | "google" | "libretranslate")),
+            "TRANSLATION_PROVIDER must be one of deepl, google, libretranslate"
+        );
+        let translation_api_key = source.var("TRANSLATION_API_KEY").ok();
+        // Ghi đè endpoint mặc định của provider (vd. LibreTranslate tự host)
+        let translation_api_url = source.var("TRANSLATION_API_URL").ok();
+        let cors_allow_credentials = source.get::<bool>("CORS_ALLOW_CREDENTIALS", "true");
+        let cors_allowed_methods = split_list(
+            &source
+                .var("CORS_ALLOWED_METHODS")
+                .unwrap_or_else(|_| "GET,POST,PUT,PATCH,DELETE,OPTIONS".to_string()),
+        );
+        let cors_allowed_headers = split_list(
+            &source
+                .var("CORS_ALLOWED_HEADERS")
+                .unwrap_or_else(|_| "Authorization,Content-Type,Accept".to_string()),
+        );
+        let cors_max_age = source.get::<usize>("CORS_MAX_AGE", "3600");
+        let run_migrations = source.get::<bool>("RUN_MIGRATIONS", "false");
+        let ws_max_sessions_per_user = source.get::<usize>("WS_MAX_SESSIONS_PER_USER", "5");
+        let ws_max_total_sessions = source.get::<usize>("WS_MAX_TOTAL_SESSIONS", "10000");
+        let json_body_limit = source.get::<usize>("JSON_BODY_LIMIT", "262144");
+        // Nén response (gzip / brotli / zstd theo Accept-Encoding), body nhỏ hơn
+        // COMPRESSION_MIN_SIZE bytes được gửi nguyên
+        let compression_enabled = source.get::<bool>("COMPRESSION_ENABLED", "true");
+        let compression_min_size = source.get::<usize>("COMPRESSION_MIN_SIZE", "1024");
+        // Bearer token của Prometheus cho `/metrics`, không đặt thì tắt endpoint
+        let metrics_token = source.var("METRICS_TOKEN").ok().filter(|token| !token.is_empty());
         Env {
             jwt_secret,
+            jwt_keys,
+            jwt_keys_file,
             access_token_expiration,
             refresh_token_expiration,
+            password_reset_expiration,
             database_url,
+            database_read_url,
             redis_url,
+            db_max_connections,
+            db_min_connections,
+            db_acquire_timeout,
+            db_idle_timeout,
+            db_statement_timeout,
+            redis_max_connections,
             frontend_url,
             ip,
             port,
+            email_verification_expiration,
+            username_change_cooldown_days,
+            username_reservation_days,
+            inactive_account_days,
+            message_archive_after_months,
+            inactive_account_action,
+            suspicious_sign_in_action,
+            sign_in_confirmation_expiration,
+            trust_proxy_headers,
+            argon2_memory_kib,
+            argon2_iterations,
+            argon2_parallelism,
+            oauth_redirect_base_url,
+            google_client_id,
+            google_client_secret,
+            github_client_id,
+            github_client_secret,
+            api_legacy_sunset,
+            report_hide_threshold,
+            moderation_action,
+            moderation_wordlist,
+            fcm_project_id,
+            fcm_client_email,
+            fcm_private_key,
+            vapid_public_key,
+            vapid_private_key,
+            vapid_subject,
+            translation_provider,
+            translation_api_key,
+            translation_api_url,
+            cors_allow_credentials,
+            cors_allowed_methods,
+            cors_allowed_headers,
+            cors_max_age,
+            run_migrations,
+            ws_max_sessions_per_user,
+            ws_max_total_sessions,
+            json_body_limit,
+            compression_enabled,
+            compression_min_size,
+            metrics_token,
         }
     }
 }
 
+/// Tách biến môi trường dạng `a, b, c` thành danh sách, bỏ phần tử rỗng
+pub fn split_list(value: &str) -> Vec<String> {
+    value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(str::to_string).collect()
+}
+
 impl Default for Env {
     fn default() -> Self {
-        Self::new()
+        let source = ConfigSource::load().unwrap_or_else(|e| panic!("{e}"));
+        let env = Self::new(&source);
+
+        // Runtime settings được load riêng (reload được), validate ngay lúc khởi động để
+        // cấu hình sai làm server dừng sớm và để biết mọi key hợp lệ của config file
+        if let Err(e) = RuntimeSettings::from_source(&source, &env.frontend_url) {
+            panic!("{e}");
+        }
+        let unknown_keys = source.unknown_keys();
+        assert!(
+            unknown_keys.is_empty(),
+            "Unknown keys in config file: {} (keys are lowercase environment variable names)",
+            unknown_keys.join(", ")
+        );
+
+        env
     }
 }
+
+/// `ENV` panic nếu thiếu biến bắt buộc, tests set giá trị giả trước khi truy cập lần đầu
+#[cfg(test)]
+pub fn init_test_env() {
+    static INIT_ENV: std::sync::Once = std::sync::Once::new();
+
+    INIT_ENV.call_once(|| {
+        for (key, value) in [
+            ("SECRET_KEY", "unit-test-secret"),
+            ("DATABASE_URL", "postgres://localhost/unused"),
+            ("REDIS_URL", "redis://localhost/"),
+        ] {
+            if std::env::var(key).is_err() {
+                std::env::set_var(key, value);
+            }
+        }
+    });
+}
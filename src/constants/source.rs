@@ -0,0 +1,116 @@
+/// Config Source
+///
+/// Giá trị cấu hình được lấy theo thứ tự: biến môi trường, file TOML `CONFIG_FILE` (nếu
+/// có), rồi giá trị mặc định. Key trong file là tên biến môi trường viết thường, mảng
+/// được nối thành danh sách phân tách bằng dấu phẩy:
+///
+/// ```toml
+/// upload_body_limit = 10485760
+/// cors_allowed_origins = ["https://chat.example.com", "https://*.example.com"]
+/// ```
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    env::VarError,
+    fmt::Display,
+    str::FromStr,
+};
+
+pub struct ConfigSource {
+    path: Option<String>,
+    file: BTreeMap<String, String>,
+    /// Keys đã được đọc, để phát hiện key không hợp lệ trong file
+    used: RefCell<BTreeSet<String>>,
+}
+
+impl ConfigSource {
+    /// Đọc `CONFIG_FILE`, lỗi đọc / parse file được trả về kèm đường dẫn
+    pub fn load() -> Result<Self, String> {
+        let Ok(path) = std::env::var("CONFIG_FILE") else {
+            return Ok(ConfigSource {
+                path: None,
+                file: BTreeMap::new(),
+                used: Default::default(),
+            });
+        };
+
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Cannot read config file {path}: {e}"))?;
+        let table = content
+            .parse::<toml::Table>()
+            .map_err(|e| format!("Invalid config file {path}: {e}"))?;
+
+        let mut file = BTreeMap::new();
+        for (key, value) in table {
+            let value = match value {
+                toml::Value::Array(items) => items
+                    .into_iter()
+                    .map(|item| scalar(&path, &key, item))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(","),
+                value => scalar(&path, &key, value)?,
+            };
+            file.insert(key.to_lowercase(), value);
+        }
+
+        Ok(ConfigSource { path: Some(path), file, used: Default::default() })
+    }
+
+    /// Giá trị của biến `name` (vd. `UPLOAD_BODY_LIMIT`), cùng kiểu kết quả với
+    /// `std::env::var`
+    pub fn var(&self, name: &str) -> Result<String, VarError> {
+        let key = name.to_lowercase();
+        let value = match std::env::var(name) {
+            Err(VarError::NotPresent) => self.file.get(&key).cloned().ok_or(VarError::NotPresent),
+            result => result,
+        };
+        self.used.borrow_mut().insert(key);
+        value
+    }
+
+    /// Parse biến `name` (hoặc `default` nếu không được cấu hình), lỗi nêu rõ biến, giá trị
+    /// và nguồn của giá trị
+    pub fn parse<T>(&self, name: &str, default: &str) -> Result<T, String>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let value = self.var(name).unwrap_or_else(|_| default.to_string());
+        value
+            .parse()
+            .map_err(|e| format!("{name}{} is invalid ({value:?}): {e}", self.origin(name)))
+    }
+
+    /// Như `parse`, dừng chương trình nếu giá trị không hợp lệ (cấu hình lúc khởi động)
+    pub fn get<T>(&self, name: &str, default: &str) -> T
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.parse(name, default).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Keys trong file không được cấu hình nào đọc tới, thường là lỗi chính tả
+    pub fn unknown_keys(&self) -> Vec<String> {
+        let used = self.used.borrow();
+        self.file.keys().filter(|key| !used.contains(*key)).cloned().collect()
+    }
+
+    fn origin(&self, name: &str) -> String {
+        match &self.path {
+            _ if std::env::var_os(name).is_some() => " from environment".to_string(),
+            Some(path) if self.file.contains_key(&name.to_lowercase()) => format!(" in {path}"),
+            _ => String::new(),
+        }
+    }
+}
+
+fn scalar(path: &str, key: &str, value: toml::Value) -> Result<String, String> {
+    match value {
+        toml::Value::String(s) => Ok(s),
+        toml::Value::Array(_) | toml::Value::Table(_) => {
+            Err(format!("Invalid config file {path}: `{key}` must be a value or a list of values"))
+        }
+        other => Ok(other.to_string()),
+    }
+}
@@ -1,35 +1,20 @@
-use actix::Actor;
-use actix_cors::Cors;
-use actix_web::{
-    self,
-    middleware::{from_fn, Logger},
-    web, App, HttpServer,
-};
-use std::sync::{Arc, LazyLock};
+use actix_web::{self, HttpServer};
+use clap::Parser;
+use std::sync::LazyLock;
 
 use crate::{
-    configs::{connect_database, RedisCache},
-    middlewares::{authentication, authorization},
-    modules::{
-        conversation::{
-            repository_pg::{
-                ConversationPgRepository, LastMessagePgRepository, ParticipantPgRepository,
-            },
-            service::ConversationService,
-        },
-        file_upload::{repository_pg::FilePgRepository, service::FileUploadService},
-        friend::{repository_pg::FriendRepositoryPg, service::FriendService},
-        message::{repository_pg::MessageRepositoryPg, service::MessageService},
-        user::{repository_pg::UserRepositoryPg, schema::UserRole, service::UserService},
-        websocket::{
-            handler::websocket_handler,
-            presence::PresenceService,
-            server::WebSocketServer,
-        },
+    app::{build_app, AppState},
+    cli::{seed::SeedOptions, Cli, Command},
+    configs::{
+        connect_database, cors::CorsConfig, jwt_keys, migrate_database, read_replica::ReadPool,
+        reload_on_sighup, settings, RedisCache,
     },
+    modules::websocket::shutdown::shutdown_on_signal,
 };
 
 mod api;
+mod app;
+mod cli;
 mod configs;
 mod constants;
 mod middlewares;
@@ -48,96 +33,78 @@ pub static ENV: LazyLock<constants::Env> = LazyLock::new(|| {
     constants::Env::default()
 });
 
-#[actix_web::get("/")]
-async fn health_check(_db_pool: web::Data<sqlx::PgPool>) -> &'static str {
-    "Server is running"
-}
+/// Thời gian tối đa chờ connections đóng khi graceful shutdown
+const SHUTDOWN_TIMEOUT_SECS: u64 = 10;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    let result = match Cli::parse().command() {
+        Command::Serve => return serve().await,
+        Command::Migrate => migrate_database().await,
+        Command::CreateAdmin { username, email, password } => {
+            cli::create_admin(username, email, password).await
+        }
+        Command::PruneFiles { older_than_hours, dry_run } => {
+            cli::prune_files(older_than_hours, dry_run).await
+        }
+        Command::RecountUnread => cli::recount_unread().await,
+        Command::Seed { users, friends_per_user, groups, messages_per_conversation, password } => {
+            cli::seed(SeedOptions {
+                users,
+                friends_per_user,
+                groups,
+                messages_per_conversation,
+                password,
+            })
+            .await
+        }
+    };
+
+    result.map_err(|e| std::io::Error::other(format!("{e:?}")))
+}
+
+async fn serve() -> std::io::Result<()> {
     let db_pool =
         connect_database().await.map_err(|_| std::io::Error::other("Database connection error"))?;
 
+    let read_pool = ReadPool::from_env(db_pool.clone())
+        .map_err(|_| std::io::Error::other("Invalid DATABASE_READ_URL"))?;
+
     let redis_pool =
         RedisCache::new().await.map_err(|_| std::io::Error::other("Redis connection error"))?;
 
-    let user_repo = UserRepositoryPg::new(db_pool.clone());
-    let friend_repo = FriendRepositoryPg::new(db_pool.clone());
-    let presence_service = PresenceService::new(redis_pool.get_pool().clone());
-    let participant_repo = ParticipantPgRepository::default();
-    let message_repo = MessageRepositoryPg::new(db_pool.clone());
-    let conversation_repo =
-        ConversationPgRepository::new(db_pool.clone(), participant_repo.clone());
-    let last_message_repo = LastMessagePgRepository::default();
-    let file_repo = FilePgRepository::new(db_pool.clone());
-    let ws_server = WebSocketServer::new().start();
-    let user_service =
-        UserService::with_dependencies(Arc::new(user_repo.clone()), Arc::new(redis_pool.clone()));
-    let friend_service =
-        FriendService::with_dependencies(Arc::new(friend_repo.clone()), Arc::new(user_repo.clone()));
-    let file_upload_service = FileUploadService::with_defaults(Arc::new(file_repo));
-    let conversation_service = ConversationService::with_dependencies(
-        Arc::new(conversation_repo.clone()),
-        Arc::new(participant_repo.clone()),
-        Arc::new(message_repo.clone()),
-        Arc::new(ws_server.clone()),
-    );
-    let message_service = MessageService::with_dependencies(
-        Arc::new(conversation_repo.clone()),
-        Arc::new(message_repo),
-        Arc::new(participant_repo),
-        Arc::new(last_message_repo),
-        Arc::new(redis_pool),
-        Arc::new(ws_server.clone()),
-    );
+    // Load JWT signing keys ngay lúc khởi động để cấu hình sai làm server dừng sớm
+    let signing_kid = jwt_keys::keyring().current().kid.clone();
+    tracing::info!("Signing JWTs with kid {}", signing_kid);
+    tracing::info!("Runtime settings: {:?}", settings::settings());
+
+    let (state, workers) = AppState::build(db_pool, read_pool, redis_pool);
+    workers.spawn(&state);
+
+    // Đọc lại JWT signing keys và runtime settings khi nhận SIGHUP
+    actix_web::rt::spawn(reload_on_sighup());
 
     tracing::info!("Starting HTTP server at http://{}:{}", ENV.ip.as_str(), ENV.port);
 
-    HttpServer::new(move || {
-        let cors = Cors::default()
-            .allowed_origin(&ENV.frontend_url)
-            .allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"])
-            .allowed_headers(vec!["Authorization", "Content-Type", "Accept"])
-            .supports_credentials()
-            .max_age(3600);
-
-        App::new()
-            .wrap(cors)
-            .wrap(Logger::default())
-            .app_data(web::Data::new(user_service.clone()))
-            .app_data(web::Data::new(friend_service.clone()))
-            .app_data(web::Data::new(file_upload_service.clone()))
-            .app_data(web::Data::new(db_pool.clone()))
-            .app_data(web::Data::new(conversation_service.clone()))
-            .app_data(web::Data::new(message_service.clone()))
-            .app_data(web::Data::new(ws_server.clone())) // WebSocket server
-            .app_data(web::Data::new(presence_service.clone())) // Presence service
-            .app_data(web::Data::new(friend_repo.clone())) // Friend repo for WS presence
-            .service(health_check)
-            // WebSocket endpoint (không cần authentication - auth trong WS handshake)
-            .route("/ws", web::get().to(websocket_handler))
-            .service(
-                web::scope("/api")
-                    .default_service(
-                        web::route()
-                            .guard(actix_web::guard::Method(actix_web::http::Method::OPTIONS))
-                            .to(|| async { actix_web::HttpResponse::Ok().finish() }),
-                    )
-                    .configure(modules::user::route::public_api_configure)
-                    .service(
-                        web::scope("")
-                            .wrap(from_fn(authorization(vec![UserRole::User])))
-                            .wrap(from_fn(authentication))
-                            .configure(modules::user::route::configure)
-                            .configure(modules::friend::route::configure)
-                            .configure(modules::conversation::route::configure)
-                            .configure(modules::message::route::configure)
-                            .configure(modules::file_upload::route::configure::<FilePgRepository>),
-                    ),
-            )
-    })
-    .bind((ENV.ip.as_str(), ENV.port))?
-    .workers(2)
-    .run()
-    .await
+    let cors_config = CorsConfig::from_env();
+
+    // Giữ lại cho graceful shutdown (closure của HttpServer move state)
+    let shutdown_ws_server = state.ws_server.clone();
+    let shutdown_presence = state.presence_service.clone();
+
+    let server = HttpServer::new(move || build_app(&state, &cors_config))
+        .bind((ENV.ip.as_str(), ENV.port))?
+        .workers(2)
+        // Signals được xử lý bởi shutdown_on_signal để đóng WebSocket sessions trước khi dừng
+        .disable_signals()
+        .shutdown_timeout(SHUTDOWN_TIMEOUT_SECS)
+        .run();
+
+    actix_web::rt::spawn(shutdown_on_signal(
+        server.handle(),
+        shutdown_ws_server,
+        shutdown_presence,
+    ));
+
+    server.await
 }
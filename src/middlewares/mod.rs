@@ -1,6 +1,7 @@
 use actix_web::{
     body::{BoxBody, MessageBody},
     dev::{ServiceRequest, ServiceResponse},
+    http::StatusCode,
     middleware::Next,
     web, Error, FromRequest, HttpMessage, HttpRequest,
 };
@@ -12,13 +13,44 @@ use validator::Validate;
 
 use crate::{
     api::error,
+    configs::payload::payload_too_large,
     modules::{
-        conversation::handle::ConversationSvc, friend::handle::FriendSvc, user::schema::UserRole,
+        conversation::service::ConversationService,
+        friend::service::FriendService,
+        user::schema::UserRole,
     },
-    utils::Claims,
+    utils::{Claims, TypeClaims},
     ENV,
 };
 
+/// Kiểm tra access token đã bị revoke hay chưa.
+/// Middleware chỉ phụ thuộc vào trait này (đăng ký dưới dạng `web::Data<dyn TokenRevocation>`)
+/// để có thể thay bằng stub khi test
+#[async_trait::async_trait]
+pub trait TokenRevocation {
+    async fn is_token_revoked(&self, claims: &Claims) -> Result<bool, error::SystemError>;
+
+    /// Revoke mọi token đã cấp cho user trước thời điểm hiện tại
+    async fn revoke_all_tokens(&self, user_id: Uuid) -> Result<(), error::SystemError>;
+}
+
+/// Resolve API key (header `X-Api-Key`) thành Claims của bot principal.
+/// Đăng ký dưới dạng `web::Data<dyn ApiKeyResolver>`, tương tự `TokenRevocation`
+#[async_trait::async_trait]
+pub trait ApiKeyResolver {
+    async fn resolve_api_key(&self, key: &str) -> Result<Option<Claims>, error::SystemError>;
+}
+
+/// Ghi nhận hoạt động của user sau mỗi request đã xác thực bằng JWT (`last_active_at`).
+/// Đăng ký dưới dạng `web::Data<dyn ActivityTracker>`, không đăng ký thì bỏ qua
+#[async_trait::async_trait]
+pub trait ActivityTracker {
+    async fn record_activity(&self, user_id: Uuid);
+}
+
+/// Header chứa API key của bot, chỉ được dùng khi request không có `Authorization`
+pub const API_KEY_HEADER: &str = "X-Api-Key";
+
 pub async fn authentication<B>(
     req: ServiceRequest,
     next: Next<B>,
@@ -31,6 +63,14 @@ where
     }
 
     let auth = req.headers().get("Authorization").and_then(|h| h.to_str().ok());
+
+    if auth.is_none() {
+        if let Some(api_key) = req.headers().get(API_KEY_HEADER).and_then(|h| h.to_str().ok()) {
+            let api_key = api_key.to_string();
+            return authenticate_api_key(req, next, &api_key).await;
+        }
+    }
+
     let token = match auth.and_then(|h| h.strip_prefix("Bearer ")) {
         Some(t) => t,
         None => {
@@ -38,8 +78,61 @@ where
         }
     };
 
-    let claims = Claims::decode(token, ENV.jwt_secret.as_ref())
-        .map_err(|_| error::Error::forbidden("Token Invalid or Expired"))?;
+    let claims = Claims::decode(token)
+        .map_err(|e| error::Error::forbidden("Token Invalid or Expired").with_code(e.code()))?;
+
+    // Refresh / reset / verification token không được dùng thay access token
+    if claims._type.as_ref() != Some(&TypeClaims::AccessToken) {
+        return Err(error::Error::forbidden("Token Invalid or Expired")
+            .with_code(error::ErrorCode::InvalidToken)
+            .into());
+    }
+
+    // Guest luôn bị giới hạn trong một conversation, token thiếu conversation bị từ chối
+    if claims.role == UserRole::Guest && claims.conversation_id.is_none() {
+        return Err(error::Error::forbidden("Token Invalid or Expired")
+            .with_code(error::ErrorCode::InvalidToken)
+            .into());
+    }
+
+    let revocation = req
+        .app_data::<web::Data<dyn TokenRevocation>>()
+        .ok_or(error::Error::InternalServer)?;
+
+    if revocation.is_token_revoked(&claims).await.map_err(error::Error::from)? {
+        return Err(error::Error::unauthorized("Token has been revoked")
+            .with_code(error::ErrorCode::TokenRevoked)
+            .into());
+    }
+
+    // Chạy nền để không làm chậm request
+    if let Some(tracker) = req.app_data::<web::Data<dyn ActivityTracker>>().cloned() {
+        let user_id = claims.sub;
+        actix_web::rt::spawn(async move { tracker.record_activity(user_id).await });
+    }
+
+    req.extensions_mut().insert(claims);
+
+    next.call(req).await
+}
+
+/// Bot principal: Claims mang role `Bot` và scopes của key, không qua kiểm tra revoke
+/// JWT (key bị revoke / bot bị xóa đã bị loại trong resolver)
+async fn authenticate_api_key<B>(
+    req: ServiceRequest,
+    next: Next<B>,
+    api_key: &str,
+) -> Result<ServiceResponse<B>, Error>
+where
+    B: MessageBody + 'static,
+{
+    let resolver =
+        req.app_data::<web::Data<dyn ApiKeyResolver>>().ok_or(error::Error::InternalServer)?;
+
+    let claims =
+        resolver.resolve_api_key(api_key).await.map_err(error::Error::from)?.ok_or_else(|| {
+            error::Error::unauthorized("Invalid API key").with_code(error::ErrorCode::InvalidToken)
+        })?;
 
     req.extensions_mut().insert(claims);
 
@@ -92,9 +185,12 @@ pub async fn require_friend(
     // req body require_friend must have recipient_id and member_ids (and any)
     let (http_req, payload) = req.parts_mut();
 
-    let body_bytes = web::Bytes::from_request(http_req, payload)
-        .await
-        .map_err(|e| error::Error::bad_request(format!("Failed to read request body: {}", e)))?;
+    let body_bytes = web::Bytes::from_request(http_req, payload).await.map_err(|e| {
+        if e.as_response_error().status_code() == StatusCode::PAYLOAD_TOO_LARGE {
+            return payload_too_large(ENV.json_body_limit);
+        }
+        error::Error::bad_request(format!("Failed to read request body: {}", e))
+    })?;
 
     let parsed = serde_json::from_slice::<RequireBody>(&body_bytes)
         .map_err(|e| error::Error::bad_request(format!("Invalid Body: {}", e)))?;
@@ -106,18 +202,21 @@ pub async fn require_friend(
         .into());
     }
 
-    parsed.validate().map_err(|e| error::Error::BadRequest(e.to_string().into()))?;
+    parsed.validate().map_err(error::Error::from)?;
 
     let user_id = get_extensions::<Claims>(req.request())?.sub;
 
-    let friend_svc = req.app_data::<web::Data<FriendSvc>>().ok_or(error::Error::InternalServer)?;
+    let friend_svc =
+        req.app_data::<web::Data<FriendService>>().ok_or(error::Error::InternalServer)?;
 
     if let Some(recipient_id) = parsed.recipient_id {
         let (user_a, user_b) =
             if user_id < recipient_id { (user_id, recipient_id) } else { (recipient_id, user_id) };
 
         if !friend_svc.is_friend(user_a, user_b).await.map_err(|_| error::Error::InternalServer)? {
-            return Err(error::Error::forbidden("You are not friends with the recipient").into());
+            return Err(error::Error::forbidden("You are not friends with the recipient")
+                .with_code(error::ErrorCode::NotFriends)
+                .into());
         }
     }
 
@@ -136,7 +235,9 @@ pub async fn require_friend(
             .map_err(|_| error::Error::InternalServer)?;
 
         if !results.into_iter().all(|v| v) {
-            return Err(error::Error::forbidden("You are not friends with all members").into());
+            return Err(error::Error::forbidden("You are not friends with all members")
+                .with_code(error::ErrorCode::NotFriends)
+                .into());
         }
     }
 
@@ -166,20 +267,20 @@ pub async fn require_group_member(
     let user_id = get_extensions::<Claims>(req.request())?.sub;
 
     let conv_svc =
-        req.app_data::<web::Data<ConversationSvc>>().ok_or(error::Error::InternalServer)?;
+        req.app_data::<web::Data<ConversationService>>().ok_or(error::Error::InternalServer)?;
 
-    let (conversation, is_member) = conv_svc
-        .get_conversation_and_check_membership(parsed.conversation_id, user_id)
+    let is_member = conv_svc
+        .is_member(parsed.conversation_id, user_id)
         .await
         .map_err(|_| error::Error::not_found("Conversation not found"))?;
 
     if !is_member {
-        return Err(error::Error::forbidden("You are not a member of this conversation").into());
+        return Err(error::Error::forbidden("You are not a member of this conversation")
+            .with_code(error::ErrorCode::NotAMember)
+            .into());
     }
 
     req.set_payload(body_bytes.into());
 
-    req.extensions_mut().insert(conversation);
-
     next.call(req).await
 }
@@ -0,0 +1,83 @@
+use actix_web::{get, post, web, HttpRequest};
+use uuid::Uuid;
+
+use crate::{
+    api::{error, success},
+    middlewares::get_extensions,
+    modules::announcement::{
+        model::{AnnouncementListResponse, AnnouncementQuery, CreateAnnouncementModel},
+        schema::AnnouncementEntity,
+        service::AnnouncementService,
+    },
+    utils::{Claims, ValidatedJson, ValidatedQuery},
+};
+
+#[utoipa::path(
+    tag = "announcements",
+    responses((status = 200, body = success::SuccessData<Vec<AnnouncementEntity>>))
+)]
+#[get("")]
+pub async fn get_announcements(
+    announcement_service: web::Data<AnnouncementService>,
+    req: HttpRequest,
+) -> Result<success::Success<Vec<AnnouncementEntity>>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let announcements = announcement_service.get_unacknowledged(user_id).await?;
+
+    Ok(success::Success::ok(Some(announcements)).message("Announcements retrieved successfully"))
+}
+
+#[utoipa::path(
+    tag = "announcements",
+    path = "/{id}/ack",
+    params(("id" = Uuid, Path, description = "Announcement ID")),
+    responses(
+        (status = 204, description = "Đã xác nhận thông báo"),
+        (status = 404, description = "Không tìm thấy thông báo", body = error::ErrorBody)
+    )
+)]
+#[post("/{id:[0-9a-fA-F-]{36}}/ack")]
+pub async fn acknowledge_announcement(
+    announcement_service: web::Data<AnnouncementService>,
+    announcement_id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    announcement_service.acknowledge(user_id, announcement_id.into_inner()).await?;
+
+    Ok(success::Success::no_content())
+}
+
+#[utoipa::path(
+    tag = "admin",
+    request_body = CreateAnnouncementModel,
+    responses((status = 201, body = success::SuccessData<AnnouncementEntity>))
+)]
+#[post("")]
+pub async fn create_announcement(
+    announcement_service: web::Data<AnnouncementService>,
+    ValidatedJson(body): ValidatedJson<CreateAnnouncementModel>,
+    req: HttpRequest,
+) -> Result<success::Success<AnnouncementEntity>, error::Error> {
+    let admin_id = get_extensions::<Claims>(&req)?.sub;
+    let announcement = announcement_service.create_announcement(admin_id, body).await?;
+
+    Ok(success::Success::created(Some(announcement)).message("Announcement published successfully"))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    params(AnnouncementQuery),
+    responses((status = 200, body = success::SuccessData<AnnouncementListResponse>))
+)]
+#[get("")]
+pub async fn list_announcements(
+    announcement_service: web::Data<AnnouncementService>,
+    ValidatedQuery(query): ValidatedQuery<AnnouncementQuery>,
+) -> Result<success::Success<AnnouncementListResponse>, error::Error> {
+    let announcements = announcement_service
+        .list_announcements(query.page.unwrap_or(1), query.limit.unwrap_or(20))
+        .await?;
+
+    Ok(success::Success::ok(Some(announcements)).message("Announcements retrieved successfully"))
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use crate::modules::announcement::schema::AnnouncementWithAcks;
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct CreateAnnouncementModel {
+    #[validate(length(
+        min = 1,
+        max = 200,
+        message = "Title must be between 1 and 200 characters"
+    ))]
+    pub title: String,
+    #[validate(length(
+        min = 1,
+        max = 5000,
+        message = "Content must be between 1 and 5000 characters"
+    ))]
+    pub content: String,
+    /// Thời điểm hết hạn, bỏ trống để thông báo hiển thị cho tới khi user xác nhận
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AnnouncementQuery {
+    #[validate(range(min = 1, message = "Page must be at least 1"))]
+    pub page: Option<i64>,
+    #[validate(range(min = 1, max = 100, message = "Limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AnnouncementListResponse {
+    pub announcements: Vec<AnnouncementWithAcks>,
+    pub total: i64,
+    pub page: i64,
+    pub limit: i64,
+}
@@ -0,0 +1,37 @@
+use uuid::Uuid;
+
+use crate::api::error;
+use crate::modules::announcement::schema::{AnnouncementEntity, AnnouncementWithAcks};
+
+#[async_trait::async_trait]
+pub trait AnnouncementRepository {
+    async fn create(
+        &self,
+        created_by: &Uuid,
+        title: &str,
+        content: &str,
+        expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<AnnouncementEntity, error::SystemError>;
+
+    /// Mọi thông báo (kể cả đã hết hạn) kèm số lượt xác nhận, mới nhất trước
+    async fn list(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<AnnouncementWithAcks>, i64), error::SystemError>;
+
+    /// Thông báo còn hiệu lực mà user chưa xác nhận, chỉ tính các thông báo tạo sau
+    /// khi user đăng ký
+    async fn find_unacknowledged(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Vec<AnnouncementEntity>, error::SystemError>;
+
+    /// Ghi nhận xác nhận của user, trả về `false` nếu thông báo không tồn tại.
+    /// Xác nhận lại thông báo đã xác nhận không có tác dụng
+    async fn acknowledge(
+        &self,
+        announcement_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, error::SystemError>;
+}
@@ -0,0 +1,123 @@
+/// In-memory `AnnouncementRepository` cho unit tests của services
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::announcement::{
+        repository::AnnouncementRepository,
+        schema::{AnnouncementEntity, AnnouncementWithAcks},
+    },
+};
+
+#[derive(Default)]
+struct State {
+    /// user_id -> thời điểm đăng ký
+    users: HashMap<Uuid, chrono::DateTime<chrono::Utc>>,
+    announcements: Vec<AnnouncementEntity>,
+    /// (announcement_id, user_id)
+    acks: HashSet<(Uuid, Uuid)>,
+}
+
+/// Clone dùng chung dữ liệu, test giữ một bản để tạo users
+#[derive(Clone, Default)]
+pub struct AnnouncementRepositoryMock {
+    state: Arc<Mutex<State>>,
+}
+
+impl AnnouncementRepositoryMock {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// User đăng ký tại thời điểm hiện tại
+    pub fn add_user(&self) -> Uuid {
+        let user_id = Uuid::now_v7();
+        self.state().users.insert(user_id, Utc::now());
+        user_id
+    }
+}
+
+#[async_trait::async_trait]
+impl AnnouncementRepository for AnnouncementRepositoryMock {
+    async fn create(
+        &self,
+        created_by: &Uuid,
+        title: &str,
+        content: &str,
+        expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<AnnouncementEntity, error::SystemError> {
+        let announcement = AnnouncementEntity {
+            id: Uuid::now_v7(),
+            title: title.to_string(),
+            content: content.to_string(),
+            created_by: Some(*created_by),
+            created_at: Utc::now(),
+            expires_at,
+        };
+        self.state().announcements.push(announcement.clone());
+        Ok(announcement)
+    }
+
+    async fn list(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<AnnouncementWithAcks>, i64), error::SystemError> {
+        let state = self.state();
+        let mut announcements: Vec<AnnouncementWithAcks> = state
+            .announcements
+            .iter()
+            .map(|announcement| AnnouncementWithAcks {
+                announcement: announcement.clone(),
+                ack_count: state.acks.iter().filter(|(id, _)| *id == announcement.id).count()
+                    as i64,
+            })
+            .collect();
+        announcements.sort_by_key(|a| std::cmp::Reverse(a.announcement.created_at));
+
+        let total = announcements.len() as i64;
+        let page = announcements.into_iter().skip(offset as usize).take(limit as usize).collect();
+        Ok((page, total))
+    }
+
+    async fn find_unacknowledged(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Vec<AnnouncementEntity>, error::SystemError> {
+        let state = self.state();
+        let Some(registered_at) = state.users.get(user_id) else {
+            return Ok(Vec::new());
+        };
+
+        let now = Utc::now();
+        let mut announcements: Vec<AnnouncementEntity> = state
+            .announcements
+            .iter()
+            .filter(|a| a.created_at >= *registered_at)
+            .filter(|a| a.expires_at.is_none_or(|expires_at| expires_at > now))
+            .filter(|a| !state.acks.contains(&(a.id, *user_id)))
+            .cloned()
+            .collect();
+        announcements.sort_by_key(|a| std::cmp::Reverse(a.created_at));
+        Ok(announcements)
+    }
+
+    async fn acknowledge(
+        &self,
+        announcement_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        let mut state = self.state();
+        if !state.announcements.iter().any(|a| a.id == *announcement_id) {
+            return Ok(false);
+        }
+        state.acks.insert((*announcement_id, *user_id));
+        Ok(true)
+    }
+}
@@ -0,0 +1,124 @@
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::announcement::{
+        repository::AnnouncementRepository,
+        schema::{AnnouncementEntity, AnnouncementWithAcks},
+    },
+};
+
+#[derive(Clone)]
+pub struct AnnouncementRepositoryPg {
+    pool: sqlx::PgPool,
+}
+
+impl AnnouncementRepositoryPg {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl AnnouncementRepository for AnnouncementRepositoryPg {
+    async fn create(
+        &self,
+        created_by: &Uuid,
+        title: &str,
+        content: &str,
+        expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<AnnouncementEntity, error::SystemError> {
+        let announcement = sqlx::query_as::<_, AnnouncementEntity>(
+            r#"
+            INSERT INTO announcements (title, content, created_by, expires_at)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(title)
+        .bind(content)
+        .bind(created_by)
+        .bind(expires_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(announcement)
+    }
+
+    async fn list(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<AnnouncementWithAcks>, i64), error::SystemError> {
+        let total: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM announcements").fetch_one(&self.pool).await?;
+
+        let announcements = sqlx::query_as::<_, AnnouncementWithAcks>(
+            r#"
+            SELECT a.*,
+                (SELECT COUNT(*) FROM announcement_acks k WHERE k.announcement_id = a.id)
+                    AS ack_count
+            FROM announcements a
+            ORDER BY a.created_at DESC
+            LIMIT $1 OFFSET $2
+            "#,
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok((announcements, total))
+    }
+
+    async fn find_unacknowledged(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Vec<AnnouncementEntity>, error::SystemError> {
+        let announcements = sqlx::query_as::<_, AnnouncementEntity>(
+            r#"
+            SELECT a.*
+            FROM announcements a
+            JOIN users u ON u.id = $1
+            WHERE a.created_at >= u.created_at
+              AND (a.expires_at IS NULL OR a.expires_at > NOW())
+              AND NOT EXISTS (
+                  SELECT 1 FROM announcement_acks k
+                  WHERE k.announcement_id = a.id AND k.user_id = $1
+              )
+            ORDER BY a.created_at DESC
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(announcements)
+    }
+
+    async fn acknowledge(
+        &self,
+        announcement_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        let exists = sqlx::query_scalar::<_, bool>(
+            r#"
+            WITH target AS (
+                SELECT id FROM announcements WHERE id = $1
+            ),
+            ack AS (
+                INSERT INTO announcement_acks (announcement_id, user_id)
+                SELECT id, $2 FROM target
+                ON CONFLICT (announcement_id, user_id) DO NOTHING
+            )
+            SELECT EXISTS(SELECT 1 FROM target)
+            "#,
+        )
+        .bind(announcement_id)
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(exists)
+    }
+}
@@ -0,0 +1,24 @@
+use crate::modules::announcement::handle::*;
+use actix_web::web::{scope, ServiceConfig};
+use utoipa::OpenApi;
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        scope("/announcements").service(get_announcements).service(acknowledge_announcement),
+    );
+}
+
+/// Routes quản lý thông báo, mount dưới scope `/admin`
+pub fn admin_configure(cfg: &mut ServiceConfig) {
+    cfg.service(scope("/announcements").service(create_announcement).service(list_announcements));
+}
+
+/// OpenAPI paths của `configure` (scope `/announcements`)
+#[derive(OpenApi)]
+#[openapi(paths(get_announcements, acknowledge_announcement))]
+pub struct AnnouncementApiDoc;
+
+/// OpenAPI paths của `admin_configure` (scope `/announcements`)
+#[derive(OpenApi)]
+#[openapi(paths(create_announcement, list_announcements))]
+pub struct AdminAnnouncementApiDoc;
@@ -0,0 +1,25 @@
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct AnnouncementEntity {
+    pub id: Uuid,
+    pub title: String,
+    pub content: String,
+    /// `None` nếu tài khoản admin tạo thông báo đã bị xóa
+    pub created_by: Option<Uuid>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Sau thời điểm này thông báo không còn hiển thị cho users
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Thông báo kèm số users đã xác nhận, dùng cho trang admin
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct AnnouncementWithAcks {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub announcement: AnnouncementEntity,
+    pub ack_count: i64,
+}
@@ -0,0 +1,188 @@
+/// Announcement Service
+///
+/// Admin đăng thông báo toàn hệ thống: thông báo được lưu lại rồi broadcast tới mọi
+/// session đang kết nối qua `BroadcastToAll`. User offline thấy các thông báo chưa xác
+/// nhận ở lần lấy danh sách conversations tiếp theo. Thông báo hiển thị cho tới khi user
+/// xác nhận (ack) hoặc hết hạn.
+use std::sync::Arc;
+
+use actix::Addr;
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::{
+        announcement::{
+            model::{AnnouncementListResponse, CreateAnnouncementModel},
+            repository::AnnouncementRepository,
+            schema::AnnouncementEntity,
+        },
+        websocket::{events::BroadcastToAll, message::ServerMessage, server::WebSocketServer},
+    },
+};
+
+#[derive(Clone)]
+pub struct AnnouncementService {
+    repo: Arc<dyn AnnouncementRepository + Send + Sync>,
+    ws_server: Arc<Addr<WebSocketServer>>,
+}
+
+impl AnnouncementService {
+    pub fn with_dependencies(
+        repo: Arc<dyn AnnouncementRepository + Send + Sync>,
+        ws_server: Arc<Addr<WebSocketServer>>,
+    ) -> Self {
+        AnnouncementService { repo, ws_server }
+    }
+
+    /// Admin: tạo thông báo và broadcast tới mọi user đang online
+    pub async fn create_announcement(
+        &self,
+        admin_id: Uuid,
+        body: CreateAnnouncementModel,
+    ) -> Result<AnnouncementEntity, error::SystemError> {
+        let title = body.title.trim();
+        let content = body.content.trim();
+        if title.is_empty() || content.is_empty() {
+            return Err(error::SystemError::bad_request("Title and content cannot be blank"));
+        }
+        if body.expires_at.is_some_and(|expires_at| expires_at <= chrono::Utc::now()) {
+            return Err(error::SystemError::bad_request("Expiry must be in the future"));
+        }
+
+        let announcement = self.repo.create(&admin_id, title, content, body.expires_at).await?;
+
+        self.ws_server.do_send(BroadcastToAll {
+            message: ServerMessage::Announcement {
+                announcement_id: announcement.id,
+                title: announcement.title.clone(),
+                content: announcement.content.clone(),
+                created_at: announcement.created_at.to_rfc3339(),
+            },
+        });
+
+        Ok(announcement)
+    }
+
+    /// Admin: danh sách thông báo kèm số lượt xác nhận
+    pub async fn list_announcements(
+        &self,
+        page: i64,
+        limit: i64,
+    ) -> Result<AnnouncementListResponse, error::SystemError> {
+        let offset = (page - 1) * limit;
+        let (announcements, total) = self.repo.list(limit, offset).await?;
+
+        Ok(AnnouncementListResponse { announcements, total, page, limit })
+    }
+
+    /// Thông báo còn hiệu lực mà user chưa xác nhận, mới nhất trước
+    pub async fn get_unacknowledged(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<AnnouncementEntity>, error::SystemError> {
+        self.repo.find_unacknowledged(&user_id).await
+    }
+
+    pub async fn acknowledge(
+        &self,
+        user_id: Uuid,
+        announcement_id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        if !self.repo.acknowledge(&announcement_id, &user_id).await? {
+            return Err(error::SystemError::not_found("Announcement not found"));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix::Actor;
+
+    use super::*;
+    use crate::{
+        constants::init_test_env,
+        modules::announcement::repository_mock::AnnouncementRepositoryMock,
+    };
+
+    fn service(repo: &AnnouncementRepositoryMock) -> AnnouncementService {
+        init_test_env();
+        AnnouncementService::with_dependencies(
+            Arc::new(repo.clone()),
+            Arc::new(WebSocketServer::new().start()),
+        )
+    }
+
+    fn announcement(
+        title: &str,
+        expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> CreateAnnouncementModel {
+        CreateAnnouncementModel {
+            title: title.to_string(),
+            content: "Scheduled maintenance tonight".to_string(),
+            expires_at,
+        }
+    }
+
+    #[actix_web::test]
+    async fn announcement_shows_until_each_user_acknowledges_it() {
+        let repo = AnnouncementRepositoryMock::default();
+        let service = service(&repo);
+        let (alice, bob) = (repo.add_user(), repo.add_user());
+        let admin_id = Uuid::now_v7();
+
+        let created =
+            service.create_announcement(admin_id, announcement("  Notice ", None)).await.unwrap();
+        assert_eq!(created.title, "Notice");
+        assert_eq!(created.created_by, Some(admin_id));
+
+        service.acknowledge(alice, created.id).await.unwrap();
+        // Xác nhận lại không có tác dụng
+        service.acknowledge(alice, created.id).await.unwrap();
+
+        assert!(service.get_unacknowledged(alice).await.unwrap().is_empty());
+        let pending = service.get_unacknowledged(bob).await.unwrap();
+        assert_eq!(pending.iter().map(|a| a.id).collect::<Vec<_>>(), vec![created.id]);
+
+        let list = service.list_announcements(1, 20).await.unwrap();
+        assert_eq!(list.total, 1);
+        assert_eq!(list.announcements[0].ack_count, 1);
+    }
+
+    #[actix_web::test]
+    async fn users_only_see_live_announcements_from_after_they_registered() {
+        let repo = AnnouncementRepositoryMock::default();
+        let service = service(&repo);
+        let admin_id = Uuid::now_v7();
+        service.create_announcement(admin_id, announcement("Old", None)).await.unwrap();
+        let newcomer = repo.add_user();
+
+        let expired = chrono::Utc::now() - chrono::Duration::minutes(1);
+        repo.create(&admin_id, "Expired", "Gone", Some(expired)).await.unwrap();
+        let live = chrono::Utc::now() + chrono::Duration::hours(1);
+        let current =
+            service.create_announcement(admin_id, announcement("Live", Some(live))).await.unwrap();
+
+        let pending = service.get_unacknowledged(newcomer).await.unwrap();
+        assert_eq!(pending.iter().map(|a| a.id).collect::<Vec<_>>(), vec![current.id]);
+    }
+
+    #[actix_web::test]
+    async fn invalid_announcements_and_unknown_acks_are_rejected() {
+        let repo = AnnouncementRepositoryMock::default();
+        let service = service(&repo);
+        let admin_id = Uuid::now_v7();
+
+        let err = service.create_announcement(admin_id, announcement("   ", None)).await;
+        assert_eq!(err.unwrap_err().code(), error::ErrorCode::BadRequest);
+        let past = chrono::Utc::now() - chrono::Duration::minutes(1);
+        let err = service.create_announcement(admin_id, announcement("Late", Some(past))).await;
+        assert_eq!(err.unwrap_err().code(), error::ErrorCode::BadRequest);
+
+        let err = service.acknowledge(repo.add_user(), Uuid::now_v7()).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotFound);
+        assert_eq!(service.list_announcements(1, 20).await.unwrap().total, 0);
+    }
+}
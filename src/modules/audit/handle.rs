@@ -0,0 +1,23 @@
+use actix_web::{get, web};
+
+use crate::{
+    api::{error, success},
+    modules::audit::{model::AuditLogQuery, schema::AuditLogEntity, service::AuditService},
+    utils::ValidatedQuery,
+};
+
+/// Sự kiện bảo mật gần nhất (mới nhất trước), lọc theo `user_id` nếu có
+#[utoipa::path(
+    tag = "admin",
+    params(AuditLogQuery),
+    responses((status = 200, body = success::SuccessData<Vec<AuditLogEntity>>))
+)]
+#[get("")]
+pub async fn list_audit_log(
+    audit_service: web::Data<AuditService>,
+    ValidatedQuery(query): ValidatedQuery<AuditLogQuery>,
+) -> Result<success::Success<Vec<AuditLogEntity>>, error::Error> {
+    let logs = audit_service.list(query.user_id, query.limit.unwrap_or(50)).await?;
+
+    Ok(success::Success::ok(Some(logs)).message("Audit log retrieved successfully"))
+}
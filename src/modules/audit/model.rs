@@ -0,0 +1,36 @@
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::modules::audit::schema::AuditAction;
+
+/// Sự kiện cần ghi vào audit log
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub user_id: Option<Uuid>,
+    pub action: AuditAction,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn new(action: AuditAction, user_id: Option<Uuid>) -> Self {
+        Self { user_id, action, ip_address: None, user_agent: None }
+    }
+
+    pub fn with_client(mut self, ip_address: Option<&str>, user_agent: Option<&str>) -> Self {
+        self.ip_address = ip_address.map(str::to_string);
+        self.user_agent = user_agent.map(str::to_string);
+        self
+    }
+}
+
+#[derive(Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditLogQuery {
+    /// Chỉ lấy sự kiện của user này
+    pub user_id: Option<Uuid>,
+    #[validate(range(min = 1, max = 200, message = "Limit must be between 1 and 200"))]
+    pub limit: Option<i64>,
+}
@@ -0,0 +1,16 @@
+use uuid::Uuid;
+
+use crate::api::error;
+use crate::modules::audit::{model::AuditEntry, schema::AuditLogEntity};
+
+#[async_trait::async_trait]
+pub trait AuditLogRepository {
+    async fn create(&self, entry: &AuditEntry) -> Result<AuditLogEntity, error::SystemError>;
+
+    /// Sự kiện gần nhất (của một user nếu có `user_id`), mới nhất trước
+    async fn find_recent(
+        &self,
+        user_id: Option<&Uuid>,
+        limit: i64,
+    ) -> Result<Vec<AuditLogEntity>, error::SystemError>;
+}
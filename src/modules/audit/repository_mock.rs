@@ -0,0 +1,57 @@
+/// In-memory `AuditLogRepository` cho unit tests của services
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::audit::{model::AuditEntry, repository::AuditLogRepository, schema::AuditLogEntity},
+};
+
+/// Clone dùng chung dữ liệu, test giữ một bản để kiểm tra các sự kiện đã ghi
+#[derive(Clone, Default)]
+pub struct AuditLogRepositoryMock {
+    logs: Arc<Mutex<Vec<AuditLogEntity>>>,
+}
+
+impl AuditLogRepositoryMock {
+    fn logs(&self) -> MutexGuard<'_, Vec<AuditLogEntity>> {
+        self.logs.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Mọi sự kiện đã ghi theo thứ tự ghi
+    pub fn entries(&self) -> Vec<AuditLogEntity> {
+        self.logs().clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl AuditLogRepository for AuditLogRepositoryMock {
+    async fn create(&self, entry: &AuditEntry) -> Result<AuditLogEntity, error::SystemError> {
+        let log = AuditLogEntity {
+            id: Uuid::now_v7(),
+            user_id: entry.user_id,
+            action: entry.action,
+            ip_address: entry.ip_address.clone(),
+            user_agent: entry.user_agent.clone(),
+            created_at: chrono::Utc::now(),
+        };
+        self.logs().push(log.clone());
+        Ok(log)
+    }
+
+    async fn find_recent(
+        &self,
+        user_id: Option<&Uuid>,
+        limit: i64,
+    ) -> Result<Vec<AuditLogEntity>, error::SystemError> {
+        Ok(self
+            .logs()
+            .iter()
+            .rev()
+            .filter(|log| user_id.is_none_or(|user_id| log.user_id.as_ref() == Some(user_id)))
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+}
@@ -0,0 +1,59 @@
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::audit::{model::AuditEntry, repository::AuditLogRepository, schema::AuditLogEntity},
+};
+
+#[derive(Clone)]
+pub struct AuditLogRepositoryPg {
+    pool: sqlx::PgPool,
+}
+
+impl AuditLogRepositoryPg {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl AuditLogRepository for AuditLogRepositoryPg {
+    async fn create(&self, entry: &AuditEntry) -> Result<AuditLogEntity, error::SystemError> {
+        let log = sqlx::query_as::<_, AuditLogEntity>(
+            r#"
+            INSERT INTO audit_log (user_id, action, ip_address, user_agent)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(entry.user_id)
+        .bind(entry.action)
+        .bind(entry.ip_address.as_deref())
+        .bind(entry.user_agent.as_deref())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(log)
+    }
+
+    async fn find_recent(
+        &self,
+        user_id: Option<&Uuid>,
+        limit: i64,
+    ) -> Result<Vec<AuditLogEntity>, error::SystemError> {
+        let logs = sqlx::query_as::<_, AuditLogEntity>(
+            r#"
+            SELECT * FROM audit_log
+            WHERE $1::uuid IS NULL OR user_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(logs)
+    }
+}
@@ -0,0 +1,13 @@
+use crate::modules::audit::handle::*;
+use actix_web::web::{scope, ServiceConfig};
+use utoipa::OpenApi;
+
+/// Routes tra cứu audit log, mount dưới scope `/admin`
+pub fn admin_configure(cfg: &mut ServiceConfig) {
+    cfg.service(scope("/audit-log").service(list_audit_log));
+}
+
+/// OpenAPI paths của `admin_configure` (scope `/audit-log`)
+#[derive(OpenApi)]
+#[openapi(paths(list_audit_log))]
+pub struct AdminAuditApiDoc;
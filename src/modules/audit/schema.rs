@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::{FromRow, Type};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Loại sự kiện bảo mật được ghi vào `audit_log`
+#[derive(Debug, PartialEq, Clone, Copy, Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "audit_action", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    /// Đăng nhập từ IP user chưa từng dùng (đã cho phép hoặc đang chờ xác nhận qua email)
+    NewSignInIp,
+    /// Username / IP bị khóa đăng nhập tạm thời sau nhiều lần nhập sai mật khẩu
+    SignInLocked,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct AuditLogEntity {
+    pub id: Uuid,
+    /// `None` nếu sự kiện không gắn với tài khoản nào
+    pub user_id: Option<Uuid>,
+    pub action: AuditAction,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
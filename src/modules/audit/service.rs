@@ -0,0 +1,81 @@
+/// Audit Log Service
+///
+/// Ghi các sự kiện bảo mật của tài khoản (đăng nhập từ IP mới, ...) vào `audit_log` để
+/// admin tra cứu. Sự kiện user cần biết được tạo thêm Notification entry cho user (xem
+/// `notification`); email đi kèm (nếu có) do service phát sinh sự kiện gửi.
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::{
+        audit::{
+            model::AuditEntry,
+            repository::AuditLogRepository,
+            schema::{AuditAction, AuditLogEntity},
+        },
+        notification::{repository::NotificationRepository, schema::NotificationKind},
+    },
+};
+
+#[derive(Clone)]
+pub struct AuditService {
+    repo: Arc<dyn AuditLogRepository + Send + Sync>,
+    notifications: Arc<dyn NotificationRepository + Send + Sync>,
+}
+
+impl AuditService {
+    pub fn with_dependencies(
+        repo: Arc<dyn AuditLogRepository + Send + Sync>,
+        notifications: Arc<dyn NotificationRepository + Send + Sync>,
+    ) -> Self {
+        AuditService { repo, notifications }
+    }
+
+    /// Ghi sự kiện vào audit log, kèm Notification entry cho user nếu sự kiện cần báo
+    pub async fn record(&self, entry: AuditEntry) -> Result<AuditLogEntity, error::SystemError> {
+        let log = self.repo.create(&entry).await?;
+
+        if let (Some(user_id), Some((kind, title, body))) = (entry.user_id, notification(&entry)) {
+            self.notifications.create(&user_id, kind, title, &body).await?;
+        }
+
+        Ok(log)
+    }
+
+    /// Admin: các sự kiện gần nhất, lọc theo user nếu có
+    pub async fn list(
+        &self,
+        user_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<AuditLogEntity>, error::SystemError> {
+        self.repo.find_recent(user_id.as_ref(), limit).await
+    }
+}
+
+/// Notification entry (loại, tiêu đề, nội dung) gửi cho user của sự kiện
+fn notification(entry: &AuditEntry) -> Option<(NotificationKind, &'static str, String)> {
+    let ip_address = entry.ip_address.as_deref().unwrap_or("unknown IP");
+    let device = entry.user_agent.as_deref().unwrap_or("unknown device");
+
+    match entry.action {
+        AuditAction::NewSignInIp => Some((
+            NotificationKind::NewSignIn,
+            "New sign-in to your account",
+            format!(
+                "Your account was signed in from a new IP address {ip_address} ({device}). \
+                 If this wasn't you, reset your password."
+            ),
+        )),
+        AuditAction::SignInLocked => Some((
+            NotificationKind::AccountLocked,
+            "Sign-in to your account was locked",
+            format!(
+                "Sign-in to your account was temporarily locked after repeated failed attempts \
+                 (last attempt from {ip_address}, {device}). If this wasn't you, consider \
+                 changing your password."
+            ),
+        )),
+    }
+}
@@ -0,0 +1,236 @@
+use actix_web::{delete, get, post, web, HttpRequest};
+use uuid::Uuid;
+
+use crate::{
+    api::{error, success},
+    middlewares::get_extensions,
+    modules::{
+        bot::{
+            model::{CreateApiKeyModel, CreateBotModel, CreatedApiKey},
+            schema::{BotApiKeyEntity, BotEntity, BotScope},
+            service::BotService,
+        },
+        conversation::{model::MessageQueryRequest, service::ConversationService},
+        message::{
+            model::{GetMessageResponse, SendGroupMessage},
+            schema::MessageEntity,
+            service::MessageService,
+        },
+    },
+    utils::{Claims, ValidatedJson, ValidatedQuery},
+};
+
+/// Bot principal của request, lỗi 403 nếu API key không có `scope`
+fn require_scope(req: &HttpRequest, scope: BotScope) -> Result<Uuid, error::Error> {
+    let claims = get_extensions::<Claims>(req)?;
+
+    if !claims.has_scope(scope) {
+        return Err(error::Error::forbidden("API key does not have the required scope"));
+    }
+
+    Ok(claims.sub)
+}
+
+#[utoipa::path(
+    tag = "bots",
+    request_body = CreateBotModel,
+    responses(
+        (status = 201, body = success::SuccessData<BotEntity>),
+        (status = 409, description = "Username đã tồn tại", body = error::ErrorBody)
+    )
+)]
+#[post("")]
+pub async fn create_bot(
+    bot_service: web::Data<BotService>,
+    ValidatedJson(body): ValidatedJson<CreateBotModel>,
+    req: HttpRequest,
+) -> Result<success::Success<BotEntity>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let bot = bot_service.create_bot(user_id, body).await?;
+
+    Ok(success::Success::created(Some(bot)).message("Bot created successfully"))
+}
+
+#[utoipa::path(
+    tag = "bots",
+    responses((status = 200, body = success::SuccessData<Vec<BotEntity>>))
+)]
+#[get("")]
+pub async fn list_bots(
+    bot_service: web::Data<BotService>,
+    req: HttpRequest,
+) -> Result<success::Success<Vec<BotEntity>>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let bots = bot_service.list_bots(user_id).await?;
+
+    Ok(success::Success::ok(Some(bots)).message("Bots retrieved successfully"))
+}
+
+#[utoipa::path(
+    tag = "bots",
+    path = "/{id}",
+    params(("id" = Uuid, Path, description = "Bot ID")),
+    responses(
+        (status = 204, description = "Bot đã bị xóa, mọi API key bị revoke"),
+        (status = 404, description = "Không tìm thấy bot", body = error::ErrorBody)
+    )
+)]
+#[delete("/{id:[0-9a-fA-F-]{36}}")]
+pub async fn delete_bot(
+    bot_service: web::Data<BotService>,
+    bot_id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    bot_service.delete_bot(user_id, bot_id.into_inner()).await?;
+
+    Ok(success::Success::no_content())
+}
+
+#[utoipa::path(
+    tag = "bots",
+    path = "/{id}/keys",
+    params(("id" = Uuid, Path, description = "Bot ID")),
+    request_body = CreateApiKeyModel,
+    responses(
+        (
+            status = 201,
+            description = "`key` chỉ được trả về một lần",
+            body = success::SuccessData<CreatedApiKey>
+        ),
+        (status = 404, description = "Không tìm thấy bot", body = error::ErrorBody)
+    )
+)]
+#[post("/{id:[0-9a-fA-F-]{36}}/keys")]
+pub async fn create_api_key(
+    bot_service: web::Data<BotService>,
+    bot_id: web::Path<Uuid>,
+    ValidatedJson(body): ValidatedJson<CreateApiKeyModel>,
+    req: HttpRequest,
+) -> Result<success::Success<CreatedApiKey>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let api_key = bot_service.create_api_key(user_id, bot_id.into_inner(), body).await?;
+
+    Ok(success::Success::created(Some(api_key)).message("API key created successfully"))
+}
+
+#[utoipa::path(
+    tag = "bots",
+    path = "/{id}/keys",
+    params(("id" = Uuid, Path, description = "Bot ID")),
+    responses(
+        (status = 200, body = success::SuccessData<Vec<BotApiKeyEntity>>),
+        (status = 404, description = "Không tìm thấy bot", body = error::ErrorBody)
+    )
+)]
+#[get("/{id:[0-9a-fA-F-]{36}}/keys")]
+pub async fn list_api_keys(
+    bot_service: web::Data<BotService>,
+    bot_id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> Result<success::Success<Vec<BotApiKeyEntity>>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let keys = bot_service.list_api_keys(user_id, bot_id.into_inner()).await?;
+
+    Ok(success::Success::ok(Some(keys)).message("API keys retrieved successfully"))
+}
+
+#[utoipa::path(
+    tag = "bots",
+    path = "/{id}/keys/{key_id}",
+    params(
+        ("id" = Uuid, Path, description = "Bot ID"),
+        ("key_id" = Uuid, Path, description = "API key ID")
+    ),
+    responses(
+        (status = 204, description = "API key đã bị revoke"),
+        (status = 404, description = "Không tìm thấy bot / API key", body = error::ErrorBody)
+    )
+)]
+#[delete("/{id:[0-9a-fA-F-]{36}}/keys/{key_id:[0-9a-fA-F-]{36}}")]
+pub async fn revoke_api_key(
+    bot_service: web::Data<BotService>,
+    path: web::Path<(Uuid, Uuid)>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let (bot_id, key_id) = path.into_inner();
+    bot_service.revoke_api_key(user_id, bot_id, key_id).await?;
+
+    Ok(success::Success::no_content())
+}
+
+#[utoipa::path(
+    tag = "bots",
+    path = "/conversations/{conversation_id}/messages",
+    params(("conversation_id" = Uuid, Path, description = "Conversation ID")),
+    request_body = SendGroupMessage,
+    security(("api_key" = [])),
+    responses(
+        (status = 200, body = success::SuccessData<MessageEntity>),
+        (
+            status = 403,
+            description = "Thiếu scope `send_messages` hoặc bot không phải thành viên",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[post("/conversations/{conversation_id}/messages")]
+pub async fn send_message(
+    conversation_svc: web::Data<ConversationService>,
+    message_service: web::Data<MessageService>,
+    conversation_id: web::Path<Uuid>,
+    ValidatedJson(body): ValidatedJson<SendGroupMessage>,
+    req: HttpRequest,
+) -> Result<success::Success<MessageEntity>, error::Error> {
+    let bot_id = require_scope(&req, BotScope::SendMessages)?;
+
+    let (conversation, is_member) =
+        conversation_svc.get_conversation_and_check_membership(*conversation_id, bot_id).await?;
+    if conversation.is_none() {
+        return Err(error::Error::not_found("Conversation not found"));
+    }
+    if !is_member {
+        return Err(error::Error::forbidden("Bot is not a member of this conversation")
+            .with_code(error::ErrorCode::NotAMember));
+    }
+
+    let message = message_service
+        .send_message(bot_id, *conversation_id, body.content, body.client, body.client_message_id)
+        .await?;
+
+    Ok(success::Success::ok(Some(message)).message("Send message successfully"))
+}
+
+#[utoipa::path(
+    tag = "bots",
+    path = "/conversations/{conversation_id}/messages",
+    params(
+        ("conversation_id" = Uuid, Path, description = "Conversation ID"),
+        MessageQueryRequest
+    ),
+    security(("api_key" = [])),
+    responses(
+        (status = 200, body = success::SuccessData<GetMessageResponse>),
+        (
+            status = 403,
+            description = "Thiếu scope `read_messages` hoặc bot không phải thành viên",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[get("/conversations/{conversation_id}/messages")]
+pub async fn get_messages(
+    conversation_svc: web::Data<ConversationService>,
+    conversation_id: web::Path<Uuid>,
+    ValidatedQuery(query): ValidatedQuery<MessageQueryRequest>,
+    req: HttpRequest,
+) -> Result<success::Success<GetMessageResponse>, error::Error> {
+    let bot_id = require_scope(&req, BotScope::ReadMessages)?;
+
+    let messages = conversation_svc
+        .get_message(*conversation_id, bot_id, query.limit, query.cursor.clone(), query.direction)
+        .await?;
+
+    Ok(success::Success::ok(Some(messages)).message("Successfully retrieved messages"))
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::modules::bot::schema::{BotApiKeyEntity, BotScope};
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct CreateBotModel {
+    #[validate(length(min = 3, max = 255, message = "Username must be 3 to 255 characters"))]
+    pub username: String,
+    #[validate(length(min = 1, max = 255, message = "Display name must be 1 to 255 characters"))]
+    pub display_name: String,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct CreateApiKeyModel {
+    /// Tên gợi nhớ của key (ví dụ tên integration dùng key)
+    #[validate(length(min = 1, max = 100, message = "Name must be 1 to 100 characters"))]
+    pub name: String,
+    #[validate(length(min = 1, message = "At least one scope is required"))]
+    pub scopes: Vec<BotScope>,
+}
+
+/// API key vừa tạo, `key` chỉ được trả về một lần
+#[derive(Serialize, ToSchema)]
+pub struct CreatedApiKey {
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: BotApiKeyEntity,
+}
+
+/// Dữ liệu insert bot user
+pub struct InsertBot {
+    pub id: Uuid,
+    pub owner_id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub hash_password: String,
+    pub display_name: String,
+}
+
+/// Bot sở hữu một API key hợp lệ
+#[derive(sqlx::FromRow)]
+pub struct BotPrincipal {
+    pub key_id: Uuid,
+    pub bot_id: Uuid,
+    pub scopes: Vec<BotScope>,
+}
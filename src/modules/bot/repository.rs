@@ -0,0 +1,44 @@
+use uuid::Uuid;
+
+use crate::api::error;
+use crate::modules::bot::model::{BotPrincipal, InsertBot};
+use crate::modules::bot::schema::{BotApiKeyEntity, BotEntity, BotScope};
+
+#[async_trait::async_trait]
+pub trait BotRepository {
+    /// Tạo bot user (role `BOT`) cùng quan hệ sở hữu
+    async fn create(&self, bot: &InsertBot) -> Result<BotEntity, error::SystemError>;
+
+    async fn find_by_owner(&self, owner_id: &Uuid) -> Result<Vec<BotEntity>, error::SystemError>;
+
+    async fn find_owned(
+        &self,
+        bot_id: &Uuid,
+        owner_id: &Uuid,
+    ) -> Result<Option<BotEntity>, error::SystemError>;
+
+    /// Soft delete bot user và revoke mọi API key của bot
+    async fn delete(&self, bot_id: &Uuid, owner_id: &Uuid) -> Result<bool, error::SystemError>;
+
+    /// Lưu API key, chỉ hash (SHA-256) của key được lưu
+    async fn create_key(
+        &self,
+        bot_id: &Uuid,
+        name: &str,
+        key_hash: &str,
+        key_prefix: &str,
+        scopes: &[BotScope],
+    ) -> Result<BotApiKeyEntity, error::SystemError>;
+
+    async fn find_keys(&self, bot_id: &Uuid) -> Result<Vec<BotApiKeyEntity>, error::SystemError>;
+
+    async fn revoke_key(&self, bot_id: &Uuid, key_id: &Uuid) -> Result<bool, error::SystemError>;
+
+    /// Bot của API key còn hiệu lực (key chưa revoke, bot chưa bị xóa / ban)
+    async fn authenticate(
+        &self,
+        key_hash: &str,
+    ) -> Result<Option<BotPrincipal>, error::SystemError>;
+
+    async fn update_last_used(&self, key_id: &Uuid) -> Result<(), error::SystemError>;
+}
@@ -0,0 +1,160 @@
+/// In-memory `BotRepository` cho unit tests của services
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::bot::{
+        model::{BotPrincipal, InsertBot},
+        repository::BotRepository,
+        schema::{BotApiKeyEntity, BotEntity, BotScope},
+    },
+};
+
+#[derive(Default)]
+struct State {
+    bots: Vec<BotEntity>,
+    /// (key, key_hash)
+    keys: Vec<(BotApiKeyEntity, String)>,
+    /// Số lần `update_last_used` được gọi
+    last_used_writes: usize,
+}
+
+/// Clone dùng chung dữ liệu, test giữ một bản để kiểm tra bots / keys đã ghi
+#[derive(Clone, Default)]
+pub struct BotRepositoryMock {
+    state: Arc<Mutex<State>>,
+}
+
+impl BotRepositoryMock {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Hash đã lưu của key `key_id`
+    pub fn key_hash(&self, key_id: &Uuid) -> Option<String> {
+        self.state().keys.iter().find(|(key, _)| key.id == *key_id).map(|(_, hash)| hash.clone())
+    }
+
+    pub fn last_used_writes(&self) -> usize {
+        self.state().last_used_writes
+    }
+}
+
+#[async_trait::async_trait]
+impl BotRepository for BotRepositoryMock {
+    async fn create(&self, bot: &InsertBot) -> Result<BotEntity, error::SystemError> {
+        let entity = BotEntity {
+            id: bot.id,
+            owner_id: bot.owner_id,
+            username: bot.username.clone(),
+            display_name: bot.display_name.clone(),
+            avatar_url: None,
+            created_at: Utc::now(),
+        };
+        self.state().bots.push(entity.clone());
+        Ok(entity)
+    }
+
+    async fn find_by_owner(&self, owner_id: &Uuid) -> Result<Vec<BotEntity>, error::SystemError> {
+        Ok(self.state().bots.iter().filter(|bot| bot.owner_id == *owner_id).cloned().collect())
+    }
+
+    async fn find_owned(
+        &self,
+        bot_id: &Uuid,
+        owner_id: &Uuid,
+    ) -> Result<Option<BotEntity>, error::SystemError> {
+        Ok(self
+            .state()
+            .bots
+            .iter()
+            .find(|bot| bot.id == *bot_id && bot.owner_id == *owner_id)
+            .cloned())
+    }
+
+    async fn delete(&self, bot_id: &Uuid, owner_id: &Uuid) -> Result<bool, error::SystemError> {
+        let mut state = self.state();
+        let before = state.bots.len();
+        state.bots.retain(|bot| !(bot.id == *bot_id && bot.owner_id == *owner_id));
+        if state.bots.len() == before {
+            return Ok(false);
+        }
+
+        for (key, _) in state.keys.iter_mut().filter(|(key, _)| key.bot_id == *bot_id) {
+            key.revoked_at.get_or_insert_with(Utc::now);
+        }
+        Ok(true)
+    }
+
+    async fn create_key(
+        &self,
+        bot_id: &Uuid,
+        name: &str,
+        key_hash: &str,
+        key_prefix: &str,
+        scopes: &[BotScope],
+    ) -> Result<BotApiKeyEntity, error::SystemError> {
+        let key = BotApiKeyEntity {
+            id: Uuid::now_v7(),
+            bot_id: *bot_id,
+            name: name.to_string(),
+            key_prefix: key_prefix.to_string(),
+            scopes: scopes.to_vec(),
+            created_at: Utc::now(),
+            last_used_at: None,
+            revoked_at: None,
+        };
+        self.state().keys.push((key.clone(), key_hash.to_string()));
+        Ok(key)
+    }
+
+    async fn find_keys(&self, bot_id: &Uuid) -> Result<Vec<BotApiKeyEntity>, error::SystemError> {
+        Ok(self
+            .state()
+            .keys
+            .iter()
+            .filter(|(key, _)| key.bot_id == *bot_id)
+            .map(|(key, _)| key.clone())
+            .collect())
+    }
+
+    async fn revoke_key(&self, bot_id: &Uuid, key_id: &Uuid) -> Result<bool, error::SystemError> {
+        let mut state = self.state();
+        let Some((key, _)) = state.keys.iter_mut().find(|(key, _)| {
+            key.id == *key_id && key.bot_id == *bot_id && key.revoked_at.is_none()
+        }) else {
+            return Ok(false);
+        };
+        key.revoked_at = Some(Utc::now());
+        Ok(true)
+    }
+
+    async fn authenticate(
+        &self,
+        key_hash: &str,
+    ) -> Result<Option<BotPrincipal>, error::SystemError> {
+        let state = self.state();
+        Ok(state
+            .keys
+            .iter()
+            .find(|(key, hash)| hash == key_hash && key.revoked_at.is_none())
+            .filter(|(key, _)| state.bots.iter().any(|bot| bot.id == key.bot_id))
+            .map(|(key, _)| BotPrincipal {
+                key_id: key.id,
+                bot_id: key.bot_id,
+                scopes: key.scopes.clone(),
+            }))
+    }
+
+    async fn update_last_used(&self, key_id: &Uuid) -> Result<(), error::SystemError> {
+        let mut state = self.state();
+        state.last_used_writes += 1;
+        if let Some((key, _)) = state.keys.iter_mut().find(|(key, _)| key.id == *key_id) {
+            key.last_used_at = Some(Utc::now());
+        }
+        Ok(())
+    }
+}
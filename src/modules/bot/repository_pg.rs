@@ -0,0 +1,206 @@
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::bot::{
+        model::{BotPrincipal, InsertBot},
+        repository::BotRepository,
+        schema::{BotApiKeyEntity, BotEntity, BotScope},
+    },
+};
+
+#[derive(Clone)]
+pub struct BotRepositoryPg {
+    pool: sqlx::PgPool,
+}
+
+impl BotRepositoryPg {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl BotRepository for BotRepositoryPg {
+    async fn create(&self, bot: &InsertBot) -> Result<BotEntity, error::SystemError> {
+        let bot = sqlx::query_as::<_, BotEntity>(
+            r#"
+            WITH u AS (
+                INSERT INTO users (id, username, email, hash_password, display_name, role, email_verified, discoverable)
+                VALUES ($1, $2, $3, $4, $5, 'BOT', true, false)
+                RETURNING id, username, display_name, avatar_url
+            ), b AS (
+                INSERT INTO bots (user_id, owner_id)
+                SELECT id, $6 FROM u
+                RETURNING user_id, owner_id, created_at
+            )
+            SELECT b.user_id AS id, b.owner_id, u.username, u.display_name, u.avatar_url, b.created_at
+            FROM b
+            JOIN u ON u.id = b.user_id
+            "#,
+        )
+        .bind(bot.id)
+        .bind(&bot.username)
+        .bind(&bot.email)
+        .bind(&bot.hash_password)
+        .bind(&bot.display_name)
+        .bind(bot.owner_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(bot)
+    }
+
+    async fn find_by_owner(&self, owner_id: &Uuid) -> Result<Vec<BotEntity>, error::SystemError> {
+        let bots = sqlx::query_as::<_, BotEntity>(
+            r#"
+            SELECT b.user_id AS id, b.owner_id, u.username, u.display_name, u.avatar_url, b.created_at
+            FROM bots b
+            JOIN users u ON u.id = b.user_id
+            WHERE b.owner_id = $1
+            AND u.deleted_at IS NULL
+            ORDER BY b.created_at DESC
+            "#,
+        )
+        .bind(owner_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(bots)
+    }
+
+    async fn find_owned(
+        &self,
+        bot_id: &Uuid,
+        owner_id: &Uuid,
+    ) -> Result<Option<BotEntity>, error::SystemError> {
+        let bot = sqlx::query_as::<_, BotEntity>(
+            r#"
+            SELECT b.user_id AS id, b.owner_id, u.username, u.display_name, u.avatar_url, b.created_at
+            FROM bots b
+            JOIN users u ON u.id = b.user_id
+            WHERE b.user_id = $1
+            AND b.owner_id = $2
+            AND u.deleted_at IS NULL
+            "#,
+        )
+        .bind(bot_id)
+        .bind(owner_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(bot)
+    }
+
+    async fn delete(&self, bot_id: &Uuid, owner_id: &Uuid) -> Result<bool, error::SystemError> {
+        let deleted = sqlx::query_scalar::<_, bool>(
+            r#"
+            WITH deleted AS (
+                UPDATE users SET deleted_at = NOW()
+                WHERE id = $1
+                AND deleted_at IS NULL
+                AND EXISTS (SELECT 1 FROM bots WHERE user_id = $1 AND owner_id = $2)
+                RETURNING id
+            ), revoked AS (
+                UPDATE bot_api_keys SET revoked_at = NOW()
+                WHERE bot_id IN (SELECT id FROM deleted)
+                AND revoked_at IS NULL
+                RETURNING id
+            )
+            SELECT EXISTS (SELECT 1 FROM deleted)
+            "#,
+        )
+        .bind(bot_id)
+        .bind(owner_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(deleted)
+    }
+
+    async fn create_key(
+        &self,
+        bot_id: &Uuid,
+        name: &str,
+        key_hash: &str,
+        key_prefix: &str,
+        scopes: &[BotScope],
+    ) -> Result<BotApiKeyEntity, error::SystemError> {
+        let api_key = sqlx::query_as::<_, BotApiKeyEntity>(
+            r#"
+            INSERT INTO bot_api_keys (bot_id, name, key_prefix, key_hash, scopes)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(bot_id)
+        .bind(name)
+        .bind(key_prefix)
+        .bind(key_hash)
+        .bind(scopes)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(api_key)
+    }
+
+    async fn find_keys(&self, bot_id: &Uuid) -> Result<Vec<BotApiKeyEntity>, error::SystemError> {
+        let keys = sqlx::query_as::<_, BotApiKeyEntity>(
+            "SELECT * FROM bot_api_keys WHERE bot_id = $1 ORDER BY created_at DESC",
+        )
+        .bind(bot_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(keys)
+    }
+
+    async fn revoke_key(&self, bot_id: &Uuid, key_id: &Uuid) -> Result<bool, error::SystemError> {
+        let rows = sqlx::query(
+            r#"
+            UPDATE bot_api_keys SET revoked_at = NOW()
+            WHERE id = $1
+            AND bot_id = $2
+            AND revoked_at IS NULL
+            "#,
+        )
+        .bind(key_id)
+        .bind(bot_id)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows > 0)
+    }
+
+    async fn authenticate(
+        &self,
+        key_hash: &str,
+    ) -> Result<Option<BotPrincipal>, error::SystemError> {
+        let principal = sqlx::query_as::<_, BotPrincipal>(
+            r#"
+            SELECT k.id AS key_id, k.bot_id, k.scopes
+            FROM bot_api_keys k
+            JOIN users u ON u.id = k.bot_id
+            WHERE k.key_hash = $1
+            AND k.revoked_at IS NULL
+            AND u.deleted_at IS NULL
+            AND u.banned_at IS NULL
+            "#,
+        )
+        .bind(key_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(principal)
+    }
+
+    async fn update_last_used(&self, key_id: &Uuid) -> Result<(), error::SystemError> {
+        sqlx::query("UPDATE bot_api_keys SET last_used_at = NOW() WHERE id = $1")
+            .bind(key_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
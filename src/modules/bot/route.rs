@@ -0,0 +1,31 @@
+use crate::modules::bot::handle::*;
+use actix_web::web::{scope, ServiceConfig};
+use utoipa::OpenApi;
+
+/// Quản lý bot và API keys, dành cho owner (JWT của user)
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        scope("/bots")
+            .service(create_bot)
+            .service(list_bots)
+            .service(delete_bot)
+            .service(create_api_key)
+            .service(list_api_keys)
+            .service(revoke_api_key),
+    );
+}
+
+/// API cho bot principal (header `X-Api-Key`), mount dưới scope `/bot`
+pub fn bot_configure(cfg: &mut ServiceConfig) {
+    cfg.service(send_message).service(get_messages);
+}
+
+/// OpenAPI paths của `configure` (scope `/bots`)
+#[derive(OpenApi)]
+#[openapi(paths(create_bot, list_bots, delete_bot, create_api_key, list_api_keys, revoke_api_key))]
+pub struct BotApiDoc;
+
+/// OpenAPI paths của `bot_configure` (scope `/bot`)
+#[derive(OpenApi)]
+#[openapi(paths(send_message, get_messages))]
+pub struct BotPrincipalApiDoc;
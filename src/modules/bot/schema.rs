@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::{FromRow, Type};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Quyền được cấp cho một API key của bot
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Type, Serialize, Deserialize, ToSchema,
+)]
+#[sqlx(type_name = "bot_scope", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum BotScope {
+    /// Gửi tin nhắn vào các conversations mà bot là thành viên
+    SendMessages,
+    /// Đọc lịch sử tin nhắn của các conversations mà bot là thành viên
+    ReadMessages,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct BotEntity {
+    /// User ID của bot (bot là một user với role `BOT`)
+    pub id: Uuid,
+    pub owner_id: Uuid,
+    pub username: String,
+    pub display_name: String,
+    pub avatar_url: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct BotApiKeyEntity {
+    pub id: Uuid,
+    pub bot_id: Uuid,
+    pub name: String,
+    /// Phần đầu của key để nhận diện, key đầy đủ chỉ được trả về lúc tạo
+    pub key_prefix: String,
+    pub scopes: Vec<BotScope>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
@@ -0,0 +1,279 @@
+/// Bot Service
+///
+/// Bot là một user với role `BOT` thuộc về user tạo ra nó (owner). Owner cấp API keys
+/// có scope cho bot; integrations (webhooks, reminders...) gọi API bằng header
+/// `X-Api-Key`, middleware `authentication` resolve key thành bot principal qua
+/// `ApiKeyResolver`. Bot chỉ gửi / đọc tin nhắn ở conversations mà nó đã được thêm vào.
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    configs::cache::CacheBackend,
+    middlewares::ApiKeyResolver,
+    modules::{
+        bot::{
+            model::{CreateApiKeyModel, CreateBotModel, CreatedApiKey, InsertBot},
+            repository::BotRepository,
+            schema::{BotApiKeyEntity, BotEntity},
+        },
+        user::schema::UserRole,
+    },
+    utils::{hash_token, random_token, Claims, UNUSABLE_PASSWORD_HASH},
+};
+
+/// Tiền tố của mọi API key, giúp nhận diện key bị lộ (secret scanning)
+const API_KEY_PREFIX: &str = "bot_";
+
+/// Số ký tự ngẫu nhiên sau tiền tố
+const API_KEY_RANDOM_LEN: usize = 40;
+
+/// Số ký tự đầu của key được lưu để hiển thị
+const KEY_PREFIX_LEN: usize = 12;
+
+/// `last_used_at` của một key được ghi tối đa một lần trong khoảng này (giây)
+const LAST_USED_WRITE_INTERVAL: usize = 5 * 60;
+
+#[derive(Clone)]
+pub struct BotService {
+    repo: Arc<dyn BotRepository + Send + Sync>,
+    cache: Arc<dyn CacheBackend>,
+}
+
+impl BotService {
+    pub fn with_dependencies(
+        repo: Arc<dyn BotRepository + Send + Sync>,
+        cache: Arc<dyn CacheBackend>,
+    ) -> Self {
+        BotService { repo, cache }
+    }
+
+    pub async fn create_bot(
+        &self,
+        owner_id: Uuid,
+        bot: CreateBotModel,
+    ) -> Result<BotEntity, error::SystemError> {
+        let username = bot.username.trim().to_string();
+        let display_name = bot.display_name.trim().to_string();
+        if username.is_empty() || display_name.is_empty() {
+            return Err(error::SystemError::bad_request(
+                "Username and display name cannot be blank",
+            ));
+        }
+
+        // Bot không đăng nhập bằng mật khẩu, email chỉ để thỏa ràng buộc của users
+        let id = Uuid::now_v7();
+        let insert = InsertBot {
+            id,
+            owner_id,
+            username,
+            email: format!("{id}@bots.invalid"),
+            hash_password: UNUSABLE_PASSWORD_HASH.to_string(),
+            display_name,
+        };
+
+        self.repo.create(&insert).await
+    }
+
+    pub async fn list_bots(&self, owner_id: Uuid) -> Result<Vec<BotEntity>, error::SystemError> {
+        self.repo.find_by_owner(&owner_id).await
+    }
+
+    pub async fn delete_bot(&self, owner_id: Uuid, bot_id: Uuid) -> Result<(), error::SystemError> {
+        if !self.repo.delete(&bot_id, &owner_id).await? {
+            return Err(error::SystemError::not_found("Bot not found"));
+        }
+
+        Ok(())
+    }
+
+    pub async fn create_api_key(
+        &self,
+        owner_id: Uuid,
+        bot_id: Uuid,
+        body: CreateApiKeyModel,
+    ) -> Result<CreatedApiKey, error::SystemError> {
+        self.find_owned(owner_id, bot_id).await?;
+
+        let name = body.name.trim();
+        if name.is_empty() {
+            return Err(error::SystemError::bad_request("Name cannot be blank"));
+        }
+
+        let mut scopes = body.scopes;
+        scopes.sort();
+        scopes.dedup();
+
+        let key = format!("{API_KEY_PREFIX}{}", random_token(API_KEY_RANDOM_LEN));
+        let api_key = self
+            .repo
+            .create_key(&bot_id, name, &hash_token(&key), &key[..KEY_PREFIX_LEN], &scopes)
+            .await?;
+
+        Ok(CreatedApiKey { key, api_key })
+    }
+
+    pub async fn list_api_keys(
+        &self,
+        owner_id: Uuid,
+        bot_id: Uuid,
+    ) -> Result<Vec<BotApiKeyEntity>, error::SystemError> {
+        self.find_owned(owner_id, bot_id).await?;
+        self.repo.find_keys(&bot_id).await
+    }
+
+    pub async fn revoke_api_key(
+        &self,
+        owner_id: Uuid,
+        bot_id: Uuid,
+        key_id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        self.find_owned(owner_id, bot_id).await?;
+
+        if !self.repo.revoke_key(&bot_id, &key_id).await? {
+            return Err(error::SystemError::not_found("API key not found"));
+        }
+
+        Ok(())
+    }
+
+    /// Claims của bot principal cho API key, `None` nếu key không hợp lệ / đã revoke
+    pub async fn authenticate(&self, key: &str) -> Result<Option<Claims>, error::SystemError> {
+        if !key.starts_with(API_KEY_PREFIX) {
+            return Ok(None);
+        }
+
+        let Some(principal) = self.repo.authenticate(&hash_token(key)).await? else {
+            return Ok(None);
+        };
+        self.record_key_usage(principal.key_id).await;
+
+        Ok(Some(Claims::new(&principal.bot_id, &UserRole::Bot, 0).with_scopes(principal.scopes)))
+    }
+
+    /// Ghi `last_used_at` của key, giới hạn tần suất bằng Redis `SET NX` như
+    /// `UserService::record_activity`. Lỗi chỉ được log vì không ảnh hưởng tới request
+    async fn record_key_usage(&self, key_id: Uuid) {
+        let key = format!("bot_key_used:{key_id}");
+        match self.cache.set_nx(&key, &true, LAST_USED_WRITE_INTERVAL).await {
+            Ok(false) => {}
+            Ok(true) => {
+                if let Err(e) = self.repo.update_last_used(&key_id).await {
+                    tracing::warn!("Failed to record usage of API key {}: {:?}", key_id, e);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to throttle usage of API key {}: {:?}", key_id, e),
+        }
+    }
+
+    async fn find_owned(
+        &self,
+        owner_id: Uuid,
+        bot_id: Uuid,
+    ) -> Result<BotEntity, error::SystemError> {
+        self.repo
+            .find_owned(&bot_id, &owner_id)
+            .await?
+            .ok_or_else(|| error::SystemError::not_found("Bot not found"))
+    }
+}
+
+#[async_trait::async_trait]
+impl ApiKeyResolver for BotService {
+    async fn resolve_api_key(&self, key: &str) -> Result<Option<Claims>, error::SystemError> {
+        self.authenticate(key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        configs::cache::MemoryCache,
+        constants::init_test_env,
+        modules::bot::{repository_mock::BotRepositoryMock, schema::BotScope},
+    };
+
+    fn service(repo: &BotRepositoryMock) -> BotService {
+        init_test_env();
+        BotService::with_dependencies(Arc::new(repo.clone()), Arc::new(MemoryCache::default()))
+    }
+
+    async fn create_bot(service: &BotService, owner_id: Uuid) -> BotEntity {
+        let bot = CreateBotModel {
+            username: "reminder-bot".to_string(),
+            display_name: "Reminder".to_string(),
+        };
+        service.create_bot(owner_id, bot).await.unwrap()
+    }
+
+    fn api_key_model() -> CreateApiKeyModel {
+        CreateApiKeyModel {
+            name: "ci".to_string(),
+            scopes: vec![BotScope::SendMessages, BotScope::SendMessages],
+        }
+    }
+
+    #[actix_web::test]
+    async fn created_key_authenticates_as_bot_and_is_stored_hashed() {
+        let repo = BotRepositoryMock::default();
+        let service = service(&repo);
+        let owner_id = Uuid::now_v7();
+        let bot = create_bot(&service, owner_id).await;
+
+        let created = service.create_api_key(owner_id, bot.id, api_key_model()).await.unwrap();
+        assert!(created.key.starts_with(API_KEY_PREFIX));
+        assert_eq!(created.api_key.scopes, vec![BotScope::SendMessages]);
+        assert_eq!(repo.key_hash(&created.api_key.id), Some(hash_token(&created.key)));
+
+        let claims = service.authenticate(&created.key).await.unwrap().unwrap();
+        assert_eq!(claims.sub, bot.id);
+        assert_eq!(claims.role, UserRole::Bot);
+        assert_eq!(claims.scopes, Some(vec![BotScope::SendMessages]));
+
+        // Lần dùng thứ hai trong cửa sổ không ghi lại `last_used_at`
+        service.authenticate(&created.key).await.unwrap().unwrap();
+        assert_eq!(repo.last_used_writes(), 1);
+    }
+
+    #[actix_web::test]
+    async fn non_owner_cannot_manage_bot_keys() {
+        let repo = BotRepositoryMock::default();
+        let service = service(&repo);
+        let owner_id = Uuid::now_v7();
+        let bot = create_bot(&service, owner_id).await;
+        let created = service.create_api_key(owner_id, bot.id, api_key_model()).await.unwrap();
+        let stranger = Uuid::now_v7();
+
+        // `CreatedApiKey` không derive Debug để key không lọt vào log
+        let Err(err) = service.create_api_key(stranger, bot.id, api_key_model()).await else {
+            panic!("non-owner created an API key");
+        };
+        assert_eq!(err.code(), error::ErrorCode::NotFound);
+        let err = service.list_api_keys(stranger, bot.id).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotFound);
+        let err = service.revoke_api_key(stranger, bot.id, created.api_key.id).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotFound);
+        let err = service.delete_bot(stranger, bot.id).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotFound);
+
+        assert!(service.authenticate(&created.key).await.unwrap().is_some());
+    }
+
+    #[actix_web::test]
+    async fn revoked_or_unknown_keys_do_not_authenticate() {
+        let repo = BotRepositoryMock::default();
+        let service = service(&repo);
+        let owner_id = Uuid::now_v7();
+        let bot = create_bot(&service, owner_id).await;
+        let created = service.create_api_key(owner_id, bot.id, api_key_model()).await.unwrap();
+
+        service.revoke_api_key(owner_id, bot.id, created.api_key.id).await.unwrap();
+
+        assert!(service.authenticate(&created.key).await.unwrap().is_none());
+        assert!(service.authenticate("bot_unknown").await.unwrap().is_none());
+        assert!(service.authenticate("not-a-bot-key").await.unwrap().is_none());
+        assert_eq!(repo.last_used_writes(), 0);
+    }
+}
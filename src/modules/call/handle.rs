@@ -0,0 +1,34 @@
+use actix_web::{get, web, HttpRequest};
+
+use crate::{
+    api::{error, success},
+    middlewares::get_extensions,
+    modules::call::{
+        model::{CallHistoryQuery, CallHistoryResponse},
+        service::CallService,
+    },
+    utils::{Claims, ValidatedQuery},
+};
+
+/// Lịch sử cuộc gọi (gọi đi và gọi đến) của user hiện tại, mới nhất trước
+///
+/// GET /calls?limit=20&cursor=<created_at>
+#[utoipa::path(
+    tag = "calls",
+    params(CallHistoryQuery),
+    responses(
+        (status = 200, body = success::SuccessData<CallHistoryResponse>),
+        (status = 400, description = "Cursor không hợp lệ", body = error::ErrorBody)
+    )
+)]
+#[get("")]
+pub async fn get_call_history(
+    call_service: web::Data<CallService>,
+    ValidatedQuery(query): ValidatedQuery<CallHistoryQuery>,
+    req: HttpRequest,
+) -> Result<success::Success<CallHistoryResponse>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let history = call_service.get_history(user_id, query.limit, query.cursor).await?;
+
+    Ok(success::Success::ok(Some(history)).message("Call history retrieved successfully"))
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::modules::call::schema::{CallEntity, CallType};
+
+#[derive(Debug, Clone)]
+pub struct InsertCall {
+    pub conversation_id: Uuid,
+    pub caller_id: Uuid,
+    pub callee_id: Uuid,
+    pub call_type: CallType,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CallHistoryQuery {
+    #[validate(range(min = 1, max = 50, message = "Limit must be between 1 and 50"))]
+    pub limit: Option<i64>,
+    /// `created_at` (RFC 3339) của cuộc gọi cuối cùng ở trang trước
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CallHistoryResponse {
+    pub calls: Vec<CallEntity>,
+    /// Cursor lấy trang tiếp theo, `None` nếu đã hết
+    pub cursor: Option<String>,
+}
@@ -0,0 +1,62 @@
+use uuid::Uuid;
+
+use crate::api::error;
+use crate::modules::call::model::InsertCall;
+use crate::modules::call::schema::CallEntity;
+
+#[async_trait::async_trait]
+pub trait CallRepository {
+    /// Caller và callee đều là thành viên active của direct conversation
+    async fn are_direct_peers(
+        &self,
+        conversation_id: &Uuid,
+        caller_id: &Uuid,
+        callee_id: &Uuid,
+    ) -> Result<bool, error::SystemError>;
+
+    /// User là thành viên active của group conversation
+    async fn is_group_member(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, error::SystemError>;
+
+    /// Cuộc gọi đang đổ chuông / diễn ra của user (caller hoặc callee)
+    async fn find_active_by_user(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Option<CallEntity>, error::SystemError>;
+
+    async fn create(&self, call: &InsertCall) -> Result<CallEntity, error::SystemError>;
+
+    async fn find_by_id(&self, id: &Uuid) -> Result<Option<CallEntity>, error::SystemError>;
+
+    /// Chuyển cuộc gọi đang đổ chuông sang `ongoing`, chỉ callee được trả lời
+    async fn answer(
+        &self,
+        id: &Uuid,
+        callee_id: &Uuid,
+    ) -> Result<Option<CallEntity>, error::SystemError>;
+
+    /// Kết thúc cuộc gọi chưa kết thúc: `ended` nếu đã trả lời (kèm thời lượng),
+    /// `missed` nếu caller hủy khi đang đổ chuông, `declined` nếu callee từ chối
+    async fn end(
+        &self,
+        id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<Option<CallEntity>, error::SystemError>;
+
+    /// Đánh dấu `missed` các cuộc gọi đổ chuông từ trước `before`
+    async fn expire_ringing(
+        &self,
+        before: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<CallEntity>, error::SystemError>;
+
+    /// Lịch sử cuộc gọi của user, mới nhất trước
+    async fn find_history(
+        &self,
+        user_id: &Uuid,
+        created_before: Option<chrono::DateTime<chrono::Utc>>,
+        limit: i64,
+    ) -> Result<Vec<CallEntity>, error::SystemError>;
+}
@@ -0,0 +1,182 @@
+/// In-memory `CallRepository` cho unit tests của services
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::call::{
+        model::InsertCall,
+        repository::CallRepository,
+        schema::{CallEntity, CallStatus},
+    },
+};
+
+#[derive(Default)]
+struct State {
+    /// (conversation_id, user_id) của direct conversations
+    direct_members: HashSet<(Uuid, Uuid)>,
+    /// (conversation_id, user_id) của group conversations
+    group_members: HashSet<(Uuid, Uuid)>,
+    calls: Vec<CallEntity>,
+}
+
+/// Clone dùng chung dữ liệu, test giữ một bản để thêm thành viên conversations
+#[derive(Clone, Default)]
+pub struct CallRepositoryMock {
+    state: Arc<Mutex<State>>,
+}
+
+impl CallRepositoryMock {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn add_direct(&self, conversation_id: Uuid, user_a: Uuid, user_b: Uuid) {
+        let mut state = self.state();
+        state.direct_members.insert((conversation_id, user_a));
+        state.direct_members.insert((conversation_id, user_b));
+    }
+
+    pub fn add_group_member(&self, conversation_id: Uuid, user_id: Uuid) {
+        self.state().group_members.insert((conversation_id, user_id));
+    }
+}
+
+#[async_trait::async_trait]
+impl CallRepository for CallRepositoryMock {
+    async fn are_direct_peers(
+        &self,
+        conversation_id: &Uuid,
+        caller_id: &Uuid,
+        callee_id: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        let state = self.state();
+        Ok(state.direct_members.contains(&(*conversation_id, *caller_id))
+            && state.direct_members.contains(&(*conversation_id, *callee_id)))
+    }
+
+    async fn is_group_member(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        Ok(self.state().group_members.contains(&(*conversation_id, *user_id)))
+    }
+
+    async fn find_active_by_user(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Option<CallEntity>, error::SystemError> {
+        Ok(self
+            .state()
+            .calls
+            .iter()
+            .find(|call| call.is_active() && call.peer_of(*user_id).is_some())
+            .cloned())
+    }
+
+    async fn create(&self, call: &InsertCall) -> Result<CallEntity, error::SystemError> {
+        let entity = CallEntity {
+            id: Uuid::now_v7(),
+            conversation_id: call.conversation_id,
+            caller_id: call.caller_id,
+            callee_id: call.callee_id,
+            _type: call.call_type,
+            status: CallStatus::Ringing,
+            created_at: Utc::now(),
+            answered_at: None,
+            ended_at: None,
+            ended_by: None,
+            duration_secs: None,
+        };
+        self.state().calls.push(entity.clone());
+        Ok(entity)
+    }
+
+    async fn find_by_id(&self, id: &Uuid) -> Result<Option<CallEntity>, error::SystemError> {
+        Ok(self.state().calls.iter().find(|call| call.id == *id).cloned())
+    }
+
+    async fn answer(
+        &self,
+        id: &Uuid,
+        callee_id: &Uuid,
+    ) -> Result<Option<CallEntity>, error::SystemError> {
+        let mut state = self.state();
+        let Some(call) = state.calls.iter_mut().find(|call| {
+            call.id == *id && call.callee_id == *callee_id && call.status == CallStatus::Ringing
+        }) else {
+            return Ok(None);
+        };
+        call.status = CallStatus::Ongoing;
+        call.answered_at = Some(Utc::now());
+        Ok(Some(call.clone()))
+    }
+
+    async fn end(
+        &self,
+        id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<Option<CallEntity>, error::SystemError> {
+        let mut state = self.state();
+        let Some(call) = state
+            .calls
+            .iter_mut()
+            .find(|call| call.id == *id && call.is_active() && call.peer_of(*user_id).is_some())
+        else {
+            return Ok(None);
+        };
+
+        let now = Utc::now();
+        call.status = if call.status == CallStatus::Ongoing {
+            CallStatus::Ended
+        } else if call.caller_id == *user_id {
+            CallStatus::Missed
+        } else {
+            CallStatus::Declined
+        };
+        call.duration_secs = call.answered_at.map(|at| (now - at).num_seconds() as i32);
+        call.ended_at = Some(now);
+        call.ended_by = Some(*user_id);
+        Ok(Some(call.clone()))
+    }
+
+    async fn expire_ringing(
+        &self,
+        before: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<CallEntity>, error::SystemError> {
+        let mut expired = Vec::new();
+        for call in self.state().calls.iter_mut() {
+            if call.status == CallStatus::Ringing && call.created_at < *before {
+                call.status = CallStatus::Missed;
+                call.ended_at = Some(Utc::now());
+                expired.push(call.clone());
+            }
+        }
+        Ok(expired)
+    }
+
+    async fn find_history(
+        &self,
+        user_id: &Uuid,
+        created_before: Option<chrono::DateTime<chrono::Utc>>,
+        limit: i64,
+    ) -> Result<Vec<CallEntity>, error::SystemError> {
+        let mut calls: Vec<CallEntity> = self
+            .state()
+            .calls
+            .iter()
+            .filter(|call| call.peer_of(*user_id).is_some())
+            .filter(|call| created_before.is_none_or(|before| call.created_at < before))
+            .cloned()
+            .collect();
+        calls.sort_by_key(|call| std::cmp::Reverse(call.created_at));
+        calls.truncate(limit as usize);
+        Ok(calls)
+    }
+}
@@ -0,0 +1,223 @@
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::call::{model::InsertCall, repository::CallRepository, schema::CallEntity},
+};
+
+#[derive(Clone)]
+pub struct CallRepositoryPg {
+    pool: sqlx::PgPool,
+}
+
+impl CallRepositoryPg {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl CallRepository for CallRepositoryPg {
+    async fn are_direct_peers(
+        &self,
+        conversation_id: &Uuid,
+        caller_id: &Uuid,
+        callee_id: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        let peers = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT COUNT(*) = 2
+            FROM conversations c
+            JOIN participants p ON p.conversation_id = c.id
+            WHERE c.id = $1
+            AND c.type = 'direct'
+            AND p.user_id IN ($2, $3)
+            AND p.status = 'active'
+            AND p.deleted_at IS NULL
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(caller_id)
+        .bind(callee_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(peers)
+    }
+
+    async fn is_group_member(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        let is_member = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM conversations c
+                JOIN participants p ON p.conversation_id = c.id
+                WHERE c.id = $1
+                AND c.type = 'group'
+                AND p.user_id = $2
+                AND p.status = 'active'
+                AND p.deleted_at IS NULL
+            )
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(is_member)
+    }
+
+    async fn find_active_by_user(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Option<CallEntity>, error::SystemError> {
+        let call = sqlx::query_as::<_, CallEntity>(
+            r#"
+            SELECT * FROM calls
+            WHERE (caller_id = $1 OR callee_id = $1)
+            AND status IN ('ringing', 'ongoing')
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(call)
+    }
+
+    async fn create(&self, call: &InsertCall) -> Result<CallEntity, error::SystemError> {
+        let call = sqlx::query_as::<_, CallEntity>(
+            r#"
+            INSERT INTO calls (conversation_id, caller_id, callee_id, type)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(call.conversation_id)
+        .bind(call.caller_id)
+        .bind(call.callee_id)
+        .bind(call.call_type)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(call)
+    }
+
+    async fn find_by_id(&self, id: &Uuid) -> Result<Option<CallEntity>, error::SystemError> {
+        let call = sqlx::query_as::<_, CallEntity>("SELECT * FROM calls WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(call)
+    }
+
+    async fn answer(
+        &self,
+        id: &Uuid,
+        callee_id: &Uuid,
+    ) -> Result<Option<CallEntity>, error::SystemError> {
+        let call = sqlx::query_as::<_, CallEntity>(
+            r#"
+            UPDATE calls
+            SET status = 'ongoing',
+                answered_at = NOW()
+            WHERE id = $1
+            AND callee_id = $2
+            AND status = 'ringing'
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(callee_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(call)
+    }
+
+    async fn end(
+        &self,
+        id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<Option<CallEntity>, error::SystemError> {
+        let call = sqlx::query_as::<_, CallEntity>(
+            r#"
+            UPDATE calls
+            SET status = CASE
+                    WHEN status = 'ongoing' THEN 'ended'::call_status
+                    WHEN caller_id = $2 THEN 'missed'::call_status
+                    ELSE 'declined'::call_status
+                END,
+                ended_at = NOW(),
+                ended_by = $2,
+                duration_secs = CASE
+                    WHEN answered_at IS NOT NULL
+                    THEN EXTRACT(EPOCH FROM NOW() - answered_at)::integer
+                END
+            WHERE id = $1
+            AND $2 IN (caller_id, callee_id)
+            AND status IN ('ringing', 'ongoing')
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(call)
+    }
+
+    async fn expire_ringing(
+        &self,
+        before: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<CallEntity>, error::SystemError> {
+        let calls = sqlx::query_as::<_, CallEntity>(
+            r#"
+            UPDATE calls
+            SET status = 'missed',
+                ended_at = NOW()
+            WHERE status = 'ringing'
+            AND created_at < $1
+            RETURNING *
+            "#,
+        )
+        .bind(before)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(calls)
+    }
+
+    async fn find_history(
+        &self,
+        user_id: &Uuid,
+        created_before: Option<chrono::DateTime<chrono::Utc>>,
+        limit: i64,
+    ) -> Result<Vec<CallEntity>, error::SystemError> {
+        let calls = sqlx::query_as::<_, CallEntity>(
+            r#"
+            SELECT * FROM calls
+            WHERE (caller_id = $1 OR callee_id = $1)
+            AND ($2::timestamptz IS NULL OR created_at < $2)
+            ORDER BY created_at DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(user_id)
+        .bind(created_before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(calls)
+    }
+}
@@ -0,0 +1,12 @@
+use crate::modules::call::handle::*;
+use actix_web::web::{scope, ServiceConfig};
+use utoipa::OpenApi;
+
+/// Signaling cuộc gọi đi qua WebSocket, HTTP chỉ phục vụ lịch sử
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(scope("/calls").service(get_call_history));
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(get_call_history))]
+pub struct CallApiDoc;
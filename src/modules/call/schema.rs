@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::{FromRow, Type};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, PartialEq, Clone, Copy, Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "call_type", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum CallType {
+    Audio,
+    Video,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "call_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum CallStatus {
+    /// Đang đổ chuông, callee chưa trả lời
+    Ringing,
+    /// Callee đã trả lời, cuộc gọi đang diễn ra
+    Ongoing,
+    /// Cuộc gọi đã được trả lời và kết thúc
+    Ended,
+    /// Caller hủy hoặc hết thời gian đổ chuông
+    Missed,
+    /// Callee từ chối
+    Declined,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct CallEntity {
+    pub id: Uuid,
+    pub conversation_id: Uuid,
+    pub caller_id: Uuid,
+    pub callee_id: Uuid,
+    #[sqlx(rename = "type")]
+    pub _type: CallType,
+    pub status: CallStatus,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub answered_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub ended_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub ended_by: Option<Uuid>,
+    /// Thời lượng tính từ lúc trả lời, chỉ có khi status là `ended`
+    pub duration_secs: Option<i32>,
+}
+
+impl CallEntity {
+    /// User còn lại của cuộc gọi, `None` nếu `user_id` không tham gia cuộc gọi
+    pub fn peer_of(&self, user_id: Uuid) -> Option<Uuid> {
+        if user_id == self.caller_id {
+            Some(self.callee_id)
+        } else if user_id == self.callee_id {
+            Some(self.caller_id)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        matches!(self.status, CallStatus::Ringing | CallStatus::Ongoing)
+    }
+}
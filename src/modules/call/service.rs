@@ -0,0 +1,503 @@
+/// Call Service
+///
+/// Signaling cho cuộc gọi thoại / video 1-1 (WebRTC) qua WebSocket. Server chỉ relay
+/// SDP và ICE candidates giữa hai bên, media đi peer-to-peer (hoặc qua TURN của client).
+/// Mỗi cuộc gọi được lưu trong bảng `calls` làm lịch sử:
+/// - `CallOffer` tạo cuộc gọi `ringing` và gửi `call-incoming` tới mọi session của callee
+/// - `CallAnswer` chuyển sang `ongoing`, các session khác của callee ngừng đổ chuông
+/// - `CallEnd` kết thúc với `ended` / `missed` / `declined` tùy trạng thái và người kết thúc
+/// - Cuộc gọi đổ chuông quá `RING_TIMEOUT` được worker đánh dấu `missed`
+///
+/// Cuộc gọi nhóm không lưu vào `calls`: ai đang ở trong cuộc gọi được `WebSocketServer`
+/// theo dõi (`CallRooms`), bắt đầu / kết thúc cuộc gọi được ghi thành system message.
+use std::sync::Arc;
+
+use actix::Addr;
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::{
+        call::{
+            model::{CallHistoryResponse, InsertCall},
+            repository::CallRepository,
+            schema::{CallEntity, CallType},
+        },
+        message::service::MessageService,
+        websocket::{
+            call_room::CallRoomChange,
+            events::{JoinCall, LeaveCall, SendToOtherSessions, SendToUser, SendToUsers},
+            message::ServerMessage,
+            server::WebSocketServer,
+        },
+    },
+};
+
+/// Thời gian đổ chuông tối đa trước khi cuộc gọi bị coi là nhỡ
+pub const RING_TIMEOUT: chrono::Duration = chrono::Duration::seconds(45);
+
+const DEFAULT_HISTORY_PAGE_SIZE: i64 = 20;
+
+#[derive(Clone)]
+pub struct CallService {
+    repo: Arc<dyn CallRepository + Send + Sync>,
+    ws_server: Arc<Addr<WebSocketServer>>,
+    message_service: Arc<MessageService>,
+}
+
+impl CallService {
+    pub fn with_dependencies(
+        repo: Arc<dyn CallRepository + Send + Sync>,
+        ws_server: Arc<Addr<WebSocketServer>>,
+        message_service: Arc<MessageService>,
+    ) -> Self {
+        CallService { repo, ws_server, message_service }
+    }
+
+    /// Tạo cuộc gọi và gửi SDP offer tới callee
+    pub async fn start_call(
+        &self,
+        caller_id: Uuid,
+        conversation_id: Uuid,
+        callee_id: Uuid,
+        call_type: CallType,
+        sdp: String,
+    ) -> Result<CallEntity, error::SystemError> {
+        if caller_id == callee_id {
+            return Err(error::SystemError::bad_request("Cannot call yourself"));
+        }
+
+        if !self.repo.are_direct_peers(&conversation_id, &caller_id, &callee_id).await? {
+            return Err(error::SystemError::forbidden(
+                "Calls are only available between participants of a direct conversation",
+            )
+            .with_code(error::ErrorCode::NotAMember));
+        }
+
+        if self.repo.find_active_by_user(&caller_id).await?.is_some() {
+            return Err(error::SystemError::bad_request("You are already in a call")
+                .with_code(error::ErrorCode::UserBusy));
+        }
+        if self.repo.find_active_by_user(&callee_id).await?.is_some() {
+            return Err(error::SystemError::bad_request("User is busy in another call")
+                .with_code(error::ErrorCode::UserBusy));
+        }
+
+        let call = self
+            .repo
+            .create(&InsertCall { conversation_id, caller_id, callee_id, call_type })
+            .await?;
+
+        self.ws_server.do_send(SendToUser {
+            user_id: callee_id,
+            message: ServerMessage::CallIncoming {
+                call_id: call.id,
+                conversation_id,
+                caller_id,
+                call_type,
+                sdp,
+            },
+        });
+
+        Ok(call)
+    }
+
+    /// Callee trả lời cuộc gọi từ session `session_id`
+    pub async fn answer_call(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        call_id: Uuid,
+        sdp: String,
+    ) -> Result<CallEntity, error::SystemError> {
+        let call =
+            self.repo.answer(&call_id, &user_id).await?.ok_or_else(|| {
+                error::SystemError::not_found("Call not found or no longer ringing")
+            })?;
+
+        let answered = ServerMessage::CallAnswered { call_id, answered_by: user_id, sdp };
+        self.ws_server.do_send(SendToOtherSessions {
+            user_id,
+            skip_session_id: Some(session_id),
+            message: answered.clone(),
+        });
+        self.ws_server.do_send(SendToUser { user_id: call.caller_id, message: answered });
+
+        Ok(call)
+    }
+
+    /// Relay ICE candidate tới user còn lại của cuộc gọi đang đổ chuông / diễn ra
+    pub async fn relay_ice_candidate(
+        &self,
+        user_id: Uuid,
+        call_id: Uuid,
+        candidate: serde_json::Value,
+    ) -> Result<(), error::SystemError> {
+        let not_found = || error::SystemError::not_found("Call not found");
+
+        let call = self.repo.find_by_id(&call_id).await?.ok_or_else(not_found)?;
+        let peer_id = call.peer_of(user_id).filter(|_| call.is_active()).ok_or_else(not_found)?;
+
+        self.ws_server.do_send(SendToUser {
+            user_id: peer_id,
+            message: ServerMessage::IceCandidate { call_id, from_user_id: user_id, candidate },
+        });
+
+        Ok(())
+    }
+
+    /// Hủy / từ chối / kết thúc cuộc gọi, báo cho cả hai bên
+    pub async fn end_call(
+        &self,
+        user_id: Uuid,
+        call_id: Uuid,
+    ) -> Result<CallEntity, error::SystemError> {
+        let call = self
+            .repo
+            .end(&call_id, &user_id)
+            .await?
+            .ok_or_else(|| error::SystemError::not_found("Call not found or already ended"))?;
+
+        self.notify_ended(&call);
+
+        Ok(call)
+    }
+
+    /// Tham gia cuộc gọi nhóm của group conversation từ `session_id`
+    ///
+    /// User đầu tiên join bắt đầu cuộc gọi và đăng system message thông báo
+    pub async fn join_group_call(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        conversation_id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        if !self.repo.is_group_member(&conversation_id, &user_id).await? {
+            return Err(error::SystemError::forbidden(
+                "Group calls are only available to members of a group conversation",
+            )
+            .with_code(error::ErrorCode::NotAMember));
+        }
+
+        let change = self
+            .ws_server
+            .send(JoinCall { conversation_id, user_id, session_id })
+            .await
+            .map_err(|e| error::SystemError::internal_error(e.to_string()))?;
+
+        if change == Some(CallRoomChange::Started) {
+            self.post_system_message(user_id, conversation_id, "Call started").await;
+        }
+
+        Ok(())
+    }
+
+    /// Rời cuộc gọi nhóm, người cuối cùng rời đi kết thúc cuộc gọi
+    ///
+    /// Không kiểm tra membership để user vừa bị xóa khỏi group vẫn rời được cuộc gọi
+    pub async fn leave_group_call(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        conversation_id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        let change = self
+            .ws_server
+            .send(LeaveCall { conversation_id, user_id, session_id })
+            .await
+            .map_err(|e| error::SystemError::internal_error(e.to_string()))?;
+
+        if let Some(CallRoomChange::Ended { participant_count, duration }) = change {
+            let secs = duration.num_seconds().max(0);
+            let content = format!(
+                "Call ended · {} participant{} · {}:{:02}",
+                participant_count,
+                if participant_count == 1 { "" } else { "s" },
+                secs / 60,
+                secs % 60
+            );
+            self.post_system_message(user_id, conversation_id, &content).await;
+        }
+
+        Ok(())
+    }
+
+    /// Đánh dấu `missed` các cuộc gọi đổ chuông quá `RING_TIMEOUT`
+    pub async fn expire_unanswered_calls(&self) -> Result<usize, error::SystemError> {
+        let calls = self.repo.expire_ringing(&(chrono::Utc::now() - RING_TIMEOUT)).await?;
+
+        for call in &calls {
+            self.notify_ended(call);
+        }
+
+        Ok(calls.len())
+    }
+
+    /// Lịch sử cuộc gọi của user, mới nhất trước, phân trang theo cursor `created_at`
+    pub async fn get_history(
+        &self,
+        user_id: Uuid,
+        limit: Option<i64>,
+        cursor: Option<String>,
+    ) -> Result<CallHistoryResponse, error::SystemError> {
+        let created_before = match cursor {
+            Some(c) => Some(
+                chrono::DateTime::parse_from_rfc3339(&c)
+                    .map_err(|_| error::SystemError::bad_request("Invalid cursor format"))?
+                    .with_timezone(&chrono::Utc),
+            ),
+            None => None,
+        };
+        let limit = limit.unwrap_or(DEFAULT_HISTORY_PAGE_SIZE);
+
+        // Lấy thừa 1 để biết còn trang sau
+        let mut calls = self.repo.find_history(&user_id, created_before, limit + 1).await?;
+
+        let has_more = calls.len() > limit as usize;
+        calls.truncate(limit as usize);
+        let cursor = if has_more { calls.last().map(|c| c.created_at.to_rfc3339()) } else { None };
+
+        Ok(CallHistoryResponse { calls, cursor })
+    }
+
+    /// System message chỉ là thông báo, lỗi không làm hỏng việc join / leave đã ghi nhận
+    async fn post_system_message(&self, user_id: Uuid, conversation_id: Uuid, content: &str) {
+        if let Err(e) =
+            self.message_service.send_system_message(user_id, conversation_id, content).await
+        {
+            tracing::error!(
+                "Failed to post call message to conversation {}: {:?}",
+                conversation_id,
+                e
+            );
+        }
+    }
+
+    fn notify_ended(&self, call: &CallEntity) {
+        self.ws_server.do_send(SendToUsers {
+            user_ids: vec![call.caller_id, call.callee_id],
+            message: ServerMessage::CallEnded {
+                call_id: call.id,
+                status: call.status,
+                ended_by: call.ended_by,
+                duration_secs: call.duration_secs,
+            },
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix::Actor;
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+    use super::*;
+    use crate::{
+        configs::{cache::MemoryCache, uow::MemoryUnitOfWork},
+        constants::init_test_env,
+        modules::{
+            call::{repository_mock::CallRepositoryMock, schema::CallStatus},
+            conversation::repository_mock::ConversationRepositoryMock,
+            message::{
+                command::CommandRegistry,
+                repository_mock::MessageRepositoryMock,
+                service::{DirectMessagePolicy, MessageServiceDeps},
+            },
+            notification::queue::PushQueue,
+            report::{moderation::ContentFilter, repository_pg::ReportRepositoryPg},
+            user::repository_mock::UserRepositoryMock,
+            webhook::delivery::WebhookQueue,
+            websocket::{
+                dispatcher::EventOutbox,
+                message::{SenderInfo, SenderResolver},
+            },
+        },
+    };
+
+    struct UnknownSenders;
+
+    #[async_trait::async_trait]
+    impl SenderResolver for UnknownSenders {
+        async fn resolve_sender(&self, user_id: Uuid) -> SenderInfo {
+            SenderInfo::unknown(user_id)
+        }
+    }
+
+    struct AllowDirect;
+
+    #[async_trait::async_trait]
+    impl DirectMessagePolicy for AllowDirect {
+        async fn check_direct_message(&self, _: Uuid, _: Uuid) -> Result<(), error::SystemError> {
+            Ok(())
+        }
+    }
+
+    fn service(repo: &CallRepositoryMock) -> CallService {
+        init_test_env();
+        let conversations = ConversationRepositoryMock::new(UserRepositoryMock::default());
+        let messages = MessageRepositoryMock::new(conversations.clone());
+        // Report repository chỉ được dùng khi nội dung bị flag, pool không bao giờ kết nối
+        let reports = ReportRepositoryPg::new(
+            PgPoolOptions::new().connect_lazy_with(PgConnectOptions::new()),
+        );
+        let ws_server = Arc::new(WebSocketServer::new().start());
+
+        let message_service = MessageService::with_dependencies(MessageServiceDeps {
+            conversation_repo: Arc::new(conversations.clone()),
+            message_repo: Arc::new(messages),
+            participant_repo: Arc::new(conversations.clone()),
+            last_message_repo: Arc::new(conversations),
+            uow: Arc::new(MemoryUnitOfWork),
+            cache: Arc::new(MemoryCache::default()),
+            ws_server: ws_server.clone(),
+            push_queue: PushQueue::new().0,
+            events: EventOutbox::new(),
+            webhooks: WebhookQueue::new(),
+            moderation: ContentFilter::from_env(Arc::new(reports)),
+            commands: Arc::new(CommandRegistry::with_builtin()),
+            senders: Arc::new(UnknownSenders),
+            direct_policy: Arc::new(AllowDirect),
+            translator: None,
+        });
+        CallService::with_dependencies(Arc::new(repo.clone()), ws_server, Arc::new(message_service))
+    }
+
+    /// Direct conversation giữa hai user mới
+    fn direct(repo: &CallRepositoryMock) -> (Uuid, Uuid, Uuid) {
+        let (conversation_id, caller_id, callee_id) =
+            (Uuid::now_v7(), Uuid::now_v7(), Uuid::now_v7());
+        repo.add_direct(conversation_id, caller_id, callee_id);
+        (conversation_id, caller_id, callee_id)
+    }
+
+    async fn start(service: &CallService, conversation_id: Uuid, caller: Uuid, callee: Uuid) {
+        service
+            .start_call(caller, conversation_id, callee, CallType::Audio, "offer".to_string())
+            .await
+            .unwrap();
+    }
+
+    #[actix_web::test]
+    async fn answered_call_ends_and_shows_in_history_of_both_users() {
+        let repo = CallRepositoryMock::default();
+        let service = service(&repo);
+        let (conversation_id, caller_id, callee_id) = direct(&repo);
+
+        let call = service
+            .start_call(caller_id, conversation_id, callee_id, CallType::Video, "offer".into())
+            .await
+            .unwrap();
+        assert_eq!(call.status, CallStatus::Ringing);
+
+        let answered =
+            service.answer_call(callee_id, Uuid::now_v7(), call.id, "answer".into()).await.unwrap();
+        assert_eq!(answered.status, CallStatus::Ongoing);
+        service.relay_ice_candidate(caller_id, call.id, serde_json::json!({})).await.unwrap();
+
+        let ended = service.end_call(caller_id, call.id).await.unwrap();
+        assert_eq!(ended.status, CallStatus::Ended);
+        assert_eq!(ended.ended_by, Some(caller_id));
+        assert!(ended.duration_secs.is_some());
+
+        for user_id in [caller_id, callee_id] {
+            let history = service.get_history(user_id, None, None).await.unwrap();
+            assert_eq!(history.calls.len(), 1);
+            assert_eq!(history.calls[0].id, call.id);
+        }
+    }
+
+    #[actix_web::test]
+    async fn unanswered_call_is_missed_when_cancelled_and_declined_by_callee() {
+        let repo = CallRepositoryMock::default();
+        let service = service(&repo);
+        let (conversation_id, caller_id, callee_id) = direct(&repo);
+
+        start(&service, conversation_id, caller_id, callee_id).await;
+        let active = repo.find_active_by_user(&caller_id).await.unwrap().unwrap();
+        let cancelled = service.end_call(caller_id, active.id).await.unwrap();
+        assert_eq!(cancelled.status, CallStatus::Missed);
+        assert!(cancelled.duration_secs.is_none());
+
+        start(&service, conversation_id, caller_id, callee_id).await;
+        let active = repo.find_active_by_user(&callee_id).await.unwrap().unwrap();
+        let declined = service.end_call(callee_id, active.id).await.unwrap();
+        assert_eq!(declined.status, CallStatus::Declined);
+    }
+
+    #[actix_web::test]
+    async fn only_conversation_members_can_call() {
+        let repo = CallRepositoryMock::default();
+        let service = service(&repo);
+        let (conversation_id, caller_id, _) = direct(&repo);
+        let stranger = Uuid::now_v7();
+
+        let err = service
+            .start_call(caller_id, conversation_id, stranger, CallType::Audio, "offer".into())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotAMember);
+
+        let err = service
+            .start_call(stranger, conversation_id, caller_id, CallType::Audio, "offer".into())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotAMember);
+
+        let group_id = Uuid::now_v7();
+        repo.add_group_member(group_id, caller_id);
+        service.join_group_call(caller_id, Uuid::now_v7(), group_id).await.unwrap();
+        let err = service.join_group_call(stranger, Uuid::now_v7(), group_id).await;
+        assert_eq!(err.unwrap_err().code(), error::ErrorCode::NotAMember);
+    }
+
+    #[actix_web::test]
+    async fn outsiders_cannot_answer_relay_or_end_a_call() {
+        let repo = CallRepositoryMock::default();
+        let service = service(&repo);
+        let (conversation_id, caller_id, callee_id) = direct(&repo);
+        start(&service, conversation_id, caller_id, callee_id).await;
+        let call = repo.find_active_by_user(&caller_id).await.unwrap().unwrap();
+        let stranger = Uuid::now_v7();
+
+        // Caller không tự trả lời cuộc gọi của mình
+        let err = service
+            .answer_call(caller_id, Uuid::now_v7(), call.id, "sdp".into())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotFound);
+        let err =
+            service.answer_call(stranger, Uuid::now_v7(), call.id, "sdp".into()).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotFound);
+        let err = service
+            .relay_ice_candidate(stranger, call.id, serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotFound);
+        let err = service.end_call(stranger, call.id).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotFound);
+
+        assert!(repo.find_by_id(&call.id).await.unwrap().unwrap().is_active());
+    }
+
+    #[actix_web::test]
+    async fn busy_users_cannot_start_or_receive_another_call() {
+        let repo = CallRepositoryMock::default();
+        let service = service(&repo);
+        let (conversation_id, caller_id, callee_id) = direct(&repo);
+        start(&service, conversation_id, caller_id, callee_id).await;
+
+        let (other_conversation, third) = (Uuid::now_v7(), Uuid::now_v7());
+        repo.add_direct(other_conversation, third, callee_id);
+        let err = service
+            .start_call(third, other_conversation, callee_id, CallType::Audio, "offer".into())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::UserBusy);
+
+        let err = service
+            .start_call(callee_id, other_conversation, third, CallType::Audio, "offer".into())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::UserBusy);
+    }
+}
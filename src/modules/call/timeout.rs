@@ -0,0 +1,25 @@
+/// Call Ring Timeout
+///
+/// Task chạy nền định kỳ đánh dấu `missed` các cuộc gọi đổ chuông quá `RING_TIMEOUT` mà
+/// không được trả lời / hủy, rồi gửi `call-ended` cho cả hai bên. Update có điều kiện
+/// `status = 'ringing'` nên chạy trùng trên nhiều instance cũng không gửi event hai lần.
+use std::time::Duration;
+
+use crate::modules::call::service::CallService;
+
+/// Khoảng thời gian giữa hai lần quét
+const SWEEP_INTERVAL: Duration = Duration::from_secs(10);
+
+pub async fn run_call_timeout_worker(service: CallService) {
+    let mut interval = actix_web::rt::time::interval(SWEEP_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match service.expire_unanswered_calls().await {
+            Ok(0) => {}
+            Ok(expired) => tracing::info!("Marked {} unanswered calls as missed", expired),
+            Err(e) => tracing::error!("Failed to expire unanswered calls: {:?}", e),
+        }
+    }
+}
@@ -1,65 +1,246 @@
-use actix_web::{get, post, web, HttpRequest};
+use actix_web::{delete, get, patch, post, put, web, HttpRequest, HttpResponse};
+use futures_util::{future, stream, Stream, StreamExt, TryStreamExt};
 use uuid::Uuid;
 
 use crate::{
     api::{error, success},
     middlewares::get_extensions,
     modules::{
+        announcement::service::AnnouncementService,
         conversation::{
-            model::{ConversationDetail, MessageQueryRequest, NewConversation},
-            repository_pg::{ConversationPgRepository, ParticipantPgRepository},
-            service::ConversationService,
+            model::{
+                AddMembersModel, AddMembersResponse, ArchiveConversationModel, ConversationDetail,
+                ConversationInvite, ConversationListQuery, ConversationListResponse, ExportChunk,
+                ExportFormat, ExportQuery, GroupInfo, MessageAroundQuery, MessageQueryRequest,
+                MuteConversationModel, NewConversation, PinConversationModel, SaveDraftModel,
+                UnreadReconcileReport, UpdateConversationDefaults, UpdateConversationSettings,
+                UpdateDuplicatePolicy, UpdateGroupModel, UpdateNotificationLevelModel,
+            },
+            reconcile,
+            schema::{ConversationDefaultsEntity, ConversationEntity, DraftEntity},
+            service::{ConversationService, ExportStream},
         },
-        message::{model::GetMessageResponse, repository_pg::MessageRepositoryPg},
+        message::model::{ExportedMessage, GetMessageResponse, MessagesAroundResponse},
     },
     utils::{Claims, ValidatedJson, ValidatedQuery},
 };
 
-pub type ConversationSvc =
-    ConversationService<ConversationPgRepository, ParticipantPgRepository, MessageRepositoryPg>;
+/// Header chứa WebSocket session ID (từ `auth-success`) của thiết bị gửi request,
+/// để không gửi lại event đồng bộ cho chính thiết bị đó
+const SESSION_ID_HEADER: &str = "X-Session-Id";
 
+fn session_id(req: &HttpRequest) -> Option<Uuid> {
+    req.headers()
+        .get(SESSION_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| Uuid::parse_str(value).ok())
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    params(ConversationListQuery),
+    responses((status = 200, body = success::SuccessData<ConversationListResponse>))
+)]
 #[get("")]
 pub async fn get_conversations(
-    conversation_svc: web::Data<ConversationSvc>,
+    conversation_svc: web::Data<ConversationService>,
+    announcement_svc: web::Data<AnnouncementService>,
+    ValidatedQuery(query): ValidatedQuery<ConversationListQuery>,
     req: HttpRequest,
-) -> Result<success::Success<Vec<ConversationDetail>>, error::Error> {
+) -> Result<success::Success<ConversationListResponse>, error::Error> {
     let user_id = get_extensions::<Claims>(&req)?.sub;
+    let first_page = query.cursor.is_none();
 
-    let conversations = conversation_svc.get_by_user_id(user_id).await?;
+    let (conversations, page) =
+        conversation_svc.get_by_user_id(user_id, query.archived, query.limit, query.cursor).await?;
+    let announcements =
+        if first_page { announcement_svc.get_unacknowledged(user_id).await? } else { Vec::new() };
 
-    Ok(success::Success::ok(Some(conversations)).message("Successfully retrieved conversations"))
+    let cursor = page.next_cursor.clone();
+    let res = ConversationListResponse { conversations, announcements, cursor, page };
+    Ok(success::Success::ok(Some(res)).message("Successfully retrieved conversations"))
 }
 
+#[utoipa::path(
+    tag = "conversations",
+    params(MessageQueryRequest),
+    responses(
+        (status = 200, body = success::SuccessData<GetMessageResponse>),
+        (
+            status = 403,
+            description = "Không phải thành viên của conversation",
+            body = error::ErrorBody
+        )
+    )
+)]
 #[get("/{conversation_id}/messages")]
 pub async fn get_messages(
-    conversation_svc: web::Data<ConversationSvc>,
+    conversation_svc: web::Data<ConversationService>,
     conversation_id: web::Path<Uuid>,
     ValidatedQuery(query): ValidatedQuery<MessageQueryRequest>,
+    req: HttpRequest,
 ) -> Result<success::Success<GetMessageResponse>, error::Error> {
-    let (messages, cursor) =
-        conversation_svc.get_message(*conversation_id, query.limit, query.cursor.clone()).await?;
-    Ok(success::Success::ok(Some(GetMessageResponse { messages, cursor }))
-        .message("Successfully retrieved messages"))
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let messages = conversation_svc
+        .get_message(*conversation_id, user_id, query.limit, query.cursor.clone(), query.direction)
+        .await?;
+    Ok(success::Success::ok(Some(messages)).message("Successfully retrieved messages"))
 }
 
+/// Số messages export mặc định mỗi request
+const DEFAULT_EXPORT_LIMIT: i32 = 1000;
+
+#[utoipa::path(
+    tag = "conversations",
+    params(ExportQuery),
+    responses(
+        (
+            status = 200,
+            description = "Messages (kèm metadata file đính kèm) dạng JSON hoặc NDJSON, \
+                           stream bằng chunked transfer encoding",
+            body = [ExportedMessage]
+        ),
+        (
+            status = 403,
+            description = "Không phải thành viên của conversation",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[get("/{conversation_id}/export")]
+pub async fn export_messages(
+    conversation_svc: web::Data<ConversationService>,
+    conversation_id: web::Path<Uuid>,
+    ValidatedQuery(query): ValidatedQuery<ExportQuery>,
+    req: HttpRequest,
+) -> Result<HttpResponse, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let limit = query.limit.unwrap_or(DEFAULT_EXPORT_LIMIT) as usize;
+
+    let chunks =
+        conversation_svc.export_messages(*conversation_id, user_id, query.cursor, limit).await?;
+
+    let content_type = match query.format {
+        ExportFormat::Json => "application/json",
+        ExportFormat::Ndjson => "application/x-ndjson",
+    };
+    let body = encode_export(query.format, *conversation_id, chunks);
+    Ok(HttpResponse::Ok().content_type(content_type).streaming(body))
+}
+
+/// Encode các phần của bản export thành body; chunk rỗng bị bỏ để không kết thúc
+/// chunked body sớm
+fn encode_export(
+    format: ExportFormat,
+    conversation_id: Uuid,
+    chunks: ExportStream,
+) -> impl Stream<Item = Result<web::Bytes, error::SystemError>> {
+    let prefix = match format {
+        ExportFormat::Json => format!(r#"{{"conversation_id":"{conversation_id}","messages":["#),
+        ExportFormat::Ndjson => String::new(),
+    };
+    let mut first = true;
+
+    let body = chunks.map(move |chunk| {
+        let mut buf = Vec::new();
+        let to_json = |e: serde_json::Error| error::SystemError::internal_error(e.to_string());
+
+        match (format, chunk?) {
+            (ExportFormat::Json, ExportChunk::Messages(messages)) => {
+                for message in messages {
+                    if !first {
+                        buf.push(b',');
+                    }
+                    first = false;
+                    serde_json::to_writer(&mut buf, &message).map_err(to_json)?;
+                }
+            }
+            (ExportFormat::Json, ExportChunk::End { next_cursor }) => {
+                buf.extend_from_slice(br#"],"next_cursor":"#);
+                serde_json::to_writer(&mut buf, &next_cursor).map_err(to_json)?;
+                buf.push(b'}');
+            }
+            (ExportFormat::Ndjson, ExportChunk::Messages(messages)) => {
+                for message in messages {
+                    serde_json::to_writer(&mut buf, &message).map_err(to_json)?;
+                    buf.push(b'\n');
+                }
+            }
+            (ExportFormat::Ndjson, ExportChunk::End { .. }) => {}
+        }
+
+        Ok(web::Bytes::from(buf))
+    });
+
+    stream::once(future::ready(Ok(web::Bytes::from(prefix))))
+        .chain(body)
+        .try_filter(|bytes| future::ready(!bytes.is_empty()))
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    params(MessageAroundQuery),
+    responses(
+        (status = 200, body = success::SuccessData<MessagesAroundResponse>),
+        (
+            status = 404,
+            description = "Message không thuộc conversation hoặc không nhìn thấy được",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[get("/{conversation_id}/messages/around/{message_id}")]
+pub async fn get_messages_around(
+    conversation_svc: web::Data<ConversationService>,
+    path: web::Path<(Uuid, Uuid)>,
+    ValidatedQuery(query): ValidatedQuery<MessageAroundQuery>,
+    req: HttpRequest,
+) -> Result<success::Success<MessagesAroundResponse>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let (conversation_id, message_id) = path.into_inner();
+    let response = conversation_svc
+        .get_messages_around(conversation_id, user_id, message_id, query.limit.unwrap_or(20))
+        .await?;
+    Ok(success::Success::ok(Some(response)).message("Successfully retrieved messages"))
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    request_body = NewConversation,
+    responses(
+        (status = 200, body = success::SuccessData<ConversationDetail>),
+        (status = 403, description = "Có member chưa là bạn bè", body = error::ErrorBody)
+    )
+)]
 #[post("")]
 pub async fn create_conversation(
-    conversation_svc: web::Data<ConversationSvc>,
+    conversation_svc: web::Data<ConversationService>,
     ValidatedJson(body): ValidatedJson<NewConversation>,
     req: HttpRequest,
 ) -> Result<success::Success<Option<ConversationDetail>>, error::Error> {
-    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let claims = get_extensions::<Claims>(&req)?;
 
     let conversation = conversation_svc
-        .create_conversation(body._type, body.name, body.member_ids, user_id)
+        .create_conversation(body._type, body.name, body.member_ids, claims.sub, &claims.role)
         .await?;
 
     Ok(success::Success::ok(Some(conversation)).message("Successfully created conversation"))
 }
 
+#[utoipa::path(
+    tag = "conversations",
+    responses(
+        (status = 200, body = success::SuccessData<String>),
+        (
+            status = 403,
+            description = "Không phải thành viên của conversation",
+            body = error::ErrorBody
+        )
+    )
+)]
 #[post("/{conversation_id}/mark-as-seen")]
 pub async fn mark_as_seen(
-    conversation_svc: web::Data<ConversationSvc>,
+    conversation_svc: web::Data<ConversationService>,
     conversation_id: web::Path<Uuid>,
     req: HttpRequest,
 ) -> Result<success::Success<String>, error::Error> {
@@ -70,3 +251,425 @@ pub async fn mark_as_seen(
     Ok(success::Success::ok(Some("Messages marked as seen".to_string()))
         .message("Successfully marked messages as seen"))
 }
+
+#[utoipa::path(
+    tag = "conversations",
+    request_body = UpdateDuplicatePolicy,
+    responses(
+        (status = 200, body = success::MessageOnly),
+        (
+            status = 403,
+            description = "Không phải thành viên hoặc admin của group",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[put("/{conversation_id}/duplicate-policy")]
+pub async fn update_duplicate_policy(
+    conversation_svc: web::Data<ConversationService>,
+    conversation_id: web::Path<Uuid>,
+    body: web::Json<UpdateDuplicatePolicy>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let claims = get_extensions::<Claims>(&req)?;
+
+    conversation_svc
+        .update_duplicate_policy(*conversation_id, claims.sub, &claims.role, body.policy)
+        .await?;
+
+    Ok(success::Success::ok(None).message("Successfully updated duplicate message policy"))
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    request_body = UpdateConversationSettings,
+    responses(
+        (status = 200, body = success::SuccessData<ConversationEntity>),
+        (
+            status = 403,
+            description = "Không phải admin của group hoặc policy không cho phép override",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[put("/{conversation_id}/settings")]
+pub async fn update_conversation_settings(
+    conversation_svc: web::Data<ConversationService>,
+    conversation_id: web::Path<Uuid>,
+    ValidatedJson(body): ValidatedJson<UpdateConversationSettings>,
+    req: HttpRequest,
+) -> Result<success::Success<ConversationEntity>, error::Error> {
+    let claims = get_extensions::<Claims>(&req)?;
+
+    let conversation =
+        conversation_svc.update_settings(*conversation_id, claims.sub, &claims.role, body).await?;
+
+    Ok(success::Success::ok(Some(conversation))
+        .message("Successfully updated conversation settings"))
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    request_body = MuteConversationModel,
+    responses(
+        (status = 200, body = success::MessageOnly),
+        (
+            status = 403,
+            description = "Không phải thành viên của conversation",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[put("/{conversation_id}/mute")]
+pub async fn mute_conversation(
+    conversation_svc: web::Data<ConversationService>,
+    conversation_id: web::Path<Uuid>,
+    ValidatedJson(body): ValidatedJson<MuteConversationModel>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+
+    conversation_svc.mute_conversation(*conversation_id, user_id, body.muted_until).await?;
+
+    Ok(success::Success::ok(None).message("Successfully updated notification settings"))
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    responses(
+        (status = 204, description = "Conversation đã bị xóa phía user hiện tại"),
+        (
+            status = 403,
+            description = "Không phải thành viên của conversation",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[delete("/{conversation_id}")]
+pub async fn delete_conversation(
+    conversation_svc: web::Data<ConversationService>,
+    conversation_id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+
+    conversation_svc.delete_for_user(*conversation_id, user_id).await?;
+
+    Ok(success::Success::no_content())
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    request_body = UpdateGroupModel,
+    responses(
+        (status = 200, body = success::SuccessData<GroupInfo>),
+        (status = 400, description = "Không phải group hoặc dữ liệu không hợp lệ", body = error::ErrorBody),
+        (status = 403, description = "Không phải admin của group", body = error::ErrorBody)
+    )
+)]
+#[patch("/{conversation_id}")]
+pub async fn update_group(
+    conversation_svc: web::Data<ConversationService>,
+    conversation_id: web::Path<Uuid>,
+    ValidatedJson(body): ValidatedJson<UpdateGroupModel>,
+    req: HttpRequest,
+) -> Result<success::Success<GroupInfo>, error::Error> {
+    let claims = get_extensions::<Claims>(&req)?;
+
+    let group =
+        conversation_svc.update_group(*conversation_id, claims.sub, &claims.role, body).await?;
+
+    Ok(success::Success::ok(Some(group)).message("Successfully updated group"))
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    params(
+        (
+            "X-Session-Id" = Option<Uuid>,
+            Header,
+            description = "WebSocket session của thiết bị hiện tại (không nhận lại event)"
+        )
+    ),
+    request_body = ArchiveConversationModel,
+    responses(
+        (status = 200, body = success::MessageOnly),
+        (
+            status = 403,
+            description = "Không phải thành viên của conversation",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[put("/{conversation_id}/archive")]
+pub async fn archive_conversation(
+    conversation_svc: web::Data<ConversationService>,
+    conversation_id: web::Path<Uuid>,
+    ValidatedJson(body): ValidatedJson<ArchiveConversationModel>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+
+    conversation_svc
+        .archive_conversation(*conversation_id, user_id, body.archived, session_id(&req))
+        .await?;
+
+    Ok(success::Success::ok(None).message("Successfully updated archive state"))
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    params(
+        (
+            "X-Session-Id" = Option<Uuid>,
+            Header,
+            description = "WebSocket session của thiết bị hiện tại (không nhận lại event)"
+        )
+    ),
+    request_body = PinConversationModel,
+    responses(
+        (status = 200, body = success::MessageOnly),
+        (
+            status = 403,
+            description = "Không phải thành viên của conversation",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[put("/{conversation_id}/pin")]
+pub async fn pin_conversation(
+    conversation_svc: web::Data<ConversationService>,
+    conversation_id: web::Path<Uuid>,
+    ValidatedJson(body): ValidatedJson<PinConversationModel>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+
+    conversation_svc
+        .pin_conversation(*conversation_id, user_id, body.pinned, session_id(&req))
+        .await?;
+
+    Ok(success::Success::ok(None).message("Successfully updated pin state"))
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    params(
+        (
+            "X-Session-Id" = Option<Uuid>,
+            Header,
+            description = "WebSocket session của thiết bị hiện tại (không nhận lại event)"
+        )
+    ),
+    request_body = UpdateNotificationLevelModel,
+    responses(
+        (status = 200, body = success::MessageOnly),
+        (
+            status = 403,
+            description = "Không phải thành viên của conversation",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[patch("/{conversation_id}/notification-level")]
+pub async fn update_notification_level(
+    conversation_svc: web::Data<ConversationService>,
+    conversation_id: web::Path<Uuid>,
+    ValidatedJson(body): ValidatedJson<UpdateNotificationLevelModel>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+
+    conversation_svc
+        .update_notification_level(*conversation_id, user_id, body.level, session_id(&req))
+        .await?;
+
+    Ok(success::Success::ok(None).message("Successfully updated notification settings"))
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    responses(
+        (
+            status = 200,
+            description = "`data` là null nếu chưa có bản nháp",
+            body = success::SuccessData<DraftEntity>
+        ),
+        (
+            status = 403,
+            description = "Không phải thành viên của conversation",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[get("/{conversation_id}/draft")]
+pub async fn get_draft(
+    conversation_svc: web::Data<ConversationService>,
+    conversation_id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> Result<success::Success<DraftEntity>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+
+    let draft = conversation_svc.get_draft(*conversation_id, user_id).await?;
+
+    Ok(success::Success::ok(draft).message("Successfully retrieved draft"))
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    params(
+        (
+            "X-Session-Id" = Option<Uuid>,
+            Header,
+            description = "WebSocket session của thiết bị hiện tại (không nhận lại `draft-updated`)"
+        )
+    ),
+    request_body = SaveDraftModel,
+    responses(
+        (
+            status = 200,
+            description = "`data` là null nếu bản nháp đã bị xóa",
+            body = success::SuccessData<DraftEntity>
+        ),
+        (
+            status = 403,
+            description = "Không phải thành viên của conversation",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[put("/{conversation_id}/draft")]
+pub async fn save_draft(
+    conversation_svc: web::Data<ConversationService>,
+    conversation_id: web::Path<Uuid>,
+    ValidatedJson(body): ValidatedJson<SaveDraftModel>,
+    req: HttpRequest,
+) -> Result<success::Success<DraftEntity>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let session_id = session_id(&req);
+
+    let draft =
+        conversation_svc.save_draft(*conversation_id, user_id, body.content, session_id).await?;
+
+    Ok(success::Success::ok(draft).message("Successfully saved draft"))
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    request_body = AddMembersModel,
+    responses(
+        (status = 200, body = success::SuccessData<AddMembersResponse>),
+        (
+            status = 403,
+            description = "Không phải thành viên của conversation",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[post("/{conversation_id}/members")]
+pub async fn add_members(
+    conversation_svc: web::Data<ConversationService>,
+    conversation_id: web::Path<Uuid>,
+    ValidatedJson(body): ValidatedJson<AddMembersModel>,
+    req: HttpRequest,
+) -> Result<success::Success<AddMembersResponse>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+
+    let result = conversation_svc.add_members(*conversation_id, user_id, body.member_ids).await?;
+
+    Ok(success::Success::ok(Some(result)).message("Successfully added members"))
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    responses((status = 200, body = success::SuccessData<Vec<ConversationInvite>>))
+)]
+#[get("/invites")]
+pub async fn get_invites(
+    conversation_svc: web::Data<ConversationService>,
+    req: HttpRequest,
+) -> Result<success::Success<Vec<ConversationInvite>>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+
+    let invites = conversation_svc.get_invites(user_id).await?;
+
+    Ok(success::Success::ok(Some(invites)).message("Successfully retrieved invites"))
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    responses(
+        (status = 200, body = success::SuccessData<ConversationDetail>),
+        (status = 404, description = "Không có lời mời", body = error::ErrorBody)
+    )
+)]
+#[post("/invites/{conversation_id}")]
+pub async fn accept_invite(
+    conversation_svc: web::Data<ConversationService>,
+    conversation_id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> Result<success::Success<Option<ConversationDetail>>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+
+    let conversation = conversation_svc.accept_invite(*conversation_id, user_id).await?;
+
+    Ok(success::Success::ok(Some(conversation)).message("Successfully accepted invite"))
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    responses(
+        (status = 200, body = success::MessageOnly),
+        (status = 404, description = "Không có lời mời", body = error::ErrorBody)
+    )
+)]
+#[delete("/invites/{conversation_id}")]
+pub async fn decline_invite(
+    conversation_svc: web::Data<ConversationService>,
+    conversation_id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+
+    conversation_svc.decline_invite(*conversation_id, user_id).await?;
+
+    Ok(success::Success::ok(None).message("Successfully declined invite"))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    responses((status = 200, body = success::SuccessData<ConversationDefaultsEntity>))
+)]
+#[get("")]
+pub async fn get_conversation_defaults(
+    conversation_svc: web::Data<ConversationService>,
+) -> Result<success::Success<ConversationDefaultsEntity>, error::Error> {
+    let defaults = conversation_svc.get_defaults().await?;
+    Ok(success::Success::ok(Some(defaults)).message("Successfully retrieved conversation defaults"))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    request_body = UpdateConversationDefaults,
+    responses((status = 200, body = success::SuccessData<ConversationDefaultsEntity>))
+)]
+#[put("")]
+pub async fn update_conversation_defaults(
+    conversation_svc: web::Data<ConversationService>,
+    ValidatedJson(body): ValidatedJson<UpdateConversationDefaults>,
+) -> Result<success::Success<ConversationDefaultsEntity>, error::Error> {
+    let defaults = conversation_svc.update_defaults(body).await?;
+    Ok(success::Success::ok(Some(defaults)).message("Successfully updated conversation defaults"))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    responses((status = 200, body = success::SuccessData<UnreadReconcileReport>))
+)]
+#[post("/reconcile")]
+pub async fn reconcile_unread_counts(
+    conversation_svc: web::Data<ConversationService>,
+) -> Result<success::Success<UnreadReconcileReport>, error::Error> {
+    let report = conversation_svc.reconcile_unread_counts(reconcile::BATCH_SIZE).await?;
+    Ok(success::Success::ok(Some(report)).message("Successfully reconciled unread counts"))
+}
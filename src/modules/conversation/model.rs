@@ -1,13 +1,21 @@
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
-use crate::modules::conversation::schema::ConversationType;
+use crate::api::pagination::PageInfo;
+use crate::modules::announcement::schema::AnnouncementEntity;
+use crate::modules::conversation::schema::{
+    ConversationType, DuplicatePolicy, GroupCreationPolicy, HistoryVisibility, NotificationLevel,
+};
+use crate::modules::message::model::{CursorDirection, ExportedMessage};
+use crate::utils::double_option;
 
-#[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize, ToSchema)]
 pub struct GroupInfo {
     pub name: String,
+    pub description: Option<String>,
     pub created_by: Uuid,
     pub avatar_url: Option<String>,
 }
@@ -21,24 +29,38 @@ pub struct ConversationRaw {
     pub updated_at: chrono::DateTime<chrono::Utc>,
 
     pub group_name: Option<String>,
+    pub group_description: Option<String>,
     pub group_created_by: Option<Uuid>,
     pub group_avatar_url: Option<String>,
 
     pub last_content: Option<String>,
     pub last_sender_id: Option<Uuid>,
     pub last_created_at: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Flags của user đang xem (không có khi lấy detail chung của conversation)
+    #[sqlx(default)]
+    pub is_archived: bool,
+    #[sqlx(default)]
+    pub is_pinned: bool,
+    #[sqlx(default)]
+    pub pinned_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// `json_agg` participants dạng text (sqlx không bật feature json), chỉ có ở danh sách
+    #[sqlx(default)]
+    pub participants_json: Option<String>,
 }
 
-#[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize, ToSchema)]
 pub struct ParticipantRow {
     pub user_id: Uuid,
     pub display_name: String,
     pub avatar_url: Option<String>,
     pub unread_count: i32,
     pub joined_at: chrono::DateTime<chrono::Utc>,
+    pub last_delivered_message_id: Option<Uuid>,
+    pub last_seen_message_id: Option<Uuid>,
 }
 
-#[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize, ToSchema)]
 pub struct LastMessageRow {
     pub content: Option<String>,
     pub sender_id: Uuid,
@@ -52,11 +74,22 @@ pub struct ConversationRow {
     pub _type: ConversationType,
     pub group_info: Option<GroupInfo>,
     pub last_message: Option<LastMessageRow>,
+    pub participants: Vec<ParticipantRow>,
+    pub is_archived: bool,
+    pub is_pinned: bool,
+    pub pinned_at: Option<chrono::DateTime<chrono::Utc>>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
+impl ConversationRow {
+    /// Thời điểm hoạt động gần nhất, dùng để sắp xếp danh sách conversations
+    pub fn activity_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.last_message.as_ref().map_or(self.updated_at, |m| m.created_at)
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize, ToSchema)]
 
 pub struct ConversationDetail {
     pub conversation_id: Uuid,
@@ -65,11 +98,17 @@ pub struct ConversationDetail {
     pub group_info: Option<GroupInfo>,
     pub last_message: Option<LastMessageRow>,
     pub participants: Vec<ParticipantRow>,
+    /// Conversation đã được user hiện tại archive
+    #[serde(default)]
+    pub is_archived: bool,
+    /// Conversation được user hiện tại ghim lên đầu danh sách
+    #[serde(default)]
+    pub is_pinned: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug, Clone, FromRow, Deserialize, Serialize, Validate)]
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize, Validate, ToSchema)]
 pub struct NewConversation {
     #[serde(rename = "type")]
     pub _type: ConversationType,
@@ -89,11 +128,14 @@ pub struct NewParticipant {
 pub struct ParticipantDetailWithConversation {
     pub user_id: Uuid,
     pub display_name: String,
-    pub avatar_url: Option<String>,
-    pub unread_count: i32,
-    pub joined_at: chrono::DateTime<chrono::Utc>,
+}
 
+/// Message vừa được recipient xác nhận đã nhận (delivered watermark đã tiến lên)
+#[derive(Debug, Clone, FromRow)]
+pub struct DeliveredMessage {
     pub conversation_id: Uuid,
+    pub sender_id: Uuid,
+    pub delivered_at: chrono::DateTime<chrono::Utc>,
 }
 
 #[allow(unused)]
@@ -105,9 +147,250 @@ pub struct NewLastMessage {
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct MessageQueryRequest {
     #[validate(range(min = 1, max = 50))]
     pub limit: i32,
     pub cursor: Option<String>,
+    #[serde(default)]
+    #[param(inline)]
+    pub direction: CursorDirection,
+}
+
+/// Định dạng export messages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Một JSON object `{conversation_id, messages, next_cursor}`
+    #[default]
+    Json,
+    /// Mỗi dòng một message; trang tiếp theo dùng `created_at` của dòng cuối làm cursor
+    Ndjson,
+}
+
+/// Export messages theo thứ tự thời gian, bắt đầu sau `cursor` (RFC 3339)
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    #[serde(default)]
+    #[param(inline)]
+    pub format: ExportFormat,
+    pub cursor: Option<String>,
+    #[validate(range(min = 1, max = 10000))]
+    pub limit: Option<i32>,
+}
+
+/// Phần của bản export được stream tới client
+#[derive(Debug)]
+pub enum ExportChunk {
+    Messages(Vec<ExportedMessage>),
+    /// Kết thúc trang export, `next_cursor` là None khi đã hết messages
+    End {
+        next_cursor: Option<String>,
+    },
+}
+
+/// Số messages lấy mỗi phía của message đích
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MessageAroundQuery {
+    #[validate(range(min = 1, max = 50))]
+    pub limit: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateDuplicatePolicy {
+    pub policy: DuplicatePolicy,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateConversationDefaults {
+    pub history_visibility: Option<HistoryVisibility>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub message_ttl_seconds: Option<Option<i32>>,
+    pub group_creation: Option<GroupCreationPolicy>,
+    #[validate(range(
+        min = 2,
+        max = 10000,
+        message = "Max group size must be between 2 and 10000"
+    ))]
+    pub max_group_size: Option<i32>,
+    pub allow_history_override: Option<bool>,
+    pub allow_ttl_override: Option<bool>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub message_retention_days: Option<Option<i32>>,
+}
+
+/// Tắt push notification của conversation cho user hiện tại, `None` để bật lại
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct MuteConversationModel {
+    #[serde(default)]
+    pub muted_until: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Lọc và phân trang danh sách conversations của user
+#[derive(Debug, Default, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ConversationListQuery {
+    /// `true`: chỉ lấy conversations đã archive, mặc định bỏ qua chúng
+    #[serde(default)]
+    pub archived: bool,
+    #[validate(range(min = 1, max = 100, message = "Limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    /// `cursor` của trang trước
+    pub cursor: Option<String>,
+}
+
+/// Vị trí của conversation cuối cùng trong trang, theo đúng thứ tự sắp xếp của danh sách
+/// (ghim trước, rồi hoạt động gần nhất). Client coi chuỗi cursor là opaque
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversationListCursor {
+    pub pinned_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub activity_at: chrono::DateTime<chrono::Utc>,
+    pub conversation_id: Uuid,
+}
+
+impl ConversationListCursor {
+    pub fn from_row(row: &ConversationRow) -> Self {
+        Self {
+            pinned_at: row.pinned_at,
+            activity_at: row.activity_at(),
+            conversation_id: row.conversation_id,
+        }
+    }
+
+    /// Format `<activity_at>|<conversation_id>[|<pinned_at>]`
+    pub fn parse(cursor: &str) -> Option<Self> {
+        let parse_time = |value: &str| {
+            chrono::DateTime::parse_from_rfc3339(value).ok().map(|t| t.with_timezone(&chrono::Utc))
+        };
+
+        let mut parts = cursor.split('|');
+        let activity_at = parse_time(parts.next()?)?;
+        let conversation_id = parts.next()?.parse().ok()?;
+        let pinned_at = match parts.next() {
+            Some(value) => Some(parse_time(value)?),
+            None => None,
+        };
+
+        parts.next().is_none().then_some(Self { pinned_at, activity_at, conversation_id })
+    }
+
+    pub fn encode(&self) -> String {
+        let format = |t: &chrono::DateTime<chrono::Utc>| {
+            t.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)
+        };
+
+        let mut cursor = format!("{}|{}", format(&self.activity_at), self.conversation_id);
+        if let Some(pinned_at) = &self.pinned_at {
+            cursor.push('|');
+            cursor.push_str(&format(pinned_at));
+        }
+        cursor
+    }
+}
+
+/// Một trang conversations, kèm các thông báo toàn hệ thống user chưa xác nhận (chỉ ở
+/// trang đầu)
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConversationListResponse {
+    pub conversations: Vec<ConversationDetail>,
+    pub announcements: Vec<AnnouncementEntity>,
+    /// Giống `page.next_cursor`, giữ lại cho client cũ
+    pub cursor: Option<String>,
+    pub page: PageInfo,
+}
+
+/// Archive (ẩn khỏi danh sách chính) hoặc bỏ archive conversation cho user hiện tại
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ArchiveConversationModel {
+    pub archived: bool,
+}
+
+/// Ghim hoặc bỏ ghim conversation lên đầu danh sách của user hiện tại
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct PinConversationModel {
+    pub pinned: bool,
+}
+
+/// Mức push notification của conversation cho user hiện tại (độc lập với mute: khi đang
+/// mute, user chỉ nhận push lúc được mention)
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateNotificationLevelModel {
+    pub level: NotificationLevel,
+}
+
+/// Lưu bản nháp, nội dung rỗng để xóa bản nháp
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct SaveDraftModel {
+    #[validate(length(max = 5000, message = "Draft must be at most 5000 characters"))]
+    pub content: String,
+}
+
+/// Override cấu hình cho một conversation (chỉ khi policy cho phép)
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateConversationSettings {
+    pub history_visibility: Option<HistoryVisibility>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub message_ttl_seconds: Option<Option<i32>>,
+}
+
+/// Đổi tên / mô tả group (chỉ group admin)
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateGroupModel {
+    #[validate(length(min = 1, max = 255, message = "Group name must be 1 to 255 characters"))]
+    pub name: Option<String>,
+    /// `null` để xóa mô tả
+    #[validate(length(max = 1000, message = "Description must be at most 1000 characters"))]
+    #[serde(default, deserialize_with = "double_option")]
+    pub description: Option<Option<String>>,
+}
+
+impl UpdateGroupModel {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.description.is_none()
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct AddMembersModel {
+    #[validate(length(min = 1, max = 100, message = "Member IDs must contain 1 to 100 users"))]
+    pub member_ids: Vec<Uuid>,
+}
+
+/// Kết quả thêm members: bạn bè được thêm trực tiếp, người lạ nhận invite
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AddMembersResponse {
+    pub added: Vec<Uuid>,
+    pub invited: Vec<Uuid>,
+}
+
+/// Lời mời tham gia group đang chờ user phản hồi
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct ConversationInvite {
+    pub conversation_id: Uuid,
+    pub group_name: String,
+    pub group_avatar_url: Option<String>,
+    pub invited_by: Option<Uuid>,
+    pub inviter_display_name: Option<String>,
+    pub invited_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Participant có `unread_count` lệch so với số messages chưa đọc thực tế
+#[derive(Debug, Clone, FromRow)]
+pub struct UnreadCorrection {
+    pub conversation_id: Uuid,
+    pub user_id: Uuid,
+    pub previous_count: i32,
+    pub unread_count: i32,
+}
+
+/// Kết quả một lần reconcile unread counts
+#[derive(Debug, Clone, Copy, Default, Serialize, ToSchema)]
+pub struct UnreadReconcileReport {
+    /// Số participants đã kiểm tra
+    pub scanned: u64,
+    /// Số participants có `unread_count` bị lệch và đã được sửa
+    pub corrected: u64,
 }
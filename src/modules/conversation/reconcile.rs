@@ -0,0 +1,114 @@
+/// Unread Count Reconciliation
+///
+/// `participants.unread_count` được tăng trong transaction gửi tin và reset khi mark seen,
+/// nên có thể lệch (transaction crash giữa chừng, message bị xóa / ẩn sau khi tăng count).
+/// Task chạy nền định kỳ tính lại count từ messages chưa đọc và sửa các giá trị lệch;
+/// admin có thể chạy ngay qua `POST /api/v1/admin/unread-counts/reconcile` hoặc CLI
+/// `recount-unread`.
+///
+/// Participants được lock theo batch với `FOR UPDATE SKIP LOCKED` rồi mới đếm messages,
+/// nên không ghi đè count đang được transaction gửi tin / mark seen cập nhật.
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    configs::uow::UnitOfWork,
+    modules::conversation::{
+        model::{UnreadCorrection, UnreadReconcileReport},
+        repository::ParticipantRepository,
+        service::ConversationService,
+    },
+};
+
+/// Khoảng thời gian giữa hai lần reconcile
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Số participants lock và kiểm tra trong mỗi transaction
+pub const BATCH_SIZE: i64 = 500;
+
+/// Tổng số unread counts đã sửa kể từ khi process start
+static CORRECTIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Ghi nhận số unread counts vừa được sửa
+pub fn record_corrections(count: u64) {
+    CORRECTIONS.fetch_add(count, Ordering::Relaxed);
+}
+
+/// Tổng số unread counts đã sửa (expose qua `GET /metrics`)
+pub fn corrections() -> u64 {
+    CORRECTIONS.load(Ordering::Relaxed)
+}
+
+/// Tính lại unread_count của mọi active participants từ messages chưa đọc
+///
+/// Mỗi batch chạy trong một transaction, `on_corrected` được gọi cho từng participant đã
+/// sửa sau khi batch commit
+pub async fn reconcile_all(
+    uow: &dyn UnitOfWork,
+    participant_repo: &(dyn ParticipantRepository + Send + Sync),
+    batch_size: i64,
+    mut on_corrected: impl FnMut(&UnreadCorrection),
+) -> Result<UnreadReconcileReport, error::SystemError> {
+    let mut report = UnreadReconcileReport::default();
+    let mut after = (Uuid::nil(), Uuid::nil());
+
+    loop {
+        let mut tx = uow.begin().await?;
+
+        let participants =
+            participant_repo.lock_active_participants(after, batch_size, &mut tx).await?;
+
+        let Some(last) = participants.last() else {
+            tx.commit().await?;
+            break;
+        };
+        after = *last;
+
+        // Lock đã được giữ nên statement này thấy mọi messages đã commit trước đó
+        let corrections = participant_repo.reconcile_unread_counts(&participants, &mut tx).await?;
+
+        tx.commit().await?;
+
+        report.scanned += participants.len() as u64;
+        report.corrected += corrections.len() as u64;
+        record_corrections(corrections.len() as u64);
+
+        for correction in &corrections {
+            tracing::info!(
+                "Corrected unread count of user {} in conversation {}: {} -> {}",
+                correction.user_id,
+                correction.conversation_id,
+                correction.previous_count,
+                correction.unread_count
+            );
+            on_corrected(correction);
+        }
+
+        if (participants.len() as i64) < batch_size {
+            break;
+        }
+    }
+
+    Ok(report)
+}
+
+pub async fn run_unread_reconciliation(service: ConversationService) {
+    let mut interval = actix_web::rt::time::interval(RECONCILE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match service.reconcile_unread_counts(BATCH_SIZE).await {
+            Ok(report) if report.corrected > 0 => tracing::warn!(
+                "Unread count reconciliation fixed {}/{} participants",
+                report.corrected,
+                report.scanned
+            ),
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to reconcile unread counts: {:?}", e),
+        }
+    }
+}
@@ -2,167 +2,335 @@ use uuid::Uuid;
 
 use crate::{
     api::error,
+    configs::uow::Transaction,
     modules::conversation::{
         model::{
-            ConversationDetail, ConversationRow, NewLastMessage, NewParticipant,
-            ParticipantDetailWithConversation,
+            ConversationDetail, ConversationInvite, ConversationListCursor, ConversationRow,
+            DeliveredMessage, NewLastMessage, NewParticipant, ParticipantDetailWithConversation,
+            UnreadCorrection, UpdateConversationDefaults, UpdateConversationSettings,
+            UpdateGroupModel,
+        },
+        schema::{
+            ConversationDefaultsEntity, ConversationEntity, ConversationType, DraftEntity,
+            DuplicatePolicy, GroupConversationEntity, LastMessageEntity, NotificationLevel,
+            ParticipantEntity,
         },
-        schema::{ConversationEntity, ConversationType, LastMessageEntity, ParticipantEntity},
     },
 };
 
 #[async_trait::async_trait]
 pub trait ConversationRepository {
-    fn get_pool(&self) -> &sqlx::Pool<sqlx::Postgres>;
-
-    async fn find_by_id<'e, E>(
+    async fn find_by_id(
         &self,
         conversation_id: &Uuid,
-        tx: E,
-    ) -> Result<Option<ConversationEntity>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        tx: &mut Transaction,
+    ) -> Result<Option<ConversationEntity>, error::SystemError>;
 
     async fn find_one_conversation_detail(
         &self,
         conversation_id: &Uuid,
     ) -> Result<Option<ConversationDetail>, error::SystemError>;
 
-    async fn create<'e, E>(
+    async fn create(
         &self,
         _type: &ConversationType,
-        tx: E,
-    ) -> Result<ConversationEntity, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        tx: &mut Transaction,
+    ) -> Result<ConversationEntity, error::SystemError>;
 
-    async fn create_direct_conversation<'e>(
+    async fn create_direct_conversation(
         &self,
         user_a: &Uuid,
         user_b: &Uuid,
-        tx: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+        tx: &mut Transaction,
     ) -> Result<ConversationEntity, error::SystemError>;
 
-    async fn create_group_conversation<'e>(
+    async fn create_group_conversation(
         &self,
         name: &str,
         unique_member_ids: &[Uuid],
         user_id: &Uuid,
-        tx: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+        tx: &mut Transaction,
     ) -> Result<ConversationEntity, error::SystemError>;
 
-    async fn find_direct_between_users<'e, E>(
+    /// Tính cả participant đã xóa conversation phía mình, để tin nhắn mới dùng lại
+    /// conversation cũ thay vì tạo conversation direct thứ hai
+    async fn find_direct_between_users(
         &self,
         user_a: &Uuid,
         user_b: &Uuid,
-        tx: E,
-    ) -> Result<Option<ConversationEntity>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        tx: &mut Transaction,
+    ) -> Result<Option<ConversationEntity>, error::SystemError>;
+
+    /// Một trang conversations của user (`archived` chọn danh sách archive hoặc danh sách
+    /// chính): conversations được ghim lên đầu, còn lại theo hoạt động gần nhất. `cursor`
+    /// là conversation cuối của trang trước. Participants active của mỗi conversation được
+    /// lấy cùng query
+    async fn find_all_conversation_with_details_by_user(
+        &self,
+        user_id: &Uuid,
+        archived: bool,
+        cursor: Option<&ConversationListCursor>,
+        limit: i64,
+    ) -> Result<Vec<ConversationRow>, error::SystemError>;
 
-    async fn find_all_conversation_with_details_by_user<'e, E>(
+    /// Số conversations trong danh sách (`archived`) của user, dùng làm tổng của trang
+    async fn count_conversations_by_user(
         &self,
         user_id: &Uuid,
-        tx: E,
-    ) -> Result<Vec<ConversationRow>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        archived: bool,
+    ) -> Result<i64, error::SystemError>;
 
-    async fn get_conversation_and_check_membership<'e, E>(
+    async fn get_conversation_and_check_membership(
         &self,
         conversation_id: &Uuid,
         user_id: &Uuid,
-        tx: E,
-    ) -> Result<(Option<ConversationEntity>, bool), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        tx: &mut Transaction,
+    ) -> Result<(Option<ConversationEntity>, bool), error::SystemError>;
 
     /// Update conversation's updated_at timestamp to current time
-    async fn update_timestamp<'e, E>(
+    async fn update_timestamp(
+        &self,
+        conversation_id: &Uuid,
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError>;
+
+    /// Update how repeated identical messages are handled in a conversation
+    async fn update_duplicate_policy(
+        &self,
+        conversation_id: &Uuid,
+        policy: &DuplicatePolicy,
+    ) -> Result<(), error::SystemError>;
+
+    /// Lock group row (FOR UPDATE) before changing name / description
+    async fn find_group_for_update(
+        &self,
+        conversation_id: &Uuid,
+        tx: &mut Transaction,
+    ) -> Result<Option<GroupConversationEntity>, error::SystemError>;
+
+    /// Update group name / description, fields not provided are kept
+    async fn update_group(
         &self,
         conversation_id: &Uuid,
-        tx: E,
-    ) -> Result<(), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        update: &UpdateGroupModel,
+        tx: &mut Transaction,
+    ) -> Result<GroupConversationEntity, error::SystemError>;
+
+    /// Override history visibility / disappearing TTL for a conversation
+    async fn update_settings(
+        &self,
+        conversation_id: &Uuid,
+        settings: &UpdateConversationSettings,
+    ) -> Result<ConversationEntity, error::SystemError>;
+
+    /// Global defaults applied when conversations are created
+    async fn get_defaults(
+        &self,
+        tx: &mut Transaction,
+    ) -> Result<ConversationDefaultsEntity, error::SystemError>;
+
+    async fn update_defaults(
+        &self,
+        defaults: &UpdateConversationDefaults,
+    ) -> Result<ConversationDefaultsEntity, error::SystemError>;
 }
 
 #[async_trait::async_trait]
 pub trait ParticipantRepository {
-    async fn create_participant<'e, E>(
+    /// Find an active participant row of a user in a conversation
+    async fn find_participant(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<Option<ParticipantEntity>, error::SystemError>;
+
+    async fn create_participant(
         &self,
         participant: &NewParticipant,
-        tx: E,
-    ) -> Result<ParticipantEntity, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        tx: &mut Transaction,
+    ) -> Result<ParticipantEntity, error::SystemError>;
 
-    async fn increment_unread_count<'e, E>(
+    async fn increment_unread_count(
         &self,
         conversation_id: &Uuid,
         user_id: &Uuid,
-        tx: E,
-    ) -> Result<(), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError>;
 
     /// Increment unread count for all participants in a conversation except the sender
-    async fn increment_unread_count_for_others<'e, E>(
+    async fn increment_unread_count_for_others(
         &self,
         conversation_id: &Uuid,
         sender_id: &Uuid,
-        tx: E,
-    ) -> Result<(), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError>;
 
     #[allow(unused)]
-    async fn reset_unread_count<'e, E>(
+    async fn reset_unread_count(
         &self,
         conversation_id: &Uuid,
         user_id: &Uuid,
-        tx: E,
-    ) -> Result<(), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError>;
 
     /// Mark messages as seen by updating last_seen_message_id and resetting unread count
-    async fn mark_as_seen<'e, E>(
+    async fn mark_as_seen(
         &self,
         conversation_id: &Uuid,
         user_id: &Uuid,
         last_seen_message_id: &Uuid,
-        tx: E,
-    ) -> Result<(), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError>;
 
-    async fn find_participants_by_conversation_id<'e, E>(
+    /// Advance the delivered watermark of a recipient to the given message.
+    /// Returns None when the message is not newer than the current watermark
+    async fn mark_as_delivered(
+        &self,
+        message_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<Option<DeliveredMessage>, error::SystemError>;
+
+    async fn find_participants_by_conversation_id(
         &self,
         conversation_ids: &[Uuid],
-        tx: E,
-    ) -> Result<Vec<ParticipantDetailWithConversation>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        tx: &mut Transaction,
+    ) -> Result<Vec<ParticipantDetailWithConversation>, error::SystemError>;
 
     /// Get unread counts for all participants in a conversation
     /// Returns a map of user_id -> unread_count
-    async fn get_unread_counts<'e, E>(
+    async fn get_unread_counts(
+        &self,
+        conversation_id: &Uuid,
+        tx: &mut Transaction,
+    ) -> Result<std::collections::HashMap<Uuid, i32>, error::SystemError>;
+
+    /// Add members to a group: friends of the inviter become active immediately,
+    /// others are stored as pending invites. Returns the rows actually inserted.
+    async fn add_members(
+        &self,
+        conversation_id: &Uuid,
+        inviter_id: &Uuid,
+        user_ids: &[Uuid],
+        tx: &mut Transaction,
+    ) -> Result<Vec<ParticipantEntity>, error::SystemError>;
+
+    /// Count active and pending members of a conversation
+    async fn count_members(
+        &self,
+        conversation_id: &Uuid,
+        tx: &mut Transaction,
+    ) -> Result<i64, error::SystemError>;
+
+    /// Pending group invites of a user
+    async fn find_pending_invites(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Vec<ConversationInvite>, error::SystemError>;
+
+    /// Turn a pending invite into an active membership
+    async fn accept_invite(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, error::SystemError>;
+
+    /// Remove a pending invite
+    async fn decline_invite(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, error::SystemError>;
+
+    /// Mute push notifications of a conversation for an active participant
+    async fn set_muted_until(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        muted_until: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<bool, error::SystemError>;
+
+    /// Xóa conversation phía user (participant.deleted_at = cleared_at = NOW()),
+    /// messages trước thời điểm này không còn hiển thị với user
+    async fn clear_for_user(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, error::SystemError>;
+
+    /// Đưa lại các participants đã xóa conversation phía mình (có message mới)
+    async fn restore_cleared(
+        &self,
+        conversation_id: &Uuid,
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError>;
+
+    /// Lock một batch active participants sau key `after` (theo conversation_id, user_id),
+    /// bỏ qua rows đang bị transaction khác giữ (gửi tin / mark seen đang chạy)
+    async fn lock_active_participants(
+        &self,
+        after: (Uuid, Uuid),
+        limit: i64,
+        tx: &mut Transaction,
+    ) -> Result<Vec<(Uuid, Uuid)>, error::SystemError>;
+
+    /// Tính lại unread_count của các participants (đã lock) từ messages, chỉ ghi các
+    /// giá trị lệch và trả về danh sách đã sửa
+    async fn reconcile_unread_counts(
+        &self,
+        participants: &[(Uuid, Uuid)],
+        tx: &mut Transaction,
+    ) -> Result<Vec<UnreadCorrection>, error::SystemError>;
+
+    /// Archive / bỏ archive conversation cho active participant
+    async fn set_archived(
         &self,
         conversation_id: &Uuid,
-        tx: E,
-    ) -> Result<std::collections::HashMap<Uuid, i32>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        user_id: &Uuid,
+        archived: bool,
+    ) -> Result<Option<ParticipantEntity>, error::SystemError>;
+
+    /// Ghim / bỏ ghim conversation cho active participant
+    async fn set_pinned(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        pinned: bool,
+    ) -> Result<Option<ParticipantEntity>, error::SystemError>;
+
+    /// Đặt mức push notification của conversation cho active participant
+    async fn set_notification_level(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        level: NotificationLevel,
+    ) -> Result<Option<ParticipantEntity>, error::SystemError>;
+
+    async fn find_draft(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<Option<DraftEntity>, error::SystemError>;
+
+    /// Insert hoặc cập nhật bản nháp của user trong conversation
+    async fn upsert_draft(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        content: &str,
+    ) -> Result<DraftEntity, error::SystemError>;
+
+    async fn delete_draft(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<(), error::SystemError>;
 }
 
 #[async_trait::async_trait]
 pub trait LastMessageRepository {
-    async fn upsert_last_message<'e, E>(
+    async fn upsert_last_message(
         &self,
         last_message: &NewLastMessage,
-        tx: E,
-    ) -> Result<LastMessageEntity, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        tx: &mut Transaction,
+    ) -> Result<LastMessageEntity, error::SystemError>;
 }
@@ -0,0 +1,1168 @@
+/// In-memory `ConversationRepository`, `ParticipantRepository` và `LastMessageRepository`
+/// cho unit tests của services
+///
+/// Giữ conversations, groups, participants (kèm watermark seen / delivered), drafts và cấu
+/// hình mặc định trong bộ nhớ, áp dụng cùng quy tắc lọc với các repository Pg (participant
+/// đã xóa conversation phía mình vẫn là member, member đã rời thì không). Messages cũng nằm
+/// ở đây để `MessageRepositoryMock` dùng chung. Thông tin user lấy từ `UserRepositoryMock`,
+/// chỉ bạn bè của người thêm được active ngay khi `add_members` (mock không có bots). Dùng
+/// cùng `MemoryUnitOfWork`, tham số transaction bị bỏ qua và thay đổi được ghi ngay.
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    configs::uow::Transaction,
+    modules::{
+        conversation::{
+            model::{
+                ConversationDetail, ConversationInvite, ConversationListCursor, ConversationRow,
+                DeliveredMessage, GroupInfo, LastMessageRow, NewLastMessage, NewParticipant,
+                ParticipantDetailWithConversation, ParticipantRow, UnreadCorrection,
+                UpdateConversationDefaults, UpdateConversationSettings, UpdateGroupModel,
+            },
+            repository::{ConversationRepository, LastMessageRepository, ParticipantRepository},
+            schema::{
+                ConversationDefaultsEntity, ConversationEntity, ConversationType, DraftEntity,
+                DuplicatePolicy, GroupConversationEntity, GroupCreationPolicy, HistoryVisibility,
+                LastMessageEntity, NotificationLevel, ParticipantEntity, ParticipantStatus,
+            },
+        },
+        message::schema::MessageEntity,
+        user::repository_mock::UserRepositoryMock,
+    },
+};
+
+/// Participant kèm các cột không có trong `ParticipantEntity`
+#[derive(Clone)]
+struct ParticipantRecord {
+    entity: ParticipantEntity,
+    last_seen_message_id: Option<Uuid>,
+    last_delivered_message_id: Option<Uuid>,
+}
+
+impl ParticipantRecord {
+    /// Member (kể cả khi đã xóa conversation phía mình)
+    fn is_member(&self) -> bool {
+        let p = &self.entity;
+        p.status == ParticipantStatus::Active
+            && (p.deleted_at.is_none() || p.deleted_at == p.cleared_at)
+    }
+}
+
+struct State {
+    conversations: HashMap<Uuid, ConversationEntity>,
+    groups: HashMap<Uuid, GroupConversationEntity>,
+    /// (conversation_id, user_id) -> participant
+    participants: HashMap<(Uuid, Uuid), ParticipantRecord>,
+    drafts: HashMap<(Uuid, Uuid), DraftEntity>,
+    last_messages: HashMap<Uuid, LastMessageEntity>,
+    defaults: ConversationDefaultsEntity,
+    messages: Vec<MessageEntity>,
+    /// (user_id, message_id) của messages user đã ẩn phía mình
+    hidden: HashSet<(Uuid, Uuid)>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State {
+            conversations: HashMap::new(),
+            groups: HashMap::new(),
+            participants: HashMap::new(),
+            drafts: HashMap::new(),
+            last_messages: HashMap::new(),
+            defaults: ConversationDefaultsEntity {
+                history_visibility: HistoryVisibility::Full,
+                message_ttl_seconds: None,
+                group_creation: GroupCreationPolicy::Everyone,
+                max_group_size: 100,
+                allow_history_override: true,
+                allow_ttl_override: true,
+                message_retention_days: None,
+                updated_at: chrono::Utc::now(),
+            },
+            messages: Vec::new(),
+            hidden: HashSet::new(),
+        }
+    }
+}
+
+impl State {
+    fn insert_participant(
+        &mut self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        status: ParticipantStatus,
+        invited_by: Option<Uuid>,
+    ) -> &mut ParticipantEntity {
+        let entity = ParticipantEntity {
+            conversation_id,
+            user_id,
+            unread_count: 0,
+            joined_at: chrono::Utc::now(),
+            deleted_at: None,
+            status,
+            invited_by,
+            muted_until: None,
+            notification_level: NotificationLevel::All,
+            is_archived: false,
+            is_pinned: false,
+            pinned_at: None,
+            cleared_at: None,
+        };
+        let record = ParticipantRecord {
+            entity,
+            last_seen_message_id: None,
+            last_delivered_message_id: None,
+        };
+        &mut self
+            .participants
+            .entry((conversation_id, user_id))
+            .insert_entry(record)
+            .into_mut()
+            .entity
+    }
+
+    fn message(&self, id: &Uuid) -> Option<&MessageEntity> {
+        self.messages.iter().find(|message| message.id == *id)
+    }
+
+    fn last_message(&self, conversation_id: &Uuid) -> Option<LastMessageRow> {
+        self.messages
+            .iter()
+            .filter(|message| message.conversation_id == *conversation_id)
+            .max_by_key(|message| message.created_at)
+            .map(|message| LastMessageRow {
+                content: message.content.clone(),
+                sender_id: message.sender_id,
+                created_at: message.created_at,
+            })
+    }
+
+    fn group_info(&self, conversation_id: &Uuid) -> Option<GroupInfo> {
+        self.groups.get(conversation_id).map(|group| GroupInfo {
+            name: group.name.clone(),
+            description: group.description.clone(),
+            created_by: group.created_by,
+            avatar_url: group.avatar_url.clone(),
+        })
+    }
+
+    /// Active participant đang có quyền thay đổi trạng thái của mình trong conversation
+    fn member_mut(
+        &mut self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Option<&mut ParticipantRecord> {
+        self.participants.get_mut(&(*conversation_id, *user_id)).filter(|record| record.is_member())
+    }
+}
+
+/// Clone dùng chung dữ liệu, test giữ một bản để kiểm tra trạng thái sau khi gọi service
+#[derive(Clone)]
+pub struct ConversationRepositoryMock {
+    users: UserRepositoryMock,
+    state: Arc<Mutex<State>>,
+}
+
+impl ConversationRepositoryMock {
+    pub fn new(users: UserRepositoryMock) -> Self {
+        ConversationRepositoryMock { users, state: Arc::default() }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn users(&self) -> &UserRepositoryMock {
+        &self.users
+    }
+
+    /// Bản ghi hiện tại của participant, kể cả khi đã rời / xóa conversation
+    pub fn participant(&self, conversation_id: &Uuid, user_id: &Uuid) -> Option<ParticipantEntity> {
+        self.state().participants.get(&(*conversation_id, *user_id)).map(|r| r.entity.clone())
+    }
+
+    /// User của mọi bản ghi participant trong conversation, kể cả đã rời / đang chờ
+    pub fn participant_ids(&self, conversation_id: &Uuid) -> Vec<Uuid> {
+        self.state()
+            .participants
+            .keys()
+            .filter(|(id, _)| id == conversation_id)
+            .map(|(_, user_id)| *user_id)
+            .collect()
+    }
+
+    pub fn insert_message(&self, message: MessageEntity) -> MessageEntity {
+        self.state().messages.push(message.clone());
+        message
+    }
+
+    /// Message hiện tại, kể cả khi đã bị xóa / ẩn
+    pub fn message(&self, id: &Uuid) -> Option<MessageEntity> {
+        self.state().message(id).cloned()
+    }
+
+    /// Messages thỏa `predicate`, kể cả messages đã bị xóa / ẩn
+    pub fn messages_where(&self, predicate: impl Fn(&MessageEntity) -> bool) -> Vec<MessageEntity> {
+        self.state().messages.iter().filter(|message| predicate(message)).cloned().collect()
+    }
+
+    /// Sửa message bằng `update`, chỉ ghi lại khi `update` trả về true
+    pub fn update_message(
+        &self,
+        id: &Uuid,
+        update: impl FnOnce(&mut MessageEntity) -> bool,
+    ) -> Option<MessageEntity> {
+        let mut state = self.state();
+        let message = state.messages.iter_mut().find(|message| message.id == *id)?;
+        let mut updated = message.clone();
+        if !update(&mut updated) {
+            return None;
+        }
+        *message = updated.clone();
+        Some(updated)
+    }
+
+    /// Messages của conversation theo thứ tự thời gian, bỏ messages bị ẩn với mọi người và
+    /// messages `viewer_id` đã ẩn phía mình
+    pub fn messages_for(
+        &self,
+        conversation_id: &Uuid,
+        viewer_id: Option<&Uuid>,
+    ) -> Vec<MessageEntity> {
+        let state = self.state();
+        let mut messages: Vec<_> = state
+            .messages
+            .iter()
+            .filter(|message| {
+                message.conversation_id == *conversation_id
+                    && message.hidden_at.is_none()
+                    && viewer_id.is_none_or(|viewer| !state.hidden.contains(&(*viewer, message.id)))
+            })
+            .cloned()
+            .collect();
+        messages.sort_by_key(|message| message.created_at);
+        messages
+    }
+
+    pub fn hide_message(&self, user_id: Uuid, message_id: Uuid) {
+        self.state().hidden.insert((user_id, message_id));
+    }
+
+    /// Xóa vĩnh viễn messages thỏa `predicate` (tối đa `limit`), trả về số messages đã xóa
+    pub fn remove_messages(&self, limit: usize, predicate: impl Fn(&MessageEntity) -> bool) -> u64 {
+        let mut state = self.state();
+        let removed: HashSet<Uuid> = state
+            .messages
+            .iter()
+            .filter(|message| predicate(message))
+            .take(limit)
+            .map(|message| message.id)
+            .collect();
+        state.messages.retain(|message| !removed.contains(&message.id));
+        state.hidden.retain(|(_, message_id)| !removed.contains(message_id));
+        removed.len() as u64
+    }
+
+    fn participant_row(&self, record: &ParticipantRecord) -> Option<ParticipantRow> {
+        let user = self.users.get(&record.entity.user_id)?;
+        Some(ParticipantRow {
+            user_id: user.id,
+            display_name: user.display_name,
+            avatar_url: user.avatar_url,
+            unread_count: record.entity.unread_count,
+            joined_at: record.entity.joined_at,
+            last_delivered_message_id: record.last_delivered_message_id,
+            last_seen_message_id: record.last_seen_message_id,
+        })
+    }
+
+    fn member_rows(&self, conversation_id: &Uuid) -> Vec<ParticipantRow> {
+        let records: Vec<_> = self
+            .state()
+            .participants
+            .values()
+            .filter(|record| {
+                record.entity.conversation_id == *conversation_id && record.is_member()
+            })
+            .cloned()
+            .collect();
+        records.iter().filter_map(|record| self.participant_row(record)).collect()
+    }
+
+    fn update_member(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        update: impl FnOnce(&mut ParticipantEntity),
+    ) -> Option<ParticipantEntity> {
+        let mut state = self.state();
+        let record = state.member_mut(conversation_id, user_id)?;
+        update(&mut record.entity);
+        Some(record.entity.clone())
+    }
+}
+
+#[async_trait::async_trait]
+impl ConversationRepository for ConversationRepositoryMock {
+    async fn find_by_id(
+        &self,
+        conversation_id: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<Option<ConversationEntity>, error::SystemError> {
+        Ok(self.state().conversations.get(conversation_id).cloned())
+    }
+
+    async fn find_one_conversation_detail(
+        &self,
+        conversation_id: &Uuid,
+    ) -> Result<Option<ConversationDetail>, error::SystemError> {
+        let (conversation, group_info, last_message) = {
+            let state = self.state();
+            let Some(conversation) = state.conversations.get(conversation_id).cloned() else {
+                return Ok(None);
+            };
+            (conversation, state.group_info(conversation_id), state.last_message(conversation_id))
+        };
+
+        let records: Vec<_> = self
+            .state()
+            .participants
+            .values()
+            .filter(|record| {
+                record.entity.conversation_id == *conversation_id
+                    && record.entity.status == ParticipantStatus::Active
+            })
+            .cloned()
+            .collect();
+
+        Ok(Some(ConversationDetail {
+            conversation_id: conversation.id,
+            _type: conversation._type,
+            group_info,
+            last_message,
+            participants: records.iter().filter_map(|r| self.participant_row(r)).collect(),
+            is_archived: false,
+            is_pinned: false,
+            created_at: conversation.created_at,
+            updated_at: conversation.updated_at,
+        }))
+    }
+
+    async fn create(
+        &self,
+        _type: &ConversationType,
+        _tx: &mut Transaction,
+    ) -> Result<ConversationEntity, error::SystemError> {
+        let mut state = self.state();
+        let now = chrono::Utc::now();
+        let conversation = ConversationEntity {
+            id: Uuid::now_v7(),
+            _type: _type.clone(),
+            duplicate_policy: DuplicatePolicy::Collapse,
+            history_visibility: state.defaults.history_visibility,
+            message_ttl_seconds: state.defaults.message_ttl_seconds,
+            created_at: now,
+            updated_at: now,
+        };
+        state.conversations.insert(conversation.id, conversation.clone());
+        Ok(conversation)
+    }
+
+    async fn create_direct_conversation(
+        &self,
+        user_a: &Uuid,
+        user_b: &Uuid,
+        tx: &mut Transaction,
+    ) -> Result<ConversationEntity, error::SystemError> {
+        let conversation = self.create(&ConversationType::Direct, tx).await?;
+
+        let mut state = self.state();
+        for user_id in [user_a, user_b] {
+            state.insert_participant(conversation.id, *user_id, ParticipantStatus::Active, None);
+        }
+
+        Ok(conversation)
+    }
+
+    async fn create_group_conversation(
+        &self,
+        name: &str,
+        unique_member_ids: &[Uuid],
+        user_id: &Uuid,
+        tx: &mut Transaction,
+    ) -> Result<ConversationEntity, error::SystemError> {
+        let conversation = self.create(&ConversationType::Group, tx).await?;
+
+        let mut state = self.state();
+        state.groups.insert(
+            conversation.id,
+            GroupConversationEntity {
+                conversation_id: conversation.id,
+                name: name.to_string(),
+                description: None,
+                created_by: *user_id,
+                avatar_url: None,
+            },
+        );
+        for member_id in unique_member_ids {
+            state.insert_participant(conversation.id, *member_id, ParticipantStatus::Active, None);
+        }
+
+        Ok(conversation)
+    }
+
+    async fn find_direct_between_users(
+        &self,
+        user_a: &Uuid,
+        user_b: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<Option<ConversationEntity>, error::SystemError> {
+        let state = self.state();
+        let includes = |conversation_id: Uuid, user_id: &Uuid| {
+            state.participants.get(&(conversation_id, *user_id)).is_some_and(|record| {
+                let p = &record.entity;
+                p.deleted_at.is_none() || p.deleted_at == p.cleared_at
+            })
+        };
+
+        Ok(state
+            .conversations
+            .values()
+            .find(|c| {
+                c._type == ConversationType::Direct
+                    && includes(c.id, user_a)
+                    && includes(c.id, user_b)
+            })
+            .cloned())
+    }
+
+    async fn find_all_conversation_with_details_by_user(
+        &self,
+        user_id: &Uuid,
+        archived: bool,
+        cursor: Option<&ConversationListCursor>,
+        limit: i64,
+    ) -> Result<Vec<ConversationRow>, error::SystemError> {
+        let mut rows: Vec<ConversationRow> = {
+            let state = self.state();
+            state
+                .participants
+                .values()
+                .filter(|record| {
+                    let p = &record.entity;
+                    p.user_id == *user_id
+                        && p.deleted_at.is_none()
+                        && p.status == ParticipantStatus::Active
+                        && p.is_archived == archived
+                })
+                .filter_map(|record| {
+                    let p = &record.entity;
+                    let conversation = state.conversations.get(&p.conversation_id)?;
+                    Some(ConversationRow {
+                        conversation_id: conversation.id,
+                        _type: conversation._type.clone(),
+                        group_info: state.group_info(&conversation.id),
+                        last_message: state.last_message(&conversation.id),
+                        participants: Vec::new(),
+                        is_archived: p.is_archived,
+                        is_pinned: p.is_pinned,
+                        pinned_at: p.pinned_at,
+                        created_at: conversation.created_at,
+                        updated_at: conversation.updated_at,
+                    })
+                })
+                .collect()
+        };
+
+        // `None` đứng trước mọi thời điểm, giống `-infinity` của query Pg
+        let key = |row: &ConversationRow| (row.pinned_at, row.activity_at(), row.conversation_id);
+        if let Some(cursor) = cursor {
+            let after = (cursor.pinned_at, cursor.activity_at, cursor.conversation_id);
+            rows.retain(|row| key(row) < after);
+        }
+        rows.sort_by_key(|row| Reverse(key(row)));
+        rows.truncate(limit.max(0) as usize);
+
+        for row in &mut rows {
+            row.participants = self.member_rows(&row.conversation_id);
+        }
+
+        Ok(rows)
+    }
+
+    async fn count_conversations_by_user(
+        &self,
+        user_id: &Uuid,
+        archived: bool,
+    ) -> Result<i64, error::SystemError> {
+        Ok(self
+            .state()
+            .participants
+            .values()
+            .filter(|record| {
+                let p = &record.entity;
+                p.user_id == *user_id
+                    && p.deleted_at.is_none()
+                    && p.status == ParticipantStatus::Active
+                    && p.is_archived == archived
+            })
+            .count() as i64)
+    }
+
+    async fn get_conversation_and_check_membership(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<(Option<ConversationEntity>, bool), error::SystemError> {
+        let state = self.state();
+        let Some(conversation) = state.conversations.get(conversation_id).cloned() else {
+            return Ok((None, false));
+        };
+        let is_member = state
+            .participants
+            .get(&(*conversation_id, *user_id))
+            .is_some_and(ParticipantRecord::is_member);
+
+        Ok((Some(conversation), is_member))
+    }
+
+    async fn update_timestamp(
+        &self,
+        conversation_id: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
+        if let Some(conversation) = self.state().conversations.get_mut(conversation_id) {
+            conversation.updated_at = chrono::Utc::now();
+        }
+        Ok(())
+    }
+
+    async fn update_duplicate_policy(
+        &self,
+        conversation_id: &Uuid,
+        policy: &DuplicatePolicy,
+    ) -> Result<(), error::SystemError> {
+        let mut state = self.state();
+        let conversation = state
+            .conversations
+            .get_mut(conversation_id)
+            .ok_or_else(|| error::SystemError::not_found("Conversation not found"))?;
+        conversation.duplicate_policy = *policy;
+        Ok(())
+    }
+
+    async fn find_group_for_update(
+        &self,
+        conversation_id: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<Option<GroupConversationEntity>, error::SystemError> {
+        Ok(self.state().groups.get(conversation_id).cloned())
+    }
+
+    async fn update_group(
+        &self,
+        conversation_id: &Uuid,
+        update: &UpdateGroupModel,
+        _tx: &mut Transaction,
+    ) -> Result<GroupConversationEntity, error::SystemError> {
+        let mut state = self.state();
+        let group = state
+            .groups
+            .get_mut(conversation_id)
+            .ok_or_else(|| error::SystemError::not_found("Group not found"))?;
+        if let Some(name) = &update.name {
+            group.name = name.clone();
+        }
+        if let Some(description) = &update.description {
+            group.description = description.clone();
+        }
+        Ok(group.clone())
+    }
+
+    async fn update_settings(
+        &self,
+        conversation_id: &Uuid,
+        settings: &UpdateConversationSettings,
+    ) -> Result<ConversationEntity, error::SystemError> {
+        let mut state = self.state();
+        let conversation = state
+            .conversations
+            .get_mut(conversation_id)
+            .ok_or_else(|| error::SystemError::not_found("Conversation not found"))?;
+        if let Some(visibility) = settings.history_visibility {
+            conversation.history_visibility = visibility;
+        }
+        if let Some(ttl) = settings.message_ttl_seconds {
+            conversation.message_ttl_seconds = ttl;
+        }
+        conversation.updated_at = chrono::Utc::now();
+        Ok(conversation.clone())
+    }
+
+    async fn get_defaults(
+        &self,
+        _tx: &mut Transaction,
+    ) -> Result<ConversationDefaultsEntity, error::SystemError> {
+        Ok(self.state().defaults.clone())
+    }
+
+    async fn update_defaults(
+        &self,
+        update: &UpdateConversationDefaults,
+    ) -> Result<ConversationDefaultsEntity, error::SystemError> {
+        let mut state = self.state();
+        let defaults = &mut state.defaults;
+        if let Some(visibility) = update.history_visibility {
+            defaults.history_visibility = visibility;
+        }
+        if let Some(ttl) = update.message_ttl_seconds {
+            defaults.message_ttl_seconds = ttl;
+        }
+        if let Some(policy) = update.group_creation {
+            defaults.group_creation = policy;
+        }
+        if let Some(size) = update.max_group_size {
+            defaults.max_group_size = size;
+        }
+        if let Some(allow) = update.allow_history_override {
+            defaults.allow_history_override = allow;
+        }
+        if let Some(allow) = update.allow_ttl_override {
+            defaults.allow_ttl_override = allow;
+        }
+        if let Some(days) = update.message_retention_days {
+            defaults.message_retention_days = days;
+        }
+        defaults.updated_at = chrono::Utc::now();
+        Ok(defaults.clone())
+    }
+}
+
+#[async_trait::async_trait]
+impl ParticipantRepository for ConversationRepositoryMock {
+    async fn find_participant(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<Option<ParticipantEntity>, error::SystemError> {
+        Ok(self.state().member_mut(conversation_id, user_id).map(|record| record.entity.clone()))
+    }
+
+    async fn create_participant(
+        &self,
+        participant: &NewParticipant,
+        _tx: &mut Transaction,
+    ) -> Result<ParticipantEntity, error::SystemError> {
+        let mut state = self.state();
+        let key = (participant.conversation_id, participant.user_id);
+        if state.participants.contains_key(&key) {
+            return Err(error::SystemError::conflict("Participant"));
+        }
+
+        let entity = state.insert_participant(key.0, key.1, ParticipantStatus::Active, None);
+        entity.unread_count = participant.unread_count;
+
+        Ok(entity.clone())
+    }
+
+    async fn increment_unread_count(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
+        if let Some(record) = self.state().participants.get_mut(&(*conversation_id, *user_id)) {
+            let p = &mut record.entity;
+            if p.deleted_at.is_none() || p.deleted_at == p.cleared_at {
+                p.unread_count += 1;
+            }
+        }
+        Ok(())
+    }
+
+    async fn increment_unread_count_for_others(
+        &self,
+        conversation_id: &Uuid,
+        sender_id: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
+        for record in self.state().participants.values_mut() {
+            if record.entity.conversation_id == *conversation_id
+                && record.entity.user_id != *sender_id
+                && record.is_member()
+            {
+                record.entity.unread_count += 1;
+            }
+        }
+        Ok(())
+    }
+
+    async fn reset_unread_count(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
+        if let Some(record) = self.state().participants.get_mut(&(*conversation_id, *user_id)) {
+            let p = &mut record.entity;
+            if p.deleted_at.is_none() || p.deleted_at == p.cleared_at {
+                p.unread_count = 0;
+            }
+        }
+        Ok(())
+    }
+
+    async fn mark_as_seen(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        last_seen_message_id: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
+        if let Some(record) = self.state().participants.get_mut(&(*conversation_id, *user_id)) {
+            let p = &record.entity;
+            if p.deleted_at.is_none() || p.deleted_at == p.cleared_at {
+                record.last_seen_message_id = Some(*last_seen_message_id);
+                record.entity.unread_count = 0;
+            }
+        }
+        Ok(())
+    }
+
+    async fn mark_as_delivered(
+        &self,
+        message_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<Option<DeliveredMessage>, error::SystemError> {
+        let mut state = self.state();
+        let Some(message) = state
+            .message(message_id)
+            .filter(|m| m.sender_id != *user_id && m.deleted_at.is_none())
+            .cloned()
+        else {
+            return Ok(None);
+        };
+
+        let watermark = state
+            .participants
+            .get(&(message.conversation_id, *user_id))
+            .and_then(|record| record.last_delivered_message_id)
+            .and_then(|id| state.message(&id).map(|delivered| delivered.created_at));
+
+        // Chỉ tiến watermark: bỏ qua ack cho message cũ hơn message đã delivered
+        let Some(record) = state.member_mut(&message.conversation_id, user_id) else {
+            return Ok(None);
+        };
+        if record.last_delivered_message_id.is_some()
+            && watermark.is_none_or(|delivered_at| message.created_at <= delivered_at)
+        {
+            return Ok(None);
+        }
+        record.last_delivered_message_id = Some(message.id);
+
+        Ok(Some(DeliveredMessage {
+            conversation_id: message.conversation_id,
+            sender_id: message.sender_id,
+            delivered_at: chrono::Utc::now(),
+        }))
+    }
+
+    async fn find_participants_by_conversation_id(
+        &self,
+        conversation_ids: &[Uuid],
+        _tx: &mut Transaction,
+    ) -> Result<Vec<ParticipantDetailWithConversation>, error::SystemError> {
+        let user_ids: Vec<Uuid> = self
+            .state()
+            .participants
+            .values()
+            .filter(|record| {
+                conversation_ids.contains(&record.entity.conversation_id) && record.is_member()
+            })
+            .map(|record| record.entity.user_id)
+            .collect();
+
+        Ok(user_ids
+            .iter()
+            .filter_map(|id| self.users.get(id))
+            .map(|user| ParticipantDetailWithConversation {
+                user_id: user.id,
+                display_name: user.display_name,
+            })
+            .collect())
+    }
+
+    async fn get_unread_counts(
+        &self,
+        conversation_id: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<HashMap<Uuid, i32>, error::SystemError> {
+        Ok(self
+            .state()
+            .participants
+            .values()
+            .filter(|record| {
+                record.entity.conversation_id == *conversation_id && record.is_member()
+            })
+            .map(|record| (record.entity.user_id, record.entity.unread_count))
+            .collect())
+    }
+
+    async fn add_members(
+        &self,
+        conversation_id: &Uuid,
+        inviter_id: &Uuid,
+        user_ids: &[Uuid],
+        _tx: &mut Transaction,
+    ) -> Result<Vec<ParticipantEntity>, error::SystemError> {
+        let mut added = Vec::new();
+        for user_id in user_ids {
+            let active = self.users.get(user_id).is_some_and(|user| user.deleted_at.is_none());
+            if !active || user_id == inviter_id {
+                continue;
+            }
+
+            let status = if self.users.is_friend(inviter_id, user_id) {
+                ParticipantStatus::Active
+            } else {
+                ParticipantStatus::Pending
+            };
+
+            // Member đã rời được thêm lại, member hiện tại / invite đang chờ giữ nguyên
+            let mut state = self.state();
+            let entity = match state.participants.get_mut(&(*conversation_id, *user_id)) {
+                Some(record) => {
+                    let p = &mut record.entity;
+                    if p.deleted_at.is_none() || p.deleted_at == p.cleared_at {
+                        continue;
+                    }
+                    p.unread_count = 0;
+                    p.joined_at = chrono::Utc::now();
+                    p.status = status;
+                    p.invited_by = Some(*inviter_id);
+                    p.deleted_at = None;
+                    p.clone()
+                }
+                None => state
+                    .insert_participant(*conversation_id, *user_id, status, Some(*inviter_id))
+                    .clone(),
+            };
+            added.push(entity);
+        }
+
+        Ok(added)
+    }
+
+    async fn count_members(
+        &self,
+        conversation_id: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<i64, error::SystemError> {
+        Ok(self
+            .state()
+            .participants
+            .values()
+            .filter(|record| {
+                let p = &record.entity;
+                p.conversation_id == *conversation_id
+                    && (p.deleted_at.is_none() || p.deleted_at == p.cleared_at)
+            })
+            .count() as i64)
+    }
+
+    async fn find_pending_invites(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Vec<ConversationInvite>, error::SystemError> {
+        let mut invites: Vec<_> = {
+            let state = self.state();
+            state
+                .participants
+                .values()
+                .filter(|record| {
+                    let p = &record.entity;
+                    p.user_id == *user_id
+                        && p.status == ParticipantStatus::Pending
+                        && p.deleted_at.is_none()
+                })
+                .filter_map(|record| {
+                    let group = state.groups.get(&record.entity.conversation_id)?;
+                    Some(ConversationInvite {
+                        conversation_id: group.conversation_id,
+                        group_name: group.name.clone(),
+                        group_avatar_url: group.avatar_url.clone(),
+                        invited_by: record.entity.invited_by,
+                        inviter_display_name: None,
+                        invited_at: record.entity.joined_at,
+                    })
+                })
+                .collect()
+        };
+
+        for invite in &mut invites {
+            invite.inviter_display_name =
+                invite.invited_by.and_then(|id| self.users.get(&id)).map(|u| u.display_name);
+        }
+        invites.sort_by_key(|invite| Reverse(invite.invited_at));
+
+        Ok(invites)
+    }
+
+    async fn accept_invite(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        let mut state = self.state();
+        match state.participants.get_mut(&(*conversation_id, *user_id)) {
+            Some(record)
+                if record.entity.status == ParticipantStatus::Pending
+                    && record.entity.deleted_at.is_none() =>
+            {
+                record.entity.status = ParticipantStatus::Active;
+                record.entity.joined_at = chrono::Utc::now();
+                record.entity.unread_count = 0;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn decline_invite(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        let mut state = self.state();
+        let key = (*conversation_id, *user_id);
+        let pending = state
+            .participants
+            .get(&key)
+            .is_some_and(|record| record.entity.status == ParticipantStatus::Pending);
+        if pending {
+            state.participants.remove(&key);
+        }
+        Ok(pending)
+    }
+
+    async fn set_muted_until(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        muted_until: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<bool, error::SystemError> {
+        Ok(self.update_member(conversation_id, user_id, |p| p.muted_until = muted_until).is_some())
+    }
+
+    async fn clear_for_user(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        let mut state = self.state();
+        match state.participants.get_mut(&(*conversation_id, *user_id)) {
+            Some(record)
+                if record.entity.status == ParticipantStatus::Active
+                    && record.entity.deleted_at.is_none() =>
+            {
+                let now = chrono::Utc::now();
+                record.entity.deleted_at = Some(now);
+                record.entity.cleared_at = Some(now);
+                record.entity.unread_count = 0;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn restore_cleared(
+        &self,
+        conversation_id: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
+        for record in self.state().participants.values_mut() {
+            let p = &mut record.entity;
+            if p.conversation_id == *conversation_id
+                && p.deleted_at.is_some()
+                && p.deleted_at == p.cleared_at
+            {
+                p.deleted_at = None;
+            }
+        }
+        Ok(())
+    }
+
+    async fn lock_active_participants(
+        &self,
+        after: (Uuid, Uuid),
+        limit: i64,
+        _tx: &mut Transaction,
+    ) -> Result<Vec<(Uuid, Uuid)>, error::SystemError> {
+        let mut keys: Vec<_> = self
+            .state()
+            .participants
+            .iter()
+            .filter(|(key, record)| {
+                **key > after
+                    && record.entity.deleted_at.is_none()
+                    && record.entity.status == ParticipantStatus::Active
+            })
+            .map(|(key, _)| *key)
+            .collect();
+        keys.sort();
+        keys.truncate(limit.max(0) as usize);
+        Ok(keys)
+    }
+
+    async fn reconcile_unread_counts(
+        &self,
+        participants: &[(Uuid, Uuid)],
+        _tx: &mut Transaction,
+    ) -> Result<Vec<UnreadCorrection>, error::SystemError> {
+        let mut state = self.state();
+        let mut corrections = Vec::new();
+
+        for key in participants {
+            let Some(record) = state.participants.get(key) else {
+                continue;
+            };
+            let p = &record.entity;
+            let seen_at = record
+                .last_seen_message_id
+                .and_then(|id| state.message(&id))
+                .map(|seen| seen.created_at);
+
+            // Unread = messages còn hiển thị của người khác, gửi sau khi join / clear
+            // và sau message đã seen
+            let expected = state
+                .messages
+                .iter()
+                .filter(|m| {
+                    m.conversation_id == p.conversation_id
+                        && m.sender_id != p.user_id
+                        && m.deleted_at.is_none()
+                        && m.hidden_at.is_none()
+                        && m.created_at >= p.joined_at
+                        && p.cleared_at.is_none_or(|cleared_at| m.created_at > cleared_at)
+                        && seen_at.is_none_or(|seen_at| m.created_at > seen_at)
+                })
+                .count() as i32;
+
+            if expected != p.unread_count {
+                corrections.push(UnreadCorrection {
+                    conversation_id: key.0,
+                    user_id: key.1,
+                    previous_count: p.unread_count,
+                    unread_count: expected,
+                });
+            }
+        }
+
+        for correction in &corrections {
+            if let Some(record) =
+                state.participants.get_mut(&(correction.conversation_id, correction.user_id))
+            {
+                record.entity.unread_count = correction.unread_count;
+            }
+        }
+
+        Ok(corrections)
+    }
+
+    async fn set_archived(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        archived: bool,
+    ) -> Result<Option<ParticipantEntity>, error::SystemError> {
+        Ok(self.update_member(conversation_id, user_id, |p| p.is_archived = archived))
+    }
+
+    async fn set_pinned(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        pinned: bool,
+    ) -> Result<Option<ParticipantEntity>, error::SystemError> {
+        // Giữ pinned_at khi ghim lại conversation đã ghim để thứ tự không đổi
+        Ok(self.update_member(conversation_id, user_id, |p| {
+            p.is_pinned = pinned;
+            p.pinned_at = match pinned {
+                true => p.pinned_at.or_else(|| Some(chrono::Utc::now())),
+                false => None,
+            };
+        }))
+    }
+
+    async fn set_notification_level(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        level: NotificationLevel,
+    ) -> Result<Option<ParticipantEntity>, error::SystemError> {
+        Ok(self.update_member(conversation_id, user_id, |p| p.notification_level = level))
+    }
+
+    async fn find_draft(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<Option<DraftEntity>, error::SystemError> {
+        Ok(self.state().drafts.get(&(*conversation_id, *user_id)).cloned())
+    }
+
+    async fn upsert_draft(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        content: &str,
+    ) -> Result<DraftEntity, error::SystemError> {
+        let draft = DraftEntity {
+            conversation_id: *conversation_id,
+            user_id: *user_id,
+            content: content.to_string(),
+            updated_at: chrono::Utc::now(),
+        };
+        self.state().drafts.insert((*conversation_id, *user_id), draft.clone());
+        Ok(draft)
+    }
+
+    async fn delete_draft(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<(), error::SystemError> {
+        self.state().drafts.remove(&(*conversation_id, *user_id));
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl LastMessageRepository for ConversationRepositoryMock {
+    async fn upsert_last_message(
+        &self,
+        last_message: &NewLastMessage,
+        _tx: &mut Transaction,
+    ) -> Result<LastMessageEntity, error::SystemError> {
+        let mut state = self.state();
+        let entity = state.last_messages.entry(last_message.conversation_id).or_insert_with(|| {
+            LastMessageEntity {
+                id: Uuid::now_v7(),
+                content: None,
+                conversation_id: last_message.conversation_id,
+                created_at: last_message.created_at,
+            }
+        });
+        entity.content = last_message.content.clone();
+        entity.created_at = chrono::Utc::now();
+        Ok(entity.clone())
+    }
+}
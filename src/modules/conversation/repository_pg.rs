@@ -2,47 +2,54 @@ use sqlx::{FromRow, Row};
 use uuid::Uuid;
 
 use crate::modules::conversation::model::{
-    ConversationDetail, ConversationRaw, ConversationRow, GroupInfo, LastMessageRow,
-    NewLastMessage, NewParticipant, ParticipantDetailWithConversation, ParticipantRow,
+    ConversationDetail, ConversationInvite, ConversationListCursor, ConversationRaw,
+    ConversationRow, DeliveredMessage, GroupInfo, LastMessageRow, NewLastMessage, NewParticipant,
+    ParticipantDetailWithConversation, ParticipantRow, UnreadCorrection,
+    UpdateConversationDefaults, UpdateConversationSettings, UpdateGroupModel,
 };
 use crate::modules::conversation::repository::{
     ConversationRepository, LastMessageRepository, ParticipantRepository,
 };
 use crate::modules::conversation::schema::{
-    ConversationType, LastMessageEntity, ParticipantEntity,
+    ConversationDefaultsEntity, ConversationType, DraftEntity, DuplicatePolicy,
+    GroupConversationEntity, LastMessageEntity, NotificationLevel, ParticipantEntity,
+};
+use crate::{
+    api::error,
+    configs::{read_replica::ReadPool, uow::Transaction},
+    modules::conversation::schema::ConversationEntity,
 };
-use crate::{api::error, modules::conversation::schema::ConversationEntity};
 
 #[derive(Clone)]
 pub struct ConversationPgRepository {
     pool: sqlx::PgPool,
+    read_pool: ReadPool,
     participant_repo: ParticipantPgRepository,
 }
 
 impl ConversationPgRepository {
     pub fn new(pool: sqlx::PgPool, participant_repo: ParticipantPgRepository) -> Self {
-        Self { pool, participant_repo }
+        Self { read_pool: ReadPool::primary_only(pool.clone()), pool, participant_repo }
+    }
+
+    /// Đọc danh sách conversations của user từ `read_pool`
+    pub fn with_read_pool(mut self, read_pool: ReadPool) -> Self {
+        self.read_pool = read_pool;
+        self
     }
 }
 
 #[async_trait::async_trait]
 impl ConversationRepository for ConversationPgRepository {
-    fn get_pool(&self) -> &sqlx::Pool<sqlx::Postgres> {
-        &self.pool
-    }
-
-    async fn find_by_id<'e, E>(
+    async fn find_by_id(
         &self,
         conversation_id: &Uuid,
-        tx: E,
-    ) -> Result<Option<ConversationEntity>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<Option<ConversationEntity>, error::SystemError> {
         let conversation =
             sqlx::query_as::<_, ConversationEntity>("SELECT * FROM conversations WHERE id = $1")
                 .bind(conversation_id)
-                .fetch_optional(tx)
+                .fetch_optional(tx.connection()?)
                 .await?;
 
         Ok(conversation)
@@ -61,6 +68,7 @@ impl ConversationRepository for ConversationPgRepository {
                 c.updated_at,
 
                 g.name AS group_name,
+                g.description AS group_description,
                 g.created_by AS group_created_by,
                 g.avatar_url AS group_avatar_url,
 
@@ -98,10 +106,13 @@ impl ConversationRepository for ConversationPgRepository {
                 u.avatar_url,
                 u.avatar_id,
                 p.unread_count,
-                p.joined_at
+                p.joined_at,
+                p.last_delivered_message_id,
+                p.last_seen_message_id
             FROM participants p
             JOIN users u ON u.id = p.user_id
             WHERE p.conversation_id = $1
+            AND p.status = 'active'
             "#,
         )
         .bind(conversation_id)
@@ -115,9 +126,12 @@ impl ConversationRepository for ConversationPgRepository {
             updated_at: raw.updated_at,
 
             group_info: match (raw.group_name, raw.group_created_by) {
-                (Some(name), Some(created_by)) => {
-                    Some(GroupInfo { name, avatar_url: raw.group_avatar_url, created_by })
-                }
+                (Some(name), Some(created_by)) => Some(GroupInfo {
+                    name,
+                    description: raw.group_description,
+                    avatar_url: raw.group_avatar_url,
+                    created_by,
+                }),
                 _ => None,
             },
 
@@ -129,42 +143,46 @@ impl ConversationRepository for ConversationPgRepository {
             },
 
             participants,
+            is_archived: false,
+            is_pinned: false,
         };
 
         Ok(Some(res))
     }
 
-    async fn create<'e, E>(
+    async fn create(
         &self,
         _type: &ConversationType,
-        tx: E,
-    ) -> Result<ConversationEntity, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<ConversationEntity, error::SystemError> {
         let id = Uuid::now_v7();
         let conversation = sqlx::query_as::<_, ConversationEntity>(
             r#"
-            INSERT INTO conversations (id, type)
-            VALUES ($1, $2)
+            INSERT INTO conversations (id, type, history_visibility, message_ttl_seconds)
+            VALUES (
+                $1,
+                $2,
+                COALESCE((SELECT history_visibility FROM conversation_defaults WHERE id = 1), 'full'),
+                (SELECT message_ttl_seconds FROM conversation_defaults WHERE id = 1)
+            )
             RETURNING *
             "#,
         )
         .bind(id)
         .bind(_type)
-        .fetch_one(tx)
+        .fetch_one(tx.connection()?)
         .await?;
 
         Ok(conversation)
     }
 
-    async fn create_direct_conversation<'e>(
+    async fn create_direct_conversation(
         &self,
         user_a: &Uuid,
         user_b: &Uuid,
-        tx: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+        tx: &mut Transaction,
     ) -> Result<ConversationEntity, error::SystemError> {
-        let conversation = self.create(&ConversationType::Direct, tx.as_mut()).await?;
+        let conversation = self.create(&ConversationType::Direct, tx).await?;
 
         self.participant_repo
             .create_participant(
@@ -173,7 +191,7 @@ impl ConversationRepository for ConversationPgRepository {
                     user_id: *user_a,
                     unread_count: 0,
                 },
-                tx.as_mut(),
+                tx,
             )
             .await?;
 
@@ -184,21 +202,21 @@ impl ConversationRepository for ConversationPgRepository {
                     user_id: *user_b,
                     unread_count: 0,
                 },
-                tx.as_mut(),
+                tx,
             )
             .await?;
 
         Ok(conversation)
     }
 
-    async fn create_group_conversation<'e>(
+    async fn create_group_conversation(
         &self,
         name: &str,
         unique_member_ids: &[Uuid],
         user_id: &Uuid,
-        tx: &mut sqlx::Transaction<'e, sqlx::Postgres>,
+        tx: &mut Transaction,
     ) -> Result<ConversationEntity, error::SystemError> {
-        let conversation = self.create(&ConversationType::Group, tx.as_mut()).await?;
+        let conversation = self.create(&ConversationType::Group, tx).await?;
 
         sqlx::query(
             r#"
@@ -209,7 +227,7 @@ impl ConversationRepository for ConversationPgRepository {
         .bind(conversation.id)
         .bind(name)
         .bind(user_id)
-        .execute(tx.as_mut())
+        .execute(tx.connection()?)
         .await?;
 
         sqlx::query(
@@ -220,21 +238,18 @@ impl ConversationRepository for ConversationPgRepository {
         )
         .bind(conversation.id)
         .bind(unique_member_ids)
-        .execute(tx.as_mut())
+        .execute(tx.connection()?)
         .await?;
 
         Ok(conversation)
     }
 
-    async fn find_direct_between_users<'e, E>(
+    async fn find_direct_between_users(
         &self,
         user_a: &Uuid,
         user_b: &Uuid,
-        tx: E,
-    ) -> Result<Option<ConversationEntity>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<Option<ConversationEntity>, error::SystemError> {
         let conversation = sqlx::query_as::<_, ConversationEntity>(
             r#"
             SELECT c.*
@@ -245,84 +260,141 @@ impl ConversationRepository for ConversationPgRepository {
                 FROM participants p1
                 WHERE p1.conversation_id = c.id
                 AND p1.user_id = $1
-                AND p1.deleted_at IS NULL
+                AND (p1.deleted_at IS NULL OR p1.deleted_at = p1.cleared_at)
             )
             AND EXISTS (
                 SELECT 1
                 FROM participants p2
                 WHERE p2.conversation_id = c.id
                 AND p2.user_id = $2
-                AND p2.deleted_at IS NULL
+                AND (p2.deleted_at IS NULL OR p2.deleted_at = p2.cleared_at)
             )
             LIMIT 1;
             "#,
         )
         .bind(user_a)
         .bind(user_b)
-        .fetch_optional(tx)
+        .fetch_optional(tx.connection()?)
         .await?;
 
         Ok(conversation)
     }
 
-    async fn find_all_conversation_with_details_by_user<'e, E>(
+    async fn find_all_conversation_with_details_by_user(
         &self,
         user_id: &Uuid,
-        tx: E,
-    ) -> Result<Vec<ConversationRow>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        archived: bool,
+        cursor: Option<&ConversationListCursor>,
+        limit: i64,
+    ) -> Result<Vec<ConversationRow>, error::SystemError> {
+        // Keyset theo (pinned_at, hoạt động gần nhất, id) giảm dần: conversation chưa ghim
+        // có pinned_at NULL, coi như -infinity để đứng sau mọi conversation được ghim.
+        // Participants được gom bằng json_agg sau khi đã cắt trang, nên chỉ tốn một round-trip
+        // và chỉ đọc participants của các conversations trong trang
         let rows = sqlx::query_as::<_, ConversationRaw>(
             r#"
-            SELECT
-                c.id,
-                c.type,
-                c.created_at,
-                c.updated_at,
+            WITH page AS (
+                SELECT
+                    c.id,
+                    c.type,
+                    c.created_at,
+                    c.updated_at,
 
-                g.name          AS group_name,
-                g.avatar_url    AS group_avatar_url,
-                g.avatar_id     AS group_avatar_id,
-                g.created_by    AS group_created_by,
+                    g.name          AS group_name,
+                    g.description   AS group_description,
+                    g.avatar_url    AS group_avatar_url,
+                    g.avatar_id     AS group_avatar_id,
+                    g.created_by    AS group_created_by,
 
-                lm.content      AS last_content,
-                lm.sender_id    AS last_sender_id,
-                lm.created_at   AS last_created_at
+                    lm.content      AS last_content,
+                    lm.sender_id    AS last_sender_id,
+                    lm.created_at   AS last_created_at,
 
-            FROM conversations c
+                    p.is_archived,
+                    p.is_pinned,
+                    p.pinned_at,
 
-            JOIN participants p
-                ON p.conversation_id = c.id
-            AND p.user_id = $1
-            AND p.deleted_at IS NULL
+                    COALESCE(p.pinned_at, '-infinity'::timestamptz) AS pinned_sort,
+                    COALESCE(lm.created_at, c.updated_at)           AS activity_at
 
-            LEFT JOIN group_conversations g
-                ON g.conversation_id = c.id
+                FROM conversations c
 
-            LEFT JOIN LATERAL (
-                SELECT content, sender_id, created_at
-                FROM messages m
-                WHERE m.conversation_id = c.id
-                ORDER BY created_at DESC
-                LIMIT 1
-            ) lm ON TRUE
+                JOIN participants p
+                    ON p.conversation_id = c.id
+                AND p.user_id = $1
+                AND p.deleted_at IS NULL
+                AND p.status = 'active'
+                AND p.is_archived = $2
 
-            ORDER BY
-                COALESCE(lm.created_at, c.updated_at) DESC
+                LEFT JOIN group_conversations g
+                    ON g.conversation_id = c.id
+
+                LEFT JOIN LATERAL (
+                    SELECT content, sender_id, created_at
+                    FROM messages m
+                    WHERE m.conversation_id = c.id
+                    ORDER BY created_at DESC
+                    LIMIT 1
+                ) lm ON TRUE
+
+                WHERE $3::uuid IS NULL
+                   OR (
+                        COALESCE(p.pinned_at, '-infinity'::timestamptz),
+                        COALESCE(lm.created_at, c.updated_at),
+                        c.id
+                      ) < (COALESCE($4::timestamptz, '-infinity'::timestamptz), $5::timestamptz, $3)
+
+                ORDER BY pinned_sort DESC, activity_at DESC, c.id DESC
+                LIMIT $6
+            )
+            SELECT
+                page.*,
+                COALESCE(
+                    (
+                        SELECT json_agg(json_build_object(
+                            'user_id', pp.user_id,
+                            'display_name', u.display_name,
+                            'avatar_url', u.avatar_url,
+                            'unread_count', pp.unread_count,
+                            'joined_at', pp.joined_at,
+                            'last_delivered_message_id', pp.last_delivered_message_id,
+                            'last_seen_message_id', pp.last_seen_message_id
+                        ))
+                        FROM participants pp
+                        JOIN users u ON u.id = pp.user_id
+                        WHERE pp.conversation_id = page.id
+                        AND (pp.deleted_at IS NULL OR pp.deleted_at = pp.cleared_at)
+                        AND pp.status = 'active'
+                    ),
+                    '[]'
+                )::text AS participants_json
+            FROM page
+            ORDER BY page.pinned_sort DESC, page.activity_at DESC, page.id DESC
             "#,
         )
         .bind(user_id)
-        .fetch_all(tx)
+        .bind(archived)
+        .bind(cursor.map(|c| c.conversation_id))
+        .bind(cursor.and_then(|c| c.pinned_at))
+        .bind(cursor.map(|c| c.activity_at))
+        .bind(limit)
+        .fetch_all(&mut *self.read_pool.acquire().await?)
         .await?;
 
-        let result = rows
-            .into_iter()
+        rows.into_iter()
             .map(|r| {
+                let participants = match &r.participants_json {
+                    Some(json) => serde_json::from_str::<Vec<ParticipantRow>>(json)?,
+                    None => Vec::new(),
+                };
+
                 let group_info = match (r.group_name, r.group_created_by) {
-                    (Some(name), Some(created_by)) => {
-                        Some(GroupInfo { name, avatar_url: r.group_avatar_url, created_by })
-                    }
+                    (Some(name), Some(created_by)) => Some(GroupInfo {
+                        name,
+                        description: r.group_description,
+                        avatar_url: r.group_avatar_url,
+                        created_by,
+                    }),
                     _ => None,
                 };
 
@@ -333,29 +405,50 @@ impl ConversationRepository for ConversationPgRepository {
                     _ => None,
                 };
 
-                ConversationRow {
+                Ok::<_, error::SystemError>(ConversationRow {
                     conversation_id: r.id,
                     _type: r._type,
                     created_at: r.created_at,
                     updated_at: r.updated_at,
                     group_info,
                     last_message,
-                }
+                    participants,
+                    is_archived: r.is_archived,
+                    is_pinned: r.is_pinned,
+                    pinned_at: r.pinned_at,
+                })
             })
-            .collect();
+            .collect()
+    }
 
-        Ok(result)
+    async fn count_conversations_by_user(
+        &self,
+        user_id: &Uuid,
+        archived: bool,
+    ) -> Result<i64, error::SystemError> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM participants
+            WHERE user_id = $1
+              AND deleted_at IS NULL
+              AND status = 'active'
+              AND is_archived = $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(archived)
+        .fetch_one(&mut *self.read_pool.acquire().await?)
+        .await?;
+
+        Ok(count)
     }
 
-    async fn get_conversation_and_check_membership<'e, E>(
+    async fn get_conversation_and_check_membership(
         &self,
         conversation_id: &Uuid,
         user_id: &Uuid,
-        tx: E,
-    ) -> Result<(Option<ConversationEntity>, bool), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<(Option<ConversationEntity>, bool), error::SystemError> {
         let row = sqlx::query(
             r#"
             SELECT c.*,
@@ -364,6 +457,8 @@ impl ConversationRepository for ConversationPgRepository {
                 FROM participants p
                 WHERE p.conversation_id = c.id
                 AND p.user_id = $2
+                AND p.status = 'active'
+                AND (p.deleted_at IS NULL OR p.deleted_at = p.cleared_at)
                 ) as is_member
             FROM conversations c
             WHERE c.id = $1
@@ -371,7 +466,7 @@ impl ConversationRepository for ConversationPgRepository {
         )
         .bind(conversation_id)
         .bind(user_id)
-        .fetch_optional(tx)
+        .fetch_optional(tx.connection()?)
         .await?;
 
         if let Some(row) = row {
@@ -383,14 +478,11 @@ impl ConversationRepository for ConversationPgRepository {
         }
     }
 
-    async fn update_timestamp<'e, E>(
+    async fn update_timestamp(
         &self,
         conversation_id: &Uuid,
-        tx: E,
-    ) -> Result<(), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
         sqlx::query(
             r#"
             UPDATE conversations
@@ -399,26 +491,204 @@ impl ConversationRepository for ConversationPgRepository {
             "#,
         )
         .bind(conversation_id)
-        .execute(tx)
+        .execute(tx.connection()?)
         .await?;
 
         Ok(())
     }
+
+    async fn update_duplicate_policy(
+        &self,
+        conversation_id: &Uuid,
+        policy: &DuplicatePolicy,
+    ) -> Result<(), error::SystemError> {
+        let rows = sqlx::query("UPDATE conversations SET duplicate_policy = $1 WHERE id = $2")
+            .bind(policy)
+            .bind(conversation_id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        if rows == 0 {
+            return Err(error::SystemError::not_found("Conversation not found"));
+        }
+
+        Ok(())
+    }
+
+    async fn find_group_for_update(
+        &self,
+        conversation_id: &Uuid,
+        tx: &mut Transaction,
+    ) -> Result<Option<GroupConversationEntity>, error::SystemError> {
+        let group = sqlx::query_as::<_, GroupConversationEntity>(
+            "SELECT * FROM group_conversations WHERE conversation_id = $1 FOR UPDATE",
+        )
+        .bind(conversation_id)
+        .fetch_optional(tx.connection()?)
+        .await?;
+
+        Ok(group)
+    }
+
+    async fn update_group(
+        &self,
+        conversation_id: &Uuid,
+        update: &UpdateGroupModel,
+        tx: &mut Transaction,
+    ) -> Result<GroupConversationEntity, error::SystemError> {
+        let group = sqlx::query_as::<_, GroupConversationEntity>(
+            r#"
+            UPDATE group_conversations
+            SET
+                name        = COALESCE($2, name),
+                description = CASE WHEN $3::boolean THEN $4 ELSE description END
+            WHERE conversation_id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(&update.name)
+        .bind(update.description.is_some())
+        .bind(update.description.as_ref().and_then(|v| v.as_ref()))
+        .fetch_optional(tx.connection()?)
+        .await?
+        .ok_or_else(|| error::SystemError::not_found("Group not found"))?;
+
+        Ok(group)
+    }
+
+    async fn update_settings(
+        &self,
+        conversation_id: &Uuid,
+        settings: &UpdateConversationSettings,
+    ) -> Result<ConversationEntity, error::SystemError> {
+        let (ttl_provided, ttl) = match settings.message_ttl_seconds {
+            Some(v) => (true, v),
+            None => (false, None),
+        };
+
+        let conversation = sqlx::query_as::<_, ConversationEntity>(
+            r#"
+            UPDATE conversations
+            SET
+                history_visibility  = COALESCE($2, history_visibility),
+                message_ttl_seconds = CASE WHEN $3::boolean THEN $4 ELSE message_ttl_seconds END,
+                updated_at          = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(settings.history_visibility)
+        .bind(ttl_provided)
+        .bind(ttl)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| error::SystemError::not_found("Conversation not found"))?;
+
+        Ok(conversation)
+    }
+
+    async fn get_defaults(
+        &self,
+        tx: &mut Transaction,
+    ) -> Result<ConversationDefaultsEntity, error::SystemError> {
+        let defaults = sqlx::query_as::<_, ConversationDefaultsEntity>(
+            "SELECT * FROM conversation_defaults WHERE id = 1",
+        )
+        .fetch_optional(tx.connection()?)
+        .await?
+        .ok_or_else(|| error::SystemError::internal_error("Conversation defaults are missing"))?;
+
+        Ok(defaults)
+    }
+
+    async fn update_defaults(
+        &self,
+        defaults: &UpdateConversationDefaults,
+    ) -> Result<ConversationDefaultsEntity, error::SystemError> {
+        let (ttl_provided, ttl) = match defaults.message_ttl_seconds {
+            Some(v) => (true, v),
+            None => (false, None),
+        };
+        let (retention_provided, retention) = match defaults.message_retention_days {
+            Some(v) => (true, v),
+            None => (false, None),
+        };
+
+        let defaults = sqlx::query_as::<_, ConversationDefaultsEntity>(
+            r#"
+            UPDATE conversation_defaults
+            SET
+                history_visibility     = COALESCE($1, history_visibility),
+                message_ttl_seconds    = CASE WHEN $2::boolean THEN $3 ELSE message_ttl_seconds END,
+                group_creation         = COALESCE($4, group_creation),
+                max_group_size         = COALESCE($5, max_group_size),
+                allow_history_override = COALESCE($6, allow_history_override),
+                allow_ttl_override     = COALESCE($7, allow_ttl_override),
+                message_retention_days = CASE WHEN $8::boolean THEN $9 ELSE message_retention_days END,
+                updated_at             = NOW()
+            WHERE id = 1
+            RETURNING *
+            "#,
+        )
+        .bind(defaults.history_visibility)
+        .bind(ttl_provided)
+        .bind(ttl)
+        .bind(defaults.group_creation)
+        .bind(defaults.max_group_size)
+        .bind(defaults.allow_history_override)
+        .bind(defaults.allow_ttl_override)
+        .bind(retention_provided)
+        .bind(retention)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| error::SystemError::internal_error("Conversation defaults are missing"))?;
+
+        Ok(defaults)
+    }
 }
 
-#[derive(Clone, Default)]
-pub struct ParticipantPgRepository {}
+#[derive(Clone)]
+pub struct ParticipantPgRepository {
+    pool: sqlx::PgPool,
+}
+
+impl ParticipantPgRepository {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
 
 #[async_trait::async_trait]
 impl ParticipantRepository for ParticipantPgRepository {
-    async fn create_participant<'e, E>(
+    async fn find_participant(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<Option<ParticipantEntity>, error::SystemError> {
+        let participant = sqlx::query_as::<_, ParticipantEntity>(
+            r#"
+            SELECT * FROM participants
+            WHERE conversation_id = $1 AND user_id = $2
+            AND (deleted_at IS NULL OR deleted_at = cleared_at)
+            AND status = 'active'
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(participant)
+    }
+
+    async fn create_participant(
         &self,
         participant: &NewParticipant,
-        tx: E,
-    ) -> Result<ParticipantEntity, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<ParticipantEntity, error::SystemError> {
         let entity = sqlx::query_as::<_, ParticipantEntity>(
             r#"
             INSERT INTO participants (conversation_id, user_id, unread_count)
@@ -429,100 +699,89 @@ impl ParticipantRepository for ParticipantPgRepository {
         .bind(participant.conversation_id)
         .bind(participant.user_id)
         .bind(participant.unread_count)
-        .fetch_one(tx)
+        .fetch_one(tx.connection()?)
         .await?;
 
         Ok(entity)
     }
 
-    async fn increment_unread_count<'e, E>(
+    async fn increment_unread_count(
         &self,
         conversation_id: &Uuid,
         user_id: &Uuid,
-        tx: E,
-    ) -> Result<(), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
         sqlx::query(
             r#"
             UPDATE participants
             SET unread_count = unread_count + 1
             WHERE conversation_id = $1
             AND user_id = $2
-            AND deleted_at IS NULL
+            AND (deleted_at IS NULL OR deleted_at = cleared_at)
             "#,
         )
         .bind(conversation_id)
         .bind(user_id)
-        .execute(tx)
+        .execute(tx.connection()?)
         .await?;
 
         Ok(())
     }
 
-    async fn increment_unread_count_for_others<'e, E>(
+    async fn increment_unread_count_for_others(
         &self,
         conversation_id: &Uuid,
         sender_id: &Uuid,
-        tx: E,
-    ) -> Result<(), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
         sqlx::query(
             r#"
             UPDATE participants
             SET unread_count = unread_count + 1
             WHERE conversation_id = $1
             AND user_id != $2
-            AND deleted_at IS NULL
+            AND (deleted_at IS NULL OR deleted_at = cleared_at)
+            AND status = 'active'
             "#,
         )
         .bind(conversation_id)
         .bind(sender_id)
-        .execute(tx)
+        .execute(tx.connection()?)
         .await?;
 
         Ok(())
     }
 
-    async fn reset_unread_count<'e, E>(
+    async fn reset_unread_count(
         &self,
         conversation_id: &Uuid,
         user_id: &Uuid,
-        tx: E,
-    ) -> Result<(), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
         sqlx::query(
             r#"
             UPDATE participants
             SET unread_count = 0
             WHERE conversation_id = $1
             AND user_id = $2
-            AND deleted_at IS NULL
+            AND (deleted_at IS NULL OR deleted_at = cleared_at)
             "#,
         )
         .bind(conversation_id)
         .bind(user_id)
-        .execute(tx)
+        .execute(tx.connection()?)
         .await?;
 
         Ok(())
     }
 
-    async fn mark_as_seen<'e, E>(
+    async fn mark_as_seen(
         &self,
         conversation_id: &Uuid,
         user_id: &Uuid,
         last_seen_message_id: &Uuid,
-        tx: E,
-    ) -> Result<(), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
         sqlx::query(
             r#"
             UPDATE participants
@@ -530,56 +789,83 @@ impl ParticipantRepository for ParticipantPgRepository {
                 unread_count = 0
             WHERE conversation_id = $2
             AND user_id = $3
-            AND deleted_at IS NULL
+            AND (deleted_at IS NULL OR deleted_at = cleared_at)
             "#,
         )
         .bind(last_seen_message_id)
         .bind(conversation_id)
         .bind(user_id)
-        .execute(tx)
+        .execute(tx.connection()?)
         .await?;
 
         Ok(())
     }
 
-    async fn find_participants_by_conversation_id<'e, E>(
+    async fn mark_as_delivered(
+        &self,
+        message_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<Option<DeliveredMessage>, error::SystemError> {
+        // Chỉ tiến watermark: bỏ qua ack cho message cũ hơn message đã delivered
+        let delivered = sqlx::query_as::<_, DeliveredMessage>(
+            r#"
+            UPDATE participants p
+            SET last_delivered_message_id = m.id,
+                last_delivered_at = NOW()
+            FROM messages m
+            WHERE m.id = $1
+            AND m.sender_id <> $2
+            AND m.deleted_at IS NULL
+            AND p.conversation_id = m.conversation_id
+            AND p.user_id = $2
+            AND p.status = 'active'
+            AND (p.deleted_at IS NULL OR p.deleted_at = p.cleared_at)
+            AND (
+                p.last_delivered_message_id IS NULL
+                OR m.created_at > (
+                    SELECT d.created_at FROM messages d WHERE d.id = p.last_delivered_message_id
+                )
+            )
+            RETURNING m.conversation_id, m.sender_id, p.last_delivered_at AS delivered_at
+            "#,
+        )
+        .bind(message_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(delivered)
+    }
+
+    async fn find_participants_by_conversation_id(
         &self,
         conversation_ids: &[Uuid],
-        tx: E,
-    ) -> Result<Vec<ParticipantDetailWithConversation>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<Vec<ParticipantDetailWithConversation>, error::SystemError> {
         let participants = sqlx::query_as::<_, ParticipantDetailWithConversation>(
             r#"
             SELECT
-                p.conversation_id,
                 p.user_id,
-                u.display_name,
-                u.avatar_url,
-                p.unread_count,
-                p.joined_at
+                u.display_name
             FROM participants p
             JOIN users u ON u.id = p.user_id
             WHERE p.conversation_id = ANY($1)
-            AND p.deleted_at IS NULL
+            AND (p.deleted_at IS NULL OR p.deleted_at = p.cleared_at)
+            AND p.status = 'active'
             "#,
         )
         .bind(conversation_ids)
-        .fetch_all(tx)
+        .fetch_all(tx.connection()?)
         .await?;
 
         Ok(participants)
     }
 
-    async fn get_unread_counts<'e, E>(
+    async fn get_unread_counts(
         &self,
         conversation_id: &Uuid,
-        tx: E,
-    ) -> Result<std::collections::HashMap<Uuid, i32>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<std::collections::HashMap<Uuid, i32>, error::SystemError> {
         #[derive(sqlx::FromRow)]
         struct UnreadCountRow {
             user_id: Uuid,
@@ -591,15 +877,452 @@ impl ParticipantRepository for ParticipantPgRepository {
             SELECT user_id, unread_count
             FROM participants
             WHERE conversation_id = $1
-            AND deleted_at IS NULL
+            AND (deleted_at IS NULL OR deleted_at = cleared_at)
+            AND status = 'active'
             "#,
         )
         .bind(conversation_id)
-        .fetch_all(tx)
+        .fetch_all(tx.connection()?)
         .await?;
 
         Ok(rows.into_iter().map(|r| (r.user_id, r.unread_count)).collect())
     }
+
+    async fn add_members(
+        &self,
+        conversation_id: &Uuid,
+        inviter_id: &Uuid,
+        user_ids: &[Uuid],
+        tx: &mut Transaction,
+    ) -> Result<Vec<ParticipantEntity>, error::SystemError> {
+        // Member đã rời (deleted_at) được thêm lại, member hiện tại / invite đang chờ giữ nguyên.
+        // Bạn bè và bot của người thêm được active ngay
+        let participants = sqlx::query_as::<_, ParticipantEntity>(
+            r#"
+            INSERT INTO participants (conversation_id, user_id, unread_count, joined_at, status, invited_by)
+            SELECT
+                $1,
+                u.id,
+                0,
+                NOW(),
+                CASE WHEN EXISTS (
+                    SELECT 1 FROM friends f
+                    WHERE f.user_a = LEAST($2, u.id)
+                    AND f.user_b = GREATEST($2, u.id)
+                    AND f.deleted_at IS NULL
+                ) OR EXISTS (
+                    SELECT 1 FROM bots b
+                    WHERE b.user_id = u.id
+                    AND b.owner_id = $2
+                ) THEN 'active'::participant_status ELSE 'pending'::participant_status END,
+                $2
+            FROM users u
+            WHERE u.id = ANY($3)
+            AND u.id <> $2
+            AND u.deleted_at IS NULL
+            ON CONFLICT (conversation_id, user_id) DO UPDATE
+            SET unread_count = 0,
+                joined_at = NOW(),
+                status = EXCLUDED.status,
+                invited_by = EXCLUDED.invited_by,
+                deleted_at = NULL
+            WHERE participants.deleted_at IS NOT NULL
+            AND participants.deleted_at IS DISTINCT FROM participants.cleared_at
+            RETURNING *
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(inviter_id)
+        .bind(user_ids)
+        .fetch_all(tx.connection()?)
+        .await?;
+
+        Ok(participants)
+    }
+
+    async fn count_members(
+        &self,
+        conversation_id: &Uuid,
+        tx: &mut Transaction,
+    ) -> Result<i64, error::SystemError> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM participants
+            WHERE conversation_id = $1
+            AND (deleted_at IS NULL OR deleted_at = cleared_at)
+            "#,
+        )
+        .bind(conversation_id)
+        .fetch_one(tx.connection()?)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn find_pending_invites(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Vec<ConversationInvite>, error::SystemError> {
+        let invites = sqlx::query_as::<_, ConversationInvite>(
+            r#"
+            SELECT
+                p.conversation_id,
+                g.name          AS group_name,
+                g.avatar_url    AS group_avatar_url,
+                p.invited_by,
+                u.display_name  AS inviter_display_name,
+                p.joined_at     AS invited_at
+            FROM participants p
+            JOIN group_conversations g ON g.conversation_id = p.conversation_id
+            LEFT JOIN users u ON u.id = p.invited_by
+            WHERE p.user_id = $1
+            AND p.status = 'pending'
+            AND p.deleted_at IS NULL
+            ORDER BY p.joined_at DESC
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(invites)
+    }
+
+    async fn accept_invite(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        let rows = sqlx::query(
+            r#"
+            UPDATE participants
+            SET status = 'active',
+                joined_at = NOW(),
+                unread_count = 0
+            WHERE conversation_id = $1
+            AND user_id = $2
+            AND status = 'pending'
+            AND deleted_at IS NULL
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows > 0)
+    }
+
+    async fn decline_invite(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        let rows = sqlx::query(
+            r#"
+            DELETE FROM participants
+            WHERE conversation_id = $1
+            AND user_id = $2
+            AND status = 'pending'
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows > 0)
+    }
+
+    async fn set_muted_until(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        muted_until: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<bool, error::SystemError> {
+        let rows = sqlx::query(
+            r#"
+            UPDATE participants
+            SET muted_until = $3
+            WHERE conversation_id = $1
+            AND user_id = $2
+            AND status = 'active'
+            AND (deleted_at IS NULL OR deleted_at = cleared_at)
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .bind(muted_until)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows > 0)
+    }
+
+    async fn clear_for_user(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        // deleted_at = cleared_at đánh dấu xóa phía user (khác với member đã rời group)
+        let rows = sqlx::query(
+            r#"
+            UPDATE participants
+            SET deleted_at = NOW(),
+                cleared_at = NOW(),
+                unread_count = 0
+            WHERE conversation_id = $1
+            AND user_id = $2
+            AND status = 'active'
+            AND deleted_at IS NULL
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows > 0)
+    }
+
+    async fn restore_cleared(
+        &self,
+        conversation_id: &Uuid,
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
+        sqlx::query(
+            r#"
+            UPDATE participants
+            SET deleted_at = NULL
+            WHERE conversation_id = $1
+            AND deleted_at IS NOT NULL
+            AND deleted_at = cleared_at
+            "#,
+        )
+        .bind(conversation_id)
+        .execute(tx.connection()?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn lock_active_participants(
+        &self,
+        after: (Uuid, Uuid),
+        limit: i64,
+        tx: &mut Transaction,
+    ) -> Result<Vec<(Uuid, Uuid)>, error::SystemError> {
+        let keys = sqlx::query_as::<_, (Uuid, Uuid)>(
+            r#"
+            SELECT conversation_id, user_id
+            FROM participants
+            WHERE (conversation_id, user_id) > ($1, $2)
+            AND deleted_at IS NULL
+            AND status = 'active'
+            ORDER BY conversation_id, user_id
+            LIMIT $3
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .bind(after.0)
+        .bind(after.1)
+        .bind(limit)
+        .fetch_all(tx.connection()?)
+        .await?;
+
+        Ok(keys)
+    }
+
+    async fn reconcile_unread_counts(
+        &self,
+        participants: &[(Uuid, Uuid)],
+        tx: &mut Transaction,
+    ) -> Result<Vec<UnreadCorrection>, error::SystemError> {
+        let (conversation_ids, user_ids): (Vec<Uuid>, Vec<Uuid>) =
+            participants.iter().copied().unzip();
+
+        // Unread = messages còn hiển thị của người khác, gửi sau khi join / clear
+        // và sau message đã seen
+        let corrections = sqlx::query_as::<_, UnreadCorrection>(
+            r#"
+            UPDATE participants p
+            SET unread_count = expected.unread_count
+            FROM (
+                SELECT
+                    cur.conversation_id,
+                    cur.user_id,
+                    cur.unread_count AS previous_count,
+                    (
+                        SELECT COUNT(*)::int
+                        FROM messages m
+                        WHERE m.conversation_id = cur.conversation_id
+                        AND m.sender_id != cur.user_id
+                        AND m.deleted_at IS NULL
+                        AND m.hidden_at IS NULL
+                        AND m.created_at >= cur.joined_at
+                        AND (cur.cleared_at IS NULL OR m.created_at > cur.cleared_at)
+                        AND (seen.created_at IS NULL OR m.created_at > seen.created_at)
+                    ) AS unread_count
+                FROM participants cur
+                LEFT JOIN messages seen ON seen.id = cur.last_seen_message_id
+                WHERE (cur.conversation_id, cur.user_id) IN (
+                    SELECT * FROM UNNEST($1::uuid[], $2::uuid[])
+                )
+            ) expected
+            WHERE p.conversation_id = expected.conversation_id
+            AND p.user_id = expected.user_id
+            AND p.unread_count != expected.unread_count
+            RETURNING p.conversation_id, p.user_id, expected.previous_count, p.unread_count
+            "#,
+        )
+        .bind(conversation_ids)
+        .bind(user_ids)
+        .fetch_all(tx.connection()?)
+        .await?;
+
+        Ok(corrections)
+    }
+
+    async fn set_archived(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        archived: bool,
+    ) -> Result<Option<ParticipantEntity>, error::SystemError> {
+        let participant = sqlx::query_as::<_, ParticipantEntity>(
+            r#"
+            UPDATE participants
+            SET is_archived = $3
+            WHERE conversation_id = $1
+            AND user_id = $2
+            AND status = 'active'
+            AND (deleted_at IS NULL OR deleted_at = cleared_at)
+            RETURNING *
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .bind(archived)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(participant)
+    }
+
+    async fn set_pinned(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        pinned: bool,
+    ) -> Result<Option<ParticipantEntity>, error::SystemError> {
+        // Giữ pinned_at khi ghim lại conversation đã ghim để thứ tự không đổi
+        let participant = sqlx::query_as::<_, ParticipantEntity>(
+            r#"
+            UPDATE participants
+            SET is_pinned = $3,
+                pinned_at = CASE
+                    WHEN NOT $3 THEN NULL
+                    ELSE COALESCE(pinned_at, NOW())
+                END
+            WHERE conversation_id = $1
+            AND user_id = $2
+            AND status = 'active'
+            AND (deleted_at IS NULL OR deleted_at = cleared_at)
+            RETURNING *
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .bind(pinned)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(participant)
+    }
+
+    async fn set_notification_level(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        level: NotificationLevel,
+    ) -> Result<Option<ParticipantEntity>, error::SystemError> {
+        let participant = sqlx::query_as::<_, ParticipantEntity>(
+            r#"
+            UPDATE participants
+            SET notification_level = $3
+            WHERE conversation_id = $1
+            AND user_id = $2
+            AND status = 'active'
+            AND (deleted_at IS NULL OR deleted_at = cleared_at)
+            RETURNING *
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .bind(level)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(participant)
+    }
+
+    async fn find_draft(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<Option<DraftEntity>, error::SystemError> {
+        let draft = sqlx::query_as::<_, DraftEntity>(
+            "SELECT * FROM conversation_drafts WHERE conversation_id = $1 AND user_id = $2",
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(draft)
+    }
+
+    async fn upsert_draft(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+        content: &str,
+    ) -> Result<DraftEntity, error::SystemError> {
+        let draft = sqlx::query_as::<_, DraftEntity>(
+            r#"
+            INSERT INTO conversation_drafts (conversation_id, user_id, content)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (conversation_id, user_id)
+            DO UPDATE SET content = EXCLUDED.content, updated_at = NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .bind(content)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(draft)
+    }
+
+    async fn delete_draft(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<(), error::SystemError> {
+        sqlx::query("DELETE FROM conversation_drafts WHERE conversation_id = $1 AND user_id = $2")
+            .bind(conversation_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
 }
 
 #[allow(unused)]
@@ -608,14 +1331,11 @@ pub struct LastMessagePgRepository {}
 
 #[async_trait::async_trait]
 impl LastMessageRepository for LastMessagePgRepository {
-    async fn upsert_last_message<'e, E>(
+    async fn upsert_last_message(
         &self,
         last_message: &NewLastMessage,
-        tx: E,
-    ) -> Result<LastMessageEntity, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<LastMessageEntity, error::SystemError> {
         let id = Uuid::now_v7();
         let res = sqlx::query_as::<_, LastMessageEntity>(
             r#"
@@ -633,7 +1353,7 @@ impl LastMessageRepository for LastMessagePgRepository {
         .bind(last_message.conversation_id)
         .bind(last_message.sender_id)
         .bind(last_message.created_at)
-        .fetch_one(tx)
+        .fetch_one(tx.connection()?)
         .await?;
 
         Ok(res)
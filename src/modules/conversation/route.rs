@@ -4,13 +4,75 @@ use actix_web::{
 };
 
 use crate::{middlewares::require_friend, modules::conversation::handle::*};
+use utoipa::OpenApi;
 
 pub fn configure(cfg: &mut ServiceConfig) {
     cfg.service(
         scope("/conversations")
             .service(get_conversations)
+            .service(get_invites)
+            .service(accept_invite)
+            .service(decline_invite)
             .service(get_messages)
+            .service(get_messages_around)
+            .service(export_messages)
             .service(mark_as_seen)
+            .service(update_duplicate_policy)
+            .service(update_conversation_settings)
+            .service(mute_conversation)
+            .service(delete_conversation)
+            .service(update_group)
+            .service(archive_conversation)
+            .service(pin_conversation)
+            .service(update_notification_level)
+            .service(get_draft)
+            .service(save_draft)
+            .service(add_members)
             .service(scope("").wrap(from_fn(require_friend)).service(create_conversation)),
     );
 }
+
+pub fn admin_configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        scope("/conversation-defaults")
+            .service(get_conversation_defaults)
+            .service(update_conversation_defaults),
+    )
+    .service(scope("/unread-counts").service(reconcile_unread_counts));
+}
+
+/// OpenAPI paths của `configure` (scope `/conversations`)
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_conversations,
+    get_invites,
+    accept_invite,
+    decline_invite,
+    get_messages,
+    get_messages_around,
+    export_messages,
+    mark_as_seen,
+    update_duplicate_policy,
+    update_conversation_settings,
+    mute_conversation,
+    delete_conversation,
+    update_group,
+    archive_conversation,
+    pin_conversation,
+    update_notification_level,
+    get_draft,
+    save_draft,
+    add_members,
+    create_conversation
+))]
+pub struct ConversationApiDoc;
+
+/// OpenAPI paths của `admin_configure` (scope `/conversation-defaults`)
+#[derive(OpenApi)]
+#[openapi(paths(get_conversation_defaults, update_conversation_defaults))]
+pub struct AdminConversationApiDoc;
+
+/// OpenAPI paths của `admin_configure` (scope `/unread-counts`)
+#[derive(OpenApi)]
+#[openapi(paths(reconcile_unread_counts))]
+pub struct AdminUnreadCountApiDoc;
@@ -1,9 +1,10 @@
 #![allow(dead_code)]
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::{FromRow, Type};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, PartialEq, Clone, Type, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Clone, Type, Serialize, Deserialize, ToSchema)]
 #[sqlx(type_name = "conversation_type", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum ConversationType {
@@ -11,15 +12,79 @@ pub enum ConversationType {
     Group,
 }
 
-#[derive(Debug, Clone, FromRow)]
+/// Cách xử lý khi một user gửi lặp lại cùng nội dung vượt quá soft limit
+#[derive(Debug, PartialEq, Clone, Copy, Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "duplicate_policy", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicatePolicy {
+    Allow,
+    Collapse,
+    Reject,
+}
+
+/// Phạm vi lịch sử tin nhắn mà một participant được xem
+#[derive(Debug, PartialEq, Clone, Copy, Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "history_visibility", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryVisibility {
+    Full,
+    SinceJoined,
+}
+
+/// Ai được phép tạo group conversation
+#[derive(Debug, PartialEq, Clone, Copy, Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "group_creation_policy", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum GroupCreationPolicy {
+    Everyone,
+    Admins,
+}
+
+/// Trạng thái membership: pending = được mời bởi người không phải bạn bè, chờ chấp nhận
+#[derive(Debug, PartialEq, Clone, Copy, Type, Serialize, Deserialize)]
+#[sqlx(type_name = "participant_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ParticipantStatus {
+    Active,
+    Pending,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
 pub struct ConversationEntity {
     pub id: Uuid,
     #[sqlx(rename = "type")]
     pub _type: ConversationType,
+    pub duplicate_policy: DuplicatePolicy,
+    pub history_visibility: HistoryVisibility,
+    pub message_ttl_seconds: Option<i32>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Mức push notification của participant cho conversation
+#[derive(Debug, PartialEq, Clone, Copy, Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "notification_level", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationLevel {
+    /// Mọi tin nhắn
+    All,
+    /// Chỉ tin nhắn mention user
+    Mentions,
+    /// Không nhận push
+    Nothing,
+}
+
+impl NotificationLevel {
+    /// Participant có nhận push cho tin nhắn hay không
+    pub fn allows(self, mentioned: bool) -> bool {
+        match self {
+            NotificationLevel::All => true,
+            NotificationLevel::Mentions => mentioned,
+            NotificationLevel::Nothing => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, FromRow)]
 pub struct ParticipantEntity {
     pub conversation_id: Uuid,
@@ -27,12 +92,22 @@ pub struct ParticipantEntity {
     pub unread_count: i32,
     pub joined_at: chrono::DateTime<chrono::Utc>,
     pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub status: ParticipantStatus,
+    pub invited_by: Option<Uuid>,
+    pub muted_until: Option<chrono::DateTime<chrono::Utc>>,
+    pub notification_level: NotificationLevel,
+    pub is_archived: bool,
+    pub is_pinned: bool,
+    pub pinned_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// User đã xóa conversation phía mình lúc này, messages trước đó bị ẩn với user
+    pub cleared_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 #[derive(Debug, Clone, FromRow)]
 pub struct GroupConversationEntity {
     pub conversation_id: Uuid,
     pub name: String,
+    pub description: Option<String>,
     pub created_by: Uuid,
     pub avatar_url: Option<String>,
 }
@@ -44,3 +119,26 @@ pub struct LastMessageEntity {
     pub conversation_id: Uuid,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
+
+/// Bản nháp tin nhắn của user trong conversation (đồng bộ giữa các thiết bị)
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct DraftEntity {
+    pub conversation_id: Uuid,
+    pub user_id: Uuid,
+    pub content: String,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Cấu hình mặc định (global) áp dụng khi tạo conversation mới
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct ConversationDefaultsEntity {
+    pub history_visibility: HistoryVisibility,
+    pub message_ttl_seconds: Option<i32>,
+    pub group_creation: GroupCreationPolicy,
+    pub max_group_size: i32,
+    pub allow_history_override: bool,
+    pub allow_ttl_override: bool,
+    /// Messages cũ hơn số ngày này bị xóa vĩnh viễn (`None` = giữ mãi)
+    pub message_retention_days: Option<i32>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
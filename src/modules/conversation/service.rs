@@ -1,71 +1,149 @@
 /// Conversation Service
 ///
 /// Service layer xử lý business logic cho conversations.
-/// Bao gồm tạo conversation, lấy danh sách, mark as seen, và WebSocket notifications.
-use std::{collections::HashMap, sync::Arc};
+/// Bao gồm tạo conversation, lấy danh sách, mark as seen, bản nháp, và WebSocket notifications.
+use std::sync::Arc;
 
 use actix::Addr;
+use futures_util::stream::{self, LocalBoxStream, StreamExt};
 use uuid::Uuid;
 
 use crate::{
-    api::error,
+    api::{error, pagination::PageInfo},
+    configs::{cache::CacheBackend, uow::UnitOfWork},
     modules::{
         conversation::{
-            model::{ConversationDetail, ParticipantDetailWithConversation, ParticipantRow},
+            model::{
+                AddMembersResponse, ConversationDetail, ConversationInvite, ConversationListCursor,
+                ExportChunk, GroupInfo, UnreadReconcileReport, UpdateConversationDefaults,
+                UpdateConversationSettings, UpdateGroupModel,
+            },
+            reconcile,
             repository::{ConversationRepository, ParticipantRepository},
-            schema::{ConversationEntity, ConversationType},
+            schema::{
+                ConversationDefaultsEntity, ConversationEntity, ConversationType, DraftEntity,
+                DuplicatePolicy, GroupCreationPolicy, HistoryVisibility, NotificationLevel,
+                ParticipantEntity, ParticipantStatus,
+            },
+        },
+        message::{
+            model::{
+                CursorDirection, ExportedMessage, GetMessageResponse, MessageQuery,
+                MessagesAroundResponse,
+            },
+            repository::MessageRepository,
+            schema::MessageEntity,
         },
-        message::{model::MessageQuery, repository::MessageRepository, schema::MessageEntity},
+        report::{
+            moderation::{ContentFilter, ContentKind},
+            schema::ReportTargetType,
+        },
+        user::schema::UserRole,
         websocket::{
-            events::{SendToUsers, BroadcastToRoom},
-            message::{LastMessageInfo, SenderInfo, ServerMessage},
+            events::{BroadcastToRoom, SendToOtherSessions, SendToUser, SendToUsers},
+            message::{LastMessageInfo, SenderResolver, ServerMessage},
             server::WebSocketServer,
         },
     },
 };
 
-/// ConversationService với generic repositories để dễ testing và decoupling
+/// Thời gian giữ bản nháp trong Redis, hết hạn thì đọc lại từ Postgres
+const DRAFT_CACHE_TTL: usize = 7 * 24 * 60 * 60;
+
+/// Thời gian cache kết quả kiểm tra membership. Mọi thay đổi participants (thêm member,
+/// chấp nhận / từ chối invite, admin merge tài khoản) xóa cache ngay, TTL ngắn chỉ là
+/// giới hạn trên khi việc xóa cache lỗi (Redis không khả dụng)
+const MEMBERSHIP_CACHE_TTL: usize = 60;
+
+/// Số messages đọc từ DB mỗi lần khi export
+const EXPORT_PAGE_SIZE: usize = 200;
+
+/// Số conversations mỗi trang khi client không truyền `limit`
+const DEFAULT_CONVERSATION_PAGE_SIZE: i64 = 30;
+
+/// Stream các phần của bản export, đọc DB theo từng trang khi được poll
+pub type ExportStream = LocalBoxStream<'static, Result<ExportChunk, error::SystemError>>;
+
+/// Trạng thái của stream export
+enum ExportState {
+    Fetch { after: Option<chrono::DateTime<chrono::Utc>>, remaining: usize },
+    End { next_cursor: Option<chrono::DateTime<chrono::Utc>> },
+    Done,
+}
+
+fn draft_key(user_id: &Uuid, conversation_id: &Uuid) -> String {
+    format!("draft:{user_id}:{conversation_id}")
+}
+
+fn membership_key(conversation_id: &Uuid, user_id: &Uuid) -> String {
+    format!("membership:{conversation_id}:{user_id}")
+}
+
+/// Thời điểm sớm nhất participant được xem messages của conversation (history visibility
+/// since_joined, disappearing TTL và lần xóa conversation gần nhất của user)
+pub fn visible_since(
+    conversation: &ConversationEntity,
+    participant: &ParticipantEntity,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    let joined_since = match conversation.history_visibility {
+        HistoryVisibility::SinceJoined => Some(participant.joined_at),
+        HistoryVisibility::Full => None,
+    };
+    let ttl_since = conversation
+        .message_ttl_seconds
+        .map(|ttl| chrono::Utc::now() - chrono::Duration::seconds(ttl as i64));
+    joined_since.max(ttl_since).max(participant.cleared_at)
+}
+
+/// Dependencies của `ConversationService`
+pub struct ConversationServiceDeps {
+    pub conversation_repo: Arc<dyn ConversationRepository + Send + Sync>,
+    pub participant_repo: Arc<dyn ParticipantRepository + Send + Sync>,
+    pub message_repo: Arc<dyn MessageRepository + Send + Sync>,
+    pub uow: Arc<dyn UnitOfWork>,
+    pub ws_server: Arc<Addr<WebSocketServer>>,
+    pub cache: Arc<dyn CacheBackend>,
+    pub moderation: ContentFilter,
+    pub senders: Arc<dyn SenderResolver>,
+}
+
+/// ConversationService giữ repositories dạng trait object để dễ testing và decoupling
 #[derive(Clone)]
-pub struct ConversationService<R, P, L>
-where
-    R: ConversationRepository + Send + Sync,
-    P: ParticipantRepository + Send + Sync,
-    L: MessageRepository + Send + Sync,
-{
-    conversation_repo: Arc<R>,
-    participant_repo: Arc<P>,
-    message_repo: Arc<L>,
+pub struct ConversationService {
+    conversation_repo: Arc<dyn ConversationRepository + Send + Sync>,
+    participant_repo: Arc<dyn ParticipantRepository + Send + Sync>,
+    message_repo: Arc<dyn MessageRepository + Send + Sync>,
+    uow: Arc<dyn UnitOfWork>,
     ws_server: Arc<Addr<WebSocketServer>>,
+    cache: Arc<dyn CacheBackend>,
+    moderation: ContentFilter,
+    senders: Arc<dyn SenderResolver>,
 }
 
-impl<R, P, L> ConversationService<R, P, L>
-where
-    R: ConversationRepository + Send + Sync,
-    P: ParticipantRepository + Send + Sync,
-    L: MessageRepository + Send + Sync,
-{
+impl ConversationService {
     /// Tạo ConversationService với tất cả dependencies
-    pub fn with_dependencies(
-        conversation_repo: Arc<R>,
-        participant_repo: Arc<P>,
-        message_repo: Arc<L>,
-        ws_server: Arc<Addr<WebSocketServer>>,
-    ) -> Self {
-        ConversationService { conversation_repo, participant_repo, message_repo, ws_server }
-    }
+    pub fn with_dependencies(deps: ConversationServiceDeps) -> Self {
+        let ConversationServiceDeps {
+            conversation_repo,
+            participant_repo,
+            message_repo,
+            uow,
+            ws_server,
+            cache,
+            moderation,
+            senders,
+        } = deps;
 
-    /// Lấy conversation theo ID
-    pub async fn get_by_id(
-        &self,
-        conversation_id: Uuid,
-    ) -> Result<ConversationEntity, error::SystemError> {
-        let conversation = self
-            .conversation_repo
-            .find_by_id(&conversation_id, self.conversation_repo.get_pool())
-            .await?
-            .ok_or_else(|| error::SystemError::not_found("Conversation not found"))?;
-
-        Ok(conversation)
+        ConversationService {
+            conversation_repo,
+            participant_repo,
+            message_repo,
+            uow,
+            ws_server,
+            cache,
+            moderation,
+            senders,
+        }
     }
 
     /// Tạo conversation mới (direct hoặc group)
@@ -78,8 +156,9 @@ where
         name: String,
         member_ids: Vec<Uuid>,
         user_id: Uuid,
+        role: &UserRole,
     ) -> Result<Option<ConversationDetail>, error::SystemError> {
-        let mut tx = self.conversation_repo.get_pool().begin().await?;
+        let mut tx = self.uow.begin().await?;
 
         let participant = member_ids.first().ok_or_else(|| {
             error::SystemError::bad_request(
@@ -87,11 +166,33 @@ where
             )
         })?;
 
+        let moderated = match _type {
+            ConversationType::Group => Some(self.moderation.check(ContentKind::GroupName, name)?),
+            ConversationType::Direct => None,
+        };
+        let name = moderated.as_ref().map(|m| m.content.clone()).unwrap_or_default();
+
+        if _type == ConversationType::Group {
+            let defaults = self.conversation_repo.get_defaults(&mut tx).await?;
+
+            if defaults.group_creation == GroupCreationPolicy::Admins && *role != UserRole::Admin {
+                return Err(error::SystemError::forbidden("Only admins can create groups"));
+            }
+
+            let group_size = member_ids.len() + usize::from(!member_ids.contains(&user_id));
+            if group_size > defaults.max_group_size as usize {
+                return Err(error::SystemError::bad_request(format!(
+                    "Group cannot have more than {} members",
+                    defaults.max_group_size
+                )));
+            }
+        }
+
         let conversation = match _type {
             ConversationType::Direct => {
                 if let Some(conv) = self
                     .conversation_repo
-                    .find_direct_between_users(&user_id, participant, tx.as_mut())
+                    .find_direct_between_users(&user_id, participant, &mut tx)
                     .await?
                 {
                     conv
@@ -111,6 +212,12 @@ where
 
         tx.commit().await?;
 
+        if let Some(moderated) = &moderated {
+            self.moderation
+                .flag(ReportTargetType::User, user_id, ContentKind::GroupName, moderated)
+                .await;
+        }
+
         let conversation_detail =
             self.conversation_repo.find_one_conversation_detail(&conversation.id).await?;
 
@@ -138,69 +245,72 @@ where
         Ok(conversation_detail)
     }
 
-    /// Lấy tất cả conversations của user
+    /// Lấy một trang conversations của user: danh sách chính (mặc định) hoặc danh sách
+    /// archive, conversations được ghim đứng đầu, còn lại theo hoạt động gần nhất.
+    /// Trả về kèm cursor của trang tiếp theo
     pub async fn get_by_user_id(
         &self,
         user_id: Uuid,
-    ) -> Result<Vec<ConversationDetail>, error::SystemError> {
-        let pool = self.conversation_repo.get_pool();
-        let conversations = self
-            .conversation_repo
-            .find_all_conversation_with_details_by_user(&user_id, pool)
-            .await?;
-
-        let conversation_ids: Vec<Uuid> =
-            conversations.iter().map(|conv_row| conv_row.conversation_id).collect();
-
-        let participants = self
-            .participant_repo
-            .find_participants_by_conversation_id(&conversation_ids, pool)
-            .await?;
+        archived: bool,
+        limit: Option<i64>,
+        cursor: Option<String>,
+    ) -> Result<(Vec<ConversationDetail>, PageInfo), error::SystemError> {
+        let cursor = match cursor {
+            Some(c) => Some(
+                ConversationListCursor::parse(&c)
+                    .ok_or_else(|| error::SystemError::bad_request("Invalid cursor format"))?,
+            ),
+            None => None,
+        };
+        let limit = limit.unwrap_or(DEFAULT_CONVERSATION_PAGE_SIZE);
 
-        let participant_map = participants.into_iter().fold(
-            HashMap::<Uuid, Vec<ParticipantDetailWithConversation>>::new(),
-            |mut acc, participant| {
-                acc.entry(participant.conversation_id).or_insert_with(Vec::new).push(participant);
-                acc
-            },
-        );
+        // Lấy thừa 1 để biết còn trang sau
+        let (mut conversations, total) = tokio::try_join!(
+            self.conversation_repo.find_all_conversation_with_details_by_user(
+                &user_id,
+                archived,
+                cursor.as_ref(),
+                limit + 1,
+            ),
+            self.conversation_repo.count_conversations_by_user(&user_id, archived),
+        )?;
 
-        let res = conversations.into_iter().map(|conv| {
-            let participants: Vec<ParticipantRow> = participant_map
-                .get(&conv.conversation_id)
-                .cloned()
-                .unwrap_or_default()
-                .into_iter()
-                .map(|p| ParticipantRow {
-                    user_id: p.user_id,
-                    display_name: p.display_name,
-                    avatar_url: p.avatar_url,
-                    unread_count: p.unread_count,
-                    joined_at: p.joined_at,
-                })
-                .collect();
+        let has_more = conversations.len() > limit as usize;
+        conversations.truncate(limit as usize);
+        let next_cursor = if has_more {
+            conversations.last().map(|c| ConversationListCursor::from_row(c).encode())
+        } else {
+            None
+        };
 
-            ConversationDetail {
-                conversation_id: conv.conversation_id,
-                _type: conv._type,
-                group_info: conv.group_info,
-                last_message: conv.last_message,
-                participants,
-                created_at: conv.created_at,
-                updated_at: conv.updated_at,
-            }
+        let res = conversations.into_iter().map(|conv| ConversationDetail {
+            conversation_id: conv.conversation_id,
+            _type: conv._type,
+            group_info: conv.group_info,
+            last_message: conv.last_message,
+            participants: conv.participants,
+            is_archived: conv.is_archived,
+            is_pinned: conv.is_pinned,
+            created_at: conv.created_at,
+            updated_at: conv.updated_at,
         });
 
-        Ok(res.collect())
+        // Thứ tự thay đổi theo hoạt động nên danh sách chỉ cuộn một chiều (không có prev_cursor)
+        Ok((res.collect(), PageInfo::new(next_cursor, None).with_total(total)))
     }
 
     /// Lấy messages của conversation với cursor-based pagination
+    ///
+    /// Áp dụng history visibility (since_joined) và disappearing TTL của conversation.
+    /// Messages trả về theo thứ tự thời gian, cursor dùng để lấy tiếp theo cùng `direction`
     pub async fn get_message(
         &self,
         conversation_id: Uuid,
+        user_id: Uuid,
         limit: i32,
         cursor: Option<String>,
-    ) -> Result<(Vec<MessageEntity>, Option<String>), error::SystemError> {
+        direction: CursorDirection,
+    ) -> Result<GetMessageResponse, error::SystemError> {
         let created_at = match cursor {
             Some(c) => Some(
                 chrono::DateTime::parse_from_rfc3339(&c)
@@ -210,39 +320,167 @@ where
             None => None,
         };
 
+        let visible_since = self.visible_since(conversation_id, user_id).await?;
+
         let mut messages = self
             .message_repo
             .find_by_query(
-                &MessageQuery { conversation_id, created_at },
+                &MessageQuery {
+                    conversation_id,
+                    created_at,
+                    visible_since,
+                    direction,
+                    viewer_id: Some(user_id),
+                },
                 limit,
-                self.message_repo.get_pool(),
             )
             .await?;
 
-        let next_cursor = if messages.len() > limit as usize {
-            messages.pop().map(|m| m.created_at)
-        } else {
-            None
-        };
+        // Cursor là message cuối cùng được trả về (không phải message thừa để dò),
+        // vì query tiếp theo lọc `created_at` strict
+        let has_more = messages.len() > limit as usize;
+        messages.truncate(limit as usize);
+        let next_cursor = if has_more { messages.last().map(|m| m.created_at) } else { None };
+        // Trang đầu tiên (không có cursor) không có trang ngược lại
+        let prev_cursor = created_at.and(messages.first().map(|m| m.created_at));
 
-        messages.reverse();
-        Ok((messages, next_cursor.map(|c| c.to_rfc3339())))
+        if direction == CursorDirection::Before {
+            messages.reverse();
+        }
+        let messages = messages.into_iter().map(MessageEntity::into_tombstone).collect();
+        let next_cursor = next_cursor.map(|c| c.to_rfc3339());
+        let page = PageInfo::new(next_cursor.clone(), prev_cursor.map(|c| c.to_rfc3339()));
+        Ok(GetMessageResponse { messages, cursor: next_cursor, page })
     }
 
-    /// Lấy participants của conversation
-    pub async fn get_participants_by_conversation_id(
+    /// Lấy `limit` messages trước và sau một message (jump tới unread đầu tiên hoặc kết
+    /// quả search), kèm cursor hai chiều để tiếp tục cuộn bằng `get_message`
+    pub async fn get_messages_around(
         &self,
         conversation_id: Uuid,
-    ) -> Result<Vec<ParticipantDetailWithConversation>, error::SystemError> {
-        let participants = self
-            .participant_repo
-            .find_participants_by_conversation_id(
-                &[conversation_id],
-                self.conversation_repo.get_pool(),
-            )
+        user_id: Uuid,
+        message_id: Uuid,
+        limit: i32,
+    ) -> Result<MessagesAroundResponse, error::SystemError> {
+        let visible_since = self.visible_since(conversation_id, user_id).await?;
+
+        let mut messages = self
+            .message_repo
+            .find_around(&conversation_id, &user_id, &message_id, limit, visible_since)
             .await?;
 
-        Ok(participants)
+        let target = messages
+            .iter()
+            .position(|m| m.id == message_id)
+            .ok_or_else(|| error::SystemError::not_found("Message not found"))?;
+
+        // Repository trả thêm 1 message mỗi phía để biết còn messages hay không
+        let limit = limit as usize;
+        let has_newer = messages.len() - target - 1 > limit;
+        messages.truncate(target + 1 + limit);
+        let has_older = target > limit;
+        messages.drain(..target.saturating_sub(limit));
+
+        let before_cursor =
+            messages.first().filter(|_| has_older).map(|m| m.created_at.to_rfc3339());
+        let after_cursor = messages.last().filter(|_| has_newer).map(|m| m.created_at.to_rfc3339());
+        let messages = messages.into_iter().map(MessageEntity::into_tombstone).collect();
+
+        Ok(MessagesAroundResponse { messages, before_cursor, after_cursor })
+    }
+
+    /// Export tối đa `limit` messages sau `cursor` mà user được xem, theo thứ tự thời gian
+    ///
+    /// Messages được đọc từ DB theo từng trang `EXPORT_PAGE_SIZE` khi client nhận dữ liệu,
+    /// nên export lớn không bị giữ toàn bộ trong memory
+    pub async fn export_messages(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        cursor: Option<String>,
+        limit: usize,
+    ) -> Result<ExportStream, error::SystemError> {
+        let after = match cursor {
+            Some(c) => Some(
+                chrono::DateTime::parse_from_rfc3339(&c)
+                    .map_err(|_| error::SystemError::bad_request("Invalid cursor format"))?
+                    .with_timezone(&chrono::Utc),
+            ),
+            None => None,
+        };
+
+        let visible_since = self.visible_since(conversation_id, user_id).await?;
+        let message_repo = self.message_repo.clone();
+
+        let pages = stream::unfold(ExportState::Fetch { after, remaining: limit }, move |state| {
+            let message_repo = message_repo.clone();
+            async move {
+                let (after, remaining) = match state {
+                    ExportState::Fetch { after, remaining } => (after, remaining),
+                    ExportState::End { next_cursor } => {
+                        let next_cursor = next_cursor.map(|c| c.to_rfc3339());
+                        return Some((Ok(ExportChunk::End { next_cursor }), ExportState::Done));
+                    }
+                    ExportState::Done => return None,
+                };
+
+                let page_size = remaining.min(EXPORT_PAGE_SIZE);
+                let rows = match message_repo
+                    .find_for_export(
+                        &conversation_id,
+                        &user_id,
+                        after,
+                        visible_since,
+                        page_size as i32,
+                    )
+                    .await
+                {
+                    Ok(rows) => rows,
+                    Err(e) => return Some((Err(e), ExportState::Done)),
+                };
+
+                // Repository trả thêm 1 message để biết còn messages hay không
+                let has_more = rows.len() > page_size;
+                let messages: Vec<ExportedMessage> =
+                    rows.into_iter().take(page_size).map(ExportedMessage::from).collect();
+                let last = messages.last().map(|m| m.message.created_at).or(after);
+                let remaining = remaining - messages.len();
+
+                let next = if !has_more {
+                    ExportState::End { next_cursor: None }
+                } else if remaining == 0 {
+                    ExportState::End { next_cursor: last }
+                } else {
+                    ExportState::Fetch { after: last, remaining }
+                };
+                Some((Ok(ExportChunk::Messages(messages)), next))
+            }
+        });
+
+        Ok(pages.boxed_local())
+    }
+
+    /// `visible_since` của user trong conversation, lỗi nếu user không phải participant
+    async fn visible_since(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>, error::SystemError> {
+        let conversation = self
+            .conversation_repo
+            .find_by_id(&conversation_id, &mut self.uow.autocommit().await?)
+            .await?
+            .ok_or_else(|| error::SystemError::not_found("Conversation not found"))?;
+
+        let participant =
+            self.participant_repo.find_participant(&conversation_id, &user_id).await?.ok_or_else(
+                || {
+                    error::SystemError::forbidden("User is not a participant of this conversation")
+                        .with_code(error::ErrorCode::NotAMember)
+                },
+            )?;
+
+        Ok(visible_since(&conversation, &participant))
     }
 
     /// Kiểm tra user có phải member của conversation không
@@ -255,11 +493,45 @@ where
             .get_conversation_and_check_membership(
                 &conversation_id,
                 &user_id,
-                self.conversation_repo.get_pool(),
+                &mut self.uow.autocommit().await?,
             )
             .await
     }
 
+    /// Kiểm tra membership qua Redis (cache cả kết quả không phải member), cache miss
+    /// hoặc Redis lỗi thì đọc Postgres
+    pub async fn is_member(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, error::SystemError> {
+        let key = membership_key(&conversation_id, &user_id);
+        match self.cache.get::<bool>(&key).await {
+            Ok(Some(is_member)) => return Ok(is_member),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to read membership from Redis: {}", e),
+        }
+
+        let (_, is_member) =
+            self.get_conversation_and_check_membership(conversation_id, user_id).await?;
+
+        if let Err(e) = self.cache.set(&key, &is_member, MEMBERSHIP_CACHE_TTL).await {
+            tracing::warn!("Failed to cache membership: {}", e);
+        }
+
+        Ok(is_member)
+    }
+
+    /// Xóa cache membership của các users trong conversation, gọi sau khi commit
+    /// thay đổi participants
+    pub async fn invalidate_membership(&self, conversation_id: Uuid, user_ids: &[Uuid]) {
+        for user_id in user_ids {
+            if let Err(e) = self.cache.delete(&membership_key(&conversation_id, user_id)).await {
+                tracing::warn!("Failed to invalidate membership cache: {}", e);
+            }
+        }
+    }
+
     /// Mark messages as seen
     ///
     /// Cập nhật last_seen_message_id và reset unread count
@@ -269,25 +541,18 @@ where
         conversation_id: Uuid,
         user_id: Uuid,
     ) -> Result<(), error::SystemError> {
-        let mut tx = self.conversation_repo.get_pool().begin().await?;
-
-        // Verify user is a participant of the conversation
-        let (_, is_member) = self
-            .conversation_repo
-            .get_conversation_and_check_membership(&conversation_id, &user_id, tx.as_mut())
-            .await?;
-
-        if !is_member {
+        if !self.is_member(conversation_id, user_id).await? {
             return Err(error::SystemError::forbidden(
                 "User is not a participant of this conversation",
-            ));
+            )
+            .with_code(error::ErrorCode::NotAMember));
         }
 
+        let mut tx = self.uow.begin().await?;
+
         // Get last message of the conversation
-        let last_message = self
-            .message_repo
-            .get_last_message_by_conversation(&conversation_id, tx.as_mut())
-            .await?;
+        let last_message =
+            self.message_repo.get_last_message_by_conversation(&conversation_id, &mut tx).await?;
 
         if let Some(msg) = last_message {
             // Check if user is the sender of the last message
@@ -299,7 +564,7 @@ where
 
             // Mark as seen with the last message ID
             self.participant_repo
-                .mark_as_seen(&conversation_id, &user_id, &msg.id, tx.as_mut())
+                .mark_as_seen(&conversation_id, &user_id, &msg.id, &mut tx)
                 .await?;
 
             tx.commit().await?;
@@ -309,11 +574,7 @@ where
                 _id: msg.id,
                 content: msg.content.clone(),
                 created_at: msg.created_at.to_rfc3339(),
-                sender: SenderInfo {
-                    _id: msg.sender_id,
-                    display_name: String::new(),
-                    avatar_url: None,
-                },
+                sender: self.senders.resolve_message_sender(&msg).await,
             };
 
             // Tạo conversation update info
@@ -334,4 +595,831 @@ where
 
         Ok(())
     }
+
+    /// Tính lại unread_count của mọi active participants từ messages chưa đọc
+    ///
+    /// Mỗi batch chạy trong một transaction; participants có count lệch được sửa và
+    /// nhận event `unread-count-corrected` để client cập nhật badge
+    pub async fn reconcile_unread_counts(
+        &self,
+        batch_size: i64,
+    ) -> Result<UnreadReconcileReport, error::SystemError> {
+        reconcile::reconcile_all(
+            self.uow.as_ref(),
+            self.participant_repo.as_ref(),
+            batch_size,
+            |correction| {
+                self.ws_server.do_send(SendToUser {
+                    user_id: correction.user_id,
+                    message: ServerMessage::UnreadCountCorrected {
+                        conversation_id: correction.conversation_id,
+                        unread_count: correction.unread_count,
+                    },
+                });
+            },
+        )
+        .await
+    }
+
+    /// Thêm members vào group
+    ///
+    /// Bạn bè và bot của người thêm được vào group ngay, những người còn lại nhận invite
+    /// (pending) và phải chấp nhận trước khi group xuất hiện trong danh sách của họ
+    pub async fn add_members(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        member_ids: Vec<Uuid>,
+    ) -> Result<AddMembersResponse, error::SystemError> {
+        let mut tx = self.uow.begin().await?;
+
+        let (conversation, is_member) = self
+            .conversation_repo
+            .get_conversation_and_check_membership(&conversation_id, &user_id, &mut tx)
+            .await?;
+
+        let conversation =
+            conversation.ok_or_else(|| error::SystemError::not_found("Conversation not found"))?;
+
+        if !is_member {
+            return Err(error::SystemError::forbidden(
+                "User is not a participant of this conversation",
+            )
+            .with_code(error::ErrorCode::NotAMember));
+        }
+
+        if conversation._type != ConversationType::Group {
+            return Err(error::SystemError::bad_request("Members can only be added to groups"));
+        }
+
+        let mut member_ids = member_ids;
+        member_ids.retain(|id| *id != user_id);
+        member_ids.sort();
+        member_ids.dedup();
+
+        let defaults = self.conversation_repo.get_defaults(&mut tx).await?;
+        let current_size = self.participant_repo.count_members(&conversation_id, &mut tx).await?;
+        if current_size + member_ids.len() as i64 > defaults.max_group_size as i64 {
+            return Err(error::SystemError::bad_request(format!(
+                "Group cannot have more than {} members",
+                defaults.max_group_size
+            )));
+        }
+
+        let participants = self
+            .participant_repo
+            .add_members(&conversation_id, &user_id, &member_ids, &mut tx)
+            .await?;
+
+        tx.commit().await?;
+
+        let (added, invited): (Vec<_>, Vec<_>) =
+            participants.into_iter().partition(|p| p.status == ParticipantStatus::Active);
+        let added: Vec<Uuid> = added.into_iter().map(|p| p.user_id).collect();
+        let invited: Vec<Uuid> = invited.into_iter().map(|p| p.user_id).collect();
+
+        self.invalidate_membership(conversation_id, &added).await;
+
+        let conversation_detail =
+            self.conversation_repo.find_one_conversation_detail(&conversation_id).await?;
+
+        if !added.is_empty() {
+            let conversation_json = serde_json::to_value(&conversation_detail).map_err(|e| {
+                error::SystemError::internal_error(format!(
+                    "Failed to serialize conversation: {}",
+                    e
+                ))
+            })?;
+
+            self.ws_server.do_send(SendToUsers {
+                user_ids: added.clone(),
+                message: ServerMessage::NewGroup { conversation: conversation_json },
+            });
+        }
+
+        if !invited.is_empty() {
+            let group_name = conversation_detail
+                .and_then(|detail| detail.group_info)
+                .map(|group| group.name)
+                .unwrap_or_default();
+
+            self.ws_server.do_send(SendToUsers {
+                user_ids: invited.clone(),
+                message: ServerMessage::ConversationInvite {
+                    conversation_id,
+                    invited_by: user_id,
+                    group_name,
+                },
+            });
+        }
+
+        Ok(AddMembersResponse { added, invited })
+    }
+
+    /// Đổi tên / mô tả group, chỉ người tạo group hoặc Admin
+    ///
+    /// Đổi tên sinh system message "X renamed the group" trong cùng transaction;
+    /// sau commit members nhận `group-updated` (kèm `new-message` nếu đổi tên)
+    pub async fn update_group(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        role: &UserRole,
+        update: UpdateGroupModel,
+    ) -> Result<GroupInfo, error::SystemError> {
+        let name = update.name.map(|name| name.trim().to_string());
+        if name.as_deref().is_some_and(str::is_empty) {
+            return Err(error::SystemError::bad_request("Group name cannot be empty"));
+        }
+        let name =
+            name.map(|name| self.moderation.check(ContentKind::GroupName, name)).transpose()?;
+        // Mô tả rỗng được lưu như xóa mô tả
+        let description = update
+            .description
+            .map(|description| {
+                description
+                    .map(|d| d.trim().to_string())
+                    .filter(|d| !d.is_empty())
+                    .map(|d| self.moderation.check(ContentKind::GroupDescription, d))
+                    .transpose()
+            })
+            .transpose()?;
+        let update = UpdateGroupModel {
+            name: name.as_ref().map(|name| name.content.clone()),
+            description: description.as_ref().map(|d| d.as_ref().map(|d| d.content.clone())),
+        };
+        if update.is_empty() {
+            return Err(error::SystemError::bad_request("No fields to update"));
+        }
+
+        let mut tx = self.uow.begin().await?;
+
+        let (conversation, is_member) = self
+            .conversation_repo
+            .get_conversation_and_check_membership(&conversation_id, &user_id, &mut tx)
+            .await?;
+
+        let conversation =
+            conversation.ok_or_else(|| error::SystemError::not_found("Conversation not found"))?;
+
+        if !is_member {
+            return Err(error::SystemError::forbidden(
+                "User is not a participant of this conversation",
+            )
+            .with_code(error::ErrorCode::NotAMember));
+        }
+
+        if conversation._type != ConversationType::Group {
+            return Err(error::SystemError::bad_request("Only groups can be renamed"));
+        }
+
+        let previous = self
+            .conversation_repo
+            .find_group_for_update(&conversation_id, &mut tx)
+            .await?
+            .ok_or_else(|| error::SystemError::not_found("Group not found"))?;
+
+        if previous.created_by != user_id && *role != UserRole::Admin {
+            return Err(error::SystemError::forbidden("Only group admins can update the group"));
+        }
+
+        let group = self.conversation_repo.update_group(&conversation_id, &update, &mut tx).await?;
+
+        let mut renamed = None;
+        if group.name != previous.name {
+            let display_name = self
+                .participant_repo
+                .find_participants_by_conversation_id(&[conversation_id], &mut tx)
+                .await?
+                .into_iter()
+                .find(|p| p.user_id == user_id)
+                .map(|p| p.display_name)
+                .unwrap_or_default();
+
+            let message = self
+                .message_repo
+                .create_system(
+                    &conversation_id,
+                    &user_id,
+                    &format!("{} renamed the group to \"{}\"", display_name, group.name),
+                    &mut tx,
+                )
+                .await?;
+
+            self.conversation_repo.update_timestamp(&conversation_id, &mut tx).await?;
+
+            let unread_counts =
+                self.participant_repo.get_unread_counts(&conversation_id, &mut tx).await?;
+
+            renamed = Some((message, unread_counts));
+        }
+
+        tx.commit().await?;
+
+        let flagged = [
+            (ContentKind::GroupName, name.as_ref()),
+            (ContentKind::GroupDescription, description.as_ref().and_then(Option::as_ref)),
+        ];
+        for (kind, moderated) in flagged {
+            if let Some(moderated) = moderated {
+                self.moderation.flag(ReportTargetType::User, user_id, kind, moderated).await;
+            }
+        }
+
+        self.ws_server.do_send(BroadcastToRoom {
+            conversation_id,
+            message: ServerMessage::GroupUpdated {
+                conversation_id,
+                name: group.name.clone(),
+                description: group.description.clone(),
+                updated_by: user_id,
+            },
+            skip_user_id: None,
+        });
+
+        if let Some((message, unread_counts)) = renamed {
+            let last_message = LastMessageInfo {
+                _id: message.id,
+                content: message.content.clone(),
+                created_at: message.created_at.to_rfc3339(),
+                sender: self.senders.resolve_message_sender(&message).await,
+            };
+            let unread_counts: serde_json::Value = unread_counts
+                .iter()
+                .map(|(k, v)| (k.to_string(), serde_json::Value::Number((*v).into())))
+                .collect();
+
+            self.ws_server.do_send(BroadcastToRoom {
+                conversation_id,
+                message: ServerMessage::new_message(
+                    serde_json::to_value(&message).unwrap_or_default(),
+                    conversation_id,
+                    last_message,
+                    message.created_at.to_rfc3339(),
+                    unread_counts,
+                ),
+                skip_user_id: None,
+            });
+        }
+
+        Ok(GroupInfo {
+            name: group.name,
+            description: group.description,
+            created_by: group.created_by,
+            avatar_url: group.avatar_url,
+        })
+    }
+
+    /// Lấy các group invite đang chờ user phản hồi
+    pub async fn get_invites(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<ConversationInvite>, error::SystemError> {
+        self.participant_repo.find_pending_invites(&user_id).await
+    }
+
+    /// Chấp nhận group invite, trả về conversation để client thêm vào danh sách
+    pub async fn accept_invite(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<ConversationDetail>, error::SystemError> {
+        let accepted = self.participant_repo.accept_invite(&conversation_id, &user_id).await?;
+
+        if !accepted {
+            return Err(error::SystemError::not_found("Invite not found"));
+        }
+
+        self.invalidate_membership(conversation_id, &[user_id]).await;
+
+        self.conversation_repo.find_one_conversation_detail(&conversation_id).await
+    }
+
+    /// Từ chối group invite
+    pub async fn decline_invite(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        let declined = self.participant_repo.decline_invite(&conversation_id, &user_id).await?;
+
+        if !declined {
+            return Err(error::SystemError::not_found("Invite not found"));
+        }
+
+        self.invalidate_membership(conversation_id, &[user_id]).await;
+
+        Ok(())
+    }
+
+    /// Tắt push notification của conversation tới thời điểm `muted_until` (None = bật lại)
+    pub async fn mute_conversation(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        muted_until: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<(), error::SystemError> {
+        if muted_until.is_some_and(|until| until <= chrono::Utc::now()) {
+            return Err(error::SystemError::bad_request("Mute end time must be in the future"));
+        }
+
+        let updated =
+            self.participant_repo.set_muted_until(&conversation_id, &user_id, muted_until).await?;
+
+        if !updated {
+            return Err(error::SystemError::forbidden(
+                "User is not a participant of this conversation",
+            )
+            .with_code(error::ErrorCode::NotAMember));
+        }
+
+        Ok(())
+    }
+
+    /// Xóa conversation phía user: ẩn khỏi danh sách và ẩn lịch sử hiện tại với user.
+    /// Conversation xuất hiện lại (chỉ với messages mới) khi có tin nhắn mới
+    pub async fn delete_for_user(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        let cleared = self.participant_repo.clear_for_user(&conversation_id, &user_id).await?;
+
+        if !cleared {
+            return Err(error::SystemError::forbidden(
+                "User is not a participant of this conversation",
+            )
+            .with_code(error::ErrorCode::NotAMember));
+        }
+
+        Ok(())
+    }
+
+    /// Archive / bỏ archive conversation cho user, đồng bộ tới các thiết bị khác
+    pub async fn archive_conversation(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        archived: bool,
+        session_id: Option<Uuid>,
+    ) -> Result<(), error::SystemError> {
+        let participant =
+            self.participant_repo.set_archived(&conversation_id, &user_id, archived).await?;
+
+        self.notify_participant_flags(participant, session_id)
+    }
+
+    /// Ghim / bỏ ghim conversation cho user, đồng bộ tới các thiết bị khác
+    pub async fn pin_conversation(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        pinned: bool,
+        session_id: Option<Uuid>,
+    ) -> Result<(), error::SystemError> {
+        let participant =
+            self.participant_repo.set_pinned(&conversation_id, &user_id, pinned).await?;
+
+        self.notify_participant_flags(participant, session_id)
+    }
+
+    /// Đổi mức push notification của conversation cho user, đồng bộ tới các thiết bị khác
+    pub async fn update_notification_level(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        level: NotificationLevel,
+        session_id: Option<Uuid>,
+    ) -> Result<(), error::SystemError> {
+        let participant = self
+            .participant_repo
+            .set_notification_level(&conversation_id, &user_id, level)
+            .await?
+            .ok_or_else(|| {
+                error::SystemError::forbidden("User is not a participant of this conversation")
+                    .with_code(error::ErrorCode::NotAMember)
+            })?;
+
+        self.ws_server.do_send(SendToOtherSessions {
+            user_id,
+            skip_session_id: session_id,
+            message: ServerMessage::NotificationLevelUpdated {
+                conversation_id,
+                level: participant.notification_level,
+            },
+        });
+
+        Ok(())
+    }
+
+    /// Gửi `conversation-updated` (flags archive/pin mới) tới các sessions khác của user
+    fn notify_participant_flags(
+        &self,
+        participant: Option<ParticipantEntity>,
+        session_id: Option<Uuid>,
+    ) -> Result<(), error::SystemError> {
+        let participant = participant.ok_or_else(|| {
+            error::SystemError::forbidden("User is not a participant of this conversation")
+                .with_code(error::ErrorCode::NotAMember)
+        })?;
+
+        self.ws_server.do_send(SendToOtherSessions {
+            user_id: participant.user_id,
+            skip_session_id: session_id,
+            message: ServerMessage::ConversationUpdated {
+                conversation_id: participant.conversation_id,
+                is_archived: participant.is_archived,
+                is_pinned: participant.is_pinned,
+            },
+        });
+
+        Ok(())
+    }
+
+    /// Lấy bản nháp của user trong conversation: đọc Redis trước, fallback Postgres
+    /// (cache miss hoặc Redis lỗi) rồi ghi lại vào cache
+    pub async fn get_draft(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<DraftEntity>, error::SystemError> {
+        self.ensure_participant(conversation_id, user_id).await?;
+
+        let key = draft_key(&user_id, &conversation_id);
+        match self.cache.get::<DraftEntity>(&key).await {
+            Ok(Some(draft)) => return Ok(Some(draft)),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to read draft from Redis: {}", e),
+        }
+
+        let draft = self.participant_repo.find_draft(&conversation_id, &user_id).await?;
+
+        if let Some(draft) = &draft {
+            if let Err(e) = self.cache.set(&key, draft, DRAFT_CACHE_TTL).await {
+                tracing::warn!("Failed to cache draft: {}", e);
+            }
+        }
+
+        Ok(draft)
+    }
+
+    /// Lưu (hoặc xóa nếu nội dung rỗng) bản nháp, rồi gửi `draft-updated` tới các
+    /// sessions khác của user. `session_id` là session WebSocket của thiết bị gửi request
+    pub async fn save_draft(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        content: String,
+        session_id: Option<Uuid>,
+    ) -> Result<Option<DraftEntity>, error::SystemError> {
+        self.ensure_participant(conversation_id, user_id).await?;
+
+        let key = draft_key(&user_id, &conversation_id);
+
+        // Postgres là nguồn bền vững, lỗi Redis không làm hỏng request
+        let draft = if content.trim().is_empty() {
+            self.participant_repo.delete_draft(&conversation_id, &user_id).await?;
+            if let Err(e) = self.cache.delete(&key).await {
+                tracing::warn!("Failed to delete cached draft: {}", e);
+            }
+            None
+        } else {
+            let draft =
+                self.participant_repo.upsert_draft(&conversation_id, &user_id, &content).await?;
+            if let Err(e) = self.cache.set(&key, &draft, DRAFT_CACHE_TTL).await {
+                tracing::warn!("Failed to cache draft: {}", e);
+            }
+            Some(draft)
+        };
+
+        self.ws_server.do_send(SendToOtherSessions {
+            user_id,
+            skip_session_id: session_id,
+            message: ServerMessage::DraftUpdated {
+                conversation_id,
+                content: draft.as_ref().map(|d| d.content.clone()),
+                updated_at: draft
+                    .as_ref()
+                    .map_or_else(chrono::Utc::now, |d| d.updated_at)
+                    .to_rfc3339(),
+            },
+        });
+
+        Ok(draft)
+    }
+
+    /// Lỗi nếu conversation không tồn tại hoặc user không phải participant
+    async fn ensure_participant(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        let (conversation, is_member) =
+            self.get_conversation_and_check_membership(conversation_id, user_id).await?;
+
+        if conversation.is_none() {
+            return Err(error::SystemError::not_found("Conversation not found"));
+        }
+        if !is_member {
+            return Err(error::SystemError::forbidden(
+                "User is not a participant of this conversation",
+            )
+            .with_code(error::ErrorCode::NotAMember));
+        }
+
+        Ok(())
+    }
+
+    /// Cấu hình cách xử lý tin nhắn trùng lặp cho conversation
+    pub async fn update_duplicate_policy(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        role: &UserRole,
+        policy: DuplicatePolicy,
+    ) -> Result<(), error::SystemError> {
+        self.ensure_settings_admin(conversation_id, user_id, role).await?;
+
+        self.conversation_repo.update_duplicate_policy(&conversation_id, &policy).await
+    }
+
+    /// Cài đặt của group chỉ do group admin (người tạo) hoặc platform Admin thay đổi,
+    /// với direct conversation thì cả hai participant đều được
+    async fn ensure_settings_admin(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        role: &UserRole,
+    ) -> Result<(), error::SystemError> {
+        let mut conn = self.uow.autocommit().await?;
+
+        let (conversation, is_member) = self
+            .conversation_repo
+            .get_conversation_and_check_membership(&conversation_id, &user_id, &mut conn)
+            .await?;
+
+        let conversation =
+            conversation.ok_or_else(|| error::SystemError::not_found("Conversation not found"))?;
+
+        if !is_member {
+            return Err(error::SystemError::forbidden(
+                "User is not a participant of this conversation",
+            )
+            .with_code(error::ErrorCode::NotAMember));
+        }
+
+        if conversation._type != ConversationType::Group || *role == UserRole::Admin {
+            return Ok(());
+        }
+
+        let group = self
+            .conversation_repo
+            .find_group_for_update(&conversation_id, &mut conn)
+            .await?
+            .ok_or_else(|| error::SystemError::not_found("Group not found"))?;
+
+        if group.created_by != user_id {
+            return Err(error::SystemError::forbidden(
+                "Only group admins can change conversation settings",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Override history visibility / disappearing TTL cho conversation,
+    /// chỉ cho phép với những thuộc tính mà global policy cho override
+    pub async fn update_settings(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        role: &UserRole,
+        settings: UpdateConversationSettings,
+    ) -> Result<ConversationEntity, error::SystemError> {
+        self.ensure_settings_admin(conversation_id, user_id, role).await?;
+
+        if let Some(Some(ttl)) = settings.message_ttl_seconds {
+            if ttl <= 0 {
+                return Err(error::SystemError::bad_request("Message TTL must be positive"));
+            }
+        }
+
+        let defaults =
+            self.conversation_repo.get_defaults(&mut self.uow.autocommit().await?).await?;
+
+        if settings.history_visibility.is_some() && !defaults.allow_history_override {
+            return Err(error::SystemError::forbidden(
+                "History visibility cannot be changed per conversation",
+            ));
+        }
+
+        if settings.message_ttl_seconds.is_some() && !defaults.allow_ttl_override {
+            return Err(error::SystemError::forbidden(
+                "Disappearing message TTL cannot be changed per conversation",
+            ));
+        }
+
+        self.conversation_repo.update_settings(&conversation_id, &settings).await
+    }
+
+    /// Admin: lấy cấu hình mặc định cho conversations
+    pub async fn get_defaults(&self) -> Result<ConversationDefaultsEntity, error::SystemError> {
+        self.conversation_repo.get_defaults(&mut self.uow.autocommit().await?).await
+    }
+
+    /// Admin: cập nhật cấu hình mặc định (chỉ áp dụng cho conversations tạo sau đó)
+    pub async fn update_defaults(
+        &self,
+        defaults: UpdateConversationDefaults,
+    ) -> Result<ConversationDefaultsEntity, error::SystemError> {
+        if let Some(Some(ttl)) = defaults.message_ttl_seconds {
+            if ttl <= 0 {
+                return Err(error::SystemError::bad_request("Message TTL must be positive"));
+            }
+        }
+        if let Some(Some(days)) = defaults.message_retention_days {
+            if days <= 0 {
+                return Err(error::SystemError::bad_request("Message retention must be positive"));
+            }
+        }
+
+        self.conversation_repo.update_defaults(&defaults).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix::Actor;
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+    use super::*;
+    use crate::{
+        configs::{cache::MemoryCache, uow::MemoryUnitOfWork},
+        constants::init_test_env,
+        modules::{
+            conversation::repository_mock::ConversationRepositoryMock,
+            message::{model::InsertMessage, repository_mock::MessageRepositoryMock},
+            report::repository_pg::ReportRepositoryPg,
+            user::repository_mock::UserRepositoryMock,
+            websocket::message::SenderInfo,
+        },
+    };
+
+    struct UnknownSenders;
+
+    #[async_trait::async_trait]
+    impl SenderResolver for UnknownSenders {
+        async fn resolve_sender(&self, user_id: Uuid) -> SenderInfo {
+            SenderInfo::unknown(user_id)
+        }
+    }
+
+    struct Fixture {
+        users: UserRepositoryMock,
+        conversations: ConversationRepositoryMock,
+        messages: MessageRepositoryMock,
+        service: ConversationService,
+    }
+
+    fn fixture() -> Fixture {
+        init_test_env();
+        let users = UserRepositoryMock::default();
+        let conversations = ConversationRepositoryMock::new(users.clone());
+        let messages = MessageRepositoryMock::new(conversations.clone());
+        // Report repository chỉ được dùng khi nội dung bị flag, pool không bao giờ kết nối
+        let reports = ReportRepositoryPg::new(
+            PgPoolOptions::new().connect_lazy_with(PgConnectOptions::new()),
+        );
+
+        let service = ConversationService::with_dependencies(ConversationServiceDeps {
+            conversation_repo: Arc::new(conversations.clone()),
+            participant_repo: Arc::new(conversations.clone()),
+            message_repo: Arc::new(messages.clone()),
+            uow: Arc::new(MemoryUnitOfWork),
+            ws_server: Arc::new(WebSocketServer::new().start()),
+            cache: Arc::new(MemoryCache::default()),
+            moderation: ContentFilter::from_env(Arc::new(reports)),
+            senders: Arc::new(UnknownSenders),
+        });
+        Fixture { users, conversations, messages, service }
+    }
+
+    fn user(users: &UserRepositoryMock, username: &str) -> Uuid {
+        users.insert(UserRepositoryMock::entity(username, "unused-hash"))
+    }
+
+    async fn group(
+        conversations: &ConversationRepositoryMock,
+        creator: Uuid,
+        members: &[Uuid],
+    ) -> Uuid {
+        let mut tx = MemoryUnitOfWork.begin().await.unwrap();
+        conversations
+            .create_group_conversation("Team", members, &creator, &mut tx)
+            .await
+            .unwrap()
+            .id
+    }
+
+    async fn send(
+        messages: &MessageRepositoryMock,
+        conversation_id: Uuid,
+        sender_id: Uuid,
+        content: &str,
+    ) -> Uuid {
+        let message = InsertMessage {
+            conversation_id,
+            sender_id,
+            content: Some(content.to_string()),
+            forwarded_from: None,
+            client_message_id: None,
+            sender_override: None,
+        };
+        let mut tx = MemoryUnitOfWork.begin().await.unwrap();
+        messages.create(&message, &mut tx).await.unwrap().id
+    }
+
+    fn since_joined() -> UpdateConversationSettings {
+        UpdateConversationSettings {
+            history_visibility: Some(HistoryVisibility::SinceJoined),
+            message_ttl_seconds: None,
+        }
+    }
+
+    #[actix_web::test]
+    async fn group_settings_are_limited_to_group_admin() {
+        let Fixture { users, conversations, service, .. } = fixture();
+        let alice = user(&users, "alice");
+        let bob = user(&users, "bob");
+        let conversation_id = group(&conversations, alice, &[alice, bob]).await;
+
+        let err = service
+            .update_settings(conversation_id, bob, &UserRole::User, since_joined())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::Forbidden);
+        let err = service
+            .update_duplicate_policy(conversation_id, bob, &UserRole::User, DuplicatePolicy::Allow)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::Forbidden);
+
+        let updated = service
+            .update_settings(conversation_id, alice, &UserRole::User, since_joined())
+            .await
+            .unwrap();
+        assert_eq!(updated.history_visibility, HistoryVisibility::SinceJoined);
+        service
+            .update_duplicate_policy(conversation_id, bob, &UserRole::Admin, DuplicatePolicy::Allow)
+            .await
+            .unwrap();
+    }
+
+    #[actix_web::test]
+    async fn direct_settings_are_open_to_both_participants_only() {
+        let Fixture { users, conversations, service, .. } = fixture();
+        let alice = user(&users, "alice");
+        let bob = user(&users, "bob");
+        let mallory = user(&users, "mallory");
+        let mut tx = MemoryUnitOfWork.begin().await.unwrap();
+        let conversation_id =
+            conversations.create_direct_conversation(&alice, &bob, &mut tx).await.unwrap().id;
+
+        service
+            .update_settings(conversation_id, bob, &UserRole::User, since_joined())
+            .await
+            .unwrap();
+
+        let err = service
+            .update_settings(conversation_id, mallory, &UserRole::User, since_joined())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotAMember);
+    }
+
+    #[actix_web::test]
+    async fn get_message_hides_history_cleared_by_viewer() {
+        let Fixture { users, conversations, messages, service } = fixture();
+        let alice = user(&users, "alice");
+        let bob = user(&users, "bob");
+        let conversation_id = group(&conversations, alice, &[alice, bob]).await;
+
+        let before = send(&messages, conversation_id, alice, "before").await;
+        service.delete_for_user(conversation_id, bob).await.unwrap();
+        let after = send(&messages, conversation_id, alice, "after").await;
+
+        let ids = |page: GetMessageResponse| -> Vec<Uuid> {
+            page.messages.iter().map(|m| m.id).collect()
+        };
+        let page = service
+            .get_message(conversation_id, bob, 10, None, CursorDirection::Before)
+            .await
+            .unwrap();
+        assert_eq!(ids(page), vec![after]);
+        let page = service
+            .get_message(conversation_id, alice, 10, None, CursorDirection::Before)
+            .await
+            .unwrap();
+        assert_eq!(ids(page), vec![before, after]);
+    }
 }
@@ -0,0 +1,34 @@
+use actix_web::{get, web, HttpRequest};
+
+use crate::{
+    api::{error, success},
+    middlewares::get_extensions,
+    modules::event::{
+        model::{PollEventsQuery, PollEventsResponse},
+        service::EventService,
+    },
+    utils::{Claims, ValidatedQuery},
+};
+
+/// Long-poll các server events (tin nhắn mới, presence, ...) bị lỡ khi mất WebSocket
+///
+/// GET /events/poll?since=<last_seq>&wait=25
+#[utoipa::path(
+    tag = "events",
+    params(PollEventsQuery),
+    responses(
+        (status = 200, body = success::SuccessData<PollEventsResponse>),
+        (status = 400, description = "Query không hợp lệ", body = error::ErrorBody)
+    )
+)]
+#[get("/events/poll")]
+pub async fn poll_events(
+    event_service: web::Data<EventService>,
+    ValidatedQuery(query): ValidatedQuery<PollEventsQuery>,
+    req: HttpRequest,
+) -> Result<success::Success<PollEventsResponse>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let response = event_service.poll(user_id, query.since, query.wait).await?;
+
+    Ok(success::Success::ok(Some(response)))
+}
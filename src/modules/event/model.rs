@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PollEventsQuery {
+    /// `user_seq` cuối cùng client đã áp dụng (`cursor` của response trước hoặc từ
+    /// WebSocket), bỏ trống để lấy toàn bộ events còn trong buffer
+    pub since: Option<u64>,
+    /// Số giây chờ events mới khi chưa có (mặc định 25, 0 = trả về ngay)
+    #[validate(range(max = 30, message = "Wait must be at most 30 seconds"))]
+    pub wait: Option<u64>,
+}
+
+/// Một server event trong buffer
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PolledEvent {
+    /// `user_seq` của event, giống field `user_seq` của WebSocket frame
+    pub seq: u64,
+    /// ServerMessage, cùng format với WebSocket frame (không có `seq`, `user_seq`)
+    #[schema(value_type = Object)]
+    pub event: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PollEventsResponse {
+    /// Events theo thứ tự gửi
+    pub events: Vec<PolledEvent>,
+    /// `user_seq` mới nhất, truyền vào `since` ở lần poll sau
+    pub cursor: u64,
+    /// Buffer còn events sau `cursor`, poll tiếp ngay không cần chờ
+    pub has_more: bool,
+    /// Buffer không còn đủ events sau `since` (hết hạn, bị cắt hoặc seq bị reset), client
+    /// cần fetch lại state qua REST rồi poll tiếp từ `cursor`
+    pub reset: bool,
+}
@@ -0,0 +1,12 @@
+use crate::modules::event::handle::*;
+use actix_web::web::ServiceConfig;
+use utoipa::OpenApi;
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(poll_events);
+}
+
+/// OpenAPI paths của `configure`
+#[derive(OpenApi)]
+#[openapi(paths(poll_events))]
+pub struct EventApiDoc;
@@ -0,0 +1,77 @@
+/// Event Poll Service
+///
+/// Long-poll fallback cho WebSocket: đọc buffer events theo user (`event_buffer`) có
+/// `user_seq` > `since`, chờ tối đa `wait` giây nếu chưa có events mới. Buffer chỉ được
+/// ghi khi user còn session (kể cả detached trong `server::RESUME_WINDOW`), nên long-poll dùng để
+/// bắc cầu những lúc mất socket ngắn chứ không thay thế hẳn WebSocket.
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::{
+        event::model::{PollEventsResponse, PolledEvent},
+        websocket::event_buffer::EventBuffer,
+    },
+};
+
+/// Số events tối đa mỗi response
+const POLL_LIMIT: usize = 100;
+/// Thời gian chờ mặc định khi chưa có events mới (giây)
+const DEFAULT_WAIT_SECS: u64 = 25;
+/// Chu kỳ đọc lại buffer trong lúc chờ
+const POLL_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone)]
+pub struct EventService {
+    buffer: EventBuffer,
+}
+
+impl EventService {
+    pub fn with_dependencies(buffer: EventBuffer) -> Self {
+        EventService { buffer }
+    }
+
+    pub async fn poll(
+        &self,
+        user_id: Uuid,
+        since: Option<u64>,
+        wait: Option<u64>,
+    ) -> Result<PollEventsResponse, error::SystemError> {
+        let deadline = Instant::now() + Duration::from_secs(wait.unwrap_or(DEFAULT_WAIT_SECS));
+        let buffered = loop {
+            let buffered = self.buffer.read_after(user_id, since, POLL_LIMIT).await?;
+            if !buffered.events.is_empty() || buffered.gap || Instant::now() >= deadline {
+                break buffered;
+            }
+            actix_web::rt::time::sleep(POLL_CHECK_INTERVAL).await;
+        };
+
+        // Sau reset client fetch lại state rồi tiếp tục từ seq mới nhất
+        if buffered.gap {
+            return Ok(PollEventsResponse {
+                events: Vec::new(),
+                cursor: buffered.head,
+                has_more: false,
+                reset: true,
+            });
+        }
+
+        let has_more = buffered.events.len() == POLL_LIMIT;
+        let cursor = buffered.events.last().map_or(buffered.head, |(seq, _)| *seq);
+        let events = buffered
+            .events
+            .into_iter()
+            .filter_map(|(seq, event)| match serde_json::from_str(&event) {
+                Ok(event) => Some(PolledEvent { seq, event }),
+                Err(e) => {
+                    tracing::warn!("Dropping undecodable buffered event {}: {}", seq, e);
+                    None
+                }
+            })
+            .collect();
+
+        Ok(PollEventsResponse { events, cursor, has_more, reset: false })
+    }
+}
@@ -5,60 +5,92 @@ use uuid::Uuid;
 
 use crate::api::success::Success;
 use crate::api::{error, success};
-use crate::modules::file_upload::schema::FileUploadResponse;
+use crate::configs::payload::payload_too_large;
+use crate::configs::settings::settings;
+use crate::modules::file_upload::schema::{FileEntity, FileUploadForm, FileUploadResponse};
 use crate::modules::file_upload::service::FileUploadService;
 
 /// Upload file handler
-pub async fn upload_file<R>(
+#[utoipa::path(
+    post,
+    path = "/upload",
+    tag = "files",
+    request_body(content = FileUploadForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, body = success::SuccessData<FileUploadResponse>),
+        (status = 400, description = "Không có file trong request", body = error::ErrorBody),
+        (status = 413, description = "File vượt `UPLOAD_BODY_LIMIT`", body = error::ErrorBody)
+    )
+)]
+pub async fn upload_file(
     mut payload: Multipart,
     req: actix_web::HttpRequest,
-    service: web::Data<FileUploadService<R>>,
-) -> Result<success::Success<FileUploadResponse>, error::Error>
-where
-    R: crate::modules::file_upload::repository::FileRepository + Send + Sync + 'static,
-{
+    service: web::Data<FileUploadService>,
+) -> Result<success::Success<FileUploadResponse>, error::Error> {
     let user_id = crate::middlewares::get_extensions::<crate::utils::Claims>(&req)?.sub;
 
-    // Process multipart form data
-    if let Some(mut field) = payload.try_next().await.map_err(|_| error::Error::InternalServer)? {
-        let content_disposition = field
-            .content_disposition()
-            .ok_or_else(|| error::Error::bad_request("Missing content disposition"))?;
-
-        let filename = content_disposition
-            .get_filename()
-            .ok_or_else(|| error::Error::bad_request("Missing filename"))?
-            .to_string();
-
-        // Detect MIME type
-        let mime_type = field
-            .content_type()
-            .map(|m| m.to_string())
-            .unwrap_or_else(|| "application/octet-stream".to_string());
-
-        // Read file bytes
-        let mut bytes = Vec::new();
-        while let Some(chunk) = field.try_next().await.map_err(|_| error::Error::InternalServer)? {
-            bytes.extend_from_slice(&chunk);
-        }
+    let (filename, mime_type, bytes) = read_file_field(&mut payload)
+        .await?
+        .ok_or_else(|| error::Error::bad_request("No file found in request"))?;
+
+    // Upload file
+    let result = service.upload_file(filename, bytes, mime_type, user_id).await?;
+
+    Ok(Success::ok(Some(result)).message("File uploaded successfully"))
+}
 
-        // Upload file
-        let result = service.upload_file(filename, bytes, mime_type, user_id).await?;
+/// Đọc file field đầu tiên của multipart form: (filename, MIME type, bytes)
+pub async fn read_file_field(
+    payload: &mut Multipart,
+) -> Result<Option<(String, String, Vec<u8>)>, error::Error> {
+    let Some(mut field) = payload.try_next().await.map_err(|_| error::Error::InternalServer)?
+    else {
+        return Ok(None);
+    };
 
-        return Ok(Success::ok(Some(result)).message("File uploaded successfully"));
+    let content_disposition = field
+        .content_disposition()
+        .ok_or_else(|| error::Error::bad_request("Missing content disposition"))?;
+
+    let filename = content_disposition
+        .get_filename()
+        .ok_or_else(|| error::Error::bad_request("Missing filename"))?
+        .to_string();
+
+    // Detect MIME type
+    let mime_type = field
+        .content_type()
+        .map(|m| m.to_string())
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+
+    // Read file bytes, dừng ngay khi vượt giới hạn upload
+    let limit = settings().upload_body_limit;
+    let mut bytes = Vec::new();
+    while let Some(chunk) = field.try_next().await.map_err(|_| error::Error::InternalServer)? {
+        if bytes.len() + chunk.len() > limit {
+            return Err(payload_too_large(limit));
+        }
+        bytes.extend_from_slice(&chunk);
     }
 
-    Err(error::Error::bad_request("No file found in request"))
+    Ok(Some((filename, mime_type, bytes)))
 }
 
 /// Get file metadata handler
-pub async fn get_file<R>(
+#[utoipa::path(
+    get,
+    path = "/{file_id}",
+    tag = "files",
+    params(("file_id" = Uuid, Path, description = "File ID")),
+    responses(
+        (status = 200, body = success::SuccessData<FileEntity>),
+        (status = 404, description = "Không tìm thấy file", body = error::ErrorBody)
+    )
+)]
+pub async fn get_file(
     file_id: web::Path<Uuid>,
-    service: web::Data<FileUploadService<R>>,
-) -> Result<success::Success<crate::modules::file_upload::schema::FileEntity>, error::Error>
-where
-    R: crate::modules::file_upload::repository::FileRepository + Send + Sync + 'static,
-{
+    service: web::Data<FileUploadService>,
+) -> Result<success::Success<FileEntity>, error::Error> {
     let file_id = file_id.into_inner();
 
     match service.get_file(&file_id).await {
@@ -69,14 +101,22 @@ where
 }
 
 /// Delete file handler
-pub async fn delete_file<R>(
+#[utoipa::path(
+    delete,
+    path = "/{file_id}",
+    tag = "files",
+    params(("file_id" = Uuid, Path, description = "File ID")),
+    responses(
+        (status = 200, body = success::SuccessData<String>),
+        (status = 403, description = "Không phải người upload", body = error::ErrorBody),
+        (status = 404, description = "Không tìm thấy file", body = error::ErrorBody)
+    )
+)]
+pub async fn delete_file(
     file_id: web::Path<Uuid>,
     req: actix_web::HttpRequest,
-    service: web::Data<FileUploadService<R>>,
-) -> Result<success::Success<String>, error::Error>
-where
-    R: crate::modules::file_upload::repository::FileRepository + Send + Sync + 'static,
-{
+    service: web::Data<FileUploadService>,
+) -> Result<success::Success<String>, error::Error> {
     let file_id = file_id.into_inner();
     let user_id = crate::middlewares::get_extensions::<crate::utils::Claims>(&req)?.sub;
 
@@ -37,3 +37,14 @@ impl Default for UploadConfig {
         }
     }
 }
+
+/// Kết quả dọn files (CLI `prune-files`)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PruneReport {
+    /// Files trong database không còn được message / avatar nào tham chiếu
+    pub unreferenced: u64,
+    /// Files trên disk không có metadata trong database
+    pub orphaned: u64,
+    /// Tổng dung lượng đã (hoặc sẽ được, khi dry run) giải phóng
+    pub bytes: u64,
+}
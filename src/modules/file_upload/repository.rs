@@ -2,20 +2,34 @@ use uuid::Uuid;
 
 use crate::{
     api::error,
+    configs::uow::Transaction,
     modules::file_upload::{model::NewFile, schema::FileEntity},
 };
 
 #[async_trait::async_trait]
 pub trait FileRepository {
-    fn get_pool(&self) -> &sqlx::Pool<sqlx::Postgres>;
-
-    async fn create<'e, E>(&self, file: &NewFile, tx: E) -> Result<FileEntity, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+    async fn create(
+        &self,
+        file: &NewFile,
+        tx: &mut Transaction,
+    ) -> Result<FileEntity, error::SystemError>;
 
     async fn find_by_id(&self, file_id: &Uuid) -> Result<Option<FileEntity>, error::SystemError>;
 
-    async fn delete<'e, E>(&self, file_id: &Uuid, tx: E) -> Result<(), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+    async fn delete(&self, file_id: &Uuid, tx: &mut Transaction) -> Result<(), error::SystemError>;
+
+    /// Files tạo trước `created_before` không được message / avatar user / avatar group nào
+    /// tham chiếu, theo thứ tự id sau `after`
+    async fn find_unreferenced(
+        &self,
+        created_before: &chrono::DateTime<chrono::Utc>,
+        after: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<FileEntity>, error::SystemError>;
+
+    /// Các filenames trong danh sách đã có metadata trong database
+    async fn find_existing_filenames(
+        &self,
+        filenames: &[String],
+    ) -> Result<Vec<String>, error::SystemError>;
 }
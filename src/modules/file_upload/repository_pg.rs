@@ -2,6 +2,7 @@ use uuid::Uuid;
 
 use crate::{
     api::error,
+    configs::uow::Transaction,
     modules::file_upload::{model::NewFile, repository::FileRepository, schema::FileEntity},
 };
 
@@ -18,14 +19,11 @@ impl FilePgRepository {
 
 #[async_trait::async_trait]
 impl FileRepository for FilePgRepository {
-    fn get_pool(&self) -> &sqlx::Pool<sqlx::Postgres> {
-        &self.pool
-    }
-
-    async fn create<'e, E>(&self, file: &NewFile, tx: E) -> Result<FileEntity, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+    async fn create(
+        &self,
+        file: &NewFile,
+        tx: &mut Transaction,
+    ) -> Result<FileEntity, error::SystemError> {
         let entity = sqlx::query_as::<_, FileEntity>(
             r#"
             INSERT INTO files (filename, original_filename, mime_type, file_size, storage_path, uploaded_by)
@@ -39,7 +37,7 @@ impl FileRepository for FilePgRepository {
         .bind(file.file_size)
         .bind(&file.storage_path)
         .bind(file.uploaded_by)
-        .fetch_one(tx)
+        .fetch_one(tx.connection()?)
         .await?;
 
         Ok(entity)
@@ -58,19 +56,73 @@ impl FileRepository for FilePgRepository {
         Ok(file)
     }
 
-    async fn delete<'e, E>(&self, file_id: &Uuid, tx: E) -> Result<(), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+    async fn delete(&self, file_id: &Uuid, tx: &mut Transaction) -> Result<(), error::SystemError> {
         sqlx::query(
             r#"
             DELETE FROM files WHERE id = $1
             "#,
         )
         .bind(file_id)
-        .execute(tx)
+        .execute(tx.connection()?)
         .await?;
 
         Ok(())
     }
+
+    async fn find_unreferenced(
+        &self,
+        created_before: &chrono::DateTime<chrono::Utc>,
+        after: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<FileEntity>, error::SystemError> {
+        // file_url / avatar_url = `{base_url}/{filename}`, avatar_id = file id
+        let files = sqlx::query_as::<_, FileEntity>(
+            r#"
+            WITH referenced AS (
+                SELECT regexp_replace(file_url, '^.*/', '') AS name
+                FROM messages WHERE file_url IS NOT NULL
+                UNION
+                SELECT avatar_id FROM users WHERE avatar_id IS NOT NULL
+                UNION
+                SELECT regexp_replace(avatar_url, '^.*/', '')
+                FROM users WHERE avatar_url IS NOT NULL
+                UNION
+                SELECT avatar_id FROM group_conversations WHERE avatar_id IS NOT NULL
+                UNION
+                SELECT regexp_replace(avatar_url, '^.*/', '')
+                FROM group_conversations WHERE avatar_url IS NOT NULL
+            )
+            SELECT f.* FROM files f
+            WHERE f.created_at < $1
+              AND ($2::uuid IS NULL OR f.id > $2)
+              AND f.id::text NOT IN (SELECT name FROM referenced)
+              AND f.filename NOT IN (SELECT name FROM referenced)
+            ORDER BY f.id
+            LIMIT $3
+            "#,
+        )
+        .bind(created_before)
+        .bind(after)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(files)
+    }
+
+    async fn find_existing_filenames(
+        &self,
+        filenames: &[String],
+    ) -> Result<Vec<String>, error::SystemError> {
+        let existing = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT filename FROM files WHERE filename = ANY($1)
+            "#,
+        )
+        .bind(filenames)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(existing)
+    }
 }
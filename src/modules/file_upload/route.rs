@@ -1,18 +1,21 @@
 use actix_web::web;
+use utoipa::OpenApi;
 
-use crate::modules::file_upload::repository::FileRepository;
+use crate::modules::file_upload::handle;
 
-pub fn configure<R>(cfg: &mut web::ServiceConfig)
-where
-    R: FileRepository + Send + Sync + 'static,
-{
+pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::resource("/upload")
-            .route(web::post().to(crate::modules::file_upload::handle::upload_file::<R>)),
+            .route(web::post().to(crate::modules::file_upload::handle::upload_file)),
     )
     .service(
         web::resource("/{file_id}")
-            .route(web::get().to(crate::modules::file_upload::handle::get_file::<R>))
-            .route(web::delete().to(crate::modules::file_upload::handle::delete_file::<R>)),
+            .route(web::get().to(crate::modules::file_upload::handle::get_file))
+            .route(web::delete().to(crate::modules::file_upload::handle::delete_file)),
     );
 }
+
+/// OpenAPI paths của `configure`
+#[derive(OpenApi)]
+#[openapi(paths(handle::upload_file, handle::get_file, handle::delete_file))]
+pub struct FileUploadApiDoc;
@@ -1,9 +1,10 @@
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 /// File metadata entity from database
-#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
 pub struct FileEntity {
     pub id: Uuid,
     pub filename: String,
@@ -16,7 +17,7 @@ pub struct FileEntity {
 }
 
 /// File upload request/response DTOs
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct FileUploadResponse {
     pub id: Uuid,
     pub filename: String,
@@ -26,3 +27,11 @@ pub struct FileUploadResponse {
     pub url: String,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
+
+/// Multipart form của upload request (chỉ dùng cho OpenAPI schema)
+#[allow(dead_code)]
+#[derive(ToSchema)]
+pub struct FileUploadForm {
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+}
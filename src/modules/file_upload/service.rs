@@ -1,33 +1,46 @@
+use std::io::Cursor;
 use std::path::Path;
 use std::sync::Arc;
+
+use actix_web::web;
+use image::{imageops::FilterType, ImageFormat};
 use uuid::Uuid;
 
 use crate::api::error;
+use crate::configs::uow::UnitOfWork;
 use crate::modules::file_upload::{
-    model::{NewFile, UploadConfig},
+    model::{NewFile, PruneReport, UploadConfig},
     repository::FileRepository,
     schema::{FileEntity, FileUploadResponse},
 };
 
+/// Kích thước (px) cạnh của avatar vuông sau khi resize
+const AVATAR_SIZE: u32 = 256;
+
+/// Số files được kiểm tra trong mỗi lần query khi dọn files
+const PRUNE_BATCH_SIZE: usize = 500;
+
 #[derive(Clone)]
-pub struct FileUploadService<R>
-where
-    R: FileRepository + Send + Sync,
-{
-    file_repo: Arc<R>,
+pub struct FileUploadService {
+    file_repo: Arc<dyn FileRepository + Send + Sync>,
+    uow: Arc<dyn UnitOfWork>,
     config: UploadConfig,
 }
 
-impl<R> FileUploadService<R>
-where
-    R: FileRepository + Send + Sync,
-{
-    pub fn new(file_repo: Arc<R>, config: UploadConfig) -> Self {
-        Self { file_repo, config }
+impl FileUploadService {
+    pub fn new(
+        file_repo: Arc<dyn FileRepository + Send + Sync>,
+        uow: Arc<dyn UnitOfWork>,
+        config: UploadConfig,
+    ) -> Self {
+        Self { file_repo, uow, config }
     }
 
-    pub fn with_defaults(file_repo: Arc<R>) -> Self {
-        Self::new(file_repo, UploadConfig::default())
+    pub fn with_defaults(
+        file_repo: Arc<dyn FileRepository + Send + Sync>,
+        uow: Arc<dyn UnitOfWork>,
+    ) -> Self {
+        Self::new(file_repo, uow, UploadConfig::default())
     }
 
     /// Validate file type and size
@@ -95,22 +108,58 @@ where
         // Generate unique filename
         let filename = self.generate_filename(&original_filename);
 
+        self.store(filename, original_filename, bytes, mime_type, uploaded_by).await
+    }
+
+    /// Upload ảnh avatar: crop giữa thành hình vuông, resize `AVATAR_SIZE` và lưu dạng PNG
+    pub async fn upload_avatar(
+        &self,
+        original_filename: String,
+        bytes: Vec<u8>,
+        mime_type: String,
+        uploaded_by: Uuid,
+    ) -> Result<FileUploadResponse, error::SystemError> {
+        self.validate_file(&original_filename, bytes.len(), &mime_type)?;
+
+        if !mime_type.starts_with("image/") {
+            return Err(error::SystemError::bad_request("Avatar must be an image"));
+        }
+
+        // Decode / resize tốn CPU, chạy trên blocking thread pool
+        let thumbnail = web::block(move || make_square_thumbnail(&bytes, AVATAR_SIZE))
+            .await
+            .map_err(|e| error::SystemError::internal_error(e.to_string()))??;
+
+        let filename = format!("{}.png", Uuid::now_v7());
+        self.store(filename, original_filename, thumbnail, "image/png".to_string(), uploaded_by)
+            .await
+    }
+
+    /// Lưu file xuống disk và metadata vào database
+    async fn store(
+        &self,
+        filename: String,
+        original_filename: String,
+        bytes: Vec<u8>,
+        mime_type: String,
+        uploaded_by: Uuid,
+    ) -> Result<FileUploadResponse, error::SystemError> {
         // Save file to disk
         let storage_path = self.save_file(&filename, &bytes).await?;
 
         // Save metadata to database
-        let mut tx = self.file_repo.get_pool().begin().await?;
+        let mut tx = self.uow.begin().await?;
 
         let new_file = NewFile {
             filename: filename.clone(),
             original_filename,
             mime_type,
-            file_size: file_size as i64,
+            file_size: bytes.len() as i64,
             storage_path,
             uploaded_by,
         };
 
-        let file_entity = self.file_repo.create(&new_file, &mut *tx).await?;
+        let file_entity = self.file_repo.create(&new_file, &mut tx).await?;
         tx.commit().await?;
 
         // Build response
@@ -144,10 +193,94 @@ where
         tokio::fs::remove_file(&file.storage_path).await.ok();
 
         // Delete from database
-        let mut tx = self.file_repo.get_pool().begin().await?;
-        self.file_repo.delete(file_id, &mut *tx).await?;
+        let mut tx = self.uow.begin().await?;
+        self.file_repo.delete(file_id, &mut tx).await?;
         tx.commit().await?;
 
         Ok(())
     }
+
+    /// Dọn files cũ hơn `older_than`: xóa files không còn được tham chiếu (cả metadata và
+    /// file trên disk) và files trên disk không có metadata (upload lỗi giữa chừng).
+    /// `dry_run` chỉ đếm, không xóa gì
+    pub async fn prune_files(
+        &self,
+        older_than: chrono::Duration,
+        dry_run: bool,
+    ) -> Result<PruneReport, error::SystemError> {
+        let cutoff = chrono::Utc::now() - older_than;
+        let mut report = PruneReport::default();
+        let mut after = None;
+
+        loop {
+            let files =
+                self.file_repo.find_unreferenced(&cutoff, after, PRUNE_BATCH_SIZE as i64).await?;
+            after = files.last().map(|file| file.id);
+
+            for file in &files {
+                tracing::info!("Pruning unreferenced file {} ({})", file.id, file.storage_path);
+                report.unreferenced += 1;
+                report.bytes += file.file_size.max(0) as u64;
+
+                if !dry_run {
+                    self.delete_file(&file.id).await?;
+                }
+            }
+
+            if files.len() < PRUNE_BATCH_SIZE {
+                break;
+            }
+        }
+
+        let mut candidates = Vec::new();
+        let mut entries = match tokio::fs::read_dir(&self.config.upload_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(report),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            let modified = chrono::DateTime::<chrono::Utc>::from(metadata.modified()?);
+            if metadata.is_file() && modified < cutoff {
+                if let Some(name) = entry.file_name().to_str() {
+                    candidates.push((name.to_string(), metadata.len()));
+                }
+            }
+        }
+
+        for batch in candidates.chunks(PRUNE_BATCH_SIZE) {
+            let names: Vec<String> = batch.iter().map(|(name, _)| name.clone()).collect();
+            let existing = self.file_repo.find_existing_filenames(&names).await?;
+
+            for (name, size) in batch.iter().filter(|(name, _)| !existing.contains(name)) {
+                tracing::info!("Pruning orphaned file {}/{}", self.config.upload_dir, name);
+                report.orphaned += 1;
+                report.bytes += size;
+
+                if !dry_run {
+                    tokio::fs::remove_file(Path::new(&self.config.upload_dir).join(name)).await?;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Decode ảnh, crop phần giữa thành hình vuông và resize về `size` x `size` (PNG)
+fn make_square_thumbnail(bytes: &[u8], size: u32) -> Result<Vec<u8>, error::SystemError> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|_| error::SystemError::bad_request("Invalid or unsupported image"))?;
+
+    let side = image.width().min(image.height());
+    let x = (image.width() - side) / 2;
+    let y = (image.height() - side) / 2;
+    let thumbnail = image.crop_imm(x, y, side, side).resize_exact(size, size, FilterType::Lanczos3);
+
+    let mut encoded = Vec::new();
+    thumbnail
+        .write_to(&mut Cursor::new(&mut encoded), ImageFormat::Png)
+        .map_err(|e| error::SystemError::internal_error(e.to_string()))?;
+
+    Ok(encoded)
 }
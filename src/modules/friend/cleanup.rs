@@ -0,0 +1,25 @@
+/// Friend Request Cleanup
+///
+/// Task chạy nền định kỳ xóa các lời mời kết bạn đã hết hạn (quá `FRIEND_REQUEST_TTL_DAYS`
+/// kể từ `created_at`). Các query đọc đã tự bỏ qua lời mời hết hạn, job này chỉ dọn dữ liệu
+/// nên chạy trùng trên nhiều instance cũng không sao.
+use std::time::Duration;
+
+use crate::modules::friend::service::FriendService;
+
+/// Khoảng thời gian giữa hai lần dọn
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+pub async fn run_friend_request_cleanup(service: FriendService) {
+    let mut interval = actix_web::rt::time::interval(CLEANUP_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match service.delete_expired_friend_requests().await {
+            Ok(0) => {}
+            Ok(deleted) => tracing::info!("Deleted {} expired friend requests", deleted),
+            Err(e) => tracing::error!("Failed to delete expired friend requests: {:?}", e),
+        }
+    }
+}
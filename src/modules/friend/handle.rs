@@ -4,37 +4,45 @@ use uuid::Uuid;
 use crate::{
     api::{error, success},
     middlewares::get_extensions,
-    modules::{
-        friend::{
-            model::{FriendRequestBody, FriendRequestResponse, FriendResponse},
-            repository_pg::FriendRepositoryPg,
-            schema::FriendRequestEntity,
-            service::FriendService,
+    modules::friend::{
+        model::{
+            BlockedUserResponse, FriendRequestBody, FriendRequestListQuery,
+            FriendRequestListResponse, FriendResponse, FriendSuggestion,
         },
-        user::repository_pg::UserRepositoryPg,
+        schema::FriendRequestEntity,
+        service::FriendService,
     },
-    utils::Claims,
+    utils::{Claims, ValidatedJson, ValidatedQuery},
 };
 
-pub type FriendSvc = FriendService<FriendRepositoryPg, UserRepositoryPg>;
-
+#[utoipa::path(
+    tag = "friends",
+    request_body = FriendRequestBody,
+    responses((status = 200, body = success::SuccessData<FriendRequestEntity>))
+)]
 #[post("/requests")]
 pub async fn send_friend_request(
-    friend_service: web::Data<FriendSvc>,
-    body: web::Json<FriendRequestBody>,
+    friend_service: web::Data<FriendService>,
+    ValidatedJson(body): ValidatedJson<FriendRequestBody>,
     req: HttpRequest,
 ) -> Result<success::Success<FriendRequestEntity>, error::Error> {
     let sender_id = get_extensions::<Claims>(&req)?.sub;
-    let request = friend_service
-        .send_friend_request(sender_id, body.recipient_id, body.message.clone())
-        .await?;
+    let request =
+        friend_service.send_friend_request(sender_id, body.recipient_id, body.message).await?;
 
     Ok(success::Success::created(Some(request)).message("Friend request sent successfully"))
 }
 
+#[utoipa::path(
+    tag = "friends",
+    responses(
+        (status = 200, body = success::SuccessData<FriendResponse>),
+        (status = 404, description = "Không tìm thấy lời mời kết bạn", body = error::ErrorBody)
+    )
+)]
 #[post("/requests/{request_id}/accept")]
 pub async fn accept_friend_request(
-    friend_service: web::Data<FriendSvc>,
+    friend_service: web::Data<FriendService>,
     request_id: web::Path<Uuid>,
     req: HttpRequest,
 ) -> Result<success::Success<FriendResponse>, error::Error> {
@@ -44,9 +52,16 @@ pub async fn accept_friend_request(
     Ok(success::Success::ok(Some(response)).message("Friend request accepted successfully"))
 }
 
+#[utoipa::path(
+    tag = "friends",
+    responses(
+        (status = 204, description = "Đã từ chối lời mời"),
+        (status = 404, description = "Không tìm thấy lời mời kết bạn", body = error::ErrorBody)
+    )
+)]
 #[post("/requests/{request_id}/decline")]
 pub async fn decline_friend_request(
-    friend_service: web::Data<FriendSvc>,
+    friend_service: web::Data<FriendService>,
     request_id: web::Path<Uuid>,
     req: HttpRequest,
 ) -> Result<success::Success<()>, error::Error> {
@@ -55,9 +70,32 @@ pub async fn decline_friend_request(
     Ok(success::Success::no_content())
 }
 
+#[utoipa::path(
+    tag = "friends",
+    responses(
+        (status = 204, description = "Đã thu hồi lời mời"),
+        (status = 403, description = "Không phải người gửi lời mời", body = error::ErrorBody),
+        (status = 404, description = "Không tìm thấy lời mời kết bạn", body = error::ErrorBody)
+    )
+)]
+#[delete("/requests/{request_id}")]
+pub async fn cancel_friend_request(
+    friend_service: web::Data<FriendService>,
+    request_id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let sender_id = get_extensions::<Claims>(&req)?.sub;
+    friend_service.cancel_friend_request(sender_id, *request_id).await?;
+    Ok(success::Success::no_content())
+}
+
+#[utoipa::path(
+    tag = "friends",
+    responses((status = 200, body = success::SuccessData<Vec<FriendResponse>>))
+)]
 #[get("/")]
 pub async fn list_friends(
-    friend_service: web::Data<FriendSvc>,
+    friend_service: web::Data<FriendService>,
     req: HttpRequest,
 ) -> Result<success::Success<Vec<FriendResponse>>, error::Error> {
     let user_id = get_extensions::<Claims>(&req)?.sub;
@@ -66,20 +104,53 @@ pub async fn list_friends(
     Ok(success::Success::ok(Some(friends)).message("Friends retrieved successfully"))
 }
 
+/// Lời mời kết bạn đến và đi (chưa hết hạn), kèm số lời mời đang chờ
+///
+/// GET /friends/requests?limit=20&cursor=<created_at>&direction=before
+#[utoipa::path(
+    tag = "friends",
+    params(FriendRequestListQuery),
+    responses(
+        (status = 200, body = success::SuccessData<FriendRequestListResponse>),
+        (status = 400, description = "Cursor không hợp lệ", body = error::ErrorBody)
+    )
+)]
 #[get("/requests")]
 pub async fn list_friend_requests(
-    friend_service: web::Data<FriendSvc>,
+    friend_service: web::Data<FriendService>,
+    ValidatedQuery(query): ValidatedQuery<FriendRequestListQuery>,
     req: HttpRequest,
-) -> Result<success::Success<Vec<FriendRequestResponse>>, error::Error> {
+) -> Result<success::Success<FriendRequestListResponse>, error::Error> {
     let user_id = get_extensions::<Claims>(&req)?.sub;
-    let requests = friend_service.get_friend_requests(user_id).await?;
+    let requests = friend_service
+        .get_friend_requests(user_id, query.limit, query.cursor, query.direction)
+        .await?;
 
     Ok(success::Success::ok(Some(requests)).message("Friend requests retrieved successfully"))
 }
 
+#[utoipa::path(
+    tag = "friends",
+    responses((status = 200, body = success::SuccessData<Vec<FriendSuggestion>>))
+)]
+#[get("/suggestions")]
+pub async fn list_friend_suggestions(
+    friend_service: web::Data<FriendService>,
+    req: HttpRequest,
+) -> Result<success::Success<Vec<FriendSuggestion>>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let suggestions = friend_service.get_friend_suggestions(user_id).await?;
+
+    Ok(success::Success::ok(Some(suggestions)).message("Friend suggestions retrieved successfully"))
+}
+
+#[utoipa::path(
+    tag = "friends",
+    responses((status = 204, description = "Đã hủy kết bạn"))
+)]
 #[delete("/{friend_id}")]
 pub async fn remove_friend(
-    friend_service: web::Data<FriendSvc>,
+    friend_service: web::Data<FriendService>,
     friend_id: web::Path<Uuid>,
     req: HttpRequest,
 ) -> Result<success::Success<()>, error::Error> {
@@ -87,3 +158,54 @@ pub async fn remove_friend(
     friend_service.remove_friend(user_id, *friend_id).await?;
     Ok(success::Success::no_content())
 }
+
+#[utoipa::path(
+    tag = "friends",
+    responses((status = 200, body = success::SuccessData<Vec<BlockedUserResponse>>))
+)]
+#[get("/blocks")]
+pub async fn list_blocked_users(
+    friend_service: web::Data<FriendService>,
+    req: HttpRequest,
+) -> Result<success::Success<Vec<BlockedUserResponse>>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let users = friend_service.get_blocked_users(user_id).await?;
+
+    Ok(success::Success::ok(Some(users)).message("Blocked users retrieved successfully"))
+}
+
+#[utoipa::path(
+    tag = "friends",
+    responses(
+        (status = 204, description = "Đã chặn, quan hệ bạn bè và lời mời giữa 2 người bị hủy"),
+        (status = 404, description = "User không tồn tại", body = error::ErrorBody)
+    )
+)]
+#[post("/blocks/{user_id}")]
+pub async fn block_user(
+    friend_service: web::Data<FriendService>,
+    blocked_id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    friend_service.block_user(user_id, *blocked_id).await?;
+    Ok(success::Success::no_content())
+}
+
+#[utoipa::path(
+    tag = "friends",
+    responses(
+        (status = 204, description = "Đã bỏ chặn"),
+        (status = 404, description = "User chưa bị chặn", body = error::ErrorBody)
+    )
+)]
+#[delete("/blocks/{user_id}")]
+pub async fn unblock_user(
+    friend_service: web::Data<FriendService>,
+    blocked_id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    friend_service.unblock_user(user_id, *blocked_id).await?;
+    Ok(success::Success::no_content())
+}
@@ -1,11 +1,14 @@
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
+use crate::api::pagination::PageInfo;
+use crate::modules::message::model::CursorDirection;
 use crate::modules::user::schema::UserEntity;
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 
 pub struct FriendResponse {
     pub id: Uuid,
@@ -14,6 +17,16 @@ pub struct FriendResponse {
     pub avatar_url: Option<String>,
 }
 
+/// User đang bị chặn
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct BlockedUserResponse {
+    pub id: Uuid,
+    pub username: String,
+    pub display_name: String,
+    pub avatar_url: Option<String>,
+    pub blocked_at: chrono::DateTime<chrono::Utc>,
+}
+
 impl From<UserEntity> for FriendResponse {
     fn from(user: UserEntity) -> Self {
         FriendResponse {
@@ -25,7 +38,17 @@ impl From<UserEntity> for FriendResponse {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Gợi ý kết bạn dựa trên bạn chung
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct FriendSuggestion {
+    pub id: Uuid,
+    pub username: String,
+    pub display_name: String,
+    pub avatar_url: Option<String>,
+    pub mutual_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub enum IdOrInfo {
     Id(Uuid),
     Info(FriendResponse),
@@ -42,7 +65,7 @@ pub struct FriendUserRow {
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 
 pub struct FriendRequestResponse {
     pub id: Uuid,
@@ -52,8 +75,51 @@ pub struct FriendRequestResponse {
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct FriendRequestBody {
     pub recipient_id: Uuid,
+    #[validate(length(max = 300, message = "Message must be at most 300 characters"))]
     pub message: Option<String>,
 }
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FriendRequestListQuery {
+    #[validate(range(min = 1, max = 50, message = "Limit must be between 1 and 50"))]
+    pub limit: Option<i64>,
+    /// `created_at` (RFC 3339) của lời mời cuối cùng ở trang trước
+    pub cursor: Option<String>,
+    /// `before`: lời mời cũ hơn cursor (mặc định), `after`: lời mời mới hơn cursor
+    #[serde(default)]
+    #[param(inline)]
+    pub direction: CursorDirection,
+}
+
+/// Điều kiện phân trang lời mời kết bạn (mới nhất trước)
+#[derive(Debug, Clone)]
+pub struct FriendRequestPage {
+    /// Chỉ lấy lời mời tạo trước thời điểm này (cursor)
+    pub created_before: Option<chrono::DateTime<chrono::Utc>>,
+    /// Chỉ lấy lời mời tạo sau thời điểm này (cursor với `direction=after`), cũ nhất trước
+    pub created_after: Option<chrono::DateTime<chrono::Utc>>,
+    /// Lời mời tạo trước thời điểm này đã hết hạn
+    pub expires_before: chrono::DateTime<chrono::Utc>,
+    pub limit: i64,
+}
+
+/// Số lời mời đang chờ (chưa hết hạn)
+#[derive(Debug, Clone, Default, Serialize, FromRow, ToSchema)]
+pub struct FriendRequestCounts {
+    pub incoming: i64,
+    pub outgoing: i64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FriendRequestListResponse {
+    pub requests: Vec<FriendRequestResponse>,
+    /// Giống `page.next_cursor`, giữ lại cho client cũ
+    pub cursor: Option<String>,
+    pub counts: FriendRequestCounts,
+    /// `total` là tổng lời mời đến và đi đang chờ
+    pub page: PageInfo,
+}
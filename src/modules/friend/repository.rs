@@ -1,103 +1,147 @@
 use uuid::Uuid;
 
 use crate::api::error;
-use crate::modules::friend::model::{FriendRequestResponse, FriendResponse};
+use crate::configs::uow::Transaction;
+use crate::modules::friend::model::{
+    BlockedUserResponse, FriendRequestCounts, FriendRequestPage, FriendRequestResponse,
+    FriendResponse, FriendSuggestion,
+};
 use crate::modules::friend::schema::{FriendEntity, FriendRequestEntity};
 
 #[async_trait::async_trait]
 pub trait FriendRepository {
-    async fn find_friendship<'e, E>(
+    async fn find_friendship(
         &self,
         user_id_a: &Uuid,
         user_id_b: &Uuid,
-        tx: E,
-    ) -> Result<Option<FriendEntity>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+    ) -> Result<Option<FriendEntity>, error::SystemError>;
 
-    async fn find_friends<'e, E>(
-        &self,
-        user_id: &Uuid,
-        tx: E,
-    ) -> Result<Vec<FriendResponse>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+    async fn find_friends(&self, user_id: &Uuid)
+        -> Result<Vec<FriendResponse>, error::SystemError>;
 
     #[allow(dead_code)]
-    async fn create_friendship<'e, E>(
+    async fn create_friendship(
+        &self,
+        user_id_a: &Uuid,
+        user_id_b: &Uuid,
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError>;
+
+    async fn delete_friendship(
         &self,
         user_id_a: &Uuid,
         user_id_b: &Uuid,
-        tx: E,
-    ) -> Result<(), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError>;
 
-    async fn delete_friendship<'e, E>(
+    /// Hai user có ít nhất một bạn chung
+    async fn has_mutual_friend(
         &self,
         user_id_a: &Uuid,
         user_id_b: &Uuid,
-        tx: E,
-    ) -> Result<(), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+    ) -> Result<bool, error::SystemError>;
+
+    /// Friends-of-friends chưa là bạn / chưa có lời mời, sắp xếp theo số bạn chung
+    async fn find_friend_suggestions(
+        &self,
+        user_id: &Uuid,
+        limit: i64,
+    ) -> Result<Vec<FriendSuggestion>, error::SystemError>;
 }
 
 #[async_trait::async_trait]
 pub trait FriendRequestRepository {
-    async fn find_friend_request<'e, E>(
+    async fn find_friend_request(
         &self,
         sender_id: &Uuid,
         receiver_id: &Uuid,
-        tx: E,
-    ) -> Result<Option<FriendRequestEntity>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+    ) -> Result<Option<FriendRequestEntity>, error::SystemError>;
 
-    async fn find_friend_request_by_id<'e, E>(
+    async fn find_friend_request_by_id(
         &self,
         request_id: &Uuid,
-        tx: E,
-    ) -> Result<Option<FriendRequestEntity>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        tx: &mut Transaction,
+    ) -> Result<Option<FriendRequestEntity>, error::SystemError>;
 
-    async fn find_friend_request_from_user<'e, E>(
+    async fn find_friend_request_from_user(
         &self,
         user_id: &Uuid,
-        tx: E,
-    ) -> Result<Vec<FriendRequestResponse>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        page: &FriendRequestPage,
+    ) -> Result<Vec<FriendRequestResponse>, error::SystemError>;
 
-    async fn find_friend_request_to_user<'e, E>(
+    async fn find_friend_request_to_user(
         &self,
         user_id: &Uuid,
-        tx: E,
-    ) -> Result<Vec<FriendRequestResponse>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        page: &FriendRequestPage,
+    ) -> Result<Vec<FriendRequestResponse>, error::SystemError>;
 
-    async fn create_friend_request<'e, E>(
+    async fn create_friend_request(
         &self,
         sender_id: &Uuid,
         receiver_id: &Uuid,
         message: &Option<String>,
-        tx: E,
-    ) -> Result<FriendRequestEntity, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+    ) -> Result<FriendRequestEntity, error::SystemError>;
 
-    async fn delete_friend_request<'e, E>(
+    async fn delete_friend_request(
         &self,
         request_id: &Uuid,
-        tx: E,
-    ) -> Result<(), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError>;
+
+    /// Xóa lời mời giữa 2 users theo cả 2 chiều
+    async fn delete_friend_requests_between(
+        &self,
+        user_id_a: &Uuid,
+        user_id_b: &Uuid,
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError>;
+
+    /// Đếm lời mời đến / đi chưa hết hạn của user
+    async fn count_friend_requests(
+        &self,
+        user_id: &Uuid,
+        expires_before: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<FriendRequestCounts, error::SystemError>;
+
+    /// Xóa các lời mời tạo trước `expires_before`, trả về số lời mời đã xóa
+    async fn delete_expired_friend_requests(
+        &self,
+        expires_before: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, error::SystemError>;
 }
 
 #[async_trait::async_trait]
-pub trait FriendRepo: FriendRepository + FriendRequestRepository + Send + Sync {
-    fn get_pool(&self) -> &sqlx::PgPool;
+pub trait BlockRepository {
+    /// Chặn user, không lỗi nếu đã chặn trước đó
+    async fn create_block(
+        &self,
+        blocker_id: &Uuid,
+        blocked_id: &Uuid,
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError>;
+
+    /// Bỏ chặn, trả về false nếu chưa chặn
+    async fn delete_block(
+        &self,
+        blocker_id: &Uuid,
+        blocked_id: &Uuid,
+    ) -> Result<bool, error::SystemError>;
+
+    /// Những users mà `blocker_id` đang chặn, mới nhất trước
+    async fn find_blocked_users(
+        &self,
+        blocker_id: &Uuid,
+    ) -> Result<Vec<BlockedUserResponse>, error::SystemError>;
+
+    /// Một trong 2 users đã chặn người còn lại
+    async fn is_blocked_between(
+        &self,
+        user_id_a: &Uuid,
+        user_id_b: &Uuid,
+    ) -> Result<bool, error::SystemError>;
+}
+
+pub trait FriendRepo:
+    FriendRepository + FriendRequestRepository + BlockRepository + Send + Sync
+{
 }
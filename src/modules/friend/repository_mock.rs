@@ -0,0 +1,389 @@
+/// In-memory `FriendRepo` cho unit tests của services
+///
+/// Quan hệ bạn bè và thông tin user dùng chung với `UserRepositoryMock`, lời mời và danh
+/// sách chặn giữ riêng trong bộ nhớ. Dùng cùng `MemoryUnitOfWork`, tham số transaction bị
+/// bỏ qua và thay đổi được ghi ngay.
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    configs::uow::Transaction,
+    modules::{
+        friend::{
+            model::{
+                BlockedUserResponse, FriendRequestCounts, FriendRequestPage, FriendRequestResponse,
+                FriendResponse, FriendSuggestion, IdOrInfo,
+            },
+            repository::{BlockRepository, FriendRepo, FriendRepository, FriendRequestRepository},
+            schema::{FriendEntity, FriendRequestEntity},
+        },
+        user::repository_mock::UserRepositoryMock,
+    },
+};
+
+#[derive(Default)]
+struct State {
+    requests: Vec<FriendRequestEntity>,
+    /// (blocker_id, blocked_id) -> thời điểm chặn
+    blocks: HashMap<(Uuid, Uuid), chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Clone)]
+pub struct FriendRepositoryMock {
+    users: UserRepositoryMock,
+    state: Arc<Mutex<State>>,
+}
+
+impl FriendRepositoryMock {
+    pub fn new(users: UserRepositoryMock) -> Self {
+        FriendRepositoryMock { users, state: Arc::default() }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Lời mời đang lưu, kể cả lời mời đã hết hạn
+    pub fn requests(&self) -> Vec<FriendRequestEntity> {
+        self.state().requests.clone()
+    }
+
+    pub fn block(&self, blocker_id: Uuid, blocked_id: Uuid) {
+        self.state().blocks.insert((blocker_id, blocked_id), chrono::Utc::now());
+    }
+
+    pub fn is_blocked(&self, blocker_id: &Uuid, blocked_id: &Uuid) -> bool {
+        self.state().blocks.contains_key(&(*blocker_id, *blocked_id))
+    }
+
+    fn profile(&self, id: &Uuid) -> Option<FriendResponse> {
+        self.users.get(id).filter(|user| user.deleted_at.is_none()).map(FriendResponse::from)
+    }
+
+    fn page_requests(
+        &self,
+        page: &FriendRequestPage,
+        filter: impl Fn(&FriendRequestEntity) -> bool,
+    ) -> Vec<FriendRequestEntity> {
+        let mut requests: Vec<_> = self
+            .state()
+            .requests
+            .iter()
+            .filter(|request| {
+                request.created_at >= page.expires_before
+                    && page.created_before.is_none_or(|before| request.created_at < before)
+                    && page.created_after.is_none_or(|after| request.created_at > after)
+                    && filter(request)
+            })
+            .cloned()
+            .collect();
+        requests.sort_by_key(|request| Reverse(request.created_at));
+        if page.created_after.is_some() {
+            requests.reverse();
+        }
+        requests.truncate(page.limit.max(0) as usize);
+        requests
+    }
+}
+
+#[async_trait::async_trait]
+impl FriendRepository for FriendRepositoryMock {
+    async fn find_friendship(
+        &self,
+        user_id_a: &Uuid,
+        user_id_b: &Uuid,
+    ) -> Result<Option<FriendEntity>, error::SystemError> {
+        if !self.users.is_friend(user_id_a, user_id_b) {
+            return Ok(None);
+        }
+
+        Ok(Some(FriendEntity {
+            user_a: *user_id_a.min(user_id_b),
+            user_b: *user_id_a.max(user_id_b),
+            deleted_at: None,
+            created_at: chrono::Utc::now(),
+        }))
+    }
+
+    async fn find_friends(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Vec<FriendResponse>, error::SystemError> {
+        Ok(self
+            .users
+            .friend_ids(user_id)
+            .iter()
+            .filter_map(|id| self.users.get(id))
+            .filter(|user| user.deactivated_at.is_none())
+            .map(FriendResponse::from)
+            .collect())
+    }
+
+    async fn create_friendship(
+        &self,
+        user_id_a: &Uuid,
+        user_id_b: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
+        self.users.add_friendship(*user_id_a, *user_id_b);
+        Ok(())
+    }
+
+    async fn delete_friendship(
+        &self,
+        user_id_a: &Uuid,
+        user_id_b: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
+        self.users.remove_friendship(*user_id_a, *user_id_b);
+        Ok(())
+    }
+
+    async fn has_mutual_friend(
+        &self,
+        user_id_a: &Uuid,
+        user_id_b: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        let friends_b = self.users.friend_ids(user_id_b);
+        Ok(self.users.friend_ids(user_id_a).iter().any(|id| friends_b.contains(id)))
+    }
+
+    async fn find_friend_suggestions(
+        &self,
+        user_id: &Uuid,
+        limit: i64,
+    ) -> Result<Vec<FriendSuggestion>, error::SystemError> {
+        let friends = self.users.friend_ids(user_id);
+        let mut mutual_counts: HashMap<Uuid, i64> = HashMap::new();
+        for via in &friends {
+            for candidate in self.users.friend_ids(via) {
+                *mutual_counts.entry(candidate).or_default() += 1;
+            }
+        }
+
+        let state = self.state();
+        let mut suggestions: Vec<_> = mutual_counts
+            .into_iter()
+            .filter(|(id, _)| {
+                id != user_id
+                    && !friends.contains(id)
+                    && !state.requests.iter().any(|request| {
+                        (request.from_user_id == *user_id && request.to_user_id == *id)
+                            || (request.from_user_id == *id && request.to_user_id == *user_id)
+                    })
+                    && !state.blocks.contains_key(&(*user_id, *id))
+                    && !state.blocks.contains_key(&(*id, *user_id))
+            })
+            .filter_map(|(id, mutual_count)| {
+                let profile = self.profile(&id)?;
+                Some(FriendSuggestion {
+                    id,
+                    username: profile.username,
+                    display_name: profile.display_name,
+                    avatar_url: profile.avatar_url,
+                    mutual_count,
+                })
+            })
+            .collect();
+        suggestions.sort_by(|a, b| b.mutual_count.cmp(&a.mutual_count).then(a.id.cmp(&b.id)));
+        suggestions.truncate(limit.max(0) as usize);
+
+        Ok(suggestions)
+    }
+}
+
+#[async_trait::async_trait]
+impl FriendRequestRepository for FriendRepositoryMock {
+    async fn find_friend_request(
+        &self,
+        sender_id: &Uuid,
+        receiver_id: &Uuid,
+    ) -> Result<Option<FriendRequestEntity>, error::SystemError> {
+        Ok(self
+            .state()
+            .requests
+            .iter()
+            .find(|request| {
+                (request.from_user_id == *sender_id && request.to_user_id == *receiver_id)
+                    || (request.from_user_id == *receiver_id && request.to_user_id == *sender_id)
+            })
+            .cloned())
+    }
+
+    async fn find_friend_request_by_id(
+        &self,
+        request_id: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<Option<FriendRequestEntity>, error::SystemError> {
+        Ok(self.state().requests.iter().find(|request| request.id == *request_id).cloned())
+    }
+
+    async fn find_friend_request_from_user(
+        &self,
+        user_id: &Uuid,
+        page: &FriendRequestPage,
+    ) -> Result<Vec<FriendRequestResponse>, error::SystemError> {
+        Ok(self
+            .page_requests(page, |request| request.from_user_id == *user_id)
+            .into_iter()
+            .filter_map(|request| {
+                Some(FriendRequestResponse {
+                    id: request.id,
+                    from: IdOrInfo::Id(*user_id),
+                    to: IdOrInfo::Info(self.profile(&request.to_user_id)?),
+                    message: request.message,
+                    created_at: request.created_at,
+                })
+            })
+            .collect())
+    }
+
+    async fn find_friend_request_to_user(
+        &self,
+        user_id: &Uuid,
+        page: &FriendRequestPage,
+    ) -> Result<Vec<FriendRequestResponse>, error::SystemError> {
+        Ok(self
+            .page_requests(page, |request| request.to_user_id == *user_id)
+            .into_iter()
+            .filter_map(|request| {
+                Some(FriendRequestResponse {
+                    id: request.id,
+                    from: IdOrInfo::Info(self.profile(&request.from_user_id)?),
+                    to: IdOrInfo::Id(*user_id),
+                    message: request.message,
+                    created_at: request.created_at,
+                })
+            })
+            .collect())
+    }
+
+    async fn create_friend_request(
+        &self,
+        sender_id: &Uuid,
+        receiver_id: &Uuid,
+        message: &Option<String>,
+    ) -> Result<FriendRequestEntity, error::SystemError> {
+        let request = FriendRequestEntity {
+            id: Uuid::now_v7(),
+            from_user_id: *sender_id,
+            to_user_id: *receiver_id,
+            message: message.clone(),
+            created_at: chrono::Utc::now(),
+        };
+        self.state().requests.push(request.clone());
+        Ok(request)
+    }
+
+    async fn delete_friend_request(
+        &self,
+        request_id: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
+        self.state().requests.retain(|request| request.id != *request_id);
+        Ok(())
+    }
+
+    async fn delete_friend_requests_between(
+        &self,
+        user_id_a: &Uuid,
+        user_id_b: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
+        self.state().requests.retain(|request| {
+            !((request.from_user_id == *user_id_a && request.to_user_id == *user_id_b)
+                || (request.from_user_id == *user_id_b && request.to_user_id == *user_id_a))
+        });
+        Ok(())
+    }
+
+    async fn count_friend_requests(
+        &self,
+        user_id: &Uuid,
+        expires_before: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<FriendRequestCounts, error::SystemError> {
+        let state = self.state();
+        let pending = || state.requests.iter().filter(|r| r.created_at >= *expires_before);
+        Ok(FriendRequestCounts {
+            incoming: pending().filter(|r| r.to_user_id == *user_id).count() as i64,
+            outgoing: pending().filter(|r| r.from_user_id == *user_id).count() as i64,
+        })
+    }
+
+    async fn delete_expired_friend_requests(
+        &self,
+        expires_before: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, error::SystemError> {
+        let mut state = self.state();
+        let before = state.requests.len();
+        state.requests.retain(|request| request.created_at >= *expires_before);
+        Ok((before - state.requests.len()) as u64)
+    }
+}
+
+#[async_trait::async_trait]
+impl BlockRepository for FriendRepositoryMock {
+    async fn create_block(
+        &self,
+        blocker_id: &Uuid,
+        blocked_id: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
+        self.state().blocks.entry((*blocker_id, *blocked_id)).or_insert_with(chrono::Utc::now);
+        Ok(())
+    }
+
+    async fn delete_block(
+        &self,
+        blocker_id: &Uuid,
+        blocked_id: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        Ok(self.state().blocks.remove(&(*blocker_id, *blocked_id)).is_some())
+    }
+
+    async fn find_blocked_users(
+        &self,
+        blocker_id: &Uuid,
+    ) -> Result<Vec<BlockedUserResponse>, error::SystemError> {
+        let blocks: Vec<_> = self
+            .state()
+            .blocks
+            .iter()
+            .filter(|((blocker, _), _)| blocker == blocker_id)
+            .map(|((_, blocked), blocked_at)| (*blocked, *blocked_at))
+            .collect();
+
+        let mut users: Vec<_> = blocks
+            .into_iter()
+            .filter_map(|(id, blocked_at)| {
+                let user = self.users.get(&id)?;
+                Some(BlockedUserResponse {
+                    id,
+                    username: user.username,
+                    display_name: user.display_name,
+                    avatar_url: user.avatar_url,
+                    blocked_at,
+                })
+            })
+            .collect();
+        users.sort_by_key(|user| Reverse(user.blocked_at));
+
+        Ok(users)
+    }
+
+    async fn is_blocked_between(
+        &self,
+        user_id_a: &Uuid,
+        user_id_b: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        Ok(self.is_blocked(user_id_a, user_id_b) || self.is_blocked(user_id_b, user_id_a))
+    }
+}
+
+impl FriendRepo for FriendRepositoryMock {}
@@ -2,9 +2,13 @@ use uuid::Uuid;
 
 use crate::{
     api::error,
+    configs::uow::Transaction,
     modules::friend::{
-        model::{FriendRequestResponse, FriendResponse, FriendUserRow, IdOrInfo},
-        repository::{FriendRepo, FriendRepository, FriendRequestRepository},
+        model::{
+            BlockedUserResponse, FriendRequestCounts, FriendRequestPage, FriendRequestResponse,
+            FriendResponse, FriendSuggestion, FriendUserRow, IdOrInfo,
+        },
+        repository::{BlockRepository, FriendRepo, FriendRepository, FriendRequestRepository},
         schema::{FriendEntity, FriendRequestEntity},
     },
 };
@@ -14,7 +18,112 @@ pub struct FriendRepositoryPg {
     pool: sqlx::PgPool,
 }
 
+#[async_trait::async_trait]
+impl BlockRepository for FriendRepositoryPg {
+    async fn create_block(
+        &self,
+        blocker_id: &Uuid,
+        blocked_id: &Uuid,
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
+        sqlx::query(
+            "INSERT INTO user_blocks (blocker_id, blocked_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        )
+        .bind(blocker_id)
+        .bind(blocked_id)
+        .execute(tx.connection()?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_block(
+        &self,
+        blocker_id: &Uuid,
+        blocked_id: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        let rows = sqlx::query("DELETE FROM user_blocks WHERE blocker_id = $1 AND blocked_id = $2")
+            .bind(blocker_id)
+            .bind(blocked_id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(rows > 0)
+    }
+
+    async fn find_blocked_users(
+        &self,
+        blocker_id: &Uuid,
+    ) -> Result<Vec<BlockedUserResponse>, error::SystemError> {
+        let users = sqlx::query_as::<_, BlockedUserResponse>(
+            r#"
+            SELECT u.id, u.username, u.display_name, u.avatar_url, b.created_at AS blocked_at
+            FROM user_blocks b
+            JOIN users u ON u.id = b.blocked_id
+            WHERE b.blocker_id = $1
+            ORDER BY b.created_at DESC
+            "#,
+        )
+        .bind(blocker_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(users)
+    }
+
+    async fn is_blocked_between(
+        &self,
+        user_id_a: &Uuid,
+        user_id_b: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        let blocked = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM user_blocks
+                WHERE (blocker_id = $1 AND blocked_id = $2)
+                   OR (blocker_id = $2 AND blocked_id = $1)
+            )
+            "#,
+        )
+        .bind(user_id_a)
+        .bind(user_id_b)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(blocked)
+    }
+}
+
 impl FriendRepositoryPg {
+    /// Những user hiển thị profile của `user_id` và cần nhận `profile-updated`:
+    /// friends và thành viên active của các conversations chung
+    pub async fn find_profile_audience(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Vec<Uuid>, error::SystemError> {
+        let ids = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT CASE WHEN f.user_a = $1 THEN f.user_b ELSE f.user_a END
+            FROM friends f
+            WHERE f.user_a = $1 OR f.user_b = $1
+            UNION
+            SELECT other.user_id
+            FROM participants mine
+            JOIN participants other
+              ON other.conversation_id = mine.conversation_id
+             AND other.user_id <> $1
+             AND other.status = 'active'
+            WHERE mine.user_id = $1 AND mine.status = 'active'
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
+
     pub fn new(pool: sqlx::PgPool) -> Self {
         Self { pool }
     }
@@ -22,15 +131,11 @@ impl FriendRepositoryPg {
 
 #[async_trait::async_trait]
 impl FriendRepository for FriendRepositoryPg {
-    async fn find_friendship<'e, E>(
+    async fn find_friendship(
         &self,
         user_id_a: &Uuid,
         user_id_b: &Uuid,
-        tx: E,
-    ) -> Result<Option<FriendEntity>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+    ) -> Result<Option<FriendEntity>, error::SystemError> {
         let (user_a, user_b) =
             if user_id_a <= user_id_b { (user_id_a, user_id_b) } else { (user_id_b, user_id_a) };
 
@@ -39,20 +144,16 @@ impl FriendRepository for FriendRepositoryPg {
         )
         .bind(user_a)
         .bind(user_b)
-        .fetch_optional(tx)
+        .fetch_optional(&self.pool)
         .await?;
 
         Ok(friendship)
     }
 
-    async fn find_friends<'e, E>(
+    async fn find_friends(
         &self,
         user_id: &Uuid,
-        tx: E,
-    ) -> Result<Vec<FriendResponse>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+    ) -> Result<Vec<FriendResponse>, error::SystemError> {
         let friends = sqlx::query_as::<_, FriendResponse>(
             r#"
         SELECT
@@ -67,71 +168,147 @@ impl FriendRepository for FriendRepositoryPg {
                 WHEN f.user_a = $1 THEN f.user_b
                 ELSE f.user_a
             END
-        WHERE f.user_a = $1
-           OR f.user_b = $1
+        WHERE (f.user_a = $1 OR f.user_b = $1)
+          AND u.deactivated_at IS NULL
         "#,
         )
         .bind(user_id)
-        .fetch_all(tx)
+        .fetch_all(&self.pool)
         .await?;
 
         Ok(friends)
     }
 
-    async fn create_friendship<'e, E>(
+    async fn create_friendship(
         &self,
         user_id_a: &Uuid,
         user_id_b: &Uuid,
-        tx: E,
-    ) -> Result<(), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
         let (user_a, user_b) =
             if user_id_a <= user_id_b { (user_id_a, user_id_b) } else { (user_id_b, user_id_a) };
 
         sqlx::query("INSERT INTO friends (user_a, user_b) VALUES ($1, $2) ON CONFLICT DO NOTHING")
             .bind(user_a)
             .bind(user_b)
-            .execute(tx)
+            .execute(tx.connection()?)
             .await?;
 
         Ok(())
     }
 
-    async fn delete_friendship<'e, E>(
+    async fn delete_friendship(
         &self,
         user_id_a: &Uuid,
         user_id_b: &Uuid,
-        tx: E,
-    ) -> Result<(), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
         let (user_a, user_b) =
             if user_id_a <= user_id_b { (user_id_a, user_id_b) } else { (user_id_b, user_id_a) };
 
         sqlx::query("DELETE FROM friends WHERE user_a = $1 AND user_b = $2")
             .bind(user_a)
             .bind(user_b)
-            .execute(tx)
+            .execute(tx.connection()?)
             .await?;
 
         Ok(())
     }
+
+    async fn has_mutual_friend(
+        &self,
+        user_id_a: &Uuid,
+        user_id_b: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        let mutual = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM friends fa
+                JOIN friends fb
+                  ON (CASE WHEN fa.user_a = $1 THEN fa.user_b ELSE fa.user_a END)
+                   = (CASE WHEN fb.user_a = $2 THEN fb.user_b ELSE fb.user_a END)
+                WHERE (fa.user_a = $1 OR fa.user_b = $1)
+                  AND (fb.user_a = $2 OR fb.user_b = $2)
+            )
+            "#,
+        )
+        .bind(user_id_a)
+        .bind(user_id_b)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(mutual)
+    }
+
+    async fn find_friend_suggestions(
+        &self,
+        user_id: &Uuid,
+        limit: i64,
+    ) -> Result<Vec<FriendSuggestion>, error::SystemError> {
+        // Self-join friends 2 bậc: bạn của bạn (qua `via`), đếm số bạn chung khác nhau
+        let suggestions = sqlx::query_as::<_, FriendSuggestion>(
+            r#"
+            WITH my_friends AS (
+                SELECT CASE WHEN f.user_a = $1 THEN f.user_b ELSE f.user_a END AS id
+                FROM friends f
+                WHERE f.user_a = $1 OR f.user_b = $1
+            ),
+            candidates AS (
+                SELECT
+                    CASE WHEN f.user_a = mf.id THEN f.user_b ELSE f.user_a END AS id,
+                    mf.id AS via
+                FROM my_friends mf
+                JOIN friends f ON f.user_a = mf.id OR f.user_b = mf.id
+            )
+            SELECT
+                u.id,
+                u.username,
+                u.display_name,
+                u.avatar_url,
+                COUNT(DISTINCT c.via) AS mutual_count
+            FROM candidates c
+            JOIN users u ON u.id = c.id
+            WHERE c.id <> $1
+              AND c.id NOT IN (SELECT id FROM my_friends)
+              AND NOT EXISTS (
+                  SELECT 1 FROM friend_requests fr
+                  WHERE (fr.from_user_id = $1 AND fr.to_user_id = c.id)
+                     OR (fr.from_user_id = c.id AND fr.to_user_id = $1)
+              )
+              AND NOT EXISTS (
+                  SELECT 1 FROM user_blocks b
+                  WHERE (b.blocker_id = $1 AND b.blocked_id = c.id)
+                     OR (b.blocker_id = c.id AND b.blocked_id = $1)
+              )
+              AND NOT EXISTS (
+                  SELECT 1 FROM user_settings s
+                  WHERE s.user_id = c.id AND s.friend_request_policy = 'nobody'
+              )
+              AND u.deleted_at IS NULL
+              AND u.banned_at IS NULL
+              AND u.deactivated_at IS NULL
+            GROUP BY u.id
+            ORDER BY mutual_count DESC, u.display_name
+            LIMIT $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(suggestions)
+    }
 }
 
 #[async_trait::async_trait]
 impl FriendRequestRepository for FriendRepositoryPg {
-    async fn find_friend_request<'e, E>(
+    async fn find_friend_request(
         &self,
         sender_id: &Uuid,
         receiver_id: &Uuid,
-        tx: E,
-    ) -> Result<Option<FriendRequestEntity>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+    ) -> Result<Option<FriendRequestEntity>, error::SystemError> {
         let request = sqlx::query_as::<_, FriendRequestEntity>(
             r#"
             SELECT *
@@ -143,37 +320,31 @@ impl FriendRequestRepository for FriendRepositoryPg {
         )
         .bind(sender_id)
         .bind(receiver_id)
-        .fetch_optional(tx)
+        .fetch_optional(&self.pool)
         .await?;
 
         Ok(request)
     }
 
-    async fn find_friend_request_by_id<'e, E>(
+    async fn find_friend_request_by_id(
         &self,
         request_id: &Uuid,
-        tx: E,
-    ) -> Result<Option<FriendRequestEntity>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<Option<FriendRequestEntity>, error::SystemError> {
         let request =
             sqlx::query_as::<_, FriendRequestEntity>("SELECT * FROM friend_requests WHERE id = $1")
                 .bind(request_id)
-                .fetch_optional(tx)
+                .fetch_optional(tx.connection()?)
                 .await?;
 
         Ok(request)
     }
 
-    async fn find_friend_request_from_user<'e, E>(
+    async fn find_friend_request_from_user(
         &self,
         user_id: &Uuid,
-        tx: E,
-    ) -> Result<Vec<FriendRequestResponse>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        page: &FriendRequestPage,
+    ) -> Result<Vec<FriendRequestResponse>, error::SystemError> {
         let rows = sqlx::query_as::<_, FriendUserRow>(
             r#"
             SELECT
@@ -189,10 +360,20 @@ impl FriendRequestRepository for FriendRepositoryPg {
             JOIN users u
                 ON fr.to_user_id = u.id
             WHERE fr.from_user_id = $1
+              AND fr.created_at >= $2
+              AND ($3::timestamptz IS NULL OR fr.created_at < $3)
+              AND ($5::timestamptz IS NULL OR fr.created_at > $5)
+            ORDER BY CASE WHEN $5::timestamptz IS NOT NULL THEN fr.created_at END ASC,
+                     fr.created_at DESC
+            LIMIT $4
             "#,
         )
         .bind(user_id)
-        .fetch_all(tx)
+        .bind(page.expires_before)
+        .bind(page.created_before)
+        .bind(page.limit)
+        .bind(page.created_after)
+        .fetch_all(&self.pool)
         .await?;
 
         Ok(rows
@@ -212,14 +393,11 @@ impl FriendRequestRepository for FriendRepositoryPg {
             .collect())
     }
 
-    async fn find_friend_request_to_user<'e, E>(
+    async fn find_friend_request_to_user(
         &self,
         user_id: &Uuid,
-        tx: E,
-    ) -> Result<Vec<FriendRequestResponse>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        page: &FriendRequestPage,
+    ) -> Result<Vec<FriendRequestResponse>, error::SystemError> {
         let rows = sqlx::query_as::<_, FriendUserRow>(
             r#"
             SELECT
@@ -235,10 +413,20 @@ impl FriendRequestRepository for FriendRepositoryPg {
             JOIN users u
                 ON fr.from_user_id = u.id
             WHERE fr.to_user_id = $1
+              AND fr.created_at >= $2
+              AND ($3::timestamptz IS NULL OR fr.created_at < $3)
+              AND ($5::timestamptz IS NULL OR fr.created_at > $5)
+            ORDER BY CASE WHEN $5::timestamptz IS NOT NULL THEN fr.created_at END ASC,
+                     fr.created_at DESC
+            LIMIT $4
             "#,
         )
         .bind(user_id)
-        .fetch_all(tx)
+        .bind(page.expires_before)
+        .bind(page.created_before)
+        .bind(page.limit)
+        .bind(page.created_after)
+        .fetch_all(&self.pool)
         .await?;
 
         Ok(rows
@@ -258,16 +446,12 @@ impl FriendRequestRepository for FriendRepositoryPg {
             .collect())
     }
 
-    async fn create_friend_request<'e, E>(
+    async fn create_friend_request(
         &self,
         sender_id: &Uuid,
         receiver_id: &Uuid,
         message: &Option<String>,
-        tx: E,
-    ) -> Result<FriendRequestEntity, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+    ) -> Result<FriendRequestEntity, error::SystemError> {
         let id = Uuid::now_v7();
         let request = sqlx::query_as::<_, FriendRequestEntity>(
             r#"
@@ -280,27 +464,81 @@ impl FriendRequestRepository for FriendRepositoryPg {
         .bind(sender_id)
         .bind(receiver_id)
         .bind(message)
-        .fetch_one(tx)
+        .fetch_one(&self.pool)
         .await?;
 
         Ok(request)
     }
 
-    async fn delete_friend_request<'e, E>(
+    async fn delete_friend_request(
         &self,
         request_id: &Uuid,
-        tx: E,
-    ) -> Result<(), error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
         sqlx::query("DELETE FROM friend_requests WHERE id = $1")
             .bind(request_id)
-            .execute(tx)
+            .execute(tx.connection()?)
             .await?;
 
         Ok(())
     }
+
+    async fn delete_friend_requests_between(
+        &self,
+        user_id_a: &Uuid,
+        user_id_b: &Uuid,
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
+        sqlx::query(
+            r#"
+            DELETE FROM friend_requests
+            WHERE (from_user_id = $1 AND to_user_id = $2)
+               OR (from_user_id = $2 AND to_user_id = $1)
+            "#,
+        )
+        .bind(user_id_a)
+        .bind(user_id_b)
+        .execute(tx.connection()?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn count_friend_requests(
+        &self,
+        user_id: &Uuid,
+        expires_before: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<FriendRequestCounts, error::SystemError> {
+        let counts = sqlx::query_as::<_, FriendRequestCounts>(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE to_user_id = $1) AS incoming,
+                COUNT(*) FILTER (WHERE from_user_id = $1) AS outgoing
+            FROM friend_requests
+            WHERE (to_user_id = $1 OR from_user_id = $1)
+              AND created_at >= $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(expires_before)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(counts)
+    }
+
+    async fn delete_expired_friend_requests(
+        &self,
+        expires_before: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, error::SystemError> {
+        let deleted = sqlx::query("DELETE FROM friend_requests WHERE created_at < $1")
+            .bind(expires_before)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(deleted)
+    }
 }
 
 impl FriendRepositoryPg {
@@ -322,9 +560,4 @@ impl FriendRepositoryPg {
     }
 }
 
-#[async_trait::async_trait]
-impl FriendRepo for FriendRepositoryPg {
-    fn get_pool(&self) -> &sqlx::PgPool {
-        &self.pool
-    }
-}
+impl FriendRepo for FriendRepositoryPg {}
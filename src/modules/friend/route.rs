@@ -1,5 +1,6 @@
 use crate::modules::friend::handle::*;
 use actix_web::web::{scope, ServiceConfig};
+use utoipa::OpenApi;
 
 pub fn configure(cfg: &mut ServiceConfig) {
     cfg.service(
@@ -7,8 +8,30 @@ pub fn configure(cfg: &mut ServiceConfig) {
             .service(send_friend_request)
             .service(accept_friend_request)
             .service(decline_friend_request)
+            .service(cancel_friend_request)
             .service(list_friends)
             .service(list_friend_requests)
+            .service(list_friend_suggestions)
+            .service(list_blocked_users)
+            .service(block_user)
+            .service(unblock_user)
             .service(remove_friend),
     );
 }
+
+/// OpenAPI paths của `configure` (scope `/friends`)
+#[derive(OpenApi)]
+#[openapi(paths(
+    send_friend_request,
+    accept_friend_request,
+    decline_friend_request,
+    cancel_friend_request,
+    list_friends,
+    list_friend_requests,
+    list_friend_suggestions,
+    list_blocked_users,
+    block_user,
+    unblock_user,
+    remove_friend
+))]
+pub struct FriendApiDoc;
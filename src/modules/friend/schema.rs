@@ -1,5 +1,6 @@
 use serde::Serialize;
 use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, FromRow)]
@@ -10,7 +11,7 @@ pub struct FriendEntity {
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, FromRow)]
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
 pub struct FriendRequestEntity {
     pub id: Uuid,
     pub from_user_id: Uuid,
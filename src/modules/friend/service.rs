@@ -1,36 +1,63 @@
 use std::sync::Arc;
 
+use actix::Addr;
 use uuid::Uuid;
 
 use crate::{
-    api::error,
+    api::{error, pagination::PageInfo},
+    configs::{cache::CacheBackend, uow::UnitOfWork},
     modules::{
         friend::{
-            model::{FriendRequestResponse, FriendResponse},
+            model::{
+                BlockedUserResponse, FriendRequestListResponse, FriendRequestPage, FriendResponse,
+                FriendSuggestion,
+            },
             repository::FriendRepo,
             schema::{FriendEntity, FriendRequestEntity},
         },
-        user::repository::UserRepository,
+        message::{model::CursorDirection, service::DirectMessagePolicy},
+        user::{repository::UserRepository, schema::FriendRequestPolicy},
+        websocket::{events::SendToUser, message::ServerMessage, server::WebSocketServer},
     },
 };
 
+/// Số gợi ý kết bạn tối đa trả về
+const SUGGESTION_LIMIT: i64 = 20;
+/// Gợi ý được cache ngắn hạn vì query friends-of-friends khá nặng
+const SUGGESTION_CACHE_TTL: usize = 5 * 60;
+
+pub fn suggestions_key(user_id: &Uuid) -> String {
+    format!("friend_suggestions:{user_id}")
+}
+
+/// Lời mời kết bạn tự hết hạn sau khoảng thời gian này
+const FRIEND_REQUEST_TTL_DAYS: i64 = 30;
+/// Số lời mời mặc định mỗi trang
+const DEFAULT_REQUEST_PAGE_SIZE: i64 = 20;
+
+/// Lời mời tạo trước thời điểm này đã hết hạn
+fn request_expiry_cutoff() -> chrono::DateTime<chrono::Utc> {
+    chrono::Utc::now() - chrono::Duration::days(FRIEND_REQUEST_TTL_DAYS)
+}
+
 #[derive(Clone)]
-pub struct FriendService<R, U>
-where
-    R: FriendRepo + Send + Sync,
-    U: UserRepository + Send + Sync,
-{
-    friend_repo: Arc<R>,
-    user_repo: Arc<U>,
+pub struct FriendService {
+    friend_repo: Arc<dyn FriendRepo + Send + Sync>,
+    user_repo: Arc<dyn UserRepository + Send + Sync>,
+    uow: Arc<dyn UnitOfWork>,
+    cache: Arc<dyn CacheBackend>,
+    ws_server: Arc<Addr<WebSocketServer>>,
 }
 
-impl<R, U> FriendService<R, U>
-where
-    R: FriendRepo + Send + Sync,
-    U: UserRepository + Send + Sync,
-{
-    pub fn with_dependencies(friend_repo: Arc<R>, user_repo: Arc<U>) -> Self {
-        FriendService { friend_repo, user_repo }
+impl FriendService {
+    pub fn with_dependencies(
+        friend_repo: Arc<dyn FriendRepo + Send + Sync>,
+        user_repo: Arc<dyn UserRepository + Send + Sync>,
+        uow: Arc<dyn UnitOfWork>,
+        cache: Arc<dyn CacheBackend>,
+        ws_server: Arc<Addr<WebSocketServer>>,
+    ) -> Self {
+        FriendService { friend_repo, user_repo, uow, cache, ws_server }
     }
 
     #[allow(dead_code)]
@@ -39,10 +66,7 @@ where
         user_id: Uuid,
         friend_id: Uuid,
     ) -> Result<bool, error::SystemError> {
-        let friendship = self
-            .friend_repo
-            .find_friendship(&user_id, &friend_id, self.friend_repo.get_pool())
-            .await?;
+        let friendship = self.friend_repo.find_friendship(&user_id, &friend_id).await?;
         Ok(friendship.is_some())
     }
 
@@ -50,7 +74,7 @@ where
         &self,
         user_id: Uuid,
     ) -> Result<Vec<FriendResponse>, error::SystemError> {
-        let friends = self.friend_repo.find_friends(&user_id, self.friend_repo.get_pool()).await?;
+        let friends = self.friend_repo.find_friends(&user_id).await?;
         Ok(friends)
     }
 
@@ -59,7 +83,60 @@ where
         user_id: Uuid,
         friend_id: Uuid,
     ) -> Result<(), error::SystemError> {
-        self.friend_repo.delete_friendship(&user_id, &friend_id, self.friend_repo.get_pool()).await
+        self.friend_repo
+            .delete_friendship(&user_id, &friend_id, &mut self.uow.autocommit().await?)
+            .await?;
+        self.invalidate_suggestions(&[user_id, friend_id]).await;
+        Ok(())
+    }
+
+    /// Chặn user: hủy kết bạn và xóa lời mời giữa 2 người. Người bị chặn không gửi được
+    /// lời mời kết bạn hay tin nhắn trực tiếp (theo cả 2 chiều) cho tới khi được bỏ chặn
+    pub async fn block_user(
+        &self,
+        user_id: Uuid,
+        blocked_id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        if user_id == blocked_id {
+            return Err(error::SystemError::bad_request("Cannot block yourself"));
+        }
+
+        if self.user_repo.find_by_id(&blocked_id).await?.is_none() {
+            return Err(error::SystemError::not_found("User not found"));
+        }
+
+        let mut tx = self.uow.begin().await?;
+
+        self.friend_repo.create_block(&user_id, &blocked_id, &mut tx).await?;
+        self.friend_repo.delete_friendship(&user_id, &blocked_id, &mut tx).await?;
+        self.friend_repo.delete_friend_requests_between(&user_id, &blocked_id, &mut tx).await?;
+
+        tx.commit().await?;
+
+        self.invalidate_suggestions(&[user_id, blocked_id]).await;
+
+        Ok(())
+    }
+
+    pub async fn unblock_user(
+        &self,
+        user_id: Uuid,
+        blocked_id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        if !self.friend_repo.delete_block(&user_id, &blocked_id).await? {
+            return Err(error::SystemError::not_found("User is not blocked"));
+        }
+
+        self.invalidate_suggestions(&[user_id, blocked_id]).await;
+
+        Ok(())
+    }
+
+    pub async fn get_blocked_users(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<BlockedUserResponse>, error::SystemError> {
+        self.friend_repo.find_blocked_users(&user_id).await
     }
 
     pub async fn send_friend_request(
@@ -72,8 +149,14 @@ where
             return Err(error::SystemError::bad_request("Cannot send friend request to yourself"));
         }
 
-        if self.user_repo.find_by_id(&receiver_id).await?.is_none() {
-            return Err(error::SystemError::not_found("Receiver user not found"));
+        let receiver = self
+            .user_repo
+            .find_by_id(&receiver_id)
+            .await?
+            .ok_or_else(|| error::SystemError::not_found("Receiver user not found"))?;
+        if receiver.deactivated_at.is_some() {
+            return Err(error::SystemError::forbidden("This account is deactivated")
+                .with_code(error::ErrorCode::AccountDeactivated));
         }
 
         let (u1, u2) = if sender_id <= receiver_id {
@@ -82,25 +165,52 @@ where
             (receiver_id, sender_id)
         };
 
-        let pool = self.friend_repo.get_pool();
+        if self.friend_repo.is_blocked_between(&sender_id, &receiver_id).await? {
+            return Err(error::SystemError::forbidden(
+                "You cannot send a friend request to this user",
+            )
+            .with_code(error::ErrorCode::Blocked));
+        }
 
         let (friends, requests): (Option<FriendEntity>, Option<FriendRequestEntity>) = tokio::try_join!(
-            self.friend_repo.find_friendship(&u1, &u2, pool),
-            self.friend_repo.find_friend_request(&sender_id, &receiver_id, pool),
+            self.friend_repo.find_friendship(&u1, &u2),
+            self.friend_repo.find_friend_request(&sender_id, &receiver_id),
         )?;
 
         if friends.is_some() {
             return Err(error::SystemError::bad_request("Users are already friends"));
         }
 
-        if requests.is_some() {
-            return Err(error::SystemError::bad_request("Friend request already exists"));
+        let policy = self.user_repo.find_settings(&receiver_id).await?.unwrap_or_default();
+        let allowed = match policy.friend_request_policy {
+            FriendRequestPolicy::Everyone => true,
+            FriendRequestPolicy::FriendsOfFriends => {
+                self.friend_repo.has_mutual_friend(&sender_id, &receiver_id).await?
+            }
+            FriendRequestPolicy::Nobody => false,
+        };
+        if !allowed {
+            return Err(error::SystemError::forbidden(
+                "This user is not accepting friend requests from you",
+            ));
+        }
+
+        // Lời mời cũ đã hết hạn (chưa bị cleanup job xóa) không chặn lời mời mới
+        match requests {
+            Some(request) if request.created_at >= request_expiry_cutoff() => {
+                return Err(error::SystemError::bad_request("Friend request already exists"));
+            }
+            Some(request) => {
+                let mut conn = self.uow.autocommit().await?;
+                self.friend_repo.delete_friend_request(&request.id, &mut conn).await?
+            }
+            None => {}
         }
 
-        let friend_request = self
-            .friend_repo
-            .create_friend_request(&sender_id, &receiver_id, &message, pool)
-            .await?;
+        let friend_request =
+            self.friend_repo.create_friend_request(&sender_id, &receiver_id, &message).await?;
+
+        self.invalidate_suggestions(&[sender_id, receiver_id]).await;
 
         Ok(friend_request)
     }
@@ -110,12 +220,13 @@ where
         user_id: Uuid,
         request_id: Uuid,
     ) -> Result<FriendResponse, error::SystemError> {
-        let mut tx = self.friend_repo.get_pool().begin().await?;
+        let mut tx = self.uow.begin().await?;
 
         let request = self
             .friend_repo
-            .find_friend_request_by_id(&request_id, tx.as_mut())
+            .find_friend_request_by_id(&request_id, &mut tx)
             .await?
+            .filter(|request| request.created_at >= request_expiry_cutoff())
             .ok_or_else(|| error::SystemError::not_found("Friend request not found"))?;
 
         if request.to_user_id != user_id {
@@ -130,12 +241,14 @@ where
             (request.to_user_id, request.from_user_id)
         };
 
-        self.friend_repo.create_friendship(&u1, &u2, tx.as_mut()).await?;
+        self.friend_repo.create_friendship(&u1, &u2, &mut tx).await?;
 
-        self.friend_repo.delete_friend_request(&request_id, tx.as_mut()).await?;
+        self.friend_repo.delete_friend_request(&request_id, &mut tx).await?;
 
         tx.commit().await?;
 
+        self.invalidate_suggestions(&[request.from_user_id, request.to_user_id]).await;
+
         let from_user = self
             .user_repo
             .find_by_id(&request.from_user_id)
@@ -150,11 +263,11 @@ where
         user_id: Uuid,
         request_id: Uuid,
     ) -> Result<(), error::SystemError> {
-        let pool = self.friend_repo.get_pool();
+        let mut conn = self.uow.autocommit().await?;
 
         let request = self
             .friend_repo
-            .find_friend_request_by_id(&request_id, pool)
+            .find_friend_request_by_id(&request_id, &mut conn)
             .await?
             .ok_or_else(|| error::SystemError::not_found("Friend request not found"))?;
 
@@ -164,24 +277,351 @@ where
             ));
         }
 
-        self.friend_repo.delete_friend_request(&request_id, pool).await?;
+        self.friend_repo.delete_friend_request(&request_id, &mut conn).await?;
+
+        Ok(())
+    }
+
+    /// Người gửi thu hồi lời mời kết bạn, báo cho người nhận nếu đang online
+    pub async fn cancel_friend_request(
+        &self,
+        user_id: Uuid,
+        request_id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        let mut conn = self.uow.autocommit().await?;
+
+        let request = self
+            .friend_repo
+            .find_friend_request_by_id(&request_id, &mut conn)
+            .await?
+            .ok_or_else(|| error::SystemError::not_found("Friend request not found"))?;
+
+        if request.from_user_id != user_id {
+            return Err(error::SystemError::forbidden(
+                "You are not allowed to cancel this friend request",
+            ));
+        }
+
+        self.friend_repo.delete_friend_request(&request_id, &mut conn).await?;
+
+        self.invalidate_suggestions(&[request.from_user_id, request.to_user_id]).await;
+
+        self.ws_server.do_send(SendToUser {
+            user_id: request.to_user_id,
+            message: ServerMessage::FriendRequestCancelled {
+                request_id,
+                from_user_id: request.from_user_id,
+            },
+        });
 
         Ok(())
     }
 
+    /// Lời mời đến và đi chưa hết hạn, mới nhất trước, phân trang theo cursor `created_at`
+    /// theo cả hai chiều (`direction`)
     pub async fn get_friend_requests(
         &self,
         user_id: Uuid,
-    ) -> Result<Vec<FriendRequestResponse>, error::SystemError> {
-        let pool = self.friend_repo.get_pool();
-        let (requests_to, requests_from) = tokio::try_join!(
-            self.friend_repo.find_friend_request_to_user(&user_id, pool),
-            self.friend_repo.find_friend_request_from_user(&user_id, pool),
+        limit: Option<i64>,
+        cursor: Option<String>,
+        direction: CursorDirection,
+    ) -> Result<FriendRequestListResponse, error::SystemError> {
+        let cursor = match cursor {
+            Some(c) => Some(
+                chrono::DateTime::parse_from_rfc3339(&c)
+                    .map_err(|_| error::SystemError::bad_request("Invalid cursor format"))?
+                    .with_timezone(&chrono::Utc),
+            ),
+            None => None,
+        };
+        let (created_before, created_after) = match direction {
+            CursorDirection::Before => (cursor, None),
+            CursorDirection::After => (None, cursor),
+        };
+        let limit = limit.unwrap_or(DEFAULT_REQUEST_PAGE_SIZE);
+        let expires_before = request_expiry_cutoff();
+        // Lấy thừa 1 ở mỗi phía để biết còn trang sau
+        let page =
+            FriendRequestPage { created_before, created_after, expires_before, limit: limit + 1 };
+
+        let (requests_to, requests_from, counts) = tokio::try_join!(
+            self.friend_repo.find_friend_request_to_user(&user_id, &page),
+            self.friend_repo.find_friend_request_from_user(&user_id, &page),
+            self.friend_repo.count_friend_requests(&user_id, &expires_before),
+        )?;
+
+        // Sắp theo hướng phân trang để cắt trang, response luôn mới nhất trước
+        let mut requests = Vec::with_capacity(requests_to.len() + requests_from.len());
+        requests.extend(requests_to);
+        requests.extend(requests_from);
+        requests.sort_by_key(|request| std::cmp::Reverse(request.created_at));
+        if direction == CursorDirection::After {
+            requests.reverse();
+        }
+
+        let has_more = requests.len() > limit as usize;
+        requests.truncate(limit as usize);
+        let next_cursor =
+            if has_more { requests.last().map(|r| r.created_at.to_rfc3339()) } else { None };
+        let prev_cursor = cursor.and(requests.first().map(|r| r.created_at.to_rfc3339()));
+
+        if direction == CursorDirection::After {
+            requests.reverse();
+        }
+        let page = PageInfo::new(next_cursor.clone(), prev_cursor)
+            .with_total(counts.incoming + counts.outgoing);
+
+        Ok(FriendRequestListResponse { requests, cursor: next_cursor, counts, page })
+    }
+
+    /// Xóa các lời mời kết bạn đã hết hạn (cleanup job)
+    pub async fn delete_expired_friend_requests(&self) -> Result<u64, error::SystemError> {
+        self.friend_repo.delete_expired_friend_requests(&request_expiry_cutoff()).await
+    }
+
+    /// Gợi ý kết bạn (friends-of-friends), cache theo user trong vài phút
+    pub async fn get_friend_suggestions(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<FriendSuggestion>, error::SystemError> {
+        let key = suggestions_key(&user_id);
+        match self.cache.get::<Vec<FriendSuggestion>>(&key).await {
+            Ok(Some(cached)) => return Ok(cached),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to read cached friend suggestions: {}", e),
+        }
+
+        let suggestions =
+            self.friend_repo.find_friend_suggestions(&user_id, SUGGESTION_LIMIT).await?;
+
+        if let Err(e) = self.cache.set(&key, &suggestions, SUGGESTION_CACHE_TTL).await {
+            tracing::warn!("Failed to cache friend suggestions: {}", e);
+        }
+
+        Ok(suggestions)
+    }
+
+    /// Xóa cache gợi ý khi quan hệ bạn bè / lời mời thay đổi
+    async fn invalidate_suggestions(&self, user_ids: &[Uuid]) {
+        for user_id in user_ids {
+            if let Err(e) = self.cache.delete(&suggestions_key(user_id)).await {
+                tracing::warn!("Failed to invalidate friend suggestions for {}: {}", user_id, e);
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl DirectMessagePolicy for FriendService {
+    async fn check_direct_message(
+        &self,
+        sender_id: Uuid,
+        recipient_id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        let (blocked, friendship, recipient) = tokio::try_join!(
+            self.friend_repo.is_blocked_between(&sender_id, &recipient_id),
+            self.friend_repo.find_friendship(&sender_id, &recipient_id),
+            self.user_repo.find_by_id(&recipient_id),
         )?;
 
-        let mut all = Vec::with_capacity(requests_to.len() + requests_from.len());
-        all.extend(requests_to);
-        all.extend(requests_from);
-        Ok(all)
+        // Chặn nhau đồng nghĩa không còn là bạn, báo lỗi chặn trước để client hiển thị đúng
+        if blocked {
+            return Err(error::SystemError::forbidden("You cannot message this user")
+                .with_code(error::ErrorCode::Blocked));
+        }
+        let recipient =
+            recipient.ok_or_else(|| error::SystemError::not_found("Recipient not found"))?;
+        if recipient.deactivated_at.is_some() {
+            return Err(error::SystemError::forbidden("This account is deactivated")
+                .with_code(error::ErrorCode::AccountDeactivated));
+        }
+        if friendship.is_none() {
+            return Err(error::SystemError::forbidden("You are not friends with the recipient")
+                .with_code(error::ErrorCode::NotFriends));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix::Actor;
+
+    use super::*;
+    use crate::{
+        configs::{cache::MemoryCache, uow::MemoryUnitOfWork},
+        constants::init_test_env,
+        modules::{
+            friend::{
+                model::{FriendRequestResponse, IdOrInfo},
+                repository_mock::FriendRepositoryMock,
+            },
+            user::{model::UserSettings, repository_mock::UserRepositoryMock},
+        },
+    };
+
+    struct Fixture {
+        users: UserRepositoryMock,
+        friends: FriendRepositoryMock,
+        service: FriendService,
+    }
+
+    fn fixture() -> Fixture {
+        init_test_env();
+        let users = UserRepositoryMock::default();
+        let friends = FriendRepositoryMock::new(users.clone());
+        let service = FriendService::with_dependencies(
+            Arc::new(friends.clone()),
+            Arc::new(users.clone()),
+            Arc::new(MemoryUnitOfWork),
+            Arc::new(MemoryCache::default()),
+            Arc::new(WebSocketServer::new().start()),
+        );
+        Fixture { users, friends, service }
+    }
+
+    fn user(users: &UserRepositoryMock, username: &str) -> Uuid {
+        users.insert(UserRepositoryMock::entity(username, "unused-hash"))
+    }
+
+    #[actix_web::test]
+    async fn send_friend_request_rejects_pending_request_in_either_direction() {
+        let Fixture { users, friends, service } = fixture();
+        let alice = user(&users, "alice");
+        let bob = user(&users, "bob");
+
+        service.send_friend_request(alice, bob, Some("Hi".to_string())).await.unwrap();
+
+        for (sender, receiver) in [(alice, bob), (bob, alice)] {
+            let err = service.send_friend_request(sender, receiver, None).await.unwrap_err();
+            assert_eq!(err.code(), error::ErrorCode::BadRequest);
+        }
+        assert_eq!(friends.requests().len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn send_friend_request_is_forbidden_when_blocked() {
+        let Fixture { users, friends, service } = fixture();
+        let alice = user(&users, "alice");
+        let bob = user(&users, "bob");
+        friends.block(bob, alice);
+
+        let err = service.send_friend_request(alice, bob, None).await.unwrap_err();
+
+        assert_eq!(err.code(), error::ErrorCode::Blocked);
+        assert!(friends.requests().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn get_friend_requests_pages_in_both_directions() {
+        let Fixture { users, service, .. } = fixture();
+        let alice = user(&users, "alice");
+        let senders = ["bob", "carol", "dave"].map(|name| user(&users, name));
+        for sender in &senders {
+            service.send_friend_request(*sender, alice, None).await.unwrap();
+        }
+        let sender_of = |page: &FriendRequestListResponse| -> Vec<Uuid> {
+            let sender = |r: &FriendRequestResponse| match &r.from {
+                IdOrInfo::Id(id) => *id,
+                IdOrInfo::Info(info) => info.id,
+            };
+            page.requests.iter().map(sender).collect()
+        };
+
+        let first = service
+            .get_friend_requests(alice, Some(2), None, CursorDirection::Before)
+            .await
+            .unwrap();
+        assert_eq!(sender_of(&first), vec![senders[2], senders[1]]);
+        assert!(first.page.has_more);
+        assert_eq!(first.page.prev_cursor, None);
+        assert_eq!(first.page.total, Some(3));
+
+        let second = service
+            .get_friend_requests(alice, Some(2), first.page.next_cursor, CursorDirection::Before)
+            .await
+            .unwrap();
+        assert_eq!(sender_of(&second), vec![senders[0]]);
+        assert!(!second.page.has_more);
+
+        let back = service
+            .get_friend_requests(alice, Some(2), second.page.prev_cursor, CursorDirection::After)
+            .await
+            .unwrap();
+        assert_eq!(sender_of(&back), vec![senders[2], senders[1]]);
+        assert!(!back.page.has_more);
+    }
+
+    #[actix_web::test]
+    async fn accept_friend_request_creates_friendship_for_receiver_only() {
+        let Fixture { users, friends, service } = fixture();
+        let alice = user(&users, "alice");
+        let bob = user(&users, "bob");
+        let request = service.send_friend_request(alice, bob, None).await.unwrap();
+
+        let err = service.accept_friend_request(alice, request.id).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::Forbidden);
+        assert!(!users.is_friend(&alice, &bob));
+
+        let friend = service.accept_friend_request(bob, request.id).await.unwrap();
+
+        assert_eq!(friend.id, alice);
+        assert!(users.is_friend(&alice, &bob));
+        assert!(friends.requests().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn block_user_removes_friendship_and_pending_requests() {
+        let Fixture { users, friends, service } = fixture();
+        let alice = user(&users, "alice");
+        let bob = user(&users, "bob");
+        let carol = user(&users, "carol");
+        users.add_friendship(alice, bob);
+        service.send_friend_request(carol, alice, None).await.unwrap();
+
+        service.block_user(alice, bob).await.unwrap();
+        service.block_user(alice, carol).await.unwrap();
+
+        assert!(friends.is_blocked(&alice, &bob));
+        assert!(!users.is_friend(&alice, &bob));
+        assert!(friends.requests().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn friends_of_friends_policy_requires_mutual_friend() {
+        let Fixture { users, friends, service } = fixture();
+        let alice = user(&users, "alice");
+        let bob = user(&users, "bob");
+        let carol = user(&users, "carol");
+        let settings = UserSettings {
+            friend_request_policy: FriendRequestPolicy::FriendsOfFriends,
+            ..UserSettings::default()
+        };
+        users.upsert_settings(&bob, &settings).await.unwrap();
+
+        let err = service.send_friend_request(alice, bob, None).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::Forbidden);
+
+        users.add_friendship(alice, carol);
+        users.add_friendship(bob, carol);
+        service.send_friend_request(alice, bob, None).await.unwrap();
+        assert_eq!(friends.requests().len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn get_friends_skips_deactivated_users() {
+        let Fixture { users, service, .. } = fixture();
+        let alice = user(&users, "alice");
+        let bob = user(&users, "bob");
+        let mut carol = UserRepositoryMock::entity("carol", "unused-hash");
+        carol.deactivated_at = Some(chrono::Utc::now());
+        let carol = users.insert(carol);
+        users.add_friendship(alice, bob);
+        users.add_friendship(alice, carol);
+
+        let friends = service.get_friends(alice).await.unwrap();
+
+        assert_eq!(friends.iter().map(|friend| friend.id).collect::<Vec<_>>(), vec![bob]);
     }
 }
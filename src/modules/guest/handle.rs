@@ -0,0 +1,189 @@
+use actix_web::{delete, get, post, web, HttpRequest};
+use uuid::Uuid;
+
+use crate::{
+    api::{error, success},
+    middlewares::get_extensions,
+    modules::{
+        conversation::{model::MessageQueryRequest, service::ConversationService},
+        guest::{
+            model::{
+                CreateGuestInviteModel, CreatedGuestInvite, GuestSession, GuestSessionRequest,
+            },
+            schema::GuestInviteEntity,
+            service::GuestService,
+        },
+        message::{
+            model::{GetMessageResponse, SendGroupMessage},
+            schema::MessageEntity,
+            service::MessageService,
+        },
+    },
+    utils::{Claims, ValidatedJson, ValidatedQuery},
+};
+
+/// Guest principal của request và conversation duy nhất mà guest được truy cập
+fn guest_principal(req: &HttpRequest) -> Result<(Uuid, Uuid), error::Error> {
+    let claims = get_extensions::<Claims>(req)?;
+    let conversation_id = claims
+        .conversation_id
+        .ok_or_else(|| error::Error::forbidden("Token is not a guest token"))?;
+
+    Ok((claims.sub, conversation_id))
+}
+
+#[utoipa::path(
+    tag = "guests",
+    path = "/{conversation_id}/guest-invites",
+    params(("conversation_id" = Uuid, Path, description = "Conversation ID")),
+    request_body = CreateGuestInviteModel,
+    responses(
+        (
+            status = 201,
+            description = "Khách đổi `token` lấy access token qua `POST /api/v1/guests/session`, `token` chỉ được trả về một lần",
+            body = success::SuccessData<CreatedGuestInvite>
+        ),
+        (status = 403, description = "Không phải thành viên", body = error::ErrorBody)
+    )
+)]
+#[post("")]
+pub async fn create_guest_invite(
+    guest_service: web::Data<GuestService>,
+    conversation_id: web::Path<Uuid>,
+    ValidatedJson(body): ValidatedJson<CreateGuestInviteModel>,
+    req: HttpRequest,
+) -> Result<success::Success<CreatedGuestInvite>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let invite = guest_service.create_invite(*conversation_id, user_id, body).await?;
+
+    Ok(success::Success::created(Some(invite)).message("Guest invite created successfully"))
+}
+
+#[utoipa::path(
+    tag = "guests",
+    path = "/{conversation_id}/guest-invites",
+    params(("conversation_id" = Uuid, Path, description = "Conversation ID")),
+    responses(
+        (status = 200, body = success::SuccessData<Vec<GuestInviteEntity>>),
+        (status = 403, description = "Không phải thành viên", body = error::ErrorBody)
+    )
+)]
+#[get("")]
+pub async fn list_guest_invites(
+    guest_service: web::Data<GuestService>,
+    conversation_id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> Result<success::Success<Vec<GuestInviteEntity>>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let invites = guest_service.list_invites(*conversation_id, user_id).await?;
+
+    Ok(success::Success::ok(Some(invites)).message("Guest invites retrieved successfully"))
+}
+
+#[utoipa::path(
+    tag = "guests",
+    path = "/{conversation_id}/guest-invites/{invite_id}",
+    params(
+        ("conversation_id" = Uuid, Path, description = "Conversation ID"),
+        ("invite_id" = Uuid, Path, description = "Guest invite ID")
+    ),
+    responses(
+        (status = 204, description = "Lời mời đã bị revoke"),
+        (status = 404, description = "Không tìm thấy lời mời", body = error::ErrorBody)
+    )
+)]
+#[delete("/{invite_id}")]
+pub async fn revoke_guest_invite(
+    guest_service: web::Data<GuestService>,
+    path: web::Path<(Uuid, Uuid)>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let (conversation_id, invite_id) = path.into_inner();
+    guest_service.revoke_invite(conversation_id, invite_id, user_id).await?;
+
+    Ok(success::Success::no_content())
+}
+
+#[utoipa::path(
+    tag = "guests",
+    path = "/session",
+    request_body = GuestSessionRequest,
+    security(()),
+    responses(
+        (status = 200, body = success::SuccessData<GuestSession>),
+        (
+            status = 401,
+            description = "Token không hợp lệ, đã hết hạn / bị revoke hoặc người mời đã rời conversation",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[post("/session")]
+pub async fn guest_sign_in(
+    guest_service: web::Data<GuestService>,
+    ValidatedJson(body): ValidatedJson<GuestSessionRequest>,
+) -> Result<success::Success<GuestSession>, error::Error> {
+    let session = guest_service.sign_in(&body.token).await?;
+
+    Ok(success::Success::ok(Some(session)).message("Guest signed in successfully"))
+}
+
+#[utoipa::path(
+    tag = "guests",
+    path = "/messages",
+    request_body = SendGroupMessage,
+    responses(
+        (status = 200, body = success::SuccessData<MessageEntity>),
+        (status = 403, description = "Guest không còn là thành viên", body = error::ErrorBody)
+    )
+)]
+#[post("/messages")]
+pub async fn send_message(
+    conversation_svc: web::Data<ConversationService>,
+    message_service: web::Data<MessageService>,
+    ValidatedJson(body): ValidatedJson<SendGroupMessage>,
+    req: HttpRequest,
+) -> Result<success::Success<MessageEntity>, error::Error> {
+    let (guest_id, conversation_id) = guest_principal(&req)?;
+
+    let (conversation, is_member) =
+        conversation_svc.get_conversation_and_check_membership(conversation_id, guest_id).await?;
+    if conversation.is_none() {
+        return Err(error::Error::not_found("Conversation not found"));
+    }
+    if !is_member {
+        return Err(error::Error::forbidden("Guest is no longer a member of this conversation")
+            .with_code(error::ErrorCode::NotAMember));
+    }
+
+    let message = message_service
+        .send_message(guest_id, conversation_id, body.content, body.client, body.client_message_id)
+        .await?;
+
+    Ok(success::Success::ok(Some(message)).message("Send message successfully"))
+}
+
+#[utoipa::path(
+    tag = "guests",
+    path = "/messages",
+    params(MessageQueryRequest),
+    responses(
+        (status = 200, body = success::SuccessData<GetMessageResponse>),
+        (status = 403, description = "Guest không còn là thành viên", body = error::ErrorBody)
+    )
+)]
+#[get("/messages")]
+pub async fn get_messages(
+    conversation_svc: web::Data<ConversationService>,
+    ValidatedQuery(query): ValidatedQuery<MessageQueryRequest>,
+    req: HttpRequest,
+) -> Result<success::Success<GetMessageResponse>, error::Error> {
+    let (guest_id, conversation_id) = guest_principal(&req)?;
+
+    let messages = conversation_svc
+        .get_message(conversation_id, guest_id, query.limit, query.cursor.clone(), query.direction)
+        .await?;
+
+    Ok(success::Success::ok(Some(messages)).message("Successfully retrieved messages"))
+}
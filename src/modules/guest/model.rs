@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::modules::guest::schema::GuestInviteEntity;
+
+#[derive(Debug, Default, Deserialize, Validate, ToSchema)]
+pub struct CreateGuestInviteModel {
+    /// Thời hạn của lời mời (giờ), mặc định 7 ngày
+    #[validate(range(min = 1, max = 720, message = "Expiry must be between 1 and 720 hours"))]
+    pub expires_in_hours: Option<i64>,
+    /// Số khách tối đa có thể vào bằng lời mời, mặc định 1
+    #[validate(range(min = 1, max = 100, message = "Max uses must be between 1 and 100"))]
+    pub max_uses: Option<i32>,
+}
+
+/// Lời mời vừa tạo, `token` chỉ được trả về một lần
+#[derive(Serialize, ToSchema)]
+pub struct CreatedGuestInvite {
+    pub token: String,
+    #[serde(flatten)]
+    pub invite: GuestInviteEntity,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct GuestSessionRequest {
+    /// Token của lời mời guest
+    #[validate(length(
+        min = 1,
+        max = 128,
+        message = "Token must be between 1 and 128 characters"
+    ))]
+    pub token: String,
+}
+
+/// Phiên guest: access token chỉ dùng được cho `conversation_id`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GuestSession {
+    pub access_token: String,
+    pub user_id: Uuid,
+    pub conversation_id: Uuid,
+}
+
+/// Dữ liệu insert guest user
+pub struct InsertGuest {
+    pub id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub hash_password: String,
+}
@@ -0,0 +1,48 @@
+use uuid::Uuid;
+
+use crate::api::error;
+use crate::modules::guest::model::InsertGuest;
+use crate::modules::guest::schema::GuestInviteEntity;
+
+#[async_trait::async_trait]
+pub trait GuestRepository {
+    /// User là thành viên active của conversation
+    async fn is_member(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, error::SystemError>;
+
+    /// Lưu lời mời, chỉ hash (SHA-256) của token được lưu
+    async fn create_invite(
+        &self,
+        conversation_id: &Uuid,
+        token_hash: &str,
+        token_prefix: &str,
+        created_by: &Uuid,
+        max_uses: i32,
+        expires_at: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<GuestInviteEntity, error::SystemError>;
+
+    async fn find_invites(
+        &self,
+        conversation_id: &Uuid,
+    ) -> Result<Vec<GuestInviteEntity>, error::SystemError>;
+
+    /// Revoke lời mời, trả về ID các guest đã vào bằng lời mời này.
+    /// `None` nếu không tìm thấy lời mời (hoặc đã bị revoke)
+    async fn revoke_invite(
+        &self,
+        conversation_id: &Uuid,
+        id: &Uuid,
+    ) -> Result<Option<Vec<Uuid>>, error::SystemError>;
+
+    /// Tạo guest user từ lời mời còn hiệu lực (chưa hết lượt dùng, người mời vẫn là
+    /// thành viên) và thêm vào conversation. Trả về conversation ID, `None` nếu token
+    /// không hợp lệ
+    async fn redeem_invite(
+        &self,
+        token_hash: &str,
+        guest: &InsertGuest,
+    ) -> Result<Option<Uuid>, error::SystemError>;
+}
@@ -0,0 +1,143 @@
+/// In-memory `GuestRepository` cho unit tests của services
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::guest::{model::InsertGuest, repository::GuestRepository, schema::GuestInviteEntity},
+};
+
+#[derive(Default)]
+struct State {
+    /// (conversation_id, user_id)
+    members: HashSet<(Uuid, Uuid)>,
+    /// (invite, token_hash)
+    invites: Vec<(GuestInviteEntity, String)>,
+    /// invite_id -> guest user IDs
+    guests: HashMap<Uuid, Vec<Uuid>>,
+}
+
+/// Clone dùng chung dữ liệu, test giữ một bản để thêm thành viên và kiểm tra guests
+#[derive(Clone, Default)]
+pub struct GuestRepositoryMock {
+    state: Arc<Mutex<State>>,
+}
+
+impl GuestRepositoryMock {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn add_member(&self, conversation_id: Uuid, user_id: Uuid) {
+        self.state().members.insert((conversation_id, user_id));
+    }
+
+    pub fn remove_member(&self, conversation_id: Uuid, user_id: Uuid) {
+        self.state().members.remove(&(conversation_id, user_id));
+    }
+
+    /// Guest user IDs đã vào conversation (mọi lời mời)
+    pub fn guests_of(&self, conversation_id: Uuid) -> Vec<Uuid> {
+        let state = self.state();
+        state
+            .invites
+            .iter()
+            .filter(|(invite, _)| invite.conversation_id == conversation_id)
+            .flat_map(|(invite, _)| state.guests.get(&invite.id).into_iter().flatten().copied())
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl GuestRepository for GuestRepositoryMock {
+    async fn is_member(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        Ok(self.state().members.contains(&(*conversation_id, *user_id)))
+    }
+
+    async fn create_invite(
+        &self,
+        conversation_id: &Uuid,
+        token_hash: &str,
+        token_prefix: &str,
+        created_by: &Uuid,
+        max_uses: i32,
+        expires_at: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<GuestInviteEntity, error::SystemError> {
+        let invite = GuestInviteEntity {
+            id: Uuid::now_v7(),
+            conversation_id: *conversation_id,
+            token_prefix: token_prefix.to_string(),
+            created_by: *created_by,
+            max_uses,
+            use_count: 0,
+            expires_at: *expires_at,
+            created_at: Utc::now(),
+            revoked_at: None,
+        };
+        self.state().invites.push((invite.clone(), token_hash.to_string()));
+        Ok(invite)
+    }
+
+    async fn find_invites(
+        &self,
+        conversation_id: &Uuid,
+    ) -> Result<Vec<GuestInviteEntity>, error::SystemError> {
+        Ok(self
+            .state()
+            .invites
+            .iter()
+            .filter(|(invite, _)| invite.conversation_id == *conversation_id)
+            .map(|(invite, _)| invite.clone())
+            .collect())
+    }
+
+    async fn revoke_invite(
+        &self,
+        conversation_id: &Uuid,
+        id: &Uuid,
+    ) -> Result<Option<Vec<Uuid>>, error::SystemError> {
+        let mut state = self.state();
+        let Some((invite, _)) = state.invites.iter_mut().find(|(invite, _)| {
+            invite.id == *id
+                && invite.conversation_id == *conversation_id
+                && invite.revoked_at.is_none()
+        }) else {
+            return Ok(None);
+        };
+        invite.revoked_at = Some(Utc::now());
+
+        Ok(Some(state.guests.get(id).cloned().unwrap_or_default()))
+    }
+
+    async fn redeem_invite(
+        &self,
+        token_hash: &str,
+        guest: &InsertGuest,
+    ) -> Result<Option<Uuid>, error::SystemError> {
+        let mut state = self.state();
+        let State { members, invites, guests } = &mut *state;
+        let Some((invite, _)) = invites.iter_mut().find(|(invite, hash)| {
+            hash == token_hash
+                && invite.revoked_at.is_none()
+                && invite.expires_at > Utc::now()
+                && invite.use_count < invite.max_uses
+                && members.contains(&(invite.conversation_id, invite.created_by))
+        }) else {
+            return Ok(None);
+        };
+        invite.use_count += 1;
+        guests.entry(invite.id).or_default().push(guest.id);
+        members.insert((invite.conversation_id, guest.id));
+
+        Ok(Some(invite.conversation_id))
+    }
+}
@@ -0,0 +1,160 @@
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::guest::{model::InsertGuest, repository::GuestRepository, schema::GuestInviteEntity},
+};
+
+#[derive(Clone)]
+pub struct GuestRepositoryPg {
+    pool: sqlx::PgPool,
+}
+
+impl GuestRepositoryPg {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl GuestRepository for GuestRepositoryPg {
+    async fn is_member(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        let is_member = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM participants
+                WHERE conversation_id = $1
+                AND user_id = $2
+                AND status = 'active'
+            )
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(is_member)
+    }
+
+    async fn create_invite(
+        &self,
+        conversation_id: &Uuid,
+        token_hash: &str,
+        token_prefix: &str,
+        created_by: &Uuid,
+        max_uses: i32,
+        expires_at: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<GuestInviteEntity, error::SystemError> {
+        let invite = sqlx::query_as::<_, GuestInviteEntity>(
+            r#"
+            INSERT INTO guest_invites (conversation_id, token_prefix, token_hash, created_by, max_uses, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(token_prefix)
+        .bind(token_hash)
+        .bind(created_by)
+        .bind(max_uses)
+        .bind(expires_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(invite)
+    }
+
+    async fn find_invites(
+        &self,
+        conversation_id: &Uuid,
+    ) -> Result<Vec<GuestInviteEntity>, error::SystemError> {
+        let invites = sqlx::query_as::<_, GuestInviteEntity>(
+            "SELECT * FROM guest_invites WHERE conversation_id = $1 ORDER BY created_at DESC",
+        )
+        .bind(conversation_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(invites)
+    }
+
+    async fn revoke_invite(
+        &self,
+        conversation_id: &Uuid,
+        id: &Uuid,
+    ) -> Result<Option<Vec<Uuid>>, error::SystemError> {
+        let guest_ids = sqlx::query_scalar::<_, Vec<Uuid>>(
+            r#"
+            WITH r AS (
+                UPDATE guest_invites SET revoked_at = NOW()
+                WHERE id = $1 AND conversation_id = $2 AND revoked_at IS NULL
+                RETURNING id
+            )
+            SELECT COALESCE(array_agg(g.user_id) FILTER (WHERE g.user_id IS NOT NULL), '{}')
+            FROM r
+            LEFT JOIN guests g ON g.invite_id = r.id
+            GROUP BY r.id
+            "#,
+        )
+        .bind(id)
+        .bind(conversation_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(guest_ids)
+    }
+
+    async fn redeem_invite(
+        &self,
+        token_hash: &str,
+        guest: &InsertGuest,
+    ) -> Result<Option<Uuid>, error::SystemError> {
+        // Tăng `use_count` bằng UPDATE có điều kiện để các lần redeem đồng thời không vượt
+        // quá `max_uses`
+        let conversation_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            WITH i AS (
+                UPDATE guest_invites gi SET use_count = gi.use_count + 1
+                WHERE gi.token_hash = $1
+                AND gi.revoked_at IS NULL
+                AND gi.expires_at > NOW()
+                AND gi.use_count < gi.max_uses
+                AND EXISTS (
+                    SELECT 1 FROM participants p
+                    JOIN users u ON u.id = p.user_id
+                    WHERE p.conversation_id = gi.conversation_id
+                    AND p.user_id = gi.created_by
+                    AND p.status = 'active'
+                    AND u.deleted_at IS NULL
+                    AND u.banned_at IS NULL
+                )
+                RETURNING gi.id, gi.conversation_id
+            ), u AS (
+                INSERT INTO users (id, username, email, hash_password, display_name, role, email_verified, discoverable)
+                SELECT $2, $3, $4, $5, 'Guest', 'GUEST', true, false FROM i
+                RETURNING id
+            ), g AS (
+                INSERT INTO guests (user_id, conversation_id, invite_id)
+                SELECT u.id, i.conversation_id, i.id FROM u, i
+            )
+            INSERT INTO participants (conversation_id, user_id, unread_count, joined_at)
+            SELECT i.conversation_id, u.id, 0, NOW() FROM u, i
+            RETURNING conversation_id
+            "#,
+        )
+        .bind(token_hash)
+        .bind(guest.id)
+        .bind(&guest.username)
+        .bind(&guest.email)
+        .bind(&guest.hash_password)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(conversation_id)
+    }
+}
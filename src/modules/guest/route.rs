@@ -0,0 +1,39 @@
+use crate::modules::guest::handle::*;
+use actix_web::web::{scope, ServiceConfig};
+use utoipa::OpenApi;
+
+/// Quản lý lời mời guest, dành cho thành viên conversation. Phải được configure trước
+/// scope `/conversations` của module conversation (scope không fall through)
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        scope("/conversations/{conversation_id}/guest-invites")
+            .service(create_guest_invite)
+            .service(list_guest_invites)
+            .service(revoke_guest_invite),
+    );
+}
+
+/// Endpoint public đổi token lời mời lấy access token của guest
+pub fn public_api_configure(cfg: &mut ServiceConfig) {
+    cfg.service(scope("/guests").service(guest_sign_in));
+}
+
+/// API cho guest principal, mount dưới scope `/guest`
+pub fn guest_configure(cfg: &mut ServiceConfig) {
+    cfg.service(send_message).service(get_messages);
+}
+
+/// OpenAPI paths của `configure` (dưới `/conversations`)
+#[derive(OpenApi)]
+#[openapi(paths(create_guest_invite, list_guest_invites, revoke_guest_invite))]
+pub struct GuestInviteApiDoc;
+
+/// OpenAPI paths của `public_api_configure` (scope `/guests`)
+#[derive(OpenApi)]
+#[openapi(paths(guest_sign_in))]
+pub struct GuestSessionApiDoc;
+
+/// OpenAPI paths của `guest_configure` (scope `/guest`)
+#[derive(OpenApi)]
+#[openapi(paths(send_message, get_messages))]
+pub struct GuestPrincipalApiDoc;
@@ -0,0 +1,19 @@
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct GuestInviteEntity {
+    pub id: Uuid,
+    pub conversation_id: Uuid,
+    /// Phần đầu của token để nhận diện, token đầy đủ chỉ được trả về lúc tạo
+    pub token_prefix: String,
+    pub created_by: Uuid,
+    pub max_uses: i32,
+    /// Số khách đã vào bằng lời mời
+    pub use_count: i32,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
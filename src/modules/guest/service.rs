@@ -0,0 +1,279 @@
+/// Guest Service
+///
+/// Guest là một user với role `GUEST` chỉ được vào đúng một conversation (vd. chat hỗ trợ).
+/// Thành viên của conversation tạo lời mời (`gst_...`), khách đổi token lời mời lấy access
+/// token qua `POST /guests/session`. Access token của guest mang `conversation_id`:
+/// - HTTP: guest chỉ dùng được các routes dưới scope `/guest`, mọi scope khác bị
+///   `authorization` chặn theo role
+/// - WebSocket: session của guest chỉ gửi / nhận events của conversation đó
+///
+/// Guest không có refresh token, hết hạn sau `GUEST_TOKEN_TTL` thì cần lời mời mới.
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    middlewares::TokenRevocation,
+    modules::{
+        guest::{
+            model::{CreateGuestInviteModel, CreatedGuestInvite, GuestSession, InsertGuest},
+            repository::GuestRepository,
+            schema::GuestInviteEntity,
+        },
+        user::schema::UserRole,
+    },
+    utils::{hash_token, random_token, Claims, TypeClaims, UNUSABLE_PASSWORD_HASH},
+};
+
+/// Tiền tố của mọi token lời mời guest
+const INVITE_TOKEN_PREFIX: &str = "gst_";
+
+/// Số ký tự ngẫu nhiên sau tiền tố
+const INVITE_TOKEN_RANDOM_LEN: usize = 40;
+
+/// Số ký tự đầu của token được lưu để hiển thị
+const TOKEN_PREFIX_LEN: usize = 12;
+
+/// Thời hạn mặc định của lời mời (giờ)
+const DEFAULT_INVITE_EXPIRY_HOURS: i64 = 24 * 7;
+
+/// Thời hạn access token của guest (giây)
+const GUEST_TOKEN_TTL: u64 = 24 * 60 * 60;
+
+#[derive(Clone)]
+pub struct GuestService {
+    repo: Arc<dyn GuestRepository + Send + Sync>,
+    revocation: Arc<dyn TokenRevocation + Send + Sync>,
+}
+
+impl GuestService {
+    pub fn with_dependencies(
+        repo: Arc<dyn GuestRepository + Send + Sync>,
+        revocation: Arc<dyn TokenRevocation + Send + Sync>,
+    ) -> Self {
+        GuestService { repo, revocation }
+    }
+
+    pub async fn create_invite(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        body: CreateGuestInviteModel,
+    ) -> Result<CreatedGuestInvite, error::SystemError> {
+        self.require_member(conversation_id, user_id).await?;
+
+        let hours = body.expires_in_hours.unwrap_or(DEFAULT_INVITE_EXPIRY_HOURS);
+        let expires_at = chrono::Utc::now() + chrono::Duration::hours(hours);
+
+        let token = format!("{INVITE_TOKEN_PREFIX}{}", random_token(INVITE_TOKEN_RANDOM_LEN));
+        let invite = self
+            .repo
+            .create_invite(
+                &conversation_id,
+                &hash_token(&token),
+                &token[..TOKEN_PREFIX_LEN],
+                &user_id,
+                body.max_uses.unwrap_or(1),
+                &expires_at,
+            )
+            .await?;
+
+        Ok(CreatedGuestInvite { token, invite })
+    }
+
+    pub async fn list_invites(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Vec<GuestInviteEntity>, error::SystemError> {
+        self.require_member(conversation_id, user_id).await?;
+        self.repo.find_invites(&conversation_id).await
+    }
+
+    /// Revoke lời mời, access token của các guest đã vào bằng lời mời cũng bị revoke
+    pub async fn revoke_invite(
+        &self,
+        conversation_id: Uuid,
+        invite_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        self.require_member(conversation_id, user_id).await?;
+
+        let guest_ids = self
+            .repo
+            .revoke_invite(&conversation_id, &invite_id)
+            .await?
+            .ok_or_else(|| error::SystemError::not_found("Guest invite not found"))?;
+
+        for guest_id in guest_ids {
+            self.revocation.revoke_all_tokens(guest_id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Đổi token lời mời lấy access token của một guest user mới
+    pub async fn sign_in(&self, token: &str) -> Result<GuestSession, error::SystemError> {
+        let invalid = || error::SystemError::unauthorized("Invalid or expired guest invite");
+
+        if !token.starts_with(INVITE_TOKEN_PREFIX) {
+            return Err(invalid());
+        }
+
+        // Guest không đăng nhập bằng mật khẩu, email chỉ để thỏa ràng buộc của users
+        let id = Uuid::now_v7();
+        let guest = InsertGuest {
+            id,
+            username: format!("guest_{}", id.simple()),
+            email: format!("{id}@guests.invalid"),
+            hash_password: UNUSABLE_PASSWORD_HASH.to_string(),
+        };
+
+        let conversation_id =
+            self.repo.redeem_invite(&hash_token(token), &guest).await?.ok_or_else(invalid)?;
+
+        let access_token = Claims::new(&id, &UserRole::Guest, GUEST_TOKEN_TTL)
+            .with_jti(Uuid::now_v7())
+            .with_type(TypeClaims::AccessToken)
+            .with_conversation(conversation_id)
+            .encode()?;
+
+        Ok(GuestSession { access_token, user_id: id, conversation_id })
+    }
+
+    async fn require_member(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        if !self.repo.is_member(&conversation_id, &user_id).await? {
+            return Err(error::SystemError::forbidden("You are not a member of this conversation")
+                .with_code(error::ErrorCode::NotAMember));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Mutex, PoisonError};
+
+    use super::*;
+    use crate::{constants::init_test_env, modules::guest::repository_mock::GuestRepositoryMock};
+
+    /// Ghi lại các user bị revoke token
+    #[derive(Default)]
+    struct RecordingRevocation {
+        revoked: Mutex<Vec<Uuid>>,
+    }
+
+    #[async_trait::async_trait]
+    impl TokenRevocation for RecordingRevocation {
+        async fn is_token_revoked(&self, _claims: &Claims) -> Result<bool, error::SystemError> {
+            Ok(false)
+        }
+
+        async fn revoke_all_tokens(&self, user_id: Uuid) -> Result<(), error::SystemError> {
+            self.revoked.lock().unwrap_or_else(PoisonError::into_inner).push(user_id);
+            Ok(())
+        }
+    }
+
+    fn service(repo: &GuestRepositoryMock, revocation: Arc<RecordingRevocation>) -> GuestService {
+        init_test_env();
+        GuestService::with_dependencies(Arc::new(repo.clone()), revocation)
+    }
+
+    fn invite_model(max_uses: Option<i32>) -> CreateGuestInviteModel {
+        CreateGuestInviteModel { expires_in_hours: None, max_uses }
+    }
+
+    #[actix_web::test]
+    async fn guest_signs_in_with_invite_scoped_to_conversation() {
+        let repo = GuestRepositoryMock::default();
+        let service = service(&repo, Arc::default());
+        let (conversation_id, member_id) = (Uuid::now_v7(), Uuid::now_v7());
+        repo.add_member(conversation_id, member_id);
+
+        let created =
+            service.create_invite(conversation_id, member_id, invite_model(None)).await.unwrap();
+        assert!(created.token.starts_with(INVITE_TOKEN_PREFIX));
+        assert_eq!(created.invite.max_uses, 1);
+
+        let session = service.sign_in(&created.token).await.unwrap();
+        assert_eq!(session.conversation_id, conversation_id);
+
+        let claims = Claims::decode(&session.access_token).unwrap();
+        assert_eq!(claims.sub, session.user_id);
+        assert_eq!(claims.role, UserRole::Guest);
+        assert_eq!(claims.conversation_id, Some(conversation_id));
+        assert_eq!(repo.guests_of(conversation_id), vec![session.user_id]);
+    }
+
+    #[actix_web::test]
+    async fn non_member_cannot_manage_invites() {
+        let repo = GuestRepositoryMock::default();
+        let service = service(&repo, Arc::default());
+        let (conversation_id, member_id) = (Uuid::now_v7(), Uuid::now_v7());
+        repo.add_member(conversation_id, member_id);
+        let created =
+            service.create_invite(conversation_id, member_id, invite_model(None)).await.unwrap();
+        let stranger = Uuid::now_v7();
+
+        // `CreatedGuestInvite` không derive Debug để token không lọt vào log
+        let Err(err) = service.create_invite(conversation_id, stranger, invite_model(None)).await
+        else {
+            panic!("non-member created a guest invite");
+        };
+        assert_eq!(err.code(), error::ErrorCode::NotAMember);
+        let err = service.list_invites(conversation_id, stranger).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotAMember);
+        let err =
+            service.revoke_invite(conversation_id, created.invite.id, stranger).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotAMember);
+    }
+
+    #[actix_web::test]
+    async fn invite_stops_working_after_max_uses_or_when_inviter_leaves() {
+        let repo = GuestRepositoryMock::default();
+        let service = service(&repo, Arc::default());
+        let (conversation_id, member_id) = (Uuid::now_v7(), Uuid::now_v7());
+        repo.add_member(conversation_id, member_id);
+
+        let created =
+            service.create_invite(conversation_id, member_id, invite_model(Some(2))).await.unwrap();
+        service.sign_in(&created.token).await.unwrap();
+        service.sign_in(&created.token).await.unwrap();
+        let err = service.sign_in(&created.token).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::Unauthorized);
+
+        let created =
+            service.create_invite(conversation_id, member_id, invite_model(None)).await.unwrap();
+        repo.remove_member(conversation_id, member_id);
+        let err = service.sign_in(&created.token).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::Unauthorized);
+    }
+
+    #[actix_web::test]
+    async fn revoking_invite_revokes_guest_tokens() {
+        let repo = GuestRepositoryMock::default();
+        let revocation = Arc::new(RecordingRevocation::default());
+        let service = service(&repo, revocation.clone());
+        let (conversation_id, member_id) = (Uuid::now_v7(), Uuid::now_v7());
+        repo.add_member(conversation_id, member_id);
+        let created =
+            service.create_invite(conversation_id, member_id, invite_model(None)).await.unwrap();
+        let session = service.sign_in(&created.token).await.unwrap();
+
+        service.revoke_invite(conversation_id, created.invite.id, member_id).await.unwrap();
+
+        assert_eq!(*revocation.revoked.lock().unwrap(), vec![session.user_id]);
+        let err = service.sign_in(&created.token).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::Unauthorized);
+        let err =
+            service.revoke_invite(conversation_id, created.invite.id, member_id).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotFound);
+    }
+}
@@ -0,0 +1,89 @@
+use actix_web::{get, post, put, web, HttpRequest};
+use uuid::Uuid;
+
+use crate::{
+    api::{error, success},
+    middlewares::get_extensions,
+    modules::keys::{
+        model::{KeyBundle, PreKeyCount, UploadKeyBundleModel, UploadPreKeysModel},
+        schema::IdentityKeyEntity,
+        service::KeyService,
+    },
+    utils::{Claims, ValidatedJson},
+};
+
+#[utoipa::path(
+    tag = "keys",
+    request_body = UploadKeyBundleModel,
+    responses((status = 200, body = success::SuccessData<IdentityKeyEntity>))
+)]
+#[put("")]
+pub async fn upload_key_bundle(
+    key_service: web::Data<KeyService>,
+    ValidatedJson(body): ValidatedJson<UploadKeyBundleModel>,
+    req: HttpRequest,
+) -> Result<success::Success<IdentityKeyEntity>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let identity = key_service.upload_bundle(user_id, body).await?;
+
+    Ok(success::Success::ok(Some(identity)).message("Keys uploaded successfully"))
+}
+
+#[utoipa::path(
+    tag = "keys",
+    path = "/prekeys",
+    request_body = UploadPreKeysModel,
+    responses(
+        (status = 200, body = success::SuccessData<PreKeyCount>),
+        (status = 400, description = "Chưa publish identity key", body = error::ErrorBody)
+    )
+)]
+#[post("/prekeys")]
+pub async fn upload_prekeys(
+    key_service: web::Data<KeyService>,
+    ValidatedJson(body): ValidatedJson<UploadPreKeysModel>,
+    req: HttpRequest,
+) -> Result<success::Success<PreKeyCount>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let count = key_service.upload_prekeys(user_id, body.prekeys).await?;
+
+    Ok(success::Success::ok(Some(count)).message("Prekeys uploaded successfully"))
+}
+
+#[utoipa::path(
+    tag = "keys",
+    path = "/prekeys/count",
+    responses((status = 200, body = success::SuccessData<PreKeyCount>))
+)]
+#[get("/prekeys/count")]
+pub async fn get_prekey_count(
+    key_service: web::Data<KeyService>,
+    req: HttpRequest,
+) -> Result<success::Success<PreKeyCount>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let count = key_service.count_prekeys(user_id).await?;
+
+    Ok(success::Success::ok(Some(count)).message("Prekey count retrieved successfully"))
+}
+
+#[utoipa::path(
+    tag = "keys",
+    path = "/{user_id}",
+    params(("user_id" = Uuid, Path, description = "User ID")),
+    responses(
+        (status = 200, body = success::SuccessData<KeyBundle>),
+        (status = 403, description = "Chưa là bạn bè", body = error::ErrorBody),
+        (status = 404, description = "User chưa publish keys", body = error::ErrorBody)
+    )
+)]
+#[get("/{user_id:[0-9a-fA-F-]{36}}")]
+pub async fn get_key_bundle(
+    key_service: web::Data<KeyService>,
+    user_id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> Result<success::Success<KeyBundle>, error::Error> {
+    let requester_id = get_extensions::<Claims>(&req)?.sub;
+    let bundle = key_service.get_bundle(requester_id, *user_id).await?;
+
+    Ok(success::Success::ok(Some(bundle)).message("Key bundle retrieved successfully"))
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Key ID theo Signal protocol (24 bit)
+const MAX_KEY_ID: i32 = 0xFF_FFFF;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct PreKey {
+    #[validate(range(min = 0, max = MAX_KEY_ID, message = "Key ID is out of range"))]
+    pub key_id: i32,
+    /// Public key, base64
+    #[validate(length(min = 1, max = 128, message = "Public key must be 1 to 128 characters"))]
+    pub public_key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct SignedPreKey {
+    #[validate(range(min = 0, max = MAX_KEY_ID, message = "Key ID is out of range"))]
+    pub key_id: i32,
+    /// Public key, base64
+    #[validate(length(min = 1, max = 128, message = "Public key must be 1 to 128 characters"))]
+    pub public_key: String,
+    /// Chữ ký của `public_key` bằng identity key, base64
+    #[validate(length(min = 1, max = 256, message = "Signature must be 1 to 256 characters"))]
+    pub signature: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UploadKeyBundleModel {
+    /// Identity public key, base64. Đổi identity key sẽ xóa các one-time prekeys cũ
+    #[validate(length(min = 1, max = 128, message = "Identity key must be 1 to 128 characters"))]
+    pub identity_key: String,
+    #[validate(nested)]
+    pub signed_prekey: SignedPreKey,
+    #[serde(default)]
+    #[validate(length(max = 100, message = "At most 100 prekeys per upload"), nested)]
+    pub one_time_prekeys: Vec<PreKey>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UploadPreKeysModel {
+    #[validate(length(min = 1, max = 100, message = "Must upload 1 to 100 prekeys"), nested)]
+    pub prekeys: Vec<PreKey>,
+}
+
+/// Key bundle để thiết lập session (X3DH) với user
+#[derive(Debug, Serialize, ToSchema)]
+pub struct KeyBundle {
+    pub user_id: Uuid,
+    pub identity_key: String,
+    pub signed_prekey: SignedPreKey,
+    /// One-time prekey đã được cấp riêng cho lần fetch này, `None` nếu user đã dùng hết
+    pub one_time_prekey: Option<PreKey>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PreKeyCount {
+    /// Số one-time prekeys còn lại trên server
+    pub count: i64,
+}
@@ -0,0 +1,39 @@
+use uuid::Uuid;
+
+use crate::api::error;
+use crate::modules::keys::model::{PreKey, SignedPreKey};
+use crate::modules::keys::schema::{IdentityKeyEntity, OneTimePreKeyEntity};
+
+#[async_trait::async_trait]
+pub trait KeyRepository {
+    async fn find_identity(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Option<IdentityKeyEntity>, error::SystemError>;
+
+    /// Lưu identity key + signed prekey. Nếu identity key thay đổi, xóa các one-time
+    /// prekeys của identity cũ trong cùng transaction
+    async fn upsert_identity(
+        &self,
+        user_id: &Uuid,
+        identity_key: &str,
+        signed_prekey: &SignedPreKey,
+    ) -> Result<IdentityKeyEntity, error::SystemError>;
+
+    /// Thêm one-time prekeys, bỏ qua key ID đã tồn tại
+    async fn add_prekeys(
+        &self,
+        user_id: &Uuid,
+        prekeys: &[PreKey],
+    ) -> Result<(), error::SystemError>;
+
+    async fn count_prekeys(&self, user_id: &Uuid) -> Result<i64, error::SystemError>;
+
+    /// Lấy và xóa một one-time prekey (mỗi prekey chỉ được cấp một lần)
+    async fn claim_prekey(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Option<OneTimePreKeyEntity>, error::SystemError>;
+
+    async fn are_friends(&self, user_a: &Uuid, user_b: &Uuid) -> Result<bool, error::SystemError>;
+}
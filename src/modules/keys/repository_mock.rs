@@ -0,0 +1,123 @@
+/// In-memory `KeyRepository` cho unit tests của services
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::keys::{
+        model::{PreKey, SignedPreKey},
+        repository::KeyRepository,
+        schema::{IdentityKeyEntity, OneTimePreKeyEntity},
+    },
+};
+
+#[derive(Default)]
+struct State {
+    identities: HashMap<Uuid, IdentityKeyEntity>,
+    /// user_id -> prekeys theo thứ tự upload
+    prekeys: HashMap<Uuid, Vec<PreKey>>,
+    /// Cặp bạn bè, lưu theo thứ tự (nhỏ, lớn)
+    friends: HashSet<(Uuid, Uuid)>,
+}
+
+/// Clone dùng chung dữ liệu, test giữ một bản để thêm bạn bè
+#[derive(Clone, Default)]
+pub struct KeyRepositoryMock {
+    state: Arc<Mutex<State>>,
+}
+
+fn friend_pair(a: Uuid, b: Uuid) -> (Uuid, Uuid) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+impl KeyRepositoryMock {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn add_friends(&self, user_a: Uuid, user_b: Uuid) {
+        self.state().friends.insert(friend_pair(user_a, user_b));
+    }
+}
+
+#[async_trait::async_trait]
+impl KeyRepository for KeyRepositoryMock {
+    async fn find_identity(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Option<IdentityKeyEntity>, error::SystemError> {
+        Ok(self.state().identities.get(user_id).cloned())
+    }
+
+    async fn upsert_identity(
+        &self,
+        user_id: &Uuid,
+        identity_key: &str,
+        signed_prekey: &SignedPreKey,
+    ) -> Result<IdentityKeyEntity, error::SystemError> {
+        let mut state = self.state();
+        let rotated = state
+            .identities
+            .get(user_id)
+            .is_some_and(|identity| identity.identity_key != identity_key);
+        if rotated {
+            state.prekeys.remove(user_id);
+        }
+
+        let identity = IdentityKeyEntity {
+            user_id: *user_id,
+            identity_key: identity_key.to_string(),
+            signed_prekey_id: signed_prekey.key_id,
+            signed_prekey: signed_prekey.public_key.clone(),
+            signed_prekey_signature: signed_prekey.signature.clone(),
+            updated_at: Utc::now(),
+        };
+        state.identities.insert(*user_id, identity.clone());
+        Ok(identity)
+    }
+
+    async fn add_prekeys(
+        &self,
+        user_id: &Uuid,
+        prekeys: &[PreKey],
+    ) -> Result<(), error::SystemError> {
+        let mut state = self.state();
+        let stored = state.prekeys.entry(*user_id).or_default();
+        for prekey in prekeys {
+            if !stored.iter().any(|k| k.key_id == prekey.key_id) {
+                stored.push(prekey.clone());
+            }
+        }
+        Ok(())
+    }
+
+    async fn count_prekeys(&self, user_id: &Uuid) -> Result<i64, error::SystemError> {
+        Ok(self.state().prekeys.get(user_id).map_or(0, Vec::len) as i64)
+    }
+
+    async fn claim_prekey(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Option<OneTimePreKeyEntity>, error::SystemError> {
+        let mut state = self.state();
+        let Some(stored) = state.prekeys.get_mut(user_id).filter(|stored| !stored.is_empty())
+        else {
+            return Ok(None);
+        };
+        let prekey = stored.remove(0);
+        Ok(Some(OneTimePreKeyEntity { key_id: prekey.key_id, public_key: prekey.public_key }))
+    }
+
+    async fn are_friends(&self, user_a: &Uuid, user_b: &Uuid) -> Result<bool, error::SystemError> {
+        Ok(self.state().friends.contains(&friend_pair(*user_a, *user_b)))
+    }
+}
@@ -0,0 +1,168 @@
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::keys::{
+        model::{PreKey, SignedPreKey},
+        repository::KeyRepository,
+        schema::{IdentityKeyEntity, OneTimePreKeyEntity},
+    },
+};
+
+#[derive(Clone)]
+pub struct KeyRepositoryPg {
+    pool: sqlx::PgPool,
+}
+
+impl KeyRepositoryPg {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl KeyRepository for KeyRepositoryPg {
+    async fn find_identity(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Option<IdentityKeyEntity>, error::SystemError> {
+        let identity = sqlx::query_as::<_, IdentityKeyEntity>(
+            "SELECT * FROM identity_keys WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(identity)
+    }
+
+    async fn upsert_identity(
+        &self,
+        user_id: &Uuid,
+        identity_key: &str,
+        signed_prekey: &SignedPreKey,
+    ) -> Result<IdentityKeyEntity, error::SystemError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM one_time_prekeys
+            WHERE user_id = $1
+            AND EXISTS (
+                SELECT 1 FROM identity_keys
+                WHERE user_id = $1 AND identity_key <> $2
+            )
+            "#,
+        )
+        .bind(user_id)
+        .bind(identity_key)
+        .execute(tx.as_mut())
+        .await?;
+
+        let identity = sqlx::query_as::<_, IdentityKeyEntity>(
+            r#"
+            INSERT INTO identity_keys (
+                user_id, identity_key, signed_prekey_id, signed_prekey, signed_prekey_signature
+            )
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (user_id) DO UPDATE SET
+                identity_key = EXCLUDED.identity_key,
+                signed_prekey_id = EXCLUDED.signed_prekey_id,
+                signed_prekey = EXCLUDED.signed_prekey,
+                signed_prekey_signature = EXCLUDED.signed_prekey_signature,
+                updated_at = NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(identity_key)
+        .bind(signed_prekey.key_id)
+        .bind(&signed_prekey.public_key)
+        .bind(&signed_prekey.signature)
+        .fetch_one(tx.as_mut())
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(identity)
+    }
+
+    async fn add_prekeys(
+        &self,
+        user_id: &Uuid,
+        prekeys: &[PreKey],
+    ) -> Result<(), error::SystemError> {
+        let key_ids: Vec<i32> = prekeys.iter().map(|k| k.key_id).collect();
+        let public_keys: Vec<String> = prekeys.iter().map(|k| k.public_key.clone()).collect();
+
+        sqlx::query(
+            r#"
+            INSERT INTO one_time_prekeys (user_id, key_id, public_key)
+            SELECT $1, k.key_id, k.public_key
+            FROM UNNEST($2::int[], $3::text[]) AS k(key_id, public_key)
+            ON CONFLICT (user_id, key_id) DO NOTHING
+            "#,
+        )
+        .bind(user_id)
+        .bind(&key_ids)
+        .bind(&public_keys)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn count_prekeys(&self, user_id: &Uuid) -> Result<i64, error::SystemError> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM one_time_prekeys WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn claim_prekey(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Option<OneTimePreKeyEntity>, error::SystemError> {
+        let prekey = sqlx::query_as::<_, OneTimePreKeyEntity>(
+            r#"
+            DELETE FROM one_time_prekeys
+            WHERE (user_id, key_id) = (
+                SELECT user_id, key_id FROM one_time_prekeys
+                WHERE user_id = $1
+                ORDER BY created_at, key_id
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING key_id, public_key
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(prekey)
+    }
+
+    async fn are_friends(&self, user_a: &Uuid, user_b: &Uuid) -> Result<bool, error::SystemError> {
+        let are_friends = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM friends
+                WHERE user_a = LEAST($1::uuid, $2::uuid)
+                AND user_b = GREATEST($1::uuid, $2::uuid)
+                AND deleted_at IS NULL
+            )
+            "#,
+        )
+        .bind(user_a)
+        .bind(user_b)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(are_friends)
+    }
+}
@@ -0,0 +1,18 @@
+use crate::modules::keys::handle::*;
+use actix_web::web::{scope, ServiceConfig};
+use utoipa::OpenApi;
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        scope("/keys")
+            .service(upload_key_bundle)
+            .service(upload_prekeys)
+            .service(get_prekey_count)
+            .service(get_key_bundle),
+    );
+}
+
+/// OpenAPI paths của `configure` (scope `/keys`)
+#[derive(OpenApi)]
+#[openapi(paths(upload_key_bundle, upload_prekeys, get_prekey_count, get_key_bundle))]
+pub struct KeyApiDoc;
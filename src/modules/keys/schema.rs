@@ -0,0 +1,22 @@
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Identity key và signed prekey hiện tại của user (public keys, base64)
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct IdentityKeyEntity {
+    pub user_id: Uuid,
+    pub identity_key: String,
+    pub signed_prekey_id: i32,
+    pub signed_prekey: String,
+    /// Chữ ký của signed prekey bằng identity key
+    pub signed_prekey_signature: String,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct OneTimePreKeyEntity {
+    pub key_id: i32,
+    pub public_key: String,
+}
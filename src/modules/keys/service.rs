@@ -0,0 +1,209 @@
+/// Key Service
+///
+/// Phân phối public keys cho mã hóa đầu cuối kiểu Signal (X3DH) trong direct chats.
+/// Mỗi user publish identity key, một signed prekey và một lô one-time prekeys; người
+/// gửi fetch key bundle của người nhận để thiết lập session rồi gửi ciphertext qua
+/// `POST /messages/direct/encrypted`. Server chỉ lưu public keys, mỗi one-time prekey
+/// được cấp cho đúng một lần fetch. Client nên bổ sung prekeys khi `count` xuống thấp.
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::keys::{
+        model::{KeyBundle, PreKey, PreKeyCount, SignedPreKey, UploadKeyBundleModel},
+        repository::KeyRepository,
+        schema::IdentityKeyEntity,
+    },
+};
+
+/// Số one-time prekeys tối đa lưu cho mỗi user
+const MAX_STORED_PREKEYS: i64 = 200;
+
+#[derive(Clone)]
+pub struct KeyService {
+    repo: Arc<dyn KeyRepository + Send + Sync>,
+}
+
+impl KeyService {
+    pub fn with_dependencies(repo: Arc<dyn KeyRepository + Send + Sync>) -> Self {
+        KeyService { repo }
+    }
+
+    /// Publish (hoặc xoay vòng) identity key, signed prekey và thêm one-time prekeys
+    pub async fn upload_bundle(
+        &self,
+        user_id: Uuid,
+        body: UploadKeyBundleModel,
+    ) -> Result<IdentityKeyEntity, error::SystemError> {
+        let identity =
+            self.repo.upsert_identity(&user_id, &body.identity_key, &body.signed_prekey).await?;
+
+        if !body.one_time_prekeys.is_empty() {
+            self.add_prekeys(user_id, &body.one_time_prekeys).await?;
+        }
+
+        Ok(identity)
+    }
+
+    /// Bổ sung one-time prekeys, cần publish identity key trước
+    pub async fn upload_prekeys(
+        &self,
+        user_id: Uuid,
+        prekeys: Vec<PreKey>,
+    ) -> Result<PreKeyCount, error::SystemError> {
+        if self.repo.find_identity(&user_id).await?.is_none() {
+            return Err(error::SystemError::bad_request("Upload an identity key first"));
+        }
+
+        self.add_prekeys(user_id, &prekeys).await?;
+
+        self.count_prekeys(user_id).await
+    }
+
+    pub async fn count_prekeys(&self, user_id: Uuid) -> Result<PreKeyCount, error::SystemError> {
+        let count = self.repo.count_prekeys(&user_id).await?;
+        Ok(PreKeyCount { count })
+    }
+
+    /// Key bundle của `user_id`, chỉ bạn bè (hoặc chính user) được fetch vì mỗi lần
+    /// fetch tiêu thụ một one-time prekey
+    pub async fn get_bundle(
+        &self,
+        requester_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<KeyBundle, error::SystemError> {
+        if requester_id != user_id && !self.repo.are_friends(&requester_id, &user_id).await? {
+            return Err(error::SystemError::forbidden("You are not friends with this user")
+                .with_code(error::ErrorCode::NotFriends));
+        }
+
+        let identity = self.repo.find_identity(&user_id).await?.ok_or_else(|| {
+            error::SystemError::not_found("User has not published encryption keys")
+        })?;
+
+        let one_time_prekey = self
+            .repo
+            .claim_prekey(&user_id)
+            .await?
+            .map(|k| PreKey { key_id: k.key_id, public_key: k.public_key });
+
+        Ok(KeyBundle {
+            user_id,
+            identity_key: identity.identity_key,
+            signed_prekey: SignedPreKey {
+                key_id: identity.signed_prekey_id,
+                public_key: identity.signed_prekey,
+                signature: identity.signed_prekey_signature,
+            },
+            one_time_prekey,
+        })
+    }
+
+    async fn add_prekeys(
+        &self,
+        user_id: Uuid,
+        prekeys: &[PreKey],
+    ) -> Result<(), error::SystemError> {
+        let stored = self.repo.count_prekeys(&user_id).await?;
+        if stored + prekeys.len() as i64 > MAX_STORED_PREKEYS {
+            return Err(error::SystemError::bad_request(format!(
+                "At most {MAX_STORED_PREKEYS} one-time prekeys can be stored"
+            )));
+        }
+
+        self.repo.add_prekeys(&user_id, prekeys).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::keys::repository_mock::KeyRepositoryMock;
+
+    fn prekeys(ids: std::ops::Range<i32>) -> Vec<PreKey> {
+        ids.map(|key_id| PreKey { key_id, public_key: format!("prekey-{key_id}") }).collect()
+    }
+
+    fn bundle(identity_key: &str, prekey_ids: std::ops::Range<i32>) -> UploadKeyBundleModel {
+        UploadKeyBundleModel {
+            identity_key: identity_key.to_string(),
+            signed_prekey: SignedPreKey {
+                key_id: 1,
+                public_key: format!("{identity_key}-signed"),
+                signature: format!("{identity_key}-signature"),
+            },
+            one_time_prekeys: prekeys(prekey_ids),
+        }
+    }
+
+    #[actix_web::test]
+    async fn friend_fetches_bundle_and_each_prekey_is_handed_out_once() {
+        let repo = KeyRepositoryMock::default();
+        let service = KeyService::with_dependencies(Arc::new(repo.clone()));
+        let (alice, bob) = (Uuid::now_v7(), Uuid::now_v7());
+        repo.add_friends(alice, bob);
+        service.upload_bundle(bob, bundle("bob-identity", 0..2)).await.unwrap();
+
+        let first = service.get_bundle(alice, bob).await.unwrap();
+        let second = service.get_bundle(alice, bob).await.unwrap();
+        let third = service.get_bundle(alice, bob).await.unwrap();
+
+        assert_eq!(first.identity_key, "bob-identity");
+        assert_eq!(first.signed_prekey.signature, "bob-identity-signature");
+        assert_eq!(first.one_time_prekey.map(|k| k.key_id), Some(0));
+        assert_eq!(second.one_time_prekey.map(|k| k.key_id), Some(1));
+        assert!(third.one_time_prekey.is_none());
+        assert_eq!(service.count_prekeys(bob).await.unwrap().count, 0);
+    }
+
+    #[actix_web::test]
+    async fn non_friend_cannot_fetch_bundle() {
+        let repo = KeyRepositoryMock::default();
+        let service = KeyService::with_dependencies(Arc::new(repo.clone()));
+        let (stranger, bob) = (Uuid::now_v7(), Uuid::now_v7());
+        service.upload_bundle(bob, bundle("bob-identity", 0..1)).await.unwrap();
+
+        let err = service.get_bundle(stranger, bob).await.unwrap_err();
+
+        assert_eq!(err.code(), error::ErrorCode::NotFriends);
+        // Lần fetch bị từ chối không tiêu thụ prekey
+        assert_eq!(service.count_prekeys(bob).await.unwrap().count, 1);
+    }
+
+    #[actix_web::test]
+    async fn rotating_identity_key_drops_old_prekeys() {
+        let repo = KeyRepositoryMock::default();
+        let service = KeyService::with_dependencies(Arc::new(repo.clone()));
+        let bob = Uuid::now_v7();
+        service.upload_bundle(bob, bundle("old-identity", 0..5)).await.unwrap();
+
+        // Upload lại cùng identity key chỉ cập nhật signed prekey, giữ prekeys cũ
+        service.upload_bundle(bob, bundle("old-identity", 5..6)).await.unwrap();
+        assert_eq!(service.count_prekeys(bob).await.unwrap().count, 6);
+
+        service.upload_bundle(bob, bundle("new-identity", 0..2)).await.unwrap();
+        assert_eq!(service.count_prekeys(bob).await.unwrap().count, 2);
+
+        let own = service.get_bundle(bob, bob).await.unwrap();
+        assert_eq!(own.identity_key, "new-identity");
+        assert_eq!(own.one_time_prekey.map(|k| k.public_key), Some("prekey-0".to_string()));
+    }
+
+    #[actix_web::test]
+    async fn prekeys_require_identity_and_are_capped() {
+        let repo = KeyRepositoryMock::default();
+        let service = KeyService::with_dependencies(Arc::new(repo.clone()));
+        let bob = Uuid::now_v7();
+
+        let err = service.upload_prekeys(bob, prekeys(0..1)).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::BadRequest);
+
+        service.upload_bundle(bob, bundle("bob-identity", 0..100)).await.unwrap();
+        let count = service.upload_prekeys(bob, prekeys(100..200)).await.unwrap();
+        assert_eq!(count.count, MAX_STORED_PREKEYS);
+        let err = service.upload_prekeys(bob, prekeys(200..201)).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::BadRequest);
+    }
+}
@@ -0,0 +1,48 @@
+/// Message Archive Worker
+///
+/// Task chạy nền định kỳ chuyển messages cũ hơn `MESSAGE_ARCHIVE_AFTER_MONTHS` từ bảng
+/// `messages` sang `messages_archive` (partition theo tháng, tạo trước mỗi lần chạy) để
+/// bảng messages và indexes của nó không phình theo thời gian. Lịch sử và export đọc cả
+/// hai bảng qua view `messages_all` nên cursor pagination không đổi.
+use std::time::Duration;
+
+use crate::modules::message::service::MessageService;
+
+/// Khoảng thời gian giữa hai lần archive
+const ARCHIVE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Số messages tối đa chuyển mỗi lần, chuyển tiếp ngay nếu còn messages cần archive
+const BATCH_SIZE: i64 = 1000;
+
+pub async fn run_message_archive_worker(service: MessageService) {
+    let mut interval = actix_web::rt::time::interval(ARCHIVE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = service.create_archive_partitions().await {
+            tracing::error!("Failed to create message archive partitions: {:?}", e);
+            continue;
+        }
+
+        let mut archived = 0;
+        loop {
+            match service.archive_old_messages(BATCH_SIZE).await {
+                Ok(moved) => {
+                    archived += moved;
+                    if moved < BATCH_SIZE as u64 {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Failed to archive old messages: {:?}", e);
+                    break;
+                }
+            }
+        }
+
+        if archived > 0 {
+            tracing::info!("Archived {} messages", archived);
+        }
+    }
+}
@@ -0,0 +1,221 @@
+/// Slash Commands
+///
+/// Tin nhắn bắt đầu bằng `/<tên>` được `MessageService::dispatch_command` chặn trước khi
+/// lưu và chuyển tới `CommandHandler` đã đăng ký trong `CommandRegistry`. Handler trả về:
+/// - `Public`: nội dung thay thế, được gửi như tin nhắn bình thường (vd. `/shrug`)
+/// - `Ephemeral`: phản hồi chỉ người gửi thấy, không lưu vào DB
+///
+/// `/help` được registry xử lý sẵn (liệt kê các lệnh đã đăng ký). Gửi `//...` để đăng
+/// nguyên văn một tin nhắn bắt đầu bằng `/`. Thêm lệnh mới: implement `CommandHandler`
+/// rồi `register` vào registry trong `CommandRegistry::with_builtin`.
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::api::error;
+
+/// Độ dài tối đa của tên lệnh
+const MAX_COMMAND_LEN: usize = 32;
+
+/// Ngữ cảnh thực thi một lệnh
+pub struct CommandContext<'a> {
+    pub sender_id: Uuid,
+    /// `None` khi gửi direct message tới conversation chưa tồn tại
+    pub conversation_id: Option<Uuid>,
+    /// Phần sau tên lệnh, đã trim
+    pub args: &'a str,
+}
+
+/// Kết quả của một lệnh
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandResponse {
+    /// Gửi nội dung này như tin nhắn bình thường
+    Public(String),
+    /// Phản hồi chỉ người gửi thấy
+    Ephemeral(String),
+}
+
+#[async_trait::async_trait]
+pub trait CommandHandler: Send + Sync {
+    /// Tên lệnh (không có `/`), lowercase
+    fn name(&self) -> &'static str;
+
+    /// Mô tả ngắn hiển thị trong `/help`
+    fn description(&self) -> &'static str;
+
+    /// Cú pháp tham số hiển thị trong `/help`
+    fn usage(&self) -> &'static str {
+        ""
+    }
+
+    async fn execute(
+        &self,
+        ctx: &CommandContext<'_>,
+    ) -> Result<CommandResponse, error::SystemError>;
+}
+
+/// Phản hồi ephemeral trả về cho người gửi thay cho message đã lưu
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CommandReply {
+    pub command: String,
+    pub conversation_id: Option<Uuid>,
+    pub text: String,
+}
+
+/// Kết quả dispatch nội dung do user gửi
+#[derive(Debug, Clone)]
+pub enum CommandOutcome {
+    /// Không phải lệnh (hoặc lệnh trả về `Public`): tiếp tục gửi nội dung này
+    Send(String),
+    /// Lệnh đã được xử lý, không tạo message
+    Reply(CommandReply),
+}
+
+#[derive(Default)]
+pub struct CommandRegistry {
+    handlers: BTreeMap<&'static str, Arc<dyn CommandHandler>>,
+}
+
+impl CommandRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry với các lệnh có sẵn
+    pub fn with_builtin() -> Self {
+        Self::new().register(MeCommand).register(ShrugCommand)
+    }
+
+    /// Đăng ký lệnh, ghi đè lệnh cùng tên đã có
+    pub fn register<H>(mut self, handler: H) -> Self
+    where
+        H: CommandHandler + 'static,
+    {
+        self.handlers.insert(handler.name(), Arc::new(handler));
+        self
+    }
+
+    pub async fn dispatch(
+        &self,
+        sender_id: Uuid,
+        conversation_id: Option<Uuid>,
+        content: String,
+    ) -> Result<CommandOutcome, error::SystemError> {
+        let trimmed = content.trim_start();
+
+        if let Some(escaped) = trimmed.strip_prefix("//") {
+            return Ok(CommandOutcome::Send(format!("/{escaped}")));
+        }
+
+        let Some((name, args)) = parse(trimmed) else {
+            return Ok(CommandOutcome::Send(content));
+        };
+
+        let reply = |text: String| {
+            CommandOutcome::Reply(CommandReply { command: name.clone(), conversation_id, text })
+        };
+
+        if name == "help" && !self.handlers.contains_key("help") {
+            return Ok(reply(self.help()));
+        }
+
+        let Some(handler) = self.handlers.get(name.as_str()) else {
+            return Ok(reply(format!(
+                "Unknown command /{name}. Type /help to see available commands"
+            )));
+        };
+
+        let ctx = CommandContext { sender_id, conversation_id, args };
+        match handler.execute(&ctx).await? {
+            CommandResponse::Public(content) => Ok(CommandOutcome::Send(content)),
+            CommandResponse::Ephemeral(text) => Ok(reply(text)),
+        }
+    }
+
+    fn help(&self) -> String {
+        let mut lines = vec!["Available commands:".to_string()];
+        for handler in self.handlers.values() {
+            let usage = match handler.usage() {
+                "" => String::new(),
+                usage => format!(" {usage}"),
+            };
+            lines.push(format!("/{}{} - {}", handler.name(), usage, handler.description()));
+        }
+        lines.push("/help - Show this list".to_string());
+        lines.join("\n")
+    }
+}
+
+/// Tách `/<tên> <args>`. Tên chỉ gồm chữ / số / `_` / `-` nên các nội dung như
+/// `/usr/bin` hay `/ ` không bị coi là lệnh
+fn parse(content: &str) -> Option<(String, &str)> {
+    let rest = content.strip_prefix('/')?;
+    let (name, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+
+    let valid = !name.is_empty()
+        && name.len() <= MAX_COMMAND_LEN
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'));
+
+    valid.then(|| (name.to_ascii_lowercase(), args.trim()))
+}
+
+/// `/me <hành động>`: tin nhắn dạng hành động (in nghiêng)
+struct MeCommand;
+
+#[async_trait::async_trait]
+impl CommandHandler for MeCommand {
+    fn name(&self) -> &'static str {
+        "me"
+    }
+
+    fn description(&self) -> &'static str {
+        "Send an action message"
+    }
+
+    fn usage(&self) -> &'static str {
+        "<action>"
+    }
+
+    async fn execute(
+        &self,
+        ctx: &CommandContext<'_>,
+    ) -> Result<CommandResponse, error::SystemError> {
+        if ctx.args.is_empty() {
+            return Ok(CommandResponse::Ephemeral("Usage: /me <action>".to_string()));
+        }
+
+        Ok(CommandResponse::Public(format!("_{}_", ctx.args)))
+    }
+}
+
+/// `/shrug [tin nhắn]`: thêm ¯\_(ツ)_/¯ vào cuối tin nhắn
+struct ShrugCommand;
+
+#[async_trait::async_trait]
+impl CommandHandler for ShrugCommand {
+    fn name(&self) -> &'static str {
+        "shrug"
+    }
+
+    fn description(&self) -> &'static str {
+        "Append ¯\\_(ツ)_/¯ to your message"
+    }
+
+    fn usage(&self) -> &'static str {
+        "[message]"
+    }
+
+    async fn execute(
+        &self,
+        ctx: &CommandContext<'_>,
+    ) -> Result<CommandResponse, error::SystemError> {
+        let shrug = r"¯\_(ツ)_/¯";
+        let content =
+            if ctx.args.is_empty() { shrug.to_string() } else { format!("{} {shrug}", ctx.args) };
+
+        Ok(CommandResponse::Public(content))
+    }
+}
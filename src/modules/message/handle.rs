@@ -1,80 +1,191 @@
-use actix_web::{delete, patch, post, web, HttpRequest};
+use actix_web::{delete, get, patch, post, web, HttpRequest};
 use uuid::Uuid;
 
 use crate::{
     api::{error, success},
     middlewares::get_extensions,
-    modules::{
-        conversation::{
-            repository_pg::{
-                ConversationPgRepository, LastMessagePgRepository, ParticipantPgRepository,
-            },
-            schema::ConversationEntity,
-        },
-        message::{
-            model::{EditMessageRequest, SendDirectMessage, SendGroupMessage},
-            repository_pg::MessageRepositoryPg,
-            schema::MessageEntity,
-            service::MessageService,
+    modules::message::{
+        command::CommandOutcome,
+        model::{
+            BroadcastMessageRequest, BroadcastResult, ClientMetadataQuery, DeleteMessageQuery,
+            EditMessageRequest, ForwardMessageRequest, ScheduleMessageRequest,
+            ScheduledMessageQuery, SendDirectMessage, SendEncryptedMessage, SendGroupMessage,
+            SendMessageResponse, TranslateMessageQuery, TranslatedMessage,
         },
+        schema::{ClientMetadataEntity, MessageEntity, ScheduledMessageEntity},
+        service::MessageService,
     },
-    utils::{Claims, ValidatedJson},
+    utils::{Claims, ValidatedJson, ValidatedQuery},
 };
 
-type MessageSvc = MessageService<
-    MessageRepositoryPg,
-    ConversationPgRepository,
-    ParticipantPgRepository,
-    LastMessagePgRepository,
->;
-
+#[utoipa::path(
+    tag = "messages",
+    path = "/direct/",
+    request_body = SendDirectMessage,
+    responses(
+        (
+            status = 200,
+            description = "Message đã gửi, hoặc phản hồi ephemeral nếu nội dung là slash command",
+            body = success::SuccessData<SendMessageResponse>
+        ),
+        (
+            status = 403,
+            description = "Chưa là bạn bè (`NOT_FRIENDS`) hoặc đã chặn nhau (`BLOCKED`)",
+            body = error::ErrorBody
+        )
+    )
+)]
 #[post("/")]
 pub async fn send_direct_message(
-    message_service: web::Data<MessageSvc>,
-    body: web::Json<SendDirectMessage>,
+    message_service: web::Data<MessageService>,
+    ValidatedJson(body): ValidatedJson<SendDirectMessage>,
     req: HttpRequest,
-) -> Result<success::Success<MessageEntity>, error::Error> {
+) -> Result<success::Success<SendMessageResponse>, error::Error> {
     let user_id = get_extensions::<Claims>(&req)?.sub;
+    let recipient_id =
+        body.recipient_id.ok_or(error::Error::bad_request("Recipient ID is required"))?;
+
+    let outcome =
+        message_service.dispatch_command(user_id, body.conversation_id, body.content).await?;
+    let content = match outcome {
+        CommandOutcome::Send(content) => content,
+        CommandOutcome::Reply(reply) => {
+            return Ok(success::Success::ok(Some(SendMessageResponse::Command(reply)))
+                .message("Command executed successfully"));
+        }
+    };
+
     let message = message_service
         .send_direct_message(
             user_id,
-            body.recipient_id.ok_or(error::Error::bad_request("Recipient ID is required"))?,
-            body.content.clone(),
+            recipient_id,
+            content,
             body.conversation_id,
+            body.client,
+            body.client_message_id,
         )
         .await?;
 
-    Ok(success::Success::ok(Some(message)).message("Send direct message successfully"))
+    Ok(success::Success::ok(Some(SendMessageResponse::Message(Box::new(message))))
+        .message("Send direct message successfully"))
 }
 
+#[utoipa::path(
+    tag = "messages",
+    path = "/direct/encrypted",
+    request_body = SendEncryptedMessage,
+    responses(
+        (status = 200, body = success::SuccessData<MessageEntity>),
+        (status = 400, description = "Conversation không phải direct", body = error::ErrorBody),
+        (
+            status = 403,
+            description = "Chưa là bạn bè (`NOT_FRIENDS`) hoặc đã chặn nhau (`BLOCKED`)",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[post("/encrypted")]
+pub async fn send_encrypted_message(
+    message_service: web::Data<MessageService>,
+    ValidatedJson(body): ValidatedJson<SendEncryptedMessage>,
+    req: HttpRequest,
+) -> Result<success::Success<MessageEntity>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let recipient_id =
+        body.recipient_id.ok_or(error::Error::bad_request("Recipient ID is required"))?;
+
+    let message = message_service
+        .send_encrypted_message(
+            user_id,
+            recipient_id,
+            body.envelope,
+            body.conversation_id,
+            body.client_message_id,
+        )
+        .await?;
+
+    Ok(success::Success::ok(Some(message)).message("Send encrypted message successfully"))
+}
+
+#[utoipa::path(
+    tag = "messages",
+    path = "/group/",
+    request_body(content = SendGroupMessage, description = "Kèm `conversation_id` của group"),
+    responses(
+        (
+            status = 200,
+            description = "Message đã gửi, hoặc phản hồi ephemeral nếu nội dung là slash command",
+            body = success::SuccessData<SendMessageResponse>
+        ),
+        (
+            status = 403,
+            description = "Không phải thành viên của conversation",
+            body = error::ErrorBody
+        )
+    )
+)]
 #[post("/")]
 pub async fn send_group_message(
-    message_service: web::Data<MessageSvc>,
-    body: web::Json<SendGroupMessage>,
+    message_service: web::Data<MessageService>,
+    ValidatedJson(body): ValidatedJson<SendGroupMessage>,
     req: HttpRequest,
-) -> Result<success::Success<MessageEntity>, error::Error> {
+) -> Result<success::Success<SendMessageResponse>, error::Error> {
     let user_id = get_extensions::<Claims>(&req)?.sub;
-    let conversation = get_extensions::<ConversationEntity>(&req)?;
-    let message =
-        message_service.send_group_message(user_id, body.content.clone(), conversation.id).await?;
 
-    Ok(success::Success::ok(Some(message)).message("Send group message successfully"))
+    let outcome =
+        message_service.dispatch_command(user_id, Some(body.conversation_id), body.content).await?;
+    let content = match outcome {
+        CommandOutcome::Send(content) => content,
+        CommandOutcome::Reply(reply) => {
+            return Ok(success::Success::ok(Some(SendMessageResponse::Command(reply)))
+                .message("Command executed successfully"));
+        }
+    };
+
+    let message = message_service
+        .send_message(user_id, body.conversation_id, content, body.client, body.client_message_id)
+        .await?;
+
+    Ok(success::Success::ok(Some(SendMessageResponse::Message(Box::new(message))))
+        .message("Send group message successfully"))
 }
 
+#[utoipa::path(
+    tag = "messages",
+    params(DeleteMessageQuery),
+    responses(
+        (status = 204, description = "Message đã bị xóa"),
+        (
+            status = 400,
+            description = "Quá thời hạn xóa với mọi người",
+            body = error::ErrorBody
+        ),
+        (status = 403, description = "Không phải người gửi", body = error::ErrorBody)
+    )
+)]
 #[delete("/{message_id}")]
 pub async fn delete_message(
-    message_service: web::Data<MessageSvc>,
+    message_service: web::Data<MessageService>,
     message_id: web::Path<Uuid>,
+    query: web::Query<DeleteMessageQuery>,
     req: HttpRequest,
 ) -> Result<success::Success<()>, error::Error> {
     let user_id = get_extensions::<Claims>(&req)?.sub;
-    message_service.delete_message(*message_id, user_id).await?;
+    message_service.delete_message(*message_id, user_id, query.mode).await?;
     Ok(success::Success::no_content())
 }
 
+#[utoipa::path(
+    tag = "messages",
+    request_body = EditMessageRequest,
+    responses(
+        (status = 200, body = success::SuccessData<MessageEntity>),
+        (status = 403, description = "Không phải người gửi", body = error::ErrorBody)
+    )
+)]
 #[patch("/{message_id}")]
 pub async fn edit_message(
-    message_service: web::Data<MessageSvc>,
+    message_service: web::Data<MessageService>,
     message_id: web::Path<Uuid>,
     ValidatedJson(body): ValidatedJson<EditMessageRequest>,
     req: HttpRequest,
@@ -84,3 +195,200 @@ pub async fn edit_message(
     let message = message_service.edit_message(*message_id, user_id, body.content).await?;
     Ok(success::Success::ok(Some(message)).message("Message edited successfully"))
 }
+
+#[utoipa::path(
+    tag = "messages",
+    request_body = BroadcastMessageRequest,
+    responses((
+        status = 200,
+        description = "Kết quả theo từng người nhận, người nhận lỗi có `error` thay cho `message`",
+        body = success::SuccessData<Vec<BroadcastResult>>
+    ))
+)]
+#[post("/broadcast")]
+pub async fn broadcast_message(
+    message_service: web::Data<MessageService>,
+    ValidatedJson(body): ValidatedJson<BroadcastMessageRequest>,
+    req: HttpRequest,
+) -> Result<success::Success<Vec<BroadcastResult>>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+
+    let results =
+        message_service.broadcast_message(user_id, body.recipient_ids, body.content).await;
+
+    Ok(success::Success::ok(Some(results)).message("Broadcast message processed"))
+}
+
+#[utoipa::path(
+    tag = "messages",
+    request_body = ForwardMessageRequest,
+    responses(
+        (
+            status = 200,
+            description = "Các bản forward đã tạo",
+            body = success::SuccessData<Vec<MessageEntity>>
+        ),
+        (
+            status = 403,
+            description = "Không phải thành viên của conversation nguồn hoặc đích",
+            body = error::ErrorBody
+        ),
+        (
+            status = 404,
+            description = "Message hoặc conversation không tồn tại",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[post("/{message_id}/forward")]
+pub async fn forward_message(
+    message_service: web::Data<MessageService>,
+    message_id: web::Path<Uuid>,
+    ValidatedJson(body): ValidatedJson<ForwardMessageRequest>,
+    req: HttpRequest,
+) -> Result<success::Success<Vec<MessageEntity>>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+
+    let messages =
+        message_service.forward_message(user_id, *message_id, body.conversation_ids).await?;
+    Ok(success::Success::ok(Some(messages)).message("Message forwarded successfully"))
+}
+
+/// Dịch nội dung message sang ngôn ngữ `lang`, không sửa message gốc
+#[utoipa::path(
+    tag = "messages",
+    params(TranslateMessageQuery),
+    responses(
+        (status = 200, body = success::SuccessData<TranslatedMessage>),
+        (
+            status = 400,
+            description = "Message mã hóa hoặc không có nội dung text",
+            body = error::ErrorBody
+        ),
+        (
+            status = 403,
+            description = "Không phải thành viên của conversation",
+            body = error::ErrorBody
+        ),
+        (
+            status = 404,
+            description = "Message không tồn tại hoặc chưa cấu hình translation provider",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[post("/{message_id}/translate")]
+pub async fn translate_message(
+    message_service: web::Data<MessageService>,
+    message_id: web::Path<Uuid>,
+    ValidatedQuery(query): ValidatedQuery<TranslateMessageQuery>,
+    req: HttpRequest,
+) -> Result<success::Success<TranslatedMessage>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+
+    let translation = message_service.translate_message(user_id, *message_id, &query.lang).await?;
+    Ok(success::Success::ok(Some(translation)))
+}
+
+#[utoipa::path(
+    tag = "messages",
+    path = "/scheduled",
+    request_body = ScheduleMessageRequest,
+    responses(
+        (status = 200, body = success::SuccessData<ScheduledMessageEntity>),
+        (status = 400, description = "Thời điểm gửi không ở tương lai", body = error::ErrorBody),
+        (
+            status = 403,
+            description = "Không phải thành viên của conversation",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[post("/scheduled")]
+pub async fn schedule_message(
+    message_service: web::Data<MessageService>,
+    ValidatedJson(body): ValidatedJson<ScheduleMessageRequest>,
+    req: HttpRequest,
+) -> Result<success::Success<ScheduledMessageEntity>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+
+    let scheduled = message_service
+        .schedule_message(user_id, body.conversation_id, body.content, body.scheduled_at)
+        .await?;
+    Ok(success::Success::ok(Some(scheduled)).message("Message scheduled successfully"))
+}
+
+#[utoipa::path(
+    tag = "messages",
+    path = "/scheduled",
+    responses(
+        (status = 200, body = success::SuccessData<Vec<ScheduledMessageEntity>>),
+        (
+            status = 403,
+            description = "Không phải thành viên của conversation",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[get("/scheduled")]
+pub async fn get_scheduled_messages(
+    message_service: web::Data<MessageService>,
+    query: web::Query<ScheduledMessageQuery>,
+    req: HttpRequest,
+) -> Result<success::Success<Vec<ScheduledMessageEntity>>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+
+    let scheduled = message_service.get_scheduled_messages(user_id, query.conversation_id).await?;
+    Ok(success::Success::ok(Some(scheduled)).message("Scheduled messages retrieved successfully"))
+}
+
+#[utoipa::path(
+    tag = "messages",
+    path = "/scheduled/{scheduled_id}",
+    responses(
+        (status = 204, description = "Tin nhắn hẹn giờ đã bị hủy"),
+        (
+            status = 404,
+            description = "Không tồn tại, không phải của caller hoặc đã được gửi",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[delete("/scheduled/{scheduled_id}")]
+pub async fn cancel_scheduled_message(
+    message_service: web::Data<MessageService>,
+    scheduled_id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    message_service.cancel_scheduled_message(user_id, *scheduled_id).await?;
+    Ok(success::Success::no_content())
+}
+
+#[utoipa::path(
+    tag = "admin",
+    responses((status = 200, body = success::SuccessData<Vec<ClientMetadataEntity>>))
+)]
+#[get("/messages/{message_id}")]
+pub async fn get_message_client_metadata(
+    message_service: web::Data<MessageService>,
+    message_id: web::Path<Uuid>,
+) -> Result<success::Success<Vec<ClientMetadataEntity>>, error::Error> {
+    let metadata = message_service.get_client_metadata_by_message(*message_id).await?;
+    Ok(success::Success::ok(Some(metadata)).message("Client metadata retrieved successfully"))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    responses((status = 200, body = success::SuccessData<Vec<ClientMetadataEntity>>))
+)]
+#[get("/users/{user_id}")]
+pub async fn get_user_client_metadata(
+    message_service: web::Data<MessageService>,
+    user_id: web::Path<Uuid>,
+    query: web::Query<ClientMetadataQuery>,
+) -> Result<success::Success<Vec<ClientMetadataEntity>>, error::Error> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let metadata = message_service.get_client_metadata_by_user(*user_id, limit).await?;
+    Ok(success::Success::ok(Some(metadata)).message("Client metadata retrieved successfully"))
+}
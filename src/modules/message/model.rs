@@ -1,6 +1,11 @@
+use crate::api::error::ErrorBody;
+use crate::api::pagination::PageInfo;
+use crate::modules::message::command::CommandReply;
 use crate::modules::message::schema::MessageEntity;
-use crate::modules::message::schema::MessageType;
+use crate::modules::message::schema::{CiphertextKind, MessageType};
+use crate::modules::message::translation::Translation;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
@@ -9,34 +14,290 @@ pub struct InsertMessage {
     pub conversation_id: Uuid,
     pub sender_id: Uuid,
     pub content: Option<String>,
+    pub forwarded_from: Option<Uuid>,
+    pub client_message_id: Option<Uuid>,
+    pub sender_override: Option<SenderOverride>,
+}
+
+/// Danh tính hiển thị của tin nhắn do incoming webhook đăng
+#[derive(Debug, Clone)]
+pub struct SenderOverride {
+    pub incoming_webhook_id: Uuid,
+    pub name: Option<String>,
+    pub avatar_url: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct MessageQuery {
     pub conversation_id: Uuid,
     pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Chỉ lấy message từ thời điểm này (history visibility / disappearing TTL)
+    #[serde(skip)]
+    pub visible_since: Option<chrono::DateTime<chrono::Utc>>,
+    /// `Before`: messages cũ hơn `created_at`, `After`: messages mới hơn `created_at`
+    #[serde(skip)]
+    pub direction: CursorDirection,
+    /// User đang xem, bỏ qua các messages user đã xóa phía mình
+    #[serde(skip)]
+    pub viewer_id: Option<Uuid>,
+}
+
+/// Hướng phân trang theo cursor
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CursorDirection {
+    /// Messages cũ hơn cursor (mặc định, cuộn lên)
+    #[default]
+    Before,
+    /// Messages mới hơn cursor (cuộn xuống sau khi jump tới giữa lịch sử)
+    After,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct GetMessageResponse {
     pub messages: Vec<MessageEntity>,
+    /// Giống `page.next_cursor`, giữ lại cho client cũ
     pub cursor: Option<String>,
+    pub page: PageInfo,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// Messages quanh một message đích, theo thứ tự thời gian (bao gồm message đích)
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MessagesAroundResponse {
+    pub messages: Vec<MessageEntity>,
+    /// Cursor lấy tiếp messages cũ hơn (`direction=before`), `None` nếu đã hết
+    pub before_cursor: Option<String>,
+    /// Cursor lấy tiếp messages mới hơn (`direction=after`), `None` nếu đã hết
+    pub after_cursor: Option<String>,
+}
+
+/// Thông tin client gửi kèm (optional) để correlate bug report với phiên bản app
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ClientMetadata {
+    #[validate(length(max = 32, message = "App version must be at most 32 characters"))]
+    pub app_version: Option<String>,
+    #[validate(length(max = 32, message = "Platform must be at most 32 characters"))]
+    pub platform: Option<String>,
+}
+
+impl ClientMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.app_version.is_none() && self.platform.is_none()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InsertClientMetadata {
+    pub user_id: Uuid,
+    pub message_id: Option<Uuid>,
+    pub session_id: Option<Uuid>,
+    pub app_version: Option<String>,
+    pub platform: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ClientMetadataQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate, ToSchema)]
 pub struct SendDirectMessage {
     pub conversation_id: Option<Uuid>,
     pub recipient_id: Option<Uuid>,
     pub content: String,
+    #[validate(nested)]
+    pub client: Option<ClientMetadata>,
+    /// Idempotency key: gửi lại cùng key (retry sau timeout) trả về message đã tạo
+    pub client_message_id: Option<Uuid>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// Envelope của tin nhắn mã hóa đầu cuối, server chỉ lưu và chuyển tiếp nguyên vẹn
+#[derive(Debug, Clone, Deserialize, Validate, ToSchema)]
+pub struct EncryptedEnvelope {
+    pub kind: CiphertextKind,
+    /// Ciphertext đã mã hóa cho thiết bị của người nhận, base64
+    #[validate(length(min = 1, max = 65536, message = "Ciphertext is too long"))]
+    pub ciphertext: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate, ToSchema)]
+pub struct SendEncryptedMessage {
+    pub conversation_id: Option<Uuid>,
+    pub recipient_id: Option<Uuid>,
+    #[validate(nested)]
+    pub envelope: EncryptedEnvelope,
+    /// Idempotency key: gửi lại cùng key (retry sau timeout) trả về message đã tạo
+    pub client_message_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate, ToSchema)]
 pub struct SendGroupMessage {
+    /// Đã được middleware `require_group_member` kiểm tra membership
+    pub conversation_id: Uuid,
     pub content: String,
+    #[validate(nested)]
+    pub client: Option<ClientMetadata>,
+    /// Idempotency key: gửi lại cùng key (retry sau timeout) trả về message đã tạo
+    pub client_message_id: Option<Uuid>,
 }
 
-#[derive(Debug, Clone, Deserialize, Validate)]
+/// Phạm vi xóa tin nhắn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DeleteMode {
+    /// Xóa với mọi người (chỉ người gửi, trong thời hạn), hiển thị tombstone
+    #[default]
+    Everyone,
+    /// Chỉ ẩn phía mình, các thành viên khác vẫn thấy
+    Me,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteMessageQuery {
+    #[serde(default)]
+    #[param(inline)]
+    pub mode: DeleteMode,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate, ToSchema)]
 pub struct EditMessageRequest {
     #[validate(length(min = 1, max = 5000, message = "Content must be between 1 and 5000 characters"))]
     pub content: String,
 }
+
+#[derive(Debug, Clone, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TranslateMessageQuery {
+    /// Ngôn ngữ đích (vd. `en`, `vi`, `pt-BR`)
+    #[validate(length(min = 2, max = 10, message = "Language must be 2 to 10 characters"))]
+    pub lang: String,
+}
+
+/// Bản dịch của một message, message gốc giữ nguyên
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TranslatedMessage {
+    pub message_id: Uuid,
+    pub lang: String,
+    pub text: String,
+    /// Ngôn ngữ gốc do provider nhận diện (nếu có)
+    pub source_lang: Option<String>,
+}
+
+/// Bản dịch trong cache, kèm `updated_at` của message lúc dịch để bỏ bản dịch cũ khi
+/// message bị sửa
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedTranslation {
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub translation: Translation,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate, ToSchema)]
+pub struct ForwardMessageRequest {
+    /// Conversations nhận bản forward, caller phải là thành viên của từng conversation
+    #[validate(length(min = 1, max = 20, message = "Must forward to 1 to 20 conversations"))]
+    pub conversation_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate, ToSchema)]
+pub struct BroadcastMessageRequest {
+    /// Người nhận, mỗi người nhận tin nhắn trong direct conversation riêng với caller
+    #[validate(length(min = 1, max = 20, message = "Must send to 1 to 20 recipients"))]
+    pub recipient_ids: Vec<Uuid>,
+    #[validate(length(min = 1, max = 5000, message = "Content must be 1 to 5000 characters"))]
+    pub content: String,
+}
+
+/// Kết quả gửi tới một người nhận của broadcast
+#[derive(Serialize, ToSchema)]
+pub struct BroadcastResult {
+    pub recipient_id: Uuid,
+    /// Message đã gửi, `None` nếu gửi thất bại
+    pub message: Option<MessageEntity>,
+    /// Lỗi khi gửi tới người nhận này (vd. `NOT_FRIENDS`, `BLOCKED`)
+    pub error: Option<ErrorBody>,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate, ToSchema)]
+pub struct ScheduleMessageRequest {
+    pub conversation_id: Uuid,
+    #[validate(length(min = 1, max = 5000, message = "Content must be 1 to 5000 characters"))]
+    pub content: String,
+    /// Thời điểm gửi, phải ở tương lai
+    pub scheduled_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ScheduledMessageQuery {
+    pub conversation_id: Uuid,
+}
+
+#[derive(Debug, Clone)]
+pub struct InsertScheduledMessage {
+    pub conversation_id: Uuid,
+    pub sender_id: Uuid,
+    pub content: String,
+    pub scheduled_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Trạng thái theo dõi nội dung lặp lại của một sender trong conversation (lưu trong Redis)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateTracker {
+    pub hash: [u8; 32],
+    pub count: u32,
+    pub message_id: Uuid,
+}
+
+/// Metadata của file đính kèm message (từ bảng files)
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AttachmentMetadata {
+    pub original_filename: String,
+    pub mime_type: String,
+    pub file_size: i64,
+}
+
+/// Row export: message kèm metadata file đính kèm (nếu có)
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ExportMessageRow {
+    #[sqlx(flatten)]
+    pub message: MessageEntity,
+    pub attachment_original_filename: Option<String>,
+    pub attachment_mime_type: Option<String>,
+    pub attachment_file_size: Option<i64>,
+}
+
+/// Message trong bản export conversation
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ExportedMessage {
+    #[serde(flatten)]
+    pub message: MessageEntity,
+    pub attachment: Option<AttachmentMetadata>,
+}
+
+impl From<ExportMessageRow> for ExportedMessage {
+    fn from(row: ExportMessageRow) -> Self {
+        let attachment = match (
+            row.attachment_original_filename,
+            row.attachment_mime_type,
+            row.attachment_file_size,
+        ) {
+            (Some(original_filename), Some(mime_type), Some(file_size)) => {
+                Some(AttachmentMetadata { original_filename, mime_type, file_size })
+            }
+            _ => None,
+        };
+
+        ExportedMessage { message: row.message, attachment }
+    }
+}
+
+/// Kết quả gửi tin nhắn: message đã lưu, hoặc phản hồi ephemeral của slash command
+/// (không có `id`, không được lưu)
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum SendMessageResponse {
+    Message(Box<MessageEntity>),
+    Command(CommandReply),
+}
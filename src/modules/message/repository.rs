@@ -1,62 +1,202 @@
-use crate::modules::message::model::{InsertMessage, MessageQuery};
-use crate::{api::error, modules::message::schema::MessageEntity};
+use crate::modules::message::model::{
+    EncryptedEnvelope, ExportMessageRow, InsertClientMetadata, InsertMessage,
+    InsertScheduledMessage, MessageQuery,
+};
+use crate::{
+    api::error,
+    configs::uow::Transaction,
+    modules::message::schema::{ClientMetadataEntity, MessageEntity, ScheduledMessageEntity},
+};
 
 #[async_trait::async_trait]
 pub trait MessageRepository {
-    fn get_pool(&self) -> &sqlx::PgPool;
-
-    async fn find_by_id<'e, E>(
+    async fn find_by_id(
         &self,
         message_id: &uuid::Uuid,
-        tx: E,
-    ) -> Result<Option<MessageEntity>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        tx: &mut Transaction,
+    ) -> Result<Option<MessageEntity>, error::SystemError>;
 
-    async fn create<'e, E>(
+    async fn create(
         &self,
         message: &InsertMessage,
-        tx: E,
-    ) -> Result<MessageEntity, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        tx: &mut Transaction,
+    ) -> Result<MessageEntity, error::SystemError>;
+
+    /// Tạo tin nhắn mã hóa (type `encrypted`), `content` luôn NULL
+    async fn create_encrypted(
+        &self,
+        conversation_id: &uuid::Uuid,
+        sender_id: &uuid::Uuid,
+        envelope: &EncryptedEnvelope,
+        client_message_id: Option<uuid::Uuid>,
+        tx: &mut Transaction,
+    ) -> Result<MessageEntity, error::SystemError>;
+
+    /// Tạo system message (type `system`), ví dụ thông báo đổi tên group
+    async fn create_system(
+        &self,
+        conversation_id: &uuid::Uuid,
+        sender_id: &uuid::Uuid,
+        content: &str,
+        tx: &mut Transaction,
+    ) -> Result<MessageEntity, error::SystemError>;
 
-    async fn find_by_query<'e, E>(
+    /// Tìm message sender đã gửi với idempotency key `client_message_id`
+    async fn find_by_client_message_id(
+        &self,
+        sender_id: &uuid::Uuid,
+        client_message_id: &uuid::Uuid,
+    ) -> Result<Option<MessageEntity>, error::SystemError>;
+
+    /// Lịch sử messages theo cursor, gồm cả messages đã chuyển sang archive
+    async fn find_by_query(
         &self,
         query: &MessageQuery,
         limit: i32,
-        tx: E,
-    ) -> Result<Vec<MessageEntity>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+    ) -> Result<Vec<MessageEntity>, error::SystemError>;
+
+    /// Lấy tối đa `limit + 1` messages mới hơn `after` theo thứ tự thời gian (thêm 1 để
+    /// biết còn messages hay không), kèm metadata file đính kèm
+    async fn find_for_export(
+        &self,
+        conversation_id: &uuid::Uuid,
+        viewer_id: &uuid::Uuid,
+        after: Option<chrono::DateTime<chrono::Utc>>,
+        visible_since: Option<chrono::DateTime<chrono::Utc>>,
+        limit: i32,
+    ) -> Result<Vec<ExportMessageRow>, error::SystemError>;
+
+    /// Xóa vĩnh viễn tối đa `limit` messages tạo trước `cutoff` trong mỗi bảng messages và
+    /// archive, trả về số messages đã xóa
+    async fn delete_older_than(
+        &self,
+        cutoff: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+    ) -> Result<u64, error::SystemError>;
+
+    /// Tạo partitions tháng của archive cho messages tạo trước `cutoff`
+    async fn create_archive_partitions(
+        &self,
+        cutoff: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), error::SystemError>;
+
+    /// Chuyển tối đa `limit` messages cũ nhất tạo trước `cutoff` sang archive, trả về số
+    /// messages đã chuyển
+    async fn archive_older_than(
+        &self,
+        cutoff: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+    ) -> Result<u64, error::SystemError>;
+
+    /// Lấy tối đa `limit + 1` messages mỗi phía của message đích (thêm 1 để biết còn
+    /// messages hay không) cùng message đích, theo thứ tự thời gian. Trả về rỗng nếu
+    /// message đích không thuộc conversation hoặc không nhìn thấy được. Messages đã bị
+    /// xóa với mọi người vẫn được trả về (tombstone), messages `viewer_id` đã ẩn thì không
+    async fn find_around(
+        &self,
+        conversation_id: &uuid::Uuid,
+        viewer_id: &uuid::Uuid,
+        message_id: &uuid::Uuid,
+        limit: i32,
+        visible_since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<Vec<MessageEntity>, error::SystemError>;
 
     /// Delete a message by ID (soft delete)
-    async fn delete_message<'e, E>(
+    async fn delete_message(
+        &self,
+        message_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        tx: &mut Transaction,
+    ) -> Result<bool, error::SystemError>;
+
+    /// Ẩn message phía `user_id` (xóa cho mình, kể cả tombstone). Trả về conversation
+    /// của message, `None` nếu message không tồn tại hoặc user không phải thành viên
+    async fn hide_for_user(
         &self,
         message_id: &uuid::Uuid,
         user_id: &uuid::Uuid,
-        tx: E,
-    ) -> Result<bool, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        tx: &mut Transaction,
+    ) -> Result<Option<uuid::Uuid>, error::SystemError>;
 
     /// Edit a message by ID (only content can be edited)
-    async fn edit_message<'e, E>(
+    async fn edit_message(
         &self,
         message_id: &uuid::Uuid,
         user_id: &uuid::Uuid,
         new_content: &str,
-        tx: E,
-    ) -> Result<Option<MessageEntity>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        tx: &mut Transaction,
+    ) -> Result<Option<MessageEntity>, error::SystemError>;
 
     /// Get the last message of a conversation
-    async fn get_last_message_by_conversation<'e, E>(
+    async fn get_last_message_by_conversation(
+        &self,
+        conversation_id: &uuid::Uuid,
+        tx: &mut Transaction,
+    ) -> Result<Option<MessageEntity>, error::SystemError>;
+
+    /// Tăng repeat counter khi tin nhắn trùng lặp được gộp vào message trước đó
+    async fn increment_repeat_count(
+        &self,
+        message_id: &uuid::Uuid,
+    ) -> Result<Option<MessageEntity>, error::SystemError>;
+
+    /// Lưu client metadata vào bảng phụ (không nằm trong messages)
+    async fn insert_client_metadata(
+        &self,
+        metadata: &InsertClientMetadata,
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError>;
+
+    /// Lưu mentions của message, chỉ giữ các username là participant active của
+    /// conversation (trừ sender). Trả về user_id của những người được mention
+    async fn insert_mentions(
+        &self,
+        message: &MessageEntity,
+        usernames: &[String],
+        tx: &mut Transaction,
+    ) -> Result<Vec<uuid::Uuid>, error::SystemError>;
+
+    async fn find_client_metadata_by_message(
+        &self,
+        message_id: &uuid::Uuid,
+    ) -> Result<Vec<ClientMetadataEntity>, error::SystemError>;
+
+    async fn find_client_metadata_by_user(
+        &self,
+        user_id: &uuid::Uuid,
+        limit: i64,
+    ) -> Result<Vec<ClientMetadataEntity>, error::SystemError>;
+
+    async fn create_scheduled(
+        &self,
+        scheduled: &InsertScheduledMessage,
+    ) -> Result<ScheduledMessageEntity, error::SystemError>;
+
+    /// Tin nhắn hẹn giờ đang chờ gửi của sender trong conversation, theo thời điểm gửi
+    async fn find_pending_scheduled(
         &self,
         conversation_id: &uuid::Uuid,
-        tx: E,
-    ) -> Result<Option<MessageEntity>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+        sender_id: &uuid::Uuid,
+    ) -> Result<Vec<ScheduledMessageEntity>, error::SystemError>;
+
+    /// Hủy tin nhắn hẹn giờ còn pending của sender, `None` nếu không tồn tại hoặc đã xử lý
+    async fn cancel_scheduled(
+        &self,
+        scheduled_id: &uuid::Uuid,
+        sender_id: &uuid::Uuid,
+    ) -> Result<Option<ScheduledMessageEntity>, error::SystemError>;
+
+    /// Claim tối đa `limit` tin nhắn đã tới giờ gửi (chuyển sang `sent`). Dùng
+    /// `FOR UPDATE SKIP LOCKED` để nhiều instance không gửi trùng
+    async fn claim_due_scheduled(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<ScheduledMessageEntity>, error::SystemError>;
+
+    /// Ghi nhận kết quả gửi: message đã tạo hoặc lỗi (chuyển sang `failed`)
+    async fn complete_scheduled(
+        &self,
+        scheduled_id: &uuid::Uuid,
+        result: Result<uuid::Uuid, String>,
+    ) -> Result<(), error::SystemError>;
 }
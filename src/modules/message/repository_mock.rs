@@ -0,0 +1,570 @@
+/// In-memory `MessageRepository` cho unit tests của services
+///
+/// Messages, participants và messages đã ẩn dùng chung với `ConversationRepositoryMock`,
+/// client metadata, mentions và tin nhắn hẹn giờ giữ riêng trong bộ nhớ. Không có bảng
+/// files nên bản export không kèm metadata file đính kèm. Dùng cùng `MemoryUnitOfWork`,
+/// tham số transaction bị bỏ qua và thay đổi được ghi ngay.
+use std::{
+    cmp::Reverse,
+    collections::HashSet,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    configs::uow::Transaction,
+    modules::{
+        conversation::{repository_mock::ConversationRepositoryMock, schema::ParticipantStatus},
+        message::{
+            model::{
+                CursorDirection, EncryptedEnvelope, ExportMessageRow, InsertClientMetadata,
+                InsertMessage, InsertScheduledMessage, MessageQuery,
+            },
+            repository::MessageRepository,
+            schema::{
+                ClientMetadataEntity, MessageEntity, MessageType, ScheduledMessageEntity,
+                ScheduledMessageStatus,
+            },
+        },
+    },
+};
+
+#[derive(Default)]
+struct State {
+    /// Messages đã chuyển sang archive (chỉ còn đọc được qua lịch sử / export)
+    archived: HashSet<Uuid>,
+    /// (message_id, user_id)
+    mentions: HashSet<(Uuid, Uuid)>,
+    client_metadata: Vec<ClientMetadataEntity>,
+    scheduled: Vec<ScheduledMessageEntity>,
+}
+
+#[derive(Clone)]
+pub struct MessageRepositoryMock {
+    conversations: ConversationRepositoryMock,
+    state: Arc<Mutex<State>>,
+}
+
+impl MessageRepositoryMock {
+    pub fn new(conversations: ConversationRepositoryMock) -> Self {
+        MessageRepositoryMock { conversations, state: Arc::default() }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// User được mention trong message
+    pub fn mentions(&self, message_id: &Uuid) -> Vec<Uuid> {
+        self.state()
+            .mentions
+            .iter()
+            .filter(|(id, _)| id == message_id)
+            .map(|(_, user_id)| *user_id)
+            .collect()
+    }
+
+    fn entity(conversation_id: Uuid, sender_id: Uuid, _type: MessageType) -> MessageEntity {
+        let now = chrono::Utc::now();
+        MessageEntity {
+            id: Uuid::now_v7(),
+            conversation_id,
+            sender_id,
+            reply_to_id: None,
+            forwarded_from: None,
+            client_message_id: None,
+            _type,
+            content: None,
+            file_url: None,
+            is_edited: false,
+            repeat_count: 1,
+            incoming_webhook_id: None,
+            sender_name_override: None,
+            sender_avatar_override: None,
+            ciphertext: None,
+            ciphertext_kind: None,
+            deleted_at: None,
+            hidden_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Lưu message mới, lỗi conflict nếu sender đã dùng `client_message_id`
+    fn insert(&self, message: MessageEntity) -> Result<MessageEntity, error::SystemError> {
+        if let Some(client_message_id) = &message.client_message_id {
+            let existing = self
+                .conversations
+                .messages_where(|m| m.client_message_id.as_ref() == Some(client_message_id));
+            if existing.iter().any(|m| m.sender_id == message.sender_id) {
+                return Err(error::SystemError::conflict("Client message id"));
+            }
+        }
+
+        Ok(self.conversations.insert_message(message))
+    }
+
+    /// Message còn trong bảng `messages` (chưa bị chuyển sang archive)
+    fn live(&self, message_id: &Uuid) -> Option<MessageEntity> {
+        if self.state().archived.contains(message_id) {
+            return None;
+        }
+        self.conversations.message(message_id)
+    }
+
+    /// Sửa message còn trong bảng `messages` nếu `update` trả về true
+    fn update_live(
+        &self,
+        message_id: &Uuid,
+        update: impl FnOnce(&mut MessageEntity) -> bool,
+    ) -> Option<MessageEntity> {
+        if self.state().archived.contains(message_id) {
+            return None;
+        }
+        self.conversations.update_message(message_id, update)
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageRepository for MessageRepositoryMock {
+    async fn find_by_id(
+        &self,
+        message_id: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<Option<MessageEntity>, error::SystemError> {
+        Ok(self.live(message_id).filter(|message| message.deleted_at.is_none()))
+    }
+
+    async fn create(
+        &self,
+        message: &InsertMessage,
+        _tx: &mut Transaction,
+    ) -> Result<MessageEntity, error::SystemError> {
+        let mut entity =
+            Self::entity(message.conversation_id, message.sender_id, MessageType::Text);
+        entity.content = message.content.clone();
+        entity.forwarded_from = message.forwarded_from;
+        entity.client_message_id = message.client_message_id;
+        if let Some(sender) = &message.sender_override {
+            entity.incoming_webhook_id = Some(sender.incoming_webhook_id);
+            entity.sender_name_override = sender.name.clone();
+            entity.sender_avatar_override = sender.avatar_url.clone();
+        }
+
+        self.insert(entity)
+    }
+
+    async fn create_encrypted(
+        &self,
+        conversation_id: &Uuid,
+        sender_id: &Uuid,
+        envelope: &EncryptedEnvelope,
+        client_message_id: Option<Uuid>,
+        _tx: &mut Transaction,
+    ) -> Result<MessageEntity, error::SystemError> {
+        let mut entity = Self::entity(*conversation_id, *sender_id, MessageType::Encrypted);
+        entity.ciphertext = Some(envelope.ciphertext.clone());
+        entity.ciphertext_kind = Some(envelope.kind);
+        entity.client_message_id = client_message_id;
+
+        self.insert(entity)
+    }
+
+    async fn create_system(
+        &self,
+        conversation_id: &Uuid,
+        sender_id: &Uuid,
+        content: &str,
+        _tx: &mut Transaction,
+    ) -> Result<MessageEntity, error::SystemError> {
+        let mut entity = Self::entity(*conversation_id, *sender_id, MessageType::System);
+        entity.content = Some(content.to_string());
+
+        self.insert(entity)
+    }
+
+    async fn find_by_client_message_id(
+        &self,
+        sender_id: &Uuid,
+        client_message_id: &Uuid,
+    ) -> Result<Option<MessageEntity>, error::SystemError> {
+        Ok(self
+            .conversations
+            .messages_where(|m| {
+                m.sender_id == *sender_id && m.client_message_id == Some(*client_message_id)
+            })
+            .into_iter()
+            .next())
+    }
+
+    async fn find_by_query(
+        &self,
+        query: &MessageQuery,
+        limit: i32,
+    ) -> Result<Vec<MessageEntity>, error::SystemError> {
+        let mut messages: Vec<_> = self
+            .conversations
+            .messages_for(&query.conversation_id, query.viewer_id.as_ref())
+            .into_iter()
+            .filter(|m| query.visible_since.is_none_or(|since| m.created_at >= since))
+            .filter(|m| match (query.direction, query.created_at) {
+                (_, None) => true,
+                (CursorDirection::Before, Some(cursor)) => m.created_at < cursor,
+                (CursorDirection::After, Some(cursor)) => m.created_at > cursor,
+            })
+            .collect();
+        if query.direction == CursorDirection::Before {
+            messages.reverse();
+        }
+        messages.truncate((limit + 1).max(0) as usize);
+
+        Ok(messages)
+    }
+
+    async fn find_for_export(
+        &self,
+        conversation_id: &Uuid,
+        viewer_id: &Uuid,
+        after: Option<chrono::DateTime<chrono::Utc>>,
+        visible_since: Option<chrono::DateTime<chrono::Utc>>,
+        limit: i32,
+    ) -> Result<Vec<ExportMessageRow>, error::SystemError> {
+        Ok(self
+            .conversations
+            .messages_for(conversation_id, Some(viewer_id))
+            .into_iter()
+            .filter(|m| {
+                m.deleted_at.is_none()
+                    && after.is_none_or(|after| m.created_at > after)
+                    && visible_since.is_none_or(|since| m.created_at >= since)
+            })
+            .take((limit + 1).max(0) as usize)
+            .map(|message| ExportMessageRow {
+                message,
+                attachment_original_filename: None,
+                attachment_mime_type: None,
+                attachment_file_size: None,
+            })
+            .collect())
+    }
+
+    async fn delete_older_than(
+        &self,
+        cutoff: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+    ) -> Result<u64, error::SystemError> {
+        let deleted =
+            self.conversations.remove_messages(limit.max(0) as usize, |m| m.created_at < cutoff);
+        Ok(deleted)
+    }
+
+    async fn create_archive_partitions(
+        &self,
+        _cutoff: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), error::SystemError> {
+        Ok(())
+    }
+
+    async fn archive_older_than(
+        &self,
+        cutoff: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+    ) -> Result<u64, error::SystemError> {
+        let mut state = self.state();
+        let mut candidates = self
+            .conversations
+            .messages_where(|m| m.created_at < cutoff && !state.archived.contains(&m.id));
+        candidates.sort_by_key(|m| m.created_at);
+        candidates.truncate(limit.max(0) as usize);
+
+        state.archived.extend(candidates.iter().map(|m| m.id));
+        Ok(candidates.len() as u64)
+    }
+
+    async fn find_around(
+        &self,
+        conversation_id: &Uuid,
+        viewer_id: &Uuid,
+        message_id: &Uuid,
+        limit: i32,
+        visible_since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<Vec<MessageEntity>, error::SystemError> {
+        let messages = self.conversations.messages_for(conversation_id, Some(viewer_id));
+        let visible = |m: &MessageEntity| visible_since.is_none_or(|since| m.created_at >= since);
+        let Some(target) = messages.iter().find(|m| m.id == *message_id && visible(m)) else {
+            return Ok(Vec::new());
+        };
+        let take = (limit + 1).max(0) as usize;
+
+        let mut older: Vec<_> = messages
+            .iter()
+            .filter(|m| m.created_at < target.created_at && visible(m))
+            .rev()
+            .take(take)
+            .cloned()
+            .collect();
+        older.reverse();
+
+        let newer = messages.iter().filter(|m| m.created_at > target.created_at).take(take);
+
+        Ok(older.into_iter().chain([target.clone()]).chain(newer.cloned()).collect())
+    }
+
+    async fn delete_message(
+        &self,
+        message_id: &Uuid,
+        user_id: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<bool, error::SystemError> {
+        let deleted = self.update_live(message_id, |m| {
+            if m.sender_id != *user_id || m.deleted_at.is_some() {
+                return false;
+            }
+            m.deleted_at = Some(chrono::Utc::now());
+            true
+        });
+        Ok(deleted.is_some())
+    }
+
+    async fn hide_for_user(
+        &self,
+        message_id: &Uuid,
+        user_id: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<Option<Uuid>, error::SystemError> {
+        let Some(message) = self.live(message_id).filter(|m| m.hidden_at.is_none()) else {
+            return Ok(None);
+        };
+        let participant = self.conversations.participant(&message.conversation_id, user_id);
+        if participant.is_none_or(|p| p.status != ParticipantStatus::Active) {
+            return Ok(None);
+        }
+
+        self.conversations.hide_message(*user_id, message.id);
+        Ok(Some(message.conversation_id))
+    }
+
+    async fn edit_message(
+        &self,
+        message_id: &Uuid,
+        user_id: &Uuid,
+        new_content: &str,
+        _tx: &mut Transaction,
+    ) -> Result<Option<MessageEntity>, error::SystemError> {
+        Ok(self.update_live(message_id, |m| {
+            if m.sender_id != *user_id || m.deleted_at.is_some() || m.hidden_at.is_some() {
+                return false;
+            }
+            m.content = Some(new_content.to_string());
+            m.updated_at = chrono::Utc::now();
+            true
+        }))
+    }
+
+    async fn get_last_message_by_conversation(
+        &self,
+        conversation_id: &Uuid,
+        _tx: &mut Transaction,
+    ) -> Result<Option<MessageEntity>, error::SystemError> {
+        let state = self.state();
+        Ok(self
+            .conversations
+            .messages_for(conversation_id, None)
+            .into_iter()
+            .rev()
+            .find(|m| m.deleted_at.is_none() && !state.archived.contains(&m.id)))
+    }
+
+    async fn increment_repeat_count(
+        &self,
+        message_id: &Uuid,
+    ) -> Result<Option<MessageEntity>, error::SystemError> {
+        Ok(self.update_live(message_id, |m| {
+            if m.deleted_at.is_some() || m.hidden_at.is_some() {
+                return false;
+            }
+            m.repeat_count += 1;
+            true
+        }))
+    }
+
+    async fn insert_client_metadata(
+        &self,
+        metadata: &InsertClientMetadata,
+        _tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
+        self.state().client_metadata.push(ClientMetadataEntity {
+            id: Uuid::now_v7(),
+            user_id: metadata.user_id,
+            message_id: metadata.message_id,
+            session_id: metadata.session_id,
+            app_version: metadata.app_version.clone(),
+            platform: metadata.platform.clone(),
+            created_at: chrono::Utc::now(),
+        });
+        Ok(())
+    }
+
+    async fn insert_mentions(
+        &self,
+        message: &MessageEntity,
+        usernames: &[String],
+        _tx: &mut Transaction,
+    ) -> Result<Vec<Uuid>, error::SystemError> {
+        let mentioned: Vec<Uuid> = self
+            .conversations
+            .participant_ids(&message.conversation_id)
+            .into_iter()
+            .filter(|user_id| *user_id != message.sender_id)
+            .filter(|user_id| {
+                self.conversations.participant(&message.conversation_id, user_id).is_some_and(|p| {
+                    p.status == ParticipantStatus::Active && p.deleted_at.is_none()
+                })
+            })
+            .filter(|user_id| {
+                self.conversations.users().get(user_id).is_some_and(|user| {
+                    user.deleted_at.is_none() && usernames.contains(&user.username.to_lowercase())
+                })
+            })
+            .collect();
+
+        let mut state = self.state();
+        Ok(mentioned.into_iter().filter(|id| state.mentions.insert((message.id, *id))).collect())
+    }
+
+    async fn find_client_metadata_by_message(
+        &self,
+        message_id: &Uuid,
+    ) -> Result<Vec<ClientMetadataEntity>, error::SystemError> {
+        let mut metadata: Vec<_> = self
+            .state()
+            .client_metadata
+            .iter()
+            .filter(|m| m.message_id == Some(*message_id))
+            .cloned()
+            .collect();
+        metadata.sort_by_key(|m| Reverse(m.created_at));
+        Ok(metadata)
+    }
+
+    async fn find_client_metadata_by_user(
+        &self,
+        user_id: &Uuid,
+        limit: i64,
+    ) -> Result<Vec<ClientMetadataEntity>, error::SystemError> {
+        let mut metadata: Vec<_> = self
+            .state()
+            .client_metadata
+            .iter()
+            .filter(|m| m.user_id == *user_id)
+            .cloned()
+            .collect();
+        metadata.sort_by_key(|m| Reverse(m.created_at));
+        metadata.truncate(limit.max(0) as usize);
+        Ok(metadata)
+    }
+
+    async fn create_scheduled(
+        &self,
+        scheduled: &InsertScheduledMessage,
+    ) -> Result<ScheduledMessageEntity, error::SystemError> {
+        let now = chrono::Utc::now();
+        let entity = ScheduledMessageEntity {
+            id: Uuid::now_v7(),
+            conversation_id: scheduled.conversation_id,
+            sender_id: scheduled.sender_id,
+            content: scheduled.content.clone(),
+            scheduled_at: scheduled.scheduled_at,
+            status: ScheduledMessageStatus::Pending,
+            message_id: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.state().scheduled.push(entity.clone());
+        Ok(entity)
+    }
+
+    async fn find_pending_scheduled(
+        &self,
+        conversation_id: &Uuid,
+        sender_id: &Uuid,
+    ) -> Result<Vec<ScheduledMessageEntity>, error::SystemError> {
+        let mut scheduled: Vec<_> = self
+            .state()
+            .scheduled
+            .iter()
+            .filter(|s| {
+                s.conversation_id == *conversation_id
+                    && s.sender_id == *sender_id
+                    && s.status == ScheduledMessageStatus::Pending
+            })
+            .cloned()
+            .collect();
+        scheduled.sort_by_key(|s| s.scheduled_at);
+        Ok(scheduled)
+    }
+
+    async fn cancel_scheduled(
+        &self,
+        scheduled_id: &Uuid,
+        sender_id: &Uuid,
+    ) -> Result<Option<ScheduledMessageEntity>, error::SystemError> {
+        let mut state = self.state();
+        let scheduled = state.scheduled.iter_mut().find(|s| {
+            s.id == *scheduled_id
+                && s.sender_id == *sender_id
+                && s.status == ScheduledMessageStatus::Pending
+        });
+
+        Ok(scheduled.map(|s| {
+            s.status = ScheduledMessageStatus::Cancelled;
+            s.updated_at = chrono::Utc::now();
+            s.clone()
+        }))
+    }
+
+    async fn claim_due_scheduled(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<ScheduledMessageEntity>, error::SystemError> {
+        let now = chrono::Utc::now();
+        let mut state = self.state();
+        let mut due: Vec<_> = state
+            .scheduled
+            .iter_mut()
+            .filter(|s| s.status == ScheduledMessageStatus::Pending && s.scheduled_at <= now)
+            .collect();
+        due.sort_by_key(|s| s.scheduled_at);
+        due.truncate(limit.max(0) as usize);
+
+        Ok(due
+            .into_iter()
+            .map(|s| {
+                s.status = ScheduledMessageStatus::Sent;
+                s.updated_at = now;
+                s.clone()
+            })
+            .collect())
+    }
+
+    async fn complete_scheduled(
+        &self,
+        scheduled_id: &Uuid,
+        result: Result<Uuid, String>,
+    ) -> Result<(), error::SystemError> {
+        if let Some(scheduled) = self.state().scheduled.iter_mut().find(|s| s.id == *scheduled_id) {
+            match result {
+                Ok(message_id) => scheduled.message_id = Some(message_id),
+                Err(error) => {
+                    scheduled.error = Some(error);
+                    scheduled.status = ScheduledMessageStatus::Failed;
+                }
+            }
+            scheduled.updated_at = chrono::Utc::now();
+        }
+        Ok(())
+    }
+}
@@ -1,104 +1,418 @@
 use crate::{
     api::error,
+    configs::{read_replica::ReadPool, uow::Transaction},
     modules::message::{
-        self, model::InsertMessage, repository::MessageRepository, schema::MessageEntity,
+        self,
+        model::{
+            CursorDirection, EncryptedEnvelope, ExportMessageRow, InsertClientMetadata,
+            InsertMessage, InsertScheduledMessage,
+        },
+        repository::MessageRepository,
+        schema::{ClientMetadataEntity, MessageEntity, ScheduledMessageEntity},
     },
 };
 
 #[derive(Clone)]
 pub struct MessageRepositoryPg {
     pool: sqlx::PgPool,
+    read_pool: ReadPool,
 }
 
 impl MessageRepositoryPg {
     pub fn new(pool: sqlx::PgPool) -> Self {
-        Self { pool }
+        Self { read_pool: ReadPool::primary_only(pool.clone()), pool }
+    }
+
+    /// Đọc lịch sử messages (phân trang, jump, export) từ `read_pool`
+    pub fn with_read_pool(mut self, read_pool: ReadPool) -> Self {
+        self.read_pool = read_pool;
+        self
     }
 }
 
 #[async_trait::async_trait]
 impl MessageRepository for MessageRepositoryPg {
-    fn get_pool(&self) -> &sqlx::PgPool {
-        &self.pool
-    }
-
-    async fn find_by_id<'e, E>(
+    async fn find_by_id(
         &self,
         message_id: &uuid::Uuid,
-        tx: E,
-    ) -> Result<Option<MessageEntity>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<Option<MessageEntity>, error::SystemError> {
         let message = sqlx::query_as::<_, MessageEntity>(
             "SELECT * FROM messages WHERE id = $1 AND deleted_at IS NULL",
         )
         .bind(message_id)
-        .fetch_optional(tx)
+        .fetch_optional(tx.connection()?)
         .await?;
         Ok(message)
     }
 
-    async fn create<'e, E>(
+    async fn create(
         &self,
         message: &InsertMessage,
-        tx: E,
-    ) -> Result<MessageEntity, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<MessageEntity, error::SystemError> {
         let message = sqlx::query_as::<_, MessageEntity>(
-            "INSERT INTO messages (conversation_id, sender_id, content) VALUES ($1, $2, $3) RETURNING *",
+            r#"
+            INSERT INTO messages (
+                conversation_id, sender_id, content, forwarded_from, client_message_id,
+                incoming_webhook_id, sender_name_override, sender_avatar_override
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING *
+            "#,
         )
         .bind(message.conversation_id)
         .bind(message.sender_id)
         .bind(&message.content)
-        .fetch_one(tx)
+        .bind(message.forwarded_from)
+        .bind(message.client_message_id)
+        .bind(message.sender_override.as_ref().map(|o| o.incoming_webhook_id))
+        .bind(message.sender_override.as_ref().and_then(|o| o.name.as_deref()))
+        .bind(message.sender_override.as_ref().and_then(|o| o.avatar_url.as_deref()))
+        .fetch_one(tx.connection()?)
         .await?;
 
         Ok(message)
     }
 
-    async fn find_by_query<'e, E>(
+    async fn create_encrypted(
+        &self,
+        conversation_id: &uuid::Uuid,
+        sender_id: &uuid::Uuid,
+        envelope: &EncryptedEnvelope,
+        client_message_id: Option<uuid::Uuid>,
+        tx: &mut Transaction,
+    ) -> Result<MessageEntity, error::SystemError> {
+        let message = sqlx::query_as::<_, MessageEntity>(
+            r#"
+            INSERT INTO messages (
+                conversation_id, sender_id, type, ciphertext, ciphertext_kind, client_message_id
+            )
+            VALUES ($1, $2, 'encrypted', $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(sender_id)
+        .bind(&envelope.ciphertext)
+        .bind(envelope.kind)
+        .bind(client_message_id)
+        .fetch_one(tx.connection()?)
+        .await?;
+
+        Ok(message)
+    }
+
+    async fn create_system(
+        &self,
+        conversation_id: &uuid::Uuid,
+        sender_id: &uuid::Uuid,
+        content: &str,
+        tx: &mut Transaction,
+    ) -> Result<MessageEntity, error::SystemError> {
+        let message = sqlx::query_as::<_, MessageEntity>(
+            r#"
+            INSERT INTO messages (conversation_id, sender_id, type, content)
+            VALUES ($1, $2, 'system', $3)
+            RETURNING *
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(sender_id)
+        .bind(content)
+        .fetch_one(tx.connection()?)
+        .await?;
+
+        Ok(message)
+    }
+
+    async fn find_by_client_message_id(
+        &self,
+        sender_id: &uuid::Uuid,
+        client_message_id: &uuid::Uuid,
+    ) -> Result<Option<MessageEntity>, error::SystemError> {
+        // has unique index on (sender_id, client_message_id) where client_message_id IS NOT NULL
+        let message = sqlx::query_as::<_, MessageEntity>(
+            "SELECT * FROM messages WHERE sender_id = $1 AND client_message_id = $2",
+        )
+        .bind(sender_id)
+        .bind(client_message_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(message)
+    }
+
+    async fn find_by_query(
         &self,
         query: &message::model::MessageQuery,
         limit: i32,
-        tx: E,
-    ) -> Result<Vec<MessageEntity>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
-        // has index on (conversation_id, created_at DESC NULLS LAST) where deleted_at IS NULL
+    ) -> Result<Vec<MessageEntity>, error::SystemError> {
+        // has index on (conversation_id, created_at DESC NULLS LAST)
+        // Messages đã xóa với mọi người vẫn được trả về để hiển thị tombstone
 
-        let messages = sqlx::query_as::<_, MessageEntity>(
+        let sql = match query.direction {
+            CursorDirection::Before => {
+                r#"
+                SELECT *
+                FROM messages_all m
+                WHERE conversation_id = $1
+                  AND hidden_at IS NULL
+                  AND ($2::timestamptz IS NULL OR created_at < $2)
+                  AND ($4::timestamptz IS NULL OR created_at >= $4)
+                  AND NOT EXISTS (
+                      SELECT 1 FROM hidden_messages h
+                      WHERE h.message_id = m.id AND h.user_id = $5
+                  )
+                ORDER BY created_at DESC
+                LIMIT $3
+                "#
+            }
+            CursorDirection::After => {
+                r#"
+                SELECT *
+                FROM messages_all m
+                WHERE conversation_id = $1
+                  AND hidden_at IS NULL
+                  AND ($2::timestamptz IS NULL OR created_at > $2)
+                  AND ($4::timestamptz IS NULL OR created_at >= $4)
+                  AND NOT EXISTS (
+                      SELECT 1 FROM hidden_messages h
+                      WHERE h.message_id = m.id AND h.user_id = $5
+                  )
+                ORDER BY created_at ASC
+                LIMIT $3
+                "#
+            }
+        };
+
+        let messages = sqlx::query_as::<_, MessageEntity>(sql)
+            .bind(query.conversation_id)
+            .bind(query.created_at)
+            .bind(limit + 1)
+            .bind(query.visible_since)
+            .bind(query.viewer_id)
+            .fetch_all(&mut *self.read_pool.acquire().await?)
+            .await?;
+
+        Ok(messages)
+    }
+
+    async fn find_for_export(
+        &self,
+        conversation_id: &uuid::Uuid,
+        viewer_id: &uuid::Uuid,
+        after: Option<chrono::DateTime<chrono::Utc>>,
+        visible_since: Option<chrono::DateTime<chrono::Utc>>,
+        limit: i32,
+    ) -> Result<Vec<ExportMessageRow>, error::SystemError> {
+        // file_url = `{base_url}/{filename}`
+        let rows = sqlx::query_as::<_, ExportMessageRow>(
             r#"
-            SELECT *
-            FROM messages
-            WHERE conversation_id = $1
-              AND deleted_at IS NULL
-              AND ($2::timestamptz IS NULL OR created_at < $2)
-            ORDER BY created_at DESC
+            SELECT
+                m.*,
+                f.original_filename AS attachment_original_filename,
+                f.mime_type AS attachment_mime_type,
+                f.file_size AS attachment_file_size
+            FROM messages_all m
+            LEFT JOIN files f ON f.filename = regexp_replace(m.file_url, '^.*/', '')
+            WHERE m.conversation_id = $1
+              AND m.deleted_at IS NULL
+              AND m.hidden_at IS NULL
+              AND ($2::timestamptz IS NULL OR m.created_at > $2)
+              AND ($4::timestamptz IS NULL OR m.created_at >= $4)
+              AND NOT EXISTS (
+                  SELECT 1 FROM hidden_messages h
+                  WHERE h.message_id = m.id AND h.user_id = $5
+              )
+            ORDER BY m.created_at ASC
             LIMIT $3
             "#,
         )
-        .bind(query.conversation_id)
-        .bind(query.created_at)
+        .bind(conversation_id)
+        .bind(after)
         .bind(limit + 1)
-        .fetch_all(tx)
+        .bind(visible_since)
+        .bind(viewer_id)
+        .fetch_all(&mut *self.read_pool.acquire().await?)
+        .await?;
+
+        Ok(rows)
+    }
+
+    async fn delete_older_than(
+        &self,
+        cutoff: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+    ) -> Result<u64, error::SystemError> {
+        // hidden_messages không có FK tới messages (để giữ trạng thái ẩn khi archive)
+        let deleted = sqlx::query_scalar::<_, i64>(
+            r#"
+            WITH live AS (
+                DELETE FROM messages
+                WHERE id IN (
+                    SELECT id
+                    FROM messages
+                    WHERE created_at < $1
+                    LIMIT $2
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING id
+            ),
+            archived AS (
+                DELETE FROM messages_archive
+                WHERE (id, created_at) IN (
+                    SELECT id, created_at
+                    FROM messages_archive
+                    WHERE created_at < $1
+                    LIMIT $2
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING id
+            ),
+            hidden AS (
+                DELETE FROM hidden_messages
+                WHERE message_id IN (SELECT id FROM live UNION ALL SELECT id FROM archived)
+            )
+            SELECT (SELECT COUNT(*) FROM live) + (SELECT COUNT(*) FROM archived)
+            "#,
+        )
+        .bind(cutoff)
+        .bind(limit)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(deleted as u64)
+    }
+
+    async fn create_archive_partitions(
+        &self,
+        cutoff: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), error::SystemError> {
+        sqlx::query(
+            r#"
+            SELECT create_messages_archive_partition(month)
+            FROM generate_series(
+                date_trunc('month', (SELECT MIN(created_at) FROM messages), 'UTC'),
+                $1,
+                interval '1 month'
+            ) AS month
+            "#,
+        )
+        .bind(cutoff)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn archive_older_than(
+        &self,
+        cutoff: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+    ) -> Result<u64, error::SystemError> {
+        // FK của participants / replies / forwards tới message bị chuyển đi được set null
+        let archived = sqlx::query(
+            r#"
+            WITH moved AS (
+                DELETE FROM messages
+                WHERE id IN (
+                    SELECT id
+                    FROM messages
+                    WHERE created_at < $1
+                    ORDER BY created_at
+                    LIMIT $2
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING *
+            )
+            INSERT INTO messages_archive
+            SELECT * FROM moved
+            "#,
+        )
+        .bind(cutoff)
+        .bind(limit)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(archived)
+    }
+
+    async fn find_around(
+        &self,
+        conversation_id: &uuid::Uuid,
+        viewer_id: &uuid::Uuid,
+        message_id: &uuid::Uuid,
+        limit: i32,
+        visible_since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<Vec<MessageEntity>, error::SystemError> {
+        // Hai nhánh dùng cùng index (conversation_id, created_at) theo hai chiều
+        let messages = sqlx::query_as::<_, MessageEntity>(
+            r#"
+            WITH target AS (
+                SELECT *
+                FROM messages_all m
+                WHERE id = $2
+                  AND conversation_id = $1
+                  AND hidden_at IS NULL
+                  AND ($4::timestamptz IS NULL OR created_at >= $4)
+                  AND NOT EXISTS (
+                      SELECT 1 FROM hidden_messages h
+                      WHERE h.message_id = m.id AND h.user_id = $5
+                  )
+            ),
+            older AS (
+                SELECT m.*
+                FROM messages_all m, target t
+                WHERE m.conversation_id = $1
+                  AND m.hidden_at IS NULL
+                  AND m.created_at < t.created_at
+                  AND ($4::timestamptz IS NULL OR m.created_at >= $4)
+                  AND NOT EXISTS (
+                      SELECT 1 FROM hidden_messages h
+                      WHERE h.message_id = m.id AND h.user_id = $5
+                  )
+                ORDER BY m.created_at DESC
+                LIMIT $3
+            ),
+            newer AS (
+                SELECT m.*
+                FROM messages_all m, target t
+                WHERE m.conversation_id = $1
+                  AND m.hidden_at IS NULL
+                  AND m.created_at > t.created_at
+                  AND NOT EXISTS (
+                      SELECT 1 FROM hidden_messages h
+                      WHERE h.message_id = m.id AND h.user_id = $5
+                  )
+                ORDER BY m.created_at ASC
+                LIMIT $3
+            )
+            SELECT * FROM older
+            UNION ALL
+            SELECT * FROM target
+            UNION ALL
+            SELECT * FROM newer
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(message_id)
+        .bind(limit + 1)
+        .bind(visible_since)
+        .bind(viewer_id)
+        .fetch_all(&mut *self.read_pool.acquire().await?)
         .await?;
 
         Ok(messages)
     }
 
-    async fn delete_message<'e, E>(
+    async fn delete_message(
         &self,
         message_id: &uuid::Uuid,
         user_id: &uuid::Uuid,
-        tx: E,
-    ) -> Result<bool, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<bool, error::SystemError> {
         // Soft delete: chỉ cho phép xóa tin nhắn của chính mình
         let rows = sqlx::query(
             r#"
@@ -111,23 +425,52 @@ impl MessageRepository for MessageRepositoryPg {
         )
         .bind(message_id)
         .bind(user_id)
-        .execute(tx)
+        .execute(tx.connection()?)
         .await?
         .rows_affected();
 
         Ok(rows > 0)
     }
 
-    async fn edit_message<'e, E>(
+    async fn hide_for_user(
+        &self,
+        message_id: &uuid::Uuid,
+        user_id: &uuid::Uuid,
+        tx: &mut Transaction,
+    ) -> Result<Option<uuid::Uuid>, error::SystemError> {
+        let conversation_id = sqlx::query_scalar::<_, uuid::Uuid>(
+            r#"
+            WITH m AS (
+                SELECT m.id, m.conversation_id
+                FROM messages m
+                JOIN participants p ON p.conversation_id = m.conversation_id
+                WHERE m.id = $2
+                  AND m.hidden_at IS NULL
+                  AND p.user_id = $1
+                  AND p.status = 'active'
+            ), hidden AS (
+                INSERT INTO hidden_messages (user_id, message_id)
+                SELECT $1, id FROM m
+                ON CONFLICT (user_id, message_id) DO NOTHING
+            )
+            SELECT conversation_id FROM m
+            "#,
+        )
+        .bind(user_id)
+        .bind(message_id)
+        .fetch_optional(tx.connection()?)
+        .await?;
+
+        Ok(conversation_id)
+    }
+
+    async fn edit_message(
         &self,
         message_id: &uuid::Uuid,
         user_id: &uuid::Uuid,
         new_content: &str,
-        tx: E,
-    ) -> Result<Option<MessageEntity>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<Option<MessageEntity>, error::SystemError> {
         // Edit message: chỉ cho phép sửa tin nhắn của chính mình
         let message = sqlx::query_as::<_, MessageEntity>(
             r#"
@@ -137,40 +480,268 @@ impl MessageRepository for MessageRepositoryPg {
             WHERE id = $2
               AND sender_id = $3
               AND deleted_at IS NULL
+              AND hidden_at IS NULL
             RETURNING *
             "#,
         )
         .bind(new_content)
         .bind(message_id)
         .bind(user_id)
-        .fetch_optional(tx)
+        .fetch_optional(tx.connection()?)
         .await?;
 
         Ok(message)
     }
 
-    async fn get_last_message_by_conversation<'e, E>(
+    async fn get_last_message_by_conversation(
         &self,
         conversation_id: &uuid::Uuid,
-        tx: E,
-    ) -> Result<Option<MessageEntity>, error::SystemError>
-    where
-        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
-    {
+        tx: &mut Transaction,
+    ) -> Result<Option<MessageEntity>, error::SystemError> {
         let message = sqlx::query_as::<_, MessageEntity>(
             r#"
             SELECT *
             FROM messages
             WHERE conversation_id = $1
               AND deleted_at IS NULL
+              AND hidden_at IS NULL
             ORDER BY created_at DESC
             LIMIT 1
             "#,
         )
         .bind(conversation_id)
-        .fetch_optional(tx)
+        .fetch_optional(tx.connection()?)
         .await?;
 
         Ok(message)
     }
+
+    async fn increment_repeat_count(
+        &self,
+        message_id: &uuid::Uuid,
+    ) -> Result<Option<MessageEntity>, error::SystemError> {
+        let message = sqlx::query_as::<_, MessageEntity>(
+            r#"
+            UPDATE messages
+            SET repeat_count = repeat_count + 1
+            WHERE id = $1
+              AND deleted_at IS NULL
+              AND hidden_at IS NULL
+            RETURNING *
+            "#,
+        )
+        .bind(message_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(message)
+    }
+
+    async fn insert_client_metadata(
+        &self,
+        metadata: &InsertClientMetadata,
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
+        sqlx::query(
+            "INSERT INTO client_metadata (user_id, message_id, session_id, app_version, platform) VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(metadata.user_id)
+        .bind(metadata.message_id)
+        .bind(metadata.session_id)
+        .bind(&metadata.app_version)
+        .bind(&metadata.platform)
+        .execute(tx.connection()?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_mentions(
+        &self,
+        message: &MessageEntity,
+        usernames: &[String],
+        tx: &mut Transaction,
+    ) -> Result<Vec<uuid::Uuid>, error::SystemError> {
+        let user_ids = sqlx::query_scalar::<_, uuid::Uuid>(
+            r#"
+            INSERT INTO message_mentions (message_id, user_id)
+            SELECT $1, u.id
+            FROM users u
+            JOIN participants p
+                ON p.user_id = u.id
+               AND p.conversation_id = $2
+               AND p.status = 'active'
+               AND p.deleted_at IS NULL
+            WHERE lower(u.username) = ANY($4)
+              AND u.id <> $3
+              AND u.deleted_at IS NULL
+            ON CONFLICT DO NOTHING
+            RETURNING user_id
+            "#,
+        )
+        .bind(message.id)
+        .bind(message.conversation_id)
+        .bind(message.sender_id)
+        .bind(usernames)
+        .fetch_all(tx.connection()?)
+        .await?;
+
+        Ok(user_ids)
+    }
+
+    async fn find_client_metadata_by_message(
+        &self,
+        message_id: &uuid::Uuid,
+    ) -> Result<Vec<ClientMetadataEntity>, error::SystemError> {
+        let metadata = sqlx::query_as::<_, ClientMetadataEntity>(
+            "SELECT * FROM client_metadata WHERE message_id = $1 ORDER BY created_at DESC",
+        )
+        .bind(message_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(metadata)
+    }
+
+    async fn find_client_metadata_by_user(
+        &self,
+        user_id: &uuid::Uuid,
+        limit: i64,
+    ) -> Result<Vec<ClientMetadataEntity>, error::SystemError> {
+        let metadata = sqlx::query_as::<_, ClientMetadataEntity>(
+            "SELECT * FROM client_metadata WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2",
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(metadata)
+    }
+
+    async fn create_scheduled(
+        &self,
+        scheduled: &InsertScheduledMessage,
+    ) -> Result<ScheduledMessageEntity, error::SystemError> {
+        let scheduled = sqlx::query_as::<_, ScheduledMessageEntity>(
+            r#"
+            INSERT INTO scheduled_messages (conversation_id, sender_id, content, scheduled_at)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(scheduled.conversation_id)
+        .bind(scheduled.sender_id)
+        .bind(&scheduled.content)
+        .bind(scheduled.scheduled_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(scheduled)
+    }
+
+    async fn find_pending_scheduled(
+        &self,
+        conversation_id: &uuid::Uuid,
+        sender_id: &uuid::Uuid,
+    ) -> Result<Vec<ScheduledMessageEntity>, error::SystemError> {
+        let scheduled = sqlx::query_as::<_, ScheduledMessageEntity>(
+            r#"
+            SELECT *
+            FROM scheduled_messages
+            WHERE conversation_id = $1
+              AND sender_id = $2
+              AND status = 'pending'
+            ORDER BY scheduled_at ASC
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(sender_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(scheduled)
+    }
+
+    async fn cancel_scheduled(
+        &self,
+        scheduled_id: &uuid::Uuid,
+        sender_id: &uuid::Uuid,
+    ) -> Result<Option<ScheduledMessageEntity>, error::SystemError> {
+        let scheduled = sqlx::query_as::<_, ScheduledMessageEntity>(
+            r#"
+            UPDATE scheduled_messages
+            SET status = 'cancelled',
+                updated_at = NOW()
+            WHERE id = $1
+              AND sender_id = $2
+              AND status = 'pending'
+            RETURNING *
+            "#,
+        )
+        .bind(scheduled_id)
+        .bind(sender_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(scheduled)
+    }
+
+    async fn claim_due_scheduled(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<ScheduledMessageEntity>, error::SystemError> {
+        // has partial index on (scheduled_at) where status = 'pending'
+        let scheduled = sqlx::query_as::<_, ScheduledMessageEntity>(
+            r#"
+            UPDATE scheduled_messages
+            SET status = 'sent',
+                updated_at = NOW()
+            WHERE id IN (
+                SELECT id
+                FROM scheduled_messages
+                WHERE status = 'pending'
+                  AND scheduled_at <= NOW()
+                ORDER BY scheduled_at ASC
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(scheduled)
+    }
+
+    async fn complete_scheduled(
+        &self,
+        scheduled_id: &uuid::Uuid,
+        result: Result<uuid::Uuid, String>,
+    ) -> Result<(), error::SystemError> {
+        let (message_id, error) = match result {
+            Ok(message_id) => (Some(message_id), None),
+            Err(error) => (None, Some(error)),
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE scheduled_messages
+            SET message_id = $2,
+                error = $3,
+                status = CASE WHEN $3::text IS NULL THEN status ELSE 'failed' END,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(scheduled_id)
+        .bind(message_id)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
 }
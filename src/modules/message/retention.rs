@@ -0,0 +1,43 @@
+/// Message Retention Worker
+///
+/// Task chạy nền định kỳ xóa vĩnh viễn messages cũ hơn `message_retention_days` trong
+/// conversation defaults (admin cấu hình qua `PUT /api/v1/admin/conversation-defaults`).
+/// Xóa theo batch với `FOR UPDATE SKIP LOCKED` để không giữ lock lâu trên bảng messages
+/// và chạy song song trên nhiều instance không xóa trùng.
+use std::time::Duration;
+
+use crate::modules::message::service::MessageService;
+
+/// Khoảng thời gian giữa hai lần dọn messages hết hạn
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Số messages tối đa xóa mỗi lần, xóa tiếp ngay nếu còn messages hết hạn
+const BATCH_SIZE: i64 = 1000;
+
+pub async fn run_message_retention_worker(service: MessageService) {
+    let mut interval = actix_web::rt::time::interval(PRUNE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let mut pruned = 0;
+        loop {
+            match service.prune_expired_messages(BATCH_SIZE).await {
+                Ok(deleted) => {
+                    pruned += deleted;
+                    if deleted < BATCH_SIZE as u64 {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Failed to prune expired messages: {:?}", e);
+                    break;
+                }
+            }
+        }
+
+        if pruned > 0 {
+            tracing::info!("Pruned {} messages past the retention period", pruned);
+        }
+    }
+}
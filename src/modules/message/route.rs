@@ -3,19 +3,53 @@ use actix_web::{
     web::{scope, ServiceConfig},
 };
 
-use crate::{
-    middlewares::{require_friend, require_group_member},
-    modules::message::handle::*,
-};
+use crate::{middlewares::require_group_member, modules::message::handle::*};
+use utoipa::OpenApi;
 
 pub fn configure(cfg: &mut ServiceConfig) {
     cfg.service(
         scope("/messages")
-            .service(scope("/direct").wrap(from_fn(require_friend)).service(send_direct_message))
+            .service(scope("/direct").service(send_direct_message).service(send_encrypted_message))
             .service(
                 scope("/group").wrap(from_fn(require_group_member)).service(send_group_message),
             )
+            .service(schedule_message)
+            .service(get_scheduled_messages)
+            .service(cancel_scheduled_message)
             .service(delete_message)
-            .service(edit_message),
+            .service(edit_message)
+            .service(forward_message)
+            .service(translate_message)
+            .service(broadcast_message),
     );
 }
+
+pub fn admin_configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        scope("/client-metadata")
+            .service(get_message_client_metadata)
+            .service(get_user_client_metadata),
+    );
+}
+
+/// OpenAPI paths của `configure` (scope `/messages`)
+#[derive(OpenApi)]
+#[openapi(paths(
+    send_direct_message,
+    send_encrypted_message,
+    send_group_message,
+    delete_message,
+    edit_message,
+    forward_message,
+    translate_message,
+    broadcast_message,
+    schedule_message,
+    get_scheduled_messages,
+    cancel_scheduled_message
+))]
+pub struct MessageApiDoc;
+
+/// OpenAPI paths của `admin_configure` (scope `/client-metadata`)
+#[derive(OpenApi)]
+#[openapi(paths(get_message_client_metadata, get_user_client_metadata))]
+pub struct AdminMessageApiDoc;
@@ -0,0 +1,33 @@
+/// Scheduled Message Worker
+///
+/// Task chạy nền định kỳ claim các tin nhắn hẹn giờ đã tới `scheduled_at` và gửi qua
+/// MessageService. Claim dùng `FOR UPDATE SKIP LOCKED` nên chạy song song trên nhiều
+/// instance không gửi trùng; mỗi tin được gửi tối đa một lần (lỗi → `failed`).
+use std::time::Duration;
+
+use crate::modules::message::service::MessageService;
+
+/// Khoảng thời gian giữa hai lần quét tin nhắn tới hạn
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Số tin nhắn tối đa claim mỗi lần, quét tiếp ngay nếu còn tin tới hạn
+const BATCH_SIZE: i64 = 100;
+
+pub async fn run_scheduled_message_worker(service: MessageService) {
+    let mut interval = actix_web::rt::time::interval(POLL_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        loop {
+            match service.deliver_due_scheduled_messages(BATCH_SIZE).await {
+                Ok(delivered) if delivered as i64 == BATCH_SIZE => continue,
+                Ok(_) => break,
+                Err(e) => {
+                    tracing::error!("Failed to deliver scheduled messages: {:?}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::{FromRow, Type};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, PartialEq, Clone, Type, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Clone, Type, Serialize, Deserialize, ToSchema)]
 #[sqlx(type_name = "message_type", rename_all = "lowercase")]
 pub enum MessageType {
     Text,
@@ -10,20 +11,107 @@ pub enum MessageType {
     Video,
     File,
     System,
+    /// Tin nhắn mã hóa đầu cuối, nội dung nằm trong `ciphertext`
+    Encrypted,
 }
 
-#[derive(Debug, Clone, FromRow, Serialize)]
+/// Loại ciphertext của tin nhắn mã hóa (Signal protocol)
+#[derive(Debug, PartialEq, Clone, Copy, Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "ciphertext_kind", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum CiphertextKind {
+    /// Tin nhắn đầu tiên của session, mang theo prekey đã dùng để thiết lập session
+    Prekey,
+    /// Tin nhắn trong session đã thiết lập
+    Message,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
 pub struct MessageEntity {
     pub id: Uuid,
     pub conversation_id: Uuid,
     pub sender_id: Uuid,
     pub reply_to_id: Option<Uuid>,
+    /// Message gốc nếu đây là bản forward
+    pub forwarded_from: Option<Uuid>,
+    /// Idempotency key do client sinh khi gửi, duy nhất theo sender
+    pub client_message_id: Option<Uuid>,
     #[sqlx(rename = "type")]
     pub _type: MessageType,
     pub content: Option<String>,
     pub file_url: Option<String>,
     pub is_edited: bool,
+    pub repeat_count: i32,
+    /// Incoming webhook đã đăng tin nhắn (sender là người tạo webhook)
+    pub incoming_webhook_id: Option<Uuid>,
+    /// Tên / avatar hiển thị thay cho sender, do incoming webhook truyền vào
+    pub sender_name_override: Option<String>,
+    pub sender_avatar_override: Option<String>,
+    /// Ciphertext (base64) của tin nhắn `encrypted`, server không đọc được
+    pub ciphertext: Option<String>,
+    pub ciphertext_kind: Option<CiphertextKind>,
     pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub hidden_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Nội dung hiển thị thay cho message đã bị xóa với mọi người
+pub const DELETED_MESSAGE_TEXT: &str = "This message was deleted";
+
+impl MessageEntity {
+    /// Tombstone của message đã bị xóa với mọi người: giữ vị trí trong lịch sử nhưng bỏ
+    /// nội dung, file và ciphertext
+    pub fn into_tombstone(self) -> Self {
+        if self.deleted_at.is_none() {
+            return self;
+        }
+
+        MessageEntity {
+            content: Some(DELETED_MESSAGE_TEXT.to_string()),
+            file_url: None,
+            ciphertext: None,
+            ciphertext_kind: None,
+            ..self
+        }
+    }
+}
+
+/// Metadata client (app version, platform) lưu ở bảng riêng để debug, không nằm trong hot row messages
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct ClientMetadataEntity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub message_id: Option<Uuid>,
+    pub session_id: Option<Uuid>,
+    pub app_version: Option<String>,
+    pub platform: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "scheduled_message_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduledMessageStatus {
+    Pending,
+    Sent,
+    Cancelled,
+    Failed,
+}
+
+/// Tin nhắn hẹn giờ, được scheduler gửi qua MessageService khi tới `scheduled_at`
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct ScheduledMessageEntity {
+    pub id: Uuid,
+    pub conversation_id: Uuid,
+    pub sender_id: Uuid,
+    pub content: String,
+    pub scheduled_at: chrono::DateTime<chrono::Utc>,
+    pub status: ScheduledMessageStatus,
+    /// Message đã được tạo khi gửi thành công
+    pub message_id: Option<Uuid>,
+    /// Lý do gửi thất bại
+    pub error: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
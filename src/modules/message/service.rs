@@ -1,118 +1,347 @@
 /// Message Service
 ///
 /// Service layer xử lý business logic cho messages, bao gồm:
-/// - Gửi tin nhắn (direct và group)
-/// - Xóa và chỉnh sửa tin nhắn
-/// - Broadcast real-time qua WebSocket
+/// - Gửi tin nhắn (direct và group), gửi cùng lúc tới nhiều bạn bè
+/// - Xóa, chỉnh sửa và forward tin nhắn
+/// - Tin nhắn hẹn giờ (scheduler gửi khi tới giờ)
+/// - Broadcast real-time qua WebSocket (ghi vào event outbox cùng transaction)
+/// - Outgoing webhooks của conversation (ghi deliveries cùng transaction)
+/// - Slash commands (dispatch trước khi lưu, xem `command`)
+/// - Dịch tin nhắn qua translation provider (xem `translation`)
 use actix::Addr;
+use futures_util::stream::{self, StreamExt};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::api::error;
-use crate::configs::RedisCache;
+use crate::configs::cache::CacheBackend;
+use crate::configs::settings::settings;
+use crate::configs::uow::{Transaction, UnitOfWork};
 use crate::modules::conversation::model::NewLastMessage;
 use crate::modules::conversation::repository::{
     ConversationRepository, LastMessageRepository, ParticipantRepository,
 };
-use crate::modules::message::model::InsertMessage;
+use crate::modules::conversation::schema::{ConversationEntity, ConversationType, DuplicatePolicy};
+use crate::modules::conversation::service::visible_since;
+use crate::modules::message::command::{CommandOutcome, CommandRegistry};
+use crate::modules::message::model::{
+    BroadcastResult, CachedTranslation, ClientMetadata, DeleteMode, DuplicateTracker,
+    EncryptedEnvelope, InsertClientMetadata, InsertMessage, InsertScheduledMessage, SenderOverride,
+    TranslatedMessage,
+};
 use crate::modules::message::repository::MessageRepository;
-use crate::modules::message::schema::MessageEntity;
-use crate::modules::websocket::events::BroadcastToRoom;
-use crate::modules::websocket::message::{LastMessageInfo, SenderInfo, ServerMessage};
+use crate::modules::message::schema::{
+    ClientMetadataEntity, MessageEntity, MessageType, ScheduledMessageEntity,
+};
+use crate::modules::message::translation::{Translator, TRANSLATION_CACHE_TTL};
+use crate::modules::notification::model::PushJob;
+use crate::modules::notification::queue::PushQueue;
+use crate::modules::report::moderation::{ContentFilter, ContentKind};
+use crate::modules::report::schema::ReportTargetType;
+use crate::modules::webhook::delivery::WebhookQueue;
+use crate::modules::websocket::bridge::FanoutEvent;
+use crate::modules::websocket::dispatcher::EventOutbox;
+use crate::modules::websocket::events::{BroadcastToRoom, SendToUser, SendToUsers};
+use crate::modules::websocket::message::{
+    LastMessageInfo, SenderInfo, SenderResolver, ServerMessage,
+};
 use crate::modules::websocket::server::WebSocketServer;
+use crate::ENV;
+
+/// Kết quả kiểm tra nội dung trùng lặp trước khi persist message
+enum DuplicateCheck {
+    /// Message mới, kèm số lần gửi lặp liên tiếp (1 = không trùng)
+    Fresh(u32),
+    /// Message đã được gộp vào message trước đó (repeat counter đã tăng)
+    Collapsed(Box<MessageEntity>),
+}
+
+/// SHA-256 của nội dung, ổn định giữa các instance và các lần build
+fn content_hash(content: &str) -> [u8; 32] {
+    Sha256::digest(content.trim().as_bytes()).into()
+}
+
+/// Số mention tối đa được xử lý trong một tin nhắn
+const MAX_MENTIONS: usize = 50;
 
-/// Message service với generic repositories để dễ testing
+/// Số người nhận được gửi đồng thời khi broadcast
+const BROADCAST_CONCURRENCY: usize = 4;
+
+/// Thời hạn người gửi được xóa tin nhắn với mọi người
+const DELETE_FOR_EVERYONE_WINDOW: chrono::Duration = chrono::Duration::hours(48);
+
+/// Tách các `@username` trong nội dung (lowercase, không trùng lặp).
+/// `@` phải đứng đầu một từ để bỏ qua email
+fn parse_mentions(content: &str) -> Vec<String> {
+    let is_username_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '.' | '-');
+
+    let mut mentions: Vec<String> = Vec::new();
+    let words = content.split(|c: char| !is_username_char(c) && c != '@');
+
+    for word in words {
+        let Some(name) = word.strip_prefix('@') else {
+            continue;
+        };
+        let name = name.split('@').next().unwrap_or_default();
+        let username = name.trim_end_matches(['.', '-']).to_lowercase();
+
+        if !username.is_empty() && !mentions.contains(&username) {
+            mentions.push(username);
+            if mentions.len() == MAX_MENTIONS {
+                break;
+            }
+        }
+    }
+
+    mentions
+}
+
+/// Quyền nhắn tin trực tiếp giữa 2 users (bạn bè, không chặn nhau), implement bởi
+/// `FriendService`. Kiểm tra trong service để mọi transport (HTTP, WebSocket, scheduled)
+/// đều đi qua
+#[async_trait::async_trait]
+pub trait DirectMessagePolicy: Send + Sync {
+    /// Lỗi `BLOCKED` nếu một trong 2 người đã chặn người kia, `NOT_FRIENDS` nếu chưa là bạn
+    async fn check_direct_message(
+        &self,
+        sender_id: Uuid,
+        recipient_id: Uuid,
+    ) -> Result<(), error::SystemError>;
+}
+
+/// Dependencies của `MessageService`, gom lại thành struct vì số lượng lớn
+pub struct MessageServiceDeps {
+    pub conversation_repo: Arc<dyn ConversationRepository + Send + Sync>,
+    pub message_repo: Arc<dyn MessageRepository + Send + Sync>,
+    pub participant_repo: Arc<dyn ParticipantRepository + Send + Sync>,
+    pub last_message_repo: Arc<dyn LastMessageRepository + Send + Sync>,
+    pub uow: Arc<dyn UnitOfWork>,
+    pub cache: Arc<dyn CacheBackend>,
+    pub ws_server: Arc<Addr<WebSocketServer>>,
+    pub push_queue: PushQueue,
+    pub events: EventOutbox,
+    pub webhooks: WebhookQueue,
+    pub moderation: ContentFilter,
+    pub commands: Arc<CommandRegistry>,
+    pub senders: Arc<dyn SenderResolver>,
+    pub direct_policy: Arc<dyn DirectMessagePolicy>,
+    /// `None` khi chưa cấu hình translation provider
+    pub translator: Option<Arc<dyn Translator>>,
+}
+
+/// Message service giữ repositories dạng trait object để dễ testing
 #[derive(Clone)]
-pub struct MessageService<M, C, P, L>
-where
-    M: MessageRepository + Send + Sync,
-    C: ConversationRepository + Send + Sync,
-    P: ParticipantRepository + Send + Sync,
-    L: LastMessageRepository + Send + Sync,
-{
-    message_repo: Arc<M>,
-    conversation_repo: Arc<C>,
-    participant_repo: Arc<P>,
-    last_message_repo: Arc<L>,
-    cache: Arc<RedisCache>,
+pub struct MessageService {
+    message_repo: Arc<dyn MessageRepository + Send + Sync>,
+    conversation_repo: Arc<dyn ConversationRepository + Send + Sync>,
+    participant_repo: Arc<dyn ParticipantRepository + Send + Sync>,
+    last_message_repo: Arc<dyn LastMessageRepository + Send + Sync>,
+    uow: Arc<dyn UnitOfWork>,
+    cache: Arc<dyn CacheBackend>,
     ws_server: Arc<Addr<WebSocketServer>>,
+    push_queue: PushQueue,
+    events: EventOutbox,
+    webhooks: WebhookQueue,
+    moderation: ContentFilter,
+    commands: Arc<CommandRegistry>,
+    senders: Arc<dyn SenderResolver>,
+    direct_policy: Arc<dyn DirectMessagePolicy>,
+    /// `None` khi chưa cấu hình translation provider
+    translator: Option<Arc<dyn Translator>>,
 }
 
-impl<M, C, P, L> MessageService<M, C, P, L>
-where
-    C: ConversationRepository + Send + Sync,
-    M: MessageRepository + Send + Sync,
-    P: ParticipantRepository + Send + Sync,
-    L: LastMessageRepository + Send + Sync,
-{
+impl MessageService {
     /// Tạo MessageService với các dependencies
-    pub fn with_dependencies(
-        conversation_repo: Arc<C>,
-        message_repo: Arc<M>,
-        participant_repo: Arc<P>,
-        last_message_repo: Arc<L>,
-        cache: Arc<RedisCache>,
-        ws_server: Arc<Addr<WebSocketServer>>,
-    ) -> Self {
+    pub fn with_dependencies(deps: MessageServiceDeps) -> Self {
+        let MessageServiceDeps {
+            conversation_repo,
+            message_repo,
+            participant_repo,
+            last_message_repo,
+            uow,
+            cache,
+            ws_server,
+            push_queue,
+            events,
+            webhooks,
+            moderation,
+            commands,
+            senders,
+            direct_policy,
+            translator,
+        } = deps;
+
         MessageService {
             conversation_repo,
             message_repo,
             participant_repo,
             last_message_repo,
+            uow,
             cache,
             ws_server,
+            push_queue,
+            events,
+            webhooks,
+            moderation,
+            commands,
+            senders,
+            direct_policy,
+            translator,
         }
     }
 
+    /// Chặn slash command trong nội dung user gửi, gọi trước `send_*_message`.
+    /// `CommandOutcome::Send` mang nội dung cần gửi tiếp (nguyên văn nếu không phải lệnh)
+    pub async fn dispatch_command(
+        &self,
+        sender_id: Uuid,
+        conversation_id: Option<Uuid>,
+        content: String,
+    ) -> Result<CommandOutcome, error::SystemError> {
+        self.commands.dispatch(sender_id, conversation_id, content).await
+    }
+
     /// Gửi direct message giữa 2 users
     ///
-    /// Flow:
-    /// 1. Tìm hoặc tạo conversation
-    /// 2. Tạo message trong DB
-    /// 3. Increment unread count cho recipient
-    /// 4. Upsert last message
-    /// 5. Broadcast qua WebSocket
+    /// Không truyền `conversation_id` thì tìm (hoặc tạo) direct conversation giữa 2 users,
+    /// phần còn lại đi qua `send_message` như mọi tin nhắn khác
     pub async fn send_direct_message(
         &self,
         sender_id: Uuid,
         recipient_id: Uuid,
         content: String,
         conversation_id: Option<Uuid>,
+        client: Option<ClientMetadata>,
+        client_message_id: Option<Uuid>,
     ) -> Result<MessageEntity, error::SystemError> {
-        let mut tx = self.conversation_repo.get_pool().begin().await?;
+        let conversation_id = match conversation_id {
+            Some(conversation_id) => conversation_id,
+            None => {
+                // Kiểm tra trước để không tạo conversation với người không được nhắn tin
+                self.direct_policy.check_direct_message(sender_id, recipient_id).await?;
+                self.find_or_create_direct(sender_id, recipient_id).await?.id
+            }
+        };
+
+        self.send_message(sender_id, conversation_id, content, client, client_message_id).await
+    }
+
+    /// Gửi cùng một tin nhắn tới nhiều người nhận ("share to multiple chats")
+    ///
+    /// Mỗi người nhận được gửi qua `send_direct_message` (tạo direct conversation nếu chưa
+    /// có, kiểm tra quyền nhắn tin), tối đa `BROADCAST_CONCURRENCY` người cùng lúc. Lỗi với
+    /// một người nhận không dừng các người khác mà được trả về trong kết quả của người đó,
+    /// theo thứ tự `recipient_ids` (đã bỏ trùng)
+    pub async fn broadcast_message(
+        &self,
+        sender_id: Uuid,
+        recipient_ids: Vec<Uuid>,
+        content: String,
+    ) -> Vec<BroadcastResult> {
+        let mut unique_ids: Vec<Uuid> = Vec::with_capacity(recipient_ids.len());
+        for recipient_id in recipient_ids {
+            if !unique_ids.contains(&recipient_id) {
+                unique_ids.push(recipient_id);
+            }
+        }
+
+        stream::iter(unique_ids)
+            .map(|recipient_id| {
+                let content = content.clone();
+                async move {
+                    let sent = if recipient_id == sender_id {
+                        Err(error::SystemError::bad_request("Cannot send a message to yourself"))
+                    } else {
+                        self.send_direct_message(sender_id, recipient_id, content, None, None, None)
+                            .await
+                    };
+
+                    match sent {
+                        Ok(message) => {
+                            BroadcastResult { recipient_id, message: Some(message), error: None }
+                        }
+                        Err(e) => {
+                            BroadcastResult { recipient_id, message: None, error: Some(e.into()) }
+                        }
+                    }
+                }
+            })
+            .buffered(BROADCAST_CONCURRENCY)
+            .collect()
+            .await
+    }
+
+    /// Gửi tin nhắn mã hóa đầu cuối trong direct conversation
+    ///
+    /// Server không đọc được nội dung nên bỏ qua moderation, slash commands, mentions
+    /// và duplicate detection; last message và push notification không có nội dung.
+    /// Gửi lại với cùng `client_message_id` trả về message đã tạo, không tạo mới
+    pub async fn send_encrypted_message(
+        &self,
+        sender_id: Uuid,
+        recipient_id: Uuid,
+        envelope: EncryptedEnvelope,
+        conversation_id: Option<Uuid>,
+        client_message_id: Option<Uuid>,
+    ) -> Result<MessageEntity, error::SystemError> {
+        if let Some(message) = self.find_retried(sender_id, client_message_id).await? {
+            return Ok(message);
+        }
+
+        self.direct_policy.check_direct_message(sender_id, recipient_id).await?;
 
         let conversation = match conversation_id {
-            Some(conv_id) => self
-                .conversation_repo
-                .find_by_id(&conv_id, self.conversation_repo.get_pool())
-                .await?
-                .ok_or_else(|| error::SystemError::not_found("Conversation not found"))?,
-            None => self
-                .conversation_repo
-                .find_direct_between_users(&sender_id, &recipient_id, tx.as_mut())
-                .await?
-                .unwrap_or(
-                    self.conversation_repo
-                        .create_direct_conversation(&sender_id, &recipient_id, &mut tx)
-                        .await?,
-                ),
+            Some(conv_id) => {
+                let conversation = self
+                    .conversation_repo
+                    .find_by_id(&conv_id, &mut self.uow.autocommit().await?)
+                    .await?
+                    .ok_or_else(|| error::SystemError::not_found("Conversation not found"))?;
+                if conversation._type != ConversationType::Direct {
+                    return Err(error::SystemError::bad_request(
+                        "Encrypted messages are only supported in direct conversations",
+                    ));
+                }
+                self.ensure_member(&conv_id, &sender_id).await?;
+
+                let (_, recipient_is_member) = self
+                    .conversation_repo
+                    .get_conversation_and_check_membership(
+                        &conv_id,
+                        &recipient_id,
+                        &mut self.uow.autocommit().await?,
+                    )
+                    .await?;
+                if !recipient_is_member {
+                    return Err(error::SystemError::bad_request(
+                        "Recipient is not part of this conversation",
+                    ));
+                }
+                conversation
+            }
+            None => self.find_or_create_direct(sender_id, recipient_id).await?,
         };
 
-        let message = self
+        let mut tx = self.uow.begin().await?;
+
+        let message = match self
             .message_repo
-            .create(
-                &InsertMessage {
-                    conversation_id: conversation.id,
-                    sender_id,
-                    content: Some(content.clone()),
-                },
-                tx.as_mut(),
-            )
-            .await?;
+            .create_encrypted(&conversation.id, &sender_id, &envelope, client_message_id, &mut tx)
+            .await
+        {
+            Ok(message) => message,
+            Err(error::SystemError::Conflict(_)) if client_message_id.is_some() => {
+                return self.existing_retried(sender_id, client_message_id).await;
+            }
+            Err(e) => return Err(e),
+        };
+
+        self.participant_repo.restore_cleared(&conversation.id, &mut tx).await?;
 
         self.participant_repo
-            .increment_unread_count(&conversation.id, &recipient_id, tx.as_mut())
+            .increment_unread_count(&conversation.id, &recipient_id, &mut tx)
             .await?;
 
         self.last_message_repo
@@ -120,59 +349,175 @@ where
                 &NewLastMessage {
                     conversation_id: conversation.id,
                     sender_id,
-                    content: Some(content.clone()),
+                    content: None,
                     created_at: message.created_at,
                 },
-                tx.as_mut(),
+                &mut tx,
             )
             .await?;
 
-        self.conversation_repo.update_timestamp(&conversation.id, tx.as_mut()).await?;
-
-        // Get unread counts for all participants
-        let unread_counts = self
-            .participant_repo
-            .get_unread_counts(&conversation.id, tx.as_mut())
-            .await?;
+        self.conversation_repo.update_timestamp(&conversation.id, &mut tx).await?;
 
-        tx.commit().await?;
+        let unread_counts =
+            self.participant_repo.get_unread_counts(&conversation.id, &mut tx).await?;
 
-        // Build and broadcast new message
-        let server_message = self.build_new_message_event(&message, &unread_counts);
-        self.ws_server.do_send(BroadcastToRoom {
+        let event = FanoutEvent::BroadcastToRoom {
             conversation_id: conversation.id,
-            message: server_message,
+            message: self.build_new_message_event(&message, &unread_counts).await,
             skip_user_id: Some(sender_id),
-        });
+        };
+        self.events.enqueue(&event, tx.connection()?).await?;
+
+        tx.commit().await?;
+        self.events.wake();
+
+        self.enqueue_push(&message, &unread_counts, Vec::new());
 
         Ok(message)
     }
 
-    /// Gửi group message
+    /// Gửi tin nhắn vào conversation đã tồn tại (direct hoặc group), điểm vào chung
+    /// của HTTP và WebSocket
     ///
     /// Flow:
-    /// 1. Tạo message trong DB
-    /// 2. Increment unread count cho tất cả participants (trừ sender)
-    /// 3. Upsert last message
-    /// 4. Broadcast qua WebSocket
-    pub async fn send_group_message(
+    /// 1. Kiểm tra sender là thành viên của conversation, với direct thì kiểm tra thêm
+    ///    quyền nhắn tin cho người còn lại (bạn bè, không chặn nhau)
+    /// 2. Tạo message trong DB
+    /// 3. Increment unread count cho tất cả participants trừ sender (với direct chính là
+    ///    recipient)
+    /// 4. Upsert last message
+    /// 5. Broadcast `new-message` kèm unread count của từng participant
+    /// 6. Push notification cho participants đang offline
+    ///
+    /// Gửi lại với cùng `client_message_id` trả về message đã tạo, không tạo mới
+    pub async fn send_message(
+        &self,
+        sender_id: Uuid,
+        conversation_id: Uuid,
+        content: String,
+        client: Option<ClientMetadata>,
+        client_message_id: Option<Uuid>,
+    ) -> Result<MessageEntity, error::SystemError> {
+        let conversation = self.ensure_member(&conversation_id, &sender_id).await?;
+        if conversation._type == ConversationType::Direct {
+            self.ensure_direct_allowed(&conversation_id, sender_id).await?;
+        }
+
+        self.deliver(sender_id, content, conversation_id, client, client_message_id, None).await
+    }
+
+    /// Gửi group message thay mặt incoming webhook
+    ///
+    /// `sender_id` là người tạo webhook, tên / avatar hiển thị lấy từ `sender_override`.
+    /// Không áp dụng phát hiện nội dung lặp (integrations thường gửi thông báo giống nhau)
+    /// và người tạo webhook cũng nhận broadcast như các thành viên khác
+    pub async fn send_webhook_message(
         &self,
         sender_id: Uuid,
         content: String,
         conversation_id: Uuid,
+        sender_override: SenderOverride,
     ) -> Result<MessageEntity, error::SystemError> {
-        let mut tx = self.conversation_repo.get_pool().begin().await?;
+        self.deliver(sender_id, content, conversation_id, None, None, Some(sender_override)).await
+    }
 
-        let message = self
-            .message_repo
-            .create(
-                &InsertMessage { content: Some(content.clone()), conversation_id, sender_id },
-                tx.as_mut(),
+    /// Đăng system message (type `system`) vào conversation, ví dụ thông báo cuộc gọi nhóm
+    ///
+    /// Không tăng unread count, nhưng cập nhật last message và broadcast `new-message`
+    /// tới mọi thành viên đang mở conversation (kể cả `sender_id`)
+    pub async fn send_system_message(
+        &self,
+        sender_id: Uuid,
+        conversation_id: Uuid,
+        content: &str,
+    ) -> Result<MessageEntity, error::SystemError> {
+        let mut tx = self.uow.begin().await?;
+
+        let message =
+            self.message_repo.create_system(&conversation_id, &sender_id, content, &mut tx).await?;
+
+        self.last_message_repo
+            .upsert_last_message(
+                &NewLastMessage {
+                    conversation_id,
+                    sender_id,
+                    content: Some(content.to_string()),
+                    created_at: message.created_at,
+                },
+                &mut tx,
             )
             .await?;
 
+        self.conversation_repo.update_timestamp(&conversation_id, &mut tx).await?;
+
+        let unread_counts =
+            self.participant_repo.get_unread_counts(&conversation_id, &mut tx).await?;
+
+        let event = FanoutEvent::BroadcastToRoom {
+            conversation_id,
+            message: self.build_new_message_event(&message, &unread_counts).await,
+            skip_user_id: None,
+        };
+        self.events.enqueue(&event, tx.connection()?).await?;
+
+        tx.commit().await?;
+        self.events.wake();
+
+        Ok(message)
+    }
+
+    async fn deliver(
+        &self,
+        sender_id: Uuid,
+        content: String,
+        conversation_id: Uuid,
+        client: Option<ClientMetadata>,
+        client_message_id: Option<Uuid>,
+        sender_override: Option<SenderOverride>,
+    ) -> Result<MessageEntity, error::SystemError> {
+        if let Some(message) = self.find_retried(sender_id, client_message_id).await? {
+            return Ok(message);
+        }
+
+        let moderated = self.moderation.check(ContentKind::Message, content)?;
+        let content = moderated.content.clone();
+
+        let from_webhook = sender_override.is_some();
+        let repeat = if from_webhook {
+            1
+        } else {
+            match self.check_duplicate(conversation_id, sender_id, &content).await? {
+                DuplicateCheck::Fresh(repeat) => repeat,
+                DuplicateCheck::Collapsed(message) => return Ok(*message),
+            }
+        };
+
+        let mut tx = self.uow.begin().await?;
+
+        let insert = InsertMessage {
+            content: Some(content.clone()),
+            conversation_id,
+            sender_id,
+            forwarded_from: None,
+            client_message_id,
+            sender_override,
+        };
+        let message = match self.message_repo.create(&insert, &mut tx).await {
+            Ok(message) => message,
+            Err(error::SystemError::Conflict(_)) if client_message_id.is_some() => {
+                return self.existing_retried(sender_id, client_message_id).await;
+            }
+            Err(e) => return Err(e),
+        };
+
+        self.store_client_metadata(sender_id, Some(message.id), None, client, &mut tx).await?;
+
+        let mentioned_ids = self.store_mentions(&message, &mut tx).await?;
+
+        self.participant_repo.restore_cleared(&conversation_id, &mut tx).await?;
+
         self.participant_repo
-            .increment_unread_count_for_others(&conversation_id, &sender_id, tx.as_mut())
+            .increment_unread_count_for_others(&conversation_id, &sender_id, &mut tx)
             .await?;
 
         self.last_message_repo
@@ -180,70 +525,503 @@ where
                 &NewLastMessage {
                     conversation_id,
                     sender_id,
-                    content: Some(content),
+                    content: Some(content.clone()),
                     created_at: message.created_at,
                 },
-                tx.as_mut(),
+                &mut tx,
             )
             .await?;
 
-        self.conversation_repo.update_timestamp(&conversation_id, tx.as_mut()).await?;
+        self.conversation_repo.update_timestamp(&conversation_id, &mut tx).await?;
 
         // Get unread counts for all participants
         let unread_counts = self
             .participant_repo
-            .get_unread_counts(&conversation_id, tx.as_mut())
+            .get_unread_counts(&conversation_id, &mut tx)
             .await?;
 
+        // Broadcast được ghi vào outbox cùng transaction, route sau khi commit
+        let event = FanoutEvent::BroadcastToRoom {
+            conversation_id,
+            message: self.build_new_message_event(&message, &unread_counts).await,
+            skip_user_id: (!from_webhook).then_some(sender_id),
+        };
+        self.events.enqueue(&event, tx.connection()?).await?;
+        self.webhooks.enqueue(&message, tx.connection()?).await?;
+
         tx.commit().await?;
+        self.events.wake();
+        self.webhooks.wake();
 
-        // Build and broadcast new message
-        let server_message = self.build_new_message_event(&message, &unread_counts);
-        self.ws_server.do_send(BroadcastToRoom {
-            conversation_id,
-            message: server_message,
-            skip_user_id: Some(sender_id),
-        });
+        self.moderation
+            .flag(ReportTargetType::Message, message.id, ContentKind::Message, &moderated)
+            .await;
+
+        if !from_webhook {
+            self.track_duplicate(&message, &content, repeat).await?;
+        }
+
+        self.notify_mentions(&message, &mentioned_ids);
+        self.enqueue_push(&message, &unread_counts, mentioned_ids);
 
         Ok(message)
     }
 
-    /// Xóa message (soft delete)
+    /// Forward message sang các conversations khác
     ///
-    /// Chỉ sender mới có thể xóa message của mình
+    /// Caller phải là thành viên của conversation chứa message gốc và của từng
+    /// conversation đích. Mỗi bản copy (giữ `forwarded_from` trỏ về message gốc) được
+    /// tạo trong một transaction riêng rồi broadcast như message mới
+    pub async fn forward_message(
+        &self,
+        user_id: Uuid,
+        message_id: Uuid,
+        conversation_ids: Vec<Uuid>,
+    ) -> Result<Vec<MessageEntity>, error::SystemError> {
+        let source = self
+            .message_repo
+            .find_by_id(&message_id, &mut self.uow.autocommit().await?)
+            .await?
+            .filter(|message| message.hidden_at.is_none())
+            .ok_or_else(|| error::SystemError::not_found("Message not found"))?;
+
+        self.ensure_visible(&source, &user_id).await?;
+
+        // Ciphertext chỉ giải mã được trong session của conversation gốc
+        if source._type == MessageType::Encrypted {
+            return Err(error::SystemError::bad_request("Encrypted messages cannot be forwarded"));
+        }
+
+        let mut target_ids: Vec<Uuid> = Vec::with_capacity(conversation_ids.len());
+        for conversation_id in conversation_ids {
+            if !target_ids.contains(&conversation_id) {
+                self.ensure_member(&conversation_id, &user_id).await?;
+                target_ids.push(conversation_id);
+            }
+        }
+
+        let forwarded_from = source.forwarded_from.or(Some(source.id));
+        let mut forwarded = Vec::with_capacity(target_ids.len());
+
+        for conversation_id in target_ids {
+            let mut tx = self.uow.begin().await?;
+
+            let message = self
+                .message_repo
+                .create(
+                    &InsertMessage {
+                        conversation_id,
+                        sender_id: user_id,
+                        content: source.content.clone(),
+                        forwarded_from,
+                        client_message_id: None,
+                        sender_override: None,
+                    },
+                    &mut tx,
+                )
+                .await?;
+
+            self.participant_repo.restore_cleared(&conversation_id, &mut tx).await?;
+
+            self.participant_repo
+                .increment_unread_count_for_others(&conversation_id, &user_id, &mut tx)
+                .await?;
+
+            self.last_message_repo
+                .upsert_last_message(
+                    &NewLastMessage {
+                        conversation_id,
+                        sender_id: user_id,
+                        content: message.content.clone(),
+                        created_at: message.created_at,
+                    },
+                    &mut tx,
+                )
+                .await?;
+
+            self.conversation_repo.update_timestamp(&conversation_id, &mut tx).await?;
+
+            let unread_counts =
+                self.participant_repo.get_unread_counts(&conversation_id, &mut tx).await?;
+
+            let event = FanoutEvent::BroadcastToRoom {
+                conversation_id,
+                message: self.build_new_message_event(&message, &unread_counts).await,
+                skip_user_id: Some(user_id),
+            };
+            self.events.enqueue(&event, tx.connection()?).await?;
+            self.webhooks.enqueue(&message, tx.connection()?).await?;
+
+            tx.commit().await?;
+            self.events.wake();
+            self.webhooks.wake();
+
+            self.enqueue_push(&message, &unread_counts, vec![]);
+
+            forwarded.push(message);
+        }
+
+        Ok(forwarded)
+    }
+
+    /// Dịch nội dung message sang `lang` cho thành viên của conversation, message gốc giữ
+    /// nguyên. Bản dịch được cache theo (message, lang) tới khi message bị sửa
+    pub async fn translate_message(
+        &self,
+        user_id: Uuid,
+        message_id: Uuid,
+        lang: &str,
+    ) -> Result<TranslatedMessage, error::SystemError> {
+        let Some(translator) = &self.translator else {
+            return Err(error::SystemError::not_found("Message translation is not configured"));
+        };
+
+        let lang = lang.trim().to_lowercase();
+        if lang.len() < 2 || !lang.chars().all(|c| c.is_ascii_alphabetic() || c == '-') {
+            return Err(error::SystemError::bad_request("Invalid language code"));
+        }
+
+        let message = self
+            .message_repo
+            .find_by_id(&message_id, &mut self.uow.autocommit().await?)
+            .await?
+            .filter(|message| message.hidden_at.is_none() && message.deleted_at.is_none())
+            .ok_or_else(|| error::SystemError::not_found("Message not found"))?;
+
+        self.ensure_visible(&message, &user_id).await?;
+
+        // Server không đọc được nội dung tin nhắn mã hóa
+        let content = match (&message._type, &message.content) {
+            (MessageType::Encrypted, _) => {
+                return Err(error::SystemError::bad_request(
+                    "Encrypted messages cannot be translated",
+                ));
+            }
+            (_, Some(content)) if !content.trim().is_empty() => content,
+            _ => return Err(error::SystemError::bad_request("Message has no text to translate")),
+        };
+
+        let key = format!("translation:{message_id}:{lang}");
+        let cached = self.cache.get::<CachedTranslation>(&key).await.unwrap_or_else(|e| {
+            tracing::warn!("Failed to read cached translation {}: {:?}", key, e);
+            None
+        });
+        let translation = match cached.filter(|cached| cached.updated_at == message.updated_at) {
+            Some(cached) => cached.translation,
+            None => {
+                let translation = translator.translate(content, &lang).await?;
+                let cached = CachedTranslation { updated_at: message.updated_at, translation };
+                if let Err(e) = self.cache.set(&key, &cached, TRANSLATION_CACHE_TTL).await {
+                    tracing::warn!("Failed to cache translation {}: {:?}", key, e);
+                }
+                cached.translation
+            }
+        };
+
+        Ok(TranslatedMessage {
+            message_id,
+            lang,
+            text: translation.text,
+            source_lang: translation.source_lang,
+        })
+    }
+
+    /// Hẹn giờ gửi tin nhắn vào conversation, caller phải là thành viên
+    pub async fn schedule_message(
+        &self,
+        sender_id: Uuid,
+        conversation_id: Uuid,
+        content: String,
+        scheduled_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<ScheduledMessageEntity, error::SystemError> {
+        if scheduled_at <= chrono::Utc::now() {
+            return Err(error::SystemError::bad_request("Scheduled time must be in the future"));
+        }
+
+        self.ensure_member(&conversation_id, &sender_id).await?;
+
+        let moderated = self.moderation.check(ContentKind::Message, content)?;
+
+        let scheduled = self
+            .message_repo
+            .create_scheduled(&InsertScheduledMessage {
+                conversation_id,
+                sender_id,
+                content: moderated.content.clone(),
+                scheduled_at,
+            })
+            .await?;
+
+        // Tin nhắn chưa tồn tại cho tới khi được gửi, report gắn với sender
+        self.moderation
+            .flag(ReportTargetType::User, sender_id, ContentKind::Message, &moderated)
+            .await;
+
+        Ok(scheduled)
+    }
+
+    /// Tin nhắn hẹn giờ đang chờ gửi của caller trong conversation
+    pub async fn get_scheduled_messages(
+        &self,
+        user_id: Uuid,
+        conversation_id: Uuid,
+    ) -> Result<Vec<ScheduledMessageEntity>, error::SystemError> {
+        self.ensure_member(&conversation_id, &user_id).await?;
+
+        self.message_repo.find_pending_scheduled(&conversation_id, &user_id).await
+    }
+
+    /// Hủy tin nhắn hẹn giờ chưa được gửi, chỉ sender mới có thể hủy
+    pub async fn cancel_scheduled_message(
+        &self,
+        user_id: Uuid,
+        scheduled_id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        self.message_repo
+            .cancel_scheduled(&scheduled_id, &user_id)
+            .await?
+            .ok_or_else(|| error::SystemError::not_found("Scheduled message not found"))?;
+
+        Ok(())
+    }
+
+    /// Xóa vĩnh viễn tối đa `limit` messages cũ hơn retention policy (admin cấu hình trong
+    /// conversation defaults), trả về số messages đã xóa. Không có policy thì giữ mãi
+    pub async fn prune_expired_messages(&self, limit: i64) -> Result<u64, error::SystemError> {
+        let defaults =
+            self.conversation_repo.get_defaults(&mut self.uow.autocommit().await?).await?;
+        let Some(days) = defaults.message_retention_days else {
+            return Ok(0);
+        };
+
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(days as i64);
+        self.message_repo.delete_older_than(cutoff, limit).await
+    }
+
+    /// Thời điểm mà messages tạo trước đó được chuyển sang archive
+    /// (`MESSAGE_ARCHIVE_AFTER_MONTHS`), `None` nếu không bật archive
+    fn archive_cutoff() -> Option<chrono::DateTime<chrono::Utc>> {
+        if ENV.message_archive_after_months == 0 {
+            return None;
+        }
+        chrono::Utc::now().checked_sub_months(chrono::Months::new(ENV.message_archive_after_months))
+    }
+
+    /// Tạo trước partitions archive cho các tháng sắp được archive
+    pub async fn create_archive_partitions(&self) -> Result<(), error::SystemError> {
+        let Some(cutoff) = Self::archive_cutoff() else {
+            return Ok(());
+        };
+        self.message_repo.create_archive_partitions(cutoff).await
+    }
+
+    /// Chuyển tối đa `limit` messages cũ nhất quá `MESSAGE_ARCHIVE_AFTER_MONTHS` sang archive,
+    /// trả về số messages đã chuyển. Messages đã archive vẫn xem được qua lịch sử / export
+    /// nhưng không sửa, xóa, reply hay forward được nữa
+    pub async fn archive_old_messages(&self, limit: i64) -> Result<u64, error::SystemError> {
+        let Some(cutoff) = Self::archive_cutoff() else {
+            return Ok(0);
+        };
+        self.message_repo.archive_older_than(cutoff, limit).await
+    }
+
+    /// Gửi các tin nhắn hẹn giờ đã tới giờ (tối đa `limit`), trả về số tin đã xử lý.
+    ///
+    /// Mỗi tin được gửi như message thường của sender (persist, unread counts,
+    /// broadcast, push). Sender không còn là thành viên thì tin chuyển sang `failed`
+    pub async fn deliver_due_scheduled_messages(
+        &self,
+        limit: i64,
+    ) -> Result<usize, error::SystemError> {
+        let due = self.message_repo.claim_due_scheduled(limit).await?;
+
+        for scheduled in &due {
+            let result = match self.deliver_scheduled(scheduled).await {
+                Ok(message) => Ok(message.id),
+                Err(e) => {
+                    tracing::warn!("Failed to deliver scheduled message {}: {}", scheduled.id, e);
+                    Err(e.to_string())
+                }
+            };
+
+            self.message_repo.complete_scheduled(&scheduled.id, result).await?;
+        }
+
+        Ok(due.len())
+    }
+
+    async fn deliver_scheduled(
+        &self,
+        scheduled: &ScheduledMessageEntity,
+    ) -> Result<MessageEntity, error::SystemError> {
+        self.send_message(
+            scheduled.sender_id,
+            scheduled.conversation_id,
+            scheduled.content.clone(),
+            None,
+            None,
+        )
+        .await
+    }
+
+    /// Direct conversation giữa 2 users, tạo mới nếu chưa có
+    async fn find_or_create_direct(
+        &self,
+        sender_id: Uuid,
+        recipient_id: Uuid,
+    ) -> Result<ConversationEntity, error::SystemError> {
+        let mut tx = self.uow.begin().await?;
+
+        let conversation = match self
+            .conversation_repo
+            .find_direct_between_users(&sender_id, &recipient_id, &mut tx)
+            .await?
+        {
+            Some(conversation) => conversation,
+            None => {
+                self.conversation_repo
+                    .create_direct_conversation(&sender_id, &recipient_id, &mut tx)
+                    .await?
+            }
+        };
+
+        tx.commit().await?;
+
+        Ok(conversation)
+    }
+
+    /// Message đã được tạo trước đó với cùng idempotency key (client retry)
+    async fn find_retried(
+        &self,
+        sender_id: Uuid,
+        client_message_id: Option<Uuid>,
+    ) -> Result<Option<MessageEntity>, error::SystemError> {
+        let Some(client_message_id) = client_message_id else {
+            return Ok(None);
+        };
+
+        self.message_repo.find_by_client_message_id(&sender_id, &client_message_id).await
+    }
+
+    /// Insert bị trùng idempotency key: request retry chạy song song đã tạo message
+    async fn existing_retried(
+        &self,
+        sender_id: Uuid,
+        client_message_id: Option<Uuid>,
+    ) -> Result<MessageEntity, error::SystemError> {
+        self.find_retried(sender_id, client_message_id)
+            .await?
+            .ok_or_else(|| error::SystemError::internal_error("Idempotent message not found"))
+    }
+
+    /// Recipient xác nhận đã nhận message: tiến delivered watermark của participant
+    /// và báo cho sender (double tick). Ack cho message cũ hơn watermark bị bỏ qua
+    pub async fn acknowledge_delivery(
+        &self,
+        user_id: Uuid,
+        message_id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        let Some(delivered) =
+            self.participant_repo.mark_as_delivered(&message_id, &user_id).await?
+        else {
+            return Ok(());
+        };
+
+        self.ws_server.do_send(SendToUser {
+            user_id: delivered.sender_id,
+            message: ServerMessage::MessageDelivered {
+                conversation_id: delivered.conversation_id,
+                message_id,
+                user_id,
+                delivered_at: delivered.delivered_at.to_rfc3339(),
+            },
+        });
+
+        Ok(())
+    }
+
+    /// Xóa message theo `mode`:
+    /// - `Everyone`: soft delete, mọi thành viên thấy tombstone. Chỉ sender, trong
+    ///   `DELETE_FOR_EVERYONE_WINDOW` kể từ lúc gửi
+    /// - `Me`: chỉ ẩn phía user, bất kỳ thành viên nào (kể cả với tombstone)
     pub async fn delete_message(
         &self,
         message_id: Uuid,
         user_id: Uuid,
+        mode: DeleteMode,
+    ) -> Result<(), error::SystemError> {
+        match mode {
+            DeleteMode::Everyone => self.delete_for_everyone(message_id, user_id).await,
+            DeleteMode::Me => self.delete_for_me(message_id, user_id).await,
+        }
+    }
+
+    async fn delete_for_me(
+        &self,
+        message_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        let mut tx = self.uow.begin().await?;
+
+        let conversation_id = self
+            .message_repo
+            .hide_for_user(&message_id, &user_id, &mut tx)
+            .await?
+            .ok_or_else(|| error::SystemError::not_found("Message not found"))?;
+
+        // Đồng bộ các thiết bị khác của user
+        let event = FanoutEvent::SendToUsers {
+            user_ids: vec![user_id],
+            message: ServerMessage::MessageDeletedForMe { conversation_id, message_id },
+        };
+        self.events.enqueue(&event, tx.connection()?).await?;
+
+        tx.commit().await?;
+        self.events.wake();
+
+        Ok(())
+    }
+
+    async fn delete_for_everyone(
+        &self,
+        message_id: Uuid,
+        user_id: Uuid,
     ) -> Result<(), error::SystemError> {
-        let mut tx = self.conversation_repo.get_pool().begin().await?;
+        let mut tx = self.uow.begin().await?;
 
         let message = self
             .message_repo
-            .find_by_id(&message_id, tx.as_mut())
+            .find_by_id(&message_id, &mut tx)
             .await?
             .ok_or_else(|| error::SystemError::not_found("Message not found"))?;
 
         if message.sender_id != user_id {
             return Err(error::SystemError::forbidden("You can only delete your own messages"));
         }
+        if chrono::Utc::now() - message.created_at > DELETE_FOR_EVERYONE_WINDOW {
+            return Err(error::SystemError::bad_request(
+                "Messages can only be deleted for everyone within 48 hours of sending",
+            ));
+        }
 
-        let deleted = self.message_repo.delete_message(&message_id, &user_id, tx.as_mut()).await?;
+        let deleted = self.message_repo.delete_message(&message_id, &user_id, &mut tx).await?;
 
         if !deleted {
             return Err(error::SystemError::not_found("Message not found or already deleted"));
         }
 
-        tx.commit().await?;
-
-        self.ws_server.do_send(BroadcastToRoom {
+        let event = FanoutEvent::BroadcastToRoom {
             conversation_id: message.conversation_id,
             message: ServerMessage::MessageDeleted {
                 conversation_id: message.conversation_id,
                 message_id,
             },
             skip_user_id: None,
-        });
+        };
+        self.events.enqueue(&event, tx.connection()?).await?;
+
+        tx.commit().await?;
+        self.events.wake();
 
         Ok(())
     }
@@ -257,27 +1035,31 @@ where
         user_id: Uuid,
         new_content: String,
     ) -> Result<MessageEntity, error::SystemError> {
-        let mut tx = self.conversation_repo.get_pool().begin().await?;
+        let moderated = self.moderation.check(ContentKind::Message, new_content)?;
+        let new_content = moderated.content.clone();
+
+        let mut tx = self.uow.begin().await?;
 
         let message = self
             .message_repo
-            .find_by_id(&message_id, tx.as_mut())
+            .find_by_id(&message_id, &mut tx)
             .await?
             .ok_or_else(|| error::SystemError::not_found("Message not found"))?;
 
         if message.sender_id != user_id {
             return Err(error::SystemError::forbidden("You can only edit your own messages"));
         }
+        if message._type == MessageType::Encrypted {
+            return Err(error::SystemError::bad_request("Encrypted messages cannot be edited"));
+        }
 
         let edited_message = self
             .message_repo
-            .edit_message(&message_id, &user_id, &new_content, tx.as_mut())
+            .edit_message(&message_id, &user_id, &new_content, &mut tx)
             .await?
             .ok_or_else(|| error::SystemError::not_found("Message not found"))?;
 
-        tx.commit().await?;
-
-        self.ws_server.do_send(BroadcastToRoom {
+        let event = FanoutEvent::BroadcastToRoom {
             conversation_id: message.conversation_id,
             message: ServerMessage::MessageEdited {
                 conversation_id: message.conversation_id,
@@ -285,13 +1067,310 @@ where
                 new_content,
             },
             skip_user_id: None,
-        });
+        };
+        self.events.enqueue(&event, tx.connection()?).await?;
+
+        tx.commit().await?;
+        self.events.wake();
+
+        self.moderation
+            .flag(ReportTargetType::Message, message_id, ContentKind::Message, &moderated)
+            .await;
 
         Ok(edited_message)
     }
 
+    /// Helper: kiểm tra conversation tồn tại và user là thành viên
+    async fn ensure_member(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<ConversationEntity, error::SystemError> {
+        let (conversation, is_member) = self
+            .conversation_repo
+            .get_conversation_and_check_membership(
+                conversation_id,
+                user_id,
+                &mut self.uow.autocommit().await?,
+            )
+            .await?;
+
+        let conversation =
+            conversation.ok_or_else(|| error::SystemError::not_found("Conversation not found"))?;
+        if !is_member {
+            return Err(error::SystemError::forbidden("You are not a member of this conversation")
+                .with_code(error::ErrorCode::NotAMember));
+        }
+
+        Ok(conversation)
+    }
+
+    /// Helper: user là member và message nằm trong phần lịch sử user được xem
+    /// (`conversation::service::visible_since`), ngoài phạm vi đó coi như không tồn tại
+    async fn ensure_visible(
+        &self,
+        message: &MessageEntity,
+        user_id: &Uuid,
+    ) -> Result<(), error::SystemError> {
+        let conversation = self.ensure_member(&message.conversation_id, user_id).await?;
+
+        let participant = self
+            .participant_repo
+            .find_participant(&message.conversation_id, user_id)
+            .await?
+            .ok_or_else(|| {
+                error::SystemError::forbidden("You are not a member of this conversation")
+                    .with_code(error::ErrorCode::NotAMember)
+            })?;
+
+        let since = visible_since(&conversation, &participant);
+        if since.is_some_and(|since| message.created_at < since) {
+            return Err(error::SystemError::not_found("Message not found"));
+        }
+
+        Ok(())
+    }
+
+    /// Helper: sender được nhắn tin cho người còn lại của direct conversation
+    async fn ensure_direct_allowed(
+        &self,
+        conversation_id: &Uuid,
+        sender_id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        let participants = self
+            .participant_repo
+            .find_participants_by_conversation_id(
+                &[*conversation_id],
+                &mut self.uow.autocommit().await?,
+            )
+            .await?;
+
+        match participants.iter().find(|p| p.user_id != sender_id) {
+            Some(recipient) => {
+                self.direct_policy.check_direct_message(sender_id, recipient.user_id).await
+            }
+            // Người còn lại đã xóa tài khoản, tin nhắn vẫn được lưu như trước
+            None => Ok(()),
+        }
+    }
+
+    /// Phát hiện copy-paste spam: cùng sender gửi cùng nội dung vào cùng conversation
+    /// nhiều lần trong cửa sổ DUPLICATE_WINDOW. Khi vượt soft limit, áp dụng
+    /// duplicate_policy của conversation (allow / collapse / reject).
+    async fn check_duplicate(
+        &self,
+        conversation_id: Uuid,
+        sender_id: Uuid,
+        content: &str,
+    ) -> Result<DuplicateCheck, error::SystemError> {
+        let key = format!("duplicate:{conversation_id}:{sender_id}");
+        let hash = content_hash(content);
+
+        let tracker = match self.cache.get::<DuplicateTracker>(&key).await? {
+            Some(tracker) if tracker.hash == hash => tracker,
+            _ => return Ok(DuplicateCheck::Fresh(1)),
+        };
+
+        let repeat = tracker.count + 1;
+        let settings = settings();
+
+        if repeat <= settings.duplicate_soft_limit {
+            if repeat == settings.duplicate_soft_limit {
+                tracing::warn!(
+                    "User {} reached duplicate soft limit ({}) in conversation {}",
+                    sender_id,
+                    repeat,
+                    conversation_id
+                );
+            }
+            return Ok(DuplicateCheck::Fresh(repeat));
+        }
+
+        let conversation = self
+            .conversation_repo
+            .find_by_id(&conversation_id, &mut self.uow.autocommit().await?)
+            .await?
+            .ok_or_else(|| error::SystemError::not_found("Conversation not found"))?;
+
+        match conversation.duplicate_policy {
+            DuplicatePolicy::Allow => Ok(DuplicateCheck::Fresh(repeat)),
+            DuplicatePolicy::Reject => Err(error::SystemError::too_many_requests(
+                "Duplicate message: identical content was sent too many times",
+            )),
+            DuplicatePolicy::Collapse => {
+                let Some(message) =
+                    self.message_repo.increment_repeat_count(&tracker.message_id).await?
+                else {
+                    // Message gốc đã bị xóa, coi như message mới
+                    return Ok(DuplicateCheck::Fresh(1));
+                };
+
+                self.cache
+                    .set(
+                        &key,
+                        &DuplicateTracker { hash, count: repeat, message_id: message.id },
+                        settings.duplicate_window,
+                    )
+                    .await?;
+
+                self.ws_server.do_send(BroadcastToRoom {
+                    conversation_id,
+                    message: ServerMessage::MessageRepeated {
+                        conversation_id,
+                        message_id: message.id,
+                        repeat_count: message.repeat_count,
+                    },
+                    skip_user_id: None,
+                });
+
+                Ok(DuplicateCheck::Collapsed(Box::new(message)))
+            }
+        }
+    }
+
+    /// Ghi nhận message vừa gửi để phát hiện nội dung lặp lại ở lần gửi sau
+    /// Ghi nhận client metadata gửi kèm khi WebSocket session authenticate
+    pub async fn record_session_metadata(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        client: ClientMetadata,
+    ) -> Result<(), error::SystemError> {
+        self.store_client_metadata(
+            user_id,
+            None,
+            Some(session_id),
+            Some(client),
+            &mut self.uow.autocommit().await?,
+        )
+        .await
+    }
+
+    /// Admin: client metadata của một message
+    pub async fn get_client_metadata_by_message(
+        &self,
+        message_id: Uuid,
+    ) -> Result<Vec<ClientMetadataEntity>, error::SystemError> {
+        self.message_repo.find_client_metadata_by_message(&message_id).await
+    }
+
+    /// Admin: client metadata gần nhất của một user (message + WS session)
+    pub async fn get_client_metadata_by_user(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<ClientMetadataEntity>, error::SystemError> {
+        self.message_repo.find_client_metadata_by_user(&user_id, limit).await
+    }
+
+    /// Lưu client metadata (nếu client có gửi) vào bảng client_metadata
+    async fn store_client_metadata(
+        &self,
+        user_id: Uuid,
+        message_id: Option<Uuid>,
+        session_id: Option<Uuid>,
+        client: Option<ClientMetadata>,
+        tx: &mut Transaction,
+    ) -> Result<(), error::SystemError> {
+        let Some(client) = client.filter(|c| !c.is_empty()) else {
+            return Ok(());
+        };
+
+        self.message_repo
+            .insert_client_metadata(
+                &InsertClientMetadata {
+                    user_id,
+                    message_id,
+                    session_id,
+                    app_version: client.app_version,
+                    platform: client.platform,
+                },
+                tx,
+            )
+            .await
+    }
+
+    async fn track_duplicate(
+        &self,
+        message: &MessageEntity,
+        content: &str,
+        repeat: u32,
+    ) -> Result<(), error::SystemError> {
+        let key = format!("duplicate:{}:{}", message.conversation_id, message.sender_id);
+        let tracker =
+            DuplicateTracker { hash: content_hash(content), count: repeat, message_id: message.id };
+        self.cache.set(&key, &tracker, settings().duplicate_window).await
+    }
+
+    /// Helper: parse và lưu mentions của message mới
+    async fn store_mentions(
+        &self,
+        message: &MessageEntity,
+        tx: &mut Transaction,
+    ) -> Result<Vec<Uuid>, error::SystemError> {
+        let usernames = message.content.as_deref().map(parse_mentions).unwrap_or_default();
+        if usernames.is_empty() {
+            return Ok(vec![]);
+        }
+
+        self.message_repo.insert_mentions(message, &usernames, tx).await
+    }
+
+    /// Helper: gửi event `mentioned` riêng cho những người được mention
+    fn notify_mentions(&self, message: &MessageEntity, mentioned_ids: &[Uuid]) {
+        if mentioned_ids.is_empty() {
+            return;
+        }
+
+        self.ws_server.do_send(SendToUsers {
+            user_ids: mentioned_ids.to_vec(),
+            message: ServerMessage::Mentioned {
+                conversation_id: message.conversation_id,
+                message_id: message.id,
+                mentioned_by: message.sender_id,
+            },
+        });
+    }
+
+    /// Helper: đưa push notification của message mới vào hàng đợi,
+    /// `unread_counts` chứa tất cả participants active của conversation
+    fn enqueue_push(
+        &self,
+        message: &MessageEntity,
+        unread_counts: &HashMap<Uuid, i32>,
+        mentioned_ids: Vec<Uuid>,
+    ) {
+        self.push_queue.enqueue(PushJob {
+            conversation_id: message.conversation_id,
+            message_id: message.id,
+            sender_id: message.sender_id,
+            recipient_ids: unread_counts.keys().copied().collect(),
+            mentioned_ids,
+            content: message.content.clone(),
+        });
+    }
+
+    /// Thông tin sender của message cho các events gửi qua WebSocket
+    pub async fn sender_info(&self, message: &MessageEntity) -> SenderInfo {
+        self.senders.resolve_message_sender(message).await
+    }
+
+    /// `new-message` event của message đã gửi, kèm unread counts hiện tại. Dùng để xác
+    /// nhận với session WebSocket đã gửi (broadcast của `send_message` bỏ qua sender)
+    pub async fn new_message_event(
+        &self,
+        message: &MessageEntity,
+    ) -> Result<ServerMessage, error::SystemError> {
+        let unread_counts = self
+            .participant_repo
+            .get_unread_counts(&message.conversation_id, &mut self.uow.autocommit().await?)
+            .await?;
+
+        Ok(self.build_new_message_event(message, &unread_counts).await)
+    }
+
     /// Helper: Build new-message event với format tương thích Socket.IO
-    fn build_new_message_event(
+    async fn build_new_message_event(
         &self,
         message: &MessageEntity,
         unread_counts: &HashMap<Uuid, i32>,
@@ -302,11 +1381,7 @@ where
             _id: message.id,
             content: message.content.clone(),
             created_at: message.created_at.to_rfc3339(),
-            sender: SenderInfo {
-                _id: message.sender_id,
-                display_name: String::new(), // Will be filled by frontend from cache
-                avatar_url: None,
-            },
+            sender: self.sender_info(message).await,
         };
 
         // Convert HashMap<Uuid, i32> to JSON object with string keys
@@ -324,3 +1399,160 @@ where
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use actix::Actor;
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+    use super::*;
+    use crate::{
+        configs::{cache::MemoryCache, uow::MemoryUnitOfWork},
+        constants::init_test_env,
+        modules::{
+            conversation::repository_mock::ConversationRepositoryMock,
+            message::{repository_mock::MessageRepositoryMock, translation::Translation},
+            report::repository_pg::ReportRepositoryPg,
+            user::repository_mock::UserRepositoryMock,
+        },
+    };
+
+    struct UnknownSenders;
+
+    #[async_trait::async_trait]
+    impl SenderResolver for UnknownSenders {
+        async fn resolve_sender(&self, user_id: Uuid) -> SenderInfo {
+            SenderInfo::unknown(user_id)
+        }
+    }
+
+    struct AllowDirect;
+
+    #[async_trait::async_trait]
+    impl DirectMessagePolicy for AllowDirect {
+        async fn check_direct_message(&self, _: Uuid, _: Uuid) -> Result<(), error::SystemError> {
+            Ok(())
+        }
+    }
+
+    /// "Dịch" bằng cách viết hoa, để test thấy kết quả đến từ translator
+    struct UppercaseTranslator;
+
+    #[async_trait::async_trait]
+    impl Translator for UppercaseTranslator {
+        async fn translate(
+            &self,
+            text: &str,
+            _target_lang: &str,
+        ) -> Result<Translation, error::SystemError> {
+            Ok(Translation { text: text.to_uppercase(), source_lang: Some("en".to_string()) })
+        }
+    }
+
+    struct Fixture {
+        users: UserRepositoryMock,
+        conversations: ConversationRepositoryMock,
+        messages: MessageRepositoryMock,
+        service: MessageService,
+    }
+
+    fn fixture() -> Fixture {
+        init_test_env();
+        let users = UserRepositoryMock::default();
+        let conversations = ConversationRepositoryMock::new(users.clone());
+        let messages = MessageRepositoryMock::new(conversations.clone());
+        // Report repository chỉ được dùng khi nội dung bị flag, pool không bao giờ kết nối
+        let reports = ReportRepositoryPg::new(
+            PgPoolOptions::new().connect_lazy_with(PgConnectOptions::new()),
+        );
+
+        let service = MessageService::with_dependencies(MessageServiceDeps {
+            conversation_repo: Arc::new(conversations.clone()),
+            message_repo: Arc::new(messages.clone()),
+            participant_repo: Arc::new(conversations.clone()),
+            last_message_repo: Arc::new(conversations.clone()),
+            uow: Arc::new(MemoryUnitOfWork),
+            cache: Arc::new(MemoryCache::default()),
+            ws_server: Arc::new(WebSocketServer::new().start()),
+            push_queue: PushQueue::new().0,
+            events: EventOutbox::new(),
+            webhooks: WebhookQueue::new(),
+            moderation: ContentFilter::from_env(Arc::new(reports)),
+            commands: Arc::new(CommandRegistry::with_builtin()),
+            senders: Arc::new(UnknownSenders),
+            direct_policy: Arc::new(AllowDirect),
+            translator: Some(Arc::new(UppercaseTranslator)),
+        });
+        Fixture { users, conversations, messages, service }
+    }
+
+    fn user(users: &UserRepositoryMock, username: &str) -> Uuid {
+        users.insert(UserRepositoryMock::entity(username, "unused-hash"))
+    }
+
+    async fn group(conversations: &ConversationRepositoryMock, members: &[Uuid]) -> Uuid {
+        let mut tx = MemoryUnitOfWork.begin().await.unwrap();
+        conversations
+            .create_group_conversation("Team", members, &members[0], &mut tx)
+            .await
+            .unwrap()
+            .id
+    }
+
+    async fn send(
+        messages: &MessageRepositoryMock,
+        conversation_id: Uuid,
+        sender_id: Uuid,
+    ) -> Uuid {
+        let message = InsertMessage {
+            conversation_id,
+            sender_id,
+            content: Some("hello".to_string()),
+            forwarded_from: None,
+            client_message_id: None,
+            sender_override: None,
+        };
+        let mut tx = MemoryUnitOfWork.begin().await.unwrap();
+        messages.create(&message, &mut tx).await.unwrap().id
+    }
+
+    #[actix_web::test]
+    async fn translate_message_respects_viewer_history_cutoff() {
+        let Fixture { users, conversations, messages, service } = fixture();
+        let alice = user(&users, "alice");
+        let bob = user(&users, "bob");
+        let mallory = user(&users, "mallory");
+        let conversation_id = group(&conversations, &[alice, bob]).await;
+
+        let before = send(&messages, conversation_id, alice).await;
+        conversations.clear_for_user(&conversation_id, &bob).await.unwrap();
+        let after = send(&messages, conversation_id, alice).await;
+
+        let err = service.translate_message(bob, before, "vi").await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotFound);
+        let err = service.translate_message(mallory, after, "vi").await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotAMember);
+
+        let translated = service.translate_message(bob, after, "vi").await.unwrap();
+        assert_eq!(translated.text, "HELLO");
+        let translated = service.translate_message(alice, before, "vi").await.unwrap();
+        assert_eq!(translated.text, "HELLO");
+    }
+
+    #[actix_web::test]
+    async fn forward_message_rejects_message_before_viewer_history_cutoff() {
+        let Fixture { users, conversations, messages, service } = fixture();
+        let alice = user(&users, "alice");
+        let bob = user(&users, "bob");
+        let source_id = group(&conversations, &[alice, bob]).await;
+        let target_id = group(&conversations, &[bob]).await;
+
+        let before = send(&messages, source_id, alice).await;
+        conversations.clear_for_user(&source_id, &bob).await.unwrap();
+
+        let err = service.forward_message(bob, before, vec![target_id]).await.unwrap_err();
+
+        assert_eq!(err.code(), error::ErrorCode::NotFound);
+        assert!(conversations.messages_where(|m| m.conversation_id == target_id).is_empty());
+    }
+}
@@ -0,0 +1,263 @@
+/// Message Translation
+///
+/// `MessageService::translate_message` dịch nội dung tin nhắn qua `Translator` được chọn
+/// bằng `TRANSLATION_PROVIDER`:
+/// - `deepl`: DeepL API v2, key free (đuôi `:fx`) tự dùng `api-free.deepl.com`
+/// - `google`: Google Cloud Translation v2 (API key)
+/// - `libretranslate`: LibreTranslate, mặc định `libretranslate.com`, thường tự host qua
+///   `TRANSLATION_API_URL`
+///
+/// Bản dịch được cache trong Redis theo (message, ngôn ngữ), message gốc không bị sửa.
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{api::error, ENV};
+
+/// Thời gian giữ bản dịch trong cache (giây)
+pub const TRANSLATION_CACHE_TTL: usize = 7 * 24 * 60 * 60;
+
+/// Kết quả dịch của provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Translation {
+    pub text: String,
+    /// Ngôn ngữ gốc provider nhận diện được (nếu có)
+    pub source_lang: Option<String>,
+}
+
+#[async_trait::async_trait]
+pub trait Translator: Send + Sync {
+    /// Dịch `text` sang `target_lang` (mã ngôn ngữ viết thường, vd. `en`, `pt-br`)
+    async fn translate(
+        &self,
+        text: &str,
+        target_lang: &str,
+    ) -> Result<Translation, error::SystemError>;
+}
+
+/// Translator theo cấu hình, `None` khi tắt dịch hoặc provider thiếu credentials
+pub fn translator_from_env(http: reqwest::Client) -> Option<Arc<dyn Translator>> {
+    let provider = ENV.translation_provider.as_deref()?;
+    let api_key = ENV.translation_api_key.clone();
+    let api_url = ENV.translation_api_url.clone();
+
+    let translator: Arc<dyn Translator> = match (provider, api_key) {
+        ("deepl", Some(api_key)) => Arc::new(DeepLTranslator::new(http, api_key, api_url)),
+        ("google", Some(api_key)) => Arc::new(GoogleTranslator::new(http, api_key, api_url)),
+        ("libretranslate", api_key) => {
+            Arc::new(LibreTranslateTranslator::new(http, api_key, api_url))
+        }
+        (provider, None) => {
+            tracing::warn!(
+                "TRANSLATION_API_KEY is required for {provider}, translation is disabled"
+            );
+            return None;
+        }
+        _ => return None,
+    };
+
+    tracing::info!("Message translation enabled with {provider}");
+    Some(translator)
+}
+
+/// Lỗi từ provider khi response không thành công
+async fn provider_error(provider: &str, response: reqwest::Response) -> error::SystemError {
+    let status = response.status();
+    let detail = response.text().await.unwrap_or_default();
+    error::SystemError::internal_error(format!(
+        "{provider} translation failed with status {status}: {detail}"
+    ))
+}
+
+pub struct DeepLTranslator {
+    http: reqwest::Client,
+    api_key: String,
+    api_url: String,
+}
+
+impl DeepLTranslator {
+    fn new(http: reqwest::Client, api_key: String, api_url: Option<String>) -> Self {
+        let api_url = api_url.unwrap_or_else(|| {
+            if api_key.ends_with(":fx") {
+                "https://api-free.deepl.com".to_string()
+            } else {
+                "https://api.deepl.com".to_string()
+            }
+        });
+        Self { http, api_key, api_url }
+    }
+}
+
+#[derive(Deserialize)]
+struct DeepLResponse {
+    translations: Vec<DeepLTranslation>,
+}
+
+#[derive(Deserialize)]
+struct DeepLTranslation {
+    text: String,
+    detected_source_language: Option<String>,
+}
+
+#[async_trait::async_trait]
+impl Translator for DeepLTranslator {
+    async fn translate(
+        &self,
+        text: &str,
+        target_lang: &str,
+    ) -> Result<Translation, error::SystemError> {
+        let body = serde_json::json!({
+            "text": [text],
+            // DeepL dùng mã viết hoa (`EN-US`)
+            "target_lang": target_lang.to_uppercase(),
+        });
+
+        let response = self
+            .http
+            .post(format!("{}/v2/translate", self.api_url.trim_end_matches('/')))
+            .header(reqwest::header::AUTHORIZATION, format!("DeepL-Auth-Key {}", self.api_key))
+            .json(&body)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(provider_error("DeepL", response).await);
+        }
+
+        let translations = response.json::<DeepLResponse>().await?.translations;
+        let translation = translations
+            .into_iter()
+            .next()
+            .ok_or_else(|| error::SystemError::internal_error("DeepL returned no translation"))?;
+
+        Ok(Translation {
+            text: translation.text,
+            source_lang: translation.detected_source_language.map(|lang| lang.to_lowercase()),
+        })
+    }
+}
+
+pub struct GoogleTranslator {
+    http: reqwest::Client,
+    api_key: String,
+    api_url: String,
+}
+
+impl GoogleTranslator {
+    fn new(http: reqwest::Client, api_key: String, api_url: Option<String>) -> Self {
+        let api_url = api_url.unwrap_or_else(|| "https://translation.googleapis.com".to_string());
+        Self { http, api_key, api_url }
+    }
+}
+
+#[derive(Deserialize)]
+struct GoogleResponse {
+    data: GoogleData,
+}
+
+#[derive(Deserialize)]
+struct GoogleData {
+    translations: Vec<GoogleTranslation>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleTranslation {
+    translated_text: String,
+    detected_source_language: Option<String>,
+}
+
+#[async_trait::async_trait]
+impl Translator for GoogleTranslator {
+    async fn translate(
+        &self,
+        text: &str,
+        target_lang: &str,
+    ) -> Result<Translation, error::SystemError> {
+        let body = serde_json::json!({
+            "q": text,
+            "target": target_lang,
+            "format": "text",
+        });
+
+        let response = self
+            .http
+            .post(format!("{}/language/translate/v2", self.api_url.trim_end_matches('/')))
+            .query(&[("key", self.api_key.as_str())])
+            .json(&body)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(provider_error("Google", response).await);
+        }
+
+        let translations = response.json::<GoogleResponse>().await?.data.translations;
+        let translation = translations
+            .into_iter()
+            .next()
+            .ok_or_else(|| error::SystemError::internal_error("Google returned no translation"))?;
+
+        Ok(Translation {
+            text: translation.translated_text,
+            source_lang: translation.detected_source_language,
+        })
+    }
+}
+
+pub struct LibreTranslateTranslator {
+    http: reqwest::Client,
+    /// Không bắt buộc với instance tự host
+    api_key: Option<String>,
+    api_url: String,
+}
+
+impl LibreTranslateTranslator {
+    fn new(http: reqwest::Client, api_key: Option<String>, api_url: Option<String>) -> Self {
+        let api_url = api_url.unwrap_or_else(|| "https://libretranslate.com".to_string());
+        Self { http, api_key, api_url }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LibreTranslateResponse {
+    translated_text: String,
+    detected_language: Option<LibreTranslateLanguage>,
+}
+
+#[derive(Deserialize)]
+struct LibreTranslateLanguage {
+    language: String,
+}
+
+#[async_trait::async_trait]
+impl Translator for LibreTranslateTranslator {
+    async fn translate(
+        &self,
+        text: &str,
+        target_lang: &str,
+    ) -> Result<Translation, error::SystemError> {
+        let body = serde_json::json!({
+            "q": text,
+            "source": "auto",
+            "target": target_lang,
+            "format": "text",
+            "api_key": self.api_key,
+        });
+
+        let response = self
+            .http
+            .post(format!("{}/translate", self.api_url.trim_end_matches('/')))
+            .json(&body)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(provider_error("LibreTranslate", response).await);
+        }
+
+        let translation = response.json::<LibreTranslateResponse>().await?;
+        Ok(Translation {
+            text: translation.translated_text,
+            source_lang: translation.detected_language.map(|detected| detected.language),
+        })
+    }
+}
@@ -1,9 +1,14 @@
 pub const CACHE_TTL: usize = 5 * 60;
 
 pub mod user {
+    pub mod cache;
     pub mod handle;
+    pub mod inactivity;
+    pub mod lockout;
     pub mod model;
     pub mod repository;
+    #[cfg(test)]
+    pub mod repository_mock;
     pub mod repository_pg;
     pub mod route;
     pub mod schema;
@@ -11,9 +16,12 @@ pub mod user {
 }
 
 pub mod friend {
+    pub mod cleanup;
     pub mod handle;
     pub mod model;
     pub mod repository;
+    #[cfg(test)]
+    pub mod repository_mock;
     pub mod repository_pg;
     pub mod route;
     pub mod schema;
@@ -22,19 +30,29 @@ pub mod friend {
 
 #[allow(unused)]
 pub mod message {
+    pub mod archive;
+    pub mod command;
     pub mod handle;
     pub mod model;
     pub mod repository;
+    #[cfg(test)]
+    pub mod repository_mock;
     pub mod repository_pg;
+    pub mod retention;
     pub mod route;
+    pub mod scheduler;
     pub mod schema;
     pub mod service;
+    pub mod translation;
 }
 
 pub mod conversation {
     pub mod handle;
     pub mod model;
+    pub mod reconcile;
     pub mod repository;
+    #[cfg(test)]
+    pub mod repository_mock;
     pub mod repository_pg;
     pub mod route;
     pub mod schema;
@@ -51,4 +69,146 @@ pub mod file_upload {
     pub mod service;
 }
 
+pub mod oauth {
+    pub mod handle;
+    pub mod model;
+    pub mod repository;
+    #[cfg(test)]
+    pub mod repository_mock;
+    pub mod repository_pg;
+    pub mod route;
+    pub mod schema;
+    pub mod service;
+}
+
+pub mod notification {
+    pub mod handle;
+    pub mod model;
+    pub mod queue;
+    pub mod repository;
+    #[cfg(test)]
+    pub mod repository_mock;
+    pub mod repository_pg;
+    pub mod route;
+    pub mod schema;
+    pub mod sender;
+    pub mod service;
+}
+
+pub mod report {
+    pub mod handle;
+    pub mod model;
+    pub mod moderation;
+    pub mod repository;
+    #[cfg(test)]
+    pub mod repository_mock;
+    pub mod repository_pg;
+    pub mod route;
+    pub mod schema;
+    pub mod service;
+}
+
+pub mod bot {
+    pub mod handle;
+    pub mod model;
+    pub mod repository;
+    #[cfg(test)]
+    pub mod repository_mock;
+    pub mod repository_pg;
+    pub mod route;
+    pub mod schema;
+    pub mod service;
+}
+
+pub mod webhook {
+    pub mod delivery;
+    pub mod handle;
+    pub mod model;
+    pub mod repository;
+    pub mod repository_pg;
+    pub mod route;
+    pub mod schema;
+    pub mod service;
+}
+
+pub mod call {
+    pub mod handle;
+    pub mod model;
+    pub mod repository;
+    #[cfg(test)]
+    pub mod repository_mock;
+    pub mod repository_pg;
+    pub mod route;
+    pub mod schema;
+    pub mod service;
+    pub mod timeout;
+}
+
+pub mod guest {
+    pub mod handle;
+    pub mod model;
+    pub mod repository;
+    #[cfg(test)]
+    pub mod repository_mock;
+    pub mod repository_pg;
+    pub mod route;
+    pub mod schema;
+    pub mod service;
+}
+
+pub mod keys {
+    pub mod handle;
+    pub mod model;
+    pub mod repository;
+    #[cfg(test)]
+    pub mod repository_mock;
+    pub mod repository_pg;
+    pub mod route;
+    pub mod schema;
+    pub mod service;
+}
+
+pub mod announcement {
+    pub mod handle;
+    pub mod model;
+    pub mod repository;
+    #[cfg(test)]
+    pub mod repository_mock;
+    pub mod repository_pg;
+    pub mod route;
+    pub mod schema;
+    pub mod service;
+}
+
+pub mod audit {
+    pub mod handle;
+    pub mod model;
+    pub mod repository;
+    #[cfg(test)]
+    pub mod repository_mock;
+    pub mod repository_pg;
+    pub mod route;
+    pub mod schema;
+    pub mod service;
+}
+
 pub mod websocket;
+
+pub mod search {
+    pub mod handle;
+    pub mod model;
+    pub mod repository;
+    #[cfg(test)]
+    pub mod repository_mock;
+    pub mod repository_pg;
+    pub mod route;
+    pub mod schema;
+    pub mod service;
+}
+
+pub mod event {
+    pub mod handle;
+    pub mod model;
+    pub mod route;
+    pub mod service;
+}
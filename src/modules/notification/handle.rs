@@ -0,0 +1,102 @@
+use actix_web::{delete, get, post, web, HttpRequest};
+use uuid::Uuid;
+
+use crate::{
+    api::{error, success},
+    middlewares::get_extensions,
+    modules::notification::{
+        model::RegisterDeviceModel,
+        schema::{DeviceEntity, NotificationEntity},
+        service::NotificationService,
+    },
+    utils::{Claims, ValidatedJson},
+};
+
+#[utoipa::path(
+    tag = "devices",
+    request_body = RegisterDeviceModel,
+    responses((status = 200, body = success::SuccessData<DeviceEntity>))
+)]
+#[post("")]
+pub async fn register_device(
+    notification_service: web::Data<NotificationService>,
+    ValidatedJson(body): ValidatedJson<RegisterDeviceModel>,
+    req: HttpRequest,
+) -> Result<success::Success<DeviceEntity>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let device = notification_service.register_device(user_id, body.platform, body.token).await?;
+
+    Ok(success::Success::created(Some(device)).message("Device registered successfully"))
+}
+
+#[utoipa::path(
+    tag = "devices",
+    responses((status = 200, body = success::SuccessData<Vec<DeviceEntity>>))
+)]
+#[get("")]
+pub async fn list_devices(
+    notification_service: web::Data<NotificationService>,
+    req: HttpRequest,
+) -> Result<success::Success<Vec<DeviceEntity>>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let devices = notification_service.list_devices(user_id).await?;
+
+    Ok(success::Success::ok(Some(devices)).message("Devices retrieved successfully"))
+}
+
+#[utoipa::path(
+    tag = "devices",
+    path = "/{id}",
+    params(("id" = Uuid, Path, description = "Device ID")),
+    responses(
+        (status = 204, description = "Thiết bị đã bị gỡ"),
+        (status = 404, description = "Không tìm thấy thiết bị", body = error::ErrorBody)
+    )
+)]
+#[delete("/{id:[0-9a-fA-F-]{36}}")]
+pub async fn remove_device(
+    notification_service: web::Data<NotificationService>,
+    device_id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    notification_service.remove_device(user_id, device_id.into_inner()).await?;
+
+    Ok(success::Success::no_content())
+}
+
+#[utoipa::path(
+    tag = "notifications",
+    responses((status = 200, body = success::SuccessData<Vec<NotificationEntity>>))
+)]
+#[get("")]
+pub async fn list_notifications(
+    notification_service: web::Data<NotificationService>,
+    req: HttpRequest,
+) -> Result<success::Success<Vec<NotificationEntity>>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let notifications = notification_service.list_notifications(user_id).await?;
+
+    Ok(success::Success::ok(Some(notifications)).message("Notifications retrieved successfully"))
+}
+
+#[utoipa::path(
+    tag = "notifications",
+    path = "/{id}/read",
+    params(("id" = Uuid, Path, description = "Notification ID")),
+    responses(
+        (status = 204, description = "Đã đánh dấu đã đọc"),
+        (status = 404, description = "Không tìm thấy notification", body = error::ErrorBody)
+    )
+)]
+#[post("/{id:[0-9a-fA-F-]{36}}/read")]
+pub async fn mark_notification_read(
+    notification_service: web::Data<NotificationService>,
+    notification_id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    notification_service.mark_notification_read(user_id, notification_id.into_inner()).await?;
+
+    Ok(success::Success::no_content())
+}
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::modules::notification::schema::DevicePlatform;
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct RegisterDeviceModel {
+    pub platform: DevicePlatform,
+    /// FCM registration token, hoặc endpoint của Web Push subscription
+    #[validate(length(
+        min = 1,
+        max = 4096,
+        message = "Token must be between 1 and 4096 characters"
+    ))]
+    pub token: String,
+}
+
+/// Thông tin hiển thị của push, lấy từ sender và conversation
+#[derive(sqlx::FromRow)]
+pub struct PushContext {
+    pub sender_name: String,
+    pub group_name: Option<String>,
+}
+
+/// Nội dung push gửi tới từng thiết bị
+#[derive(Debug, Clone, Serialize)]
+pub struct PushPayload {
+    pub title: String,
+    pub body: String,
+    pub conversation_id: Uuid,
+    pub message_id: Uuid,
+}
+
+/// Job được MessageService đưa vào hàng đợi sau khi persist message
+#[derive(Debug)]
+pub struct PushJob {
+    pub conversation_id: Uuid,
+    pub message_id: Uuid,
+    pub sender_id: Uuid,
+    /// Participants của conversation (có thể bao gồm sender)
+    pub recipient_ids: Vec<Uuid>,
+    /// Participants được mention, vẫn nhận push khi đã mute conversation hoặc chọn `mentions`
+    pub mentioned_ids: Vec<Uuid>,
+    pub content: Option<String>,
+}
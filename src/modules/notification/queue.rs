@@ -0,0 +1,162 @@
+/// Push Queue
+///
+/// MessageService đưa `PushJob` vào hàng đợi sau khi persist message (không chờ provider).
+/// Worker chạy nền xử lý từng job:
+/// 1. Bỏ sender và các participants đang online (đã nhận qua WebSocket)
+/// 2. Bỏ các participants theo mức notification của conversation: `nothing` không nhận,
+///    `mentions` (hoặc đang mute) chỉ nhận khi được mention
+/// 3. Bỏ các users đang trong giờ Do Not Disturb
+/// 4. Gửi tới mọi thiết bị của user còn lại, xóa thiết bị có token không còn hợp lệ
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::{
+        conversation::schema::NotificationLevel,
+        notification::{
+            model::{PushJob, PushPayload},
+            repository::DeviceRepository,
+            sender::{PushSenders, SendOutcome},
+        },
+        websocket::presence::PresenceService,
+    },
+};
+
+/// Số job tối đa chờ xử lý, job mới bị bỏ khi hàng đợi đầy
+const PUSH_QUEUE_CAPACITY: usize = 1024;
+
+/// Độ dài tối đa của nội dung tin nhắn hiển thị trong push
+const PREVIEW_MAX_CHARS: usize = 100;
+
+#[derive(Clone)]
+pub struct PushQueue {
+    tx: mpsc::Sender<PushJob>,
+}
+
+impl PushQueue {
+    pub fn new() -> (Self, mpsc::Receiver<PushJob>) {
+        let (tx, rx) = mpsc::channel(PUSH_QUEUE_CAPACITY);
+        (Self { tx }, rx)
+    }
+
+    /// Đưa job vào hàng đợi, không block request gửi tin nhắn
+    pub fn enqueue(&self, job: PushJob) {
+        if let Err(e) = self.tx.try_send(job) {
+            tracing::warn!("Dropping push notification job: {}", e);
+        }
+    }
+}
+
+/// Xử lý job cho tới khi mọi `PushQueue` bị drop
+pub async fn run_push_worker<R>(
+    mut rx: mpsc::Receiver<PushJob>,
+    repo: Arc<R>,
+    presence: PresenceService,
+    senders: PushSenders,
+) where
+    R: DeviceRepository + Send + Sync,
+{
+    while let Some(job) = rx.recv().await {
+        let message_id = job.message_id;
+        if let Err(e) = dispatch(job, repo.as_ref(), &presence, &senders).await {
+            tracing::error!("Failed to push notifications for message {}: {:?}", message_id, e);
+        }
+    }
+}
+
+async fn dispatch<R>(
+    job: PushJob,
+    repo: &R,
+    presence: &PresenceService,
+    senders: &PushSenders,
+) -> Result<(), error::SystemError>
+where
+    R: DeviceRepository + Send + Sync,
+{
+    let recipients: Vec<Uuid> =
+        job.recipient_ids.into_iter().filter(|id| *id != job.sender_id).collect();
+    if recipients.is_empty() {
+        return Ok(());
+    }
+
+    let offline: Vec<Uuid> = presence
+        .get_online_status_batch(&recipients)
+        .await?
+        .into_iter()
+        .filter(|info| !info.is_online)
+        .map(|info| info.user_id)
+        .collect();
+    if offline.is_empty() {
+        return Ok(());
+    }
+
+    let levels: HashMap<Uuid, NotificationLevel> =
+        repo.find_notification_levels(&job.conversation_id, &offline).await?.into_iter().collect();
+    let targets: Vec<Uuid> = offline
+        .into_iter()
+        .filter(|id| {
+            levels.get(id).is_none_or(|level| level.allows(job.mentioned_ids.contains(id)))
+        })
+        .collect();
+    if targets.is_empty() {
+        return Ok(());
+    }
+
+    let dnd: HashSet<Uuid> = repo.find_dnd_users(&targets).await?.into_iter().collect();
+    let targets: Vec<Uuid> = targets.into_iter().filter(|id| !dnd.contains(id)).collect();
+    if targets.is_empty() {
+        return Ok(());
+    }
+
+    let devices = repo.find_by_users(&targets).await?;
+    if devices.is_empty() {
+        return Ok(());
+    }
+
+    let Some(context) = repo.find_push_context(&job.conversation_id, &job.sender_id).await? else {
+        return Ok(());
+    };
+
+    let title = match context.group_name {
+        Some(group_name) => format!("{} ({})", context.sender_name, group_name),
+        None => context.sender_name,
+    };
+    let body = match job.content {
+        Some(content) if content.chars().count() > PREVIEW_MAX_CHARS => {
+            format!("{}…", content.chars().take(PREVIEW_MAX_CHARS).collect::<String>())
+        }
+        Some(content) => content,
+        None => "Sent an attachment".to_string(),
+    };
+    let payload = PushPayload {
+        title,
+        body,
+        conversation_id: job.conversation_id,
+        message_id: job.message_id,
+    };
+
+    for device in devices {
+        let Some(sender) = senders.for_platform(device.platform) else {
+            continue;
+        };
+
+        match sender.send(&device.token, &payload).await {
+            Ok(SendOutcome::Delivered) => {}
+            Ok(SendOutcome::InvalidToken) => {
+                tracing::info!("Removing device {} with invalid push token", device.id);
+                repo.delete_by_token(&device.token).await?;
+            }
+            Err(e) => {
+                tracing::warn!("Push to device {} failed: {:?}", device.id, e);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use uuid::Uuid;
+
+use crate::api::error;
+use crate::modules::conversation::schema::NotificationLevel;
+use crate::modules::notification::model::PushContext;
+use crate::modules::notification::schema::{
+    DeviceEntity, DevicePlatform, NotificationEntity, NotificationKind,
+};
+
+#[async_trait::async_trait]
+pub trait DeviceRepository {
+    /// Đăng ký thiết bị, token đã tồn tại sẽ được chuyển sang user hiện tại
+    async fn upsert(
+        &self,
+        user_id: &Uuid,
+        platform: DevicePlatform,
+        token: &str,
+    ) -> Result<DeviceEntity, error::SystemError>;
+
+    async fn find_by_user(&self, user_id: &Uuid) -> Result<Vec<DeviceEntity>, error::SystemError>;
+
+    async fn find_by_users(
+        &self,
+        user_ids: &[Uuid],
+    ) -> Result<Vec<DeviceEntity>, error::SystemError>;
+
+    async fn delete(&self, id: &Uuid, user_id: &Uuid) -> Result<bool, error::SystemError>;
+
+    /// Xóa token bị provider từ chối (app gỡ cài đặt, subscription hết hạn)
+    async fn delete_by_token(&self, token: &str) -> Result<(), error::SystemError>;
+
+    /// Mức notification hiệu lực của các user trong `user_ids` không nhận mọi tin nhắn
+    /// (đang mute được tính là `mentions`), user không có trong kết quả nhận tất cả
+    async fn find_notification_levels(
+        &self,
+        conversation_id: &Uuid,
+        user_ids: &[Uuid],
+    ) -> Result<Vec<(Uuid, NotificationLevel)>, error::SystemError>;
+
+    /// Các user trong `user_ids` đang trong giờ Do Not Disturb
+    async fn find_dnd_users(&self, user_ids: &[Uuid]) -> Result<Vec<Uuid>, error::SystemError>;
+
+    async fn find_push_context(
+        &self,
+        conversation_id: &Uuid,
+        sender_id: &Uuid,
+    ) -> Result<Option<PushContext>, error::SystemError>;
+}
+
+/// Notification entries trong app của user
+#[async_trait::async_trait]
+pub trait NotificationRepository {
+    async fn create(
+        &self,
+        user_id: &Uuid,
+        kind: NotificationKind,
+        title: &str,
+        body: &str,
+    ) -> Result<NotificationEntity, error::SystemError>;
+
+    /// Notification entries gần nhất của user, mới nhất trước
+    async fn find_by_user(
+        &self,
+        user_id: &Uuid,
+        limit: i64,
+    ) -> Result<Vec<NotificationEntity>, error::SystemError>;
+
+    /// Đánh dấu đã đọc, trả về `false` nếu entry không tồn tại hoặc không thuộc user
+    async fn mark_read(&self, id: &Uuid, user_id: &Uuid) -> Result<bool, error::SystemError>;
+}
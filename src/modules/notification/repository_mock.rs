@@ -0,0 +1,79 @@
+/// In-memory `NotificationRepository` cho unit tests của services
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::notification::{
+        repository::NotificationRepository,
+        schema::{NotificationEntity, NotificationKind},
+    },
+};
+
+/// Clone dùng chung dữ liệu, test giữ một bản để kiểm tra các entries đã tạo
+#[derive(Clone, Default)]
+pub struct NotificationRepositoryMock {
+    /// (user_id, entry) theo thứ tự tạo
+    notifications: Arc<Mutex<Vec<(Uuid, NotificationEntity)>>>,
+}
+
+impl NotificationRepositoryMock {
+    fn notifications(&self) -> MutexGuard<'_, Vec<(Uuid, NotificationEntity)>> {
+        self.notifications.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Mọi entries của user theo thứ tự tạo
+    pub fn entries_of(&self, user_id: &Uuid) -> Vec<NotificationEntity> {
+        self.notifications()
+            .iter()
+            .filter(|(owner, _)| owner == user_id)
+            .map(|(_, notification)| notification.clone())
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl NotificationRepository for NotificationRepositoryMock {
+    async fn create(
+        &self,
+        user_id: &Uuid,
+        kind: NotificationKind,
+        title: &str,
+        body: &str,
+    ) -> Result<NotificationEntity, error::SystemError> {
+        let notification = NotificationEntity {
+            id: Uuid::now_v7(),
+            kind,
+            title: title.to_string(),
+            body: body.to_string(),
+            read_at: None,
+            created_at: chrono::Utc::now(),
+        };
+        self.notifications().push((*user_id, notification.clone()));
+        Ok(notification)
+    }
+
+    async fn find_by_user(
+        &self,
+        user_id: &Uuid,
+        limit: i64,
+    ) -> Result<Vec<NotificationEntity>, error::SystemError> {
+        let mut notifications = self.entries_of(user_id);
+        notifications.reverse();
+        notifications.truncate(limit.max(0) as usize);
+        Ok(notifications)
+    }
+
+    async fn mark_read(&self, id: &Uuid, user_id: &Uuid) -> Result<bool, error::SystemError> {
+        let mut notifications = self.notifications();
+        let Some((_, notification)) = notifications
+            .iter_mut()
+            .find(|(owner, notification)| owner == user_id && notification.id == *id)
+        else {
+            return Ok(false);
+        };
+        notification.read_at.get_or_insert_with(chrono::Utc::now);
+        Ok(true)
+    }
+}
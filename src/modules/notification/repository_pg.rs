@@ -0,0 +1,235 @@
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::{
+        conversation::schema::NotificationLevel,
+        notification::{
+            model::PushContext,
+            repository::{DeviceRepository, NotificationRepository},
+            schema::{DeviceEntity, DevicePlatform, NotificationEntity, NotificationKind},
+        },
+    },
+};
+
+#[derive(Clone)]
+pub struct DeviceRepositoryPg {
+    pool: sqlx::PgPool,
+}
+
+impl DeviceRepositoryPg {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl DeviceRepository for DeviceRepositoryPg {
+    async fn upsert(
+        &self,
+        user_id: &Uuid,
+        platform: DevicePlatform,
+        token: &str,
+    ) -> Result<DeviceEntity, error::SystemError> {
+        let device = sqlx::query_as::<_, DeviceEntity>(
+            r#"
+            INSERT INTO devices (user_id, platform, token)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (token)
+            DO UPDATE SET user_id = EXCLUDED.user_id,
+                          platform = EXCLUDED.platform,
+                          updated_at = NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(platform)
+        .bind(token)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(device)
+    }
+
+    async fn find_by_user(&self, user_id: &Uuid) -> Result<Vec<DeviceEntity>, error::SystemError> {
+        let devices = sqlx::query_as::<_, DeviceEntity>(
+            "SELECT * FROM devices WHERE user_id = $1 ORDER BY updated_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(devices)
+    }
+
+    async fn find_by_users(
+        &self,
+        user_ids: &[Uuid],
+    ) -> Result<Vec<DeviceEntity>, error::SystemError> {
+        let devices =
+            sqlx::query_as::<_, DeviceEntity>("SELECT * FROM devices WHERE user_id = ANY($1)")
+                .bind(user_ids)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(devices)
+    }
+
+    async fn delete(&self, id: &Uuid, user_id: &Uuid) -> Result<bool, error::SystemError> {
+        let rows = sqlx::query("DELETE FROM devices WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(rows > 0)
+    }
+
+    async fn delete_by_token(&self, token: &str) -> Result<(), error::SystemError> {
+        sqlx::query("DELETE FROM devices WHERE token = $1").bind(token).execute(&self.pool).await?;
+
+        Ok(())
+    }
+
+    async fn find_notification_levels(
+        &self,
+        conversation_id: &Uuid,
+        user_ids: &[Uuid],
+    ) -> Result<Vec<(Uuid, NotificationLevel)>, error::SystemError> {
+        let levels = sqlx::query_as::<_, (Uuid, NotificationLevel)>(
+            r#"
+            SELECT
+                user_id,
+                CASE
+                    WHEN notification_level = 'all' AND muted_until > NOW()
+                    THEN 'mentions'::notification_level
+                    ELSE notification_level
+                END
+            FROM participants
+            WHERE conversation_id = $1
+            AND user_id = ANY($2)
+            AND (notification_level <> 'all' OR muted_until > NOW())
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(user_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(levels)
+    }
+
+    async fn find_dnd_users(&self, user_ids: &[Uuid]) -> Result<Vec<Uuid>, error::SystemError> {
+        let user_ids = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT user_id
+            FROM user_settings
+            WHERE user_id = ANY($1)
+            AND dnd_active(dnd_start, dnd_end, dnd_timezone)
+            "#,
+        )
+        .bind(user_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(user_ids)
+    }
+
+    async fn find_push_context(
+        &self,
+        conversation_id: &Uuid,
+        sender_id: &Uuid,
+    ) -> Result<Option<PushContext>, error::SystemError> {
+        let context = sqlx::query_as::<_, PushContext>(
+            r#"
+            SELECT u.display_name AS sender_name, g.name AS group_name
+            FROM users u
+            LEFT JOIN group_conversations g ON g.conversation_id = $1
+            WHERE u.id = $2
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(sender_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(context)
+    }
+}
+
+#[derive(Clone)]
+pub struct NotificationRepositoryPg {
+    pool: sqlx::PgPool,
+}
+
+impl NotificationRepositoryPg {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl NotificationRepository for NotificationRepositoryPg {
+    async fn create(
+        &self,
+        user_id: &Uuid,
+        kind: NotificationKind,
+        title: &str,
+        body: &str,
+    ) -> Result<NotificationEntity, error::SystemError> {
+        let notification = sqlx::query_as::<_, NotificationEntity>(
+            r#"
+            INSERT INTO notifications (user_id, kind, title, body)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(kind)
+        .bind(title)
+        .bind(body)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(notification)
+    }
+
+    async fn find_by_user(
+        &self,
+        user_id: &Uuid,
+        limit: i64,
+    ) -> Result<Vec<NotificationEntity>, error::SystemError> {
+        let notifications = sqlx::query_as::<_, NotificationEntity>(
+            r#"
+            SELECT * FROM notifications
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(notifications)
+    }
+
+    async fn mark_read(&self, id: &Uuid, user_id: &Uuid) -> Result<bool, error::SystemError> {
+        let rows = sqlx::query(
+            r#"
+            UPDATE notifications
+            SET read_at = COALESCE(read_at, NOW())
+            WHERE id = $1 AND user_id = $2
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows > 0)
+    }
+}
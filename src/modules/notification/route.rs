@@ -0,0 +1,20 @@
+use crate::modules::notification::handle::*;
+use actix_web::web::{scope, ServiceConfig};
+use utoipa::OpenApi;
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        scope("/devices").service(register_device).service(list_devices).service(remove_device),
+    )
+    .service(scope("/notifications").service(list_notifications).service(mark_notification_read));
+}
+
+/// OpenAPI paths của `configure` (scope `/devices`)
+#[derive(OpenApi)]
+#[openapi(paths(register_device, list_devices, remove_device))]
+pub struct NotificationApiDoc;
+
+/// OpenAPI paths của `configure` (scope `/notifications`)
+#[derive(OpenApi)]
+#[openapi(paths(list_notifications, mark_notification_read))]
+pub struct NotificationEntryApiDoc;
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::{FromRow, Type};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Nền tảng của thiết bị nhận push. Android/iOS đi qua FCM (FCM forward tới APNs),
+/// Web dùng Web Push với `token` là endpoint của push subscription
+#[derive(Debug, PartialEq, Clone, Copy, Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "device_platform", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DevicePlatform {
+    Android,
+    Ios,
+    Web,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct DeviceEntity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub platform: DevicePlatform,
+    #[serde(skip_serializing)]
+    pub token: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Loại Notification entry hiển thị trong app (khác push: được lưu lại cho tới khi user xem)
+#[derive(Debug, PartialEq, Clone, Copy, Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "notification_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// Tài khoản được đăng nhập từ IP mới
+    NewSignIn,
+    /// Tài khoản bị khóa đăng nhập tạm thời sau nhiều lần nhập sai mật khẩu
+    AccountLocked,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct NotificationEntity {
+    pub id: Uuid,
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+    pub read_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
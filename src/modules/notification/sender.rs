@@ -0,0 +1,281 @@
+/// Push Senders
+///
+/// Mỗi provider implement `NotificationSender`:
+/// - `FcmSender`: FCM HTTP v1 API, xác thực bằng service account (OAuth2 JWT bearer).
+///   Android và iOS đều đi qua FCM (FCM forward tới APNs)
+/// - `WebPushSender`: Web Push protocol với VAPID. Push không kèm payload (không cần
+///   mã hóa aes128gcm), service worker nhận event rồi tự fetch tin nhắn mới qua API
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::{
+    api::error,
+    modules::notification::{model::PushPayload, schema::DevicePlatform},
+    ENV,
+};
+
+const FCM_SCOPE: &str = "https://www.googleapis.com/auth/firebase.messaging";
+const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+
+/// Thời gian push được provider giữ lại khi thiết bị offline (giây)
+const PUSH_TTL: u64 = 24 * 60 * 60;
+
+/// Làm mới access token FCM trước khi hết hạn
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// Kết quả gửi push tới một thiết bị
+#[derive(Debug, PartialEq)]
+pub enum SendOutcome {
+    Delivered,
+    /// Provider báo token không còn hợp lệ, thiết bị cần được xóa
+    InvalidToken,
+}
+
+#[async_trait::async_trait]
+pub trait NotificationSender: Send + Sync {
+    async fn send(
+        &self,
+        token: &str,
+        payload: &PushPayload,
+    ) -> Result<SendOutcome, error::SystemError>;
+}
+
+/// Các sender đã được cấu hình, provider thiếu credentials sẽ là `None`
+#[derive(Clone, Default)]
+pub struct PushSenders {
+    fcm: Option<Arc<dyn NotificationSender>>,
+    web_push: Option<Arc<dyn NotificationSender>>,
+}
+
+impl PushSenders {
+    pub fn from_env(http: reqwest::Client) -> Self {
+        let fcm = FcmSender::from_env(http.clone())
+            .map(|sender| Arc::new(sender) as Arc<dyn NotificationSender>);
+        let web_push = WebPushSender::from_env(http)
+            .map(|sender| Arc::new(sender) as Arc<dyn NotificationSender>);
+
+        if fcm.is_none() {
+            tracing::warn!("FCM is not configured, mobile push notifications are disabled");
+        }
+        if web_push.is_none() {
+            tracing::warn!("VAPID keys are not configured, web push notifications are disabled");
+        }
+
+        Self { fcm, web_push }
+    }
+
+    pub fn for_platform(&self, platform: DevicePlatform) -> Option<&Arc<dyn NotificationSender>> {
+        match platform {
+            DevicePlatform::Android | DevicePlatform::Ios => self.fcm.as_ref(),
+            DevicePlatform::Web => self.web_push.as_ref(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ServiceAccountClaims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+#[derive(Deserialize)]
+struct AccessTokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+pub struct FcmSender {
+    http: reqwest::Client,
+    project_id: String,
+    client_email: String,
+    key: EncodingKey,
+    /// (access token, thời điểm hết hạn)
+    access_token: Mutex<Option<(String, Instant)>>,
+}
+
+impl FcmSender {
+    fn from_env(http: reqwest::Client) -> Option<Self> {
+        let project_id = ENV.fcm_project_id.clone()?;
+        let client_email = ENV.fcm_client_email.clone()?;
+        let key = match EncodingKey::from_rsa_pem(ENV.fcm_private_key.as_ref()?.as_bytes()) {
+            Ok(key) => key,
+            Err(e) => {
+                tracing::error!("Invalid FCM_PRIVATE_KEY: {:?}", e);
+                return None;
+            }
+        };
+
+        Some(Self { http, project_id, client_email, key, access_token: Mutex::new(None) })
+    }
+
+    /// Lấy access token OAuth2 từ service account, cache tới khi gần hết hạn
+    async fn access_token(&self) -> Result<String, error::SystemError> {
+        let mut cached = self.access_token.lock().await;
+        if let Some((token, expires_at)) = cached.as_ref() {
+            if Instant::now() + TOKEN_REFRESH_MARGIN < *expires_at {
+                return Ok(token.clone());
+            }
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let assertion = encode(
+            &Header::new(Algorithm::RS256),
+            &ServiceAccountClaims {
+                iss: &self.client_email,
+                scope: FCM_SCOPE,
+                aud: GOOGLE_TOKEN_URL,
+                iat: now,
+                exp: now + 3600,
+            },
+            &self.key,
+        )?;
+
+        let response = self
+            .http
+            .post(GOOGLE_TOKEN_URL)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<AccessTokenResponse>()
+            .await?;
+
+        let expires_at = Instant::now() + Duration::from_secs(response.expires_in);
+        *cached = Some((response.access_token.clone(), expires_at));
+
+        Ok(response.access_token)
+    }
+}
+
+#[async_trait::async_trait]
+impl NotificationSender for FcmSender {
+    async fn send(
+        &self,
+        token: &str,
+        payload: &PushPayload,
+    ) -> Result<SendOutcome, error::SystemError> {
+        let access_token = self.access_token().await?;
+        let url =
+            format!("https://fcm.googleapis.com/v1/projects/{}/messages:send", self.project_id);
+
+        let body = serde_json::json!({
+            "message": {
+                "token": token,
+                "notification": {
+                    "title": payload.title,
+                    "body": payload.body,
+                },
+                "data": {
+                    "conversation_id": payload.conversation_id.to_string(),
+                    "message_id": payload.message_id.to_string(),
+                },
+                "android": {
+                    "priority": "high",
+                    "ttl": format!("{PUSH_TTL}s"),
+                },
+                "apns": {
+                    "headers": { "apns-priority": "10" },
+                },
+            }
+        });
+
+        let response = self.http.post(url).bearer_auth(access_token).json(&body).send().await?;
+
+        match response.status() {
+            status if status.is_success() => Ok(SendOutcome::Delivered),
+            // UNREGISTERED: app đã gỡ hoặc token đã được thay thế
+            reqwest::StatusCode::NOT_FOUND => Ok(SendOutcome::InvalidToken),
+            status => {
+                let detail = response.text().await.unwrap_or_default();
+                Err(error::SystemError::internal_error(format!(
+                    "FCM send failed with status {status}: {detail}"
+                )))
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct VapidClaims<'a> {
+    aud: &'a str,
+    exp: i64,
+    sub: &'a str,
+}
+
+pub struct WebPushSender {
+    http: reqwest::Client,
+    /// Public key dạng base64url (uncompressed P-256 point), gửi kèm header `k=`
+    public_key: String,
+    key: EncodingKey,
+}
+
+impl WebPushSender {
+    fn from_env(http: reqwest::Client) -> Option<Self> {
+        let public_key = ENV.vapid_public_key.clone()?;
+        let key = match EncodingKey::from_ec_pem(ENV.vapid_private_key.as_ref()?.as_bytes()) {
+            Ok(key) => key,
+            Err(e) => {
+                tracing::error!("Invalid VAPID_PRIVATE_KEY: {:?}", e);
+                return None;
+            }
+        };
+
+        Some(Self { http, public_key, key })
+    }
+}
+
+#[async_trait::async_trait]
+impl NotificationSender for WebPushSender {
+    async fn send(
+        &self,
+        token: &str,
+        _payload: &PushPayload,
+    ) -> Result<SendOutcome, error::SystemError> {
+        let endpoint = reqwest::Url::parse(token)
+            .map_err(|_| error::SystemError::bad_request("Invalid web push endpoint"))?;
+        let audience = endpoint.origin().ascii_serialization();
+
+        let jwt = encode(
+            &Header::new(Algorithm::ES256),
+            &VapidClaims {
+                aud: &audience,
+                exp: chrono::Utc::now().timestamp() + 12 * 60 * 60,
+                sub: &ENV.vapid_subject,
+            },
+            &self.key,
+        )?;
+
+        let response = self
+            .http
+            .post(endpoint)
+            .header(reqwest::header::AUTHORIZATION, format!("vapid t={jwt}, k={}", self.public_key))
+            .header("TTL", PUSH_TTL.to_string())
+            .header("Urgency", "high")
+            .header(reqwest::header::CONTENT_LENGTH, "0")
+            .send()
+            .await?;
+
+        match response.status() {
+            status if status.is_success() => Ok(SendOutcome::Delivered),
+            // Subscription đã hết hạn hoặc bị hủy
+            reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::GONE => {
+                Ok(SendOutcome::InvalidToken)
+            }
+            status => Err(error::SystemError::internal_error(format!(
+                "Web push failed with status {status}"
+            ))),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+/// Notification Service
+///
+/// Quản lý thiết bị nhận push của user. Việc gửi push chạy nền trong `queue`.
+/// Notification entries trong app (cảnh báo bảo mật, ...) được tạo bởi `audit`, user xem
+/// và đánh dấu đã đọc qua service này.
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::notification::{
+        repository::{DeviceRepository, NotificationRepository},
+        schema::{DeviceEntity, DevicePlatform, NotificationEntity},
+    },
+};
+
+/// Số Notification entries tối đa trả về cho user
+const NOTIFICATION_LIST_LIMIT: i64 = 50;
+
+#[derive(Clone)]
+pub struct NotificationService {
+    repo: Arc<dyn DeviceRepository + Send + Sync>,
+    notifications: Arc<dyn NotificationRepository + Send + Sync>,
+}
+
+impl NotificationService {
+    pub fn with_dependencies(
+        repo: Arc<dyn DeviceRepository + Send + Sync>,
+        notifications: Arc<dyn NotificationRepository + Send + Sync>,
+    ) -> Self {
+        NotificationService { repo, notifications }
+    }
+
+    pub async fn register_device(
+        &self,
+        user_id: Uuid,
+        platform: DevicePlatform,
+        token: String,
+    ) -> Result<DeviceEntity, error::SystemError> {
+        let token = token.trim();
+
+        // Web Push: token là endpoint của push subscription
+        if platform == DevicePlatform::Web && !token.starts_with("https://") {
+            return Err(error::SystemError::bad_request(
+                "Web push token must be the subscription endpoint URL",
+            ));
+        }
+
+        self.repo.upsert(&user_id, platform, token).await
+    }
+
+    pub async fn list_devices(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<DeviceEntity>, error::SystemError> {
+        self.repo.find_by_user(&user_id).await
+    }
+
+    pub async fn remove_device(&self, user_id: Uuid, id: Uuid) -> Result<(), error::SystemError> {
+        if !self.repo.delete(&id, &user_id).await? {
+            return Err(error::SystemError::not_found("Device not found"));
+        }
+
+        Ok(())
+    }
+
+    /// Notification entries gần nhất của user
+    pub async fn list_notifications(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<NotificationEntity>, error::SystemError> {
+        self.notifications.find_by_user(&user_id, NOTIFICATION_LIST_LIMIT).await
+    }
+
+    pub async fn mark_notification_read(
+        &self,
+        user_id: Uuid,
+        id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        if !self.notifications.mark_read(&id, &user_id).await? {
+            return Err(error::SystemError::not_found("Notification not found"));
+        }
+
+        Ok(())
+    }
+}
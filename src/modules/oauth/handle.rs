@@ -0,0 +1,70 @@
+use actix_web::{
+    cookie::{self, time, Cookie},
+    get, web, HttpResponse,
+};
+
+use crate::{
+    api::{error, success},
+    modules::{
+        oauth::{model, service::OAuthService},
+        user::model::SignInResponse,
+    },
+    utils::ValidatedQuery,
+    ENV,
+};
+
+#[utoipa::path(
+    tag = "auth",
+    security(()),
+    params(("provider" = String, Path, description = "`google` hoặc `github`")),
+    responses(
+        (status = 302, description = "Redirect tới trang đăng nhập của provider"),
+        (status = 404, description = "Provider không được hỗ trợ", body = error::ErrorBody)
+    )
+)]
+#[get("/{provider}/start")]
+pub async fn oauth_start(
+    oauth_service: web::Data<OAuthService>,
+    provider: web::Path<String>,
+) -> Result<HttpResponse, error::Error> {
+    let url = oauth_service.start(&provider).await?;
+    Ok(HttpResponse::Found().insert_header(("Location", url)).finish())
+}
+
+#[utoipa::path(
+    tag = "auth",
+    security(()),
+    params(
+        ("provider" = String, Path, description = "`google` hoặc `github`"),
+        model::OAuthCallbackQuery
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Set cookie `refresh_token`",
+            body = success::SuccessData<SignInResponse>
+        ),
+        (status = 401, description = "State không hợp lệ hoặc đã hết hạn", body = error::ErrorBody)
+    )
+)]
+#[get("/{provider}/callback")]
+pub async fn oauth_callback(
+    oauth_service: web::Data<OAuthService>,
+    provider: web::Path<String>,
+    ValidatedQuery(query): ValidatedQuery<model::OAuthCallbackQuery>,
+) -> Result<success::Success<SignInResponse>, error::Error> {
+    let (access_token, refresh_token) =
+        oauth_service.callback(&provider, &query.code, &query.state).await?;
+    let response = SignInResponse { access_token };
+    let refresh_cookie = Cookie::build("refresh_token", refresh_token)
+        .path("/")
+        .http_only(true)
+        .same_site(cookie::SameSite::Strict)
+        .secure(true)
+        .max_age(time::Duration::seconds(ENV.refresh_token_expiration as i64))
+        .finish();
+
+    Ok(success::Success::ok(Some(response))
+        .message("Signin successful")
+        .cookies(vec![refresh_cookie]))
+}
@@ -0,0 +1,51 @@
+use serde::Deserialize;
+use utoipa::IntoParams;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OAuthCallbackQuery {
+    #[validate(length(min = 1, message = "Authorization code cannot be empty"))]
+    pub code: String,
+    #[validate(length(min = 1, message = "State cannot be empty"))]
+    pub state: String,
+}
+
+/// Thông tin user lấy được từ provider sau khi đổi code lấy access token
+#[derive(Debug, Clone)]
+pub struct OAuthIdentity {
+    pub provider_user_id: String,
+    pub email: String,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GoogleUserInfo {
+    pub sub: String,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub name: Option<String>,
+    pub picture: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitHubUser {
+    pub id: i64,
+    pub login: String,
+    pub name: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitHubEmail {
+    pub email: String,
+    pub primary: bool,
+    pub verified: bool,
+}
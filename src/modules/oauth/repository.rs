@@ -0,0 +1,22 @@
+use uuid::Uuid;
+
+use crate::api::error;
+
+#[async_trait::async_trait]
+pub trait OAuthRepository {
+    /// Tìm user đã liên kết với tài khoản của provider
+    async fn find_user_id(
+        &self,
+        provider: &str,
+        provider_user_id: &str,
+    ) -> Result<Option<Uuid>, error::SystemError>;
+
+    /// Liên kết tài khoản provider với user
+    async fn link(
+        &self,
+        provider: &str,
+        provider_user_id: &str,
+        user_id: &Uuid,
+        email: &str,
+    ) -> Result<(), error::SystemError>;
+}
@@ -0,0 +1,48 @@
+/// In-memory `OAuthRepository` cho unit tests của services
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use uuid::Uuid;
+
+use crate::{api::error, modules::oauth::repository::OAuthRepository};
+
+/// Clone dùng chung dữ liệu, test giữ một bản để kiểm tra các liên kết đã tạo
+#[derive(Clone, Default)]
+pub struct OAuthRepositoryMock {
+    /// (provider, provider_user_id) -> user_id
+    links: Arc<Mutex<HashMap<(String, String), Uuid>>>,
+}
+
+impl OAuthRepositoryMock {
+    fn links(&self) -> MutexGuard<'_, HashMap<(String, String), Uuid>> {
+        self.links.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn linked_user(&self, provider: &str, provider_user_id: &str) -> Option<Uuid> {
+        self.links().get(&(provider.to_string(), provider_user_id.to_string())).copied()
+    }
+}
+
+#[async_trait::async_trait]
+impl OAuthRepository for OAuthRepositoryMock {
+    async fn find_user_id(
+        &self,
+        provider: &str,
+        provider_user_id: &str,
+    ) -> Result<Option<Uuid>, error::SystemError> {
+        Ok(self.linked_user(provider, provider_user_id))
+    }
+
+    async fn link(
+        &self,
+        provider: &str,
+        provider_user_id: &str,
+        user_id: &Uuid,
+        _email: &str,
+    ) -> Result<(), error::SystemError> {
+        self.links().insert((provider.to_string(), provider_user_id.to_string()), *user_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use uuid::Uuid;
+
+use crate::{api::error, modules::oauth::repository::OAuthRepository};
+
+#[derive(Clone)]
+pub struct OAuthRepositoryPg {
+    pool: sqlx::PgPool,
+}
+
+impl OAuthRepositoryPg {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl OAuthRepository for OAuthRepositoryPg {
+    async fn find_user_id(
+        &self,
+        provider: &str,
+        provider_user_id: &str,
+    ) -> Result<Option<Uuid>, error::SystemError> {
+        let user_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT o.user_id
+            FROM oauth_accounts o
+            JOIN users u ON u.id = o.user_id
+            WHERE o.provider = $1 AND o.provider_user_id = $2 AND u.deleted_at IS NULL
+            "#,
+        )
+        .bind(provider)
+        .bind(provider_user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(user_id)
+    }
+
+    async fn link(
+        &self,
+        provider: &str,
+        provider_user_id: &str,
+        user_id: &Uuid,
+        email: &str,
+    ) -> Result<(), error::SystemError> {
+        sqlx::query(
+            r#"
+            INSERT INTO oauth_accounts (provider, provider_user_id, user_id, email)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (provider, provider_user_id)
+            DO UPDATE SET user_id = EXCLUDED.user_id, email = EXCLUDED.email
+            "#,
+        )
+        .bind(provider)
+        .bind(provider_user_id)
+        .bind(user_id)
+        .bind(email)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,13 @@
+use actix_web::web::{scope, ServiceConfig};
+use utoipa::OpenApi;
+
+use crate::modules::oauth::handle::*;
+
+pub fn public_api_configure(cfg: &mut ServiceConfig) {
+    cfg.service(scope("/auth/oauth").service(oauth_start).service(oauth_callback));
+}
+
+/// OpenAPI paths của `public_api_configure` (scope `/auth/oauth`)
+#[derive(OpenApi)]
+#[openapi(paths(oauth_start, oauth_callback))]
+pub struct OAuthApiDoc;
@@ -0,0 +1,73 @@
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+use crate::ENV;
+
+/// OAuth providers được hỗ trợ
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum OAuthProvider {
+    Google,
+    GitHub,
+}
+
+impl OAuthProvider {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "google" => Some(Self::Google),
+            "github" => Some(Self::GitHub),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Google => "google",
+            Self::GitHub => "github",
+        }
+    }
+
+    pub fn authorize_url(&self) -> &'static str {
+        match self {
+            Self::Google => "https://accounts.google.com/o/oauth2/v2/auth",
+            Self::GitHub => "https://github.com/login/oauth/authorize",
+        }
+    }
+
+    pub fn token_url(&self) -> &'static str {
+        match self {
+            Self::Google => "https://oauth2.googleapis.com/token",
+            Self::GitHub => "https://github.com/login/oauth/access_token",
+        }
+    }
+
+    pub fn scope(&self) -> &'static str {
+        match self {
+            Self::Google => "openid email profile",
+            Self::GitHub => "read:user user:email",
+        }
+    }
+
+    /// (client_id, client_secret) từ ENV, None nếu provider chưa được cấu hình
+    pub fn credentials(&self) -> Option<(&'static str, &'static str)> {
+        let (id, secret) = match self {
+            Self::Google => (&ENV.google_client_id, &ENV.google_client_secret),
+            Self::GitHub => (&ENV.github_client_id, &ENV.github_client_secret),
+        };
+        Some((id.as_deref()?, secret.as_deref()?))
+    }
+
+    pub fn redirect_uri(&self) -> String {
+        format!("{}/{}/callback", ENV.oauth_redirect_base_url, self.as_str())
+    }
+}
+
+#[allow(unused)]
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct OAuthAccountEntity {
+    pub provider: String,
+    pub provider_user_id: String,
+    pub user_id: Uuid,
+    pub email: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
@@ -0,0 +1,445 @@
+/// OAuth Service
+///
+/// Authorization-code flow cho Google/GitHub:
+/// 1. `start`: tạo state (lưu Redis), trả về URL authorize của provider
+/// 2. `callback`: verify state, đổi code lấy access token, lấy profile
+/// 3. Liên kết hoặc tạo user theo email đã verify, cấp access/refresh token như sign_in.
+///    Chỉ tự liên kết với tài khoản local đã xác thực email: tài khoản chưa xác thực có
+///    thể do người khác đăng ký trước bằng email của nạn nhân
+use rand::Rng;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::api::error;
+use crate::configs::cache::CacheBackend;
+use crate::modules::oauth::model::{
+    GitHubEmail, GitHubUser, GoogleUserInfo, OAuthIdentity, TokenResponse,
+};
+use crate::modules::oauth::repository::OAuthRepository;
+use crate::modules::oauth::schema::OAuthProvider;
+use crate::modules::user::model::{InsertUser, UpdateUser};
+use crate::modules::user::repository::UserRepository;
+use crate::modules::user::service::{username_reservation_cutoff, UserService};
+use crate::utils::UNUSABLE_PASSWORD_HASH;
+
+/// Thời gian sống của OAuth state (giây)
+const STATE_TTL: usize = 10 * 60;
+const USER_AGENT: &str = "AppChat-Backend";
+
+#[derive(Clone)]
+pub struct OAuthService {
+    oauth_repo: Arc<dyn OAuthRepository + Send + Sync>,
+    user_repo: Arc<dyn UserRepository + Send + Sync>,
+    user_service: Arc<UserService>,
+    cache: Arc<dyn CacheBackend>,
+    http: reqwest::Client,
+}
+
+impl OAuthService {
+    pub fn with_dependencies(
+        oauth_repo: Arc<dyn OAuthRepository + Send + Sync>,
+        user_repo: Arc<dyn UserRepository + Send + Sync>,
+        user_service: Arc<UserService>,
+        cache: Arc<dyn CacheBackend>,
+    ) -> Self {
+        OAuthService { oauth_repo, user_repo, user_service, cache, http: reqwest::Client::new() }
+    }
+
+    fn configured(
+        provider: &str,
+    ) -> Result<(OAuthProvider, &'static str, &'static str), error::SystemError> {
+        let provider = OAuthProvider::parse(provider)
+            .ok_or_else(|| error::SystemError::not_found("Unsupported OAuth provider"))?;
+        let (client_id, client_secret) = provider
+            .credentials()
+            .ok_or_else(|| error::SystemError::not_found("OAuth provider is not configured"))?;
+        Ok((provider, client_id, client_secret))
+    }
+
+    /// Tạo URL authorize của provider kèm state chống CSRF
+    pub async fn start(&self, provider: &str) -> Result<String, error::SystemError> {
+        let (provider, client_id, _) = Self::configured(provider)?;
+
+        let state = Uuid::now_v7().simple().to_string();
+        self.cache.set(&format!("oauth_state:{state}"), &provider.as_str(), STATE_TTL).await?;
+
+        let url = reqwest::Url::parse_with_params(
+            provider.authorize_url(),
+            &[
+                ("client_id", client_id),
+                ("redirect_uri", provider.redirect_uri().as_str()),
+                ("response_type", "code"),
+                ("scope", provider.scope()),
+                ("state", state.as_str()),
+            ],
+        )
+        .map_err(|e| error::SystemError::internal_error(e.to_string()))?;
+
+        Ok(url.to_string())
+    }
+
+    /// Xử lý callback: trả về cặp (access_token, refresh_token)
+    pub async fn callback(
+        &self,
+        provider: &str,
+        code: &str,
+        state: &str,
+    ) -> Result<(String, String), error::SystemError> {
+        let (provider, client_id, client_secret) = Self::configured(provider)?;
+
+        // State chỉ dùng được 1 lần và phải khớp provider
+        let state_key = format!("oauth_state:{state}");
+        let stored = self.cache.get::<String>(&state_key).await?;
+        self.cache.delete(&state_key).await?;
+        if stored.as_deref() != Some(provider.as_str()) {
+            return Err(error::SystemError::unauthorized("Invalid or expired OAuth state"));
+        }
+
+        let access_token = self.exchange_code(&provider, client_id, client_secret, code).await?;
+
+        let identity = match provider {
+            OAuthProvider::Google => self.fetch_google_identity(&access_token).await?,
+            OAuthProvider::GitHub => self.fetch_github_identity(&access_token).await?,
+        };
+
+        let user_id = self.resolve_user(&provider, &identity).await?;
+
+        let user = self
+            .user_repo
+            .find_by_id(&user_id)
+            .await?
+            .ok_or_else(|| error::SystemError::not_found("User not found"))?;
+
+        if user.banned_at.is_some() {
+            return Err(error::SystemError::forbidden("Account has been banned")
+                .with_code(error::ErrorCode::AccountBanned));
+        }
+
+        self.user_service.reactivate_if_deactivated(&user).await?;
+
+        self.user_service.issue_tokens(&user.id, &user.role).await
+    }
+
+    async fn exchange_code(
+        &self,
+        provider: &OAuthProvider,
+        client_id: &str,
+        client_secret: &str,
+        code: &str,
+    ) -> Result<String, error::SystemError> {
+        let redirect_uri = provider.redirect_uri();
+        let response = self
+            .http
+            .post(provider.token_url())
+            .header(reqwest::header::ACCEPT, "application/json")
+            .form(&[
+                ("client_id", client_id),
+                ("client_secret", client_secret),
+                ("code", code),
+                ("redirect_uri", redirect_uri.as_str()),
+                ("grant_type", "authorization_code"),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            tracing::warn!(
+                "OAuth token exchange with {} failed: {}",
+                provider.as_str(),
+                response.status()
+            );
+            return Err(error::SystemError::unauthorized("OAuth authorization failed"));
+        }
+
+        // GitHub trả về 200 kèm body lỗi khi code không hợp lệ -> parse thất bại
+        let token = response
+            .json::<TokenResponse>()
+            .await
+            .map_err(|_| error::SystemError::unauthorized("OAuth authorization failed"))?;
+
+        Ok(token.access_token)
+    }
+
+    async fn fetch_google_identity(
+        &self,
+        access_token: &str,
+    ) -> Result<OAuthIdentity, error::SystemError> {
+        let info = self
+            .http
+            .get("https://openidconnect.googleapis.com/v1/userinfo")
+            .bearer_auth(access_token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<GoogleUserInfo>()
+            .await?;
+
+        let email = info
+            .email
+            .filter(|_| info.email_verified)
+            .ok_or_else(|| error::SystemError::forbidden("OAuth account has no verified email"))?;
+
+        Ok(OAuthIdentity {
+            provider_user_id: info.sub,
+            email,
+            display_name: info.name,
+            avatar_url: info.picture,
+        })
+    }
+
+    async fn fetch_github_identity(
+        &self,
+        access_token: &str,
+    ) -> Result<OAuthIdentity, error::SystemError> {
+        let user = self
+            .http
+            .get("https://api.github.com/user")
+            .bearer_auth(access_token)
+            .header(reqwest::header::USER_AGENT, USER_AGENT)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<GitHubUser>()
+            .await?;
+
+        let emails = self
+            .http
+            .get("https://api.github.com/user/emails")
+            .bearer_auth(access_token)
+            .header(reqwest::header::USER_AGENT, USER_AGENT)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Vec<GitHubEmail>>()
+            .await?;
+
+        let email =
+            emails.into_iter().find(|e| e.primary && e.verified).map(|e| e.email).ok_or_else(
+                || error::SystemError::forbidden("OAuth account has no verified email"),
+            )?;
+
+        Ok(OAuthIdentity {
+            provider_user_id: user.id.to_string(),
+            email,
+            display_name: user.name.or(Some(user.login)),
+            avatar_url: user.avatar_url,
+        })
+    }
+
+    /// Tìm user đã liên kết, hoặc liên kết theo email đã verify, hoặc tạo user mới
+    async fn resolve_user(
+        &self,
+        provider: &OAuthProvider,
+        identity: &OAuthIdentity,
+    ) -> Result<Uuid, error::SystemError> {
+        if let Some(user_id) =
+            self.oauth_repo.find_user_id(provider.as_str(), &identity.provider_user_id).await?
+        {
+            return Ok(user_id);
+        }
+
+        let user_id = match self.user_repo.find_by_email(&identity.email).await? {
+            Some(user) if user.email_verified => user.id,
+            Some(_) => {
+                return Err(error::SystemError::forbidden(
+                    "An account with this email exists but its email is not verified",
+                )
+                .with_code(error::ErrorCode::EmailNotVerified));
+            }
+            None => self.create_user(identity).await?,
+        };
+
+        self.oauth_repo
+            .link(provider.as_str(), &identity.provider_user_id, &user_id, &identity.email)
+            .await?;
+
+        Ok(user_id)
+    }
+
+    async fn create_user(&self, identity: &OAuthIdentity) -> Result<Uuid, error::SystemError> {
+        let username = self.generate_username(&identity.email).await?;
+
+        // User OAuth không có mật khẩu, có thể đặt sau qua forgot-password
+        let user_id = self
+            .user_repo
+            .create(&InsertUser {
+                username: username.clone(),
+                email: identity.email.clone(),
+                hash_password: UNUSABLE_PASSWORD_HASH.to_string(),
+                display_name: identity.display_name.clone().unwrap_or(username),
+            })
+            .await?;
+
+        self.user_repo.mark_email_verified(&user_id).await?;
+
+        if let Some(avatar_url) = &identity.avatar_url {
+            self.user_repo
+                .update(
+                    &user_id,
+                    &UpdateUser {
+                        username: None,
+                        email: None,
+                        display_name: None,
+                        avatar_url: Some(Some(avatar_url.clone())),
+                        bio: None,
+                        phone: None,
+                        discoverable: None,
+                    },
+                )
+                .await?;
+        }
+
+        Ok(user_id)
+    }
+
+    /// Sinh username từ phần local của email, thêm hậu tố số nếu bị trùng
+    async fn generate_username(&self, email: &str) -> Result<String, error::SystemError> {
+        let local = email.split('@').next().unwrap_or_default();
+        let mut base: String = local
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .take(20)
+            .collect::<String>()
+            .to_lowercase();
+        if base.len() < 3 {
+            base = format!("user{base}");
+        }
+
+        let reserved_since = username_reservation_cutoff();
+        if self.user_repo.is_username_available(&base, None, reserved_since).await? {
+            return Ok(base);
+        }
+
+        for _ in 0..5 {
+            let candidate = format!("{}{}", base, rand::thread_rng().gen_range(1000..10000));
+            if self.user_repo.is_username_available(&candidate, None, reserved_since).await? {
+                return Ok(candidate);
+            }
+        }
+
+        Ok(format!("{}{}", base, Uuid::now_v7().simple()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix::Actor;
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+    use super::*;
+    use crate::{
+        configs::{cache::MemoryCache, mailer::LogMailer},
+        constants::init_test_env,
+        modules::{
+            audit::{repository_mock::AuditLogRepositoryMock, service::AuditService},
+            notification::repository_mock::NotificationRepositoryMock,
+            oauth::repository_mock::OAuthRepositoryMock,
+            report::{moderation::ContentFilter, repository_pg::ReportRepositoryPg},
+            user::{cache::LocalProfileCache, repository_mock::UserRepositoryMock},
+            websocket::server::WebSocketServer,
+        },
+    };
+
+    fn service(oauth_repo: &OAuthRepositoryMock, user_repo: &UserRepositoryMock) -> OAuthService {
+        init_test_env();
+        let cache = Arc::new(MemoryCache::default());
+        // Report repository chỉ được dùng khi nội dung bị flag, pool không bao giờ kết nối
+        let reports = ReportRepositoryPg::new(
+            PgPoolOptions::new().connect_lazy_with(PgConnectOptions::new()),
+        );
+        let user_service = UserService::with_dependencies(
+            Arc::new(user_repo.clone()),
+            cache.clone(),
+            Arc::new(LogMailer),
+            Arc::new(WebSocketServer::new().start()),
+            LocalProfileCache::default(),
+            ContentFilter::from_env(Arc::new(reports)),
+            AuditService::with_dependencies(
+                Arc::new(AuditLogRepositoryMock::default()),
+                Arc::new(NotificationRepositoryMock::default()),
+            ),
+        );
+
+        OAuthService::with_dependencies(
+            Arc::new(oauth_repo.clone()),
+            Arc::new(user_repo.clone()),
+            Arc::new(user_service),
+            cache,
+        )
+    }
+
+    fn identity(email: &str) -> OAuthIdentity {
+        OAuthIdentity {
+            provider_user_id: "12345".to_string(),
+            email: email.to_string(),
+            display_name: Some("Alice".to_string()),
+            avatar_url: None,
+        }
+    }
+
+    #[actix_web::test]
+    async fn resolve_user_links_verified_account_with_same_email() {
+        let (oauth_repo, user_repo) =
+            (OAuthRepositoryMock::default(), UserRepositoryMock::default());
+        let service = service(&oauth_repo, &user_repo);
+        let user_id = user_repo.insert(UserRepositoryMock::entity("alice", "hash"));
+
+        let resolved =
+            service.resolve_user(&OAuthProvider::GitHub, &identity("alice@example.com")).await;
+
+        assert_eq!(resolved.unwrap(), user_id);
+        assert_eq!(oauth_repo.linked_user("github", "12345"), Some(user_id));
+    }
+
+    #[actix_web::test]
+    async fn resolve_user_rejects_unverified_account_with_same_email() {
+        let (oauth_repo, user_repo) =
+            (OAuthRepositoryMock::default(), UserRepositoryMock::default());
+        let service = service(&oauth_repo, &user_repo);
+        let mut user = UserRepositoryMock::entity("alice", "hash");
+        user.email_verified = false;
+        let user_id = user_repo.insert(user);
+
+        let err = service
+            .resolve_user(&OAuthProvider::GitHub, &identity("alice@example.com"))
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.code(), error::ErrorCode::EmailNotVerified);
+        assert_eq!(oauth_repo.linked_user("github", "12345"), None);
+        assert!(!user_repo.get(&user_id).unwrap().email_verified);
+    }
+
+    #[actix_web::test]
+    async fn resolve_user_creates_verified_user_without_password() {
+        let (oauth_repo, user_repo) =
+            (OAuthRepositoryMock::default(), UserRepositoryMock::default());
+        let service = service(&oauth_repo, &user_repo);
+
+        let user_id = service
+            .resolve_user(&OAuthProvider::Google, &identity("new.user@example.com"))
+            .await
+            .unwrap();
+
+        let user = user_repo.get(&user_id).unwrap();
+        assert!(user.email_verified);
+        assert_eq!(user.hash_password, UNUSABLE_PASSWORD_HASH);
+        assert_eq!(user.username, "newuser");
+        assert_eq!(oauth_repo.linked_user("google", "12345"), Some(user_id));
+    }
+
+    #[actix_web::test]
+    async fn resolve_user_prefers_existing_link_over_email() {
+        let (oauth_repo, user_repo) =
+            (OAuthRepositoryMock::default(), UserRepositoryMock::default());
+        let service = service(&oauth_repo, &user_repo);
+        let linked_id = user_repo.insert(UserRepositoryMock::entity("bob", "hash"));
+        user_repo.insert(UserRepositoryMock::entity("alice", "hash"));
+        oauth_repo.link("github", "12345", &linked_id, "bob@example.com").await.unwrap();
+
+        let resolved =
+            service.resolve_user(&OAuthProvider::GitHub, &identity("alice@example.com")).await;
+
+        assert_eq!(resolved.unwrap(), linked_id);
+    }
+}
@@ -0,0 +1,97 @@
+use actix_web::{get, post, web, HttpRequest};
+use uuid::Uuid;
+
+use crate::{
+    api::{error, success},
+    middlewares::get_extensions,
+    modules::report::{
+        model::{CreateReportModel, ReportListResponse, ReportQuery, ResolveReportModel},
+        schema::ReportEntity,
+        service::ReportService,
+    },
+    utils::{Claims, ValidatedJson, ValidatedQuery},
+};
+
+#[utoipa::path(
+    tag = "reports",
+    request_body = CreateReportModel,
+    responses((status = 200, body = success::SuccessData<ReportEntity>))
+)]
+#[post("")]
+pub async fn create_report(
+    report_service: web::Data<ReportService>,
+    ValidatedJson(body): ValidatedJson<CreateReportModel>,
+    req: HttpRequest,
+) -> Result<success::Success<ReportEntity>, error::Error> {
+    let reporter_id = get_extensions::<Claims>(&req)?.sub;
+    let report = report_service
+        .create_report(reporter_id, body.target_type, body.target_id, body.reason)
+        .await?;
+
+    Ok(success::Success::created(Some(report)).message("Report submitted successfully"))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    params(ReportQuery),
+    responses((status = 200, body = success::SuccessData<ReportListResponse>))
+)]
+#[get("")]
+pub async fn list_reports(
+    report_service: web::Data<ReportService>,
+    ValidatedQuery(query): ValidatedQuery<ReportQuery>,
+) -> Result<success::Success<ReportListResponse>, error::Error> {
+    let reports = report_service
+        .list_reports(
+            query.status,
+            query.target_type,
+            query.page.unwrap_or(1),
+            query.limit.unwrap_or(20),
+        )
+        .await?;
+
+    Ok(success::Success::ok(Some(reports)).message("Reports retrieved successfully"))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    path = "/{id}",
+    params(("id" = Uuid, Path, description = "Report ID")),
+    responses(
+        (status = 200, body = success::SuccessData<ReportEntity>),
+        (status = 404, description = "Không tìm thấy report", body = error::ErrorBody)
+    )
+)]
+#[get("/{id:[0-9a-fA-F-]{36}}")]
+pub async fn get_report(
+    report_service: web::Data<ReportService>,
+    report_id: web::Path<Uuid>,
+) -> Result<success::Success<ReportEntity>, error::Error> {
+    let report = report_service.get_report(report_id.into_inner()).await?;
+    Ok(success::Success::ok(Some(report)).message("Report retrieved successfully"))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    path = "/{id}/resolve",
+    params(("id" = Uuid, Path, description = "Report ID")),
+    request_body = ResolveReportModel,
+    responses(
+        (status = 200, body = success::SuccessData<ReportEntity>),
+        (status = 404, description = "Không tìm thấy report", body = error::ErrorBody)
+    )
+)]
+#[post("/{id:[0-9a-fA-F-]{36}}/resolve")]
+pub async fn resolve_report(
+    report_service: web::Data<ReportService>,
+    report_id: web::Path<Uuid>,
+    ValidatedJson(body): ValidatedJson<ResolveReportModel>,
+    req: HttpRequest,
+) -> Result<success::Success<ReportEntity>, error::Error> {
+    let admin_id = get_extensions::<Claims>(&req)?.sub;
+    let report = report_service
+        .resolve_report(admin_id, report_id.into_inner(), body.status, body.note)
+        .await?;
+
+    Ok(success::Success::ok(Some(report)).message("Report resolved successfully"))
+}
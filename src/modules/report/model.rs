@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::modules::report::schema::{ReportEntity, ReportStatus, ReportTargetType};
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct CreateReportModel {
+    pub target_type: ReportTargetType,
+    pub target_id: Uuid,
+    #[validate(length(
+        min = 1,
+        max = 500,
+        message = "Reason must be between 1 and 500 characters"
+    ))]
+    pub reason: String,
+}
+
+#[derive(Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReportQuery {
+    pub status: Option<ReportStatus>,
+    pub target_type: Option<ReportTargetType>,
+    #[validate(range(min = 1, message = "Page must be at least 1"))]
+    pub page: Option<i64>,
+    #[validate(range(min = 1, max = 100, message = "Limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct ResolveReportModel {
+    /// `resolved` hoặc `dismissed`
+    pub status: ReportStatus,
+    #[validate(length(max = 500, message = "Note must be at most 500 characters"))]
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReportListResponse {
+    pub reports: Vec<ReportEntity>,
+    pub total: i64,
+    pub page: i64,
+    pub limit: i64,
+}
@@ -0,0 +1,233 @@
+/// Content Moderation
+///
+/// `ContentFilter` được gọi trước khi lưu nội dung do user nhập (tin nhắn, tên group,
+/// bio). Việc phát hiện vi phạm do một `ContentModerator` đảm nhiệm (mặc định là
+/// `WordlistModerator`), còn cách xử lý theo `MODERATION_ACTION`:
+/// - `reject`: từ chối request (400)
+/// - `mask`: thay các từ vi phạm bằng `*` rồi lưu
+/// - `flag`: lưu nguyên văn và tạo report tự động (không có reporter) để admin kiểm duyệt
+///
+/// Report tự động không tính vào ngưỡng tự ẩn tin nhắn (`REPORT_HIDE_THRESHOLD`).
+use std::collections::HashSet;
+use std::ops::Range;
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::report::{repository::ReportRepository, schema::ReportTargetType},
+    ENV,
+};
+
+/// Wordlist dùng khi `MODERATION_WORDLIST` không được cấu hình
+const DEFAULT_WORDLIST: &[&str] = &[
+    "asshole",
+    "bastard",
+    "bitch",
+    "bullshit",
+    "cunt",
+    "dickhead",
+    "fuck",
+    "fucker",
+    "fucking",
+    "motherfucker",
+    "shit",
+    "slut",
+    "whore",
+];
+
+/// Độ dài tối đa của `reports.reason`
+const MAX_REASON_LEN: usize = 500;
+
+/// Phát hiện nội dung vi phạm, implement trait này để thay wordlist bằng
+/// classifier / dịch vụ kiểm duyệt khác
+pub trait ContentModerator: Send + Sync {
+    /// Vị trí (byte range) của các đoạn vi phạm trong `text`, không chồng lấn nhau
+    fn find_violations(&self, text: &str) -> Vec<Range<usize>>;
+}
+
+/// So khớp nguyên từ (không phân biệt hoa thường) với danh sách từ cấm
+pub struct WordlistModerator {
+    words: HashSet<String>,
+}
+
+impl WordlistModerator {
+    pub fn new<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let words = words.into_iter().map(|word| word.as_ref().trim().to_lowercase()).collect();
+        Self { words }
+    }
+
+    /// Wordlist từ `MODERATION_WORDLIST`, rỗng thì dùng `DEFAULT_WORDLIST`
+    pub fn from_env() -> Self {
+        if ENV.moderation_wordlist.is_empty() {
+            Self::new(DEFAULT_WORDLIST)
+        } else {
+            Self::new(&ENV.moderation_wordlist)
+        }
+    }
+}
+
+impl ContentModerator for WordlistModerator {
+    fn find_violations(&self, text: &str) -> Vec<Range<usize>> {
+        let mut violations = Vec::new();
+        let mut start = None;
+
+        // Tách từ theo ký tự chữ / số để "class" không khớp "ass"
+        for (index, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+            match (c.is_alphanumeric(), start) {
+                (true, None) => start = Some(index),
+                (false, Some(begin)) => {
+                    if self.words.contains(&text[begin..index].to_lowercase()) {
+                        violations.push(begin..index);
+                    }
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+
+        violations
+    }
+}
+
+/// Cách xử lý nội dung vi phạm (`MODERATION_ACTION`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModerationAction {
+    Reject,
+    Mask,
+    Flag,
+}
+
+impl ModerationAction {
+    pub fn from_env() -> Self {
+        match ENV.moderation_action.as_str() {
+            "reject" => ModerationAction::Reject,
+            "mask" => ModerationAction::Mask,
+            _ => ModerationAction::Flag,
+        }
+    }
+}
+
+/// Loại nội dung được kiểm duyệt, dùng trong thông báo lỗi và lý do report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentKind {
+    Message,
+    GroupName,
+    GroupDescription,
+    Bio,
+}
+
+impl std::fmt::Display for ContentKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContentKind::Message => f.write_str("message"),
+            ContentKind::GroupName => f.write_str("group name"),
+            ContentKind::GroupDescription => f.write_str("group description"),
+            ContentKind::Bio => f.write_str("bio"),
+        }
+    }
+}
+
+/// Nội dung sau kiểm duyệt
+#[derive(Debug, Clone)]
+pub struct Moderated {
+    /// Nội dung được phép lưu (đã mask nếu action là `mask`)
+    pub content: String,
+    /// Các từ vi phạm cần đưa vào hàng đợi kiểm duyệt (chỉ với action `flag`)
+    pub flagged_terms: Vec<String>,
+}
+
+impl Moderated {
+    pub fn is_flagged(&self) -> bool {
+        !self.flagged_terms.is_empty()
+    }
+}
+
+#[derive(Clone)]
+pub struct ContentFilter {
+    moderator: Arc<dyn ContentModerator>,
+    action: ModerationAction,
+    reports: Arc<dyn ReportRepository + Send + Sync>,
+}
+
+impl ContentFilter {
+    pub fn new(
+        moderator: Arc<dyn ContentModerator>,
+        action: ModerationAction,
+        reports: Arc<dyn ReportRepository + Send + Sync>,
+    ) -> Self {
+        ContentFilter { moderator, action, reports }
+    }
+
+    /// Filter với wordlist và action từ biến môi trường
+    pub fn from_env(reports: Arc<dyn ReportRepository + Send + Sync>) -> Self {
+        Self::new(Arc::new(WordlistModerator::from_env()), ModerationAction::from_env(), reports)
+    }
+
+    /// Kiểm duyệt nội dung trước khi lưu, trả về lỗi 400 nếu action là `reject`
+    pub fn check(&self, kind: ContentKind, text: String) -> Result<Moderated, error::SystemError> {
+        let violations = self.moderator.find_violations(&text);
+        if violations.is_empty() {
+            return Ok(Moderated { content: text, flagged_terms: Vec::new() });
+        }
+
+        match self.action {
+            ModerationAction::Reject => Err(error::SystemError::bad_request(format!(
+                "The {} contains inappropriate language",
+                kind
+            ))),
+            ModerationAction::Mask => {
+                let mut masked = String::with_capacity(text.len());
+                let mut last = 0;
+                for range in violations {
+                    masked.push_str(&text[last..range.start]);
+                    masked.push_str(&"*".repeat(text[range.clone()].chars().count()));
+                    last = range.end;
+                }
+                masked.push_str(&text[last..]);
+
+                Ok(Moderated { content: masked, flagged_terms: Vec::new() })
+            }
+            ModerationAction::Flag => {
+                let mut terms: Vec<String> =
+                    violations.into_iter().map(|range| text[range].to_lowercase()).collect();
+                terms.sort();
+                terms.dedup();
+
+                Ok(Moderated { content: text, flagged_terms: terms })
+            }
+        }
+    }
+
+    /// Đưa nội dung bị flag vào hàng đợi kiểm duyệt; lỗi chỉ được log, không làm hỏng
+    /// request vì nội dung đã được lưu
+    pub async fn flag(
+        &self,
+        target_type: ReportTargetType,
+        target_id: Uuid,
+        kind: ContentKind,
+        moderated: &Moderated,
+    ) {
+        if !moderated.is_flagged() {
+            return;
+        }
+
+        let mut reason = format!("Auto-flagged {}: {}", kind, moderated.flagged_terms.join(", "));
+        if reason.len() > MAX_REASON_LEN {
+            let mut end = MAX_REASON_LEN;
+            while !reason.is_char_boundary(end) {
+                end -= 1;
+            }
+            reason.truncate(end);
+        }
+
+        if let Err(e) = self.reports.create_flag(target_type, &target_id, &reason).await {
+            tracing::warn!("Failed to queue flagged {} {} for review: {:?}", kind, target_id, e);
+        }
+    }
+}
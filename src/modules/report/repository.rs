@@ -0,0 +1,65 @@
+use uuid::Uuid;
+
+use crate::api::error;
+use crate::modules::report::schema::{ReportEntity, ReportStatus, ReportTargetType};
+
+#[async_trait::async_trait]
+pub trait ReportRepository {
+    /// Trả về `sender_id` của tin nhắn chưa xóa mà `user_id` có quyền xem
+    /// (là member active của conversation)
+    async fn find_message_sender(
+        &self,
+        message_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<Option<Uuid>, error::SystemError>;
+
+    async fn user_exists(&self, user_id: &Uuid) -> Result<bool, error::SystemError>;
+
+    /// Tạo report, trả về `None` nếu reporter đã report target này trước đó
+    async fn create(
+        &self,
+        reporter_id: &Uuid,
+        target_type: ReportTargetType,
+        target_id: &Uuid,
+        reason: &str,
+    ) -> Result<Option<ReportEntity>, error::SystemError>;
+
+    /// Tạo report tự động (không có reporter) cho nội dung bị content filter flag
+    async fn create_flag(
+        &self,
+        target_type: ReportTargetType,
+        target_id: &Uuid,
+        reason: &str,
+    ) -> Result<ReportEntity, error::SystemError>;
+
+    /// Số reporter khác nhau của target, không tính các report đã bị dismiss
+    async fn count_reporters(
+        &self,
+        target_type: ReportTargetType,
+        target_id: &Uuid,
+    ) -> Result<i64, error::SystemError>;
+
+    /// Ẩn tin nhắn, trả về `conversation_id` nếu tin nhắn vừa được ẩn (chưa ẩn trước đó)
+    async fn hide_message(&self, message_id: &Uuid) -> Result<Option<Uuid>, error::SystemError>;
+
+    async fn unhide_message(&self, message_id: &Uuid) -> Result<bool, error::SystemError>;
+
+    async fn find_by_id(&self, id: &Uuid) -> Result<Option<ReportEntity>, error::SystemError>;
+
+    async fn list(
+        &self,
+        status: Option<ReportStatus>,
+        target_type: Option<ReportTargetType>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<ReportEntity>, i64), error::SystemError>;
+
+    /// Đóng report đang mở, trả về `None` nếu report không tồn tại hoặc đã được xử lý
+    async fn resolve(
+        &self,
+        id: &Uuid,
+        status: ReportStatus,
+        resolved_by: &Uuid,
+        note: Option<&str>,
+    ) -> Result<Option<ReportEntity>, error::SystemError>;
+}
@@ -0,0 +1,225 @@
+/// In-memory `ReportRepository` cho unit tests của services
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::report::{
+        repository::ReportRepository,
+        schema::{ReportEntity, ReportStatus, ReportTargetType},
+    },
+};
+
+struct MockMessage {
+    conversation_id: Uuid,
+    sender_id: Uuid,
+    hidden: bool,
+}
+
+#[derive(Default)]
+struct State {
+    users: HashSet<Uuid>,
+    /// (conversation_id, user_id)
+    members: HashSet<(Uuid, Uuid)>,
+    messages: HashMap<Uuid, MockMessage>,
+    reports: Vec<ReportEntity>,
+}
+
+/// Clone dùng chung dữ liệu, test giữ một bản để tạo users / tin nhắn và kiểm tra
+/// trạng thái ẩn
+#[derive(Clone, Default)]
+pub struct ReportRepositoryMock {
+    state: Arc<Mutex<State>>,
+}
+
+impl ReportRepositoryMock {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn add_user(&self) -> Uuid {
+        let user_id = Uuid::now_v7();
+        self.state().users.insert(user_id);
+        user_id
+    }
+
+    pub fn add_member(&self, conversation_id: Uuid, user_id: Uuid) {
+        self.state().members.insert((conversation_id, user_id));
+    }
+
+    pub fn add_message(&self, conversation_id: Uuid, sender_id: Uuid) -> Uuid {
+        let message_id = Uuid::now_v7();
+        self.state()
+            .messages
+            .insert(message_id, MockMessage { conversation_id, sender_id, hidden: false });
+        message_id
+    }
+
+    pub fn is_hidden(&self, message_id: &Uuid) -> bool {
+        self.state().messages.get(message_id).is_some_and(|message| message.hidden)
+    }
+}
+
+#[async_trait::async_trait]
+impl ReportRepository for ReportRepositoryMock {
+    async fn find_message_sender(
+        &self,
+        message_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<Option<Uuid>, error::SystemError> {
+        let state = self.state();
+        Ok(state
+            .messages
+            .get(message_id)
+            .filter(|message| state.members.contains(&(message.conversation_id, *user_id)))
+            .map(|message| message.sender_id))
+    }
+
+    async fn user_exists(&self, user_id: &Uuid) -> Result<bool, error::SystemError> {
+        Ok(self.state().users.contains(user_id))
+    }
+
+    async fn create(
+        &self,
+        reporter_id: &Uuid,
+        target_type: ReportTargetType,
+        target_id: &Uuid,
+        reason: &str,
+    ) -> Result<Option<ReportEntity>, error::SystemError> {
+        let mut state = self.state();
+        let duplicate = state.reports.iter().any(|report| {
+            report.reporter_id == Some(*reporter_id)
+                && report.target_type == target_type
+                && report.target_id == *target_id
+        });
+        if duplicate {
+            return Ok(None);
+        }
+
+        let report = report(Some(*reporter_id), target_type, *target_id, reason);
+        state.reports.push(report.clone());
+        Ok(Some(report))
+    }
+
+    async fn create_flag(
+        &self,
+        target_type: ReportTargetType,
+        target_id: &Uuid,
+        reason: &str,
+    ) -> Result<ReportEntity, error::SystemError> {
+        let report = report(None, target_type, *target_id, reason);
+        self.state().reports.push(report.clone());
+        Ok(report)
+    }
+
+    async fn count_reporters(
+        &self,
+        target_type: ReportTargetType,
+        target_id: &Uuid,
+    ) -> Result<i64, error::SystemError> {
+        let reporters: HashSet<Uuid> = self
+            .state()
+            .reports
+            .iter()
+            .filter(|report| report.target_type == target_type && report.target_id == *target_id)
+            .filter(|report| report.status != ReportStatus::Dismissed)
+            .filter_map(|report| report.reporter_id)
+            .collect();
+        Ok(reporters.len() as i64)
+    }
+
+    async fn hide_message(&self, message_id: &Uuid) -> Result<Option<Uuid>, error::SystemError> {
+        let mut state = self.state();
+        let Some(message) = state.messages.get_mut(message_id).filter(|message| !message.hidden)
+        else {
+            return Ok(None);
+        };
+        message.hidden = true;
+        Ok(Some(message.conversation_id))
+    }
+
+    async fn unhide_message(&self, message_id: &Uuid) -> Result<bool, error::SystemError> {
+        let mut state = self.state();
+        let Some(message) = state.messages.get_mut(message_id).filter(|message| message.hidden)
+        else {
+            return Ok(false);
+        };
+        message.hidden = false;
+        Ok(true)
+    }
+
+    async fn find_by_id(&self, id: &Uuid) -> Result<Option<ReportEntity>, error::SystemError> {
+        Ok(self.state().reports.iter().find(|report| report.id == *id).cloned())
+    }
+
+    async fn list(
+        &self,
+        status: Option<ReportStatus>,
+        target_type: Option<ReportTargetType>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<ReportEntity>, i64), error::SystemError> {
+        let mut reports: Vec<ReportEntity> = self
+            .state()
+            .reports
+            .iter()
+            .filter(|report| status.is_none_or(|status| report.status == status))
+            .filter(|report| {
+                target_type.is_none_or(|target_type| report.target_type == target_type)
+            })
+            .cloned()
+            .collect();
+        reports.sort_by_key(|report| std::cmp::Reverse(report.created_at));
+
+        let total = reports.len() as i64;
+        let page = reports.into_iter().skip(offset as usize).take(limit as usize).collect();
+        Ok((page, total))
+    }
+
+    async fn resolve(
+        &self,
+        id: &Uuid,
+        status: ReportStatus,
+        resolved_by: &Uuid,
+        note: Option<&str>,
+    ) -> Result<Option<ReportEntity>, error::SystemError> {
+        let mut state = self.state();
+        let Some(report) = state
+            .reports
+            .iter_mut()
+            .find(|report| report.id == *id && report.status == ReportStatus::Open)
+        else {
+            return Ok(None);
+        };
+        report.status = status;
+        report.resolved_by = Some(*resolved_by);
+        report.resolution_note = note.map(str::to_string);
+        report.resolved_at = Some(Utc::now());
+        Ok(Some(report.clone()))
+    }
+}
+
+fn report(
+    reporter_id: Option<Uuid>,
+    target_type: ReportTargetType,
+    target_id: Uuid,
+    reason: &str,
+) -> ReportEntity {
+    ReportEntity {
+        id: Uuid::now_v7(),
+        reporter_id,
+        target_type,
+        target_id,
+        reason: reason.to_string(),
+        status: ReportStatus::Open,
+        resolved_by: None,
+        resolution_note: None,
+        created_at: Utc::now(),
+        resolved_at: None,
+    }
+}
@@ -0,0 +1,235 @@
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::report::{
+        repository::ReportRepository,
+        schema::{ReportEntity, ReportStatus, ReportTargetType},
+    },
+};
+
+#[derive(Clone)]
+pub struct ReportRepositoryPg {
+    pool: sqlx::PgPool,
+}
+
+impl ReportRepositoryPg {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl ReportRepository for ReportRepositoryPg {
+    async fn find_message_sender(
+        &self,
+        message_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<Option<Uuid>, error::SystemError> {
+        let sender_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT m.sender_id
+            FROM messages m
+            JOIN participants p
+                ON p.conversation_id = m.conversation_id
+               AND p.user_id = $2
+               AND p.status = 'active'
+               AND p.deleted_at IS NULL
+            WHERE m.id = $1
+              AND m.deleted_at IS NULL
+            "#,
+        )
+        .bind(message_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(sender_id)
+    }
+
+    async fn user_exists(&self, user_id: &Uuid) -> Result<bool, error::SystemError> {
+        let exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM users WHERE id = $1 AND deleted_at IS NULL)",
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(exists)
+    }
+
+    async fn create(
+        &self,
+        reporter_id: &Uuid,
+        target_type: ReportTargetType,
+        target_id: &Uuid,
+        reason: &str,
+    ) -> Result<Option<ReportEntity>, error::SystemError> {
+        let report = sqlx::query_as::<_, ReportEntity>(
+            r#"
+            INSERT INTO reports (reporter_id, target_type, target_id, reason)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (reporter_id, target_type, target_id) DO NOTHING
+            RETURNING *
+            "#,
+        )
+        .bind(reporter_id)
+        .bind(target_type)
+        .bind(target_id)
+        .bind(reason)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(report)
+    }
+
+    async fn create_flag(
+        &self,
+        target_type: ReportTargetType,
+        target_id: &Uuid,
+        reason: &str,
+    ) -> Result<ReportEntity, error::SystemError> {
+        let report = sqlx::query_as::<_, ReportEntity>(
+            r#"
+            INSERT INTO reports (target_type, target_id, reason)
+            VALUES ($1, $2, $3)
+            RETURNING *
+            "#,
+        )
+        .bind(target_type)
+        .bind(target_id)
+        .bind(reason)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(report)
+    }
+
+    async fn count_reporters(
+        &self,
+        target_type: ReportTargetType,
+        target_id: &Uuid,
+    ) -> Result<i64, error::SystemError> {
+        let count = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(DISTINCT reporter_id)
+            FROM reports
+            WHERE target_type = $1
+              AND target_id = $2
+              AND status <> 'dismissed'
+            "#,
+        )
+        .bind(target_type)
+        .bind(target_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn hide_message(&self, message_id: &Uuid) -> Result<Option<Uuid>, error::SystemError> {
+        let conversation_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            UPDATE messages
+            SET hidden_at = NOW()
+            WHERE id = $1
+              AND hidden_at IS NULL
+              AND deleted_at IS NULL
+            RETURNING conversation_id
+            "#,
+        )
+        .bind(message_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(conversation_id)
+    }
+
+    async fn unhide_message(&self, message_id: &Uuid) -> Result<bool, error::SystemError> {
+        let rows = sqlx::query(
+            "UPDATE messages SET hidden_at = NULL WHERE id = $1 AND hidden_at IS NOT NULL",
+        )
+        .bind(message_id)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows > 0)
+    }
+
+    async fn find_by_id(&self, id: &Uuid) -> Result<Option<ReportEntity>, error::SystemError> {
+        let report = sqlx::query_as::<_, ReportEntity>("SELECT * FROM reports WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(report)
+    }
+
+    async fn list(
+        &self,
+        status: Option<ReportStatus>,
+        target_type: Option<ReportTargetType>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<ReportEntity>, i64), error::SystemError> {
+        let total: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM reports
+            WHERE ($1::report_status IS NULL OR status = $1)
+            AND ($2::report_target_type IS NULL OR target_type = $2)
+            "#,
+        )
+        .bind(status)
+        .bind(target_type)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let reports = sqlx::query_as::<_, ReportEntity>(
+            r#"
+            SELECT * FROM reports
+            WHERE ($1::report_status IS NULL OR status = $1)
+            AND ($2::report_target_type IS NULL OR target_type = $2)
+            ORDER BY created_at DESC
+            LIMIT $3 OFFSET $4
+            "#,
+        )
+        .bind(status)
+        .bind(target_type)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok((reports, total))
+    }
+
+    async fn resolve(
+        &self,
+        id: &Uuid,
+        status: ReportStatus,
+        resolved_by: &Uuid,
+        note: Option<&str>,
+    ) -> Result<Option<ReportEntity>, error::SystemError> {
+        let report = sqlx::query_as::<_, ReportEntity>(
+            r#"
+            UPDATE reports
+            SET status = $2,
+                resolved_by = $3,
+                resolution_note = $4,
+                resolved_at = NOW()
+            WHERE id = $1
+              AND status = 'open'
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(status)
+        .bind(resolved_by)
+        .bind(note)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(report)
+    }
+}
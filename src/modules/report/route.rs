@@ -0,0 +1,24 @@
+use crate::modules::report::handle::*;
+use actix_web::web::{scope, ServiceConfig};
+use utoipa::OpenApi;
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(scope("/reports").service(create_report));
+}
+
+/// Routes kiểm duyệt report, mount dưới scope `/admin`
+pub fn admin_configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        scope("/reports").service(list_reports).service(get_report).service(resolve_report),
+    );
+}
+
+/// OpenAPI paths của `configure` (scope `/reports`)
+#[derive(OpenApi)]
+#[openapi(paths(create_report))]
+pub struct ReportApiDoc;
+
+/// OpenAPI paths của `admin_configure` (scope `/reports`)
+#[derive(OpenApi)]
+#[openapi(paths(list_reports, get_report, resolve_report))]
+pub struct AdminReportApiDoc;
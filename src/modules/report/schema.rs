@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::{FromRow, Type};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Loại đối tượng bị report
+#[derive(Debug, PartialEq, Clone, Copy, Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "report_target_type", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ReportTargetType {
+    Message,
+    User,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "report_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ReportStatus {
+    Open,
+    Resolved,
+    Dismissed,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct ReportEntity {
+    pub id: Uuid,
+    /// `None` với report tự động từ content filter
+    pub reporter_id: Option<Uuid>,
+    pub target_type: ReportTargetType,
+    pub target_id: Uuid,
+    pub reason: String,
+    pub status: ReportStatus,
+    pub resolved_by: Option<Uuid>,
+    pub resolution_note: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub resolved_at: Option<chrono::DateTime<chrono::Utc>>,
+}
@@ -0,0 +1,317 @@
+/// Report Service
+///
+/// Cho phép user report tin nhắn hoặc user khác để admin kiểm duyệt.
+/// Tin nhắn bị report bởi đủ `REPORT_HIDE_THRESHOLD` user khác nhau sẽ tự động bị ẩn
+/// khỏi lịch sử conversation cho tới khi admin dismiss report.
+use std::sync::Arc;
+
+use actix::Addr;
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::{
+        report::{
+            model::ReportListResponse,
+            repository::ReportRepository,
+            schema::{ReportEntity, ReportStatus, ReportTargetType},
+        },
+        websocket::{events::BroadcastToRoom, message::ServerMessage, server::WebSocketServer},
+    },
+    ENV,
+};
+
+#[derive(Clone)]
+pub struct ReportService {
+    repo: Arc<dyn ReportRepository + Send + Sync>,
+    ws_server: Arc<Addr<WebSocketServer>>,
+}
+
+impl ReportService {
+    pub fn with_dependencies(
+        repo: Arc<dyn ReportRepository + Send + Sync>,
+        ws_server: Arc<Addr<WebSocketServer>>,
+    ) -> Self {
+        ReportService { repo, ws_server }
+    }
+
+    pub async fn create_report(
+        &self,
+        reporter_id: Uuid,
+        target_type: ReportTargetType,
+        target_id: Uuid,
+        reason: String,
+    ) -> Result<ReportEntity, error::SystemError> {
+        let reason = reason.trim();
+        if reason.is_empty() {
+            return Err(error::SystemError::bad_request("Reason must not be empty"));
+        }
+
+        // Chỉ report được tin nhắn trong conversation mà reporter là member
+        match target_type {
+            ReportTargetType::Message => {
+                let sender_id = self
+                    .repo
+                    .find_message_sender(&target_id, &reporter_id)
+                    .await?
+                    .ok_or_else(|| error::SystemError::not_found("Message not found"))?;
+                if sender_id == reporter_id {
+                    return Err(error::SystemError::bad_request(
+                        "You cannot report your own message",
+                    ));
+                }
+            }
+            ReportTargetType::User => {
+                if target_id == reporter_id {
+                    return Err(error::SystemError::bad_request("You cannot report yourself"));
+                }
+                if !self.repo.user_exists(&target_id).await? {
+                    return Err(error::SystemError::not_found("User not found"));
+                }
+            }
+        }
+
+        let report = self
+            .repo
+            .create(&reporter_id, target_type, &target_id, reason)
+            .await?
+            .ok_or_else(|| error::SystemError::bad_request("You have already reported this"))?;
+
+        if target_type == ReportTargetType::Message {
+            self.hide_message_if_over_threshold(target_id).await?;
+        }
+
+        Ok(report)
+    }
+
+    /// Ẩn tin nhắn khi số reporter khác nhau đạt ngưỡng và báo cho các member đang online
+    async fn hide_message_if_over_threshold(
+        &self,
+        message_id: Uuid,
+    ) -> Result<(), error::SystemError> {
+        let reporters = self.repo.count_reporters(ReportTargetType::Message, &message_id).await?;
+        if reporters < ENV.report_hide_threshold {
+            return Ok(());
+        }
+
+        if let Some(conversation_id) = self.repo.hide_message(&message_id).await? {
+            tracing::info!("Message {} hidden after {} reports", message_id, reporters);
+
+            self.ws_server.do_send(BroadcastToRoom {
+                conversation_id,
+                message: ServerMessage::MessageHidden { conversation_id, message_id },
+                skip_user_id: None,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Admin: danh sách report, mới nhất trước
+    pub async fn list_reports(
+        &self,
+        status: Option<ReportStatus>,
+        target_type: Option<ReportTargetType>,
+        page: i64,
+        limit: i64,
+    ) -> Result<ReportListResponse, error::SystemError> {
+        let offset = (page - 1) * limit;
+        let (reports, total) = self.repo.list(status, target_type, limit, offset).await?;
+
+        Ok(ReportListResponse { reports, total, page, limit })
+    }
+
+    pub async fn get_report(&self, id: Uuid) -> Result<ReportEntity, error::SystemError> {
+        self.repo
+            .find_by_id(&id)
+            .await?
+            .ok_or_else(|| error::SystemError::not_found("Report not found"))
+    }
+
+    /// Admin: đóng report. Dismiss report của tin nhắn sẽ hiện lại tin nhắn
+    /// nếu số report còn hiệu lực xuống dưới ngưỡng
+    pub async fn resolve_report(
+        &self,
+        admin_id: Uuid,
+        id: Uuid,
+        status: ReportStatus,
+        note: Option<String>,
+    ) -> Result<ReportEntity, error::SystemError> {
+        if status == ReportStatus::Open {
+            return Err(error::SystemError::bad_request(
+                "Status must be either resolved or dismissed",
+            ));
+        }
+
+        let report = match self.repo.resolve(&id, status, &admin_id, note.as_deref()).await? {
+            Some(report) => report,
+            None => {
+                // Phân biệt report không tồn tại với report đã được xử lý
+                self.get_report(id).await?;
+                return Err(error::SystemError::bad_request("Report has already been resolved"));
+            }
+        };
+
+        if report.status == ReportStatus::Dismissed
+            && report.target_type == ReportTargetType::Message
+        {
+            let reporters =
+                self.repo.count_reporters(ReportTargetType::Message, &report.target_id).await?;
+            if reporters < ENV.report_hide_threshold {
+                self.repo.unhide_message(&report.target_id).await?;
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix::Actor;
+
+    use super::*;
+    use crate::{constants::init_test_env, modules::report::repository_mock::ReportRepositoryMock};
+
+    fn service(repo: &ReportRepositoryMock) -> ReportService {
+        init_test_env();
+        ReportService::with_dependencies(
+            Arc::new(repo.clone()),
+            Arc::new(WebSocketServer::new().start()),
+        )
+    }
+
+    /// Conversation với `count` members, trả về tin nhắn, người gửi và các member còn lại
+    fn conversation_with_message(
+        repo: &ReportRepositoryMock,
+        count: usize,
+    ) -> (Uuid, Uuid, Vec<Uuid>) {
+        let conversation_id = Uuid::now_v7();
+        let members: Vec<Uuid> = (0..count).map(|_| repo.add_user()).collect();
+        for member in &members {
+            repo.add_member(conversation_id, *member);
+        }
+        let message_id = repo.add_message(conversation_id, members[0]);
+        (message_id, members[0], members[1..].to_vec())
+    }
+
+    async fn report_message(service: &ReportService, reporter: Uuid, message_id: Uuid) {
+        service
+            .create_report(reporter, ReportTargetType::Message, message_id, "spam".into())
+            .await
+            .unwrap();
+    }
+
+    #[actix_web::test]
+    async fn message_is_hidden_at_threshold_and_shown_again_when_dismissed() {
+        let repo = ReportRepositoryMock::default();
+        let service = service(&repo);
+        let threshold = ENV.report_hide_threshold as usize;
+        let (message_id, _, reporters) = conversation_with_message(&repo, threshold + 1);
+
+        for reporter in &reporters[..threshold - 1] {
+            report_message(&service, *reporter, message_id).await;
+        }
+        assert!(!repo.is_hidden(&message_id));
+
+        report_message(&service, reporters[threshold - 1], message_id).await;
+        assert!(repo.is_hidden(&message_id));
+
+        let open = service.list_reports(Some(ReportStatus::Open), None, 1, 100).await.unwrap();
+        assert_eq!(open.total, threshold as i64);
+
+        let admin_id = Uuid::now_v7();
+        let dismissed = service
+            .resolve_report(admin_id, open.reports[0].id, ReportStatus::Dismissed, None)
+            .await
+            .unwrap();
+        assert_eq!(dismissed.resolved_by, Some(admin_id));
+        assert!(!repo.is_hidden(&message_id));
+    }
+
+    #[actix_web::test]
+    async fn only_members_can_report_other_peoples_messages() {
+        let repo = ReportRepositoryMock::default();
+        let service = service(&repo);
+        let (message_id, sender, members) = conversation_with_message(&repo, 2);
+        let stranger = repo.add_user();
+
+        let err = service
+            .create_report(stranger, ReportTargetType::Message, message_id, "spam".into())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotFound);
+
+        report_message(&service, members[0], message_id).await;
+        let err = service
+            .create_report(members[0], ReportTargetType::Message, message_id, "again".into())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::BadRequest);
+
+        let err = service
+            .create_report(sender, ReportTargetType::Message, message_id, "mine".into())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::BadRequest);
+    }
+
+    #[actix_web::test]
+    async fn users_cannot_report_themselves_or_unknown_users() {
+        let repo = ReportRepositoryMock::default();
+        let service = service(&repo);
+        let (reporter, target) = (repo.add_user(), repo.add_user());
+
+        let err = service
+            .create_report(reporter, ReportTargetType::User, reporter, "me".into())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::BadRequest);
+        let err = service
+            .create_report(reporter, ReportTargetType::User, Uuid::now_v7(), "ghost".into())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotFound);
+
+        let report = service
+            .create_report(reporter, ReportTargetType::User, target, "abuse".into())
+            .await
+            .unwrap();
+        assert_eq!(report.reporter_id, Some(reporter));
+    }
+
+    #[actix_web::test]
+    async fn report_can_only_be_resolved_once() {
+        let repo = ReportRepositoryMock::default();
+        let service = service(&repo);
+        let (reporter, target) = (repo.add_user(), repo.add_user());
+        let report = service
+            .create_report(reporter, ReportTargetType::User, target, "abuse".into())
+            .await
+            .unwrap();
+        let admin_id = Uuid::now_v7();
+
+        let err = service
+            .resolve_report(admin_id, report.id, ReportStatus::Open, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::BadRequest);
+
+        let resolved = service
+            .resolve_report(admin_id, report.id, ReportStatus::Resolved, Some("banned".into()))
+            .await
+            .unwrap();
+        assert_eq!(resolved.resolution_note.as_deref(), Some("banned"));
+
+        let err = service
+            .resolve_report(admin_id, report.id, ReportStatus::Dismissed, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::BadRequest);
+        let err = service
+            .resolve_report(admin_id, Uuid::now_v7(), ReportStatus::Resolved, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotFound);
+    }
+}
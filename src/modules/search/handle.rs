@@ -0,0 +1,35 @@
+use actix_web::{get, web, HttpRequest};
+
+use crate::{
+    api::{error, success},
+    middlewares::get_extensions,
+    modules::search::{
+        model::{SearchQuery, SearchResponse},
+        service::SearchService,
+    },
+    utils::{Claims, ValidatedQuery},
+};
+
+/// Tìm kiếm conversations, bạn bè và messages của caller trong một request
+///
+/// GET /search?q=hello&limit=5
+/// GET /search?q=hello&section=messages&cursor=<next_cursor>
+#[utoipa::path(
+    tag = "search",
+    params(SearchQuery),
+    responses(
+        (status = 200, body = success::SuccessData<SearchResponse>),
+        (status = 400, description = "Query quá ngắn / cursor sai", body = error::ErrorBody)
+    )
+)]
+#[get("/search")]
+pub async fn search(
+    search_service: web::Data<SearchService>,
+    ValidatedQuery(query): ValidatedQuery<SearchQuery>,
+    req: HttpRequest,
+) -> Result<success::Success<SearchResponse>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let response = search_service.search(user_id, query).await?;
+
+    Ok(success::Success::ok(Some(response)))
+}
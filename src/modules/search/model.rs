@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::modules::search::schema::{ConversationHit, FriendHit, MessageHit};
+
+/// Các phần của kết quả tìm kiếm, mỗi phần phân trang riêng
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchSection {
+    Conversations,
+    Friends,
+    Messages,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    #[validate(length(min = 2, max = 100, message = "Query must be 2 to 100 characters"))]
+    pub q: String,
+    /// Chỉ lấy trang tiếp theo của một phần (dùng cùng `cursor`), mặc định trả về trang
+    /// đầu của cả ba phần
+    #[param(inline)]
+    pub section: Option<SearchSection>,
+    /// `next_cursor` của `section` trong response trước
+    pub cursor: Option<String>,
+    /// Số kết quả tối đa mỗi phần
+    #[validate(range(min = 1, max = 50, message = "Limit must be between 1 and 50"))]
+    pub limit: Option<i64>,
+}
+
+/// Cursor của một phần: `<sort key>|<id>`, id phân định các kết quả cùng sort key.
+/// Sort key là `updated_at` / `created_at` (RFC 3339) hoặc display name đã lowercase
+#[derive(Debug, Clone)]
+pub struct SearchCursor {
+    pub key: String,
+    pub id: Uuid,
+}
+
+impl SearchCursor {
+    pub fn parse(cursor: &str) -> Option<Self> {
+        let (key, id) = cursor.rsplit_once('|')?;
+        Some(Self { key: key.to_string(), id: id.parse().ok()? })
+    }
+
+    pub fn encode(&self) -> String {
+        format!("{}|{}", self.key, self.id)
+    }
+
+    pub fn from_time(time: &chrono::DateTime<chrono::Utc>, id: Uuid) -> Self {
+        Self { key: time.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true), id }
+    }
+
+    pub fn time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        chrono::DateTime::parse_from_rfc3339(&self.key).ok().map(|t| t.with_timezone(&chrono::Utc))
+    }
+}
+
+/// Một trang kết quả của một phần
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SearchPage<T> {
+    pub items: Vec<T>,
+    /// `None` khi đã hết kết quả
+    pub next_cursor: Option<String>,
+}
+
+/// Kết quả tìm kiếm, phần không được yêu cầu (khi có `section`) bị bỏ qua
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct SearchResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversations: Option<SearchPage<ConversationHit>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub friends: Option<SearchPage<FriendHit>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub messages: Option<SearchPage<MessageHit>>,
+}
@@ -0,0 +1,38 @@
+use uuid::Uuid;
+
+use crate::api::error;
+use crate::modules::search::model::SearchCursor;
+use crate::modules::search::schema::{ConversationHit, FriendHit, MessageHit};
+
+/// Các methods trả về tối đa `limit + 1` kết quả sau `cursor` (thêm 1 để biết còn trang
+/// sau), `pattern` là chuỗi LIKE đã escape và lowercase
+#[async_trait::async_trait]
+pub trait SearchRepository {
+    /// Group conversations `user_id` đang tham gia, hoạt động gần nhất trước
+    async fn search_conversations(
+        &self,
+        user_id: &Uuid,
+        pattern: &str,
+        cursor: Option<&SearchCursor>,
+        limit: i64,
+    ) -> Result<Vec<ConversationHit>, error::SystemError>;
+
+    /// Bạn bè của `user_id`, theo display name
+    async fn search_friends(
+        &self,
+        user_id: &Uuid,
+        pattern: &str,
+        cursor: Option<&SearchCursor>,
+        limit: i64,
+    ) -> Result<Vec<FriendHit>, error::SystemError>;
+
+    /// Messages `user_id` xem được trong các conversations đang tham gia (áp dụng history
+    /// visibility, disappearing TTL, clear history và ẩn tin), mới nhất trước
+    async fn search_messages(
+        &self,
+        user_id: &Uuid,
+        pattern: &str,
+        cursor: Option<&SearchCursor>,
+        limit: i64,
+    ) -> Result<Vec<MessageHit>, error::SystemError>;
+}
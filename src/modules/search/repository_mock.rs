@@ -0,0 +1,192 @@
+/// In-memory `SearchRepository` cho unit tests của services
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::search::{
+        model::SearchCursor,
+        repository::SearchRepository,
+        schema::{ConversationHit, FriendHit, MessageHit},
+    },
+};
+
+struct MockConversation {
+    /// `None` với direct conversation
+    name: Option<String>,
+    members: HashSet<Uuid>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Default)]
+struct State {
+    /// user_id -> (username, display_name)
+    users: HashMap<Uuid, (String, String)>,
+    /// Cặp bạn bè, lưu theo thứ tự (nhỏ, lớn)
+    friends: HashSet<(Uuid, Uuid)>,
+    conversations: HashMap<Uuid, MockConversation>,
+    messages: Vec<MessageHit>,
+}
+
+/// Clone dùng chung dữ liệu, test giữ một bản để tạo users / conversations / messages
+#[derive(Clone, Default)]
+pub struct SearchRepositoryMock {
+    state: Arc<Mutex<State>>,
+}
+
+fn friend_pair(a: Uuid, b: Uuid) -> (Uuid, Uuid) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// `LIKE '%pattern%'` trên chuỗi đã lowercase, `pattern` đã escape như repository Pg nhận
+fn like(text: &str, pattern: &str) -> bool {
+    let mut needle = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        needle.push(if c == '\\' { chars.next().unwrap_or('\\') } else { c });
+    }
+    text.to_lowercase().contains(&needle)
+}
+
+/// Kết quả nằm sau cursor thời gian khi sắp xếp (time, id) giảm dần
+fn before_cursor(
+    time: &chrono::DateTime<chrono::Utc>,
+    id: Uuid,
+    cursor: Option<&SearchCursor>,
+) -> bool {
+    cursor.is_none_or(|c| c.time().is_some_and(|t| (*time, id) < (t, c.id)))
+}
+
+impl SearchRepositoryMock {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn add_user(&self, username: &str, display_name: &str) -> Uuid {
+        let user_id = Uuid::now_v7();
+        self.state().users.insert(user_id, (username.to_string(), display_name.to_string()));
+        user_id
+    }
+
+    pub fn add_friends(&self, user_a: Uuid, user_b: Uuid) {
+        self.state().friends.insert(friend_pair(user_a, user_b));
+    }
+
+    /// Conversation với `members`, `name` là `None` với direct conversation
+    pub fn add_conversation(&self, name: Option<&str>, members: &[Uuid]) -> Uuid {
+        let conversation_id = Uuid::now_v7();
+        let conversation = MockConversation {
+            name: name.map(str::to_string),
+            members: members.iter().copied().collect(),
+            updated_at: Utc::now(),
+        };
+        self.state().conversations.insert(conversation_id, conversation);
+        conversation_id
+    }
+
+    pub fn add_message(&self, conversation_id: Uuid, sender_id: Uuid, content: &str) -> Uuid {
+        let mut state = self.state();
+        let message = MessageHit {
+            id: Uuid::now_v7(),
+            conversation_id,
+            conversation_name: state.conversations[&conversation_id].name.clone(),
+            sender_id,
+            sender_display_name: state.users[&sender_id].1.clone(),
+            content: content.to_string(),
+            created_at: Utc::now(),
+        };
+        let id = message.id;
+        state.messages.push(message);
+        id
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchRepository for SearchRepositoryMock {
+    async fn search_conversations(
+        &self,
+        user_id: &Uuid,
+        pattern: &str,
+        cursor: Option<&SearchCursor>,
+        limit: i64,
+    ) -> Result<Vec<ConversationHit>, error::SystemError> {
+        let mut hits: Vec<ConversationHit> = self
+            .state()
+            .conversations
+            .iter()
+            .filter(|(_, c)| c.members.contains(user_id))
+            .filter_map(|(id, c)| c.name.as_ref().map(|name| (id, name, c.updated_at)))
+            .filter(|(id, name, updated_at)| {
+                like(name, pattern) && before_cursor(updated_at, **id, cursor)
+            })
+            .map(|(id, name, updated_at)| ConversationHit {
+                conversation_id: *id,
+                name: name.clone(),
+                avatar_url: None,
+                updated_at,
+            })
+            .collect();
+        hits.sort_by_key(|c| std::cmp::Reverse((c.updated_at, c.conversation_id)));
+        hits.truncate(limit as usize + 1);
+        Ok(hits)
+    }
+
+    async fn search_friends(
+        &self,
+        user_id: &Uuid,
+        pattern: &str,
+        cursor: Option<&SearchCursor>,
+        limit: i64,
+    ) -> Result<Vec<FriendHit>, error::SystemError> {
+        let state = self.state();
+        let mut hits: Vec<FriendHit> = state
+            .users
+            .iter()
+            .filter(|(id, _)| state.friends.contains(&friend_pair(*user_id, **id)))
+            .filter(|(_, (username, display_name))| {
+                like(username, pattern) || like(display_name, pattern)
+            })
+            .filter(|(id, (_, display_name))| {
+                cursor.is_none_or(|c| (display_name.to_lowercase(), **id) > (c.key.clone(), c.id))
+            })
+            .map(|(id, (username, display_name))| FriendHit {
+                id: *id,
+                username: username.clone(),
+                display_name: display_name.clone(),
+                avatar_url: None,
+            })
+            .collect();
+        hits.sort_by_key(|f| (f.display_name.to_lowercase(), f.id));
+        hits.truncate(limit as usize + 1);
+        Ok(hits)
+    }
+
+    async fn search_messages(
+        &self,
+        user_id: &Uuid,
+        pattern: &str,
+        cursor: Option<&SearchCursor>,
+        limit: i64,
+    ) -> Result<Vec<MessageHit>, error::SystemError> {
+        let state = self.state();
+        let mut hits: Vec<MessageHit> = state
+            .messages
+            .iter()
+            .filter(|m| state.conversations[&m.conversation_id].members.contains(user_id))
+            .filter(|m| like(&m.content, pattern) && before_cursor(&m.created_at, m.id, cursor))
+            .cloned()
+            .collect();
+        hits.sort_by_key(|m| std::cmp::Reverse((m.created_at, m.id)));
+        hits.truncate(limit as usize + 1);
+        Ok(hits)
+    }
+}
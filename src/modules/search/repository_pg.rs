@@ -0,0 +1,155 @@
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    configs::read_replica::ReadPool,
+    modules::search::{
+        model::SearchCursor,
+        repository::SearchRepository,
+        schema::{ConversationHit, FriendHit, MessageHit},
+    },
+};
+
+/// Tất cả queries đều chỉ đọc nên chạy trên `read_pool` (read replica nếu có)
+#[derive(Clone)]
+pub struct SearchRepositoryPg {
+    read_pool: ReadPool,
+}
+
+impl SearchRepositoryPg {
+    pub fn new(read_pool: ReadPool) -> Self {
+        Self { read_pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchRepository for SearchRepositoryPg {
+    async fn search_conversations(
+        &self,
+        user_id: &Uuid,
+        pattern: &str,
+        cursor: Option<&SearchCursor>,
+        limit: i64,
+    ) -> Result<Vec<ConversationHit>, error::SystemError> {
+        let conversations = sqlx::query_as::<_, ConversationHit>(
+            r#"
+            SELECT
+                c.id AS conversation_id,
+                g.name,
+                g.avatar_url,
+                c.updated_at
+            FROM participants p
+            JOIN conversations c ON c.id = p.conversation_id
+            JOIN group_conversations g ON g.conversation_id = c.id
+            WHERE p.user_id = $1
+            AND p.status = 'active'
+            AND lower(g.name) LIKE '%' || $2 || '%'
+            AND ($3::timestamptz IS NULL OR (c.updated_at, c.id) < ($3, $4))
+            ORDER BY c.updated_at DESC, c.id DESC
+            LIMIT $5
+            "#,
+        )
+        .bind(user_id)
+        .bind(pattern)
+        .bind(cursor.and_then(SearchCursor::time))
+        .bind(cursor.map(|c| c.id))
+        .bind(limit + 1)
+        .fetch_all(&mut *self.read_pool.acquire().await?)
+        .await?;
+
+        Ok(conversations)
+    }
+
+    async fn search_friends(
+        &self,
+        user_id: &Uuid,
+        pattern: &str,
+        cursor: Option<&SearchCursor>,
+        limit: i64,
+    ) -> Result<Vec<FriendHit>, error::SystemError> {
+        let friends = sqlx::query_as::<_, FriendHit>(
+            r#"
+            SELECT u.id, u.username, u.display_name, u.avatar_url
+            FROM friends f
+            JOIN users u
+                ON u.id = CASE WHEN f.user_a = $1 THEN f.user_b ELSE f.user_a END
+            WHERE (f.user_a = $1 OR f.user_b = $1)
+            AND u.deleted_at IS NULL
+            AND u.deactivated_at IS NULL
+            AND (
+                lower(u.username) LIKE '%' || $2 || '%'
+                OR lower(u.display_name) LIKE '%' || $2 || '%'
+            )
+            AND ($3::text IS NULL OR (lower(u.display_name), u.id) > ($3, $4))
+            ORDER BY lower(u.display_name), u.id
+            LIMIT $5
+            "#,
+        )
+        .bind(user_id)
+        .bind(pattern)
+        .bind(cursor.map(|c| c.key.as_str()))
+        .bind(cursor.map(|c| c.id))
+        .bind(limit + 1)
+        .fetch_all(&mut *self.read_pool.acquire().await?)
+        .await?;
+
+        Ok(friends)
+    }
+
+    async fn search_messages(
+        &self,
+        user_id: &Uuid,
+        pattern: &str,
+        cursor: Option<&SearchCursor>,
+        limit: i64,
+    ) -> Result<Vec<MessageHit>, error::SystemError> {
+        // Cùng điều kiện hiển thị với lịch sử messages (ConversationService::visible_since),
+        // tin nhắn mã hóa đầu cuối không tìm được vì server không đọc được nội dung
+        let messages = sqlx::query_as::<_, MessageHit>(
+            r#"
+            SELECT
+                m.id,
+                m.conversation_id,
+                g.name AS conversation_name,
+                m.sender_id,
+                u.display_name AS sender_display_name,
+                m.content,
+                m.created_at
+            FROM participants p
+            JOIN conversations c ON c.id = p.conversation_id
+            JOIN messages m ON m.conversation_id = p.conversation_id
+            JOIN users u ON u.id = m.sender_id
+            LEFT JOIN group_conversations g ON g.conversation_id = c.id
+            WHERE p.user_id = $1
+            AND p.status = 'active'
+            AND m.deleted_at IS NULL
+            AND m.hidden_at IS NULL
+            AND m.type != 'encrypted'
+            AND m.content IS NOT NULL
+            AND lower(m.content) LIKE '%' || $2 || '%'
+            AND (c.history_visibility = 'full' OR m.created_at >= p.joined_at)
+            AND (
+                c.message_ttl_seconds IS NULL
+                OR m.created_at >= NOW() - make_interval(secs => c.message_ttl_seconds)
+            )
+            AND (p.cleared_at IS NULL OR m.created_at >= p.cleared_at)
+            AND NOT EXISTS (
+                SELECT 1 FROM hidden_messages h
+                WHERE h.message_id = m.id AND h.user_id = $1
+            )
+            AND ($3::timestamptz IS NULL OR (m.created_at, m.id) < ($3, $4))
+            ORDER BY m.created_at DESC, m.id DESC
+            LIMIT $5
+            "#,
+        )
+        .bind(user_id)
+        .bind(pattern)
+        .bind(cursor.and_then(SearchCursor::time))
+        .bind(cursor.map(|c| c.id))
+        .bind(limit + 1)
+        .fetch_all(&mut *self.read_pool.acquire().await?)
+        .await?;
+
+        Ok(messages)
+    }
+}
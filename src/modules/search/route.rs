@@ -0,0 +1,12 @@
+use crate::modules::search::handle::*;
+use actix_web::web::ServiceConfig;
+use utoipa::OpenApi;
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(search);
+}
+
+/// OpenAPI paths của `configure`
+#[derive(OpenApi)]
+#[openapi(paths(search))]
+pub struct SearchApiDoc;
@@ -0,0 +1,35 @@
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Group conversation có tên khớp query
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct ConversationHit {
+    pub conversation_id: Uuid,
+    pub name: String,
+    pub avatar_url: Option<String>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Bạn bè có username / display name khớp query
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct FriendHit {
+    pub id: Uuid,
+    pub username: String,
+    pub display_name: String,
+    pub avatar_url: Option<String>,
+}
+
+/// Message có nội dung khớp query, kèm tên conversation / người gửi để hiển thị
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct MessageHit {
+    pub id: Uuid,
+    pub conversation_id: Uuid,
+    /// Tên group, `None` với direct conversation
+    pub conversation_name: Option<String>,
+    pub sender_id: Uuid,
+    pub sender_display_name: String,
+    pub content: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
@@ -0,0 +1,209 @@
+/// Search Service
+///
+/// Tìm kiếm hợp nhất cho ô search của client: group conversations theo tên, bạn bè theo
+/// username / display name và messages gần đây, tất cả trong phạm vi của caller. Request
+/// đầu trả về trang đầu của cả ba phần (chạy song song), các trang sau lấy riêng từng
+/// phần bằng `section` + `cursor`.
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::search::{
+        model::{SearchCursor, SearchPage, SearchQuery, SearchResponse, SearchSection},
+        repository::SearchRepository,
+    },
+};
+
+/// Số kết quả mặc định mỗi phần
+const DEFAULT_SECTION_LIMIT: i64 = 5;
+
+/// Chuỗi LIKE `%...%` cho query: lowercase, escape `\`, `%` và `_`
+fn like_pattern(query: &str) -> String {
+    query.to_lowercase().replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Cắt kết quả thừa (repository trả thêm 1) và tạo cursor từ kết quả cuối
+fn page<T>(mut items: Vec<T>, limit: i64, cursor_of: impl Fn(&T) -> SearchCursor) -> SearchPage<T> {
+    let has_more = items.len() > limit as usize;
+    items.truncate(limit as usize);
+    let next_cursor =
+        if has_more { items.last().map(|item| cursor_of(item).encode()) } else { None };
+    SearchPage { items, next_cursor }
+}
+
+#[derive(Clone)]
+pub struct SearchService {
+    repo: Arc<dyn SearchRepository + Send + Sync>,
+}
+
+impl SearchService {
+    pub fn with_dependencies(repo: Arc<dyn SearchRepository + Send + Sync>) -> Self {
+        SearchService { repo }
+    }
+
+    pub async fn search(
+        &self,
+        user_id: Uuid,
+        query: SearchQuery,
+    ) -> Result<SearchResponse, error::SystemError> {
+        let q = query.q.trim();
+        if q.chars().count() < 2 {
+            return Err(error::SystemError::bad_request(
+                "Search query must be at least 2 characters",
+            ));
+        }
+        let pattern = like_pattern(q);
+        let limit = query.limit.unwrap_or(DEFAULT_SECTION_LIMIT);
+
+        let cursor = match (&query.cursor, query.section) {
+            (None, _) => None,
+            (Some(_), None) => {
+                return Err(error::SystemError::bad_request("cursor requires a section"));
+            }
+            (Some(c), Some(section)) => {
+                let cursor = SearchCursor::parse(c)
+                    .filter(|c| section == SearchSection::Friends || c.time().is_some())
+                    .ok_or_else(|| error::SystemError::bad_request("Invalid cursor format"))?;
+                Some(cursor)
+            }
+        };
+        let cursor = cursor.as_ref();
+
+        let mut response = SearchResponse::default();
+        match query.section {
+            None => {
+                let (conversations, friends, messages) = tokio::try_join!(
+                    self.repo.search_conversations(&user_id, &pattern, None, limit),
+                    self.repo.search_friends(&user_id, &pattern, None, limit),
+                    self.repo.search_messages(&user_id, &pattern, None, limit),
+                )?;
+                response.conversations = Some(page(conversations, limit, |c| {
+                    SearchCursor::from_time(&c.updated_at, c.conversation_id)
+                }));
+                response.friends = Some(page(friends, limit, |f| SearchCursor {
+                    key: f.display_name.to_lowercase(),
+                    id: f.id,
+                }));
+                response.messages =
+                    Some(page(messages, limit, |m| SearchCursor::from_time(&m.created_at, m.id)));
+            }
+            Some(SearchSection::Conversations) => {
+                let conversations =
+                    self.repo.search_conversations(&user_id, &pattern, cursor, limit).await?;
+                response.conversations = Some(page(conversations, limit, |c| {
+                    SearchCursor::from_time(&c.updated_at, c.conversation_id)
+                }));
+            }
+            Some(SearchSection::Friends) => {
+                let friends = self.repo.search_friends(&user_id, &pattern, cursor, limit).await?;
+                response.friends = Some(page(friends, limit, |f| SearchCursor {
+                    key: f.display_name.to_lowercase(),
+                    id: f.id,
+                }));
+            }
+            Some(SearchSection::Messages) => {
+                let messages = self.repo.search_messages(&user_id, &pattern, cursor, limit).await?;
+                response.messages =
+                    Some(page(messages, limit, |m| SearchCursor::from_time(&m.created_at, m.id)));
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::search::repository_mock::SearchRepositoryMock;
+
+    fn query(q: &str, section: Option<SearchSection>, cursor: Option<String>) -> SearchQuery {
+        SearchQuery { q: q.to_string(), section, cursor, limit: Some(1) }
+    }
+
+    #[actix_web::test]
+    async fn search_returns_each_section_and_pages_with_cursor() {
+        let repo = SearchRepositoryMock::default();
+        let service = SearchService::with_dependencies(Arc::new(repo.clone()));
+        let alice = repo.add_user("alice", "Alice");
+        let bob = repo.add_user("bob", "Bob Rocket");
+        repo.add_friends(alice, bob);
+        let group = repo.add_conversation(Some("Rocket Team"), &[alice, bob]);
+        let older = repo.add_message(group, bob, "rocket launch at 9");
+        let newer = repo.add_message(group, alice, "Rocket is ready");
+
+        let response = service.search(alice, query("rocket", None, None)).await.unwrap();
+
+        let conversations = response.conversations.unwrap();
+        assert_eq!(conversations.items[0].conversation_id, group);
+        assert!(conversations.next_cursor.is_none());
+        assert_eq!(response.friends.unwrap().items[0].id, bob);
+        let messages = response.messages.unwrap();
+        assert_eq!(messages.items[0].id, newer);
+
+        let next = query("rocket", Some(SearchSection::Messages), messages.next_cursor);
+        let response = service.search(alice, next).await.unwrap();
+        assert!(response.conversations.is_none() && response.friends.is_none());
+        let messages = response.messages.unwrap();
+        assert_eq!(messages.items[0].id, older);
+        assert!(messages.next_cursor.is_none());
+    }
+
+    #[actix_web::test]
+    async fn search_only_covers_the_callers_conversations_and_friends() {
+        let repo = SearchRepositoryMock::default();
+        let service = SearchService::with_dependencies(Arc::new(repo.clone()));
+        let alice = repo.add_user("alice", "Alice");
+        let bob = repo.add_user("bob", "Bob Rocket");
+        let carol = repo.add_user("carol", "Carol");
+        repo.add_friends(alice, bob);
+        let group = repo.add_conversation(Some("Rocket Team"), &[alice, bob]);
+        repo.add_message(group, bob, "rocket launch at 9");
+
+        // Carol không phải bạn của Bob và không ở trong group
+        let response = service.search(carol, query("rocket", None, None)).await.unwrap();
+
+        assert!(response.conversations.unwrap().items.is_empty());
+        assert!(response.friends.unwrap().items.is_empty());
+        assert!(response.messages.unwrap().items.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn like_wildcards_in_query_match_literally() {
+        let repo = SearchRepositoryMock::default();
+        let service = SearchService::with_dependencies(Arc::new(repo.clone()));
+        let alice = repo.add_user("alice", "Alice");
+        let direct = repo.add_conversation(None, &[alice]);
+        repo.add_message(direct, alice, "nothing special");
+        let discount = repo.add_message(direct, alice, "50% off");
+
+        let response = service.search(alice, query("0%", None, None)).await.unwrap();
+
+        let messages = response.messages.unwrap();
+        assert_eq!(messages.items.iter().map(|m| m.id).collect::<Vec<_>>(), vec![discount]);
+        assert_eq!(messages.items[0].conversation_name, None);
+    }
+
+    #[actix_web::test]
+    async fn invalid_queries_and_cursors_are_rejected() {
+        let repo = SearchRepositoryMock::default();
+        let service = SearchService::with_dependencies(Arc::new(repo.clone()));
+        let alice = repo.add_user("alice", "Alice");
+        let cursor = SearchCursor { key: "alice".to_string(), id: alice }.encode();
+
+        for query in [
+            query(" a ", None, None),
+            query("alice", None, Some(cursor.clone())),
+            query("alice", Some(SearchSection::Messages), Some(cursor.clone())),
+            query("alice", Some(SearchSection::Friends), Some("garbage".to_string())),
+        ] {
+            let err = service.search(alice, query).await.unwrap_err();
+            assert_eq!(err.code(), error::ErrorCode::BadRequest);
+        }
+
+        let friends = query("alice", Some(SearchSection::Friends), Some(cursor));
+        assert!(service.search(alice, friends).await.unwrap().friends.is_some());
+    }
+}
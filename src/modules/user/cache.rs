@@ -0,0 +1,164 @@
+/// Profile Cache
+///
+/// Cache 2 tầng cho user profile:
+/// - Local (in-process) cache, TTL ngắn, tránh round-trip Redis cho các lookup nóng
+/// - Redis cache `user:{id}` dùng chung giữa các instances
+///
+/// Khi profile thay đổi, instance xử lý request publish một `ProfileInvalidation`
+/// lên channel `PROFILE_INVALIDATION_CHANNEL`. Mọi instance (kể cả instance publish)
+/// subscribe channel này để evict local cache và push `profile-updated` tới friends
+/// và thành viên các conversations chung đang kết nối WebSocket với instance đó
+/// (deliver local, không qua fan-out bridge).
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use actix::Addr;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::{
+        friend::repository_pg::FriendRepositoryPg,
+        user::model::UserResponse,
+        websocket::{
+            bridge::FanoutEvent, event_buffer::event_id_from_payload, events::DeliverLocal,
+            message::ServerMessage, server::WebSocketServer,
+        },
+    },
+    ENV,
+};
+
+pub const PROFILE_INVALIDATION_CHANNEL: &str = "cache:invalidate:user";
+
+/// TTL của local cache, ngắn hơn CACHE_TTL để giới hạn độ stale nếu mất message pub/sub
+const LOCAL_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Thời gian chờ trước khi subscribe lại khi mất kết nối Redis
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// Message được publish khi profile của user thay đổi
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileInvalidation {
+    pub user_id: Uuid,
+    /// Profile mới, `None` nếu user bị xóa / ban / merge (chỉ evict, không notify)
+    pub profile: Option<UserResponse>,
+}
+
+/// In-process cache cho user profile
+#[derive(Clone, Default)]
+pub struct LocalProfileCache {
+    entries: Arc<RwLock<HashMap<Uuid, (UserResponse, Instant)>>>,
+}
+
+impl LocalProfileCache {
+    pub fn get(&self, id: &Uuid) -> Option<UserResponse> {
+        let entries = self.entries.read().ok()?;
+        entries
+            .get(id)
+            .filter(|(_, cached_at)| cached_at.elapsed() < LOCAL_CACHE_TTL)
+            .map(|(user, _)| user.clone())
+    }
+
+    pub fn insert(&self, user: UserResponse) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.retain(|_, (_, cached_at)| cached_at.elapsed() < LOCAL_CACHE_TTL);
+            entries.insert(user.id, (user, Instant::now()));
+        }
+    }
+
+    pub fn evict(&self, id: &Uuid) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.remove(id);
+        }
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.clear();
+        }
+    }
+}
+
+/// Subscribe channel invalidation và xử lý cho tới khi process dừng,
+/// tự subscribe lại nếu kết nối Redis bị ngắt
+pub async fn run_invalidation_listener(
+    local_cache: LocalProfileCache,
+    friend_repo: FriendRepositoryPg,
+    ws_server: Addr<WebSocketServer>,
+) {
+    loop {
+        if let Err(e) = listen(&local_cache, &friend_repo, &ws_server).await {
+            tracing::error!("Profile invalidation listener error: {:?}", e);
+        }
+
+        // Trong lúc mất kết nối có thể bỏ lỡ invalidation, xóa toàn bộ local cache
+        local_cache.clear();
+        tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+    }
+}
+
+async fn listen(
+    local_cache: &LocalProfileCache,
+    friend_repo: &FriendRepositoryPg,
+    ws_server: &Addr<WebSocketServer>,
+) -> Result<(), error::SystemError> {
+    let client = deadpool_redis::redis::Client::open(ENV.redis_url.as_str())?;
+    let mut pubsub = client.get_async_pubsub().await?;
+    pubsub.subscribe(PROFILE_INVALIDATION_CHANNEL).await?;
+
+    tracing::info!("Subscribed to {}", PROFILE_INVALIDATION_CHANNEL);
+
+    let mut messages = pubsub.on_message();
+    while let Some(msg) = messages.next().await {
+        let payload: Vec<u8> = msg.get_payload()?;
+        let invalidation = match serde_json::from_slice::<ProfileInvalidation>(&payload) {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::warn!("Invalid profile invalidation payload: {:?}", e);
+                continue;
+            }
+        };
+
+        local_cache.evict(&invalidation.user_id);
+
+        let Some(profile) = invalidation.profile else {
+            continue;
+        };
+
+        let audience = match friend_repo.find_profile_audience(&invalidation.user_id).await {
+            Ok(ids) => ids,
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to load profile audience of user {} for profile update: {:?}",
+                    invalidation.user_id,
+                    e
+                );
+                continue;
+            }
+        };
+
+        // Mọi instance đều nhận invalidation nên chỉ deliver local, không fan-out lại.
+        // Event ID lấy theo payload để các instances gán cùng user_seq
+        if !audience.is_empty() {
+            ws_server.do_send(DeliverLocal {
+                event_id: event_id_from_payload(&payload),
+                event: FanoutEvent::SendToUsers {
+                    user_ids: audience,
+                    message: ServerMessage::ProfileUpdated {
+                        user_id: profile.id,
+                        username: profile.username,
+                        display_name: profile.display_name,
+                        avatar_url: profile.avatar_url,
+                    },
+                },
+            });
+        }
+    }
+
+    Err(error::SystemError::internal_error("Profile invalidation subscription closed"))
+}
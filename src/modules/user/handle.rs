@@ -1,26 +1,41 @@
+use actix::Addr;
+use actix_multipart::Multipart;
 use actix_web::{
     cookie::{self, time, Cookie},
-    delete, get, patch, post, web, HttpRequest,
+    delete, get, patch, post, put, web, HttpRequest,
 };
+use std::collections::HashSet;
 use uuid::Uuid;
 
+use crate::modules::conversation::service::ConversationService;
+use crate::modules::file_upload::{
+    handle::read_file_field, schema::FileUploadForm, service::FileUploadService,
+};
+use crate::modules::friend::repository_pg::FriendRepositoryPg;
+use crate::modules::user::schema::{PresenceVisibility, SignInEntity};
 use crate::modules::user::{model, service::UserService};
+use crate::modules::websocket::{
+    events::UserPresenceChanged,
+    presence::{PresenceInfo, PresenceService},
+    server::WebSocketServer,
+};
 use crate::{
     api::{error, success},
     utils::{ValidatedJson, ValidatedQuery},
 };
 use crate::{middlewares::get_extensions, ENV};
 use crate::{
-    modules::user::{model::SignUpResponse, repository_pg::UserRepositoryPg},
-    utils::Claims,
+    modules::user::model::SignUpResponse,
+    utils::{client_ip, Claims},
 };
-use crate::modules::websocket::presence::{PresenceInfo, PresenceService};
-
-pub type UserSvc = UserService<UserRepositoryPg>;
 
+#[utoipa::path(
+    tag = "users",
+    responses((status = 200, body = success::SuccessData<model::UserResponse>))
+)]
 #[get("/profile")]
 pub async fn get_profile(
-    user_service: web::Data<UserSvc>,
+    user_service: web::Data<UserService>,
     req: HttpRequest,
 ) -> Result<success::Success<model::UserResponse>, error::Error> {
     let id = get_extensions::<Claims>(&req)?.sub;
@@ -28,18 +43,53 @@ pub async fn get_profile(
     Ok(success::Success::ok(Some(user)).message("Profile retrieved successfully"))
 }
 
+#[utoipa::path(
+    tag = "users",
+    path = "/{id}",
+    params(("id" = Uuid, Path, description = "User ID")),
+    responses(
+        (
+            status = 200,
+            description = "Email / số điện thoại / bio bị ẩn (`null`) theo cài đặt quyền riêng tư",
+            body = success::SuccessData<model::UserResponse>
+        ),
+        (status = 404, body = error::ErrorBody)
+    )
+)]
 #[get("/{id:[0-9a-fA-F-]{36}}")]
 pub async fn get_user(
-    user_service: web::Data<UserSvc>,
+    user_service: web::Data<UserService>,
     user_id: web::Path<Uuid>,
+    req: HttpRequest,
 ) -> Result<success::Success<model::UserResponse>, error::Error> {
-    let user = user_service.get_by_id(user_id.into_inner()).await?;
+    let viewer_id = get_extensions::<Claims>(&req)?.sub;
+    let user = user_service.get_for_viewer(viewer_id, user_id.into_inner()).await?;
     Ok(success::Success::ok(Some(user)).message("User retrieved successfully"))
 }
 
+#[utoipa::path(
+    tag = "users",
+    path = "/{id}",
+    params(("id" = Uuid, Path, description = "User ID")),
+    request_body = model::UpdateUserModel,
+    responses(
+        (status = 200, body = success::MessageOnly),
+        (status = 403, description = "Không phải profile của chính mình", body = error::ErrorBody),
+        (
+            status = 409,
+            description = "Username đã được dùng hoặc đang được giữ",
+            body = error::ErrorBody
+        ),
+        (
+            status = 429,
+            description = "Chưa hết cooldown giữa 2 lần đổi username",
+            body = error::ErrorBody
+        )
+    )
+)]
 #[patch("/{id:[0-9a-fA-F-]{36}}")]
 pub async fn update_user(
-    user_service: web::Data<UserSvc>,
+    user_service: web::Data<UserService>,
     user_id: web::Path<Uuid>,
     req: HttpRequest,
     ValidatedJson(user_data): ValidatedJson<model::UpdateUserModel>,
@@ -53,36 +103,154 @@ pub async fn update_user(
     Ok(success::Success::ok(None).message("User updated successfully"))
 }
 
+/// Upload avatar: ảnh được crop vuông, resize rồi gán cho user; avatar cũ bị xóa
+#[utoipa::path(
+    tag = "users",
+    request_body(content = FileUploadForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, body = success::SuccessData<model::UserResponse>),
+        (status = 400, description = "Không có file hoặc file không phải ảnh hợp lệ", body = error::ErrorBody),
+        (status = 413, description = "File vượt `UPLOAD_BODY_LIMIT`", body = error::ErrorBody)
+    )
+)]
+#[post("/me/avatar")]
+pub async fn upload_avatar(
+    user_service: web::Data<UserService>,
+    file_service: web::Data<FileUploadService>,
+    mut payload: Multipart,
+    req: HttpRequest,
+) -> Result<success::Success<model::UserResponse>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+
+    let (filename, mime_type, bytes) = read_file_field(&mut payload)
+        .await?
+        .ok_or_else(|| error::Error::bad_request("No file found in request"))?;
+
+    let avatar = file_service.upload_avatar(filename, bytes, mime_type, user_id).await?;
+
+    let (user, previous) = match user_service.update_avatar(user_id, &avatar.url, avatar.id).await {
+        Ok(result) => result,
+        Err(e) => {
+            // Không gán được avatar thì bỏ file vừa upload
+            if let Err(e) = file_service.delete_file(&avatar.id).await {
+                tracing::warn!("Failed to delete orphaned avatar {}: {:?}", avatar.id, e);
+            }
+            return Err(e.into());
+        }
+    };
+
+    if let Some(previous) = previous {
+        if let Err(e) = file_service.delete_file(&previous).await {
+            tracing::warn!("Failed to delete previous avatar {}: {:?}", previous, e);
+        }
+    }
+
+    Ok(success::Success::ok(Some(user)).message("Avatar updated successfully"))
+}
+
+#[utoipa::path(
+    tag = "users",
+    path = "/{id}",
+    params(("id" = Uuid, Path, description = "User ID")),
+    responses(
+        (status = 204, description = "Tài khoản đã bị xóa"),
+        (status = 403, description = "Không phải tài khoản của chính mình", body = error::ErrorBody)
+    )
+)]
 #[delete("/{id:[0-9a-fA-F-]{36}}")]
 pub async fn delete_user(
-    user_service: web::Data<UserSvc>,
+    user_service: web::Data<UserService>,
     user_id: web::Path<Uuid>,
     req: HttpRequest,
 ) -> Result<success::Success<()>, error::Error> {
-    let auth_user_id = get_extensions::<Claims>(&req)?.sub;
+    let claims = get_extensions::<Claims>(&req)?;
     let target_id = user_id.into_inner();
-    if auth_user_id != target_id {
+    if claims.sub != target_id {
         return Err(error::Error::forbidden("You can only delete your own account"));
     }
     user_service.delete(target_id).await?;
+    user_service.revoke_access_token(&claims).await?;
     Ok(success::Success::no_content())
 }
 
+/// Tạm khóa tài khoản: profile bị ẩn, presence bị ẩn và mọi session bị đăng xuất cho
+/// tới lần đăng nhập tiếp theo
+#[utoipa::path(
+    tag = "users",
+    responses((status = 204, description = "Tài khoản đã tạm khóa"))
+)]
+#[post("/me/deactivate")]
+pub async fn deactivate_user(
+    user_service: web::Data<UserService>,
+    presence_service: web::Data<PresenceService>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let claims = get_extensions::<Claims>(&req)?;
+
+    // Ẩn presence trước khi các session bị đăng xuất, session mới sẽ đồng bộ lại từ Postgres
+    presence_service.set_visibility(claims.sub, PresenceVisibility::Nobody).await?;
+    user_service.deactivate(claims.sub).await?;
+    user_service.revoke_access_token(&claims).await?;
+    Ok(success::Success::no_content())
+}
+
+#[utoipa::path(
+    tag = "auth",
+    security(()),
+    request_body = model::SignUpModel,
+    responses(
+        (status = 201, body = success::SuccessData<SignUpResponse>),
+        (status = 409, description = "Username hoặc email đã tồn tại", body = error::ErrorBody)
+    )
+)]
 #[post("/signup")]
 pub async fn sign_up(
-    user_service: web::Data<UserSvc>,
+    user_service: web::Data<UserService>,
     ValidatedJson(user_data): ValidatedJson<model::SignUpModel>,
 ) -> Result<success::Success<SignUpResponse>, error::Error> {
     let user_id = user_service.sign_up(user_data).await?;
     Ok(success::Success::created(Some(SignUpResponse { id: user_id })).message("Signup successful"))
 }
 
+#[utoipa::path(
+    tag = "auth",
+    security(()),
+    request_body = model::SignInModel,
+    responses(
+        (
+            status = 200,
+            description = "Set cookie `refresh_token`",
+            body = success::SuccessData<model::SignInResponse>
+        ),
+        (status = 401, body = error::ErrorBody),
+        (
+            status = 403,
+            description = "Đăng nhập từ IP mới cần xác nhận qua email \
+                           (`SIGN_IN_CONFIRMATION_REQUIRED`)",
+            body = error::ErrorBody
+        ),
+        (
+            status = 429,
+            description = "Tạm khóa do đăng nhập sai nhiều lần (`ACCOUNT_LOCKED`)",
+            body = error::ErrorBody
+        )
+    )
+)]
 #[post("/signin")]
 pub async fn sign_in(
-    user_service: web::Data<UserSvc>,
+    user_service: web::Data<UserService>,
+    req: HttpRequest,
     ValidatedJson(user_data): ValidatedJson<model::SignInModel>,
 ) -> Result<success::Success<model::SignInResponse>, error::Error> {
-    let (access_token, refresh_token) = user_service.sign_in(user_data).await?;
+    let context = model::SignInContext {
+        ip_address: client_ip(&req),
+        user_agent: req
+            .headers()
+            .get(actix_web::http::header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+    };
+    let (access_token, refresh_token) = user_service.sign_in(user_data, context).await?;
     let response = model::SignInResponse { access_token };
     let refresh_cookie = Cookie::build("refresh_token", refresh_token)
         .path("/")
@@ -97,13 +265,145 @@ pub async fn sign_in(
         .cookies(vec![refresh_cookie]))
 }
 
+#[utoipa::path(
+    tag = "auth",
+    request_body = model::ChangePasswordModel,
+    responses(
+        (
+            status = 200,
+            description = "Set cookie `refresh_token` mới",
+            body = success::SuccessData<model::SignInResponse>
+        ),
+        (status = 401, body = error::ErrorBody)
+    )
+)]
+#[post("/change-password")]
+pub async fn change_password(
+    user_service: web::Data<UserService>,
+    req: HttpRequest,
+    ValidatedJson(body): ValidatedJson<model::ChangePasswordModel>,
+) -> Result<success::Success<model::SignInResponse>, error::Error> {
+    let claims = get_extensions::<Claims>(&req)?;
+    let (access_token, refresh_token) = user_service.change_password(&claims, body).await?;
+    let response = model::SignInResponse { access_token };
+    let refresh_cookie = Cookie::build("refresh_token", refresh_token)
+        .path("/")
+        .http_only(true)
+        .same_site(cookie::SameSite::Strict)
+        .secure(true)
+        .max_age(time::Duration::seconds(ENV.refresh_token_expiration as i64))
+        .finish();
+
+    Ok(success::Success::ok(Some(response))
+        .message("Password changed successfully")
+        .cookies(vec![refresh_cookie]))
+}
+
+#[utoipa::path(
+    tag = "auth",
+    security(()),
+    request_body = model::ForgotPasswordModel,
+    responses((status = 200, body = success::MessageOnly))
+)]
+#[post("/forgot-password")]
+pub async fn forgot_password(
+    user_service: web::Data<UserService>,
+    ValidatedJson(body): ValidatedJson<model::ForgotPasswordModel>,
+) -> Result<success::Success<()>, error::Error> {
+    user_service.forgot_password(&body.email).await?;
+    Ok(success::Success::ok(None)
+        .message("If the email is registered, a password reset link has been sent"))
+}
+
+#[utoipa::path(
+    tag = "auth",
+    security(()),
+    request_body = model::ResetPasswordModel,
+    responses(
+        (status = 200, body = success::MessageOnly),
+        (status = 400, description = "Token không hợp lệ hoặc đã hết hạn", body = error::ErrorBody)
+    )
+)]
+#[post("/reset-password")]
+pub async fn reset_password(
+    user_service: web::Data<UserService>,
+    ValidatedJson(body): ValidatedJson<model::ResetPasswordModel>,
+) -> Result<success::Success<()>, error::Error> {
+    user_service.reset_password(body).await?;
+    Ok(success::Success::ok(None).message("Password reset successfully"))
+}
+
+#[utoipa::path(
+    tag = "auth",
+    security(()),
+    params(model::VerifyEmailQuery),
+    responses(
+        (status = 200, body = success::MessageOnly),
+        (status = 400, description = "Token không hợp lệ hoặc đã hết hạn", body = error::ErrorBody)
+    )
+)]
+#[get("/verify-email")]
+pub async fn verify_email(
+    user_service: web::Data<UserService>,
+    ValidatedQuery(query): ValidatedQuery<model::VerifyEmailQuery>,
+) -> Result<success::Success<()>, error::Error> {
+    user_service.verify_email(&query.token).await?;
+    Ok(success::Success::ok(None).message("Email verified successfully"))
+}
+
+/// Cho phép đăng nhập từ IP mới bằng token trong email xác nhận, sau đó đăng nhập lại
+#[utoipa::path(
+    tag = "auth",
+    security(()),
+    request_body = model::ConfirmSignInModel,
+    responses(
+        (status = 200, body = success::MessageOnly),
+        (status = 401, description = "Token không hợp lệ hoặc đã hết hạn", body = error::ErrorBody)
+    )
+)]
+#[post("/confirm-sign-in")]
+pub async fn confirm_sign_in(
+    user_service: web::Data<UserService>,
+    ValidatedJson(body): ValidatedJson<model::ConfirmSignInModel>,
+) -> Result<success::Success<()>, error::Error> {
+    user_service.confirm_sign_in(&body.token).await?;
+    Ok(success::Success::ok(None).message("Sign-in confirmed, please sign in again"))
+}
+
+#[utoipa::path(
+    tag = "auth",
+    security(()),
+    request_body = model::ResendVerificationModel,
+    responses((status = 200, body = success::MessageOnly))
+)]
+#[post("/resend-verification")]
+pub async fn resend_verification(
+    user_service: web::Data<UserService>,
+    ValidatedJson(body): ValidatedJson<model::ResendVerificationModel>,
+) -> Result<success::Success<()>, error::Error> {
+    user_service.resend_verification(&body.email).await?;
+    Ok(success::Success::ok(None)
+        .message("If the email is registered and unverified, a verification link has been sent"))
+}
+
+#[utoipa::path(
+    tag = "auth",
+    security(()),
+    responses((status = 204, description = "Thu hồi tokens và xóa cookie `refresh_token`"))
+)]
 #[get("/signout")]
 pub async fn sign_out(
-    user_service: web::Data<UserSvc>,
+    user_service: web::Data<UserService>,
     req: HttpRequest,
 ) -> Result<success::Success<()>, error::Error> {
     let refresh_token = req.cookie("refresh_token").map(|c| c.value().to_string());
-    user_service.sign_out(refresh_token).await?;
+    let access_token = req
+        .headers()
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .map(str::to_string);
+    user_service.sign_out(refresh_token, access_token).await?;
     let refresh_cookie = Cookie::build("refresh_token", "")
         .path("/")
         .http_only(true)
@@ -116,9 +416,21 @@ pub async fn sign_out(
     Ok(success::Success::no_content().cookies(vec![refresh_cookie]))
 }
 
+#[utoipa::path(
+    tag = "auth",
+    security(()),
+    responses(
+        (
+            status = 200,
+            description = "Đọc và xoay vòng cookie `refresh_token`",
+            body = success::SuccessData<model::SignInResponse>
+        ),
+        (status = 401, body = error::ErrorBody)
+    )
+)]
 #[post("/refresh")]
 pub async fn refresh(
-    user_service: web::Data<UserSvc>,
+    user_service: web::Data<UserService>,
     req: HttpRequest,
 ) -> Result<success::Success<model::SignInResponse>, error::Error> {
     let refresh_token = req.cookie("refresh_token").map(|c| c.value().to_string());
@@ -136,35 +448,392 @@ pub async fn refresh(
         .cookies(vec![refresh_cookie]))
 }
 
+#[utoipa::path(
+    tag = "users",
+    params(model::UserSearchQuery),
+    responses((status = 200, body = success::SuccessData<Vec<model::UserResponse>>))
+)]
 #[get("/search")]
 pub async fn search_users(
-    user_service: web::Data<UserSvc>,
+    user_service: web::Data<UserService>,
     ValidatedQuery(query): ValidatedQuery<model::UserSearchQuery>,
+    req: HttpRequest,
 ) -> Result<success::Success<Vec<model::UserResponse>>, error::Error> {
-    let users = user_service.search_users(&query.q, query.limit.unwrap_or(10)).await?;
+    let viewer_id = get_extensions::<Claims>(&req)?.sub;
+    let users = user_service.search_users(viewer_id, &query.q, query.limit.unwrap_or(10)).await?;
     Ok(success::Success::ok(Some(users)).message("Users found successfully"))
 }
 
+/// Gợi ý users khi đang gõ (gọi theo từng phím), bạn bè và người có conversations chung
+/// được xếp trước
+///
+/// GET /users/autocomplete?q=al&limit=8
+#[utoipa::path(
+    tag = "users",
+    params(model::UserAutocompleteQuery),
+    responses((status = 200, body = success::SuccessData<Vec<model::UserResponse>>))
+)]
+#[get("/autocomplete")]
+pub async fn autocomplete_users(
+    user_service: web::Data<UserService>,
+    ValidatedQuery(query): ValidatedQuery<model::UserAutocompleteQuery>,
+    req: HttpRequest,
+) -> Result<success::Success<Vec<model::UserResponse>>, error::Error> {
+    let viewer_id = get_extensions::<Claims>(&req)?.sub;
+    let users =
+        user_service.autocomplete_users(viewer_id, &query.q, query.limit.unwrap_or(8)).await?;
+    Ok(success::Success::ok(Some(users)))
+}
+
+/// Kiểm tra username trước khi đổi: chưa thuộc ai và không phải username cũ đang được
+/// giữ cho người khác. Username hiện tại / cũ của chính caller luôn dùng được
+#[utoipa::path(
+    tag = "users",
+    params(model::UsernameAvailabilityQuery),
+    responses(
+        (status = 200, body = success::SuccessData<model::UsernameAvailability>),
+        (status = 400, description = "Username không đúng định dạng", body = error::ErrorBody)
+    )
+)]
+#[get("/username-available")]
+pub async fn check_username(
+    user_service: web::Data<UserService>,
+    ValidatedQuery(query): ValidatedQuery<model::UsernameAvailabilityQuery>,
+    req: HttpRequest,
+) -> Result<success::Success<model::UsernameAvailability>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let availability = user_service.check_username(Some(user_id), &query.name).await?;
+    Ok(success::Success::ok(Some(availability)))
+}
+
+/// Contact sync: trả về users đã đăng ký khớp với email / số điện thoại đã hash
+///
+/// POST /users/lookup
+/// Body: { "hashes": ["<sha256 hex>", ...] }
+///
+/// Users tắt `discoverable` không xuất hiện trong kết quả.
+#[utoipa::path(
+    tag = "users",
+    request_body = model::ContactLookupRequest,
+    responses(
+        (status = 200, body = success::SuccessData<Vec<model::UserResponse>>),
+        (status = 400, description = "Quá 500 contacts", body = error::ErrorBody)
+    )
+)]
+#[post("/lookup")]
+pub async fn lookup_contacts(
+    user_service: web::Data<UserService>,
+    req: HttpRequest,
+    ValidatedJson(body): ValidatedJson<model::ContactLookupRequest>,
+) -> Result<success::Success<Vec<model::UserResponse>>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let users = user_service.lookup_contacts(user_id, body.hashes).await?;
+    Ok(success::Success::ok(Some(users)).message("Contacts matched successfully"))
+}
+
 /// Batch query presence status cho nhiều users
 ///
 /// POST /users/presence
 /// Body: { "user_ids": ["uuid1", "uuid2", ...] }
 ///
-/// Response: [{ "user_id": "...", "is_online": true, "last_seen": null }, ...]
+/// Response: [{ "user_id": "...", "is_online": true, "last_seen": null, "status": null,
+/// "dnd": false }, ...]
+///
+/// `dnd` chỉ bật cho bạn bè (và chính mình) đang trong giờ DND và chọn hiện badge
+#[utoipa::path(
+    tag = "users",
+    request_body = model::PresenceQuery,
+    responses(
+        (status = 200, body = success::SuccessData<Vec<PresenceInfo>>),
+        (status = 400, description = "Quá 200 user IDs", body = error::ErrorBody)
+    )
+)]
 #[post("/presence")]
 pub async fn get_presence(
     presence_service: web::Data<PresenceService>,
-    body: web::Json<model::PresenceQuery>,
+    user_service: web::Data<UserService>,
+    friend_repo: web::Data<FriendRepositoryPg>,
+    req: HttpRequest,
+    ValidatedJson(body): ValidatedJson<model::PresenceQuery>,
 ) -> Result<success::Success<Vec<PresenceInfo>>, error::Error> {
     if body.user_ids.is_empty() {
         return Ok(success::Success::ok(Some(vec![])));
     }
 
-    // Giới hạn số lượng users per request để tránh abuse
-    if body.user_ids.len() > 200 {
-        return Err(error::Error::bad_request("Maximum 200 user IDs per request"));
+    // Ẩn trạng thái theo presence visibility của từng user
+    let viewer_id = get_extensions::<Claims>(&req)?.sub;
+    let friend_ids: HashSet<Uuid> =
+        friend_repo.find_friend_ids(&viewer_id).await?.into_iter().collect();
+    let mut presences =
+        presence_service.get_presence_for_viewer(viewer_id, &friend_ids, &body.user_ids).await?;
+
+    let visible: Vec<Uuid> = body
+        .user_ids
+        .iter()
+        .filter(|id| **id == viewer_id || friend_ids.contains(id))
+        .copied()
+        .collect();
+    let dnd: HashSet<Uuid> = user_service.find_dnd_badges(&visible).await?.into_iter().collect();
+    for presence in &mut presences {
+        presence.dnd = dnd.contains(&presence.user_id);
     }
 
-    let presences = presence_service.get_online_status_batch(&body.user_ids).await?;
     Ok(success::Success::ok(Some(presences)))
 }
+
+#[utoipa::path(
+    tag = "users",
+    responses((status = 200, body = success::SuccessData<model::PresenceSettingsModel>))
+)]
+#[get("/settings/presence")]
+pub async fn get_presence_settings(
+    user_service: web::Data<UserService>,
+    req: HttpRequest,
+) -> Result<success::Success<model::PresenceSettingsModel>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let visibility = user_service.get_presence_visibility(user_id).await?;
+    Ok(success::Success::ok(Some(model::PresenceSettingsModel { visibility })))
+}
+
+/// Đổi ai được xem trạng thái online / last_seen (everyone / friends / nobody)
+///
+/// Nếu user đang online và chuyển sang / từ `nobody`, friends nhận presence event tương ứng
+#[utoipa::path(
+    tag = "users",
+    request_body = model::PresenceSettingsModel,
+    responses((status = 200, body = success::SuccessData<model::PresenceSettingsModel>))
+)]
+#[put("/settings/presence")]
+pub async fn update_presence_settings(
+    user_service: web::Data<UserService>,
+    presence_service: web::Data<PresenceService>,
+    friend_repo: web::Data<FriendRepositoryPg>,
+    ws_server: web::Data<Addr<WebSocketServer>>,
+    req: HttpRequest,
+    body: web::Json<model::PresenceSettingsModel>,
+) -> Result<success::Success<model::PresenceSettingsModel>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let visibility = body.visibility;
+
+    let previous = user_service.get_presence_visibility(user_id).await?;
+    user_service.update_presence_visibility(user_id, visibility).await?;
+    presence_service.set_visibility(user_id, visibility).await?;
+
+    let hidden = visibility == PresenceVisibility::Nobody;
+    if hidden != (previous == PresenceVisibility::Nobody)
+        && presence_service.is_online(user_id).await?
+    {
+        let friend_ids = friend_repo.find_friend_ids(&user_id).await?;
+        if !friend_ids.is_empty() {
+            ws_server.do_send(UserPresenceChanged {
+                user_id,
+                is_online: !hidden,
+                friend_ids,
+                last_seen: None,
+            });
+        }
+    }
+
+    Ok(success::Success::ok(Some(model::PresenceSettingsModel { visibility }))
+        .message("Presence settings updated successfully"))
+}
+
+/// Lịch sử đăng nhập thành công gần nhất (IP, user agent), mới nhất trước
+#[utoipa::path(
+    tag = "users",
+    responses((status = 200, body = success::SuccessData<Vec<SignInEntity>>))
+)]
+#[get("/me/sign-ins")]
+pub async fn list_sign_ins(
+    user_service: web::Data<UserService>,
+    req: HttpRequest,
+) -> Result<success::Success<Vec<SignInEntity>>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let sign_ins = user_service.list_sign_ins(user_id).await?;
+    Ok(success::Success::ok(Some(sign_ins)))
+}
+
+#[utoipa::path(
+    tag = "users",
+    responses((status = 200, body = success::SuccessData<model::UserSettings>))
+)]
+#[get("/me/settings")]
+pub async fn get_settings(
+    user_service: web::Data<UserService>,
+    req: HttpRequest,
+) -> Result<success::Success<model::UserSettings>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let settings = user_service.get_settings(user_id).await?;
+    Ok(success::Success::ok(Some(settings)))
+}
+
+/// Đổi ai được xem email / số điện thoại / bio (everyone / friends / nobody) và ai được
+/// gửi lời mời kết bạn (everyone / friends_of_friends / nobody)
+#[utoipa::path(
+    tag = "users",
+    request_body = model::UpdateUserSettingsModel,
+    responses((status = 200, body = success::SuccessData<model::UserSettings>))
+)]
+#[patch("/me/settings")]
+pub async fn update_settings(
+    user_service: web::Data<UserService>,
+    req: HttpRequest,
+    body: web::Json<model::UpdateUserSettingsModel>,
+) -> Result<success::Success<model::UserSettings>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let settings = user_service.update_settings(user_id, body.into_inner()).await?;
+    Ok(success::Success::ok(Some(settings)).message("Settings updated successfully"))
+}
+
+#[utoipa::path(
+    tag = "users",
+    responses(
+        (status = 200, body = success::SuccessData<model::DndSettings>),
+        (status = 404, description = "Chưa đặt lịch DND", body = error::ErrorBody)
+    )
+)]
+#[get("/me/dnd")]
+pub async fn get_dnd(
+    user_service: web::Data<UserService>,
+    req: HttpRequest,
+) -> Result<success::Success<model::DndSettings>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let dnd = user_service.get_dnd(user_id).await?;
+    Ok(success::Success::ok(Some(dnd)))
+}
+
+/// Đặt lịch Do Not Disturb hằng ngày theo giờ địa phương (`timezone` IANA). Lịch qua nửa
+/// đêm được hỗ trợ (vd 22:00 - 07:00). Trong giờ DND không có push notification, friends
+/// thấy badge DND ở presence nếu `show_badge = true`
+#[utoipa::path(
+    tag = "users",
+    request_body = model::DndSchedule,
+    responses(
+        (status = 200, body = success::SuccessData<model::DndSettings>),
+        (status = 400, description = "Lịch rỗng hoặc timezone sai", body = error::ErrorBody)
+    )
+)]
+#[put("/me/dnd")]
+pub async fn set_dnd(
+    user_service: web::Data<UserService>,
+    req: HttpRequest,
+    ValidatedJson(body): ValidatedJson<model::DndSchedule>,
+) -> Result<success::Success<model::DndSettings>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    let dnd = user_service.set_dnd(user_id, body).await?;
+    Ok(success::Success::ok(Some(dnd)).message("DND schedule updated successfully"))
+}
+
+#[utoipa::path(
+    tag = "users",
+    responses(
+        (status = 204, description = "Đã xóa lịch DND"),
+        (status = 404, description = "Chưa đặt lịch DND", body = error::ErrorBody)
+    )
+)]
+#[delete("/me/dnd")]
+pub async fn clear_dnd(
+    user_service: web::Data<UserService>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let user_id = get_extensions::<Claims>(&req)?.sub;
+    user_service.clear_dnd(user_id).await?;
+    Ok(success::Success::no_content())
+}
+
+#[utoipa::path(
+    tag = "admin",
+    request_body = model::MergeAccountsModel,
+    responses((status = 200, body = success::SuccessData<model::MergeSummary>))
+)]
+#[post("/merge")]
+pub async fn merge_accounts(
+    user_service: web::Data<UserService>,
+    conversation_svc: web::Data<ConversationService>,
+    ValidatedJson(body): ValidatedJson<model::MergeAccountsModel>,
+) -> Result<success::Success<model::MergeSummary>, error::Error> {
+    let summary = user_service.merge_accounts(body.primary_id, body.duplicate_id).await?;
+    for (conversation_id, user_id) in &summary.membership_changes {
+        conversation_svc.invalidate_membership(*conversation_id, &[*user_id]).await;
+    }
+    Ok(success::Success::ok(Some(summary)).message("Accounts merged successfully"))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    params(model::AdminUserQuery),
+    responses((status = 200, body = success::SuccessData<model::AdminUserListResponse>))
+)]
+#[get("")]
+pub async fn list_users(
+    user_service: web::Data<UserService>,
+    ValidatedQuery(query): ValidatedQuery<model::AdminUserQuery>,
+) -> Result<success::Success<model::AdminUserListResponse>, error::Error> {
+    let users = user_service
+        .list_users(query.q.as_deref(), query.page.unwrap_or(1), query.limit.unwrap_or(20))
+        .await?;
+    Ok(success::Success::ok(Some(users)).message("Users retrieved successfully"))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    path = "/{id}/ban",
+    params(("id" = Uuid, Path, description = "User ID")),
+    request_body = model::BanUserModel,
+    responses((status = 200, body = success::MessageOnly))
+)]
+#[post("/{id:[0-9a-fA-F-]{36}}/ban")]
+pub async fn ban_user(
+    user_service: web::Data<UserService>,
+    user_id: web::Path<Uuid>,
+    req: HttpRequest,
+    ValidatedJson(body): ValidatedJson<model::BanUserModel>,
+) -> Result<success::Success<()>, error::Error> {
+    let admin_id = get_extensions::<Claims>(&req)?.sub;
+    user_service.ban_user(admin_id, user_id.into_inner(), body.reason).await?;
+    Ok(success::Success::ok(None).message("User banned successfully"))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    path = "/{id}/ban",
+    params(("id" = Uuid, Path, description = "User ID")),
+    responses((status = 200, body = success::MessageOnly))
+)]
+#[delete("/{id:[0-9a-fA-F-]{36}}/ban")]
+pub async fn unban_user(
+    user_service: web::Data<UserService>,
+    user_id: web::Path<Uuid>,
+) -> Result<success::Success<()>, error::Error> {
+    user_service.unban_user(user_id.into_inner()).await?;
+    Ok(success::Success::ok(None).message("User unbanned successfully"))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    path = "/{id}/signout",
+    params(("id" = Uuid, Path, description = "User ID")),
+    responses((status = 200, body = success::MessageOnly))
+)]
+#[post("/{id:[0-9a-fA-F-]{36}}/signout")]
+pub async fn force_sign_out(
+    user_service: web::Data<UserService>,
+    user_id: web::Path<Uuid>,
+) -> Result<success::Success<()>, error::Error> {
+    user_service.force_sign_out(user_id.into_inner()).await?;
+    Ok(success::Success::ok(None).message("User signed out successfully"))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    params(model::PlatformStatsQuery),
+    responses((status = 200, body = success::SuccessData<model::PlatformStats>))
+)]
+#[get("/stats")]
+pub async fn platform_stats(
+    user_service: web::Data<UserService>,
+    ValidatedQuery(query): ValidatedQuery<model::PlatformStatsQuery>,
+) -> Result<success::Success<model::PlatformStats>, error::Error> {
+    let stats = user_service.platform_stats(query.days.unwrap_or(7)).await?;
+    Ok(success::Success::ok(Some(stats)).message("Platform stats retrieved successfully"))
+}
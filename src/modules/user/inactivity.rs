@@ -0,0 +1,27 @@
+/// Inactive Account Worker
+///
+/// Task chạy nền định kỳ xử lý các tài khoản không hoạt động (không có request đã xác thực
+/// hay WebSocket heartbeat) quá `INACTIVE_ACCOUNT_DAYS` ngày: chỉ đánh dấu
+/// `inactive_flagged_at` để admin xem xét, hoặc tạm khóa luôn tùy `INACTIVE_ACCOUNT_ACTION`.
+/// Mỗi lần chạy xử lý một batch, các rows bị lock được bỏ qua nên chạy trên nhiều instance
+/// không xử lý trùng.
+use std::time::Duration;
+
+use crate::modules::user::service::UserService;
+
+/// Khoảng thời gian giữa hai lần chạy
+const INACTIVITY_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+pub async fn run_inactive_account_worker(service: UserService) {
+    let mut interval = actix_web::rt::time::interval(INACTIVITY_CHECK_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match service.process_inactive_accounts().await {
+            Ok(0) => {}
+            Ok(processed) => tracing::info!("Processed {} inactive accounts", processed),
+            Err(e) => tracing::error!("Failed to process inactive accounts: {:?}", e),
+        }
+    }
+}
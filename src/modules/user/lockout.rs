@@ -0,0 +1,129 @@
+/// Sign-in Lockout
+///
+/// Đếm số lần đăng nhập sai theo username và theo IP trong Redis (cửa sổ cố định
+/// `FAILURE_WINDOW`). Đủ ngưỡng (`SIGN_IN_MAX_ATTEMPTS` / `SIGN_IN_IP_MAX_ATTEMPTS`) thì
+/// khóa tạm thời, mỗi lần bị khóa tiếp theo trong `LOCKOUT_LEVEL_TTL` thời gian khóa tăng
+/// gấp đôi (từ `SIGN_IN_LOCKOUT_SECONDS`, tối đa `SIGN_IN_LOCKOUT_MAX_SECONDS`).
+/// Đăng nhập thành công xóa bộ đếm và mức khóa của username.
+use std::sync::Arc;
+
+use crate::{
+    api::error,
+    configs::{cache::CacheBackend, settings::settings},
+};
+
+/// Cửa sổ đếm số lần đăng nhập sai (giây)
+const FAILURE_WINDOW: usize = 15 * 60;
+
+/// Mức khóa được nhớ trong khoảng này (giây), hết hạn thì thời gian khóa về mức cơ bản
+const LOCKOUT_LEVEL_TTL: usize = 24 * 60 * 60;
+
+/// Đối tượng bị đếm số lần đăng nhập sai
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LockoutTarget<'a> {
+    Username(&'a str),
+    Ip(&'a str),
+}
+
+impl LockoutTarget<'_> {
+    fn key(&self, kind: &str) -> String {
+        match self {
+            LockoutTarget::Username(username) => {
+                format!("sign_in_{kind}:user:{}", username.trim().to_lowercase())
+            }
+            LockoutTarget::Ip(ip) => format!("sign_in_{kind}:ip:{ip}"),
+        }
+    }
+
+    /// Số lần sai tối đa trước khi bị khóa, 0 là tắt
+    fn max_attempts(&self) -> i64 {
+        let settings = settings();
+        match self {
+            LockoutTarget::Username(_) => settings.sign_in_max_attempts,
+            LockoutTarget::Ip(_) => settings.sign_in_ip_max_attempts,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SignInLockout {
+    cache: Arc<dyn CacheBackend>,
+}
+
+impl SignInLockout {
+    pub fn new(cache: Arc<dyn CacheBackend>) -> Self {
+        SignInLockout { cache }
+    }
+
+    /// Lỗi `ACCOUNT_LOCKED` nếu một trong các targets đang bị khóa
+    pub async fn ensure_unlocked(
+        &self,
+        targets: &[LockoutTarget<'_>],
+    ) -> Result<(), error::SystemError> {
+        let now = chrono::Utc::now().timestamp();
+
+        for target in targets {
+            if let Some(locked_until) = self.cache.get::<i64>(&target.key("lock")).await? {
+                if locked_until > now {
+                    return Err(locked_error(locked_until - now));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ghi nhận một lần đăng nhập sai, trả về các targets vừa bị khóa kèm thời gian khóa
+    /// (giây)
+    pub async fn record_failure<'a>(
+        &self,
+        targets: &[LockoutTarget<'a>],
+    ) -> Result<Vec<(LockoutTarget<'a>, i64)>, error::SystemError> {
+        let mut locked = Vec::new();
+
+        for target in targets {
+            let max_attempts = target.max_attempts();
+            if max_attempts <= 0 {
+                continue;
+            }
+
+            let failures_key = target.key("failures");
+            if self.cache.incr(&failures_key, FAILURE_WINDOW).await? < max_attempts {
+                continue;
+            }
+
+            let level = self.cache.incr(&target.key("lockout_level"), LOCKOUT_LEVEL_TTL).await?;
+            let duration = lockout_duration(level);
+            let locked_until = chrono::Utc::now().timestamp() + duration;
+            self.cache.set(&target.key("lock"), &locked_until, duration as usize).await?;
+            self.cache.delete(&failures_key).await?;
+
+            locked.push((*target, duration));
+        }
+
+        Ok(locked)
+    }
+
+    /// Xóa bộ đếm và mức khóa sau khi đăng nhập thành công
+    pub async fn reset(&self, target: LockoutTarget<'_>) -> Result<(), error::SystemError> {
+        self.cache.delete(&target.key("failures")).await?;
+        self.cache.delete(&target.key("lockout_level")).await
+    }
+}
+
+/// Thời gian khóa của lần khóa thứ `level` (bắt đầu từ 1), tăng gấp đôi mỗi lần
+fn lockout_duration(level: i64) -> i64 {
+    let settings = settings();
+    let base = settings.sign_in_lockout_seconds as i64;
+    let max = settings.sign_in_lockout_max_seconds as i64;
+    let doublings = (level - 1).clamp(0, 30) as u32;
+
+    base.saturating_mul(1_i64 << doublings).min(max).max(1)
+}
+
+pub fn locked_error(retry_after_secs: i64) -> error::SystemError {
+    error::SystemError::too_many_requests(format!(
+        "Too many failed sign-in attempts, try again in {retry_after_secs} seconds"
+    ))
+    .with_code(error::ErrorCode::AccountLocked)
+}
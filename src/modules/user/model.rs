@@ -1,10 +1,13 @@
 use core::str;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
-use crate::modules::user::schema::UserEntity;
+use crate::modules::user::schema::{
+    FieldVisibility, FriendRequestPolicy, PresenceVisibility, UserEntity, UserRole,
+};
 
-#[derive(Deserialize, Validate)]
+#[derive(Deserialize, Validate, ToSchema)]
 pub struct SignUpModel {
     #[validate(length(min = 3, message = "Username must be at least 3 characters long"))]
     pub username: String,
@@ -16,7 +19,7 @@ pub struct SignUpModel {
     pub display_name: String,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Deserialize, Validate, ToSchema)]
 pub struct SignInModel {
     #[validate(length(min = 3, message = "Username must be at least 3 characters long"))]
     pub username: String,
@@ -24,6 +27,54 @@ pub struct SignInModel {
     pub password: String,
 }
 
+/// Thông tin client của một lần đăng nhập, lấy từ request
+#[derive(Debug, Clone, Default)]
+pub struct SignInContext {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct ConfirmSignInModel {
+    #[validate(length(min = 1, message = "Confirmation token cannot be empty"))]
+    pub token: String,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct ChangePasswordModel {
+    #[validate(length(min = 6, message = "Password must be at least 6 characters long"))]
+    pub old_password: String,
+    #[validate(length(min = 6, message = "Password must be at least 6 characters long"))]
+    pub new_password: String,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct ForgotPasswordModel {
+    #[validate(email(message = "Invalid email format"))]
+    pub email: String,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct ResetPasswordModel {
+    #[validate(length(min = 1, message = "Reset token cannot be empty"))]
+    pub token: String,
+    #[validate(length(min = 6, message = "Password must be at least 6 characters long"))]
+    pub new_password: String,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct ResendVerificationModel {
+    #[validate(email(message = "Invalid email format"))]
+    pub email: String,
+}
+
+#[derive(Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VerifyEmailQuery {
+    #[validate(length(min = 1, message = "Verification token cannot be empty"))]
+    pub token: String,
+}
+
 #[allow(unused)]
 #[derive(Deserialize, Validate)]
 pub struct RefreshTokenModel {
@@ -33,7 +84,7 @@ pub struct RefreshTokenModel {
 
 use crate::utils::double_option;
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UpdateUserModel {
     #[validate(length(min = 3, message = "Username must be at least 3 characters long"))]
     pub username: Option<String>,
@@ -48,6 +99,8 @@ pub struct UpdateUserModel {
     #[validate(length(min = 10, message = "Phone number must be at least 10 digits long"))]
     #[serde(default, deserialize_with = "double_option")]
     pub phone: Option<Option<String>>,
+    /// Cho phép người khác tìm thấy mình qua contact lookup (email / số điện thoại)
+    pub discoverable: Option<bool>,
 }
 
 impl UpdateUserModel {
@@ -58,6 +111,7 @@ impl UpdateUserModel {
             && self.avatar_url.is_none()
             && self.bio.is_none()
             && self.phone.is_none()
+            && self.discoverable.is_none()
     }
 }
 
@@ -76,20 +130,22 @@ pub struct UpdateUser {
     pub avatar_url: Option<Option<String>>,
     pub bio: Option<Option<String>>,
     pub phone: Option<Option<String>>,
+    pub discoverable: Option<bool>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct SignUpResponse {
     pub id: uuid::Uuid,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 
 pub struct SignInResponse {
     pub access_token: String,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct UserSearchQuery {
     #[validate(length(min = 2, message = "Search query must be at least 2 characters"))]
     pub q: String,
@@ -97,12 +153,22 @@ pub struct UserSearchQuery {
     pub limit: Option<i32>,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UserAutocompleteQuery {
+    /// Phần đầu username hoặc một phần display name đang gõ
+    #[validate(length(min = 1, max = 64, message = "Query must be 1 to 64 characters"))]
+    pub q: String,
+    #[validate(range(min = 1, max = 20, message = "Limit must be between 1 and 20"))]
+    pub limit: Option<i32>,
+}
 
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct UserResponse {
     pub id: uuid::Uuid,
     pub username: String,
-    pub email: String,
+    /// `None` khi bị ẩn theo cài đặt quyền riêng tư của user
+    pub email: Option<String>,
     pub display_name: String,
     pub avatar_url: Option<String>,
     pub bio: Option<String>,
@@ -114,7 +180,7 @@ impl From<UserEntity> for UserResponse {
         UserResponse {
             id: entity.id,
             username: entity.username,
-            email: entity.email,
+            email: Some(entity.email),
             display_name: entity.display_name,
             avatar_url: entity.avatar_url,
             bio: entity.bio,
@@ -123,8 +189,220 @@ impl From<UserEntity> for UserResponse {
     }
 }
 
+impl UserResponse {
+    /// Ẩn các trường mà người xem không được thấy theo cài đặt quyền riêng tư
+    pub fn redact_for(mut self, privacy: &ProfilePrivacy) -> Self {
+        if !privacy.email_visibility.allows(privacy.is_friend) {
+            self.email = None;
+        }
+        if !privacy.phone_visibility.allows(privacy.is_friend) {
+            self.phone = None;
+        }
+        if !privacy.bio_visibility.allows(privacy.is_friend) {
+            self.bio = None;
+        }
+        self
+    }
+}
+
+/// Cài đặt quyền riêng tư của profile, user chưa lưu cài đặt dùng giá trị mặc định
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct UserSettings {
+    pub email_visibility: FieldVisibility,
+    pub phone_visibility: FieldVisibility,
+    pub bio_visibility: FieldVisibility,
+    /// Ai được gửi lời mời kết bạn
+    pub friend_request_policy: FriendRequestPolicy,
+}
+
+/// Body của PATCH settings, trường bỏ trống giữ nguyên giá trị hiện tại
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateUserSettingsModel {
+    pub email_visibility: Option<FieldVisibility>,
+    pub phone_visibility: Option<FieldVisibility>,
+    pub bio_visibility: Option<FieldVisibility>,
+    pub friend_request_policy: Option<FriendRequestPolicy>,
+}
+
+/// Lịch Do Not Disturb: không nhận push trong khung giờ [start, end) theo `timezone` của
+/// user, khung giờ qua nửa đêm khi `start` > `end` (vd 22:00 → 07:00)
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, sqlx::FromRow, ToSchema)]
+pub struct DndSchedule {
+    #[schema(value_type = String, example = "22:00:00")]
+    pub start: chrono::NaiveTime,
+    #[schema(value_type = String, example = "07:00:00")]
+    pub end: chrono::NaiveTime,
+    /// IANA timezone, vd `Asia/Ho_Chi_Minh`
+    #[validate(length(min = 1, max = 64, message = "Timezone must be 1 to 64 characters"))]
+    pub timezone: String,
+    /// Bạn bè thấy badge DND trong presence khi đang trong khung giờ
+    #[serde(default)]
+    pub show_badge: bool,
+}
+
+/// Lịch DND đã lưu kèm trạng thái hiện tại
+#[derive(Debug, Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct DndSettings {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub schedule: DndSchedule,
+    /// Đang trong khung giờ DND
+    pub active: bool,
+}
+
+/// Cài đặt hiển thị profile của một user kèm quan hệ với người xem
+#[derive(Debug, sqlx::FromRow)]
+pub struct ProfilePrivacy {
+    pub user_id: uuid::Uuid,
+    pub email_visibility: FieldVisibility,
+    pub phone_visibility: FieldVisibility,
+    pub bio_visibility: FieldVisibility,
+    /// Người xem là bạn bè của user
+    pub is_friend: bool,
+    /// User đang tạm khóa tài khoản, profile bị ẩn với người khác
+    pub deactivated: bool,
+}
+
+#[derive(Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UsernameAvailabilityQuery {
+    #[validate(length(min = 1, max = 64, message = "Name must be 1 to 64 characters"))]
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UsernameAvailability {
+    /// Username sau chuẩn hóa (lowercase, bỏ `@` ở đầu)
+    pub username: String,
+    pub available: bool,
+}
+
+/// Body cho contact lookup: SHA-256 (hex) của email lowercase hoặc số điện thoại
+/// chỉ giữ chữ số và dấu `+`, client hash trước khi gửi để không lộ danh bạ thô
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ContactLookupRequest {
+    #[validate(length(min = 1, max = 500, message = "Must lookup 1 to 500 contacts"))]
+    pub hashes: Vec<String>,
+}
+
+/// Cài đặt quyền riêng tư của trạng thái online / last_seen
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PresenceSettingsModel {
+    pub visibility: PresenceVisibility,
+}
+
 /// Query body cho batch presence check
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct PresenceQuery {
+    #[validate(length(max = 200, message = "Maximum 200 user IDs per request"))]
     pub user_ids: Vec<uuid::Uuid>,
 }
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct MergeAccountsModel {
+    pub primary_id: uuid::Uuid,
+    pub duplicate_id: uuid::Uuid,
+}
+
+/// Kết quả cập nhật avatar: user sau cập nhật kèm `avatar_id` (file ID) của avatar cũ
+#[derive(Debug, sqlx::FromRow)]
+pub struct AvatarChange {
+    #[sqlx(flatten)]
+    pub user: UserEntity,
+    pub previous_avatar_id: Option<String>,
+}
+
+/// Kết quả merge tài khoản trùng vào tài khoản chính
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MergeSummary {
+    pub primary_id: uuid::Uuid,
+    pub duplicate_id: uuid::Uuid,
+    pub moved_messages: u64,
+    pub merged_conversations: u64,
+    pub affected_user_ids: Vec<uuid::Uuid>,
+    /// Các cặp (conversation, user) có membership thay đổi, cache membership cần được xóa
+    #[serde(skip)]
+    pub membership_changes: Vec<(uuid::Uuid, uuid::Uuid)>,
+}
+
+#[derive(Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AdminUserQuery {
+    #[validate(length(max = 100, message = "Search query must be at most 100 characters"))]
+    pub q: Option<String>,
+    #[validate(range(min = 1, message = "Page must be at least 1"))]
+    pub page: Option<i64>,
+    #[validate(range(min = 1, max = 100, message = "Limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AdminUserResponse {
+    pub id: uuid::Uuid,
+    pub username: String,
+    pub email: String,
+    pub display_name: String,
+    pub role: UserRole,
+    pub email_verified: bool,
+    pub banned_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub ban_reason: Option<String>,
+    pub deactivated_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_active_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub inactive_flagged_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<UserEntity> for AdminUserResponse {
+    fn from(entity: UserEntity) -> Self {
+        AdminUserResponse {
+            id: entity.id,
+            username: entity.username,
+            email: entity.email,
+            display_name: entity.display_name,
+            role: entity.role,
+            email_verified: entity.email_verified,
+            banned_at: entity.banned_at,
+            ban_reason: entity.ban_reason,
+            deactivated_at: entity.deactivated_at,
+            last_active_at: entity.last_active_at,
+            inactive_flagged_at: entity.inactive_flagged_at,
+            created_at: entity.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AdminUserListResponse {
+    pub users: Vec<AdminUserResponse>,
+    pub total: i64,
+    pub page: i64,
+    pub limit: i64,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct BanUserModel {
+    #[validate(length(max = 500, message = "Ban reason must be at most 500 characters"))]
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PlatformStatsQuery {
+    #[validate(range(min = 1, max = 90, message = "Days must be between 1 and 90"))]
+    pub days: Option<i32>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow, ToSchema)]
+pub struct DailyMessageCount {
+    pub day: chrono::NaiveDate,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PlatformStats {
+    pub total_users: i64,
+    pub banned_users: i64,
+    pub new_users_today: i64,
+    pub total_messages: i64,
+    pub messages_per_day: Vec<DailyMessageCount>,
+}
@@ -1,8 +1,13 @@
 use uuid::Uuid;
 
 use crate::{
-    api::error, modules::user::model::InsertUser, modules::user::model::UpdateUser,
-    modules::user::schema::UserEntity,
+    api::error, modules::user::model::AvatarChange, modules::user::model::DndSchedule,
+    modules::user::model::DndSettings, modules::user::model::InsertUser,
+    modules::user::model::MergeSummary, modules::user::model::PlatformStats,
+    modules::user::model::ProfilePrivacy, modules::user::model::UpdateUser,
+    modules::user::model::UserSettings, modules::user::schema::PresenceVisibility,
+    modules::user::schema::SignInEntity, modules::user::schema::UserEntity,
+    modules::user::schema::UserRole,
 };
 
 #[async_trait::async_trait]
@@ -12,9 +17,23 @@ pub trait UserRepository {
         &self,
         username: &str,
     ) -> Result<Option<UserEntity>, error::SystemError>;
+    async fn find_by_email(&self, email: &str) -> Result<Option<UserEntity>, error::SystemError>;
     async fn create(&self, user: &InsertUser) -> Result<Uuid, error::SystemError>;
-    #[allow(unused)]
+    /// Đổi username (nếu có) thì username cũ được ghi vào `username_history`
     async fn update(&self, id: &Uuid, user: &UpdateUser) -> Result<UserEntity, error::SystemError>;
+    /// Cập nhật đồng thời `avatar_url` và `avatar_id`, trả về kèm `avatar_id` cũ
+    async fn update_avatar(
+        &self,
+        id: &Uuid,
+        avatar_url: &str,
+        avatar_id: &str,
+    ) -> Result<AvatarChange, error::SystemError>;
+    async fn update_password(
+        &self,
+        id: &Uuid,
+        hash_password: &str,
+    ) -> Result<bool, error::SystemError>;
+    async fn mark_email_verified(&self, id: &Uuid) -> Result<bool, error::SystemError>;
     async fn delete(&self, id: &Uuid) -> Result<bool, error::SystemError>;
 
     /// Search users by username or display name (case-insensitive, partial match)
@@ -23,4 +42,156 @@ pub trait UserRepository {
         query: &str,
         limit: i32,
     ) -> Result<Vec<UserEntity>, error::SystemError>;
+
+    /// Gợi ý users khi đang gõ: username bắt đầu bằng `query` hoặc display name chứa
+    /// `query` (không phân biệt hoa thường), bỏ `viewer_id` và users chặn / bị chặn.
+    /// Bạn bè đứng đầu, tiếp theo là users có nhiều conversations chung với `viewer_id`
+    async fn autocomplete_users(
+        &self,
+        viewer_id: &Uuid,
+        query: &str,
+        limit: i32,
+    ) -> Result<Vec<UserEntity>, error::SystemError>;
+
+    async fn find_presence_visibility(
+        &self,
+        id: &Uuid,
+    ) -> Result<Option<PresenceVisibility>, error::SystemError>;
+    async fn update_presence_visibility(
+        &self,
+        id: &Uuid,
+        visibility: PresenceVisibility,
+    ) -> Result<bool, error::SystemError>;
+
+    /// Thời điểm đổi username gần nhất của user
+    async fn find_last_username_change(
+        &self,
+        id: &Uuid,
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>, error::SystemError>;
+
+    /// Username (so sánh không phân biệt hoa thường) chưa thuộc user nào và không nằm
+    /// trong `username_history` của user khác kể từ `reserved_since`. `user_id` là người
+    /// đang hỏi, được dùng lại username của chính mình
+    async fn is_username_available(
+        &self,
+        username: &str,
+        user_id: Option<&Uuid>,
+        reserved_since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<bool, error::SystemError>;
+
+    /// `None` nếu user chưa lưu cài đặt (dùng mặc định)
+    async fn find_settings(&self, id: &Uuid) -> Result<Option<UserSettings>, error::SystemError>;
+    async fn upsert_settings(
+        &self,
+        id: &Uuid,
+        settings: &UserSettings,
+    ) -> Result<UserSettings, error::SystemError>;
+
+    /// `None` nếu user chưa đặt lịch DND
+    async fn find_dnd(&self, id: &Uuid) -> Result<Option<DndSettings>, error::SystemError>;
+    async fn upsert_dnd(
+        &self,
+        id: &Uuid,
+        schedule: &DndSchedule,
+    ) -> Result<DndSettings, error::SystemError>;
+    /// Xóa lịch DND, `false` nếu user chưa đặt lịch
+    async fn delete_dnd(&self, id: &Uuid) -> Result<bool, error::SystemError>;
+    /// Timezone IANA mà database hiểu được
+    async fn is_valid_timezone(&self, timezone: &str) -> Result<bool, error::SystemError>;
+    /// Các user trong `user_ids` đang trong khung giờ DND và cho bạn bè thấy badge
+    async fn find_dnd_badges(&self, user_ids: &[Uuid]) -> Result<Vec<Uuid>, error::SystemError>;
+
+    /// Cài đặt hiển thị profile của các users kèm việc `viewer_id` có là bạn của từng
+    /// user hay không, user chưa lưu cài đặt nhận giá trị mặc định
+    async fn find_profile_privacy(
+        &self,
+        viewer_id: &Uuid,
+        user_ids: &[Uuid],
+    ) -> Result<Vec<ProfilePrivacy>, error::SystemError>;
+
+    /// Tìm users khớp SHA-256 (hex) của email hoặc số điện thoại, chỉ gồm users
+    /// cho phép discovery, chưa bị ban / xóa
+    async fn find_by_contact_hashes(
+        &self,
+        hashes: &[String],
+        exclude_user_id: &Uuid,
+    ) -> Result<Vec<UserEntity>, error::SystemError>;
+
+    /// Merge tài khoản trùng vào tài khoản chính trong một transaction (messages,
+    /// friendships, conversations, files, mentions, blocks, devices, bots, settings, OAuth
+    /// identities), xóa key bundle của tài khoản trùng rồi soft-delete tài khoản trùng
+    async fn merge_accounts(
+        &self,
+        primary_id: &Uuid,
+        duplicate_id: &Uuid,
+    ) -> Result<MergeSummary, error::SystemError>;
+
+    /// Admin: list/search users (bao gồm cả user bị ban) với offset pagination,
+    /// trả về kèm tổng số bản ghi
+    async fn list_users(
+        &self,
+        query: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<UserEntity>, i64), error::SystemError>;
+
+    /// Ban (banned = true, kèm reason) hoặc unban (banned = false) user
+    async fn set_banned(
+        &self,
+        id: &Uuid,
+        banned: bool,
+        reason: Option<&str>,
+    ) -> Result<bool, error::SystemError>;
+
+    /// Tạm khóa (deactivated = true) hoặc mở lại tài khoản
+    async fn set_deactivated(
+        &self,
+        id: &Uuid,
+        deactivated: bool,
+    ) -> Result<bool, error::SystemError>;
+
+    /// Đổi role của user (CLI `create-admin`)
+    async fn set_role(&self, id: &Uuid, role: &UserRole) -> Result<bool, error::SystemError>;
+
+    /// Ghi lại một lần đăng nhập thành công
+    async fn record_sign_in(
+        &self,
+        id: &Uuid,
+        ip_address: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<(), error::SystemError>;
+
+    /// `true` nếu user đã từng đăng nhập thành công nhưng chưa bao giờ từ `ip_address`
+    /// (lần đăng nhập đầu tiên không bị coi là lạ)
+    async fn is_new_sign_in_ip(
+        &self,
+        id: &Uuid,
+        ip_address: &str,
+    ) -> Result<bool, error::SystemError>;
+
+    /// Các lần đăng nhập gần nhất của user, mới nhất trước
+    async fn find_sign_ins(
+        &self,
+        id: &Uuid,
+        limit: i64,
+    ) -> Result<Vec<SignInEntity>, error::SystemError>;
+
+    /// Ghi nhận user vừa hoạt động, bỏ đánh dấu không hoạt động nếu có
+    async fn update_last_active(&self, id: &Uuid) -> Result<(), error::SystemError>;
+
+    /// Đánh dấu (hoặc tạm khóa nếu `deactivate`) tối đa `limit` tài khoản role `USER`
+    /// không hoạt động từ trước `inactive_before`, trả về IDs đã xử lý
+    async fn mark_inactive_accounts(
+        &self,
+        inactive_before: chrono::DateTime<chrono::Utc>,
+        deactivate: bool,
+        limit: i64,
+    ) -> Result<Vec<Uuid>, error::SystemError>;
+
+    /// Thống kê tổng quan cho admin
+    async fn platform_stats(&self, days: i32) -> Result<PlatformStats, error::SystemError>;
+
+    /// Bạn của bạn (chưa là bạn) của user, tức những người có thể đang thấy user
+    /// trong gợi ý kết bạn đã cache
+    async fn find_friends_of_friends(&self, id: &Uuid) -> Result<Vec<Uuid>, error::SystemError>;
 }
@@ -0,0 +1,682 @@
+/// In-memory `UserRepository` cho unit tests của services
+///
+/// Giữ users, username history, settings và lịch sử đăng nhập trong bộ nhớ, áp dụng cùng
+/// quy tắc lọc với `UserRepositoryPg` (bỏ user đã xóa, so sánh username / email không phân
+/// biệt hoa thường). Quan hệ bạn bè chỉ có khi test khai báo bằng `add_friendship`.
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::user::{
+        model::{
+            AvatarChange, DndSchedule, DndSettings, InsertUser, MergeSummary, PlatformStats,
+            ProfilePrivacy, UpdateUser, UserSettings,
+        },
+        repository::UserRepository,
+        schema::{PresenceVisibility, SignInEntity, UserEntity, UserRole},
+    },
+};
+
+#[derive(Default)]
+struct State {
+    users: HashMap<Uuid, UserEntity>,
+    avatar_ids: HashMap<Uuid, String>,
+    /// (user_id, username cũ, thời điểm đổi)
+    username_history: Vec<(Uuid, String, chrono::DateTime<chrono::Utc>)>,
+    settings: HashMap<Uuid, UserSettings>,
+    dnd: HashMap<Uuid, DndSchedule>,
+    /// (user_id, lần đăng nhập)
+    sign_ins: Vec<(Uuid, SignInEntity)>,
+    friendships: HashSet<(Uuid, Uuid)>,
+}
+
+/// Clone dùng chung dữ liệu, test giữ một bản để kiểm tra trạng thái sau khi gọi service
+#[derive(Clone, Default)]
+pub struct UserRepositoryMock {
+    state: Arc<Mutex<State>>,
+}
+
+impl UserRepositoryMock {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Lịch DND đang có hiệu lực, luôn tính theo giờ UTC (bỏ qua `timezone`)
+    fn dnd_active(schedule: &DndSchedule) -> bool {
+        let now = chrono::Utc::now().time();
+        if schedule.start <= schedule.end {
+            schedule.start <= now && now < schedule.end
+        } else {
+            now >= schedule.start || now < schedule.end
+        }
+    }
+
+    /// User active mới (role `USER`, email đã xác thực) với `hash_password` cho trước
+    pub fn entity(username: &str, hash_password: &str) -> UserEntity {
+        let now = chrono::Utc::now();
+        UserEntity {
+            id: Uuid::now_v7(),
+            username: username.to_string(),
+            email: format!("{username}@example.com"),
+            hash_password: hash_password.to_string(),
+            role: UserRole::User,
+            display_name: username.to_string(),
+            avatar_url: None,
+            bio: None,
+            phone: None,
+            email_verified: true,
+            discoverable: true,
+            presence_visibility: PresenceVisibility::default(),
+            banned_at: None,
+            ban_reason: None,
+            deactivated_at: None,
+            last_active_at: None,
+            inactive_flagged_at: None,
+            deleted_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn insert(&self, user: UserEntity) -> Uuid {
+        let id = user.id;
+        self.state().users.insert(id, user);
+        id
+    }
+
+    /// Bản ghi hiện tại của user, kể cả khi đã bị xóa
+    pub fn get(&self, id: &Uuid) -> Option<UserEntity> {
+        self.state().users.get(id).cloned()
+    }
+
+    pub fn add_friendship(&self, a: Uuid, b: Uuid) {
+        self.state().friendships.insert((a.min(b), a.max(b)));
+    }
+
+    pub fn remove_friendship(&self, a: Uuid, b: Uuid) {
+        self.state().friendships.remove(&(a.min(b), a.max(b)));
+    }
+
+    pub fn is_friend(&self, a: &Uuid, b: &Uuid) -> bool {
+        are_friends(&self.state(), a, b)
+    }
+
+    pub fn friend_ids(&self, id: &Uuid) -> HashSet<Uuid> {
+        friends_of(&self.state(), id)
+    }
+
+    pub fn sign_ins(&self) -> Vec<SignInEntity> {
+        self.state().sign_ins.iter().map(|(_, sign_in)| sign_in.clone()).collect()
+    }
+
+    fn find_active(
+        &self,
+        predicate: impl Fn(&UserEntity) -> bool,
+    ) -> Result<Option<UserEntity>, error::SystemError> {
+        Ok(self
+            .state()
+            .users
+            .values()
+            .find(|user| user.deleted_at.is_none() && predicate(user))
+            .cloned())
+    }
+
+    fn update_user(
+        &self,
+        id: &Uuid,
+        update: impl FnOnce(&mut UserEntity),
+    ) -> Result<bool, error::SystemError> {
+        let mut state = self.state();
+        match state.users.get_mut(id).filter(|user| user.deleted_at.is_none()) {
+            Some(user) => {
+                update(user);
+                user.updated_at = chrono::Utc::now();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+fn are_friends(state: &State, a: &Uuid, b: &Uuid) -> bool {
+    state.friendships.contains(&(*a.min(b), *a.max(b)))
+}
+
+fn friends_of(state: &State, id: &Uuid) -> HashSet<Uuid> {
+    state
+        .friendships
+        .iter()
+        .filter_map(|&(a, b)| {
+            if a == *id {
+                Some(b)
+            } else if b == *id {
+                Some(a)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn contains_ignore_case(value: &str, query: &str) -> bool {
+    value.to_lowercase().contains(&query.to_lowercase())
+}
+
+#[async_trait::async_trait]
+impl UserRepository for UserRepositoryMock {
+    async fn find_by_id(&self, id: &Uuid) -> Result<Option<UserEntity>, error::SystemError> {
+        self.find_active(|user| user.id == *id)
+    }
+
+    async fn find_by_username(
+        &self,
+        username: &str,
+    ) -> Result<Option<UserEntity>, error::SystemError> {
+        self.find_active(|user| user.username.eq_ignore_ascii_case(username))
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<Option<UserEntity>, error::SystemError> {
+        self.find_active(|user| user.email.eq_ignore_ascii_case(email))
+    }
+
+    async fn create(&self, user: &InsertUser) -> Result<Uuid, error::SystemError> {
+        let taken = self.find_active(|existing| {
+            existing.username.eq_ignore_ascii_case(&user.username)
+                || existing.email.eq_ignore_ascii_case(&user.email)
+        })?;
+        if taken.is_some() {
+            return Err(error::SystemError::conflict("username or email"));
+        }
+
+        let mut entity = Self::entity(&user.username, &user.hash_password);
+        entity.email = user.email.clone();
+        entity.display_name = user.display_name.clone();
+        entity.email_verified = false;
+        Ok(self.insert(entity))
+    }
+
+    async fn update(&self, id: &Uuid, user: &UpdateUser) -> Result<UserEntity, error::SystemError> {
+        let mut state = self.state();
+        let entity = state
+            .users
+            .get_mut(id)
+            .filter(|entity| entity.deleted_at.is_none())
+            .ok_or_else(|| error::SystemError::not_found("User not found"))?;
+
+        let previous_username = entity.username.clone();
+        if let Some(username) = &user.username {
+            entity.username = username.clone();
+        }
+        if let Some(email) = &user.email {
+            entity.email = email.clone();
+        }
+        if let Some(display_name) = &user.display_name {
+            entity.display_name = display_name.clone();
+        }
+        if let Some(avatar_url) = &user.avatar_url {
+            entity.avatar_url = avatar_url.clone();
+        }
+        if let Some(bio) = &user.bio {
+            entity.bio = bio.clone();
+        }
+        if let Some(phone) = &user.phone {
+            entity.phone = phone.clone();
+        }
+        if let Some(discoverable) = user.discoverable {
+            entity.discoverable = discoverable;
+        }
+        entity.updated_at = chrono::Utc::now();
+
+        let updated = entity.clone();
+        if updated.username != previous_username {
+            state.username_history.push((*id, previous_username, updated.updated_at));
+        }
+        Ok(updated)
+    }
+
+    async fn update_avatar(
+        &self,
+        id: &Uuid,
+        avatar_url: &str,
+        avatar_id: &str,
+    ) -> Result<AvatarChange, error::SystemError> {
+        if !self.update_user(id, |user| user.avatar_url = Some(avatar_url.to_string()))? {
+            return Err(error::SystemError::not_found("User not found"));
+        }
+
+        let mut state = self.state();
+        let previous_avatar_id = state.avatar_ids.insert(*id, avatar_id.to_string());
+        let user = state.users[id].clone();
+        Ok(AvatarChange { user, previous_avatar_id })
+    }
+
+    async fn update_password(
+        &self,
+        id: &Uuid,
+        hash_password: &str,
+    ) -> Result<bool, error::SystemError> {
+        self.update_user(id, |user| user.hash_password = hash_password.to_string())
+    }
+
+    async fn mark_email_verified(&self, id: &Uuid) -> Result<bool, error::SystemError> {
+        self.update_user(id, |user| user.email_verified = true)
+    }
+
+    async fn delete(&self, id: &Uuid) -> Result<bool, error::SystemError> {
+        self.update_user(id, |user| user.deleted_at = Some(chrono::Utc::now()))
+    }
+
+    async fn search_users(
+        &self,
+        query: &str,
+        limit: i32,
+    ) -> Result<Vec<UserEntity>, error::SystemError> {
+        let mut users: Vec<UserEntity> = self
+            .state()
+            .users
+            .values()
+            .filter(|user| user.deleted_at.is_none() && user.deactivated_at.is_none())
+            .filter(|user| {
+                contains_ignore_case(&user.username, query)
+                    || contains_ignore_case(&user.display_name, query)
+            })
+            .cloned()
+            .collect();
+        users.sort_by(|a, b| a.display_name.cmp(&b.display_name));
+        users.truncate(limit.max(0) as usize);
+        Ok(users)
+    }
+
+    async fn autocomplete_users(
+        &self,
+        viewer_id: &Uuid,
+        query: &str,
+        limit: i32,
+    ) -> Result<Vec<UserEntity>, error::SystemError> {
+        let query = query.to_lowercase();
+        let state = self.state();
+        let mut users: Vec<(bool, bool, UserEntity)> = state
+            .users
+            .values()
+            .filter(|user| user.deleted_at.is_none() && user.deactivated_at.is_none())
+            .filter(|user| user.id != *viewer_id)
+            .filter(|user| {
+                user.username.to_lowercase().starts_with(&query)
+                    || contains_ignore_case(&user.display_name, &query)
+            })
+            .map(|user| {
+                let prefix = user.username.to_lowercase().starts_with(&query);
+                (are_friends(&state, viewer_id, &user.id), prefix, user.clone())
+            })
+            .collect();
+        // Mock không có conversations nên bỏ qua tiêu chí conversations chung
+        users.sort_by(|a, b| {
+            b.0.cmp(&a.0)
+                .then(b.1.cmp(&a.1))
+                .then(a.2.display_name.len().cmp(&b.2.display_name.len()))
+                .then(a.2.display_name.cmp(&b.2.display_name))
+        });
+        users.truncate(limit.max(0) as usize);
+        Ok(users.into_iter().map(|(_, _, user)| user).collect())
+    }
+
+    async fn find_presence_visibility(
+        &self,
+        id: &Uuid,
+    ) -> Result<Option<PresenceVisibility>, error::SystemError> {
+        Ok(self.find_by_id(id).await?.map(|user| user.presence_visibility))
+    }
+
+    async fn update_presence_visibility(
+        &self,
+        id: &Uuid,
+        visibility: PresenceVisibility,
+    ) -> Result<bool, error::SystemError> {
+        self.update_user(id, |user| user.presence_visibility = visibility)
+    }
+
+    async fn find_last_username_change(
+        &self,
+        id: &Uuid,
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>, error::SystemError> {
+        Ok(self
+            .state()
+            .username_history
+            .iter()
+            .filter(|(user_id, _, _)| user_id == id)
+            .map(|(_, _, changed_at)| *changed_at)
+            .max())
+    }
+
+    async fn is_username_available(
+        &self,
+        username: &str,
+        user_id: Option<&Uuid>,
+        reserved_since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<bool, error::SystemError> {
+        let state = self.state();
+        let is_other = |id: &Uuid| user_id != Some(id);
+
+        let taken = state.users.values().any(|user| {
+            user.deleted_at.is_none()
+                && user.username.eq_ignore_ascii_case(username)
+                && is_other(&user.id)
+        });
+        let reserved = state.username_history.iter().any(|(id, old, changed_at)| {
+            old.eq_ignore_ascii_case(username) && *changed_at >= reserved_since && is_other(id)
+        });
+
+        Ok(!taken && !reserved)
+    }
+
+    async fn find_settings(&self, id: &Uuid) -> Result<Option<UserSettings>, error::SystemError> {
+        Ok(self.state().settings.get(id).cloned())
+    }
+
+    async fn upsert_settings(
+        &self,
+        id: &Uuid,
+        settings: &UserSettings,
+    ) -> Result<UserSettings, error::SystemError> {
+        self.state().settings.insert(*id, settings.clone());
+        Ok(settings.clone())
+    }
+
+    async fn find_dnd(&self, id: &Uuid) -> Result<Option<DndSettings>, error::SystemError> {
+        Ok(self.state().dnd.get(id).map(|schedule| DndSettings {
+            schedule: schedule.clone(),
+            active: Self::dnd_active(schedule),
+        }))
+    }
+
+    async fn upsert_dnd(
+        &self,
+        id: &Uuid,
+        schedule: &DndSchedule,
+    ) -> Result<DndSettings, error::SystemError> {
+        self.state().dnd.insert(*id, schedule.clone());
+        Ok(DndSettings { schedule: schedule.clone(), active: Self::dnd_active(schedule) })
+    }
+
+    async fn delete_dnd(&self, id: &Uuid) -> Result<bool, error::SystemError> {
+        Ok(self.state().dnd.remove(id).is_some())
+    }
+
+    /// Chỉ nhận `UTC` và tên dạng `Area/City`
+    async fn is_valid_timezone(&self, timezone: &str) -> Result<bool, error::SystemError> {
+        Ok(timezone == "UTC" || timezone.contains('/'))
+    }
+
+    async fn find_dnd_badges(&self, user_ids: &[Uuid]) -> Result<Vec<Uuid>, error::SystemError> {
+        let state = self.state();
+        Ok(user_ids
+            .iter()
+            .filter(|id| {
+                state
+                    .dnd
+                    .get(id)
+                    .is_some_and(|schedule| schedule.show_badge && Self::dnd_active(schedule))
+            })
+            .copied()
+            .collect())
+    }
+
+    async fn find_profile_privacy(
+        &self,
+        viewer_id: &Uuid,
+        user_ids: &[Uuid],
+    ) -> Result<Vec<ProfilePrivacy>, error::SystemError> {
+        let state = self.state();
+        Ok(user_ids
+            .iter()
+            .filter_map(|id| state.users.get(id).filter(|user| user.deleted_at.is_none()))
+            .map(|user| {
+                let settings = state.settings.get(&user.id).cloned().unwrap_or_default();
+                ProfilePrivacy {
+                    user_id: user.id,
+                    email_visibility: settings.email_visibility,
+                    phone_visibility: settings.phone_visibility,
+                    bio_visibility: settings.bio_visibility,
+                    is_friend: are_friends(&state, viewer_id, &user.id),
+                    deactivated: user.deactivated_at.is_some(),
+                }
+            })
+            .collect())
+    }
+
+    /// Mock so khớp trực tiếp email / số điện thoại với `hashes` (không băm)
+    async fn find_by_contact_hashes(
+        &self,
+        hashes: &[String],
+        exclude_user_id: &Uuid,
+    ) -> Result<Vec<UserEntity>, error::SystemError> {
+        Ok(self
+            .state()
+            .users
+            .values()
+            .filter(|user| {
+                user.deleted_at.is_none()
+                    && user.banned_at.is_none()
+                    && user.deactivated_at.is_none()
+                    && user.discoverable
+                    && user.id != *exclude_user_id
+            })
+            .filter(|user| {
+                hashes.iter().any(|hash| {
+                    hash.eq_ignore_ascii_case(&user.email) || user.phone.as_ref() == Some(hash)
+                })
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Chỉ chuyển bạn bè và soft-delete tài khoản trùng, messages / conversations nằm ở
+    /// repositories khác
+    async fn merge_accounts(
+        &self,
+        primary_id: &Uuid,
+        duplicate_id: &Uuid,
+    ) -> Result<MergeSummary, error::SystemError> {
+        let mut state = self.state();
+        if !state.users.contains_key(primary_id) || !state.users.contains_key(duplicate_id) {
+            return Err(error::SystemError::not_found("User not found"));
+        }
+
+        let duplicate_friends = friends_of(&state, duplicate_id);
+        state.friendships.retain(|(a, b)| a != duplicate_id && b != duplicate_id);
+        for friend in &duplicate_friends {
+            if friend != primary_id {
+                state.friendships.insert((*friend.min(primary_id), *friend.max(primary_id)));
+            }
+        }
+        if let Some(user) = state.users.get_mut(duplicate_id) {
+            user.deleted_at = Some(chrono::Utc::now());
+        }
+
+        Ok(MergeSummary {
+            primary_id: *primary_id,
+            duplicate_id: *duplicate_id,
+            moved_messages: 0,
+            merged_conversations: 0,
+            affected_user_ids: duplicate_friends.into_iter().collect(),
+            membership_changes: Vec::new(),
+        })
+    }
+
+    async fn list_users(
+        &self,
+        query: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<UserEntity>, i64), error::SystemError> {
+        let mut users: Vec<UserEntity> = self
+            .state()
+            .users
+            .values()
+            .filter(|user| user.deleted_at.is_none())
+            .filter(|user| {
+                query.is_none_or(|q| {
+                    contains_ignore_case(&user.username, q)
+                        || contains_ignore_case(&user.display_name, q)
+                        || contains_ignore_case(&user.email, q)
+                })
+            })
+            .cloned()
+            .collect();
+        users.sort_by_key(|user| std::cmp::Reverse(user.created_at));
+
+        let total = users.len() as i64;
+        let page = users.into_iter().skip(offset.max(0) as usize).take(limit.max(0) as usize);
+        Ok((page.collect(), total))
+    }
+
+    async fn set_banned(
+        &self,
+        id: &Uuid,
+        banned: bool,
+        reason: Option<&str>,
+    ) -> Result<bool, error::SystemError> {
+        self.update_user(id, |user| {
+            user.banned_at = banned.then(chrono::Utc::now);
+            user.ban_reason = if banned { reason.map(str::to_string) } else { None };
+        })
+    }
+
+    async fn set_deactivated(
+        &self,
+        id: &Uuid,
+        deactivated: bool,
+    ) -> Result<bool, error::SystemError> {
+        self.update_user(id, |user| user.deactivated_at = deactivated.then(chrono::Utc::now))
+    }
+
+    async fn set_role(&self, id: &Uuid, role: &UserRole) -> Result<bool, error::SystemError> {
+        self.update_user(id, |user| user.role = role.clone())
+    }
+
+    async fn record_sign_in(
+        &self,
+        id: &Uuid,
+        ip_address: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<(), error::SystemError> {
+        self.state().sign_ins.push((
+            *id,
+            SignInEntity {
+                id: Uuid::now_v7(),
+                ip_address: ip_address.map(str::to_string),
+                user_agent: user_agent.map(str::to_string),
+                created_at: chrono::Utc::now(),
+            },
+        ));
+        Ok(())
+    }
+
+    async fn is_new_sign_in_ip(
+        &self,
+        id: &Uuid,
+        ip_address: &str,
+    ) -> Result<bool, error::SystemError> {
+        let state = self.state();
+        let mut successes = state.sign_ins.iter().filter(|(user_id, _)| user_id == id).peekable();
+
+        let has_history = successes.peek().is_some();
+        let known_ip =
+            successes.any(|(_, sign_in)| sign_in.ip_address.as_deref() == Some(ip_address));
+        Ok(has_history && !known_ip)
+    }
+
+    async fn find_sign_ins(
+        &self,
+        id: &Uuid,
+        limit: i64,
+    ) -> Result<Vec<SignInEntity>, error::SystemError> {
+        Ok(self
+            .state()
+            .sign_ins
+            .iter()
+            .rev()
+            .filter(|(user_id, _)| user_id == id)
+            .take(limit.max(0) as usize)
+            .map(|(_, sign_in)| sign_in.clone())
+            .collect())
+    }
+
+    async fn update_last_active(&self, id: &Uuid) -> Result<(), error::SystemError> {
+        self.update_user(id, |user| {
+            user.last_active_at = Some(chrono::Utc::now());
+            user.inactive_flagged_at = None;
+        })?;
+        Ok(())
+    }
+
+    async fn mark_inactive_accounts(
+        &self,
+        inactive_before: chrono::DateTime<chrono::Utc>,
+        deactivate: bool,
+        limit: i64,
+    ) -> Result<Vec<Uuid>, error::SystemError> {
+        let now = chrono::Utc::now();
+        let mut state = self.state();
+        let mut ids = Vec::new();
+
+        for user in state.users.values_mut() {
+            if ids.len() as i64 >= limit {
+                break;
+            }
+            let inactive = user.deleted_at.is_none()
+                && user.role == UserRole::User
+                && user.inactive_flagged_at.is_none()
+                && user.deactivated_at.is_none()
+                && user.last_active_at.unwrap_or(user.created_at) < inactive_before;
+            if inactive {
+                user.inactive_flagged_at = Some(now);
+                if deactivate {
+                    user.deactivated_at = Some(now);
+                }
+                ids.push(user.id);
+            }
+        }
+
+        Ok(ids)
+    }
+
+    /// Không có messages trong repository này, `total_messages` luôn là 0
+    async fn platform_stats(&self, _days: i32) -> Result<PlatformStats, error::SystemError> {
+        let state = self.state();
+        let today = chrono::Utc::now().date_naive();
+        let users = state.users.values().filter(|user| user.deleted_at.is_none());
+
+        let (mut total_users, mut banned_users, mut new_users_today) = (0, 0, 0);
+        for user in users {
+            total_users += 1;
+            banned_users += i64::from(user.banned_at.is_some());
+            new_users_today += i64::from(user.created_at.date_naive() == today);
+        }
+
+        Ok(PlatformStats {
+            total_users,
+            banned_users,
+            new_users_today,
+            total_messages: 0,
+            messages_per_day: Vec::new(),
+        })
+    }
+
+    async fn find_friends_of_friends(&self, id: &Uuid) -> Result<Vec<Uuid>, error::SystemError> {
+        let state = self.state();
+        let friends = friends_of(&state, id);
+        let candidates: HashSet<Uuid> =
+            friends.iter().flat_map(|friend| friends_of(&state, friend)).collect();
+
+        Ok(candidates
+            .into_iter()
+            .filter(|candidate| candidate != id && !friends.contains(candidate))
+            .collect())
+    }
+}
@@ -2,21 +2,32 @@ use uuid::Uuid;
 
 use crate::{
     api::error,
+    configs::read_replica::ReadPool,
     modules::user::{
-        model::{InsertUser, UpdateUser},
+        model::{
+            AvatarChange, DailyMessageCount, DndSchedule, DndSettings, InsertUser, MergeSummary,
+            PlatformStats, ProfilePrivacy, UpdateUser, UserSettings,
+        },
         repository::UserRepository,
-        schema::UserEntity,
+        schema::{PresenceVisibility, SignInEntity, UserEntity, UserRole},
     },
 };
 
 #[derive(Clone)]
 pub struct UserRepositoryPg {
     pool: sqlx::PgPool,
+    read_pool: ReadPool,
 }
 
 impl UserRepositoryPg {
     pub fn new(pool: sqlx::PgPool) -> Self {
-        Self { pool }
+        Self { read_pool: ReadPool::primary_only(pool.clone()), pool }
+    }
+
+    /// Tìm kiếm users trên `read_pool`
+    pub fn with_read_pool(mut self, read_pool: ReadPool) -> Self {
+        self.read_pool = read_pool;
+        self
     }
 }
 
@@ -45,6 +56,16 @@ impl UserRepository for UserRepositoryPg {
         Ok(user)
     }
 
+    async fn find_by_email(&self, email: &str) -> Result<Option<UserEntity>, error::SystemError> {
+        let user = sqlx::query_as::<_, UserEntity>(
+            "SELECT * FROM users WHERE lower(email) = lower($1) AND deleted_at IS NULL",
+        )
+        .bind(email)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(user)
+    }
+
     async fn create(&self, user: &InsertUser) -> Result<Uuid, error::SystemError> {
         let id = Uuid::new_v7(uuid::Timestamp::now(uuid::NoContext));
         sqlx::query(
@@ -61,8 +82,18 @@ impl UserRepository for UserRepositoryPg {
     }
 
     async fn update(&self, id: &Uuid, user: &UpdateUser) -> Result<UserEntity, error::SystemError> {
+        // Username cũ được ghi vào history trong cùng statement (chỉ khi thực sự đổi)
         let user = sqlx::query_as::<_, UserEntity>(
             r#"
+        WITH previous AS (
+            SELECT username FROM users WHERE id = $1 FOR UPDATE
+        ),
+        history AS (
+            INSERT INTO username_history (user_id, username)
+            SELECT $1, previous.username
+            FROM previous
+            WHERE $2::text IS NOT NULL AND lower(previous.username) <> lower($2)
+        )
         UPDATE users
         SET
             username     = COALESCE($2, username),
@@ -70,7 +101,8 @@ impl UserRepository for UserRepositoryPg {
             display_name = COALESCE($4, display_name),
             avatar_url   = CASE WHEN $5::boolean THEN $6 ELSE avatar_url END,
             bio          = CASE WHEN $7::boolean THEN $8 ELSE bio END,
-            phone        = CASE WHEN $9::boolean THEN $10 ELSE phone END
+            phone        = CASE WHEN $9::boolean THEN $10 ELSE phone END,
+            discoverable = COALESCE($11, discoverable)
         WHERE id = $1
         RETURNING *
         "#,
@@ -85,6 +117,7 @@ impl UserRepository for UserRepositoryPg {
         .bind(user.bio.as_ref().and_then(|v| v.as_ref())) // $8: Option<&String>
         .bind(user.phone.is_some()) // $9: bool - was phone provided?
         .bind(user.phone.as_ref().and_then(|v| v.as_ref())) // $10: Option<&String>
+        .bind(user.discoverable) // $11: Option<bool>
         .fetch_optional(&self.pool)
         .await?
         .ok_or_else(|| error::SystemError::not_found("User not found"))?;
@@ -92,6 +125,65 @@ impl UserRepository for UserRepositoryPg {
         Ok(user)
     }
 
+    async fn update_avatar(
+        &self,
+        id: &Uuid,
+        avatar_url: &str,
+        avatar_id: &str,
+    ) -> Result<AvatarChange, error::SystemError> {
+        // Khóa row để đọc avatar_id cũ và ghi giá trị mới trong cùng một statement
+        let change = sqlx::query_as::<_, AvatarChange>(
+            r#"
+        UPDATE users u
+        SET avatar_url = $2, avatar_id = $3, updated_at = NOW()
+        FROM (
+            SELECT id, avatar_id FROM users
+            WHERE id = $1 AND deleted_at IS NULL
+            FOR UPDATE
+        ) previous
+        WHERE u.id = previous.id
+        RETURNING u.*, previous.avatar_id AS previous_avatar_id
+        "#,
+        )
+        .bind(id)
+        .bind(avatar_url)
+        .bind(avatar_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| error::SystemError::not_found("User not found"))?;
+
+        Ok(change)
+    }
+
+    async fn update_password(
+        &self,
+        id: &Uuid,
+        hash_password: &str,
+    ) -> Result<bool, error::SystemError> {
+        let rows = sqlx::query(
+            "UPDATE users SET hash_password = $2, updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .bind(hash_password)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows > 0)
+    }
+
+    async fn mark_email_verified(&self, id: &Uuid) -> Result<bool, error::SystemError> {
+        let rows = sqlx::query(
+            "UPDATE users SET email_verified = true, updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows > 0)
+    }
+
     async fn delete(&self, id: &Uuid) -> Result<bool, error::SystemError> {
         let rows =
             sqlx::query("UPDATE users SET deleted_at = NOW() WHERE id = $1 AND deleted_at IS NULL")
@@ -113,6 +205,7 @@ impl UserRepository for UserRepositoryPg {
             r#"
             SELECT * FROM users
             WHERE deleted_at IS NULL
+            AND deactivated_at IS NULL
             AND (
                 lower(username) LIKE lower($1)
                 OR lower(display_name) LIKE lower($1)
@@ -123,8 +216,938 @@ impl UserRepository for UserRepositoryPg {
         )
         .bind(&search_pattern)
         .bind(limit)
+        .fetch_all(&mut *self.read_pool.acquire().await?)
+        .await?;
+        Ok(users)
+    }
+
+    async fn autocomplete_users(
+        &self,
+        viewer_id: &Uuid,
+        query: &str,
+        limit: i32,
+    ) -> Result<Vec<UserEntity>, error::SystemError> {
+        // Biểu thức lower(...) và điều kiện WHERE phải khớp với partial indexes trong
+        // migration 0046 (btree prefix cho username, pg_trgm cho display name)
+        let escaped =
+            query.to_lowercase().replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+        let users = sqlx::query_as::<_, UserEntity>(
+            r#"
+            SELECT u.*
+            FROM users u
+            WHERE u.deleted_at IS NULL
+            AND u.deactivated_at IS NULL
+            AND u.id != $1
+            AND (
+                lower(u.username) LIKE $2 || '%'
+                OR lower(u.display_name) LIKE '%' || $2 || '%'
+            )
+            AND NOT EXISTS (
+                SELECT 1 FROM user_blocks b
+                WHERE (b.blocker_id = $1 AND b.blocked_id = u.id)
+                OR (b.blocker_id = u.id AND b.blocked_id = $1)
+            )
+            ORDER BY
+                EXISTS (
+                    SELECT 1 FROM friends f
+                    WHERE f.user_a = LEAST(u.id, $1) AND f.user_b = GREATEST(u.id, $1)
+                ) DESC,
+                (
+                    SELECT COUNT(*)
+                    FROM participants mine
+                    JOIN participants theirs
+                        ON theirs.conversation_id = mine.conversation_id
+                    WHERE mine.user_id = $1
+                    AND theirs.user_id = u.id
+                    AND mine.status = 'active'
+                    AND theirs.status = 'active'
+                ) DESC,
+                lower(u.username) LIKE $2 || '%' DESC,
+                length(u.display_name),
+                u.display_name
+            LIMIT $3
+            "#,
+        )
+        .bind(viewer_id)
+        .bind(&escaped)
+        .bind(limit)
+        .fetch_all(&mut *self.read_pool.acquire().await?)
+        .await?;
+        Ok(users)
+    }
+
+    async fn find_presence_visibility(
+        &self,
+        id: &Uuid,
+    ) -> Result<Option<PresenceVisibility>, error::SystemError> {
+        let visibility = sqlx::query_scalar::<_, PresenceVisibility>(
+            "SELECT presence_visibility FROM users WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(visibility)
+    }
+
+    async fn update_presence_visibility(
+        &self,
+        id: &Uuid,
+        visibility: PresenceVisibility,
+    ) -> Result<bool, error::SystemError> {
+        let rows = sqlx::query(
+            "UPDATE users SET presence_visibility = $2, updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .bind(visibility)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        Ok(rows > 0)
+    }
+
+    async fn find_last_username_change(
+        &self,
+        id: &Uuid,
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>, error::SystemError> {
+        let changed_at = sqlx::query_scalar::<_, Option<chrono::DateTime<chrono::Utc>>>(
+            "SELECT MAX(changed_at) FROM username_history WHERE user_id = $1",
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(changed_at)
+    }
+
+    async fn is_username_available(
+        &self,
+        username: &str,
+        user_id: Option<&Uuid>,
+        reserved_since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<bool, error::SystemError> {
+        let available = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT NOT EXISTS (
+                SELECT 1 FROM users
+                WHERE lower(username) = lower($1)
+                  AND deleted_at IS NULL
+                  AND id IS DISTINCT FROM $2
+            )
+            AND NOT EXISTS (
+                SELECT 1 FROM username_history
+                WHERE lower(username) = lower($1)
+                  AND changed_at >= $3
+                  AND user_id IS DISTINCT FROM $2
+            )
+            "#,
+        )
+        .bind(username)
+        .bind(user_id)
+        .bind(reserved_since)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(available)
+    }
+
+    async fn find_settings(&self, id: &Uuid) -> Result<Option<UserSettings>, error::SystemError> {
+        let settings = sqlx::query_as::<_, UserSettings>(
+            r#"
+            SELECT email_visibility, phone_visibility, bio_visibility, friend_request_policy
+            FROM user_settings
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(settings)
+    }
+
+    async fn upsert_settings(
+        &self,
+        id: &Uuid,
+        settings: &UserSettings,
+    ) -> Result<UserSettings, error::SystemError> {
+        let settings = sqlx::query_as::<_, UserSettings>(
+            r#"
+            INSERT INTO user_settings
+                (user_id, email_visibility, phone_visibility, bio_visibility, friend_request_policy)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (user_id) DO UPDATE SET
+                email_visibility = EXCLUDED.email_visibility,
+                phone_visibility = EXCLUDED.phone_visibility,
+                bio_visibility = EXCLUDED.bio_visibility,
+                friend_request_policy = EXCLUDED.friend_request_policy,
+                updated_at = NOW()
+            RETURNING email_visibility, phone_visibility, bio_visibility, friend_request_policy
+            "#,
+        )
+        .bind(id)
+        .bind(settings.email_visibility)
+        .bind(settings.phone_visibility)
+        .bind(settings.bio_visibility)
+        .bind(settings.friend_request_policy)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(settings)
+    }
+
+    async fn find_dnd(&self, id: &Uuid) -> Result<Option<DndSettings>, error::SystemError> {
+        let dnd = sqlx::query_as::<_, DndSettings>(
+            r#"
+            SELECT
+                dnd_start AS start,
+                dnd_end AS "end",
+                dnd_timezone AS timezone,
+                dnd_show_badge AS show_badge,
+                dnd_active(dnd_start, dnd_end, dnd_timezone) AS active
+            FROM user_settings
+            WHERE user_id = $1
+            AND dnd_start IS NOT NULL
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(dnd)
+    }
+
+    async fn upsert_dnd(
+        &self,
+        id: &Uuid,
+        schedule: &DndSchedule,
+    ) -> Result<DndSettings, error::SystemError> {
+        let dnd = sqlx::query_as::<_, DndSettings>(
+            r#"
+            INSERT INTO user_settings (user_id, dnd_start, dnd_end, dnd_timezone, dnd_show_badge)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (user_id) DO UPDATE SET
+                dnd_start = EXCLUDED.dnd_start,
+                dnd_end = EXCLUDED.dnd_end,
+                dnd_timezone = EXCLUDED.dnd_timezone,
+                dnd_show_badge = EXCLUDED.dnd_show_badge,
+                updated_at = NOW()
+            RETURNING
+                dnd_start AS start,
+                dnd_end AS "end",
+                dnd_timezone AS timezone,
+                dnd_show_badge AS show_badge,
+                dnd_active(dnd_start, dnd_end, dnd_timezone) AS active
+            "#,
+        )
+        .bind(id)
+        .bind(schedule.start)
+        .bind(schedule.end)
+        .bind(&schedule.timezone)
+        .bind(schedule.show_badge)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(dnd)
+    }
+
+    async fn delete_dnd(&self, id: &Uuid) -> Result<bool, error::SystemError> {
+        let rows = sqlx::query(
+            r#"
+            UPDATE user_settings
+            SET dnd_start = NULL,
+                dnd_end = NULL,
+                dnd_timezone = NULL,
+                dnd_show_badge = false,
+                updated_at = NOW()
+            WHERE user_id = $1
+            AND dnd_start IS NOT NULL
+            "#,
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        Ok(rows > 0)
+    }
+
+    async fn is_valid_timezone(&self, timezone: &str) -> Result<bool, error::SystemError> {
+        let valid = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1)",
+        )
+        .bind(timezone)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(valid)
+    }
+
+    async fn find_dnd_badges(&self, user_ids: &[Uuid]) -> Result<Vec<Uuid>, error::SystemError> {
+        let user_ids = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT user_id
+            FROM user_settings
+            WHERE user_id = ANY($1)
+            AND dnd_show_badge
+            AND dnd_active(dnd_start, dnd_end, dnd_timezone)
+            "#,
+        )
+        .bind(user_ids)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(user_ids)
+    }
+
+    async fn find_profile_privacy(
+        &self,
+        viewer_id: &Uuid,
+        user_ids: &[Uuid],
+    ) -> Result<Vec<ProfilePrivacy>, error::SystemError> {
+        let privacy = sqlx::query_as::<_, ProfilePrivacy>(
+            r#"
+            SELECT
+                ids.id AS user_id,
+                COALESCE(s.email_visibility, 'everyone') AS email_visibility,
+                COALESCE(s.phone_visibility, 'everyone') AS phone_visibility,
+                COALESCE(s.bio_visibility, 'everyone') AS bio_visibility,
+                EXISTS (
+                    SELECT 1 FROM users u WHERE u.id = ids.id AND u.deactivated_at IS NOT NULL
+                ) AS deactivated,
+                EXISTS (
+                    SELECT 1 FROM friends f
+                    WHERE f.user_a = LEAST(ids.id, $1) AND f.user_b = GREATEST(ids.id, $1)
+                ) AS is_friend
+            FROM UNNEST($2::uuid[]) AS ids(id)
+            LEFT JOIN user_settings s ON s.user_id = ids.id
+            "#,
+        )
+        .bind(viewer_id)
+        .bind(user_ids)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(privacy)
+    }
+
+    async fn find_by_contact_hashes(
+        &self,
+        hashes: &[String],
+        exclude_user_id: &Uuid,
+    ) -> Result<Vec<UserEntity>, error::SystemError> {
+        // Biểu thức hash phải khớp với index trong migration 0022
+        let users = sqlx::query_as::<_, UserEntity>(
+            r#"
+            SELECT * FROM users
+            WHERE deleted_at IS NULL
+            AND banned_at IS NULL
+            AND deactivated_at IS NULL
+            AND discoverable = true
+            AND id <> $2
+            AND (
+                encode(sha256(lower(email)::bytea), 'hex') = ANY($1)
+                OR (
+                    phone IS NOT NULL
+                    AND encode(sha256(regexp_replace(phone, '[^0-9+]', '', 'g')::bytea), 'hex')
+                        = ANY($1)
+                )
+            )
+            ORDER BY display_name
+            "#,
+        )
+        .bind(hashes)
+        .bind(exclude_user_id)
         .fetch_all(&self.pool)
         .await?;
         Ok(users)
     }
+
+    async fn merge_accounts(
+        &self,
+        primary_id: &Uuid,
+        duplicate_id: &Uuid,
+    ) -> Result<MergeSummary, error::SystemError> {
+        let mut tx = self.pool.begin().await?;
+
+        // Khóa cả 2 user để tránh thay đổi đồng thời trong lúc merge
+        let locked: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT id FROM users WHERE id = ANY($1) AND deleted_at IS NULL FOR UPDATE",
+        )
+        .bind(vec![*primary_id, *duplicate_id])
+        .fetch_all(tx.as_mut())
+        .await?;
+
+        if locked.len() != 2 {
+            return Err(error::SystemError::not_found("User not found"));
+        }
+
+        // Những user có liên quan (friends, thành viên chung conversation) để notify sau merge
+        let mut affected_user_ids: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+            SELECT CASE WHEN user_a = $1 THEN user_b ELSE user_a END
+            FROM friends
+            WHERE (user_a = $1 OR user_b = $1) AND deleted_at IS NULL
+            UNION
+            SELECT o.user_id
+            FROM participants p
+            JOIN participants o ON o.conversation_id = p.conversation_id AND o.user_id <> p.user_id
+            WHERE p.user_id = $1
+            "#,
+        )
+        .bind(duplicate_id)
+        .fetch_all(tx.as_mut())
+        .await?;
+        affected_user_ids.retain(|id| id != primary_id);
+
+        // Conversations của tài khoản trùng: membership của cả 2 tài khoản đều thay đổi
+        let duplicate_conversations: Vec<Uuid> =
+            sqlx::query_scalar("SELECT conversation_id FROM participants WHERE user_id = $1")
+                .bind(duplicate_id)
+                .fetch_all(tx.as_mut())
+                .await?;
+        let mut membership_changes: Vec<(Uuid, Uuid)> = duplicate_conversations
+            .into_iter()
+            .flat_map(|id| [(id, *duplicate_id), (id, *primary_id)])
+            .collect();
+
+        // Direct conversations của tài khoản trùng
+        let direct_conversations: Vec<(Uuid, Uuid)> = sqlx::query_as(
+            r#"
+            SELECT p.conversation_id, o.user_id
+            FROM participants p
+            JOIN conversations c ON c.id = p.conversation_id AND c.type = 'direct'
+            JOIN participants o ON o.conversation_id = p.conversation_id AND o.user_id <> p.user_id
+            WHERE p.user_id = $1
+            "#,
+        )
+        .bind(duplicate_id)
+        .fetch_all(tx.as_mut())
+        .await?;
+
+        let mut merged_conversations = 0;
+        for (conversation_id, other_id) in direct_conversations {
+            // Conversation giữa 2 tài khoản sẽ thành conversation với chính mình -> bỏ
+            if other_id == *primary_id {
+                sqlx::query("DELETE FROM conversations WHERE id = $1")
+                    .bind(conversation_id)
+                    .execute(tx.as_mut())
+                    .await?;
+                continue;
+            }
+
+            let target: Option<Uuid> = sqlx::query_scalar(
+                r#"
+                SELECT c.id
+                FROM conversations c
+                JOIN participants p1 ON p1.conversation_id = c.id AND p1.user_id = $1
+                JOIN participants p2 ON p2.conversation_id = c.id AND p2.user_id = $2
+                WHERE c.type = 'direct'
+                LIMIT 1
+                "#,
+            )
+            .bind(primary_id)
+            .bind(other_id)
+            .fetch_optional(tx.as_mut())
+            .await?;
+
+            // Chưa có direct conversation với user này -> chỉ cần chuyển participant
+            let Some(target) = target else {
+                continue;
+            };
+
+            sqlx::query("UPDATE messages SET conversation_id = $1 WHERE conversation_id = $2")
+                .bind(target)
+                .bind(conversation_id)
+                .execute(tx.as_mut())
+                .await?;
+
+            sqlx::query(
+                r#"
+                UPDATE participants SET unread_count = unread_count + COALESCE((
+                    SELECT unread_count FROM participants WHERE conversation_id = $2 AND user_id = $3
+                ), 0)
+                WHERE conversation_id = $1 AND user_id = $3
+                "#,
+            )
+            .bind(target)
+            .bind(conversation_id)
+            .bind(other_id)
+            .execute(tx.as_mut())
+            .await?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO last_messages (conversation_id, sender_id, content, created_at)
+                SELECT conversation_id, sender_id, content, created_at
+                FROM messages
+                WHERE conversation_id = $1 AND deleted_at IS NULL
+                ORDER BY created_at DESC
+                LIMIT 1
+                ON CONFLICT (conversation_id) DO UPDATE SET
+                    sender_id = EXCLUDED.sender_id,
+                    content = EXCLUDED.content,
+                    created_at = EXCLUDED.created_at
+                "#,
+            )
+            .bind(target)
+            .execute(tx.as_mut())
+            .await?;
+
+            sqlx::query("DELETE FROM conversations WHERE id = $1")
+                .bind(conversation_id)
+                .execute(tx.as_mut())
+                .await?;
+
+            membership_changes.push((conversation_id, other_id));
+            merged_conversations += 1;
+        }
+
+        // Group conversations: bỏ participant trùng nếu tài khoản chính đã là thành viên
+        sqlx::query(
+            r#"
+            DELETE FROM participants
+            WHERE user_id = $2
+            AND conversation_id IN (SELECT conversation_id FROM participants WHERE user_id = $1)
+            "#,
+        )
+        .bind(primary_id)
+        .bind(duplicate_id)
+        .execute(tx.as_mut())
+        .await?;
+
+        sqlx::query("UPDATE participants SET user_id = $1 WHERE user_id = $2")
+            .bind(primary_id)
+            .bind(duplicate_id)
+            .execute(tx.as_mut())
+            .await?;
+
+        let moved_messages = sqlx::query("UPDATE messages SET sender_id = $1 WHERE sender_id = $2")
+            .bind(primary_id)
+            .bind(duplicate_id)
+            .execute(tx.as_mut())
+            .await?
+            .rows_affected();
+
+        for statement in [
+            "UPDATE last_messages SET sender_id = $1 WHERE sender_id = $2",
+            "UPDATE group_conversations SET created_by = $1 WHERE created_by = $2",
+            "UPDATE files SET uploaded_by = $1 WHERE uploaded_by = $2",
+            "UPDATE client_metadata SET user_id = $1 WHERE user_id = $2",
+            "UPDATE oauth_accounts SET user_id = $1 WHERE user_id = $2",
+        ] {
+            sqlx::query(statement).bind(primary_id).bind(duplicate_id).execute(tx.as_mut()).await?;
+        }
+
+        // Friendships: chuyển sang tài khoản chính, bỏ qua các quan hệ đã tồn tại
+        sqlx::query(
+            r#"
+            INSERT INTO friends (user_a, user_b)
+            SELECT LEAST($1, other_id), GREATEST($1, other_id)
+            FROM (
+                SELECT CASE WHEN user_a = $2 THEN user_b ELSE user_a END AS other_id
+                FROM friends
+                WHERE (user_a = $2 OR user_b = $2) AND deleted_at IS NULL
+            ) f
+            WHERE other_id <> $1
+            ON CONFLICT (user_a, user_b) DO UPDATE SET deleted_at = NULL
+            "#,
+        )
+        .bind(primary_id)
+        .bind(duplicate_id)
+        .execute(tx.as_mut())
+        .await?;
+
+        sqlx::query("DELETE FROM friends WHERE user_a = $1 OR user_b = $1")
+            .bind(duplicate_id)
+            .execute(tx.as_mut())
+            .await?;
+
+        // Friend requests: chuyển những request không bị trùng, xóa phần còn lại
+        sqlx::query(
+            r#"
+            UPDATE friend_requests fr SET from_user_id = $1
+            WHERE fr.from_user_id = $2
+            AND fr.to_user_id <> $1
+            AND NOT EXISTS (
+                SELECT 1 FROM friend_requests x WHERE x.from_user_id = $1 AND x.to_user_id = fr.to_user_id
+            )
+            "#,
+        )
+        .bind(primary_id)
+        .bind(duplicate_id)
+        .execute(tx.as_mut())
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE friend_requests fr SET to_user_id = $1
+            WHERE fr.to_user_id = $2
+            AND fr.from_user_id <> $1
+            AND NOT EXISTS (
+                SELECT 1 FROM friend_requests x WHERE x.to_user_id = $1 AND x.from_user_id = fr.from_user_id
+            )
+            "#,
+        )
+        .bind(primary_id)
+        .bind(duplicate_id)
+        .execute(tx.as_mut())
+        .await?;
+
+        sqlx::query("DELETE FROM friend_requests WHERE from_user_id = $1 OR to_user_id = $1")
+            .bind(duplicate_id)
+            .execute(tx.as_mut())
+            .await?;
+
+        // Blocks: chuyển sang tài khoản chính, bỏ block giữa 2 tài khoản và các block đã tồn tại
+        for statement in [
+            r#"
+            INSERT INTO user_blocks (blocker_id, blocked_id, created_at)
+            SELECT $1, blocked_id, created_at FROM user_blocks
+            WHERE blocker_id = $2 AND blocked_id <> $1
+            ON CONFLICT DO NOTHING
+            "#,
+            r#"
+            INSERT INTO user_blocks (blocker_id, blocked_id, created_at)
+            SELECT blocker_id, $1, created_at FROM user_blocks
+            WHERE blocked_id = $2 AND blocker_id <> $1
+            ON CONFLICT DO NOTHING
+            "#,
+            "DELETE FROM user_blocks WHERE blocker_id = $2 OR blocked_id = $2",
+        ] {
+            sqlx::query(statement).bind(primary_id).bind(duplicate_id).execute(tx.as_mut()).await?;
+        }
+
+        // Mentions / tin nhắn đã ẩn: gộp vào tài khoản chính, bỏ các dòng trùng
+        for statement in [
+            r#"
+            INSERT INTO message_mentions (message_id, user_id, created_at)
+            SELECT message_id, $1, created_at FROM message_mentions WHERE user_id = $2
+            ON CONFLICT DO NOTHING
+            "#,
+            "DELETE FROM message_mentions WHERE user_id = $2",
+            r#"
+            INSERT INTO hidden_messages (user_id, message_id, created_at)
+            SELECT $1, message_id, created_at FROM hidden_messages WHERE user_id = $2
+            ON CONFLICT DO NOTHING
+            "#,
+            "DELETE FROM hidden_messages WHERE user_id = $2",
+        ] {
+            sqlx::query(statement).bind(primary_id).bind(duplicate_id).execute(tx.as_mut()).await?;
+        }
+
+        // Devices và bots thuộc về cùng một người -> chuyển sang tài khoản chính. Settings
+        // của tài khoản chính được giữ, chỉ lấy settings của tài khoản trùng khi chưa có
+        for statement in [
+            "UPDATE devices SET user_id = $1 WHERE user_id = $2",
+            "UPDATE bots SET owner_id = $1 WHERE owner_id = $2",
+            r#"
+            UPDATE user_settings SET user_id = $1
+            WHERE user_id = $2
+            AND NOT EXISTS (SELECT 1 FROM user_settings WHERE user_id = $1)
+            "#,
+            "DELETE FROM user_settings WHERE user_id = $2",
+        ] {
+            sqlx::query(statement).bind(primary_id).bind(duplicate_id).execute(tx.as_mut()).await?;
+        }
+
+        // Key bundle (E2EE) gắn với thiết bị của tài khoản trùng, không thể gộp -> xóa để
+        // không ai mã hóa tin nhắn tới tài khoản đã bị merge
+        for statement in [
+            "DELETE FROM identity_keys WHERE user_id = $1",
+            "DELETE FROM one_time_prekeys WHERE user_id = $1",
+        ] {
+            sqlx::query(statement).bind(duplicate_id).execute(tx.as_mut()).await?;
+        }
+
+        sqlx::query("UPDATE users SET deleted_at = NOW() WHERE id = $1")
+            .bind(duplicate_id)
+            .execute(tx.as_mut())
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(MergeSummary {
+            primary_id: *primary_id,
+            duplicate_id: *duplicate_id,
+            moved_messages,
+            merged_conversations,
+            affected_user_ids,
+            membership_changes,
+        })
+    }
+
+    async fn list_users(
+        &self,
+        query: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<UserEntity>, i64), error::SystemError> {
+        let search_pattern =
+            query.map(|q| format!("%{}%", q.replace('%', "\\%").replace('_', "\\_")));
+
+        let total: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM users
+            WHERE deleted_at IS NULL
+            AND ($1::text IS NULL OR username ILIKE $1 OR display_name ILIKE $1 OR email ILIKE $1)
+            "#,
+        )
+        .bind(&search_pattern)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let users = sqlx::query_as::<_, UserEntity>(
+            r#"
+            SELECT * FROM users
+            WHERE deleted_at IS NULL
+            AND ($1::text IS NULL OR username ILIKE $1 OR display_name ILIKE $1 OR email ILIKE $1)
+            ORDER BY created_at DESC
+            LIMIT $2 OFFSET $3
+            "#,
+        )
+        .bind(&search_pattern)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok((users, total))
+    }
+
+    async fn set_deactivated(
+        &self,
+        id: &Uuid,
+        deactivated: bool,
+    ) -> Result<bool, error::SystemError> {
+        let rows = sqlx::query(
+            r#"
+            UPDATE users
+            SET
+                deactivated_at = CASE WHEN $2 THEN NOW() ELSE NULL END,
+                updated_at = NOW()
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(deactivated)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows > 0)
+    }
+
+    async fn set_role(&self, id: &Uuid, role: &UserRole) -> Result<bool, error::SystemError> {
+        let rows = sqlx::query(
+            r#"
+            UPDATE users
+            SET role = $2, updated_at = NOW()
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(role)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows > 0)
+    }
+
+    async fn record_sign_in(
+        &self,
+        id: &Uuid,
+        ip_address: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<(), error::SystemError> {
+        sqlx::query(
+            r#"
+            INSERT INTO sign_in_history (user_id, ip_address, user_agent)
+            VALUES ($1, $2, $3)
+            "#,
+        )
+        .bind(id)
+        .bind(ip_address)
+        .bind(user_agent)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn is_new_sign_in_ip(
+        &self,
+        id: &Uuid,
+        ip_address: &str,
+    ) -> Result<bool, error::SystemError> {
+        let is_new = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT
+                EXISTS (SELECT 1 FROM sign_in_history WHERE user_id = $1)
+                AND NOT EXISTS (
+                    SELECT 1 FROM sign_in_history WHERE user_id = $1 AND ip_address = $2
+                )
+            "#,
+        )
+        .bind(id)
+        .bind(ip_address)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(is_new)
+    }
+
+    async fn find_sign_ins(
+        &self,
+        id: &Uuid,
+        limit: i64,
+    ) -> Result<Vec<SignInEntity>, error::SystemError> {
+        let sign_ins = sqlx::query_as::<_, SignInEntity>(
+            r#"
+            SELECT * FROM sign_in_history
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(sign_ins)
+    }
+
+    async fn update_last_active(&self, id: &Uuid) -> Result<(), error::SystemError> {
+        sqlx::query(
+            "UPDATE users SET last_active_at = NOW(), inactive_flagged_at = NULL WHERE id = $1",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn mark_inactive_accounts(
+        &self,
+        inactive_before: chrono::DateTime<chrono::Utc>,
+        deactivate: bool,
+        limit: i64,
+    ) -> Result<Vec<Uuid>, error::SystemError> {
+        // Biểu thức COALESCE phải khớp với index trong migration 0042
+        let ids = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            UPDATE users
+            SET
+                inactive_flagged_at = NOW(),
+                deactivated_at = CASE WHEN $2 THEN NOW() ELSE deactivated_at END
+            WHERE id IN (
+                SELECT id FROM users
+                WHERE deleted_at IS NULL
+                  AND role = 'USER'
+                  AND inactive_flagged_at IS NULL
+                  AND deactivated_at IS NULL
+                  AND COALESCE(last_active_at, created_at) < $1
+                LIMIT $3
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id
+            "#,
+        )
+        .bind(inactive_before)
+        .bind(deactivate)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
+
+    async fn set_banned(
+        &self,
+        id: &Uuid,
+        banned: bool,
+        reason: Option<&str>,
+    ) -> Result<bool, error::SystemError> {
+        let rows = sqlx::query(
+            r#"
+            UPDATE users
+            SET
+                banned_at  = CASE WHEN $2 THEN NOW() ELSE NULL END,
+                ban_reason = CASE WHEN $2 THEN $3 ELSE NULL END,
+                updated_at = NOW()
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(banned)
+        .bind(reason)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows > 0)
+    }
+
+    async fn platform_stats(&self, days: i32) -> Result<PlatformStats, error::SystemError> {
+        let (total_users, banned_users, new_users_today): (i64, i64, i64) = sqlx::query_as(
+            r#"
+            SELECT
+                COUNT(*),
+                COUNT(*) FILTER (WHERE banned_at IS NOT NULL),
+                COUNT(*) FILTER (WHERE created_at >= date_trunc('day', NOW()))
+            FROM users
+            WHERE deleted_at IS NULL
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let total_messages: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM messages WHERE deleted_at IS NULL")
+                .fetch_one(&self.pool)
+                .await?;
+
+        let messages_per_day = sqlx::query_as::<_, DailyMessageCount>(
+            r#"
+            SELECT d.day::date AS day, COUNT(m.id) AS count
+            FROM generate_series(
+                date_trunc('day', NOW()) - make_interval(days => $1 - 1),
+                date_trunc('day', NOW()),
+                interval '1 day'
+            ) AS d(day)
+            LEFT JOIN messages m
+                ON m.created_at >= d.day
+                AND m.created_at < d.day + interval '1 day'
+                AND m.deleted_at IS NULL
+            GROUP BY d.day
+            ORDER BY d.day
+            "#,
+        )
+        .bind(days)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(PlatformStats {
+            total_users,
+            banned_users,
+            new_users_today,
+            total_messages,
+            messages_per_day,
+        })
+    }
+
+    async fn find_friends_of_friends(&self, id: &Uuid) -> Result<Vec<Uuid>, error::SystemError> {
+        let ids = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            WITH my_friends AS (
+                SELECT CASE WHEN f.user_a = $1 THEN f.user_b ELSE f.user_a END AS id
+                FROM friends f
+                WHERE f.user_a = $1 OR f.user_b = $1
+            )
+            SELECT DISTINCT CASE WHEN f.user_a = mf.id THEN f.user_b ELSE f.user_a END AS id
+            FROM my_friends mf
+            JOIN friends f ON f.user_a = mf.id OR f.user_b = mf.id
+            WHERE f.user_a <> $1 AND f.user_b <> $1
+              AND (CASE WHEN f.user_a = mf.id THEN f.user_b ELSE f.user_a END)
+                  NOT IN (SELECT id FROM my_friends)
+            "#,
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
 }
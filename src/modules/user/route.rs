@@ -1,9 +1,31 @@
-use crate::modules::user::handle::*;
-use actix_web::web::{scope, ServiceConfig};
+use crate::{
+    middlewares::{authentication, authorization},
+    modules::user::{handle::*, schema::UserRole},
+};
+use actix_web::{
+    middleware::from_fn,
+    web::{scope, ServiceConfig},
+};
+use utoipa::OpenApi;
 
 pub fn public_api_configure(cfg: &mut ServiceConfig) {
     cfg.service(
-        scope("/auth").service(sign_up).service(sign_in).service(sign_out).service(refresh),
+        scope("/auth")
+            .service(sign_up)
+            .service(sign_in)
+            .service(sign_out)
+            .service(refresh)
+            .service(forgot_password)
+            .service(reset_password)
+            .service(verify_email)
+            .service(resend_verification)
+            .service(confirm_sign_in)
+            .service(
+                scope("")
+                    .wrap(from_fn(authorization(vec![UserRole::User, UserRole::Admin])))
+                    .wrap(from_fn(authentication))
+                    .service(change_password),
+            ),
     );
 }
 
@@ -11,10 +33,85 @@ pub fn configure(cfg: &mut ServiceConfig) {
     cfg.service(
         scope("/users")
             .service(update_user)
+            .service(upload_avatar)
             .service(get_profile)
             .service(get_user)
             .service(delete_user)
+            .service(deactivate_user)
             .service(search_users)
-            .service(get_presence),
+            .service(autocomplete_users)
+            .service(check_username)
+            .service(lookup_contacts)
+            .service(get_presence)
+            .service(get_presence_settings)
+            .service(update_presence_settings)
+            .service(get_settings)
+            .service(update_settings)
+            .service(get_dnd)
+            .service(set_dnd)
+            .service(clear_dnd)
+            .service(list_sign_ins),
     );
 }
+
+pub fn admin_configure(cfg: &mut ServiceConfig) {
+    cfg.service(platform_stats).service(
+        scope("/users")
+            .service(list_users)
+            .service(merge_accounts)
+            .service(ban_user)
+            .service(unban_user)
+            .service(force_sign_out),
+    );
+}
+
+/// OpenAPI paths của `public_api_configure` (scope `/auth`)
+#[derive(OpenApi)]
+#[openapi(paths(
+    sign_up,
+    sign_in,
+    sign_out,
+    refresh,
+    forgot_password,
+    reset_password,
+    verify_email,
+    resend_verification,
+    confirm_sign_in,
+    change_password
+))]
+pub struct AuthApiDoc;
+
+/// OpenAPI paths của `configure` (scope `/users`)
+#[derive(OpenApi)]
+#[openapi(paths(
+    update_user,
+    upload_avatar,
+    get_profile,
+    get_user,
+    delete_user,
+    deactivate_user,
+    search_users,
+    autocomplete_users,
+    check_username,
+    lookup_contacts,
+    get_presence,
+    get_presence_settings,
+    update_presence_settings,
+    get_settings,
+    update_settings,
+    get_dnd,
+    set_dnd,
+    clear_dnd,
+    list_sign_ins
+))]
+pub struct UserApiDoc;
+
+/// OpenAPI paths của `admin_configure` ngoài scope `/users`
+#[derive(OpenApi)]
+#[openapi(paths(platform_stats))]
+pub struct AdminApiDoc;
+
+/// OpenAPI paths của `admin_configure` (scope `/users`)
+#[derive(OpenApi)]
+#[openapi(paths(list_users, merge_accounts, ban_user, unban_user, force_sign_out))]
+pub struct AdminUserApiDoc;
@@ -1,14 +1,66 @@
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::{FromRow, Type};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, PartialEq, Clone, Type, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Clone, Type, Serialize, Deserialize, ToSchema)]
 #[sqlx(type_name = "user_role", rename_all = "UPPERCASE")]
 pub enum UserRole {
     #[sqlx(rename = "ADMIN")]
     Admin,
     #[sqlx(rename = "USER")]
     User,
+    /// Tài khoản bot, chỉ xác thực bằng API key (`X-Api-Key`)
+    #[sqlx(rename = "BOT")]
+    Bot,
+    /// Khách được mời vào một conversation duy nhất, chỉ có access token ngắn hạn
+    #[sqlx(rename = "GUEST")]
+    Guest,
+}
+
+/// Ai được xem trạng thái online / last_seen của user
+#[derive(Debug, Default, PartialEq, Clone, Copy, Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "presence_visibility", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum PresenceVisibility {
+    #[default]
+    Everyone,
+    Friends,
+    Nobody,
+}
+
+/// Ai được xem một trường profile (email / số điện thoại / bio)
+#[derive(Debug, Default, PartialEq, Clone, Copy, Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "field_visibility", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum FieldVisibility {
+    #[default]
+    Everyone,
+    Friends,
+    Nobody,
+}
+
+impl FieldVisibility {
+    /// Người xem (không phải chủ profile) có được thấy trường này không
+    pub fn allows(self, is_friend: bool) -> bool {
+        match self {
+            FieldVisibility::Everyone => true,
+            FieldVisibility::Friends => is_friend,
+            FieldVisibility::Nobody => false,
+        }
+    }
+}
+
+/// Ai được gửi lời mời kết bạn tới user
+#[derive(Debug, Default, PartialEq, Clone, Copy, Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "friend_request_policy", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum FriendRequestPolicy {
+    #[default]
+    Everyone,
+    /// Chỉ những người có ít nhất một bạn chung
+    FriendsOfFriends,
+    Nobody,
 }
 
 #[allow(unused)]
@@ -23,7 +75,28 @@ pub struct UserEntity {
     pub avatar_url: Option<String>,
     pub bio: Option<String>,
     pub phone: Option<String>,
+    pub email_verified: bool,
+    pub discoverable: bool,
+    pub presence_visibility: PresenceVisibility,
+    pub banned_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub ban_reason: Option<String>,
+    /// Tạm khóa bởi chính user, lần đăng nhập tiếp theo sẽ mở lại
+    pub deactivated_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Lần cuối có request đã xác thực / WebSocket heartbeat (ghi tối đa vài phút một lần)
+    pub last_active_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Được job dọn tài khoản đánh dấu không hoạt động, xóa khi user hoạt động lại
+    pub inactive_flagged_at: Option<chrono::DateTime<chrono::Utc>>,
     pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
+
+/// Một lần đăng nhập thành công, dùng để nhận diện đăng nhập từ IP lạ. Các lần bị khóa
+/// được ghi vào audit log
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct SignInEntity {
+    pub id: Uuid,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
@@ -1,47 +1,190 @@
+use actix::Addr;
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::api::error;
-use crate::configs::RedisCache;
+use crate::configs::{cache::CacheBackend, mailer::Mailer, settings::settings};
+use crate::middlewares::{ActivityTracker, TokenRevocation};
+use crate::modules::audit::{model::AuditEntry, schema::AuditAction, service::AuditService};
+use crate::modules::friend::service::suggestions_key;
+use crate::modules::report::moderation::{ContentFilter, ContentKind};
+use crate::modules::report::schema::ReportTargetType;
+use crate::modules::user::cache::{
+    LocalProfileCache, ProfileInvalidation, PROFILE_INVALIDATION_CHANNEL,
+};
+use crate::modules::user::lockout::{locked_error, LockoutTarget, SignInLockout};
 use crate::modules::user::model::{
-    SignInModel, SignUpModel, UpdateUser, UpdateUserModel, UserResponse,
+    AdminUserListResponse, AdminUserResponse, ChangePasswordModel, DndSchedule, DndSettings,
+    MergeSummary, PlatformStats, ProfilePrivacy, ResetPasswordModel, SignInContext, SignInModel,
+    SignUpModel, UpdateUser, UpdateUserModel, UpdateUserSettingsModel, UserResponse, UserSettings,
+    UsernameAvailability,
 };
+use crate::modules::user::schema::{PresenceVisibility, SignInEntity, UserEntity, UserRole};
 use crate::modules::user::{model::InsertUser, repository::UserRepository};
+use crate::modules::websocket::{
+    events::{SendToUser, SendToUsers},
+    message::{SenderInfo, SenderResolver, ServerMessage},
+    server::WebSocketServer,
+};
 use crate::modules::CACHE_TTL;
-use crate::utils::{hash_password, verify_password, Claims, TypeClaims};
+use crate::utils::{hash_password, needs_rehash, verify_password, Claims, TypeClaims};
 use crate::ENV;
 
+/// Độ dài cho phép của username (sau chuẩn hóa)
+const USERNAME_MIN_LEN: usize = 3;
+const USERNAME_MAX_LEN: usize = 32;
+
+/// Chuẩn hóa username: bỏ khoảng trắng và `@` ở đầu, lowercase. Chỉ gồm chữ cái / số
+/// ASCII, `_` và `.`
+pub fn normalize_username(raw: &str) -> Result<String, error::SystemError> {
+    let username = raw.trim().trim_start_matches('@').to_ascii_lowercase();
+
+    if !(USERNAME_MIN_LEN..=USERNAME_MAX_LEN).contains(&username.len()) {
+        return Err(error::SystemError::bad_request(format!(
+            "Username must be {USERNAME_MIN_LEN} to {USERNAME_MAX_LEN} characters"
+        )));
+    }
+    if !username.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'.') {
+        return Err(error::SystemError::bad_request(
+            "Username can only contain letters, digits, '_' and '.'",
+        ));
+    }
+
+    Ok(username)
+}
+
+/// `last_active_at` được ghi tối đa một lần trong khoảng này (giây) cho mỗi user
+const LAST_ACTIVE_WRITE_INTERVAL: usize = 5 * 60;
+
+/// Số tài khoản không hoạt động xử lý mỗi lần chạy job
+const INACTIVE_ACCOUNT_BATCH_SIZE: i64 = 500;
+
+/// Số lần đăng nhập gần nhất trả về cho user
+const SIGN_IN_HISTORY_LIMIT: i64 = 20;
+
+/// Kết quả autocomplete được cache rất ngắn: đủ cho các phím gõ / xóa lặp lại, không cần
+/// invalidate khi profile hay quan hệ bạn bè thay đổi
+const AUTOCOMPLETE_CACHE_TTL: usize = 30;
+
+/// Username cũ được đổi sau thời điểm này vẫn đang được giữ cho chủ cũ
+pub fn username_reservation_cutoff() -> chrono::DateTime<chrono::Utc> {
+    chrono::Utc::now() - chrono::Duration::days(ENV.username_reservation_days)
+}
+
 #[derive(Clone)]
-pub struct UserService<U>
-where
-    U: UserRepository + Send + Sync,
-{
-    repo: Arc<U>,
-    cache: Arc<RedisCache>,
+pub struct UserService {
+    repo: Arc<dyn UserRepository + Send + Sync>,
+    cache: Arc<dyn CacheBackend>,
+    mailer: Arc<dyn Mailer>,
+    ws_server: Arc<Addr<WebSocketServer>>,
+    local_cache: LocalProfileCache,
+    moderation: ContentFilter,
+    lockout: SignInLockout,
+    audit: AuditService,
 }
 
-impl<U> UserService<U>
-where
-    U: UserRepository + Send + Sync,
-{
-    pub fn with_dependencies(repo: Arc<U>, cache: Arc<RedisCache>) -> Self {
-        UserService { repo, cache }
+impl UserService {
+    pub fn with_dependencies(
+        repo: Arc<dyn UserRepository + Send + Sync>,
+        cache: Arc<dyn CacheBackend>,
+        mailer: Arc<dyn Mailer>,
+        ws_server: Arc<Addr<WebSocketServer>>,
+        local_cache: LocalProfileCache,
+        moderation: ContentFilter,
+        audit: AuditService,
+    ) -> Self {
+        let lockout = SignInLockout::new(cache.clone());
+        UserService { repo, cache, mailer, ws_server, local_cache, moderation, lockout, audit }
     }
 
     pub async fn get_by_id(&self, id: Uuid) -> Result<UserResponse, error::SystemError> {
+        if let Some(local_user) = self.local_cache.get(&id) {
+            return Ok(local_user);
+        }
         let key = format!("user:{}", id);
         if let Some(cached_user) = self.cache.get::<UserResponse>(&key).await? {
+            self.local_cache.insert(cached_user.clone());
             return Ok(cached_user);
         }
         let user_entity = self.repo.find_by_id(&id).await?;
         if let Some(entity) = user_entity {
-            self.cache.set(&key, &UserResponse::from(entity.clone()), CACHE_TTL).await?;
-            Ok(UserResponse::from(entity))
+            let response = UserResponse::from(entity);
+            self.cache.set(&key, &response, CACHE_TTL).await?;
+            self.local_cache.insert(response.clone());
+            Ok(response)
         } else {
             Err(error::SystemError::not_found("User not found"))
         }
     }
 
+    /// Profile của user `id` dưới góc nhìn của `viewer_id`: email / số điện thoại / bio
+    /// bị ẩn theo cài đặt quyền riêng tư (chủ profile luôn thấy đầy đủ)
+    pub async fn get_for_viewer(
+        &self,
+        viewer_id: Uuid,
+        id: Uuid,
+    ) -> Result<UserResponse, error::SystemError> {
+        let user = self.get_by_id(id).await?;
+        if viewer_id == id {
+            return Ok(user);
+        }
+
+        let privacy = self.repo.find_profile_privacy(&viewer_id, &[id]).await?;
+        match privacy.first() {
+            Some(privacy) if privacy.deactivated => {
+                Err(error::SystemError::not_found("User not found"))
+            }
+            Some(privacy) => Ok(user.redact_for(privacy)),
+            None => Ok(user),
+        }
+    }
+
+    /// Cập nhật (hoặc xóa nếu `profile` là None) Redis cache và publish invalidation
+    /// để mọi instance evict local cache và notify friends
+    async fn invalidate_profile(
+        &self,
+        id: Uuid,
+        profile: Option<UserResponse>,
+    ) -> Result<(), error::SystemError> {
+        let key = format!("user:{}", id);
+        match &profile {
+            Some(response) => self.cache.set(&key, response, CACHE_TTL).await?,
+            None => self.cache.delete(&key).await?,
+        }
+        self.local_cache.evict(&id);
+        self.invalidate_suggestions_containing(id).await;
+
+        // Lỗi publish không làm hỏng request, local cache của instance khác sẽ tự hết hạn
+        if let Err(e) = self
+            .cache
+            .publish(PROFILE_INVALIDATION_CHANNEL, &ProfileInvalidation { user_id: id, profile })
+            .await
+        {
+            tracing::warn!("Failed to publish profile invalidation for user {}: {:?}", id, e);
+        }
+
+        Ok(())
+    }
+
+    /// Gợi ý kết bạn đã cache chứa username / avatar của user, xóa cache của những
+    /// người có thể đang thấy user trong gợi ý. Lỗi chỉ được log, cache tự hết hạn
+    async fn invalidate_suggestions_containing(&self, id: Uuid) {
+        let viewer_ids = match self.repo.find_friends_of_friends(&id).await {
+            Ok(ids) => ids,
+            Err(e) => {
+                tracing::warn!("Failed to load suggestion viewers of user {}: {:?}", id, e);
+                return;
+            }
+        };
+
+        for viewer_id in viewer_ids {
+            if let Err(e) = self.cache.delete(&suggestions_key(&viewer_id)).await {
+                tracing::warn!("Failed to invalidate friend suggestions for {}: {}", viewer_id, e);
+            }
+        }
+    }
+
     pub async fn update(
         &self,
         id: Uuid,
@@ -51,85 +194,765 @@ where
             return Err(error::SystemError::bad_request("No fields to update"));
         }
 
+        let username = match &user.username {
+            Some(raw) => Some(self.prepare_username_change(id, raw).await?),
+            None => None,
+        };
+
+        let bio = user
+            .bio
+            .map(|bio| bio.map(|bio| self.moderation.check(ContentKind::Bio, bio)).transpose())
+            .transpose()?;
+
         let update_user = UpdateUser {
-            username: user.username,
+            username,
             email: user.email,
             display_name: user.display_name,
             avatar_url: user.avatar_url,
-            bio: user.bio,
+            bio: bio.as_ref().map(|bio| bio.as_ref().map(|bio| bio.content.clone())),
             phone: user.phone,
+            discoverable: user.discoverable,
         };
 
         let updated_user = self.repo.update(&id, &update_user).await?;
 
-        let key = format!("user:{}", id);
         let response = UserResponse::from(updated_user);
-        self.cache.set(&key, &response, CACHE_TTL).await?;
+        self.invalidate_profile(id, Some(response.clone())).await?;
+
+        if let Some(Some(bio)) = &bio {
+            self.moderation.flag(ReportTargetType::User, id, ContentKind::Bio, bio).await;
+        }
 
         Ok(response)
     }
 
+    /// Chuẩn hóa username mới, kiểm tra cooldown giữa các lần đổi và username chưa bị
+    /// người khác dùng / giữ chỗ
+    async fn prepare_username_change(
+        &self,
+        id: Uuid,
+        raw: &str,
+    ) -> Result<String, error::SystemError> {
+        let username = normalize_username(raw)?;
+
+        let current = self
+            .repo
+            .find_by_id(&id)
+            .await?
+            .ok_or_else(|| error::SystemError::not_found("User not found"))?;
+        // Chỉ đổi hoa / thường không tính là đổi username
+        if current.username.to_ascii_lowercase() == username {
+            return Ok(username);
+        }
+
+        if let Some(changed_at) = self.repo.find_last_username_change(&id).await? {
+            let next_change =
+                changed_at + chrono::Duration::days(ENV.username_change_cooldown_days);
+            if next_change > chrono::Utc::now() {
+                return Err(error::SystemError::too_many_requests(format!(
+                    "Username can be changed again after {}",
+                    next_change.to_rfc3339()
+                )));
+            }
+        }
+
+        if !self
+            .repo
+            .is_username_available(&username, Some(&id), username_reservation_cutoff())
+            .await?
+        {
+            return Err(error::SystemError::conflict("username"));
+        }
+
+        Ok(username)
+    }
+
+    /// Username có dùng được không (đăng ký hoặc đổi sang), kèm dạng đã chuẩn hóa
+    pub async fn check_username(
+        &self,
+        user_id: Option<Uuid>,
+        raw: &str,
+    ) -> Result<UsernameAvailability, error::SystemError> {
+        let username = normalize_username(raw)?;
+        let available = self
+            .repo
+            .is_username_available(&username, user_id.as_ref(), username_reservation_cutoff())
+            .await?;
+
+        Ok(UsernameAvailability { username, available })
+    }
+
+    /// Gán avatar mới (file đã upload), trả về profile mới và file ID của avatar cũ
+    /// (None nếu chưa có avatar hoặc avatar cũ không phải file upload)
+    pub async fn update_avatar(
+        &self,
+        id: Uuid,
+        avatar_url: &str,
+        avatar_id: Uuid,
+    ) -> Result<(UserResponse, Option<Uuid>), error::SystemError> {
+        let change = self.repo.update_avatar(&id, avatar_url, &avatar_id.to_string()).await?;
+
+        let response = UserResponse::from(change.user);
+        self.invalidate_profile(id, Some(response.clone())).await?;
+
+        let previous = change
+            .previous_avatar_id
+            .and_then(|previous| Uuid::parse_str(&previous).ok())
+            .filter(|previous| *previous != avatar_id);
+
+        Ok((response, previous))
+    }
+
     pub async fn delete(&self, id: Uuid) -> Result<(), error::SystemError> {
         let deleted = self.repo.delete(&id).await?;
         if !deleted {
             return Err(error::SystemError::not_found("User not found"));
         }
+        self.revoke_all_tokens(id).await?;
+        self.invalidate_profile(id, None).await?;
+        Ok(())
+    }
+
+    /// Tạm khóa tài khoản: ẩn khỏi search / danh sách bạn bè / gợi ý, người khác không
+    /// nhắn tin được (`ACCOUNT_DEACTIVATED`). Mọi session bị đăng xuất, lần đăng nhập tiếp
+    /// theo sẽ mở lại tài khoản. Khác với `delete`, dữ liệu và username vẫn được giữ
+    pub async fn deactivate(&self, id: Uuid) -> Result<(), error::SystemError> {
+        if !self.repo.set_deactivated(&id, true).await? {
+            return Err(error::SystemError::not_found("User not found"));
+        }
+
+        self.revoke_all_tokens(id).await?;
+        self.invalidate_profile(id, None).await?;
+
+        self.ws_server.do_send(SendToUser { user_id: id, message: ServerMessage::ForceSignOut });
+
+        Ok(())
+    }
+
+    /// Mở lại tài khoản đang tạm khóa khi user đăng nhập
+    pub async fn reactivate_if_deactivated(
+        &self,
+        user: &UserEntity,
+    ) -> Result<(), error::SystemError> {
+        if user.deactivated_at.is_none() {
+            return Ok(());
+        }
+
+        self.repo.set_deactivated(&user.id, false).await?;
+        self.invalidate_profile(user.id, None).await?;
+
+        Ok(())
+    }
+
+    /// Ghi nhận user vừa hoạt động. Redis `SET NX` giới hạn tần suất ghi `last_active_at`,
+    /// lỗi chỉ được log vì không ảnh hưởng tới request
+    pub async fn record_activity(&self, user_id: Uuid) {
+        let key = format!("last_active:{user_id}");
+        match self.cache.set_nx(&key, &true, LAST_ACTIVE_WRITE_INTERVAL).await {
+            Ok(false) => {}
+            Ok(true) => {
+                if let Err(e) = self.repo.update_last_active(&user_id).await {
+                    tracing::warn!("Failed to record last activity of user {}: {:?}", user_id, e);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to throttle activity of user {}: {:?}", user_id, e),
+        }
+    }
+
+    /// Đánh dấu (`INACTIVE_ACCOUNT_ACTION=flag`) hoặc tạm khóa (`deactivate`) một batch
+    /// tài khoản không hoạt động quá `INACTIVE_ACCOUNT_DAYS` ngày, trả về số tài khoản đã
+    /// xử lý. Tài khoản bị tạm khóa được mở lại ở lần đăng nhập tiếp theo như thường
+    pub async fn process_inactive_accounts(&self) -> Result<usize, error::SystemError> {
+        let inactive_before =
+            chrono::Utc::now() - chrono::Duration::days(ENV.inactive_account_days);
+        let deactivate = ENV.inactive_account_action == "deactivate";
+
+        let ids = self
+            .repo
+            .mark_inactive_accounts(inactive_before, deactivate, INACTIVE_ACCOUNT_BATCH_SIZE)
+            .await?;
+
+        if deactivate {
+            for id in &ids {
+                if let Err(e) = self.invalidate_profile(*id, None).await {
+                    tracing::warn!("Failed to invalidate profile of user {}: {:?}", id, e);
+                }
+            }
+        }
+
+        Ok(ids.len())
+    }
+
+    /// Admin: merge tài khoản trùng vào tài khoản chính, revoke token của tài khoản trùng
+    /// và thông báo cho các session liên quan
+    pub async fn merge_accounts(
+        &self,
+        primary_id: Uuid,
+        duplicate_id: Uuid,
+    ) -> Result<MergeSummary, error::SystemError> {
+        if primary_id == duplicate_id {
+            return Err(error::SystemError::bad_request("Cannot merge an account into itself"));
+        }
+
+        let summary = self.repo.merge_accounts(&primary_id, &duplicate_id).await?;
+
+        self.revoke_all_tokens(duplicate_id).await?;
+        self.invalidate_profile(primary_id, None).await?;
+        self.invalidate_profile(duplicate_id, None).await?;
+
+        let mut user_ids = summary.affected_user_ids.clone();
+        user_ids.extend([primary_id, duplicate_id]);
+        self.ws_server.do_send(SendToUsers {
+            user_ids,
+            message: ServerMessage::AccountMerged { primary_id, duplicate_id },
+        });
+
+        Ok(summary)
+    }
+
+    /// Admin: list/search users với offset pagination
+    pub async fn list_users(
+        &self,
+        query: Option<&str>,
+        page: i64,
+        limit: i64,
+    ) -> Result<AdminUserListResponse, error::SystemError> {
+        let query = query.map(str::trim).filter(|q| !q.is_empty());
+        let offset = (page - 1) * limit;
+
+        let (users, total) = self.repo.list_users(query, limit, offset).await?;
+
+        Ok(AdminUserListResponse {
+            users: users.into_iter().map(AdminUserResponse::from).collect(),
+            total,
+            page,
+            limit,
+        })
+    }
+
+    /// Admin: ban user, revoke tất cả token và đá các session đang mở
+    pub async fn ban_user(
+        &self,
+        admin_id: Uuid,
+        user_id: Uuid,
+        reason: Option<String>,
+    ) -> Result<(), error::SystemError> {
+        if admin_id == user_id {
+            return Err(error::SystemError::bad_request("You cannot ban yourself"));
+        }
+
+        if !self.repo.set_banned(&user_id, true, reason.as_deref()).await? {
+            return Err(error::SystemError::not_found("User not found"));
+        }
+
+        self.force_sign_out(user_id).await
+    }
+
+    /// Admin: gỡ ban user
+    pub async fn unban_user(&self, user_id: Uuid) -> Result<(), error::SystemError> {
+        if !self.repo.set_banned(&user_id, false, None).await? {
+            return Err(error::SystemError::not_found("User not found"));
+        }
+        self.invalidate_profile(user_id, None).await?;
         Ok(())
     }
 
+    /// Admin: revoke tất cả token của user và thông báo cho các WS session
+    pub async fn force_sign_out(&self, user_id: Uuid) -> Result<(), error::SystemError> {
+        if self.repo.find_by_id(&user_id).await?.is_none() {
+            return Err(error::SystemError::not_found("User not found"));
+        }
+
+        self.revoke_all_tokens(user_id).await?;
+        self.invalidate_profile(user_id, None).await?;
+
+        self.ws_server.do_send(SendToUser { user_id, message: ServerMessage::ForceSignOut });
+
+        Ok(())
+    }
+
+    /// Admin: thống kê tổng quan (users, messages theo ngày)
+    pub async fn platform_stats(&self, days: i32) -> Result<PlatformStats, error::SystemError> {
+        self.repo.platform_stats(days).await
+    }
+
     pub async fn sign_up(&self, user: SignUpModel) -> Result<uuid::Uuid, error::SystemError> {
+        let username = normalize_username(&user.username)?;
+        if !self.repo.is_username_available(&username, None, username_reservation_cutoff()).await? {
+            return Err(error::SystemError::conflict("username"));
+        }
+
         let hash_password = hash_password(&user.password)?;
 
         let new_user = InsertUser {
-            username: user.username,
+            username,
             email: user.email,
             hash_password,
             display_name: user.display_name,
         };
 
         let user_id = self.repo.create(&new_user).await?;
+
+        // Lỗi gửi mail không làm hỏng việc đăng ký, user có thể yêu cầu gửi lại
+        if let Err(e) =
+            self.send_verification_email(&user_id, &UserRole::User, &new_user.email).await
+        {
+            tracing::warn!("Failed to send verification email to user {}: {:?}", user_id, e);
+        }
+
         Ok(user_id)
     }
 
-    pub async fn sign_in(&self, user: SignInModel) -> Result<(String, String), error::SystemError> {
+    /// Tạo verification token và gửi email xác thực
+    async fn send_verification_email(
+        &self,
+        user_id: &Uuid,
+        role: &UserRole,
+        email: &str,
+    ) -> Result<(), error::SystemError> {
+        let jti = Uuid::now_v7();
+        let token = Claims::new(user_id, role, ENV.email_verification_expiration)
+            .with_jti(jti)
+            .with_type(TypeClaims::EmailVerification)
+            .encode()?;
+
+        let verification_key = format!("email_verification:{jti}");
+        self.cache
+            .set(&verification_key, user_id, ENV.email_verification_expiration as usize)
+            .await?;
+
+        let link = format!("{}/verify-email?token={}", ENV.frontend_url, token);
+        self.mailer
+            .send(
+                email,
+                "Verify your email",
+                &format!("Use the following link to verify your email: {link}"),
+            )
+            .await
+    }
+
+    /// Xác thực email bằng verification token (chỉ dùng được 1 lần)
+    pub async fn verify_email(&self, token: &str) -> Result<(), error::SystemError> {
+        let invalid = || error::SystemError::unauthorized("Invalid or expired verification token");
+
+        let payload = Claims::decode(token).map_err(|_| invalid())?;
+
+        let Some(TypeClaims::EmailVerification) = payload._type else {
+            return Err(invalid());
+        };
+
+        let Some(jti) = payload.jti else {
+            return Err(invalid());
+        };
+
+        let verification_key = format!("email_verification:{jti}");
+        if !self.cache.exists(&verification_key).await? {
+            return Err(invalid());
+        }
+        self.cache.delete(&verification_key).await?;
+
+        if !self.repo.mark_email_verified(&payload.sub).await? {
+            return Err(invalid());
+        }
+
+        Ok(())
+    }
+
+    /// Gửi lại email xác thực, giới hạn theo email để tránh spam.
+    /// Không báo lỗi nếu email không tồn tại hoặc đã xác thực.
+    pub async fn resend_verification(&self, email: &str) -> Result<(), error::SystemError> {
+        self.start_email_cooldown(
+            &format!("verification_resend:{}", email.to_lowercase()),
+            "Please wait before requesting another verification email",
+        )
+        .await?;
+
+        let Some(user_entity) = self.repo.find_by_email(email).await? else {
+            return Ok(());
+        };
+
+        if user_entity.email_verified {
+            return Ok(());
+        }
+
+        self.send_verification_email(&user_entity.id, &user_entity.role, &user_entity.email).await
+    }
+
+    /// Giới hạn tần suất gửi email theo `key` (`VERIFICATION_RESEND_COOLDOWN`). Cooldown
+    /// được đặt cả khi email không tồn tại để không lộ email nào đã đăng ký
+    async fn start_email_cooldown(
+        &self,
+        key: &str,
+        message: &'static str,
+    ) -> Result<(), error::SystemError> {
+        if self.cache.exists(key).await? {
+            return Err(error::SystemError::too_many_requests(message));
+        }
+        self.cache.set(key, &true, settings().verification_resend_cooldown as usize).await
+    }
+
+    pub async fn sign_in(
+        &self,
+        user: SignInModel,
+        context: SignInContext,
+    ) -> Result<(String, String), error::SystemError> {
+        let mut targets = vec![LockoutTarget::Username(&user.username)];
+        if let Some(ip_address) = context.ip_address.as_deref() {
+            targets.push(LockoutTarget::Ip(ip_address));
+        }
+        self.lockout.ensure_unlocked(&targets).await?;
+
+        // Bot chỉ xác thực bằng API key, guest chỉ bằng token lời mời
         let user_entity = self
             .repo
             .find_by_username(&user.username)
             .await?
-            .ok_or_else(|| error::SystemError::unauthorized("Invalid username or password"))?;
+            .filter(|entity| !matches!(entity.role, UserRole::Bot | UserRole::Guest));
 
-        let valid = verify_password(&user_entity.hash_password, &user.password)?;
-        if !valid {
-            return Err(error::SystemError::unauthorized("Invalid username or password"));
+        let valid = match &user_entity {
+            Some(entity) => verify_password(&entity.hash_password, &user.password)?,
+            None => false,
+        };
+        let user_entity = match user_entity {
+            Some(entity) if valid => entity,
+            found => {
+                return Err(self.record_failed_sign_in(found.as_ref(), &targets, &context).await?)
+            }
+        };
+        self.lockout.reset(LockoutTarget::Username(&user.username)).await?;
+
+        // Lỗi nâng cấp hash không chặn việc đăng nhập, sẽ thử lại ở lần đăng nhập sau
+        if let Err(e) = self.rehash_password_if_needed(&user_entity, &user.password).await {
+            tracing::warn!("Failed to rehash password of user {}: {:?}", user_entity.id, e);
         }
 
-        let access_token =
-            Claims::new(&user_entity.id, &user_entity.role, ENV.access_token_expiration)
-                .with_type(TypeClaims::AccessToken)
-                .encode(ENV.jwt_secret.as_ref())?;
+        if !user_entity.email_verified {
+            return Err(error::SystemError::forbidden("Email is not verified")
+                .with_code(error::ErrorCode::EmailNotVerified));
+        }
+
+        if user_entity.banned_at.is_some() {
+            return Err(error::SystemError::forbidden("Account has been banned")
+                .with_code(error::ErrorCode::AccountBanned));
+        }
+
+        self.check_sign_in_ip(&user_entity, &context).await?;
+        self.reactivate_if_deactivated(&user_entity).await?;
+
+        self.repo
+            .record_sign_in(
+                &user_entity.id,
+                context.ip_address.as_deref(),
+                context.user_agent.as_deref(),
+            )
+            .await?;
+
+        self.issue_tokens(&user_entity.id, &user_entity.role).await
+    }
+
+    /// Hash lại mật khẩu (vừa được xác thực đúng) nếu hash đang lưu dùng tham số Argon2 /
+    /// thuật toán khác cấu hình hiện tại
+    async fn rehash_password_if_needed(
+        &self,
+        user: &UserEntity,
+        password: &str,
+    ) -> Result<(), error::SystemError> {
+        if !needs_rehash(&user.hash_password)? {
+            return Ok(());
+        }
+
+        let hash_password = hash_password(password)?;
+        self.repo.update_password(&user.id, &hash_password).await?;
+
+        Ok(())
+    }
+
+    /// Đếm lần đăng nhập sai, trả về lỗi cho client: `ACCOUNT_LOCKED` nếu lần sai này
+    /// làm username / IP bị khóa, ngược lại là lỗi sai thông tin đăng nhập. Mỗi lần khóa
+    /// được ghi vào audit log, gắn với user (nếu tồn tại) khi username bị khóa
+    async fn record_failed_sign_in(
+        &self,
+        user: Option<&UserEntity>,
+        targets: &[LockoutTarget<'_>],
+        context: &SignInContext,
+    ) -> Result<error::SystemError, error::SystemError> {
+        let locked = self.lockout.record_failure(targets).await?;
+
+        let mut longest = None;
+        for (target, duration) in locked {
+            tracing::warn!(
+                "Sign-in locked for {:?} for {}s after failed attempts",
+                target,
+                duration
+            );
+            longest = longest.max(Some(duration));
+
+            let user_id = match target {
+                LockoutTarget::Username(_) => user.map(|user| user.id),
+                LockoutTarget::Ip(_) => None,
+            };
+            let entry = AuditEntry::new(AuditAction::SignInLocked, user_id)
+                .with_client(context.ip_address.as_deref(), context.user_agent.as_deref());
+            self.audit.record(entry).await?;
+        }
+
+        Ok(match longest {
+            Some(duration) => locked_error(duration),
+            None => error::SystemError::unauthorized("Invalid username or password"),
+        })
+    }
+
+    /// Đăng nhập từ IP user chưa từng dùng: gửi email cảnh báo (`notify`) hoặc bắt xác nhận
+    /// qua email trước khi cấp token (`confirm`), theo `SUSPICIOUS_SIGN_IN_ACTION`. Sự kiện
+    /// được ghi vào audit log kèm Notification entry cho user (mỗi lần gửi email xác nhận
+    /// với `confirm`). Chỉ so sánh theo IP vì server không có dữ liệu GeoIP để suy ra quốc gia
+    async fn check_sign_in_ip(
+        &self,
+        user: &UserEntity,
+        context: &SignInContext,
+    ) -> Result<(), error::SystemError> {
+        let Some(ip_address) = context.ip_address.as_deref() else {
+            return Ok(());
+        };
+        if ENV.suspicious_sign_in_action == "off"
+            || !self.repo.is_new_sign_in_ip(&user.id, ip_address).await?
+        {
+            return Ok(());
+        }
+
+        let entry = AuditEntry::new(AuditAction::NewSignInIp, Some(user.id))
+            .with_client(Some(ip_address), context.user_agent.as_deref());
+
+        if ENV.suspicious_sign_in_action == "confirm" {
+            if self.send_sign_in_confirmation(user, context, ip_address).await? {
+                self.audit.record(entry).await?;
+            }
+            return Err(error::SystemError::forbidden(
+                "Sign-in from a new location must be confirmed via email",
+            )
+            .with_code(error::ErrorCode::SignInConfirmationRequired));
+        }
+
+        self.audit.record(entry).await?;
+
+        // Lỗi gửi mail cảnh báo không chặn việc đăng nhập
+        let device = context.user_agent.as_deref().unwrap_or("unknown device");
+        let body = format!(
+            "Your account was signed in from a new IP address {ip_address} ({device}). \
+             If this wasn't you, reset your password: {}/forgot-password",
+            ENV.frontend_url
+        );
+        if let Err(e) = self.mailer.send(&user.email, "New sign-in to your account", &body).await {
+            tracing::warn!("Failed to send sign-in alert to user {}: {:?}", user.id, e);
+        }
+
+        Ok(())
+    }
+
+    /// Gửi email xác nhận đăng nhập từ IP mới, tối đa một email cho mỗi IP trong thời
+    /// hạn của token để không spam hộp thư khi đăng nhập lại nhiều lần. Trả về `false` nếu
+    /// email đã được gửi trước đó
+    async fn send_sign_in_confirmation(
+        &self,
+        user: &UserEntity,
+        context: &SignInContext,
+        ip_address: &str,
+    ) -> Result<bool, error::SystemError> {
+        let ttl = ENV.sign_in_confirmation_expiration as usize;
+        let sent_key = format!("sign_in_confirmation_sent:{}:{ip_address}", user.id);
+        if !self.cache.set_nx(&sent_key, &true, ttl).await? {
+            return Ok(false);
+        }
+
+        let jti = Uuid::now_v7();
+        let token = Claims::new(&user.id, &user.role, ENV.sign_in_confirmation_expiration)
+            .with_jti(jti)
+            .with_type(TypeClaims::SignInConfirmation)
+            .encode()?;
+
+        let confirmation_key = format!("sign_in_confirmation:{jti}");
+        let pending = (ip_address, context.user_agent.as_deref());
+        self.cache.set(&confirmation_key, &pending, ttl).await?;
+
+        let device = context.user_agent.as_deref().unwrap_or("unknown device");
+        let link = format!("{}/confirm-sign-in?token={}", ENV.frontend_url, token);
+        self.mailer
+            .send(
+                &user.email,
+                "Confirm sign-in from a new location",
+                &format!(
+                    "Someone is signing in to your account from IP {ip_address} ({device}). \
+                     If this was you, use the following link to allow it: {link}"
+                ),
+            )
+            .await?;
+
+        Ok(true)
+    }
+
+    /// Xác nhận đăng nhập từ IP mới (token chỉ dùng được 1 lần). IP được ghi vào lịch sử
+    /// đăng nhập nên lần đăng nhập lại từ IP đó không cần xác nhận nữa
+    pub async fn confirm_sign_in(&self, token: &str) -> Result<(), error::SystemError> {
+        let invalid = || error::SystemError::unauthorized("Invalid or expired confirmation token");
+
+        let payload = Claims::decode(token).map_err(|_| invalid())?;
+
+        let Some(TypeClaims::SignInConfirmation) = payload._type else {
+            return Err(invalid());
+        };
+
+        let Some(jti) = payload.jti else {
+            return Err(invalid());
+        };
+
+        let confirmation_key = format!("sign_in_confirmation:{jti}");
+        let Some((ip_address, user_agent)) =
+            self.cache.get::<(String, Option<String>)>(&confirmation_key).await?
+        else {
+            return Err(invalid());
+        };
+        self.cache.delete(&confirmation_key).await?;
+
+        self.repo.record_sign_in(&payload.sub, Some(&ip_address), user_agent.as_deref()).await
+    }
+
+    /// Các lần đăng nhập gần nhất của user
+    pub async fn list_sign_ins(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<SignInEntity>, error::SystemError> {
+        self.repo.find_sign_ins(&user_id, SIGN_IN_HISTORY_LIMIT).await
+    }
+
+    /// Cấp cặp access/refresh token mới và lưu refresh jti vào Redis
+    pub async fn issue_tokens(
+        &self,
+        user_id: &Uuid,
+        role: &UserRole,
+    ) -> Result<(String, String), error::SystemError> {
+        let access_token = Claims::new(user_id, role, ENV.access_token_expiration)
+            .with_jti(Uuid::now_v7())
+            .with_type(TypeClaims::AccessToken)
+            .encode()?;
 
         let jti = Uuid::new_v7(uuid::Timestamp::now(uuid::NoContext));
 
-        let refresh_token =
-            Claims::new(&user_entity.id, &user_entity.role, ENV.refresh_token_expiration)
-                .with_jti(jti)
-                .with_type(TypeClaims::RefreshToken)
-                .encode(ENV.jwt_secret.as_ref())?;
+        let refresh_token = Claims::new(user_id, role, ENV.refresh_token_expiration)
+            .with_jti(jti)
+            .with_type(TypeClaims::RefreshToken)
+            .encode()?;
 
         let refresh_key = format!("refresh_token:{jti}");
-        self.cache
-            .set(&refresh_key, &user_entity.id, ENV.refresh_token_expiration as usize)
-            .await?;
+        self.cache.set(&refresh_key, user_id, ENV.refresh_token_expiration as usize).await?;
 
         Ok((access_token, refresh_token))
     }
 
-    pub async fn sign_out(&self, refresh_token: Option<String>) -> Result<(), error::SystemError> {
+    /// Đổi mật khẩu: verify mật khẩu cũ, hash lại, revoke tất cả token cũ
+    /// và cấp cặp token mới cho session hiện tại
+    pub async fn change_password(
+        &self,
+        claims: &Claims,
+        model: ChangePasswordModel,
+    ) -> Result<(String, String), error::SystemError> {
+        let user_entity = self
+            .repo
+            .find_by_id(&claims.sub)
+            .await?
+            .ok_or_else(|| error::SystemError::not_found("User not found"))?;
+
+        if !verify_password(&user_entity.hash_password, &model.old_password)? {
+            return Err(error::SystemError::bad_request("Old password is incorrect"));
+        }
+
+        let hash_password = hash_password(&model.new_password)?;
+        self.repo.update_password(&user_entity.id, &hash_password).await?;
+
+        self.revoke_all_tokens(user_entity.id).await?;
+        self.revoke_access_token(claims).await?;
+
+        self.issue_tokens(&user_entity.id, &user_entity.role).await
+    }
+
+    /// Gửi email chứa reset token ngắn hạn. Không báo lỗi nếu email không tồn tại
+    /// để tránh lộ thông tin tài khoản.
+    pub async fn forgot_password(&self, email: &str) -> Result<(), error::SystemError> {
+        self.start_email_cooldown(
+            &format!("password_reset_request:{}", email.to_lowercase()),
+            "Please wait before requesting another password reset email",
+        )
+        .await?;
+
+        let Some(user_entity) = self.repo.find_by_email(email).await? else {
+            return Ok(());
+        };
+
+        let jti = Uuid::now_v7();
+        let token = Claims::new(&user_entity.id, &user_entity.role, ENV.password_reset_expiration)
+            .with_jti(jti)
+            .with_type(TypeClaims::PasswordReset)
+            .encode()?;
+
+        let reset_key = format!("password_reset:{jti}");
+        self.cache.set(&reset_key, &user_entity.id, ENV.password_reset_expiration as usize).await?;
+
+        let link = format!("{}/reset-password?token={}", ENV.frontend_url, token);
+        self.mailer
+            .send(
+                &user_entity.email,
+                "Reset your password",
+                &format!("Use the following link to reset your password: {link}"),
+            )
+            .await
+    }
+
+    /// Đặt lại mật khẩu bằng reset token (chỉ dùng được 1 lần)
+    pub async fn reset_password(
+        &self,
+        model: ResetPasswordModel,
+    ) -> Result<(), error::SystemError> {
+        let invalid = || error::SystemError::unauthorized("Invalid or expired reset token");
+
+        let payload = Claims::decode(&model.token).map_err(|_| invalid())?;
+
+        let Some(TypeClaims::PasswordReset) = payload._type else {
+            return Err(invalid());
+        };
+
+        let Some(jti) = payload.jti else {
+            return Err(invalid());
+        };
+
+        let reset_key = format!("password_reset:{jti}");
+        if !self.cache.exists(&reset_key).await? {
+            return Err(invalid());
+        }
+        self.cache.delete(&reset_key).await?;
+
+        let hash_password = hash_password(&model.new_password)?;
+        if !self.repo.update_password(&payload.sub, &hash_password).await? {
+            return Err(invalid());
+        }
+
+        self.revoke_all_tokens(payload.sub).await
+    }
+
+    pub async fn sign_out(
+        &self,
+        refresh_token: Option<String>,
+        access_token: Option<String>,
+    ) -> Result<(), error::SystemError> {
+        if let Some(claims) = access_token.and_then(|t| Claims::decode(&t).ok()) {
+            if claims._type == Some(TypeClaims::AccessToken) {
+                self.revoke_access_token(&claims).await?;
+            }
+        }
+
         let Some(token) = refresh_token else {
             return Ok(());
         };
 
-        let payload = Claims::decode(&token, ENV.jwt_secret.as_ref())?;
+        let payload = Claims::decode(&token)?;
 
         let Some(TypeClaims::RefreshToken) = payload._type else {
             return Ok(());
@@ -155,7 +978,7 @@ where
             return Err(invalid());
         };
 
-        let payload = Claims::decode(&old_refresh_token, ENV.jwt_secret.as_ref())?;
+        let payload = Claims::decode(&old_refresh_token)?;
 
         let Some(TypeClaims::RefreshToken) = payload._type else {
             return Err(invalid());
@@ -171,6 +994,11 @@ where
             return Err(invalid());
         }
 
+        if self.is_token_revoked(&payload).await? {
+            self.cache.delete(&old_key).await?;
+            return Err(invalid());
+        }
+
         self.cache.delete(&old_key).await?;
 
         let new_jti = Uuid::new_v7(uuid::Timestamp::now(uuid::NoContext));
@@ -178,23 +1006,26 @@ where
 
         let new_access_token =
             Claims::new(&payload.sub, &payload.role, ENV.access_token_expiration)
+                .with_jti(Uuid::now_v7())
                 .with_type(TypeClaims::AccessToken)
-                .encode(ENV.jwt_secret.as_ref())?;
+                .encode()?;
 
         let new_refresh_token =
             Claims::new(&payload.sub, &payload.role, ENV.refresh_token_expiration)
                 .with_jti(new_jti)
                 .with_type(TypeClaims::RefreshToken)
-                .encode(ENV.jwt_secret.as_ref())?;
+                .encode()?;
 
         self.cache.set(&new_key, &payload.sub, ENV.refresh_token_expiration as usize).await?;
 
         Ok((new_access_token, new_refresh_token))
     }
 
-    /// Search users by username or display name
+    /// Search users by username or display name, các trường riêng tư bị ẩn theo cài đặt
+    /// của từng user
     pub async fn search_users(
         &self,
+        viewer_id: Uuid,
         query: &str,
         limit: i32,
     ) -> Result<Vec<UserResponse>, error::SystemError> {
@@ -214,8 +1045,560 @@ where
 
         let users = self.repo.search_users(query, limit).await?;
 
-        let responses: Vec<UserResponse> = users.into_iter().map(UserResponse::from).collect();
+        let ids: Vec<Uuid> = users.iter().map(|user| user.id).collect();
+        let privacy: HashMap<Uuid, ProfilePrivacy> = self
+            .repo
+            .find_profile_privacy(&viewer_id, &ids)
+            .await?
+            .into_iter()
+            .map(|privacy| (privacy.user_id, privacy))
+            .collect();
+
+        let responses: Vec<UserResponse> = users
+            .into_iter()
+            .map(|user| {
+                let response = UserResponse::from(user);
+                match privacy.get(&response.id) {
+                    Some(privacy) if response.id != viewer_id => response.redact_for(privacy),
+                    _ => response,
+                }
+            })
+            .collect();
 
         Ok(responses)
     }
+
+    /// Gợi ý users theo từng phím gõ (mention, tạo nhóm, ...), cache theo viewer và query
+    pub async fn autocomplete_users(
+        &self,
+        viewer_id: Uuid,
+        query: &str,
+        limit: i32,
+    ) -> Result<Vec<UserResponse>, error::SystemError> {
+        let query = query.trim().trim_start_matches('@').to_lowercase();
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let limit = limit.clamp(1, 20);
+
+        let key = format!("user_autocomplete:{viewer_id}:{limit}:{query}");
+        match self.cache.get::<Vec<UserResponse>>(&key).await {
+            Ok(Some(cached)) => return Ok(cached),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to read cached autocomplete results: {}", e),
+        }
+
+        let users = self.repo.autocomplete_users(&viewer_id, &query, limit).await?;
+
+        let ids: Vec<Uuid> = users.iter().map(|user| user.id).collect();
+        let privacy: HashMap<Uuid, ProfilePrivacy> = self
+            .repo
+            .find_profile_privacy(&viewer_id, &ids)
+            .await?
+            .into_iter()
+            .map(|privacy| (privacy.user_id, privacy))
+            .collect();
+        let responses: Vec<UserResponse> = users
+            .into_iter()
+            .map(|user| {
+                let response = UserResponse::from(user);
+                match privacy.get(&response.id) {
+                    Some(privacy) => response.redact_for(privacy),
+                    None => response,
+                }
+            })
+            .collect();
+
+        if let Err(e) = self.cache.set(&key, &responses, AUTOCOMPLETE_CACHE_TTL).await {
+            tracing::warn!("Failed to cache autocomplete results: {}", e);
+        }
+
+        Ok(responses)
+    }
+
+    pub async fn get_presence_visibility(
+        &self,
+        user_id: Uuid,
+    ) -> Result<PresenceVisibility, error::SystemError> {
+        self.repo
+            .find_presence_visibility(&user_id)
+            .await?
+            .ok_or_else(|| error::SystemError::not_found("User not found"))
+    }
+
+    pub async fn update_presence_visibility(
+        &self,
+        user_id: Uuid,
+        visibility: PresenceVisibility,
+    ) -> Result<(), error::SystemError> {
+        if !self.repo.update_presence_visibility(&user_id, visibility).await? {
+            return Err(error::SystemError::not_found("User not found"));
+        }
+        Ok(())
+    }
+
+    pub async fn get_settings(&self, user_id: Uuid) -> Result<UserSettings, error::SystemError> {
+        Ok(self.repo.find_settings(&user_id).await?.unwrap_or_default())
+    }
+
+    /// Cập nhật cài đặt quyền riêng tư, trường bỏ trống giữ nguyên giá trị hiện tại
+    pub async fn update_settings(
+        &self,
+        user_id: Uuid,
+        body: UpdateUserSettingsModel,
+    ) -> Result<UserSettings, error::SystemError> {
+        let current = self.get_settings(user_id).await?;
+        let settings = UserSettings {
+            email_visibility: body.email_visibility.unwrap_or(current.email_visibility),
+            phone_visibility: body.phone_visibility.unwrap_or(current.phone_visibility),
+            bio_visibility: body.bio_visibility.unwrap_or(current.bio_visibility),
+            friend_request_policy: body
+                .friend_request_policy
+                .unwrap_or(current.friend_request_policy),
+        };
+
+        let settings = self.repo.upsert_settings(&user_id, &settings).await?;
+
+        // User chặn mọi lời mời kết bạn không còn được gợi ý cho người khác
+        if settings.friend_request_policy != current.friend_request_policy {
+            self.invalidate_suggestions_containing(user_id).await;
+        }
+
+        Ok(settings)
+    }
+
+    pub async fn get_dnd(&self, user_id: Uuid) -> Result<DndSettings, error::SystemError> {
+        self.repo
+            .find_dnd(&user_id)
+            .await?
+            .ok_or_else(|| error::SystemError::not_found("DND schedule not found"))
+    }
+
+    /// Đặt lịch Do Not Disturb, `timezone` là tên IANA (vd `Asia/Ho_Chi_Minh`)
+    pub async fn set_dnd(
+        &self,
+        user_id: Uuid,
+        schedule: DndSchedule,
+    ) -> Result<DndSettings, error::SystemError> {
+        if schedule.start == schedule.end {
+            return Err(error::SystemError::bad_request("DND start and end must differ"));
+        }
+        if !self.repo.is_valid_timezone(&schedule.timezone).await? {
+            return Err(error::SystemError::bad_request("Unknown timezone"));
+        }
+
+        self.repo.upsert_dnd(&user_id, &schedule).await
+    }
+
+    pub async fn clear_dnd(&self, user_id: Uuid) -> Result<(), error::SystemError> {
+        if !self.repo.delete_dnd(&user_id).await? {
+            return Err(error::SystemError::not_found("DND schedule not found"));
+        }
+        Ok(())
+    }
+
+    /// Users trong `user_ids` đang trong giờ DND và cho phép hiện badge
+    pub async fn find_dnd_badges(
+        &self,
+        user_ids: &[Uuid],
+    ) -> Result<Vec<Uuid>, error::SystemError> {
+        if user_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.repo.find_dnd_badges(user_ids).await
+    }
+
+    /// Contact sync: tìm users đã đăng ký khớp với danh bạ (đã hash) của client
+    pub async fn lookup_contacts(
+        &self,
+        user_id: Uuid,
+        hashes: Vec<String>,
+    ) -> Result<Vec<UserResponse>, error::SystemError> {
+        let mut hashes: Vec<String> = hashes
+            .into_iter()
+            .map(|hash| hash.trim().to_ascii_lowercase())
+            .filter(|hash| hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+            .collect();
+        hashes.sort_unstable();
+        hashes.dedup();
+
+        if hashes.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let users = self.repo.find_by_contact_hashes(&hashes, &user_id).await?;
+
+        Ok(users.into_iter().map(UserResponse::from).collect())
+    }
+
+    /// Revoke một access token cho tới khi nó hết hạn (denylist theo jti)
+    pub async fn revoke_access_token(&self, claims: &Claims) -> Result<(), error::SystemError> {
+        let Some(jti) = claims.jti else {
+            return Ok(());
+        };
+
+        let now = chrono::Utc::now().timestamp() as u64;
+        if claims.exp <= now {
+            return Ok(());
+        }
+
+        let key = format!("revoked_token:{jti}");
+        self.cache.set(&key, &true, (claims.exp - now) as usize).await
+    }
+
+    /// Revoke tất cả token đã cấp cho user trước thời điểm hiện tại
+    pub async fn revoke_all_tokens(&self, user_id: Uuid) -> Result<(), error::SystemError> {
+        // Mốc tính theo mili giây để token cấp trong cùng giây trước khi revoke cũng bị chặn
+        let key = format!("token_cutoff_ms:{user_id}");
+        let now = chrono::Utc::now().timestamp_millis() as u64;
+        self.cache.set(&key, &now, ENV.refresh_token_expiration as usize).await
+    }
+
+    /// Kiểm tra token đã bị revoke (theo jti hoặc theo mốc revoke của user)
+    pub async fn is_token_revoked(&self, claims: &Claims) -> Result<bool, error::SystemError> {
+        if let Some(jti) = claims.jti {
+            if self.cache.exists(&format!("revoked_token:{jti}")).await? {
+                return Ok(true);
+            }
+        }
+
+        let cutoff = self.cache.get::<u64>(&format!("token_cutoff_ms:{}", claims.sub)).await?;
+        Ok(cutoff.is_some_and(|cutoff| issued_at_millis(claims) < cutoff))
+    }
+}
+
+/// Thời điểm cấp token (mili giây): lấy từ `jti` UUID v7 nếu có, ngược lại từ `iat`
+fn issued_at_millis(claims: &Claims) -> u64 {
+    claims
+        .jti
+        .and_then(|jti| jti.get_timestamp())
+        .map(|timestamp| {
+            let (secs, nanos) = timestamp.to_unix();
+            secs * 1000 + u64::from(nanos) / 1_000_000
+        })
+        .unwrap_or(claims.iat * 1000)
+}
+
+#[async_trait::async_trait]
+impl TokenRevocation for UserService {
+    async fn is_token_revoked(&self, claims: &Claims) -> Result<bool, error::SystemError> {
+        UserService::is_token_revoked(self, claims).await
+    }
+
+    async fn revoke_all_tokens(&self, user_id: Uuid) -> Result<(), error::SystemError> {
+        UserService::revoke_all_tokens(self, user_id).await
+    }
+}
+
+#[async_trait::async_trait]
+impl ActivityTracker for UserService {
+    async fn record_activity(&self, user_id: Uuid) {
+        UserService::record_activity(self, user_id).await
+    }
+}
+
+#[async_trait::async_trait]
+impl SenderResolver for UserService {
+    async fn resolve_sender(&self, user_id: Uuid) -> SenderInfo {
+        match self.get_by_id(user_id).await {
+            Ok(user) => SenderInfo {
+                _id: user_id,
+                display_name: user.display_name,
+                avatar_url: user.avatar_url,
+            },
+            Err(e) => {
+                tracing::warn!("Failed to resolve sender {}: {:?}", user_id, e);
+                SenderInfo::unknown(user_id)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix::Actor;
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+    use super::*;
+    use crate::{
+        configs::{cache::MemoryCache, mailer::LogMailer},
+        constants::init_test_env,
+        modules::{
+            audit::repository_mock::AuditLogRepositoryMock,
+            notification::{repository_mock::NotificationRepositoryMock, schema::NotificationKind},
+            report::repository_pg::ReportRepositoryPg,
+            user::repository_mock::UserRepositoryMock,
+        },
+    };
+
+    const PASSWORD: &str = "correct-password";
+
+    fn service(repo: &UserRepositoryMock) -> UserService {
+        service_with_cache(repo, Arc::default())
+    }
+
+    fn service_with_cache(repo: &UserRepositoryMock, cache: Arc<MemoryCache>) -> UserService {
+        let audit = AuditService::with_dependencies(
+            Arc::new(AuditLogRepositoryMock::default()),
+            Arc::new(NotificationRepositoryMock::default()),
+        );
+        service_with_audit(repo, cache, audit)
+    }
+
+    fn service_with_audit(
+        repo: &UserRepositoryMock,
+        cache: Arc<MemoryCache>,
+        audit: AuditService,
+    ) -> UserService {
+        init_test_env();
+        // Report repository chỉ được dùng khi nội dung bị flag, pool không bao giờ kết nối
+        let reports = ReportRepositoryPg::new(
+            PgPoolOptions::new().connect_lazy_with(PgConnectOptions::new()),
+        );
+
+        UserService::with_dependencies(
+            Arc::new(repo.clone()),
+            cache,
+            Arc::new(LogMailer),
+            Arc::new(WebSocketServer::new().start()),
+            LocalProfileCache::default(),
+            ContentFilter::from_env(Arc::new(reports)),
+            audit,
+        )
+    }
+
+    fn sign_up_model(username: &str) -> SignUpModel {
+        SignUpModel {
+            username: username.to_string(),
+            email: format!("{}@example.com", username.to_lowercase()),
+            password: PASSWORD.to_string(),
+            display_name: username.to_string(),
+        }
+    }
+
+    fn sign_in_model(username: &str, password: &str) -> SignInModel {
+        SignInModel { username: username.to_string(), password: password.to_string() }
+    }
+
+    #[actix_web::test]
+    async fn sign_up_rejects_taken_username_case_insensitively() {
+        let repo = UserRepositoryMock::default();
+        let service = service(&repo);
+
+        service.sign_up(sign_up_model("alice")).await.unwrap();
+        let err = service.sign_up(sign_up_model("Alice")).await.unwrap_err();
+
+        assert_eq!(err.code(), error::ErrorCode::Conflict);
+    }
+
+    #[actix_web::test]
+    async fn forgot_password_is_rate_limited_per_email() {
+        let repo = UserRepositoryMock::default();
+        let service = service(&repo);
+        service.sign_up(sign_up_model("carol")).await.unwrap();
+
+        service.forgot_password("carol@example.com").await.unwrap();
+        let err = service.forgot_password("Carol@Example.com").await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::RateLimited);
+
+        // Email chưa đăng ký cũng bị giới hạn như email đã đăng ký
+        service.forgot_password("nobody@example.com").await.unwrap();
+        let err = service.forgot_password("nobody@example.com").await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::RateLimited);
+    }
+
+    #[actix_web::test]
+    async fn sign_in_requires_verified_email() {
+        let repo = UserRepositoryMock::default();
+        let service = service(&repo);
+
+        let id = service.sign_up(sign_up_model("bob")).await.unwrap();
+        assert!(!repo.get(&id).unwrap().email_verified);
+
+        let err = service
+            .sign_in(sign_in_model("bob", PASSWORD), SignInContext::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::EmailNotVerified);
+    }
+
+    #[actix_web::test]
+    async fn sign_in_locks_username_after_max_failed_attempts() {
+        let repo = UserRepositoryMock::default();
+        let audit_log = AuditLogRepositoryMock::default();
+        let notifications = NotificationRepositoryMock::default();
+        let audit = AuditService::with_dependencies(
+            Arc::new(audit_log.clone()),
+            Arc::new(notifications.clone()),
+        );
+        let service = service_with_audit(&repo, Arc::default(), audit);
+        let id =
+            repo.insert(UserRepositoryMock::entity("carol", &hash_password(PASSWORD).unwrap()));
+
+        let max_attempts = settings().sign_in_max_attempts;
+        assert!(max_attempts > 0, "SIGN_IN_MAX_ATTEMPTS must be enabled for this test");
+
+        for attempt in 1..=max_attempts {
+            let err = service
+                .sign_in(sign_in_model("carol", "wrong-password"), SignInContext::default())
+                .await
+                .unwrap_err();
+            let expected = if attempt < max_attempts {
+                error::ErrorCode::Unauthorized
+            } else {
+                error::ErrorCode::AccountLocked
+            };
+            assert_eq!(err.code(), expected, "attempt {attempt}");
+        }
+
+        // Đang bị khóa thì mật khẩu đúng cũng bị từ chối
+        let err = service
+            .sign_in(sign_in_model("carol", PASSWORD), SignInContext::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::AccountLocked);
+
+        // Lockout đi qua audit log, không vào lịch sử đăng nhập
+        let entries = audit_log.entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, AuditAction::SignInLocked);
+        assert_eq!(entries[0].user_id, Some(id));
+        assert!(repo.sign_ins().is_empty());
+        assert_eq!(notifications.entries_of(&id)[0].kind, NotificationKind::AccountLocked);
+    }
+
+    #[actix_web::test]
+    async fn sign_in_records_successful_attempt() {
+        let repo = UserRepositoryMock::default();
+        let service = service(&repo);
+        let id = repo.insert(UserRepositoryMock::entity("dave", &hash_password(PASSWORD).unwrap()));
+
+        let (access_token, refresh_token) = service
+            .sign_in(sign_in_model("Dave", PASSWORD), SignInContext::default())
+            .await
+            .unwrap();
+
+        assert_eq!(Claims::decode(&access_token).unwrap().sub, id);
+        assert!(!refresh_token.is_empty());
+        assert_eq!(repo.sign_ins().len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn sign_in_from_new_ip_is_audited_and_notified() {
+        let repo = UserRepositoryMock::default();
+        let audit_log = AuditLogRepositoryMock::default();
+        let notifications = NotificationRepositoryMock::default();
+        let audit = AuditService::with_dependencies(
+            Arc::new(audit_log.clone()),
+            Arc::new(notifications.clone()),
+        );
+        let service = service_with_audit(&repo, Arc::default(), audit);
+        let id = repo.insert(UserRepositoryMock::entity("erin", &hash_password(PASSWORD).unwrap()));
+        let from = |ip_address: &str| SignInContext {
+            ip_address: Some(ip_address.to_string()),
+            user_agent: Some("test-agent".to_string()),
+        };
+
+        // Lần đăng nhập đầu tiên không có IP nào để so sánh
+        service.sign_in(sign_in_model("erin", PASSWORD), from("10.0.0.1")).await.unwrap();
+        service.sign_in(sign_in_model("erin", PASSWORD), from("10.0.0.1")).await.unwrap();
+        assert!(audit_log.entries().is_empty());
+
+        service.sign_in(sign_in_model("erin", PASSWORD), from("10.0.0.2")).await.unwrap();
+
+        let entries = audit_log.entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].user_id, Some(id));
+        assert_eq!(entries[0].action, AuditAction::NewSignInIp);
+        assert_eq!(entries[0].ip_address.as_deref(), Some("10.0.0.2"));
+
+        let notified = notifications.entries_of(&id);
+        assert_eq!(notified.len(), 1);
+        assert_eq!(notified[0].kind, NotificationKind::NewSignIn);
+        assert!(notified[0].body.contains("10.0.0.2"));
+    }
+
+    #[actix_web::test]
+    async fn update_publishes_profile_invalidation() {
+        let repo = UserRepositoryMock::default();
+        let cache = Arc::new(MemoryCache::default());
+        let service = service_with_cache(&repo, cache.clone());
+        let id = repo.insert(UserRepositoryMock::entity("carol", "unused-hash"));
+
+        let update = UpdateUserModel {
+            username: None,
+            email: None,
+            display_name: Some("Carol".to_string()),
+            avatar_url: None,
+            bio: None,
+            phone: None,
+            discoverable: None,
+        };
+        service.update(id, update).await.unwrap();
+
+        let published = cache.published();
+        assert_eq!(published.len(), 1);
+        let (channel, payload) = &published[0];
+        assert_eq!(channel, PROFILE_INVALIDATION_CHANNEL);
+        let invalidation: ProfileInvalidation = serde_json::from_slice(payload).unwrap();
+        assert_eq!(invalidation.user_id, id);
+        assert_eq!(invalidation.profile.unwrap().display_name, "Carol");
+    }
+
+    #[actix_web::test]
+    async fn revoke_all_tokens_covers_same_second_but_not_reissued_tokens() {
+        let repo = UserRepositoryMock::default();
+        let service = service(&repo);
+        let id = repo.insert(UserRepositoryMock::entity("frank", PASSWORD));
+
+        let (before, _) = service.issue_tokens(&id, &UserRole::User).await.unwrap();
+        actix_web::rt::time::sleep(std::time::Duration::from_millis(2)).await;
+        service.revoke_all_tokens(id).await.unwrap();
+        actix_web::rt::time::sleep(std::time::Duration::from_millis(2)).await;
+        let (after, _) = service.issue_tokens(&id, &UserRole::User).await.unwrap();
+
+        assert!(service.is_token_revoked(&Claims::decode(&before).unwrap()).await.unwrap());
+        assert!(!service.is_token_revoked(&Claims::decode(&after).unwrap()).await.unwrap());
+    }
+
+    #[actix_web::test]
+    async fn autocomplete_ranks_friends_first_and_excludes_viewer() {
+        let repo = UserRepositoryMock::default();
+        let service = service(&repo);
+        let viewer = repo.insert(UserRepositoryMock::entity("alex", PASSWORD));
+        repo.insert(UserRepositoryMock::entity("alice", PASSWORD));
+        let friend = repo.insert(UserRepositoryMock::entity("alfred", PASSWORD));
+        repo.insert(UserRepositoryMock::entity("bob", PASSWORD));
+        repo.add_friendship(viewer, friend);
+
+        let users = service.autocomplete_users(viewer, "@Al", 8).await.unwrap();
+
+        let usernames: Vec<&str> = users.iter().map(|user| user.username.as_str()).collect();
+        assert_eq!(usernames, ["alfred", "alice"]);
+    }
+
+    #[actix_web::test]
+    async fn dnd_schedule_validates_window_and_timezone() {
+        let repo = UserRepositoryMock::default();
+        let service = service(&repo);
+        let id = repo.insert(UserRepositoryMock::entity("erin", PASSWORD));
+        let schedule = |start: u32, end: u32, timezone: &str| DndSchedule {
+            start: chrono::NaiveTime::from_hms_opt(start, 0, 0).unwrap(),
+            end: chrono::NaiveTime::from_hms_opt(end, 0, 0).unwrap(),
+            timezone: timezone.to_string(),
+            show_badge: true,
+        };
+
+        let err = service.set_dnd(id, schedule(22, 22, "UTC")).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::BadRequest);
+        let err = service.set_dnd(id, schedule(22, 7, "Mars")).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::BadRequest);
+
+        service.set_dnd(id, schedule(22, 7, "Asia/Ho_Chi_Minh")).await.unwrap();
+        assert_eq!(service.get_dnd(id).await.unwrap().schedule.timezone, "Asia/Ho_Chi_Minh");
+
+        service.clear_dnd(id).await.unwrap();
+        let err = service.get_dnd(id).await.unwrap_err();
+        assert_eq!(err.code(), error::ErrorCode::NotFound);
+    }
 }
@@ -0,0 +1,358 @@
+/// Webhook Delivery
+///
+/// MessageService ghi một delivery cho mỗi webhook của conversation trong cùng
+/// transaction với message mới (`WebhookQueue::enqueue`), nên message đã commit thì
+/// webhook chắc chắn được gửi. Worker chạy nền claim các deliveries tới hạn và `POST`
+/// payload JSON tới từng endpoint với các headers:
+/// - `X-Webhook-Id`: ID của delivery, receiver dùng để bỏ trùng khi bị gửi lại
+/// - `X-Webhook-Timestamp`: unix timestamp lúc gửi
+/// - `X-Webhook-Signature`: `sha256=<hex>`, HMAC-SHA256 của `"{timestamp}.{body}"` với
+///   secret của webhook
+///
+/// Response 2xx là thành công; lỗi khác được retry với exponential backoff, quá
+/// `MAX_ATTEMPTS` lần thì delivery bị đánh dấu `failed`.
+///
+/// Host của endpoint được resolve lại lúc gửi (`PublicResolver`) và chỉ kết nối tới địa
+/// chỉ public, domain trỏ về mạng nội bộ sau khi tạo webhook cũng bị chặn.
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::{
+        message::schema::MessageEntity,
+        webhook::{model::ClaimedDelivery, repository::WebhookRepository},
+    },
+};
+
+/// Khoảng thời gian tối đa giữa hai lần quét (retry tới hạn / deliveries của instance khác)
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Số deliveries tối đa claim và gửi song song mỗi lần
+const BATCH_SIZE: i64 = 50;
+
+/// Thời gian giữ delivery đã claim, hết hạn thì instance khác được claim lại
+const LEASE: Duration = Duration::from_secs(60);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Số lần gửi tối đa của một delivery (kể cả lần đầu)
+const MAX_ATTEMPTS: i32 = 8;
+
+/// Backoff sau lần gửi lỗi thứ n là `BASE_BACKOFF_SECS * 2^(n-1)`, tối đa `MAX_BACKOFF_SECS`
+const BASE_BACKOFF_SECS: i64 = 10;
+const MAX_BACKOFF_SECS: i64 = 60 * 60;
+
+/// Event của payload gửi cho tin nhắn mới
+const MESSAGE_CREATED: &str = "message.created";
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    event: &'static str,
+    conversation_id: Uuid,
+    message: &'a MessageEntity,
+}
+
+/// Handle cho services: ghi deliveries trong transaction và đánh thức worker sau commit
+#[derive(Clone, Default)]
+pub struct WebhookQueue {
+    notify: Arc<Notify>,
+}
+
+impl WebhookQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tạo delivery cho mọi webhook của conversation, gọi trong transaction tạo message
+    pub async fn enqueue<'e, E>(
+        &self,
+        message: &MessageEntity,
+        tx: E,
+    ) -> Result<(), error::SystemError>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        let payload = serde_json::to_string(&WebhookPayload {
+            event: MESSAGE_CREATED,
+            conversation_id: message.conversation_id,
+            message,
+        })
+        .map_err(|e| error::SystemError::internal_error(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries (webhook_id, message_id, payload)
+            SELECT id, $2, $3::jsonb
+            FROM conversation_webhooks
+            WHERE conversation_id = $1
+            "#,
+        )
+        .bind(message.conversation_id)
+        .bind(message.id)
+        .bind(payload)
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Báo worker có deliveries mới, gọi sau khi transaction đã commit
+    pub fn wake(&self) {
+        self.notify.notify_one();
+    }
+}
+
+/// Gửi webhooks cho tới khi process dừng
+pub async fn run_webhook_worker<R>(queue: WebhookQueue, repo: Arc<R>)
+where
+    R: WebhookRepository + Send + Sync,
+{
+    // Không follow redirect để endpoint không thể chuyển request sang host nội bộ
+    let http = match reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .dns_resolver(Arc::new(PublicResolver))
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+    {
+        Ok(http) => http,
+        Err(e) => {
+            tracing::error!("Failed to build webhook HTTP client: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        tokio::select! {
+            _ = queue.notify.notified() => {}
+            _ = actix_web::rt::time::sleep(POLL_INTERVAL) => {}
+        }
+
+        loop {
+            match deliver_batch(repo.as_ref(), &http).await {
+                Ok(delivered) if delivered as i64 == BATCH_SIZE => continue,
+                Ok(_) => break,
+                Err(e) => {
+                    tracing::error!("Failed to deliver webhooks: {:?}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+async fn deliver_batch<R>(repo: &R, http: &reqwest::Client) -> Result<usize, error::SystemError>
+where
+    R: WebhookRepository + Send + Sync,
+{
+    let claimed = repo.claim_due(BATCH_SIZE, LEASE.as_secs() as i64).await?;
+    let count = claimed.len();
+
+    future::join_all(claimed.into_iter().map(|delivery| deliver(repo, http, delivery))).await;
+
+    Ok(count)
+}
+
+async fn deliver<R>(repo: &R, http: &reqwest::Client, delivery: ClaimedDelivery)
+where
+    R: WebhookRepository + Send + Sync,
+{
+    let result = match send(http, &delivery).await {
+        Ok(status) => repo.mark_delivered(&delivery.id, status).await,
+        Err((status, reason)) => {
+            let retry_at = (delivery.attempts < MAX_ATTEMPTS)
+                .then(|| chrono::Utc::now() + backoff(delivery.attempts));
+            if retry_at.is_none() {
+                tracing::warn!(
+                    "Webhook {} delivery {} failed after {} attempts: {}",
+                    delivery.webhook_id,
+                    delivery.id,
+                    delivery.attempts,
+                    reason
+                );
+            }
+            repo.mark_failed(&delivery.id, status, &reason, retry_at).await
+        }
+    };
+
+    if let Err(e) = result {
+        tracing::error!("Failed to record webhook delivery {}: {:?}", delivery.id, e);
+    }
+}
+
+/// `Ok(status)` khi endpoint trả 2xx, ngược lại `Err((status, lý do))`
+async fn send(
+    http: &reqwest::Client,
+    delivery: &ClaimedDelivery,
+) -> Result<i32, (Option<i32>, String)> {
+    // Host là IP literal không đi qua resolver
+    if literal_ip(&delivery.url).is_some_and(|ip| !is_public_ip(ip)) {
+        return Err((None, "Endpoint resolves to a non-public address".to_string()));
+    }
+
+    let timestamp = chrono::Utc::now().timestamp();
+    let signature = sign(&delivery.secret, timestamp, &delivery.payload);
+
+    let response = http
+        .post(&delivery.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header("X-Webhook-Id", delivery.id.to_string())
+        .header("X-Webhook-Timestamp", timestamp.to_string())
+        .header("X-Webhook-Signature", format!("sha256={signature}"))
+        .body(delivery.payload.clone())
+        .send()
+        .await
+        .map_err(|e| (None, e.to_string()))?;
+
+    let status = response.status();
+    if status.is_success() {
+        Ok(status.as_u16() as i32)
+    } else {
+        Err((Some(status.as_u16() as i32), format!("Endpoint responded with {status}")))
+    }
+}
+
+/// DNS resolver chỉ trả về địa chỉ public của host
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public_ip(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// IP của URL khi host là IP literal
+fn literal_ip(url: &str) -> Option<IpAddr> {
+    let parsed = reqwest::Url::parse(url).ok()?;
+    parsed.host_str()?.trim_matches(['[', ']']).parse().ok()
+}
+
+/// Địa chỉ có thể route ra internet: loại private, loopback, link-local, unique local,
+/// multicast và IPv4-mapped của các dải đó
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || a == 0
+                // 100.64.0.0/10 shared address space (CGNAT)
+                || (a == 100 && b & 0xc0 == 64)
+                // 198.18.0.0/15 benchmarking
+                || (a == 198 && b & 0xfe == 18))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(mapped));
+            }
+            let segments = ip.segments();
+            let first = segments[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // fc00::/7 unique local
+                || first & 0xfe00 == 0xfc00
+                // fe80::/10 link-local
+                || first & 0xffc0 == 0xfe80
+                // 64:ff9b::/96 NAT64, 2002::/16 6to4 và 2001::/32 Teredo nhúng địa chỉ IPv4
+                || segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0]
+                || first == 0x2002
+                || (first == 0x2001 && segments[1] == 0))
+        }
+    }
+}
+
+/// Chữ ký hex HMAC-SHA256 của `"{timestamp}.{body}"`
+fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(format!("{timestamp}.").as_bytes());
+    mac.update(body.as_bytes());
+
+    mac.finalize().into_bytes().iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn backoff(attempts: i32) -> chrono::Duration {
+    let exponent = (attempts - 1).clamp(0, 16) as u32;
+    chrono::Duration::seconds((BASE_BACKOFF_SECS << exponent).min(MAX_BACKOFF_SECS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_produces_hex_hmac_of_timestamp_and_body() {
+        let signature = sign("whsec_test", 1_700_000_000, r#"{"event":"message.created"}"#);
+
+        assert_eq!(signature, "9884eb2fcc09ffc10f00127fff0a0c5686da2fef61d0363442271c6dfa1917eb");
+        assert_ne!(signature, sign("whsec_other", 1_700_000_000, r#"{"event":"message.created"}"#));
+        assert_ne!(signature, sign("whsec_test", 1_700_000_001, r#"{"event":"message.created"}"#));
+    }
+
+    #[test]
+    fn is_public_ip_rejects_internal_ranges() {
+        for ip in [
+            "10.0.0.1",
+            "127.0.0.1",
+            "169.254.169.254",
+            "192.168.1.1",
+            "0.0.0.0",
+            "::1",
+            "fc00::1",
+            "fd12:3456::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::ffff:10.0.0.1",
+            "100.64.0.1",
+            "100.127.255.254",
+            "198.18.0.1",
+            "198.19.255.1",
+            "64:ff9b::7f00:1",
+            "2002:7f00:1::1",
+            "2001:0:4136:e378::1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{ip} should not be public");
+        }
+
+        for ip in [
+            "93.184.216.34",
+            "100.128.0.1",
+            "198.20.0.1",
+            "2606:2800:220:1::1",
+            "2001:4860:4860::8888",
+            "::ffff:93.184.216.34",
+        ] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{ip} should be public");
+        }
+    }
+
+    #[test]
+    fn literal_ip_only_matches_ip_hosts() {
+        assert_eq!(literal_ip("https://[::1]/hook"), Some("::1".parse().unwrap()));
+        assert_eq!(literal_ip("https://10.0.0.1/hook"), Some("10.0.0.1".parse().unwrap()));
+        assert_eq!(literal_ip("https://hooks.example.com/hook"), None);
+    }
+}
@@ -0,0 +1,204 @@
+use actix_web::{delete, get, post, web, HttpRequest};
+use uuid::Uuid;
+
+use crate::{
+    api::{error, success},
+    middlewares::get_extensions,
+    modules::{
+        message::{schema::MessageEntity, service::MessageService},
+        webhook::{
+            model::{
+                CreateIncomingWebhookModel, CreateWebhookModel, CreatedIncomingWebhook,
+                CreatedWebhook, IncomingWebhookPayload, WebhookWithDeliveries,
+            },
+            schema::IncomingWebhookEntity,
+            service::WebhookService,
+        },
+    },
+    utils::{Claims, ValidatedJson},
+};
+
+#[utoipa::path(
+    tag = "conversations",
+    path = "/{conversation_id}/webhooks",
+    params(("conversation_id" = Uuid, Path, description = "Conversation ID của group")),
+    request_body = CreateWebhookModel,
+    responses(
+        (
+            status = 201,
+            description = "`secret` dùng để verify `X-Webhook-Signature`, chỉ được trả về một lần",
+            body = success::SuccessData<CreatedWebhook>
+        ),
+        (status = 403, description = "Không phải người tạo group", body = error::ErrorBody)
+    )
+)]
+#[post("")]
+pub async fn create_webhook(
+    webhook_service: web::Data<WebhookService>,
+    conversation_id: web::Path<Uuid>,
+    ValidatedJson(body): ValidatedJson<CreateWebhookModel>,
+    req: HttpRequest,
+) -> Result<success::Success<CreatedWebhook>, error::Error> {
+    let claims = get_extensions::<Claims>(&req)?;
+    let webhook = webhook_service
+        .create_webhook(*conversation_id, claims.sub, &claims.role, &body.url)
+        .await?;
+
+    Ok(success::Success::created(Some(webhook)).message("Webhook created successfully"))
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    path = "/{conversation_id}/webhooks",
+    params(("conversation_id" = Uuid, Path, description = "Conversation ID của group")),
+    responses(
+        (status = 200, body = success::SuccessData<Vec<WebhookWithDeliveries>>),
+        (status = 403, description = "Không phải người tạo group", body = error::ErrorBody)
+    )
+)]
+#[get("")]
+pub async fn list_webhooks(
+    webhook_service: web::Data<WebhookService>,
+    conversation_id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> Result<success::Success<Vec<WebhookWithDeliveries>>, error::Error> {
+    let claims = get_extensions::<Claims>(&req)?;
+    let webhooks =
+        webhook_service.list_webhooks(*conversation_id, claims.sub, &claims.role).await?;
+
+    Ok(success::Success::ok(Some(webhooks)).message("Webhooks retrieved successfully"))
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    path = "/{conversation_id}/webhooks/{webhook_id}",
+    params(
+        ("conversation_id" = Uuid, Path, description = "Conversation ID của group"),
+        ("webhook_id" = Uuid, Path, description = "Webhook ID")
+    ),
+    responses(
+        (status = 204, description = "Webhook đã bị xóa cùng các deliveries đang chờ"),
+        (status = 404, description = "Không tìm thấy webhook", body = error::ErrorBody)
+    )
+)]
+#[delete("/{webhook_id}")]
+pub async fn delete_webhook(
+    webhook_service: web::Data<WebhookService>,
+    path: web::Path<(Uuid, Uuid)>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let claims = get_extensions::<Claims>(&req)?;
+    let (conversation_id, webhook_id) = path.into_inner();
+    webhook_service.delete_webhook(conversation_id, webhook_id, claims.sub, &claims.role).await?;
+
+    Ok(success::Success::no_content())
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    path = "/{conversation_id}/incoming-webhooks",
+    params(("conversation_id" = Uuid, Path, description = "Conversation ID của group")),
+    request_body = CreateIncomingWebhookModel,
+    responses(
+        (
+            status = 201,
+            description = "Đăng tin nhắn bằng `POST /api/v1/webhooks/{token}`, `token` chỉ được trả về một lần",
+            body = success::SuccessData<CreatedIncomingWebhook>
+        ),
+        (status = 403, description = "Không phải người tạo group", body = error::ErrorBody)
+    )
+)]
+#[post("")]
+pub async fn create_incoming_webhook(
+    webhook_service: web::Data<WebhookService>,
+    conversation_id: web::Path<Uuid>,
+    ValidatedJson(body): ValidatedJson<CreateIncomingWebhookModel>,
+    req: HttpRequest,
+) -> Result<success::Success<CreatedIncomingWebhook>, error::Error> {
+    let claims = get_extensions::<Claims>(&req)?;
+    let webhook = webhook_service
+        .create_incoming_webhook(*conversation_id, claims.sub, &claims.role, &body.name)
+        .await?;
+
+    Ok(success::Success::created(Some(webhook)).message("Incoming webhook created successfully"))
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    path = "/{conversation_id}/incoming-webhooks",
+    params(("conversation_id" = Uuid, Path, description = "Conversation ID của group")),
+    responses(
+        (status = 200, body = success::SuccessData<Vec<IncomingWebhookEntity>>),
+        (status = 403, description = "Không phải người tạo group", body = error::ErrorBody)
+    )
+)]
+#[get("")]
+pub async fn list_incoming_webhooks(
+    webhook_service: web::Data<WebhookService>,
+    conversation_id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> Result<success::Success<Vec<IncomingWebhookEntity>>, error::Error> {
+    let claims = get_extensions::<Claims>(&req)?;
+    let webhooks =
+        webhook_service.list_incoming_webhooks(*conversation_id, claims.sub, &claims.role).await?;
+
+    Ok(success::Success::ok(Some(webhooks)).message("Incoming webhooks retrieved successfully"))
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    path = "/{conversation_id}/incoming-webhooks/{webhook_id}",
+    params(
+        ("conversation_id" = Uuid, Path, description = "Conversation ID của group"),
+        ("webhook_id" = Uuid, Path, description = "Incoming webhook ID")
+    ),
+    responses(
+        (status = 204, description = "Incoming webhook đã bị revoke"),
+        (status = 404, description = "Không tìm thấy incoming webhook", body = error::ErrorBody)
+    )
+)]
+#[delete("/{webhook_id}")]
+pub async fn revoke_incoming_webhook(
+    webhook_service: web::Data<WebhookService>,
+    path: web::Path<(Uuid, Uuid)>,
+    req: HttpRequest,
+) -> Result<success::Success<()>, error::Error> {
+    let claims = get_extensions::<Claims>(&req)?;
+    let (conversation_id, webhook_id) = path.into_inner();
+    webhook_service
+        .revoke_incoming_webhook(conversation_id, webhook_id, claims.sub, &claims.role)
+        .await?;
+
+    Ok(success::Success::no_content())
+}
+
+#[utoipa::path(
+    tag = "conversations",
+    path = "/{token}",
+    params(("token" = String, Path, description = "Token của incoming webhook")),
+    request_body = IncomingWebhookPayload,
+    security(()),
+    responses(
+        (status = 200, body = success::SuccessData<MessageEntity>),
+        (
+            status = 404,
+            description = "Token không hợp lệ, đã bị revoke hoặc người tạo đã rời group",
+            body = error::ErrorBody
+        )
+    )
+)]
+#[post("/{token}")]
+pub async fn post_incoming_webhook(
+    webhook_service: web::Data<WebhookService>,
+    message_service: web::Data<MessageService>,
+    token: web::Path<String>,
+    ValidatedJson(body): ValidatedJson<IncomingWebhookPayload>,
+) -> Result<success::Success<MessageEntity>, error::Error> {
+    let (webhook, sender) = webhook_service.resolve_incoming(&token, &body).await?;
+
+    let message = message_service
+        .send_webhook_message(webhook.created_by, body.text, webhook.conversation_id, sender)
+        .await?;
+
+    Ok(success::Success::ok(Some(message)).message("Message posted successfully"))
+}
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::modules::webhook::schema::{
+    IncomingWebhookEntity, WebhookDeliveryEntity, WebhookEntity,
+};
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct CreateWebhookModel {
+    /// Endpoint HTTPS nhận `POST` cho mỗi tin nhắn mới
+    #[validate(length(
+        min = 1,
+        max = 2048,
+        message = "URL must be between 1 and 2048 characters"
+    ))]
+    pub url: String,
+}
+
+/// Webhook vừa tạo, `secret` chỉ được trả về một lần
+#[derive(Serialize, ToSchema)]
+pub struct CreatedWebhook {
+    pub secret: String,
+    #[serde(flatten)]
+    pub webhook: WebhookEntity,
+}
+
+/// Webhook kèm các lần gửi gần nhất
+#[derive(Serialize, ToSchema)]
+pub struct WebhookWithDeliveries {
+    #[serde(flatten)]
+    pub webhook: WebhookEntity,
+    pub recent_deliveries: Vec<WebhookDeliveryEntity>,
+}
+
+/// Quyền của user với group, dùng để kiểm tra quản lý webhooks
+#[derive(sqlx::FromRow)]
+pub struct GroupAccess {
+    pub created_by: Uuid,
+    pub is_member: bool,
+}
+
+/// Delivery đã được worker claim, kèm endpoint và khóa ký
+#[derive(sqlx::FromRow)]
+pub struct ClaimedDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub attempts: i32,
+    pub payload: String,
+    pub url: String,
+    pub secret: String,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct CreateIncomingWebhookModel {
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    pub name: String,
+}
+
+/// Incoming webhook vừa tạo, `token` (phần cuối của URL) chỉ được trả về một lần
+#[derive(Serialize, ToSchema)]
+pub struct CreatedIncomingWebhook {
+    pub token: String,
+    #[serde(flatten)]
+    pub webhook: IncomingWebhookEntity,
+}
+
+/// Payload của `POST /webhooks/{token}`, tương tự Slack incoming webhooks
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct IncomingWebhookPayload {
+    #[validate(length(
+        min = 1,
+        max = 5000,
+        message = "Text must be between 1 and 5000 characters"
+    ))]
+    pub text: String,
+    /// Tên hiển thị thay cho người tạo webhook
+    #[validate(length(
+        min = 1,
+        max = 80,
+        message = "Username must be between 1 and 80 characters"
+    ))]
+    pub username: Option<String>,
+    /// Avatar hiển thị (HTTPS) thay cho avatar của người tạo webhook
+    #[validate(length(
+        min = 1,
+        max = 2048,
+        message = "Avatar URL must be between 1 and 2048 characters"
+    ))]
+    pub avatar_url: Option<String>,
+}
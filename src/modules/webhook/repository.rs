@@ -0,0 +1,100 @@
+use uuid::Uuid;
+
+use crate::api::error;
+use crate::modules::webhook::model::{ClaimedDelivery, GroupAccess};
+use crate::modules::webhook::schema::{
+    IncomingWebhookEntity, WebhookDeliveryEntity, WebhookEntity,
+};
+
+#[async_trait::async_trait]
+pub trait WebhookRepository {
+    /// Người tạo group và membership của user, `None` nếu không phải group
+    async fn find_group_access(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<Option<GroupAccess>, error::SystemError>;
+
+    async fn count_by_conversation(
+        &self,
+        conversation_id: &Uuid,
+    ) -> Result<i64, error::SystemError>;
+
+    async fn create(
+        &self,
+        conversation_id: &Uuid,
+        url: &str,
+        secret: &str,
+        created_by: &Uuid,
+    ) -> Result<WebhookEntity, error::SystemError>;
+
+    async fn find_by_conversation(
+        &self,
+        conversation_id: &Uuid,
+    ) -> Result<Vec<WebhookEntity>, error::SystemError>;
+
+    /// Tối đa `per_webhook` deliveries mới nhất của mỗi webhook
+    async fn find_recent_deliveries(
+        &self,
+        webhook_ids: &[Uuid],
+        per_webhook: i64,
+    ) -> Result<Vec<WebhookDeliveryEntity>, error::SystemError>;
+
+    async fn delete(&self, conversation_id: &Uuid, id: &Uuid) -> Result<bool, error::SystemError>;
+
+    /// Claim các deliveries tới hạn và tăng `attempts`. Delivery được lease `lease_secs`
+    /// giây: worker crash giữa chừng thì delivery được claim lại sau khi lease hết hạn
+    async fn claim_due(
+        &self,
+        limit: i64,
+        lease_secs: i64,
+    ) -> Result<Vec<ClaimedDelivery>, error::SystemError>;
+
+    async fn mark_delivered(
+        &self,
+        id: &Uuid,
+        response_status: i32,
+    ) -> Result<(), error::SystemError>;
+
+    /// Ghi nhận lần gửi lỗi, `retry_at` là `None` khi đã hết số lần retry
+    async fn mark_failed(
+        &self,
+        id: &Uuid,
+        response_status: Option<i32>,
+        error: &str,
+        retry_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<(), error::SystemError>;
+
+    async fn count_incoming_by_conversation(
+        &self,
+        conversation_id: &Uuid,
+    ) -> Result<i64, error::SystemError>;
+
+    /// Lưu incoming webhook, chỉ hash (SHA-256) của token được lưu
+    async fn create_incoming(
+        &self,
+        conversation_id: &Uuid,
+        name: &str,
+        token_hash: &str,
+        token_prefix: &str,
+        created_by: &Uuid,
+    ) -> Result<IncomingWebhookEntity, error::SystemError>;
+
+    async fn find_incoming_by_conversation(
+        &self,
+        conversation_id: &Uuid,
+    ) -> Result<Vec<IncomingWebhookEntity>, error::SystemError>;
+
+    async fn revoke_incoming(
+        &self,
+        conversation_id: &Uuid,
+        id: &Uuid,
+    ) -> Result<bool, error::SystemError>;
+
+    /// Incoming webhook của token còn hiệu lực (chưa revoke, người tạo vẫn là thành viên
+    /// của group), đồng thời cập nhật `last_used_at`
+    async fn authenticate_incoming(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<IncomingWebhookEntity>, error::SystemError>;
+}
@@ -0,0 +1,335 @@
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::webhook::{
+        model::{ClaimedDelivery, GroupAccess},
+        repository::WebhookRepository,
+        schema::{IncomingWebhookEntity, WebhookDeliveryEntity, WebhookEntity},
+    },
+};
+
+#[derive(Clone)]
+pub struct WebhookRepositoryPg {
+    pool: sqlx::PgPool,
+}
+
+impl WebhookRepositoryPg {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl WebhookRepository for WebhookRepositoryPg {
+    async fn find_group_access(
+        &self,
+        conversation_id: &Uuid,
+        user_id: &Uuid,
+    ) -> Result<Option<GroupAccess>, error::SystemError> {
+        let access = sqlx::query_as::<_, GroupAccess>(
+            r#"
+            SELECT
+                g.created_by,
+                EXISTS (
+                    SELECT 1 FROM participants p
+                    WHERE p.conversation_id = g.conversation_id
+                    AND p.user_id = $2
+                    AND p.status = 'active'
+                    AND p.deleted_at IS NULL
+                ) AS is_member
+            FROM group_conversations g
+            WHERE g.conversation_id = $1
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(access)
+    }
+
+    async fn count_by_conversation(
+        &self,
+        conversation_id: &Uuid,
+    ) -> Result<i64, error::SystemError> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM conversation_webhooks WHERE conversation_id = $1",
+        )
+        .bind(conversation_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn create(
+        &self,
+        conversation_id: &Uuid,
+        url: &str,
+        secret: &str,
+        created_by: &Uuid,
+    ) -> Result<WebhookEntity, error::SystemError> {
+        let webhook = sqlx::query_as::<_, WebhookEntity>(
+            r#"
+            INSERT INTO conversation_webhooks (conversation_id, url, secret, created_by)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(url)
+        .bind(secret)
+        .bind(created_by)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(webhook)
+    }
+
+    async fn find_by_conversation(
+        &self,
+        conversation_id: &Uuid,
+    ) -> Result<Vec<WebhookEntity>, error::SystemError> {
+        let webhooks = sqlx::query_as::<_, WebhookEntity>(
+            "SELECT * FROM conversation_webhooks WHERE conversation_id = $1 ORDER BY created_at",
+        )
+        .bind(conversation_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(webhooks)
+    }
+
+    async fn find_recent_deliveries(
+        &self,
+        webhook_ids: &[Uuid],
+        per_webhook: i64,
+    ) -> Result<Vec<WebhookDeliveryEntity>, error::SystemError> {
+        let deliveries = sqlx::query_as::<_, WebhookDeliveryEntity>(
+            r#"
+            SELECT d.id, d.webhook_id, d.message_id, d.status, d.attempts, d.next_attempt_at,
+                   d.response_status, d.last_error, d.created_at, d.delivered_at
+            FROM unnest($1::uuid[]) AS w(id)
+            CROSS JOIN LATERAL (
+                SELECT * FROM webhook_deliveries
+                WHERE webhook_id = w.id
+                ORDER BY created_at DESC
+                LIMIT $2
+            ) d
+            ORDER BY d.created_at DESC
+            "#,
+        )
+        .bind(webhook_ids)
+        .bind(per_webhook)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(deliveries)
+    }
+
+    async fn delete(&self, conversation_id: &Uuid, id: &Uuid) -> Result<bool, error::SystemError> {
+        let rows =
+            sqlx::query("DELETE FROM conversation_webhooks WHERE id = $1 AND conversation_id = $2")
+                .bind(id)
+                .bind(conversation_id)
+                .execute(&self.pool)
+                .await?
+                .rows_affected();
+
+        Ok(rows > 0)
+    }
+
+    async fn claim_due(
+        &self,
+        limit: i64,
+        lease_secs: i64,
+    ) -> Result<Vec<ClaimedDelivery>, error::SystemError> {
+        let claimed = sqlx::query_as::<_, ClaimedDelivery>(
+            r#"
+            UPDATE webhook_deliveries d
+            SET attempts = d.attempts + 1,
+                next_attempt_at = NOW() + make_interval(secs => $2)
+            FROM conversation_webhooks w
+            WHERE d.id IN (
+                SELECT id FROM webhook_deliveries
+                WHERE status = 'pending'
+                AND next_attempt_at <= NOW()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            AND w.id = d.webhook_id
+            RETURNING d.id, d.webhook_id, d.attempts, d.payload::text AS payload, w.url, w.secret
+            "#,
+        )
+        .bind(limit)
+        .bind(lease_secs as f64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(claimed)
+    }
+
+    async fn mark_delivered(
+        &self,
+        id: &Uuid,
+        response_status: i32,
+    ) -> Result<(), error::SystemError> {
+        sqlx::query(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'delivered',
+                response_status = $2,
+                last_error = NULL,
+                delivered_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(response_status)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn mark_failed(
+        &self,
+        id: &Uuid,
+        response_status: Option<i32>,
+        error: &str,
+        retry_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<(), error::SystemError> {
+        sqlx::query(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = CASE WHEN $4::timestamptz IS NULL
+                              THEN 'failed'::webhook_delivery_status
+                              ELSE 'pending'::webhook_delivery_status END,
+                response_status = $2,
+                last_error = LEFT($3, 500),
+                next_attempt_at = COALESCE($4, next_attempt_at)
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(response_status)
+        .bind(error)
+        .bind(retry_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn count_incoming_by_conversation(
+        &self,
+        conversation_id: &Uuid,
+    ) -> Result<i64, error::SystemError> {
+        let count = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM incoming_webhooks
+            WHERE conversation_id = $1
+            AND revoked_at IS NULL
+            "#,
+        )
+        .bind(conversation_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn create_incoming(
+        &self,
+        conversation_id: &Uuid,
+        name: &str,
+        token_hash: &str,
+        token_prefix: &str,
+        created_by: &Uuid,
+    ) -> Result<IncomingWebhookEntity, error::SystemError> {
+        let webhook = sqlx::query_as::<_, IncomingWebhookEntity>(
+            r#"
+            INSERT INTO incoming_webhooks (conversation_id, name, token_prefix, token_hash, created_by)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(name)
+        .bind(token_prefix)
+        .bind(token_hash)
+        .bind(created_by)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(webhook)
+    }
+
+    async fn find_incoming_by_conversation(
+        &self,
+        conversation_id: &Uuid,
+    ) -> Result<Vec<IncomingWebhookEntity>, error::SystemError> {
+        let webhooks = sqlx::query_as::<_, IncomingWebhookEntity>(
+            "SELECT * FROM incoming_webhooks WHERE conversation_id = $1 ORDER BY created_at DESC",
+        )
+        .bind(conversation_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(webhooks)
+    }
+
+    async fn revoke_incoming(
+        &self,
+        conversation_id: &Uuid,
+        id: &Uuid,
+    ) -> Result<bool, error::SystemError> {
+        let rows = sqlx::query(
+            r#"
+            UPDATE incoming_webhooks SET revoked_at = NOW()
+            WHERE id = $1
+            AND conversation_id = $2
+            AND revoked_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(conversation_id)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows > 0)
+    }
+
+    async fn authenticate_incoming(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<IncomingWebhookEntity>, error::SystemError> {
+        let webhook = sqlx::query_as::<_, IncomingWebhookEntity>(
+            r#"
+            UPDATE incoming_webhooks w SET last_used_at = NOW()
+            WHERE w.token_hash = $1
+            AND w.revoked_at IS NULL
+            AND EXISTS (
+                SELECT 1 FROM participants p
+                JOIN users u ON u.id = p.user_id
+                WHERE p.conversation_id = w.conversation_id
+                AND p.user_id = w.created_by
+                AND p.status = 'active'
+                AND p.deleted_at IS NULL
+                AND u.deleted_at IS NULL
+                AND u.banned_at IS NULL
+            )
+            RETURNING w.*
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(webhook)
+    }
+}
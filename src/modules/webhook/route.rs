@@ -0,0 +1,42 @@
+use crate::modules::webhook::handle::*;
+use actix_web::web::{scope, ServiceConfig};
+use utoipa::OpenApi;
+
+/// Phải được configure trước scope `/conversations` của module conversation
+/// (scope không fall through)
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        scope("/conversations/{conversation_id}/webhooks")
+            .service(create_webhook)
+            .service(list_webhooks)
+            .service(delete_webhook),
+    )
+    .service(
+        scope("/conversations/{conversation_id}/incoming-webhooks")
+            .service(create_incoming_webhook)
+            .service(list_incoming_webhooks)
+            .service(revoke_incoming_webhook),
+    );
+}
+
+/// Endpoint public cho incoming webhooks, xác thực bằng token trong path
+pub fn public_api_configure(cfg: &mut ServiceConfig) {
+    cfg.service(scope("/webhooks").service(post_incoming_webhook));
+}
+
+/// OpenAPI paths của `configure` (dưới `/conversations`)
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_webhook,
+    list_webhooks,
+    delete_webhook,
+    create_incoming_webhook,
+    list_incoming_webhooks,
+    revoke_incoming_webhook
+))]
+pub struct WebhookApiDoc;
+
+/// OpenAPI paths của `public_api_configure` (scope `/webhooks`)
+#[derive(OpenApi)]
+#[openapi(paths(post_incoming_webhook))]
+pub struct IncomingWebhookApiDoc;
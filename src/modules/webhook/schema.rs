@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::{FromRow, Type};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, PartialEq, Clone, Copy, Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "webhook_delivery_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookDeliveryStatus {
+    /// Chờ gửi hoặc chờ retry
+    Pending,
+    Delivered,
+    /// Hết số lần retry
+    Failed,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct WebhookEntity {
+    pub id: Uuid,
+    pub conversation_id: Uuid,
+    pub url: String,
+    pub created_by: Option<Uuid>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct WebhookDeliveryEntity {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub message_id: Uuid,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: chrono::DateTime<chrono::Utc>,
+    /// HTTP status của lần gửi gần nhất
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub delivered_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Incoming webhook: endpoint public cho phép service bên ngoài đăng tin nhắn vào group
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct IncomingWebhookEntity {
+    pub id: Uuid,
+    pub conversation_id: Uuid,
+    pub name: String,
+    /// Phần đầu của token để nhận diện, token đầy đủ chỉ được trả về lúc tạo
+    pub token_prefix: String,
+    /// Người tạo, tin nhắn được đăng dưới tên người này (có thể override)
+    pub created_by: Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
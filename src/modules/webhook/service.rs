@@ -0,0 +1,310 @@
+/// Webhook Service
+///
+/// Quản lý webhooks của group (chỉ người tạo group hoặc Admin):
+/// - Outgoing: endpoint HTTPS nhận mọi tin nhắn mới của group, việc gửi chạy nền
+///   trong `delivery`
+/// - Incoming: URL bí mật `POST /webhooks/{token}` cho service bên ngoài đăng tin nhắn
+///   vào group dưới tên người tạo webhook (có thể override tên / avatar)
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    api::error,
+    modules::{
+        message::model::SenderOverride,
+        user::schema::UserRole,
+        webhook::{
+            delivery::is_public_ip,
+            model::{
+                CreatedIncomingWebhook, CreatedWebhook, IncomingWebhookPayload,
+                WebhookWithDeliveries,
+            },
+            repository::WebhookRepository,
+            schema::IncomingWebhookEntity,
+        },
+    },
+    utils::{hash_token, random_token},
+};
+
+/// Số webhooks tối đa của một conversation
+const MAX_WEBHOOKS_PER_CONVERSATION: i64 = 5;
+
+/// Số deliveries gần nhất trả về cho mỗi webhook
+const RECENT_DELIVERIES: i64 = 20;
+
+const SECRET_PREFIX: &str = "whsec_";
+const SECRET_RANDOM_LEN: usize = 32;
+
+/// Tiền tố token của incoming webhooks, giúp nhận diện token bị lộ (secret scanning)
+const INCOMING_TOKEN_PREFIX: &str = "whin_";
+const INCOMING_TOKEN_RANDOM_LEN: usize = 40;
+
+/// Số ký tự đầu của token được lưu để hiển thị
+const INCOMING_TOKEN_DISPLAY_LEN: usize = 12;
+
+#[derive(Clone)]
+pub struct WebhookService {
+    repo: Arc<dyn WebhookRepository + Send + Sync>,
+}
+
+impl WebhookService {
+    pub fn with_dependencies(repo: Arc<dyn WebhookRepository + Send + Sync>) -> Self {
+        WebhookService { repo }
+    }
+
+    pub async fn create_webhook(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        role: &UserRole,
+        url: &str,
+    ) -> Result<CreatedWebhook, error::SystemError> {
+        self.ensure_group_admin(conversation_id, user_id, role).await?;
+
+        let url = validate_url(url, "Webhook URL")?;
+
+        if self.repo.count_by_conversation(&conversation_id).await? >= MAX_WEBHOOKS_PER_CONVERSATION
+        {
+            return Err(error::SystemError::bad_request(format!(
+                "A conversation cannot have more than {} webhooks",
+                MAX_WEBHOOKS_PER_CONVERSATION
+            )));
+        }
+
+        let secret = format!("{SECRET_PREFIX}{}", random_token(SECRET_RANDOM_LEN));
+
+        let webhook = self.repo.create(&conversation_id, &url, &secret, &user_id).await?;
+
+        Ok(CreatedWebhook { secret, webhook })
+    }
+
+    /// Webhooks của group kèm trạng thái các lần gửi gần nhất
+    pub async fn list_webhooks(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        role: &UserRole,
+    ) -> Result<Vec<WebhookWithDeliveries>, error::SystemError> {
+        self.ensure_group_admin(conversation_id, user_id, role).await?;
+
+        let webhooks = self.repo.find_by_conversation(&conversation_id).await?;
+        let ids: Vec<Uuid> = webhooks.iter().map(|webhook| webhook.id).collect();
+
+        let mut deliveries: HashMap<Uuid, Vec<_>> = HashMap::new();
+        for delivery in self.repo.find_recent_deliveries(&ids, RECENT_DELIVERIES).await? {
+            deliveries.entry(delivery.webhook_id).or_default().push(delivery);
+        }
+
+        Ok(webhooks
+            .into_iter()
+            .map(|webhook| WebhookWithDeliveries {
+                recent_deliveries: deliveries.remove(&webhook.id).unwrap_or_default(),
+                webhook,
+            })
+            .collect())
+    }
+
+    pub async fn delete_webhook(
+        &self,
+        conversation_id: Uuid,
+        webhook_id: Uuid,
+        user_id: Uuid,
+        role: &UserRole,
+    ) -> Result<(), error::SystemError> {
+        self.ensure_group_admin(conversation_id, user_id, role).await?;
+
+        if !self.repo.delete(&conversation_id, &webhook_id).await? {
+            return Err(error::SystemError::not_found("Webhook not found"));
+        }
+
+        Ok(())
+    }
+
+    pub async fn create_incoming_webhook(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        role: &UserRole,
+        name: &str,
+    ) -> Result<CreatedIncomingWebhook, error::SystemError> {
+        self.ensure_group_admin(conversation_id, user_id, role).await?;
+
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(error::SystemError::bad_request("Name cannot be blank"));
+        }
+
+        if self.repo.count_incoming_by_conversation(&conversation_id).await?
+            >= MAX_WEBHOOKS_PER_CONVERSATION
+        {
+            return Err(error::SystemError::bad_request(format!(
+                "A conversation cannot have more than {} incoming webhooks",
+                MAX_WEBHOOKS_PER_CONVERSATION
+            )));
+        }
+
+        let token = format!("{INCOMING_TOKEN_PREFIX}{}", random_token(INCOMING_TOKEN_RANDOM_LEN));
+        let webhook = self
+            .repo
+            .create_incoming(
+                &conversation_id,
+                name,
+                &hash_token(&token),
+                &token[..INCOMING_TOKEN_DISPLAY_LEN],
+                &user_id,
+            )
+            .await?;
+
+        Ok(CreatedIncomingWebhook { token, webhook })
+    }
+
+    /// Incoming webhooks của group, kể cả các webhook đã bị revoke
+    pub async fn list_incoming_webhooks(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        role: &UserRole,
+    ) -> Result<Vec<IncomingWebhookEntity>, error::SystemError> {
+        self.ensure_group_admin(conversation_id, user_id, role).await?;
+        self.repo.find_incoming_by_conversation(&conversation_id).await
+    }
+
+    pub async fn revoke_incoming_webhook(
+        &self,
+        conversation_id: Uuid,
+        webhook_id: Uuid,
+        user_id: Uuid,
+        role: &UserRole,
+    ) -> Result<(), error::SystemError> {
+        self.ensure_group_admin(conversation_id, user_id, role).await?;
+
+        if !self.repo.revoke_incoming(&conversation_id, &webhook_id).await? {
+            return Err(error::SystemError::not_found("Incoming webhook not found"));
+        }
+
+        Ok(())
+    }
+
+    /// Incoming webhook của token cùng danh tính hiển thị cho tin nhắn sẽ đăng.
+    /// Token sai, đã revoke hoặc người tạo đã rời group đều trả về 404
+    pub async fn resolve_incoming(
+        &self,
+        token: &str,
+        payload: &IncomingWebhookPayload,
+    ) -> Result<(IncomingWebhookEntity, SenderOverride), error::SystemError> {
+        let not_found = || error::SystemError::not_found("Incoming webhook not found");
+
+        if !token.starts_with(INCOMING_TOKEN_PREFIX) {
+            return Err(not_found());
+        }
+
+        let avatar_url =
+            payload.avatar_url.as_deref().map(|url| validate_url(url, "Avatar URL")).transpose()?;
+
+        let webhook =
+            self.repo.authenticate_incoming(&hash_token(token)).await?.ok_or_else(not_found)?;
+
+        let name = payload
+            .username
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&webhook.name)
+            .to_string();
+
+        let sender =
+            SenderOverride { incoming_webhook_id: webhook.id, name: Some(name), avatar_url };
+
+        Ok((webhook, sender))
+    }
+
+    /// Chỉ thành viên là người tạo group hoặc Admin được quản lý webhooks
+    async fn ensure_group_admin(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        role: &UserRole,
+    ) -> Result<(), error::SystemError> {
+        let access = self
+            .repo
+            .find_group_access(&conversation_id, &user_id)
+            .await?
+            .ok_or_else(|| error::SystemError::not_found("Group not found"))?;
+
+        if !access.is_member {
+            return Err(error::SystemError::forbidden(
+                "User is not a participant of this conversation",
+            )
+            .with_code(error::ErrorCode::NotAMember));
+        }
+
+        if access.created_by != user_id && *role != UserRole::Admin {
+            return Err(error::SystemError::forbidden(
+                "Only the group creator can manage webhooks",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Chỉ chấp nhận URL HTTPS trên host public (không gửi / load từ mạng nội bộ)
+fn validate_url(url: &str, label: &str) -> Result<String, error::SystemError> {
+    let parsed = reqwest::Url::parse(url.trim())
+        .map_err(|_| error::SystemError::bad_request(format!("{label} is not a valid URL")))?;
+
+    if parsed.scheme() != "https" {
+        return Err(error::SystemError::bad_request(format!("{label} must use https")));
+    }
+
+    // Domain được kiểm tra lại lúc gửi, khi đã resolve (`delivery::is_public_ip`)
+    let host = parsed.host_str().unwrap_or_default().to_lowercase();
+    let internal = match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(ip) => !is_public_ip(ip),
+        Err(_) => host.is_empty() || host == "localhost" || host.ends_with(".localhost"),
+    };
+    if internal {
+        return Err(error::SystemError::bad_request(format!(
+            "{label} must point to a public host"
+        )));
+    }
+
+    Ok(parsed.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_url_accepts_public_https_urls() {
+        assert_eq!(
+            validate_url(" https://hooks.example.com/chat ", "Webhook URL").unwrap(),
+            "https://hooks.example.com/chat"
+        );
+        assert!(validate_url("https://93.184.216.34/hook", "Webhook URL").is_ok());
+    }
+
+    #[test]
+    fn validate_url_rejects_non_https_and_internal_hosts() {
+        for url in [
+            "not a url",
+            "http://hooks.example.com/chat",
+            "https://localhost/hook",
+            "https://api.localhost/hook",
+            "https://127.0.0.1/hook",
+            "https://10.1.2.3/hook",
+            "https://169.254.169.254/latest/meta-data",
+            "https://[::1]/hook",
+            "https://[fc00::1]/hook",
+            "https://[fe80::1]/hook",
+            "https://[::ffff:127.0.0.1]/hook",
+        ] {
+            let err = validate_url(url, "Webhook URL").unwrap_err();
+            assert_eq!(err.code(), error::ErrorCode::BadRequest, "{url} should be rejected");
+        }
+    }
+}
@@ -0,0 +1,183 @@
+/// Outbound Backpressure
+///
+/// Outbound channel (session actor → handler.rs / socketio.rs) có giới hạn
+/// `OUTBOUND_QUEUE_CAPACITY` frames để client đọc chậm không làm queue tăng vô hạn:
+/// - Queue depth vượt `LAG_WARN_THRESHOLD`: log warning (một lần mỗi lần vượt ngưỡng)
+/// - Queue đầy: bỏ frame mới và disconnect client. Session đã xác thực được giữ detached
+///   nên client reconnect có thể resume các events bị bỏ từ event buffer
+///
+/// Queue depth của từng session được đo từ channel; tổng depth và số lần cảnh báo /
+/// disconnect được expose qua `GET /metrics`.
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use tokio::sync::mpsc::{self, error::TrySendError};
+use uuid::Uuid;
+
+use super::session::Outbound;
+
+/// Số frames tối đa đang chờ gửi tới một client
+pub const OUTBOUND_QUEUE_CAPACITY: usize = 256;
+
+/// Queue depth bắt đầu log warning (75% capacity)
+const LAG_WARN_THRESHOLD: usize = OUTBOUND_QUEUE_CAPACITY * 3 / 4;
+
+/// Close reason gửi cho client bị disconnect vì đọc quá chậm
+pub const SLOW_CLIENT_REASON: &str = "client too slow";
+
+/// Tổng số frames đang chờ trong tất cả outbound queues
+static QUEUED_FRAMES: AtomicI64 = AtomicI64::new(0);
+/// Số lần queue depth vượt ngưỡng cảnh báo
+static LAG_WARNINGS: AtomicU64 = AtomicU64::new(0);
+/// Số clients bị disconnect vì queue đầy
+static LAG_DISCONNECTS: AtomicU64 = AtomicU64::new(0);
+
+/// Snapshot metrics của outbound queues
+#[derive(Debug, Clone, Copy)]
+pub struct QueueMetrics {
+    pub queued_frames: i64,
+    pub lag_warnings: u64,
+    pub lag_disconnects: u64,
+}
+
+/// Đọc metrics hiện tại
+pub fn metrics() -> QueueMetrics {
+    QueueMetrics {
+        queued_frames: QUEUED_FRAMES.load(Ordering::Relaxed),
+        lag_warnings: LAG_WARNINGS.load(Ordering::Relaxed),
+        lag_disconnects: LAG_DISCONNECTS.load(Ordering::Relaxed),
+    }
+}
+
+/// Lỗi khi đưa frame vào outbound queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendError {
+    /// Client đọc quá chậm, queue đầy (connection đã được đánh dấu disconnect)
+    Lagging,
+    /// Connection đã đóng
+    Closed,
+}
+
+impl std::fmt::Display for SendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendError::Lagging => f.write_str("outbound queue full"),
+            SendError::Closed => f.write_str("channel closed"),
+        }
+    }
+}
+
+/// State chung giữa sender (session actor) và receiver (message loop)
+#[derive(Debug, Default)]
+struct QueueState {
+    /// Queue đã đầy, message loop phải đóng connection
+    lagging: AtomicBool,
+    /// Đang ở trên ngưỡng cảnh báo (tránh log lặp lại mỗi frame)
+    warned: AtomicBool,
+}
+
+/// Phía gửi của outbound queue (session actor)
+#[derive(Debug, Clone)]
+pub struct OutboundSender {
+    tx: mpsc::Sender<Outbound>,
+    state: Arc<QueueState>,
+    session_id: Uuid,
+}
+
+/// Phía nhận của outbound queue (message loop ghi ra WebSocket)
+#[derive(Debug)]
+pub struct OutboundReceiver {
+    rx: mpsc::Receiver<Outbound>,
+    state: Arc<QueueState>,
+}
+
+/// Tạo outbound queue có giới hạn cho một session
+pub fn outbound_channel(session_id: Uuid) -> (OutboundSender, OutboundReceiver) {
+    let (tx, rx) = mpsc::channel(OUTBOUND_QUEUE_CAPACITY);
+    let state = Arc::new(QueueState::default());
+
+    (OutboundSender { tx, state: state.clone(), session_id }, OutboundReceiver { rx, state })
+}
+
+impl OutboundSender {
+    /// Session sở hữu queue
+    pub fn session_id(&self) -> Uuid {
+        self.session_id
+    }
+
+    /// Số frames đang chờ gửi tới client
+    pub fn depth(&self) -> usize {
+        self.tx.max_capacity() - self.tx.capacity()
+    }
+
+    /// Đưa frame vào queue, không chờ; queue đầy thì đánh dấu client lagging
+    pub fn send(&self, outbound: Outbound) -> Result<(), SendError> {
+        if self.state.lagging.load(Ordering::Relaxed) {
+            return Err(SendError::Lagging);
+        }
+
+        match self.tx.try_send(outbound) {
+            Ok(()) => {
+                QUEUED_FRAMES.fetch_add(1, Ordering::Relaxed);
+                self.check_depth();
+                Ok(())
+            }
+            Err(TrySendError::Full(_)) => {
+                if !self.state.lagging.swap(true, Ordering::Relaxed) {
+                    LAG_DISCONNECTS.fetch_add(1, Ordering::Relaxed);
+                    tracing::warn!(
+                        "Outbound queue của session {} đầy ({} frames), disconnect client",
+                        self.session_id,
+                        OUTBOUND_QUEUE_CAPACITY
+                    );
+                }
+                Err(SendError::Lagging)
+            }
+            Err(TrySendError::Closed(_)) => Err(SendError::Closed),
+        }
+    }
+
+    /// Log warning khi queue depth vượt ngưỡng, reset khi client đọc kịp
+    fn check_depth(&self) {
+        let depth = self.depth();
+
+        if depth >= LAG_WARN_THRESHOLD {
+            if !self.state.warned.swap(true, Ordering::Relaxed) {
+                LAG_WARNINGS.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!(
+                    "Client của session {} đọc chậm: {}/{} frames đang chờ",
+                    self.session_id,
+                    depth,
+                    OUTBOUND_QUEUE_CAPACITY
+                );
+            }
+        } else if depth < LAG_WARN_THRESHOLD / 2 {
+            self.state.warned.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+impl OutboundReceiver {
+    /// Nhận frame tiếp theo; `None` khi channel đóng hoặc client bị disconnect vì lagging
+    pub async fn recv(&mut self) -> Option<Outbound> {
+        if self.is_lagging() {
+            return None;
+        }
+
+        let outbound = self.rx.recv().await?;
+        QUEUED_FRAMES.fetch_sub(1, Ordering::Relaxed);
+        Some(outbound)
+    }
+
+    /// Client bị disconnect vì queue đầy
+    pub fn is_lagging(&self) -> bool {
+        self.state.lagging.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for OutboundReceiver {
+    fn drop(&mut self) {
+        // Frames chưa gửi bị bỏ cùng connection
+        QUEUED_FRAMES.fetch_sub(self.rx.len() as i64, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,163 @@
+/// WebSocket Fan-out Bridge
+///
+/// State của WebSocketServer (sessions/users/rooms) chỉ nằm trong memory của từng
+/// instance. Để chạy nhiều instances, các events routing (broadcast room, gửi tới
+/// users, presence) được publish lên channel `WS_FANOUT_CHANNEL`; mọi instance
+/// subscribe channel này và deliver tới các sessions đang kết nối với mình.
+///
+/// Instance publish đã deliver local trước khi publish, nên bỏ qua các envelopes
+/// có `origin` trùng instance ID của chính nó.
+use std::time::Duration;
+
+use actix::Addr;
+use deadpool_redis::redis::AsyncCommands;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use super::events::DeliverLocal;
+use super::message::ServerMessage;
+use super::server::WebSocketServer;
+use crate::{api::error, ENV};
+
+pub const WS_FANOUT_CHANNEL: &str = "ws:fanout";
+
+/// Thời gian chờ trước khi subscribe lại khi mất kết nối Redis
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// Event routing được chia sẻ giữa các instances
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FanoutEvent {
+    BroadcastToRoom {
+        conversation_id: Uuid,
+        message: ServerMessage,
+        skip_user_id: Option<Uuid>,
+    },
+    SendToUsers {
+        user_ids: Vec<Uuid>,
+        message: ServerMessage,
+    },
+    SendToOtherSessions {
+        user_id: Uuid,
+        skip_session_id: Option<Uuid>,
+        message: ServerMessage,
+    },
+    UserPresenceChanged {
+        user_id: Uuid,
+        is_online: bool,
+        friend_ids: Vec<Uuid>,
+        last_seen: Option<String>,
+    },
+    BroadcastToAll {
+        message: ServerMessage,
+    },
+}
+
+/// Message được publish lên `WS_FANOUT_CHANNEL`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FanoutEnvelope {
+    /// Instance ID của instance publish
+    origin: Uuid,
+    /// ID chung của event trên mọi instances (xem `event_buffer`)
+    event_id: Uuid,
+    event: FanoutEvent,
+}
+
+/// Publisher phía WebSocketServer
+///
+/// Events được đưa vào channel và publish tuần tự bởi một task riêng, giữ đúng thứ tự
+/// events và không block server actor khi chờ Redis
+#[derive(Clone)]
+pub struct FanoutBridge {
+    instance_id: Uuid,
+    tx: mpsc::UnboundedSender<(Uuid, FanoutEvent)>,
+}
+
+impl FanoutBridge {
+    /// Tạo bridge với instance ID mới và spawn publisher task
+    pub fn start(pool: deadpool_redis::Pool) -> Self {
+        let instance_id = Uuid::now_v7();
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        actix_web::rt::spawn(run_publisher(instance_id, pool, rx));
+
+        Self { instance_id, tx }
+    }
+
+    pub fn instance_id(&self) -> Uuid {
+        self.instance_id
+    }
+
+    pub fn publish(&self, event_id: Uuid, event: FanoutEvent) {
+        if self.tx.send((event_id, event)).is_err() {
+            tracing::error!("Fan-out publisher stopped, event not published");
+        }
+    }
+}
+
+async fn run_publisher(
+    origin: Uuid,
+    pool: deadpool_redis::Pool,
+    mut rx: mpsc::UnboundedReceiver<(Uuid, FanoutEvent)>,
+) {
+    while let Some((event_id, event)) = rx.recv().await {
+        if let Err(e) = publish(&pool, &FanoutEnvelope { origin, event_id, event }).await {
+            tracing::warn!("Failed to publish WebSocket fan-out event: {:?}", e);
+        }
+    }
+}
+
+async fn publish(
+    pool: &deadpool_redis::Pool,
+    envelope: &FanoutEnvelope,
+) -> Result<(), error::SystemError> {
+    let mut conn = pool.get().await?;
+    let payload = serde_json::to_vec(envelope)?;
+    conn.publish::<_, _, ()>(WS_FANOUT_CHANNEL, payload).await?;
+    Ok(())
+}
+
+/// Subscribe channel fan-out và deliver events của instances khác tới local sessions,
+/// tự subscribe lại nếu kết nối Redis bị ngắt
+pub async fn run_fanout_listener(instance_id: Uuid, ws_server: Addr<WebSocketServer>) {
+    loop {
+        if let Err(e) = listen(instance_id, &ws_server).await {
+            tracing::error!("WebSocket fan-out listener error: {:?}", e);
+        }
+
+        tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+    }
+}
+
+async fn listen(
+    instance_id: Uuid,
+    ws_server: &Addr<WebSocketServer>,
+) -> Result<(), error::SystemError> {
+    let client = deadpool_redis::redis::Client::open(ENV.redis_url.as_str())?;
+    let mut pubsub = client.get_async_pubsub().await?;
+    pubsub.subscribe(WS_FANOUT_CHANNEL).await?;
+
+    tracing::info!("Subscribed to {} as instance {}", WS_FANOUT_CHANNEL, instance_id);
+
+    let mut messages = pubsub.on_message();
+    while let Some(msg) = messages.next().await {
+        let payload: Vec<u8> = msg.get_payload()?;
+        let envelope = match serde_json::from_slice::<FanoutEnvelope>(&payload) {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::warn!("Invalid WebSocket fan-out payload: {:?}", e);
+                continue;
+            }
+        };
+
+        if envelope.origin == instance_id {
+            continue;
+        }
+
+        ws_server.do_send(DeliverLocal { event_id: envelope.event_id, event: envelope.event });
+    }
+
+    Err(error::SystemError::internal_error("WebSocket fan-out subscription closed"))
+}
@@ -0,0 +1,100 @@
+/// Group Call Rooms
+///
+/// Registry các cuộc gọi nhóm đang diễn ra: conversation_id → `CallRoom` (ai đang ở trong
+/// cuộc gọi, mỗi user một session). Cuộc gọi bắt đầu khi user đầu tiên join và kết thúc
+/// khi user cuối cùng rời đi. Media giữa các participants do client tự thiết lập, server
+/// chỉ giữ trạng thái phòng và broadcast `group-call-joined` / `group-call-left`.
+///
+/// Registry nằm trong `WebSocketServer` nên là state của từng instance: các participants
+/// của cùng một cuộc gọi cần kết nối tới cùng instance (sticky theo conversation).
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+/// Thay đổi trạng thái phòng sau một lần join / leave
+#[derive(Debug, Clone, PartialEq)]
+pub enum CallRoomChange {
+    /// User đầu tiên join, cuộc gọi bắt đầu
+    Started,
+    /// User join cuộc gọi đang diễn ra (hoặc chuyển sang session khác)
+    Joined,
+    /// User rời đi, cuộc gọi vẫn còn người khác
+    Left,
+    /// User cuối cùng rời đi, cuộc gọi kết thúc
+    Ended {
+        /// Số user khác nhau đã tham gia trong suốt cuộc gọi
+        participant_count: usize,
+        duration: chrono::Duration,
+    },
+}
+
+struct CallRoom {
+    started_at: chrono::DateTime<chrono::Utc>,
+    /// user_id -> session đang ở trong cuộc gọi
+    participants: HashMap<Uuid, Uuid>,
+    /// Mọi user đã từng join, để tính số người tham gia khi kết thúc
+    joined: HashSet<Uuid>,
+}
+
+#[derive(Default)]
+pub struct CallRooms {
+    rooms: HashMap<Uuid, CallRoom>,
+}
+
+impl CallRooms {
+    /// User join cuộc gọi của conversation từ `session_id`, `None` nếu session này đã ở
+    /// trong cuộc gọi
+    pub fn join(
+        &mut self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        session_id: Uuid,
+    ) -> Option<CallRoomChange> {
+        let room = self.rooms.entry(conversation_id).or_insert_with(|| CallRoom {
+            started_at: chrono::Utc::now(),
+            participants: HashMap::new(),
+            joined: HashSet::new(),
+        });
+
+        let started = room.participants.is_empty();
+        if room.participants.insert(user_id, session_id) == Some(session_id) {
+            return None;
+        }
+        room.joined.insert(user_id);
+
+        Some(if started { CallRoomChange::Started } else { CallRoomChange::Joined })
+    }
+
+    /// User rời cuộc gọi, `None` nếu `session_id` không phải session đang ở trong cuộc gọi
+    /// (user đã rời đi hoặc đã chuyển sang thiết bị khác)
+    pub fn leave(
+        &mut self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        session_id: Uuid,
+    ) -> Option<CallRoomChange> {
+        let room = self.rooms.get_mut(&conversation_id)?;
+        if room.participants.get(&user_id) != Some(&session_id) {
+            return None;
+        }
+        room.participants.remove(&user_id);
+
+        if !room.participants.is_empty() {
+            return Some(CallRoomChange::Left);
+        }
+
+        let room = self.rooms.remove(&conversation_id)?;
+        Some(CallRoomChange::Ended {
+            participant_count: room.joined.len(),
+            duration: chrono::Utc::now() - room.started_at,
+        })
+    }
+
+    /// Users đang ở trong cuộc gọi của conversation
+    pub fn participant_ids(&self, conversation_id: &Uuid) -> Vec<Uuid> {
+        self.rooms
+            .get(conversation_id)
+            .map(|room| room.participants.keys().copied().collect())
+            .unwrap_or_default()
+    }
+}
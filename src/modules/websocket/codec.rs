@@ -0,0 +1,177 @@
+/// Frame Encoding
+///
+/// Client chọn encoding trong handshake qua header `Sec-WebSocket-Protocol`:
+/// - `json` (mặc định, cũng dùng khi client không gửi subprotocol): text frames JSON
+/// - `msgpack`: binary frames MessagePack (rmp-serde), giảm bandwidth
+///
+/// Với `msgpack`, ClientMessage được decode từ binary frames (text frames JSON vẫn được
+/// chấp nhận). ServerMessage đi qua outbound queue dưới dạng `Frame` và được serialize
+/// trực tiếp sang encoding của connection ngay trước khi gửi.
+use std::sync::{Arc, OnceLock};
+
+use actix_web::{http::header, web::Bytes, HttpRequest};
+use serde::Serialize;
+
+use super::message::{ClientMessage, ServerMessage};
+
+pub const JSON_PROTOCOL: &str = "json";
+pub const MSGPACK_PROTOCOL: &str = "msgpack";
+
+/// Encoding của frames trên một connection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Json,
+    MsgPack,
+}
+
+impl Encoding {
+    /// Chọn subprotocol đầu tiên được hỗ trợ trong `Sec-WebSocket-Protocol`
+    ///
+    /// Trả về `None` nếu client không yêu cầu subprotocol nào được hỗ trợ
+    pub fn negotiate(req: &HttpRequest) -> Option<Self> {
+        req.headers()
+            .get_all(header::SEC_WEBSOCKET_PROTOCOL)
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .find_map(|protocol| match protocol.trim() {
+                JSON_PROTOCOL => Some(Encoding::Json),
+                MSGPACK_PROTOCOL => Some(Encoding::MsgPack),
+                _ => None,
+            })
+    }
+
+    /// Tên subprotocol echo lại trong handshake response
+    pub fn protocol(self) -> &'static str {
+        match self {
+            Encoding::Json => JSON_PROTOCOL,
+            Encoding::MsgPack => MSGPACK_PROTOCOL,
+        }
+    }
+}
+
+/// Decode ClientMessage từ binary frame MessagePack
+pub fn decode_msgpack(bytes: &[u8]) -> Result<ClientMessage, rmp_serde::decode::Error> {
+    rmp_serde::from_slice(bytes)
+}
+
+/// ServerMessage chờ gửi tới client. Clone chỉ tăng reference count nên một event gửi tới
+/// nhiều sessions dùng chung một `Frame`: mỗi encoding được serialize một lần, ở lần đầu
+/// có session cần tới, các sessions sau gửi lại đúng bytes đó
+#[derive(Debug, Clone)]
+pub struct Frame(Arc<FrameInner>);
+
+#[derive(Debug)]
+struct FrameInner {
+    message: ServerMessage,
+    /// Seq của event theo user (chỉ có ở events đã qua sequencer)
+    user_seq: Option<u64>,
+    json: OnceLock<Option<String>>,
+    msgpack: OnceLock<Option<Bytes>>,
+}
+
+/// ServerMessage kèm field `user_seq` cùng cấp với các field của message
+#[derive(Serialize)]
+struct Sequenced<'a> {
+    #[serde(flatten)]
+    message: &'a ServerMessage,
+    user_seq: u64,
+}
+
+impl Frame {
+    pub fn new(message: ServerMessage) -> Self {
+        Self::build(message, None)
+    }
+
+    /// Frame của event đã được gán seq theo user
+    pub fn sequenced(message: ServerMessage, user_seq: u64) -> Self {
+        Self::build(message, Some(user_seq))
+    }
+
+    fn build(message: ServerMessage, user_seq: Option<u64>) -> Self {
+        Frame(Arc::new(FrameInner {
+            message,
+            user_seq,
+            json: OnceLock::new(),
+            msgpack: OnceLock::new(),
+        }))
+    }
+
+    /// Text frame JSON, `None` nếu không serialize được (đã log)
+    pub fn json(&self) -> Option<&str> {
+        let inner = &*self.0;
+        inner
+            .json
+            .get_or_init(|| {
+                let json = match inner.user_seq {
+                    Some(user_seq) => {
+                        serde_json::to_string(&Sequenced { message: &inner.message, user_seq })
+                    }
+                    None => serde_json::to_string(&inner.message),
+                };
+                json.inspect_err(|e| tracing::error!("Không thể serialize ServerMessage: {}", e))
+                    .ok()
+            })
+            .as_deref()
+    }
+
+    /// Binary frame MessagePack, `None` nếu không encode được (đã log)
+    ///
+    /// Dùng map encoding để giữ tên field (`type`, `user_seq`, ...) như JSON
+    pub fn msgpack(&self) -> Option<Bytes> {
+        let inner = &*self.0;
+        inner
+            .msgpack
+            .get_or_init(|| {
+                let bytes = match inner.user_seq {
+                    Some(user_seq) => {
+                        rmp_serde::to_vec_named(&Sequenced { message: &inner.message, user_seq })
+                    }
+                    None => rmp_serde::to_vec_named(&inner.message),
+                };
+                bytes
+                    .map(Bytes::from)
+                    .inspect_err(|e| tracing::error!("Không thể encode MessagePack: {}", e))
+                    .ok()
+            })
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::api::error::ErrorCode;
+
+    fn decode(frame: &Frame) -> (Value, Value) {
+        let json = serde_json::from_str(frame.json().unwrap()).unwrap();
+        let msgpack = rmp_serde::from_slice(&frame.msgpack().unwrap()).unwrap();
+        (json, msgpack)
+    }
+
+    #[test]
+    fn frame_encodings_match_and_carry_user_seq() {
+        let message = ServerMessage::error(ErrorCode::InvalidMessage, "bad");
+
+        let (json, msgpack) = decode(&Frame::new(message.clone()));
+        assert_eq!(json, msgpack);
+        assert_eq!(json["type"], json!("error"));
+        assert!(json.get("user_seq").is_none());
+
+        let (json, msgpack) = decode(&Frame::sequenced(message, 7));
+        assert_eq!(json, msgpack);
+        assert_eq!(json["type"], json!("error"));
+        assert_eq!(json["user_seq"], json!(7));
+    }
+
+    #[test]
+    fn cloned_frames_share_encoded_bytes() {
+        let frame = Frame::new(ServerMessage::error(ErrorCode::InvalidMessage, "bad"));
+        let clone = frame.clone();
+
+        assert!(std::ptr::eq(frame.json().unwrap(), clone.json().unwrap()));
+        assert_eq!(frame.msgpack().unwrap().as_ptr(), clone.msgpack().unwrap().as_ptr());
+    }
+}
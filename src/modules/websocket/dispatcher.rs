@@ -0,0 +1,131 @@
+/// Event Outbox Dispatcher
+///
+/// Broadcast gửi sau `tx.commit()` bị mất nếu process crash giữa commit và broadcast.
+/// Thay vào đó services ghi `FanoutEvent` vào bảng `event_outbox` trong cùng transaction
+/// với thay đổi dữ liệu; dispatcher chạy nền claim các events đã commit theo thứ tự `id`
+/// và route qua WebSocketServer (deliver local + publish Redis cho các instances khác).
+///
+/// Semantics at-least-once: events chỉ bị xóa khi transaction claim commit sau khi đã
+/// route xong, lỗi giữa chừng thì rollback và batch được gửi lại ở lần quét sau.
+/// Claim dùng `FOR UPDATE SKIP LOCKED` nên nhiều instances chạy song song không route trùng.
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix::Addr;
+use sqlx::PgPool;
+use tokio::sync::Notify;
+
+use super::bridge::FanoutEvent;
+use super::events::RouteEvent;
+use super::server::WebSocketServer;
+use crate::api::error;
+
+/// Khoảng thời gian tối đa giữa hai lần quét (events ghi bởi instance khác / bị bỏ lỡ)
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Số events tối đa claim mỗi lần, quét tiếp ngay nếu còn events
+const BATCH_SIZE: i64 = 200;
+
+/// Handle cho services: ghi events vào outbox và đánh thức dispatcher sau commit
+#[derive(Clone, Default)]
+pub struct EventOutbox {
+    notify: Arc<Notify>,
+}
+
+impl EventOutbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ghi event vào outbox, gọi trong transaction của thay đổi dữ liệu
+    pub async fn enqueue<'e, E>(&self, event: &FanoutEvent, tx: E) -> Result<(), error::SystemError>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        let payload = serde_json::to_string(event)
+            .map_err(|e| error::SystemError::internal_error(e.to_string()))?;
+
+        sqlx::query("INSERT INTO event_outbox (payload) VALUES ($1::jsonb)")
+            .bind(payload)
+            .execute(tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Báo dispatcher có events mới, gọi sau khi transaction đã commit
+    pub fn wake(&self) {
+        self.notify.notify_one();
+    }
+}
+
+/// Route events trong outbox cho tới khi process dừng
+pub async fn run_event_dispatcher(
+    outbox: EventOutbox,
+    pool: PgPool,
+    ws_server: Addr<WebSocketServer>,
+) {
+    loop {
+        tokio::select! {
+            _ = outbox.notify.notified() => {}
+            _ = actix_web::rt::time::sleep(POLL_INTERVAL) => {}
+        }
+
+        loop {
+            match dispatch_batch(&pool, &ws_server).await {
+                Ok(dispatched) if dispatched as i64 == BATCH_SIZE => continue,
+                Ok(_) => break,
+                Err(e) => {
+                    tracing::error!("Failed to dispatch outbox events: {:?}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Claim một batch events, route theo thứ tự ghi rồi mới xóa khỏi outbox
+async fn dispatch_batch(
+    pool: &PgPool,
+    ws_server: &Addr<WebSocketServer>,
+) -> Result<usize, error::SystemError> {
+    let mut tx = pool.begin().await?;
+
+    let mut rows = sqlx::query_as::<_, (i64, String)>(
+        r#"
+        DELETE FROM event_outbox
+        WHERE id IN (
+            SELECT id FROM event_outbox
+            ORDER BY id
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, payload::text
+        "#,
+    )
+    .bind(BATCH_SIZE)
+    .fetch_all(tx.as_mut())
+    .await?;
+
+    rows.sort_unstable_by_key(|(id, _)| *id);
+
+    for (id, payload) in &rows {
+        let event = match serde_json::from_str::<FanoutEvent>(payload) {
+            Ok(event) => event,
+            Err(e) => {
+                // Payload không decode được sẽ không bao giờ thành công, bỏ để không chặn queue
+                tracing::error!("Dropping undecodable outbox event {}: {}", id, e);
+                continue;
+            }
+        };
+
+        ws_server
+            .send(RouteEvent { event })
+            .await
+            .map_err(|e| error::SystemError::internal_error(e.to_string()))?;
+    }
+
+    tx.commit().await?;
+
+    Ok(rows.len())
+}
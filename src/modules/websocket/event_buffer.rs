@@ -0,0 +1,247 @@
+/// Event Buffer
+///
+/// Mỗi user có một dãy `user_seq` tăng dần trong Redis (dùng chung mọi instances) cho các
+/// server events gửi tới user. Events được buffer ngắn hạn theo seq để client lấy lại events
+/// bị lỡ thay vì fetch lại toàn bộ state:
+/// - qua WebSocket: client gửi `sync { last_seq }`, server replay events có seq > last_seq
+/// - khi reconnect: `auth` kèm `resume { session_id, last_seq }`, replay như `sync`
+/// - qua long-poll `GET /events/poll?since=<last_seq>`
+///
+/// Khi buffer không còn đủ events sau `last_seq` (hết hạn, bị cắt, counter bị reset) server
+/// yêu cầu client resync toàn bộ state qua REST.
+///
+/// Mỗi lần route, event có một `event_id` dùng chung trên mọi instances. Seq được gán theo
+/// (user, event_id) nên mọi thiết bị của user nhận cùng seq cho cùng event, kể cả khi các
+/// sessions nằm trên nhiều instances. Client chỉ áp dụng event có seq = last_seq + 1, bỏ qua
+/// seq <= last_seq và gửi `sync` khi thấy seq nhảy cóc.
+///
+/// Redis key schema:
+/// - `ws:events:seq:{user_id}` → STRING, seq cuối cùng của user, TTL = SEQ_TTL
+/// - `ws:events:{user_id}` → STREAM (ID `<seq>-0`, field `event` = JSON của ServerMessage),
+///   giữ khoảng EVENT_BUFFER_MAX_LEN events mới nhất, TTL = EVENT_BUFFER_TTL
+/// - `ws:events:assigned:{user_id}:{event_id}` → STRING, seq đã gán, TTL = ASSIGNED_TTL
+use std::collections::HashMap;
+use std::time::Duration;
+
+use actix::Addr;
+use deadpool_redis::redis;
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use super::events::DeliverSequenced;
+use super::message::ServerMessage;
+use super::server::WebSocketServer;
+use crate::api::error;
+
+/// Thời gian giữ buffer của user kể từ event cuối cùng
+pub const EVENT_BUFFER_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Thời gian giữ seq của user kể từ event cuối cùng. Hết hạn thì seq bắt đầu lại từ 1,
+/// client có last_seq lớn hơn sẽ nhận yêu cầu resync
+const SEQ_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Thời gian nhớ seq đã gán cho một event, đủ để mọi instances deliver cùng event
+const ASSIGNED_TTL: Duration = Duration::from_secs(60);
+
+/// Số events (xấp xỉ) tối đa giữ lại cho mỗi user
+const EVENT_BUFFER_MAX_LEN: usize = 200;
+
+const EVENT_BUFFER_PREFIX: &str = "ws:events:";
+
+const EVENT_FIELD: &str = "event";
+
+/// Kết quả pipeline đọc buffer: (seq mới nhất, entries của XRANGE)
+type ReadReply = (Option<u64>, Vec<(String, HashMap<String, String>)>);
+
+/// Gán seq cho event và ghi vào buffer của từng user
+///
+/// KEYS: bộ ba (seq, stream, assigned) của mỗi user
+/// ARGV: JSON event, max len, TTL buffer, TTL seq, TTL assigned
+/// Trả về seq của event theo thứ tự users
+const APPEND_SCRIPT: &str = r#"
+local seqs = {}
+for i = 1, #KEYS, 3 do
+    local seq = redis.call('GET', KEYS[i + 2])
+    if not seq then
+        seq = redis.call('INCR', KEYS[i])
+        redis.call('XADD', KEYS[i + 1], 'MAXLEN', '~', ARGV[2], seq .. '-0', 'event', ARGV[1])
+        redis.call('SET', KEYS[i + 2], seq, 'EX', ARGV[5])
+        redis.call('EXPIRE', KEYS[i + 1], ARGV[3])
+        redis.call('EXPIRE', KEYS[i], ARGV[4])
+    end
+    seqs[#seqs + 1] = tonumber(seq)
+end
+return seqs
+"#;
+
+/// Kết quả đọc buffer sau một seq
+#[derive(Debug, Default)]
+pub struct BufferedEvents {
+    /// (seq, JSON của ServerMessage) theo thứ tự seq
+    pub events: Vec<(u64, String)>,
+    /// Seq mới nhất của user
+    pub head: u64,
+    /// Buffer không còn đủ events sau seq đã cho, client cần resync toàn bộ state
+    pub gap: bool,
+}
+
+/// Event ID cố định theo payload, cho events mà mọi instance tự nhận và deliver độc lập
+/// (không qua `route`)
+pub fn event_id_from_payload(payload: &[u8]) -> Uuid {
+    let digest = Sha256::digest(payload);
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    Uuid::from_bytes(bytes)
+}
+
+#[derive(Clone)]
+pub struct EventBuffer {
+    pool: deadpool_redis::Pool,
+}
+
+impl EventBuffer {
+    pub fn new(pool: deadpool_redis::Pool) -> Self {
+        Self { pool }
+    }
+
+    fn key(user_id: &Uuid) -> String {
+        format!("{EVENT_BUFFER_PREFIX}{user_id}")
+    }
+
+    fn seq_key(user_id: &Uuid) -> String {
+        format!("{EVENT_BUFFER_PREFIX}seq:{user_id}")
+    }
+
+    fn assigned_key(user_id: &Uuid, event_id: &Uuid) -> String {
+        format!("{EVENT_BUFFER_PREFIX}assigned:{user_id}:{event_id}")
+    }
+
+    /// Gán seq cho event và ghi vào buffer của từng user trong một round-trip.
+    /// Event đã được gán seq (từ instance khác) giữ nguyên seq cũ
+    pub async fn append(
+        &self,
+        event_id: Uuid,
+        user_ids: &[Uuid],
+        message: &ServerMessage,
+    ) -> Result<Vec<u64>, error::SystemError> {
+        if user_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let event = serde_json::to_string(message)?;
+        let mut conn = self.pool.get().await?;
+
+        let mut cmd = redis::cmd("EVAL");
+        cmd.arg(APPEND_SCRIPT).arg(user_ids.len() * 3);
+        for user_id in user_ids {
+            cmd.arg(Self::seq_key(user_id))
+                .arg(Self::key(user_id))
+                .arg(Self::assigned_key(user_id, &event_id));
+        }
+        cmd.arg(event)
+            .arg(EVENT_BUFFER_MAX_LEN)
+            .arg(EVENT_BUFFER_TTL.as_secs())
+            .arg(SEQ_TTL.as_secs())
+            .arg(ASSIGNED_TTL.as_secs());
+
+        Ok(cmd.query_async::<Vec<u64>>(&mut *conn).await?)
+    }
+
+    /// Tối đa `limit` events có seq > `after` (toàn bộ buffer nếu không có `after`)
+    pub async fn read_after(
+        &self,
+        user_id: Uuid,
+        after: Option<u64>,
+        limit: usize,
+    ) -> Result<BufferedEvents, error::SystemError> {
+        let mut conn = self.pool.get().await?;
+
+        let start = after.map_or_else(|| "-".to_string(), |seq| format!("{}-0", seq + 1));
+        let (head, entries): ReadReply = redis::pipe()
+            .atomic()
+            .get(Self::seq_key(&user_id))
+            .cmd("XRANGE")
+            .arg(Self::key(&user_id))
+            .arg(start)
+            .arg("+")
+            .arg("COUNT")
+            .arg(limit)
+            .query_async(&mut *conn)
+            .await?;
+
+        let events: Vec<(u64, String)> = entries
+            .into_iter()
+            .filter_map(|(id, mut fields)| {
+                let seq = id.split_once('-')?.0.parse().ok()?;
+                Some((seq, fields.remove(EVENT_FIELD)?))
+            })
+            .collect();
+        let head = head.unwrap_or_default();
+
+        let gap = match after {
+            None => false,
+            // Seq của user đã bị reset (hết hạn)
+            Some(after) if after >= head => after > head,
+            Some(after) => events.first().is_none_or(|(seq, _)| *seq != after + 1),
+        };
+
+        Ok(BufferedEvents { events, head, gap })
+    }
+}
+
+/// Event chờ được gán seq trước khi gửi tới local sessions
+pub struct SequenceJob {
+    pub event_id: Uuid,
+    /// (user, sessions của user trên instance này)
+    pub targets: Vec<(Uuid, Vec<Uuid>)>,
+    pub message: ServerMessage,
+}
+
+/// Gán seq cho events phía WebSocketServer
+///
+/// Events được đưa vào channel và gán seq tuần tự bởi một task riêng (như publisher của
+/// `bridge`), giữ đúng thứ tự events và không block server actor khi chờ Redis. Event đã
+/// gán seq quay lại server qua `DeliverSequenced`
+#[derive(Clone)]
+pub struct EventSequencer {
+    tx: mpsc::UnboundedSender<SequenceJob>,
+}
+
+impl EventSequencer {
+    pub fn start(buffer: EventBuffer, ws_server: Addr<WebSocketServer>) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        actix_web::rt::spawn(run_sequencer(buffer, ws_server, rx));
+
+        Self { tx }
+    }
+
+    /// Trả lại job nếu sequencer task đã dừng để server gửi trực tiếp (không có seq)
+    pub fn enqueue(&self, job: SequenceJob) -> Result<(), Box<SequenceJob>> {
+        self.tx.send(job).map_err(|e| {
+            tracing::error!("Event sequencer stopped, delivering without user_seq");
+            Box::new(e.0)
+        })
+    }
+}
+
+async fn run_sequencer(
+    buffer: EventBuffer,
+    ws_server: Addr<WebSocketServer>,
+    mut rx: mpsc::UnboundedReceiver<SequenceJob>,
+) {
+    while let Some(job) = rx.recv().await {
+        let user_ids: Vec<Uuid> = job.targets.iter().map(|(user_id, _)| *user_id).collect();
+
+        let seqs = match buffer.append(job.event_id, &user_ids, &job.message).await {
+            Ok(seqs) => Some(seqs),
+            Err(e) => {
+                tracing::warn!("Failed to assign user seqs to event {}: {:?}", job.event_id, e);
+                None
+            }
+        };
+
+        ws_server.do_send(DeliverSequenced { targets: job.targets, message: job.message, seqs });
+    }
+}
@@ -5,7 +5,10 @@
 use actix::prelude::*;
 use uuid::Uuid;
 
-use super::message::ServerMessage;
+use super::bridge::FanoutEvent;
+use super::call_room::CallRoomChange;
+use super::codec::Frame;
+use super::message::{ResumeRequest, ServerMessage};
 use super::session::WebSocketSession;
 
 /// Event: User connected đến WebSocket server
@@ -34,6 +37,8 @@ pub struct Authenticate {
     pub session_id: Uuid,
     /// User ID sau khi authenticate
     pub user_id: Uuid,
+    /// Optional: Resume detached session trước đó của user
+    pub resume: Option<ResumeRequest>,
 }
 
 /// Event: User tham gia vào conversation room
@@ -56,6 +61,25 @@ pub struct LeaveRoom {
     pub conversation_id: Uuid,
 }
 
+/// Event: User join cuộc gọi nhóm của conversation từ một session
+#[derive(Message)]
+#[rtype(result = "Option<CallRoomChange>")]
+pub struct JoinCall {
+    pub conversation_id: Uuid,
+    pub user_id: Uuid,
+    /// Session đang ở trong cuộc gọi (thay thế session cũ nếu user đổi thiết bị)
+    pub session_id: Uuid,
+}
+
+/// Event: User rời cuộc gọi nhóm (bỏ qua nếu session không còn ở trong cuộc gọi)
+#[derive(Message)]
+#[rtype(result = "Option<CallRoomChange>")]
+pub struct LeaveCall {
+    pub conversation_id: Uuid,
+    pub user_id: Uuid,
+    pub session_id: Uuid,
+}
+
 /// Event: Broadcast message tới tất cả users trong room
 #[derive(Message, Clone)]
 #[rtype(result = "()")]
@@ -78,6 +102,18 @@ pub struct SendToUser {
     pub message: ServerMessage,
 }
 
+/// Event: Gửi message cho các sessions khác của user (đồng bộ giữa các thiết bị)
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SendToOtherSessions {
+    /// User ID cần nhận message
+    pub user_id: Uuid,
+    /// Optional: Session đã thực hiện thay đổi, không cần nhận lại
+    pub skip_session_id: Option<Uuid>,
+    /// Message cần gửi
+    pub message: ServerMessage,
+}
+
 /// Event: Lấy danh sách users đang online
 #[derive(Message)]
 #[rtype(result = "Vec<Uuid>")]
@@ -126,3 +162,73 @@ pub struct SendInitialPresence {
     /// Danh sách friend IDs để kiểm tra
     pub friend_ids: Vec<Uuid>,
 }
+
+/// Event: Frame (có thể kèm user_seq) gửi từ server actor tới session, dùng chung giữa
+/// các sessions nhận cùng event
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct OutboundFrame {
+    pub frame: Frame,
+}
+
+/// Event: Route event đã được ghi vào outbox (deliver local + publish qua bridge)
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct RouteEvent {
+    pub event: FanoutEvent,
+}
+
+/// Event: Deliver event routing tới local sessions, không publish lại qua bridge
+/// (event đến từ instance khác, hoặc mọi instance đã tự nhận cùng event)
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct DeliverLocal {
+    /// ID chung của event trên mọi instances (gán `user_seq` một lần cho mỗi user)
+    pub event_id: Uuid,
+    pub event: FanoutEvent,
+}
+
+/// Event: Event đã được `EventSequencer` gán seq theo user, gửi tới local sessions
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct DeliverSequenced {
+    /// (user, sessions của user trên instance này)
+    pub targets: Vec<(Uuid, Vec<Uuid>)>,
+    pub message: ServerMessage,
+    /// Seq của event theo thứ tự users trong `targets` (`None` nếu không gán được seq)
+    pub seqs: Option<Vec<u64>>,
+}
+
+/// Event: Client yêu cầu replay các events có user seq > `last_seq`
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SyncEvents {
+    pub session_id: Uuid,
+    pub user_id: Uuid,
+    /// Seq lớn nhất client đã áp dụng
+    pub last_seq: u64,
+}
+
+/// Event: Server bắt đầu shutdown - ngừng nhận connections mới và đóng tất cả sessions
+/// Trả về danh sách user IDs đang kết nối với instance này (để set offline)
+#[derive(Message)]
+#[rtype(result = "Vec<Uuid>")]
+pub struct Shutdown {
+    /// Reason gửi kèm Close frame
+    pub reason: String,
+}
+
+/// Event: Kiểm tra server có đang shutdown không (từ chối WebSocket upgrade mới)
+#[derive(Message)]
+#[rtype(result = "bool")]
+pub struct IsDraining;
+
+/// Event: Yêu cầu session gửi Close frame và kết thúc connection
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct CloseSession {
+    /// Reason gửi kèm Close frame
+    pub reason: String,
+    /// Session bị thay thế bởi session mới hơn của cùng user (bỏ qua set offline)
+    pub superseded: bool,
+}
@@ -2,77 +2,91 @@
 ///
 /// Module này xử lý HTTP upgrade request và quản lý bidirectional message flow:
 /// - Inbound:  Client → WebSocket → parse ClientMessage → Session Actor
-/// - Outbound: Server Actor → Session Actor → mpsc channel → WebSocket → Client
+/// - Outbound: Server Actor → Session Actor → bounded queue → WebSocket → Client
+///
+/// Client đọc quá chậm làm outbound queue đầy thì bị disconnect (xem `backpressure`).
+/// Frames là JSON text hoặc MessagePack binary tùy subprotocol đã negotiate (xem `codec`).
+///
+/// Frame vượt `MAX_FRAME_SIZE`, không parse được hoặc không hợp lệ (ví dụ nội dung tin nhắn
+/// quá dài) được trả lời bằng `error` event có `code` thay vì chỉ log.
 use actix::Addr;
-use actix_web::{web, Error, HttpRequest, HttpResponse};
-use actix_ws::Message;
-use tokio::sync::mpsc;
-
-use super::message::ClientMessage;
-use super::presence::PresenceService;
+use actix_web::{
+    http::header::{HeaderValue, SEC_WEBSOCKET_PROTOCOL},
+    web, Error, HttpRequest, HttpResponse,
+};
+use actix_ws::{CloseCode, CloseReason, Closed, Message, ProtocolError, Session};
+use uuid::Uuid;
+
+use super::backpressure::{outbound_channel, SLOW_CLIENT_REASON};
+use super::codec::{self, Encoding, Frame};
+use super::events::{CloseSession, IsDraining};
+use super::message::{ClientMessage, ServerMessage};
 use super::server::WebSocketServer;
-use super::session::{MessageSvc, WebSocketSession};
-use crate::modules::friend::repository_pg::FriendRepositoryPg;
+use super::session::{Outbound, SessionServices, WebSocketSession};
+use crate::api::error::ErrorCode;
+
+/// Kích thước tối đa của một inbound frame (bytes)
+const MAX_FRAME_SIZE: usize = 64 * 1024;
 
 /// HTTP handler để upgrade connection thành WebSocket
 ///
 /// Endpoint: GET /ws
 ///
 /// Flow:
-/// 1. HTTP handshake → WebSocket connection
-/// 2. Tạo mpsc channel (session actor → client)
+/// 1. HTTP handshake → WebSocket connection (negotiate subprotocol `json` / `msgpack`)
+/// 2. Tạo outbound queue có giới hạn (session actor → client)
 /// 3. Start WebSocketSession actor
 /// 4. Spawn async task xử lý bidirectional messages
+///
+/// Trả về 503 nếu server đang shutdown (draining).
 pub async fn websocket_handler(
     req: HttpRequest,
     stream: web::Payload,
     server: web::Data<Addr<WebSocketServer>>,
-    message_service: web::Data<MessageSvc>,
-    presence_service: web::Data<PresenceService>,
-    friend_repo: web::Data<FriendRepositoryPg>,
+    services: SessionServices,
 ) -> Result<HttpResponse, Error> {
     tracing::debug!("WebSocket upgrade request từ {:?}", req.peer_addr());
 
-    // Thực hiện WebSocket handshake
-    let (response, mut ws_session, mut msg_stream) = actix_ws::handle(&req, stream)?;
-
-    // Tạo mpsc channel: session actor gửi JSON → spawned task → WebSocket → client
-    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    // Server đang shutdown: từ chối upgrade mới, client sẽ reconnect tới instance khác
+    if server.send(IsDraining).await.unwrap_or(true) {
+        return Ok(HttpResponse::ServiceUnavailable().finish());
+    }
+
+    // Thực hiện WebSocket handshake, echo subprotocol client đã chọn
+    let negotiated = Encoding::negotiate(&req);
+    let encoding = negotiated.unwrap_or_default();
+    let (mut response, mut ws_session, msg_stream) = actix_ws::handle(&req, stream)?;
+    let mut msg_stream = msg_stream.max_frame_size(MAX_FRAME_SIZE);
+    if let Some(encoding) = negotiated {
+        response
+            .headers_mut()
+            .insert(SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static(encoding.protocol()));
+    }
+
+    // Tạo outbound queue: session actor gửi frames → spawned task → WebSocket → client
+    let (tx, mut rx) = outbound_channel(Uuid::now_v7());
 
     // Tạo session actor với outbound channel và dependencies
-    let ws_actor = WebSocketSession::new(
-        server.get_ref().clone(),
-        tx,
-        message_service,
-        presence_service,
-        friend_repo,
-    );
+    let ws_actor = WebSocketSession::new(server.get_ref().clone(), tx, services);
 
     use actix::Actor;
     let addr = ws_actor.start();
 
     // Spawn async task xử lý bidirectional message flow
     actix_web::rt::spawn(async move {
+        let mut close_reason: Option<CloseReason> = None;
+
         loop {
             tokio::select! {
                 // === INBOUND: Client → Server ===
                 msg = msg_stream.recv() => {
                     match msg {
                         Some(Ok(Message::Text(text))) => {
-                            let text_str = text.to_string();
-
-                            // Parse và forward tới session actor
-                            match serde_json::from_str::<ClientMessage>(&text_str) {
-                                Ok(client_msg) => {
-                                    addr.do_send(client_msg);
-                                }
-                                Err(e) => {
-                                    tracing::warn!(
-                                        "Không thể parse client message: {} - raw: {}",
-                                        e,
-                                        &text_str[..100.min(text_str.len())]
-                                    );
-                                }
+                            // Parse, validate và forward tới session actor
+                            let decoded = serde_json::from_str::<ClientMessage>(&text)
+                                .map_err(|e| e.to_string());
+                            if dispatch(&addr, &mut ws_session, encoding, decoded).await.is_err() {
+                                break;
                             }
                         }
 
@@ -93,12 +107,39 @@ pub async fn websocket_handler(
                             break;
                         }
 
+                        Some(Ok(Message::Binary(bytes))) if encoding == Encoding::MsgPack => {
+                            let decoded = codec::decode_msgpack(&bytes).map_err(|e| e.to_string());
+                            if dispatch(&addr, &mut ws_session, encoding, decoded).await.is_err() {
+                                break;
+                            }
+                        }
+
                         Some(Ok(Message::Binary(_))) => {
-                            tracing::warn!("Binary messages không được hỗ trợ");
+                            let error = ServerMessage::error(
+                                ErrorCode::InvalidMessage,
+                                "Binary messages chỉ hỗ trợ với subprotocol msgpack",
+                            );
+                            if send_message(&mut ws_session, encoding, error).await.is_err() {
+                                break;
+                            }
                         }
 
                         Some(Ok(Message::Continuation(_) | Message::Nop)) => {}
 
+                        Some(Err(ProtocolError::Overflow)) => {
+                            tracing::warn!("WebSocket frame vượt quá {} bytes", MAX_FRAME_SIZE);
+                            let error = ServerMessage::error(
+                                ErrorCode::MessageTooLarge,
+                                format!("Message vượt quá {MAX_FRAME_SIZE} bytes"),
+                            );
+                            let _ = send_message(&mut ws_session, encoding, error).await;
+                            close_reason = Some(CloseReason {
+                                code: CloseCode::Size,
+                                description: Some("message too large".to_string()),
+                            });
+                            break;
+                        }
+
                         Some(Err(e)) => {
                             tracing::error!("WebSocket protocol error: {}", e);
                             break;
@@ -110,20 +151,102 @@ pub async fn websocket_handler(
                 }
 
                 // === OUTBOUND: Server → Client ===
-                Some(json) = rx.recv() => {
-                    if ws_session.text(json).await.is_err() {
-                        tracing::error!("Không thể gửi message tới WebSocket client");
-                        break;
+                outbound = rx.recv() => {
+                    match outbound {
+                        Some(Outbound::Frame(frame)) => {
+                            if send_frame(&mut ws_session, encoding, &frame).await.is_err() {
+                                tracing::error!("Không thể gửi message tới WebSocket client");
+                                break;
+                            }
+                        }
+
+                        // Server shutdown: các messages trước đó đã được gửi theo thứ tự channel
+                        Some(Outbound::Close(reason)) => {
+                            close_reason = Some(CloseReason {
+                                code: CloseCode::Restart,
+                                description: Some(reason),
+                            });
+                            break;
+                        }
+
+                        // Queue đầy (client đọc quá chậm) hoặc session actor đã dừng
+                        None => {
+                            if rx.is_lagging() {
+                                close_reason = Some(CloseReason {
+                                    code: CloseCode::Again,
+                                    description: Some(SLOW_CLIENT_REASON.to_string()),
+                                });
+                                addr.do_send(CloseSession {
+                                    reason: SLOW_CLIENT_REASON.to_string(),
+                                    superseded: false,
+                                });
+                            }
+                            break;
+                        }
                     }
                 }
             }
         }
 
         // Cleanup: đóng WebSocket session
-        let _ = ws_session.close(None).await;
+        let _ = ws_session.close(close_reason).await;
         tracing::debug!("WebSocket message loop kết thúc");
     });
 
     tracing::info!("WebSocket connection established");
     Ok(response)
 }
+
+/// Validate ClientMessage đã decode rồi forward tới session actor
+///
+/// Lỗi decode (JSON / MessagePack, UUID sai format, ...) hoặc validate được trả về
+/// client dưới dạng error event
+async fn dispatch(
+    addr: &Addr<WebSocketSession>,
+    ws_session: &mut Session,
+    encoding: Encoding,
+    decoded: Result<ClientMessage, String>,
+) -> Result<(), Closed> {
+    let error = match decoded {
+        Ok(client_msg) => match client_msg.validate() {
+            Ok(()) => {
+                addr.do_send(client_msg);
+                return Ok(());
+            }
+            Err(error) => *error,
+        },
+        Err(e) => {
+            tracing::debug!("Không thể parse client message: {}", e);
+            ServerMessage::error(ErrorCode::InvalidMessage, format!("Message không hợp lệ: {e}"))
+        }
+    };
+
+    send_message(ws_session, encoding, error).await
+}
+
+/// Gửi ServerMessage trực tiếp tới client (không qua session actor, không đánh seq)
+async fn send_message(
+    ws_session: &mut Session,
+    encoding: Encoding,
+    message: ServerMessage,
+) -> Result<(), Closed> {
+    send_frame(ws_session, encoding, &Frame::new(message)).await
+}
+
+/// Gửi frame tới client theo encoding đã negotiate, frame không encode được thì bỏ qua
+async fn send_frame(
+    ws_session: &mut Session,
+    encoding: Encoding,
+    frame: &Frame,
+) -> Result<(), Closed> {
+    match encoding {
+        Encoding::Json => match frame.json() {
+            Some(json) => ws_session.text(json.to_owned()).await,
+            None => Ok(()),
+        },
+        Encoding::MsgPack => match frame.msgpack() {
+            Some(bytes) => ws_session.binary(bytes).await,
+            None => Ok(()),
+        },
+    }
+}
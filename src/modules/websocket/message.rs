@@ -6,15 +6,34 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::presence::PresenceStatus;
+use crate::api::error::ErrorCode;
+use crate::modules::call::schema::{CallStatus, CallType};
+use crate::modules::conversation::schema::NotificationLevel;
+use crate::modules::message::model::ClientMetadata;
+use crate::modules::message::schema::MessageEntity;
+
 /// Messages được gửi từ client đến server
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ClientMessage {
-    /// Xác thực WebSocket connection với JWT token
-    Auth { token: String },
+    /// Xác thực WebSocket connection với JWT token (kèm client metadata optional)
+    Auth {
+        token: String,
+        #[serde(default)]
+        client: Option<ClientMetadata>,
+        /// Resume session trước đó sau khi mất kết nối ngắn
+        #[serde(default)]
+        resume: Option<ResumeRequest>,
+    },
 
-    /// Gửi tin nhắn đến conversation
-    SendMessage { conversation_id: Uuid, content: String },
+    /// Gửi tin nhắn đến conversation, `client_message_id` là idempotency key cho retry
+    SendMessage {
+        conversation_id: Uuid,
+        content: String,
+        #[serde(default)]
+        client_message_id: Option<Uuid>,
+    },
 
     /// Tham gia vào conversation room để nhận real-time updates
     JoinConversation { conversation_id: Uuid },
@@ -28,10 +47,130 @@ pub enum ClientMessage {
     /// Dừng typing trong conversation
     TypingStop { conversation_id: Uuid },
 
+    /// Xác nhận đã nhận message (qua new-message event) để sender hiển thị delivered
+    Ack { message_id: Uuid },
+
+    /// Yêu cầu replay các events có `user_seq` > `last_seq` (khi thấy `user_seq` nhảy cóc
+    /// hoặc sau khi reconnect)
+    Sync { last_seq: u64 },
+
+    /// Đặt custom status (busy / away + text, emoji), `available` không kèm text để xóa
+    SetStatus {
+        status: PresenceStatus,
+        #[serde(default)]
+        text: Option<String>,
+        #[serde(default)]
+        emoji: Option<String>,
+    },
+
+    /// Gọi (WebRTC) tới user còn lại của direct conversation, kèm SDP offer
+    CallOffer {
+        conversation_id: Uuid,
+        callee_id: Uuid,
+        #[serde(default = "default_call_type")]
+        call_type: CallType,
+        sdp: String,
+    },
+
+    /// Callee trả lời cuộc gọi, kèm SDP answer
+    CallAnswer { call_id: Uuid, sdp: String },
+
+    /// ICE candidate (trickle ICE), relay nguyên văn tới user còn lại của cuộc gọi
+    IceCandidate { call_id: Uuid, candidate: serde_json::Value },
+
+    /// Hủy / từ chối / kết thúc cuộc gọi
+    CallEnd { call_id: Uuid },
+
+    /// Tham gia cuộc gọi nhóm của conversation (bắt đầu cuộc gọi nếu chưa có ai)
+    JoinGroupCall { conversation_id: Uuid },
+
+    /// Rời cuộc gọi nhóm
+    LeaveGroupCall { conversation_id: Uuid },
+
     /// Ping để giữ connection alive
     Ping,
 }
 
+/// Độ dài tối đa nội dung tin nhắn gửi qua WebSocket (ký tự), giống REST API
+pub const MAX_MESSAGE_CONTENT_LEN: usize = 5000;
+
+/// Kích thước tối đa của SDP offer / answer (bytes)
+pub const MAX_SDP_LEN: usize = 32 * 1024;
+
+fn default_call_type() -> CallType {
+    CallType::Video
+}
+
+impl ClientMessage {
+    /// Kiểm tra nội dung message trước khi dispatch tới session actor
+    ///
+    /// Format (UTF-8, UUID) đã được kiểm tra khi deserialize
+    pub fn validate(&self) -> Result<(), Box<ServerMessage>> {
+        match self {
+            ClientMessage::SendMessage { content, .. } => {
+                if content.trim().is_empty() {
+                    return Err(Box::new(ServerMessage::error(
+                        ErrorCode::InvalidContent,
+                        "Nội dung tin nhắn không được để trống",
+                    )));
+                }
+                if content.chars().count() > MAX_MESSAGE_CONTENT_LEN {
+                    return Err(Box::new(ServerMessage::error(
+                        ErrorCode::InvalidContent,
+                        format!("Nội dung tin nhắn tối đa {MAX_MESSAGE_CONTENT_LEN} ký tự"),
+                    )));
+                }
+                if content.contains('\0') {
+                    return Err(Box::new(ServerMessage::error(
+                        ErrorCode::InvalidContent,
+                        "Nội dung tin nhắn chứa ký tự không hợp lệ",
+                    )));
+                }
+                Ok(())
+            }
+            ClientMessage::CallOffer { sdp, .. } | ClientMessage::CallAnswer { sdp, .. } => {
+                if sdp.trim().is_empty() || sdp.len() > MAX_SDP_LEN {
+                    return Err(Box::new(ServerMessage::error(
+                        ErrorCode::InvalidMessage,
+                        "SDP không hợp lệ",
+                    )));
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Conversation mà message thao tác trên, `None` với các message không gắn với
+    /// conversation (auth, ack, call signaling theo call_id...)
+    pub fn conversation_id(&self) -> Option<Uuid> {
+        match self {
+            ClientMessage::SendMessage { conversation_id, .. }
+            | ClientMessage::JoinConversation { conversation_id }
+            | ClientMessage::LeaveConversation { conversation_id }
+            | ClientMessage::TypingStart { conversation_id }
+            | ClientMessage::TypingStop { conversation_id }
+            | ClientMessage::CallOffer { conversation_id, .. }
+            | ClientMessage::JoinGroupCall { conversation_id }
+            | ClientMessage::LeaveGroupCall { conversation_id } => Some(*conversation_id),
+            _ => None,
+        }
+    }
+}
+
+/// Thông tin resume gửi kèm Auth khi client reconnect
+///
+/// Server thay thế session cũ (đang detached) rồi replay các events có `user_seq` >
+/// `last_seq` như `sync`. Events vừa được gán seq có thể đến lại sau replay, client bỏ trùng
+/// theo `user_seq`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ResumeRequest {
+    /// Session ID nhận được trong auth-success của connection trước
+    pub session_id: Uuid,
+    /// `user_seq` lớn nhất client đã nhận
+    pub last_seq: u64,
+}
+
 /// Thông tin last message gọn nhẹ để gửi trong events
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LastMessageInfo {
@@ -50,13 +189,40 @@ pub struct LastMessageInfo {
 pub struct SenderInfo {
     /// User ID
     pub _id: Uuid,
-    /// Display name (có thể empty trong một số context)
+    /// Display name (empty nếu không tra được profile của sender)
     #[serde(default)]
     pub display_name: String,
     /// Avatar URL
     pub avatar_url: Option<String>,
 }
 
+impl SenderInfo {
+    /// Sender chỉ có ID, dùng khi không tra được profile
+    pub fn unknown(user_id: Uuid) -> Self {
+        SenderInfo { _id: user_id, display_name: String::new(), avatar_url: None }
+    }
+}
+
+/// Tra thông tin sender để đính kèm vào `new-message` / `read-message`, implement bởi
+/// `UserService` (local cache → Redis → Postgres)
+#[async_trait::async_trait]
+pub trait SenderResolver: Send + Sync {
+    /// Không bao giờ lỗi: profile không tra được thì trả về `SenderInfo::unknown`
+    async fn resolve_sender(&self, user_id: Uuid) -> SenderInfo;
+
+    /// Sender của message, ưu tiên tên / avatar override của incoming webhook
+    async fn resolve_message_sender(&self, message: &MessageEntity) -> SenderInfo {
+        match &message.sender_name_override {
+            Some(display_name) => SenderInfo {
+                _id: message.sender_id,
+                display_name: display_name.clone(),
+                avatar_url: message.sender_avatar_override.clone(),
+            },
+            None => self.resolve_sender(message.sender_id).await,
+        }
+    }
+}
+
 /// Thông tin conversation gọn nhẹ để gửi trong new-message event
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConversationInfo {
@@ -92,8 +258,8 @@ pub struct ReadMessagePayload {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "kebab-case")]
 pub enum ServerMessage {
-    /// Xác thực thành công
-    AuthSuccess { user_id: Uuid },
+    /// Xác thực thành công (client giữ `session_id` để resume khi reconnect)
+    AuthSuccess { user_id: Uuid, session_id: Uuid },
 
     /// Xác thực thất bại
     AuthFailed { reason: String },
@@ -105,9 +271,61 @@ pub enum ServerMessage {
     /// Tin nhắn đã được chỉnh sửa
     MessageEdited { conversation_id: Uuid, message_id: Uuid, new_content: String },
 
-    /// Tin nhắn đã bị xóa
+    /// Recipient đã nhận tin nhắn (gửi riêng cho sender)
+    MessageDelivered {
+        conversation_id: Uuid,
+        message_id: Uuid,
+        user_id: Uuid,
+        delivered_at: String,
+    },
+
+    /// Tin nhắn đã bị xóa với mọi người, hiển thị tombstone
     MessageDeleted { conversation_id: Uuid, message_id: Uuid },
 
+    /// User đã xóa tin nhắn phía mình (từ thiết bị khác), bỏ khỏi lịch sử
+    MessageDeletedForMe { conversation_id: Uuid, message_id: Uuid },
+
+    /// Tin nhắn bị ẩn do nhận quá nhiều report
+    MessageHidden { conversation_id: Uuid, message_id: Uuid },
+
+    /// Tin nhắn trùng lặp đã được gộp vào message trước đó (tăng repeat counter)
+    MessageRepeated { conversation_id: Uuid, message_id: Uuid, repeat_count: i32 },
+
+    /// Phản hồi ephemeral của slash command (chỉ gửi cho session đã gửi lệnh)
+    CommandReply { conversation_id: Option<Uuid>, command: String, text: String },
+
+    /// Cuộc gọi đã được tạo và đang đổ chuông ở callee (gửi cho session đã gọi)
+    CallRinging { call_id: Uuid, conversation_id: Uuid, callee_id: Uuid },
+
+    /// Cuộc gọi đến (gửi tới mọi session của callee)
+    CallIncoming {
+        call_id: Uuid,
+        conversation_id: Uuid,
+        caller_id: Uuid,
+        call_type: CallType,
+        sdp: String,
+    },
+
+    /// Callee đã trả lời (gửi cho caller và các session khác của callee)
+    CallAnswered { call_id: Uuid, answered_by: Uuid, sdp: String },
+
+    /// ICE candidate từ user còn lại của cuộc gọi
+    IceCandidate { call_id: Uuid, from_user_id: Uuid, candidate: serde_json::Value },
+
+    /// Cuộc gọi đã kết thúc (`ended_by` là `None` khi hết thời gian đổ chuông)
+    CallEnded {
+        call_id: Uuid,
+        status: CallStatus,
+        ended_by: Option<Uuid>,
+        duration_secs: Option<i32>,
+    },
+
+    /// User đã join cuộc gọi nhóm, kèm danh sách users đang ở trong cuộc gọi
+    GroupCallJoined { conversation_id: Uuid, user_id: Uuid, participant_ids: Vec<Uuid> },
+
+    /// User đã rời cuộc gọi nhóm (`participant_ids` rỗng khi cuộc gọi kết thúc)
+    GroupCallLeft { conversation_id: Uuid, user_id: Uuid, participant_ids: Vec<Uuid> },
+
     /// User đã đọc messages (read receipt) - format tương thích Socket.IO
     ReadMessage(ReadMessagePayload),
 
@@ -123,23 +341,114 @@ pub enum ServerMessage {
     /// Một user vừa offline (incremental update)
     UserOffline { user_id: Uuid, last_seen: Option<String> },
 
+    /// Friend (hoặc thiết bị khác của chính user) vừa đổi custom status
+    PresenceStatusChanged {
+        user_id: Uuid,
+        status: PresenceStatus,
+        text: Option<String>,
+        emoji: Option<String>,
+    },
+
+    /// User được mention (@username) trong tin nhắn, gửi kể cả khi conversation bị mute
+    Mentioned { conversation_id: Uuid, message_id: Uuid, mentioned_by: Uuid },
+
     /// Group chat mới được tạo
     NewGroup { conversation: serde_json::Value },
 
+    /// Tên / mô tả group được thay đổi
+    GroupUpdated {
+        conversation_id: Uuid,
+        name: String,
+        description: Option<String>,
+        updated_by: Uuid,
+    },
+
+    /// User được mời vào group bởi người không phải bạn bè (chờ chấp nhận)
+    ConversationInvite { conversation_id: Uuid, invited_by: Uuid, group_name: String },
+
+    /// Danh sách users đang mở conversation (gửi khi có user mở / rời)
+    ConversationPresence { conversation_id: Uuid, active_user_ids: Vec<Uuid> },
+
     /// User bắt đầu typing
     UserTyping { conversation_id: Uuid, user_id: Uuid },
 
     /// User ngừng typing
     UserStoppedTyping { conversation_id: Uuid, user_id: Uuid },
 
+    /// User đã archive / ghim conversation trên thiết bị khác
+    ConversationUpdated { conversation_id: Uuid, is_archived: bool, is_pinned: bool },
+
+    /// User đã đổi mức push notification của conversation trên thiết bị khác
+    NotificationLevelUpdated { conversation_id: Uuid, level: NotificationLevel },
+
+    /// Unread count của conversation bị lệch và đã được server tính lại
+    UnreadCountCorrected { conversation_id: Uuid, unread_count: i32 },
+
+    /// Bản nháp của conversation đã thay đổi trên thiết bị khác (`content` None = đã xóa)
+    DraftUpdated { conversation_id: Uuid, content: Option<String>, updated_at: String },
+
+    /// Người gửi đã thu hồi lời mời kết bạn
+    FriendRequestCancelled { request_id: Uuid, from_user_id: Uuid },
+
+    /// Friend hoặc thành viên conversation chung vừa cập nhật profile (client cập nhật
+    /// lại thông tin sender / participant đã cache)
+    ProfileUpdated {
+        user_id: Uuid,
+        username: String,
+        display_name: String,
+        avatar_url: Option<String>,
+    },
+
+    /// Tài khoản trùng đã được merge vào tài khoản chính (client cần reload dữ liệu)
+    AccountMerged { primary_id: Uuid, duplicate_id: Uuid },
+
+    /// Admin đã force sign-out hoặc ban user (client cần xóa token và đăng xuất)
+    ForceSignOut,
+
+    /// Thông báo toàn hệ thống từ admin (client hiển thị cho tới khi user xác nhận)
+    Announcement { announcement_id: Uuid, title: String, content: String, created_at: String },
+
+    /// Resume thành công, đã replay `replayed` events bị lỡ, `last_seq` là `user_seq` mới
+    /// nhất
+    Resumed { replayed: usize, last_seq: u64 },
+
+    /// Resume thất bại (session hết hạn, events đã hết hạn, ...), client cần fetch lại state
+    /// qua REST
+    ResumeFailed { reason: String },
+
+    /// Sync thành công, đã replay `replayed` events bị lỡ, `last_seq` là `user_seq` mới nhất
+    Synced { replayed: usize, last_seq: u64 },
+
+    /// Không còn đủ events để sync, client cần fetch lại state qua REST rồi tiếp tục từ
+    /// `last_seq`
+    ResyncRequired { last_seq: u64 },
+
     /// Pong response cho Ping
     Pong,
 
     /// Lỗi xảy ra
-    Error { message: String },
+    Error { code: ErrorCode, message: String },
 }
 
 impl ServerMessage {
+    /// Tạo error event
+    pub fn error(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self::Error { code, message: message.into() }
+    }
+
+    /// Event chỉ có ý nghĩa tức thời (typing, signaling, ...), không cần replay cho client
+    /// kết nối lại sau
+    pub fn is_ephemeral(&self) -> bool {
+        matches!(
+            self,
+            Self::UserTyping { .. }
+                | Self::UserStoppedTyping { .. }
+                | Self::ConversationPresence { .. }
+                | Self::IceCandidate { .. }
+                | Self::CommandReply { .. }
+        )
+    }
+
     /// Tạo new-message event với format tương thích Socket.IO
     #[must_use]
     pub fn new_message(
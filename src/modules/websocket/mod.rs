@@ -7,9 +7,26 @@
 /// - WebSocket Server actor (quản lý connections và rooms)
 /// - WebSocket Session actor (xử lý từng connection)
 /// - HTTP handler (upgrade HTTP thành WebSocket)
+/// - Codec (negotiate JSON / MessagePack frames)
+/// - Event dispatcher (outbox Postgres cho broadcasts sau commit, at-least-once)
+/// - Event buffer (user_seq theo user, Redis buffer cho sync / resume / long-poll khi mất
+///   WebSocket)
+/// - Fan-out bridge (Redis pub/sub giữa các server instances)
+/// - Graceful shutdown (đóng sessions với Close frame khi nhận SIGTERM)
+/// - Socket.IO adapter (endpoint tương thích socket.io clients)
+/// - Backpressure (outbound queue có giới hạn cho client đọc chậm)
+/// - Call rooms (ai đang ở trong cuộc gọi nhóm của mỗi conversation)
+pub mod backpressure;
+pub mod bridge;
+pub mod call_room;
+pub mod codec;
+pub mod dispatcher;
+pub mod event_buffer;
 pub mod events;
 pub mod handler;
 pub mod message;
 pub mod presence;
 pub mod server;
 pub mod session;
+pub mod shutdown;
+pub mod socketio;
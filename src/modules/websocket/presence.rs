@@ -11,10 +11,18 @@
 /// Redis key schema:
 /// - `presence:{user_id}` → "1" (TTL 60s) - user đang online
 /// - `last_seen:{user_id}` → ISO 8601 timestamp - thời điểm offline cuối cùng
+/// - `presence_visibility:{user_id}` → everyone | friends | nobody (bản sao của setting
+///   trong Postgres, đồng bộ lại mỗi lần user kết nối; thiếu key = everyone)
+/// - `presence_status:{user_id}` → JSON `CustomStatus` (busy / away + text, emoji)
+/// - `conversation_active:{conversation_id}` → ZSET user_id (score = lần refresh cuối),
+///   users đang mở conversation; entry quá PRESENCE_TTL bị coi là đã rời
+use std::collections::HashSet;
+
 use deadpool_redis::redis::{self, AsyncCommands};
 use uuid::Uuid;
 
 use crate::api::error;
+use crate::modules::user::schema::PresenceVisibility;
 
 /// TTL cho presence key (giây). Được refresh mỗi HEARTBEAT_INTERVAL (15s).
 /// Nếu client mất kết nối mà server không nhận được disconnect,
@@ -23,6 +31,9 @@ const PRESENCE_TTL: u64 = 60;
 
 const PRESENCE_PREFIX: &str = "presence:";
 const LAST_SEEN_PREFIX: &str = "last_seen:";
+const VISIBILITY_PREFIX: &str = "presence_visibility:";
+const STATUS_PREFIX: &str = "presence_status:";
+const CONVERSATION_ACTIVE_PREFIX: &str = "conversation_active:";
 
 /// Service quản lý presence state trong Redis
 #[derive(Clone)]
@@ -134,28 +145,240 @@ impl PresenceService {
                 user_id: *user_id,
                 is_online,
                 last_seen,
+                status: None,
+                dnd: false,
             });
         }
 
         Ok(results)
     }
 
-    /// Lấy last_seen của 1 user
-    pub async fn get_last_seen(
+    /// Batch query presence theo góc nhìn của `viewer_id`: user chọn `friends` chỉ hiện
+    /// với bạn bè, `nobody` luôn hiện offline và không có last_seen (trừ chính mình).
+    ///
+    /// `get_online_status_batch` trả về trạng thái thật, chỉ dùng nội bộ (push, ...).
+    pub async fn get_presence_for_viewer(
+        &self,
+        viewer_id: Uuid,
+        viewer_friend_ids: &HashSet<Uuid>,
+        user_ids: &[Uuid],
+    ) -> Result<Vec<PresenceInfo>, error::SystemError> {
+        let (mut statuses, visibilities, custom_statuses) = tokio::try_join!(
+            self.get_online_status_batch(user_ids),
+            self.get_visibility_batch(user_ids),
+            self.get_status_batch(user_ids),
+        )?;
+
+        for ((status, visibility), custom) in
+            statuses.iter_mut().zip(visibilities).zip(custom_statuses)
+        {
+            let visible = status.user_id == viewer_id
+                || match visibility {
+                    PresenceVisibility::Everyone => true,
+                    PresenceVisibility::Friends => viewer_friend_ids.contains(&status.user_id),
+                    PresenceVisibility::Nobody => false,
+                };
+            if visible {
+                status.status = custom;
+            } else {
+                status.is_online = false;
+                status.last_seen = None;
+            }
+        }
+
+        Ok(statuses)
+    }
+
+    /// Lưu presence visibility của user (không có TTL)
+    pub async fn set_visibility(
+        &self,
+        user_id: Uuid,
+        visibility: PresenceVisibility,
+    ) -> Result<(), error::SystemError> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("{VISIBILITY_PREFIX}{user_id}");
+        conn.set::<_, _, ()>(&key, visibility_as_str(visibility)).await?;
+        Ok(())
+    }
+
+    /// Batch query presence visibility, cùng thứ tự với `user_ids`
+    pub async fn get_visibility_batch(
+        &self,
+        user_ids: &[Uuid],
+    ) -> Result<Vec<PresenceVisibility>, error::SystemError> {
+        if user_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut conn = self.pool.get().await?;
+        let keys: Vec<String> =
+            user_ids.iter().map(|user_id| format!("{VISIBILITY_PREFIX}{user_id}")).collect();
+        let values: Vec<Option<String>> =
+            redis::cmd("MGET").arg(&keys).query_async(&mut *conn).await?;
+
+        Ok(values.iter().map(|value| parse_visibility(value.as_deref())).collect())
+    }
+
+    /// Lưu custom status của user, status mặc định (available, không text / emoji) xóa key
+    pub async fn set_status(
+        &self,
+        user_id: Uuid,
+        status: &CustomStatus,
+    ) -> Result<(), error::SystemError> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("{STATUS_PREFIX}{user_id}");
+        if status.is_default() {
+            conn.del::<_, ()>(&key).await?;
+        } else {
+            conn.set::<_, _, ()>(&key, serde_json::to_string(status)?).await?;
+        }
+        Ok(())
+    }
+
+    /// Batch query custom status, cùng thứ tự với `user_ids` (`None` = chưa đặt)
+    pub async fn get_status_batch(
+        &self,
+        user_ids: &[Uuid],
+    ) -> Result<Vec<Option<CustomStatus>>, error::SystemError> {
+        if user_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut conn = self.pool.get().await?;
+        let keys: Vec<String> =
+            user_ids.iter().map(|user_id| format!("{STATUS_PREFIX}{user_id}")).collect();
+        let values: Vec<Option<String>> =
+            redis::cmd("MGET").arg(&keys).query_async(&mut *conn).await?;
+
+        Ok(values
+            .iter()
+            .map(|value| value.as_deref().and_then(|v| serde_json::from_str(v).ok()))
+            .collect())
+    }
+
+    /// Đánh dấu user đang mở conversation, trả về danh sách users đang active
+    pub async fn mark_conversation_active(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Vec<Uuid>, error::SystemError> {
+        self.update_conversation_active(conversation_id, user_id, true).await
+    }
+
+    /// Đánh dấu user đã rời conversation, trả về danh sách users còn active
+    pub async fn mark_conversation_inactive(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Vec<Uuid>, error::SystemError> {
+        self.update_conversation_active(conversation_id, user_id, false).await
+    }
+
+    /// Refresh các conversations user đang mở (gọi mỗi heartbeat interval)
+    pub async fn refresh_conversations_active(
         &self,
+        conversation_ids: &[Uuid],
         user_id: Uuid,
-    ) -> Result<Option<String>, error::SystemError> {
+    ) -> Result<(), error::SystemError> {
+        if conversation_ids.is_empty() {
+            return Ok(());
+        }
+
         let mut conn = self.pool.get().await?;
-        let key = format!("{LAST_SEEN_PREFIX}{user_id}");
-        let last_seen: Option<String> = conn.get(&key).await?;
-        Ok(last_seen)
+        let now = chrono::Utc::now().timestamp();
+        let member = user_id.to_string();
+
+        let mut pipe = redis::pipe();
+        for conversation_id in conversation_ids {
+            let key = format!("{CONVERSATION_ACTIVE_PREFIX}{conversation_id}");
+            pipe.zadd(&key, &member, now).ignore();
+            pipe.expire(&key, PRESENCE_TTL as i64).ignore();
+        }
+        pipe.query_async::<()>(&mut *conn).await?;
+
+        Ok(())
+    }
+
+    async fn update_conversation_active(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        active: bool,
+    ) -> Result<Vec<Uuid>, error::SystemError> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("{CONVERSATION_ACTIVE_PREFIX}{conversation_id}");
+        let now = chrono::Utc::now().timestamp();
+        let member = user_id.to_string();
+
+        // Pipeline: cập nhật user, dọn entries quá hạn rồi đọc danh sách còn lại
+        let mut pipe = redis::pipe();
+        if active {
+            pipe.zadd(&key, &member, now).ignore();
+            pipe.expire(&key, PRESENCE_TTL as i64).ignore();
+        } else {
+            pipe.zrem(&key, &member).ignore();
+        }
+        pipe.zrembyscore(&key, "-inf", now - PRESENCE_TTL as i64).ignore();
+        pipe.zrange(&key, 0, -1);
+        let (members,): (Vec<String>,) = pipe.query_async(&mut *conn).await?;
+
+        Ok(members.iter().filter_map(|m| Uuid::parse_str(m).ok()).collect())
+    }
+
+}
+
+fn visibility_as_str(visibility: PresenceVisibility) -> &'static str {
+    match visibility {
+        PresenceVisibility::Everyone => "everyone",
+        PresenceVisibility::Friends => "friends",
+        PresenceVisibility::Nobody => "nobody",
+    }
+}
+
+fn parse_visibility(value: Option<&str>) -> PresenceVisibility {
+    match value {
+        Some("friends") => PresenceVisibility::Friends,
+        Some("nobody") => PresenceVisibility::Nobody,
+        _ => PresenceVisibility::Everyone,
+    }
+}
+
+/// Trạng thái user tự đặt, độc lập với online / offline
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum PresenceStatus {
+    #[default]
+    Available,
+    Busy,
+    Away,
+}
+
+/// Custom status: trạng thái kèm text / emoji tùy chọn
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct CustomStatus {
+    pub status: PresenceStatus,
+    pub text: Option<String>,
+    pub emoji: Option<String>,
+}
+
+impl CustomStatus {
+    fn is_default(&self) -> bool {
+        self.status == PresenceStatus::Available && self.text.is_none() && self.emoji.is_none()
     }
 }
 
 /// Thông tin presence của 1 user
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 pub struct PresenceInfo {
     pub user_id: Uuid,
     pub is_online: bool,
     pub last_seen: Option<String>,
+    /// Custom status (chỉ có trong kết quả `get_presence_for_viewer`)
+    #[serde(default)]
+    pub status: Option<CustomStatus>,
+    /// Đang trong giờ Do Not Disturb và cho phép hiện badge (chỉ với bạn bè / chính mình)
+    #[serde(default)]
+    pub dnd: bool,
 }
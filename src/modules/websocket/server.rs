@@ -3,13 +3,55 @@
 /// Server actor chịu trách nhiệm quản lý tất cả WebSocket connections,
 /// user sessions, và conversation rooms. Nó xử lý routing messages
 /// giữa các clients và maintain state của hệ thống real-time.
+///
+/// Khi có `FanoutBridge`, các events routing được deliver tới local sessions rồi
+/// publish cho các instances khác (xem `bridge`).
+///
+/// Khi có `EventBuffer`, events gửi tới users đang có session trên instance được gán
+/// `user_seq` theo user và ghi vào buffer theo user, client lấy lại events bị lỡ bằng
+/// `sync` hoặc long-poll (xem `event_buffer`). Session đã xác thực mất kết nối được giữ ở
+/// trạng thái detached trong `RESUME_WINDOW` để buffer tiếp tục ghi events cho user, client
+/// reconnect resume bằng `user_seq` cuối cùng đã nhận (như `sync`).
+///
+/// Số sessions bị giới hạn (`with_limits`): quá tổng số sessions thì connection mới bị
+/// từ chối, quá số sessions của một user thì session cũ nhất của user đó bị đóng.
 use actix::prelude::*;
 use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+use super::bridge::{FanoutBridge, FanoutEvent};
+use super::call_room::{CallRoomChange, CallRooms};
+use super::codec::Frame;
+use super::event_buffer::{EventBuffer, EventSequencer, SequenceJob};
 use super::events::*;
-use super::message::ServerMessage;
+use super::message::{ResumeRequest, ServerMessage};
 use super::session::WebSocketSession;
+use crate::api::error::ErrorCode;
+
+/// Close reason gửi cho sessions khi server shutdown
+pub const SHUTDOWN_REASON: &str = "server restarting";
+
+/// Close reason khi server đã đạt giới hạn tổng số sessions
+pub const CAPACITY_REASON: &str = "server at capacity";
+
+/// Close reason cho session cũ bị đóng khi user vượt giới hạn sessions
+pub const SESSION_LIMIT_REASON: &str = "session limit exceeded";
+
+/// Thời gian giữ detached session chờ client resume
+pub const RESUME_WINDOW: Duration = Duration::from_secs(120);
+
+/// Chu kỳ dọn các detached sessions đã quá RESUME_WINDOW
+const DETACHED_SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Số events tối đa replay cho một lần sync (lớn hơn kích thước event buffer)
+const SYNC_REPLAY_LIMIT: usize = 1000;
+
+/// Session đã mất kết nối nhưng user vẫn được ghi events vào buffer, chờ client resume
+struct DetachedSession {
+    user_id: Uuid,
+    since: Instant,
+}
 
 /// WebSocket server quản lý tất cả client sessions và conversation rooms
 pub struct WebSocketServer {
@@ -24,75 +66,169 @@ pub struct WebSocketServer {
     /// Map: conversation_id -> set of user_ids
     /// Track users nào đang ở trong room nào để broadcast messages
     rooms: HashMap<Uuid, HashSet<Uuid>>,
+
+    /// Cuộc gọi nhóm đang diễn ra theo conversation
+    call_rooms: CallRooms,
+
+    /// Map: session_id -> detached session đang chờ resume
+    detached: HashMap<Uuid, DetachedSession>,
+
+    /// Redis pub/sub bridge cho multi-instance (None = chỉ chạy một instance)
+    bridge: Option<FanoutBridge>,
+
+    /// Redis buffer events theo user cho sync / resume / long-poll (None = không gán
+    /// user_seq, không hỗ trợ resume)
+    event_buffer: Option<EventBuffer>,
+
+    /// Gán user_seq cho events, khởi động cùng server khi có event buffer
+    sequencer: Option<EventSequencer>,
+
+    /// Server đang shutdown: từ chối connections mới
+    draining: bool,
+
+    /// Số sessions tối đa của một user (bao gồm detached sessions)
+    max_sessions_per_user: usize,
+
+    /// Số sessions đang kết nối tối đa trên instance này
+    max_total_sessions: usize,
 }
 
 impl WebSocketServer {
     /// Tạo WebSocket server mới với state rỗng
     pub fn new() -> Self {
-        Self { sessions: HashMap::new(), users: HashMap::new(), rooms: HashMap::new() }
+        Self {
+            sessions: HashMap::new(),
+            users: HashMap::new(),
+            rooms: HashMap::new(),
+            call_rooms: CallRooms::default(),
+            detached: HashMap::new(),
+            bridge: None,
+            event_buffer: None,
+            sequencer: None,
+            draining: false,
+            max_sessions_per_user: usize::MAX,
+            max_total_sessions: usize::MAX,
+        }
+    }
+
+    /// Gắn fan-out bridge để routing events tới sessions trên các instances khác
+    pub fn with_bridge(mut self, bridge: FanoutBridge) -> Self {
+        self.bridge = Some(bridge);
+        self
+    }
+
+    /// Gán user_seq cho events gửi tới users và ghi vào buffer cho sync / resume / long-poll
+    pub fn with_event_buffer(mut self, event_buffer: EventBuffer) -> Self {
+        self.event_buffer = Some(event_buffer);
+        self
+    }
+
+    /// Giới hạn số sessions mỗi user và tổng số sessions (mặc định không giới hạn)
+    pub fn with_limits(mut self, max_sessions_per_user: usize, max_total_sessions: usize) -> Self {
+        self.max_sessions_per_user = max_sessions_per_user.max(1);
+        self.max_total_sessions = max_total_sessions.max(1);
+        self
+    }
+
+    /// User có ít nhất một session đang kết nối (không tính detached sessions)
+    fn is_connected(&self, user_id: &Uuid) -> bool {
+        self.users
+            .get(user_id)
+            .is_some_and(|sessions| sessions.iter().any(|id| self.sessions.contains_key(id)))
     }
 
     /// Lấy danh sách user IDs đang online
     fn get_online_users(&self) -> Vec<Uuid> {
-        self.users.keys().copied().collect()
+        self.users.keys().filter(|user_id| self.is_connected(user_id)).copied().collect()
+    }
+
+    /// Session IDs của một user (bao gồm detached sessions)
+    fn session_ids_of(&self, user_id: &Uuid) -> Vec<Uuid> {
+        self.users
+            .get(user_id)
+            .map(|sessions| sessions.iter().copied().collect())
+            .unwrap_or_default()
     }
 
     /// Gửi message tới một session cụ thể
-    fn send_to_session(&self, session_id: &Uuid, message: ServerMessage) {
+    fn send_to_session(&mut self, session_id: &Uuid, message: ServerMessage) {
         if let Some(session_addr) = self.sessions.get(session_id) {
             session_addr.do_send(message);
         }
     }
 
-    /// Gửi message tới tất cả sessions của một user (multi-device)
-    fn send_to_user(&self, user_id: &Uuid, message: ServerMessage) {
-        if let Some(session_ids) = self.users.get(user_id) {
-            for session_id in session_ids {
-                self.send_to_session(session_id, message.clone());
-            }
+    /// Gửi frame dùng chung tới một session
+    fn send_frame_to_session(&self, session_id: &Uuid, frame: Frame) {
+        if let Some(session_addr) = self.sessions.get(session_id) {
+            session_addr.do_send(OutboundFrame { frame });
         }
     }
-}
 
-impl Actor for WebSocketServer {
-    type Context = Context<Self>;
+    /// Gửi event tới sessions của các users trên instance này, bỏ qua users không có
+    /// session. Khi có sequencer, event (trừ events tức thời) được gán user_seq trước rồi
+    /// mới gửi qua `DeliverSequenced`, nên có thể đến sau các events gửi thẳng tới session
+    fn send_to_targets(
+        &mut self,
+        event_id: Uuid,
+        targets: Vec<(Uuid, Vec<Uuid>)>,
+        message: &ServerMessage,
+    ) {
+        let targets: Vec<(Uuid, Vec<Uuid>)> =
+            targets.into_iter().filter(|(user_id, _)| self.users.contains_key(user_id)).collect();
+        if targets.is_empty() {
+            return;
+        }
 
-    fn started(&mut self, _ctx: &mut Self::Context) {
-        tracing::info!("WebSocket server started");
-    }
+        let job = SequenceJob { event_id, targets, message: message.clone() };
+        let job = match &self.sequencer {
+            Some(sequencer) if !message.is_ephemeral() => match sequencer.enqueue(job) {
+                Ok(()) => return,
+                Err(job) => *job,
+            },
+            _ => job,
+        };
 
-    fn stopped(&mut self, _ctx: &mut Self::Context) {
-        tracing::info!("WebSocket server stopped");
+        self.deliver_sequenced(job.targets, job.message, None);
     }
-}
-
-/// Handler: User mới connected
-impl Handler<Connect> for WebSocketServer {
-    type Result = ();
 
-    fn handle(&mut self, msg: Connect, _: &mut Context<Self>) {
-        tracing::debug!("New WebSocket session connected: {}", msg.id);
-
-        // Lưu session vào map
-        self.sessions.insert(msg.id, msg.addr);
+    /// Gửi event tới sessions của từng user, kèm user_seq của user đó nếu có
+    fn deliver_sequenced(
+        &mut self,
+        targets: Vec<(Uuid, Vec<Uuid>)>,
+        message: ServerMessage,
+        seqs: Option<Vec<u64>>,
+    ) {
+        // Events không có seq dùng chung một frame cho mọi sessions, events có seq dùng chung
+        // frame giữa các sessions của cùng user
+        let unsequenced = Frame::new(message.clone());
+        for (i, (_, session_ids)) in targets.into_iter().enumerate() {
+            let frame = match seqs.as_ref().and_then(|seqs| seqs.get(i).copied()) {
+                Some(user_seq) => Frame::sequenced(message.clone(), user_seq),
+                None => unsequenced.clone(),
+            };
+            for session_id in session_ids {
+                self.send_frame_to_session(&session_id, frame.clone());
+            }
+        }
     }
-}
 
-/// Handler: User disconnected
-impl Handler<Disconnect> for WebSocketServer {
-    type Result = ();
-
-    fn handle(&mut self, msg: Disconnect, _: &mut Context<Self>) {
-        tracing::debug!("WebSocket session disconnected: {}", msg.id);
+    /// Gửi message tới tất cả sessions của một user (multi-device)
+    fn send_to_user(&mut self, user_id: &Uuid, message: ServerMessage) {
+        for session_id in self.session_ids_of(user_id) {
+            self.send_to_session(&session_id, message.clone());
+        }
+    }
 
-        // Xóa session
-        self.sessions.remove(&msg.id);
+    /// Xóa hẳn session khỏi server, xóa user khỏi rooms nếu không còn session nào
+    fn remove_session(&mut self, session_id: &Uuid) {
+        self.sessions.remove(session_id);
+        self.detached.remove(session_id);
 
         // Tìm user có session này và xóa session khỏi set
         let mut user_to_remove: Option<Uuid> = None;
         for (&user_id, sessions) in self.users.iter_mut() {
-            if sessions.remove(&msg.id) {
-                tracing::debug!("Removed session {} from user {}", msg.id, user_id);
+            if sessions.remove(session_id) {
+                tracing::debug!("Removed session {} from user {}", session_id, user_id);
                 // Nếu user không còn session nào, đánh dấu để xóa
                 if sessions.is_empty() {
                     user_to_remove = Some(user_id);
@@ -122,21 +258,399 @@ impl Handler<Disconnect> for WebSocketServer {
             // từ session actor (session có friend_ids và presence_service)
         }
     }
+
+    /// Đóng các sessions cũ nhất của user để chừa chỗ cho `session_id` mới
+    ///
+    /// Session IDs là UUID v7 nên ID nhỏ hơn là session cũ hơn. Session bị đóng không
+    /// được giữ lại để resume.
+    fn evict_oldest_sessions(&mut self, user_id: &Uuid, session_id: &Uuid) {
+        let mut existing: Vec<Uuid> =
+            self.session_ids_of(user_id).into_iter().filter(|id| id != session_id).collect();
+        if existing.len() < self.max_sessions_per_user {
+            return;
+        }
+
+        existing.sort_unstable();
+        let excess = existing.len() + 1 - self.max_sessions_per_user;
+
+        for old_session_id in existing.into_iter().take(excess) {
+            if let Some(session_addr) = self.sessions.get(&old_session_id) {
+                session_addr.do_send(ServerMessage::error(
+                    ErrorCode::SessionLimitExceeded,
+                    "Đã vượt quá số sessions tối đa, session này bị đóng",
+                ));
+                session_addr.do_send(CloseSession {
+                    reason: SESSION_LIMIT_REASON.to_string(),
+                    superseded: true,
+                });
+            }
+
+            self.remove_session(&old_session_id);
+
+            tracing::warn!(
+                "User {} vượt giới hạn {} sessions, đóng session {}",
+                user_id,
+                self.max_sessions_per_user,
+                old_session_id
+            );
+        }
+    }
+
+    /// Deliver event tới local sessions rồi publish cho các instances khác
+    fn route(&mut self, event: FanoutEvent) {
+        // Mọi instance dùng chung event_id để mỗi user chỉ được gán một user_seq cho event
+        let event_id = Uuid::now_v7();
+        self.deliver(event_id, &event);
+
+        if let Some(bridge) = &self.bridge {
+            bridge.publish(event_id, event);
+        }
+    }
+
+    /// Deliver event tới các sessions đang nằm trên instance này
+    fn deliver(&mut self, event_id: Uuid, event: &FanoutEvent) {
+        match event {
+            FanoutEvent::BroadcastToRoom { conversation_id, message, skip_user_id } => {
+                self.deliver_to_room(event_id, conversation_id, message, *skip_user_id);
+            }
+            FanoutEvent::SendToUsers { user_ids, message } => {
+                self.deliver_to_users(event_id, user_ids, message);
+            }
+            FanoutEvent::SendToOtherSessions { user_id, skip_session_id, message } => {
+                let session_ids: Vec<Uuid> = self
+                    .session_ids_of(user_id)
+                    .into_iter()
+                    .filter(|&session_id| Some(session_id) != *skip_session_id)
+                    .collect();
+                self.send_to_targets(event_id, vec![(*user_id, session_ids)], message);
+            }
+            FanoutEvent::UserPresenceChanged { user_id, is_online, friend_ids, last_seen } => {
+                self.deliver_presence(
+                    event_id,
+                    *user_id,
+                    *is_online,
+                    friend_ids,
+                    last_seen.clone(),
+                );
+            }
+            FanoutEvent::BroadcastToAll { message } => {
+                for session_addr in self.sessions.values() {
+                    session_addr.do_send(message.clone());
+                }
+
+                tracing::debug!("Broadcast to all: {} sessions", self.sessions.len());
+            }
+        }
+    }
+
+    fn deliver_to_room(
+        &mut self,
+        event_id: Uuid,
+        conversation_id: &Uuid,
+        message: &ServerMessage,
+        skip_user_id: Option<Uuid>,
+    ) {
+        let Some(room_users) = self.rooms.get(conversation_id) else {
+            tracing::debug!("Attempted to broadcast to non-existent room: {}", conversation_id);
+            return;
+        };
+
+        // Skip user nếu được chỉ định (ví dụ: sender không cần nhận lại)
+        // Gửi tới tất cả sessions của mỗi user (multi-device)
+        let targets: Vec<(Uuid, Vec<Uuid>)> = room_users
+            .iter()
+            .filter(|&&user_id| skip_user_id != Some(user_id))
+            .map(|&user_id| (user_id, self.session_ids_of(&user_id)))
+            .collect();
+        let sent_count: usize = targets.iter().map(|(_, session_ids)| session_ids.len()).sum();
+        self.send_to_targets(event_id, targets, message);
+
+        tracing::debug!("Broadcast to room {}: sent to {} sessions", conversation_id, sent_count);
+    }
+
+    fn deliver_to_users(&mut self, event_id: Uuid, user_ids: &[Uuid], message: &ServerMessage) {
+        let targets: Vec<(Uuid, Vec<Uuid>)> =
+            user_ids.iter().map(|&user_id| (user_id, self.session_ids_of(&user_id))).collect();
+        let sent_count: usize = targets.iter().map(|(_, session_ids)| session_ids.len()).sum();
+        self.send_to_targets(event_id, targets, message);
+
+        tracing::debug!("Sent message to {} users ({} total sessions)", user_ids.len(), sent_count);
+    }
+
+    /// Chỉ gửi notification đến friends có session trên instance này (friend-scoped
+    /// fan-out), friends đang detached nhận qua event buffer
+    fn deliver_presence(
+        &mut self,
+        event_id: Uuid,
+        user_id: Uuid,
+        is_online: bool,
+        friend_ids: &[Uuid],
+        last_seen: Option<String>,
+    ) {
+        let event = if is_online {
+            ServerMessage::UserOnline { user_id }
+        } else {
+            ServerMessage::UserOffline { user_id, last_seen }
+        };
+
+        let targets: Vec<(Uuid, Vec<Uuid>)> = friend_ids
+            .iter()
+            .filter(|friend_id| self.users.contains_key(friend_id))
+            .map(|&friend_id| (friend_id, self.session_ids_of(&friend_id)))
+            .collect();
+        let notified_count = targets.len();
+        self.send_to_targets(event_id, targets, &event);
+
+        tracing::debug!(
+            "Presence change: user {} {} → notified {}/{} local friends",
+            user_id,
+            if is_online { "online" } else { "offline" },
+            notified_count,
+            friend_ids.len()
+        );
+    }
+
+    /// Xóa các detached sessions đã quá RESUME_WINDOW
+    fn sweep_detached(&mut self) {
+        let expired: Vec<Uuid> = self
+            .detached
+            .iter()
+            .filter(|(_, detached)| detached.since.elapsed() > RESUME_WINDOW)
+            .map(|(&session_id, _)| session_id)
+            .collect();
+
+        for session_id in expired {
+            self.remove_session(&session_id);
+            tracing::debug!("Detached session {} expired", session_id);
+        }
+    }
+
+    /// Resume detached session: session mới thay thế session cũ của user rồi nhận lại
+    /// các events có user_seq > `last_seq` từ event buffer (giống `sync`)
+    fn resume_session(
+        &mut self,
+        session_id: Uuid,
+        user_id: Uuid,
+        resume: ResumeRequest,
+        ctx: &mut Context<Self>,
+    ) -> Result<(), String> {
+        if self.event_buffer.is_none() {
+            return Err("Server không hỗ trợ resume".to_string());
+        }
+
+        let is_owner = self
+            .detached
+            .get(&resume.session_id)
+            .is_some_and(|detached| detached.user_id == user_id);
+        if !is_owner {
+            return Err("Session không tồn tại hoặc đã hết hạn".to_string());
+        }
+
+        self.remove_session(&resume.session_id);
+        self.replay_events(session_id, user_id, resume.last_seq, ReplayKind::Resume, ctx);
+
+        Ok(())
+    }
+
+    /// Replay events có user_seq > `last_seq` từ event buffer tới session rồi báo kết quả
+    ///
+    /// Mailbox bị chặn (`ctx.wait`) tới khi replay xong nên events replay luôn đi trước events
+    /// mới; events vừa được gán seq có thể đến lại sau replay, client bỏ qua theo seq
+    fn replay_events(
+        &mut self,
+        session_id: Uuid,
+        user_id: Uuid,
+        last_seq: u64,
+        kind: ReplayKind,
+        ctx: &mut Context<Self>,
+    ) {
+        let Some(event_buffer) = self.event_buffer.clone() else {
+            self.send_to_session(&session_id, kind.gap(0));
+            return;
+        };
+
+        let read = async move {
+            event_buffer.read_after(user_id, Some(last_seq), SYNC_REPLAY_LIMIT).await
+        };
+
+        ctx.wait(read.into_actor(self).map(move |result, act, _ctx| {
+            let buffered = match result {
+                Ok(buffered) => buffered,
+                Err(e) => {
+                    tracing::warn!("Lỗi đọc event buffer của user {}: {}", user_id, e);
+                    act.send_to_session(&session_id, kind.failed());
+                    return;
+                }
+            };
+
+            if buffered.gap {
+                act.send_to_session(&session_id, kind.gap(buffered.head));
+                return;
+            }
+
+            let mut replayed = 0;
+            let mut last_seq = last_seq;
+            for (seq, event) in buffered.events {
+                match serde_json::from_str::<ServerMessage>(&event) {
+                    Ok(message) => {
+                        act.send_frame_to_session(&session_id, Frame::sequenced(message, seq));
+                        replayed += 1;
+                    }
+                    Err(e) => {
+                        tracing::warn!("Bỏ qua event {} của user {}: {}", seq, user_id, e);
+                    }
+                }
+                last_seq = seq;
+            }
+
+            act.send_to_session(&session_id, kind.done(replayed, last_seq));
+        }));
+    }
+}
+
+/// Replay events theo yêu cầu `sync` hay resume khi reconnect (khác nhau ở message kết quả)
+#[derive(Clone, Copy)]
+enum ReplayKind {
+    Sync,
+    Resume,
+}
+
+impl ReplayKind {
+    fn done(self, replayed: usize, last_seq: u64) -> ServerMessage {
+        match self {
+            Self::Sync => ServerMessage::Synced { replayed, last_seq },
+            Self::Resume => ServerMessage::Resumed { replayed, last_seq },
+        }
+    }
+
+    fn gap(self, head: u64) -> ServerMessage {
+        match self {
+            Self::Sync => ServerMessage::ResyncRequired { last_seq: head },
+            Self::Resume => ServerMessage::ResumeFailed {
+                reason: "Events đã hết hạn, cần đồng bộ lại state".to_string(),
+            },
+        }
+    }
+
+    fn failed(self) -> ServerMessage {
+        match self {
+            Self::Sync => {
+                ServerMessage::error(ErrorCode::InternalError, "Không thể đồng bộ events")
+            }
+            Self::Resume => ServerMessage::ResumeFailed {
+                reason: "Không thể khôi phục events".to_string(),
+            },
+        }
+    }
+}
+
+impl Actor for WebSocketServer {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        tracing::info!("WebSocket server started");
+
+        if self.event_buffer.is_some() {
+            ctx.run_interval(DETACHED_SWEEP_INTERVAL, |act, _ctx| act.sweep_detached());
+        }
+
+        if let Some(event_buffer) = self.event_buffer.clone() {
+            self.sequencer = Some(EventSequencer::start(event_buffer, ctx.address()));
+        }
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        tracing::info!("WebSocket server stopped");
+    }
+}
+
+/// Handler: User mới connected
+impl Handler<Connect> for WebSocketServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: Connect, _: &mut Context<Self>) {
+        tracing::debug!("New WebSocket session connected: {}", msg.id);
+
+        // Upgrade đã qua kiểm tra IsDraining trước khi shutdown bắt đầu
+        if self.draining {
+            msg.addr
+                .do_send(CloseSession { reason: SHUTDOWN_REASON.to_string(), superseded: false });
+            return;
+        }
+
+        if self.sessions.len() >= self.max_total_sessions {
+            tracing::warn!(
+                "Từ chối session {}: đã đạt giới hạn {} sessions",
+                msg.id,
+                self.max_total_sessions
+            );
+            msg.addr.do_send(ServerMessage::error(
+                ErrorCode::ServerAtCapacity,
+                "Server đã đạt số kết nối tối đa, vui lòng thử lại sau",
+            ));
+            msg.addr
+                .do_send(CloseSession { reason: CAPACITY_REASON.to_string(), superseded: false });
+            return;
+        }
+
+        // Lưu session vào map
+        self.sessions.insert(msg.id, msg.addr);
+    }
+}
+
+/// Handler: User disconnected
+impl Handler<Disconnect> for WebSocketServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: Disconnect, _: &mut Context<Self>) {
+        tracing::debug!("WebSocket session disconnected: {}", msg.id);
+
+        // Session đã xác thực: giữ lại ở trạng thái detached để client có thể resume
+        if self.event_buffer.is_some() {
+            self.sessions.remove(&msg.id);
+
+            if let Some(user_id) = self
+                .users
+                .iter()
+                .find(|(_, sessions)| sessions.contains(&msg.id))
+                .map(|(&id, _)| id)
+            {
+                self.detached.insert(msg.id, DetachedSession { user_id, since: Instant::now() });
+                return;
+            }
+        }
+
+        self.remove_session(&msg.id);
+    }
 }
 
 /// Handler: Authenticate user
 impl Handler<Authenticate> for WebSocketServer {
     type Result = Result<Uuid, String>;
 
-    fn handle(&mut self, msg: Authenticate, _: &mut Context<Self>) -> Self::Result {
+    fn handle(&mut self, msg: Authenticate, ctx: &mut Context<Self>) -> Self::Result {
         tracing::info!("User {} authenticated on session {}", msg.user_id, msg.session_id);
 
+        self.evict_oldest_sessions(&msg.user_id, &msg.session_id);
+
         // Thêm session vào set của user (hỗ trợ multi-device)
         let sessions = self.users.entry(msg.user_id).or_default();
         sessions.insert(msg.session_id);
 
         tracing::info!("User {} now has {} active session(s)", msg.user_id, sessions.len());
 
+        if let Some(resume) = msg.resume {
+            if let Err(reason) = self.resume_session(msg.session_id, msg.user_id, resume, ctx) {
+                tracing::debug!(
+                    "Resume session {} thất bại (session {}): {}",
+                    resume.session_id,
+                    msg.session_id,
+                    reason
+                );
+                self.send_to_session(&msg.session_id, ServerMessage::ResumeFailed { reason });
+            }
+        }
+
         // NOTE: Presence notification (online-users, user-online) được xử lý
         // bởi session actor sau khi load friend list và set Redis presence
 
@@ -187,39 +701,62 @@ impl Handler<LeaveRoom> for WebSocketServer {
     }
 }
 
+/// Handler: User join cuộc gọi nhóm, broadcast danh sách participants tới room
+impl Handler<JoinCall> for WebSocketServer {
+    type Result = Option<CallRoomChange>;
+
+    fn handle(&mut self, msg: JoinCall, _: &mut Context<Self>) -> Self::Result {
+        let change = self.call_rooms.join(msg.conversation_id, msg.user_id, msg.session_id)?;
+
+        tracing::debug!("User {} joined call in conversation {}", msg.user_id, msg.conversation_id);
+
+        self.route(FanoutEvent::BroadcastToRoom {
+            conversation_id: msg.conversation_id,
+            message: ServerMessage::GroupCallJoined {
+                conversation_id: msg.conversation_id,
+                user_id: msg.user_id,
+                participant_ids: self.call_rooms.participant_ids(&msg.conversation_id),
+            },
+            skip_user_id: None,
+        });
+
+        Some(change)
+    }
+}
+
+/// Handler: User rời cuộc gọi nhóm, broadcast danh sách participants còn lại tới room
+impl Handler<LeaveCall> for WebSocketServer {
+    type Result = Option<CallRoomChange>;
+
+    fn handle(&mut self, msg: LeaveCall, _: &mut Context<Self>) -> Self::Result {
+        let change = self.call_rooms.leave(msg.conversation_id, msg.user_id, msg.session_id)?;
+
+        tracing::debug!("User {} left call in conversation {}", msg.user_id, msg.conversation_id);
+
+        self.route(FanoutEvent::BroadcastToRoom {
+            conversation_id: msg.conversation_id,
+            message: ServerMessage::GroupCallLeft {
+                conversation_id: msg.conversation_id,
+                user_id: msg.user_id,
+                participant_ids: self.call_rooms.participant_ids(&msg.conversation_id),
+            },
+            skip_user_id: None,
+        });
+
+        Some(change)
+    }
+}
+
 /// Handler: Broadcast message tới room
 impl Handler<BroadcastToRoom> for WebSocketServer {
     type Result = ();
 
     fn handle(&mut self, msg: BroadcastToRoom, _: &mut Context<Self>) {
-        if let Some(room_users) = self.rooms.get(&msg.conversation_id) {
-            let mut sent_count = 0;
-
-            for &user_id in room_users {
-                // Skip user nếu được chỉ định (ví dụ: sender không cần nhận lại)
-                if let Some(skip_id) = msg.skip_user_id {
-                    if user_id == skip_id {
-                        continue;
-                    }
-                }
-
-                // Lấy tất cả sessions của user và gửi message tới mỗi session (multi-device)
-                if let Some(session_ids) = self.users.get(&user_id) {
-                    for session_id in session_ids {
-                        self.send_to_session(session_id, msg.message.clone());
-                        sent_count += 1;
-                    }
-                }
-            }
-
-            tracing::debug!(
-                "Broadcast to room {}: sent to {} sessions",
-                msg.conversation_id,
-                sent_count
-            );
-        } else {
-            tracing::debug!("Attempted to broadcast to non-existent room: {}", msg.conversation_id);
-        }
+        self.route(FanoutEvent::BroadcastToRoom {
+            conversation_id: msg.conversation_id,
+            message: msg.message,
+            skip_user_id: msg.skip_user_id,
+        });
     }
 }
 
@@ -228,15 +765,20 @@ impl Handler<SendToUser> for WebSocketServer {
     type Result = ();
 
     fn handle(&mut self, msg: SendToUser, _: &mut Context<Self>) {
-        if let Some(session_ids) = self.users.get(&msg.user_id) {
-            let session_count = session_ids.len();
-            for session_id in session_ids {
-                self.send_to_session(session_id, msg.message.clone());
-            }
-            tracing::debug!("Sent message to user {} ({} sessions)", msg.user_id, session_count);
-        } else {
-            tracing::debug!("User {} not online, message not sent", msg.user_id);
-        }
+        self.route(FanoutEvent::SendToUsers { user_ids: vec![msg.user_id], message: msg.message });
+    }
+}
+
+/// Handler: Gửi message tới các sessions khác của user (multi-device sync)
+impl Handler<SendToOtherSessions> for WebSocketServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: SendToOtherSessions, _: &mut Context<Self>) {
+        self.route(FanoutEvent::SendToOtherSessions {
+            user_id: msg.user_id,
+            skip_session_id: msg.skip_session_id,
+            message: msg.message,
+        });
     }
 }
 
@@ -245,18 +787,76 @@ impl Handler<SendToUsers> for WebSocketServer {
     type Result = ();
 
     fn handle(&mut self, msg: SendToUsers, _: &mut Context<Self>) {
-        let mut sent_count = 0;
+        self.route(FanoutEvent::SendToUsers { user_ids: msg.user_ids, message: msg.message });
+    }
+}
 
-        for user_id in &msg.user_ids {
-            if let Some(session_ids) = self.users.get(user_id) {
-                for session_id in session_ids {
-                    self.send_to_session(session_id, msg.message.clone());
-                    sent_count += 1;
-                }
-            }
+/// Handler: Route event từ outbox dispatcher
+impl Handler<RouteEvent> for WebSocketServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: RouteEvent, _: &mut Context<Self>) {
+        self.route(msg.event);
+    }
+}
+
+/// Handler: Event từ instance khác (qua fan-out bridge), chỉ deliver tới local sessions
+impl Handler<DeliverLocal> for WebSocketServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: DeliverLocal, _: &mut Context<Self>) {
+        self.deliver(msg.event_id, &msg.event);
+    }
+}
+
+/// Handler: Event đã được gán user_seq, gửi tới local sessions
+impl Handler<DeliverSequenced> for WebSocketServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: DeliverSequenced, _: &mut Context<Self>) {
+        self.deliver_sequenced(msg.targets, msg.message, msg.seqs);
+    }
+}
+
+/// Handler: Client yêu cầu replay events có user_seq > `last_seq`
+impl Handler<SyncEvents> for WebSocketServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: SyncEvents, ctx: &mut Context<Self>) {
+        self.replay_events(msg.session_id, msg.user_id, msg.last_seq, ReplayKind::Sync, ctx);
+    }
+}
+
+/// Handler: Bắt đầu graceful shutdown
+/// Mailbox xử lý theo thứ tự nên mọi broadcast gửi trước Shutdown đã được chuyển tới
+/// sessions; mỗi session gửi hết messages đang chờ rồi mới gửi Close frame
+impl Handler<Shutdown> for WebSocketServer {
+    type Result = Vec<Uuid>;
+
+    fn handle(&mut self, msg: Shutdown, _: &mut Context<Self>) -> Self::Result {
+        self.draining = true;
+
+        let online_users = self.get_online_users();
+        for session_addr in self.sessions.values() {
+            session_addr.do_send(CloseSession { reason: msg.reason.clone(), superseded: false });
         }
 
-        tracing::debug!("Sent message to {} users ({} total sessions)", msg.user_ids.len(), sent_count);
+        tracing::info!(
+            "WebSocket server draining: closing {} sessions of {} users",
+            self.sessions.len(),
+            online_users.len()
+        );
+
+        online_users
+    }
+}
+
+/// Handler: Kiểm tra server có đang shutdown không
+impl Handler<IsDraining> for WebSocketServer {
+    type Result = bool;
+
+    fn handle(&mut self, _: IsDraining, _: &mut Context<Self>) -> Self::Result {
+        self.draining
     }
 }
 
@@ -274,11 +874,7 @@ impl Handler<BroadcastToAll> for WebSocketServer {
     type Result = ();
 
     fn handle(&mut self, msg: BroadcastToAll, _: &mut Context<Self>) {
-        for session_addr in self.sessions.values() {
-            session_addr.do_send(msg.message.clone());
-        }
-
-        tracing::debug!("Broadcast to all: {} sessions", self.sessions.len());
+        self.route(FanoutEvent::BroadcastToAll { message: msg.message });
     }
 }
 
@@ -300,30 +896,12 @@ impl Handler<UserPresenceChanged> for WebSocketServer {
     type Result = ();
 
     fn handle(&mut self, msg: UserPresenceChanged, _: &mut Context<Self>) {
-        let event = if msg.is_online {
-            ServerMessage::UserOnline { user_id: msg.user_id }
-        } else {
-            ServerMessage::UserOffline {
-                user_id: msg.user_id,
-                last_seen: msg.last_seen,
-            }
-        };
-
-        let mut notified_count = 0;
-        for friend_id in &msg.friend_ids {
-            if self.users.contains_key(friend_id) {
-                self.send_to_user(friend_id, event.clone());
-                notified_count += 1;
-            }
-        }
-
-        tracing::info!(
-            "Presence change: user {} {} → notified {}/{} friends",
-            msg.user_id,
-            if msg.is_online { "online" } else { "offline" },
-            notified_count,
-            msg.friend_ids.len()
-        );
+        self.route(FanoutEvent::UserPresenceChanged {
+            user_id: msg.user_id,
+            is_online: msg.is_online,
+            friend_ids: msg.friend_ids,
+            last_seen: msg.last_seen,
+        });
     }
 }
 
@@ -338,7 +916,7 @@ impl Handler<SendInitialPresence> for WebSocketServer {
         let online_friend_ids: Vec<Uuid> = msg
             .friend_ids
             .iter()
-            .filter(|fid| self.users.contains_key(fid))
+            .filter(|fid| self.is_connected(fid))
             .copied()
             .collect();
 
@@ -8,40 +8,96 @@
 /// - Khi auth thành công: load friend list, set Redis presence, notify friends
 /// - Heartbeat: refresh Redis TTL mỗi 15s
 /// - Khi disconnect: set Redis offline + last_seen, notify friends
+/// - User đặt presence visibility = nobody thì friends không nhận presence events
+/// - Join / leave conversation: cập nhật active users trong Redis, broadcast tới room
 ///
 /// Async operations (DB calls) sử dụng `ctx.spawn()` + `into_actor()`.
 use actix::prelude::*;
+use actix_web::{dev::Payload, web, FromRequest, HttpRequest};
+use std::collections::HashSet;
+use std::future::{ready, Ready};
 use std::time::{Duration, Instant};
-use tokio::sync::mpsc;
 use uuid::Uuid;
+use validator::Validate;
 
-use crate::modules::conversation::repository_pg::{
-    ConversationPgRepository, LastMessagePgRepository, ParticipantPgRepository,
-};
+use crate::api::error::{self, ErrorCode};
+use crate::modules::call::service::CallService;
+use crate::modules::conversation::service::ConversationService;
 use crate::modules::friend::repository_pg::FriendRepositoryPg;
-use crate::modules::message::repository_pg::MessageRepositoryPg;
+use crate::modules::message::command::CommandOutcome;
+use crate::modules::message::model::ClientMetadata;
 use crate::modules::message::service::MessageService;
+use crate::modules::user::schema::{PresenceVisibility, UserRole};
+use crate::modules::user::service::UserService;
 use crate::utils::{Claims, TypeClaims};
-use crate::ENV;
 
+use super::backpressure::{OutboundSender, SendError};
+use super::codec::Frame;
 use super::events::*;
-use super::message::{ClientMessage, LastMessageInfo, SenderInfo, ServerMessage};
-use super::presence::PresenceService;
+use super::message::{ClientMessage, ResumeRequest, ServerMessage};
+use super::presence::{CustomStatus, PresenceService, PresenceStatus};
 use super::server::WebSocketServer;
 
-/// Type alias cho MessageService với concrete repository types
-pub type MessageSvc = MessageService<
-    MessageRepositoryPg,
-    ConversationPgRepository,
-    ParticipantPgRepository,
-    LastMessagePgRepository,
->;
-
 /// Heartbeat ping interval (server gửi ping mỗi 15s)
 const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
 /// Client timeout - nếu không nhận được pong sau 30s, disconnect
 const CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// Giới hạn độ dài custom status (tính theo ký tự)
+const MAX_STATUS_TEXT_LEN: usize = 100;
+const MAX_STATUS_EMOJI_LEN: usize = 16;
+
+const NOT_A_MEMBER_MESSAGE: &str = "Bạn không phải thành viên của conversation này";
+
+/// Item gửi qua outbound channel tới handler.rs
+#[derive(Debug)]
+pub enum Outbound {
+    /// ServerMessage, handler encode theo encoding của connection
+    Frame(Frame),
+    /// Gửi Close frame với reason rồi kết thúc connection
+    Close(String),
+}
+
+/// Dependencies của session actor, extract từ app data của request upgrade
+#[derive(Clone)]
+pub struct SessionServices {
+    pub message_service: web::Data<MessageService>,
+    pub presence_service: web::Data<PresenceService>,
+    pub friend_repo: web::Data<FriendRepositoryPg>,
+    pub user_service: web::Data<UserService>,
+    pub call_service: web::Data<CallService>,
+    pub conversation_service: web::Data<ConversationService>,
+}
+
+impl SessionServices {
+    fn from_app_data(req: &HttpRequest) -> Result<Self, error::Error> {
+        fn data<T: 'static>(req: &HttpRequest) -> Result<web::Data<T>, error::Error> {
+            req.app_data::<web::Data<T>>().cloned().ok_or_else(|| {
+                tracing::error!("Thiếu app data {}", std::any::type_name::<T>());
+                error::Error::InternalServer
+            })
+        }
+
+        Ok(SessionServices {
+            message_service: data(req)?,
+            presence_service: data(req)?,
+            friend_repo: data(req)?,
+            user_service: data(req)?,
+            call_service: data(req)?,
+            conversation_service: data(req)?,
+        })
+    }
+}
+
+impl FromRequest for SessionServices {
+    type Error = error::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Self::from_app_data(req))
+    }
+}
+
 /// WebSocket session cho một client
 pub struct WebSocketSession {
     /// Unique session ID
@@ -53,11 +109,11 @@ pub struct WebSocketSession {
     /// Address của WebSocket server actor
     pub server: Addr<WebSocketServer>,
 
-    /// Channel gửi JSON messages tới client (bridge → handler.rs → WebSocket)
-    pub tx: mpsc::UnboundedSender<String>,
+    /// Queue có giới hạn gửi JSON messages tới client (bridge → handler.rs → WebSocket)
+    pub tx: OutboundSender,
 
     /// Message service để persist messages vào DB (None trong test environment)
-    pub message_service: Option<actix_web::web::Data<MessageSvc>>,
+    pub message_service: Option<actix_web::web::Data<MessageService>>,
 
     /// Presence service cho Redis presence tracking
     pub presence_service: Option<actix_web::web::Data<PresenceService>>,
@@ -65,62 +121,98 @@ pub struct WebSocketSession {
     /// Friend repository cho loading friend IDs
     pub friend_repo: Option<actix_web::web::Data<FriendRepositoryPg>>,
 
+    /// User service để kiểm tra access token đã bị revoke chưa
+    pub user_service: Option<actix_web::web::Data<UserService>>,
+
+    /// Call service cho signaling cuộc gọi (offer / answer / ICE / end)
+    pub call_service: Option<actix_web::web::Data<CallService>>,
+
+    /// Conversation service để kiểm tra membership (cache Redis) khi join room / gửi tin
+    pub conversation_service: Option<actix_web::web::Data<ConversationService>>,
+
+    /// Group conversation mà session đang tham gia cuộc gọi nhóm
+    pub group_call: Option<Uuid>,
+
+    /// Conversation duy nhất mà guest được truy cập (`None` với user thường)
+    pub guest_conversation: Option<Uuid>,
+
+    /// Đang chờ kiểm tra revoke cho Auth message (tránh auth song song)
+    pub authenticating: bool,
+
+    /// Client metadata (app version, platform) gửi kèm Auth message
+    pub client_metadata: Option<ClientMetadata>,
+
     /// Cached friend IDs - loaded sau khi auth, dùng cho presence notifications
     pub friend_ids: Vec<Uuid>,
 
+    /// Conversations client đang mở (đã join room), dùng cho "active now" indicator
+    pub open_conversations: HashSet<Uuid>,
+
     /// Thời điểm nhận heartbeat cuối cùng từ client
     pub last_heartbeat: Instant,
+
+    /// Session bị đóng do user vượt giới hạn sessions (user vẫn online ở session mới)
+    pub superseded: bool,
 }
 
 impl WebSocketSession {
-    /// Tạo session mới với outbound channel và dependencies
+    /// Tạo session mới với outbound queue (mang session ID) và dependencies
     pub fn new(
         server: Addr<WebSocketServer>,
-        tx: mpsc::UnboundedSender<String>,
-        message_service: actix_web::web::Data<MessageSvc>,
-        presence_service: actix_web::web::Data<PresenceService>,
-        friend_repo: actix_web::web::Data<FriendRepositoryPg>,
+        tx: OutboundSender,
+        services: SessionServices,
     ) -> Self {
         Self {
-            id: Uuid::now_v7(),
+            id: tx.session_id(),
             user_id: None,
             server,
             tx,
-            message_service: Some(message_service),
-            presence_service: Some(presence_service),
-            friend_repo: Some(friend_repo),
+            message_service: Some(services.message_service),
+            presence_service: Some(services.presence_service),
+            friend_repo: Some(services.friend_repo),
+            user_service: Some(services.user_service),
+            call_service: Some(services.call_service),
+            conversation_service: Some(services.conversation_service),
+            group_call: None,
+            guest_conversation: None,
+            authenticating: false,
+            client_metadata: None,
             friend_ids: Vec::new(),
+            open_conversations: HashSet::new(),
             last_heartbeat: Instant::now(),
+            superseded: false,
         }
     }
 
-    /// Gửi ServerMessage tới client thông qua channel
-    fn send_to_client(&self, msg: &ServerMessage) {
-        match serde_json::to_string(msg) {
-            Ok(json) => {
-                if let Err(e) = self.tx.send(json) {
-                    tracing::error!(
-                        "Không thể gửi message tới client (session {}): {}",
-                        self.id,
-                        e
-                    );
-                }
-            }
+    /// Đưa frame vào outbound queue
+    ///
+    /// Queue đầy thì frame bị bỏ, message loop đóng connection (đã log trong backpressure)
+    fn send_outbound(&self, outbound: Outbound) {
+        match self.tx.send(outbound) {
+            Ok(()) | Err(SendError::Lagging) => {}
             Err(e) => {
-                tracing::error!("Không thể serialize ServerMessage (session {}): {}", self.id, e);
+                tracing::error!("Không thể gửi message tới client (session {}): {}", self.id, e);
             }
         }
     }
 
+    /// Gửi ServerMessage tới client thông qua channel
+    fn send_to_client(&self, msg: ServerMessage) {
+        self.send_outbound(Outbound::Frame(Frame::new(msg)));
+    }
+
     /// Gửi error message tới client
-    fn send_error(&self, message: &str) {
-        self.send_to_client(&ServerMessage::Error { message: message.to_string() });
+    fn send_error(&self, code: ErrorCode, message: &str) {
+        self.send_to_client(ServerMessage::error(code, message));
     }
 
     /// Kiểm tra user đã authenticate chưa, trả về user_id nếu có
     fn require_auth(&self) -> Option<Uuid> {
         if self.user_id.is_none() {
-            self.send_error("Bạn cần xác thực trước khi thực hiện thao tác này");
+            self.send_error(
+                ErrorCode::Unauthorized,
+                "Bạn cần xác thực trước khi thực hiện thao tác này",
+            );
             tracing::warn!("Session {} chưa authenticate, từ chối request", self.id);
         }
         self.user_id
@@ -128,17 +220,37 @@ impl WebSocketSession {
 
     /// Xử lý message từ client - dispatch tới handler tương ứng
     fn handle_client_message(&mut self, msg: &ClientMessage, ctx: &mut Context<Self>) {
+        // Guest chỉ được thao tác trên conversation đã được mời
+        if let (Some(allowed), Some(conversation_id)) =
+            (self.guest_conversation, msg.conversation_id())
+        {
+            if allowed != conversation_id {
+                self.send_error(
+                    ErrorCode::Forbidden,
+                    "Guest chỉ được truy cập conversation đã được mời",
+                );
+                return;
+            }
+        }
+
         match msg {
-            ClientMessage::Auth { token } => {
-                self.handle_auth(token, ctx);
+            ClientMessage::Auth { token, client, resume } => {
+                // Metadata không hợp lệ bị bỏ qua, không ảnh hưởng tới việc auth
+                self.client_metadata = client.clone().filter(|c| c.validate().is_ok());
+                self.handle_auth(token, *resume, ctx);
             }
 
-            ClientMessage::SendMessage { conversation_id, content } => {
-                self.handle_send_message(*conversation_id, content.clone(), ctx);
+            ClientMessage::SendMessage { conversation_id, content, client_message_id } => {
+                self.handle_send_message(
+                    *conversation_id,
+                    content.clone(),
+                    *client_message_id,
+                    ctx,
+                );
             }
 
             ClientMessage::JoinConversation { conversation_id } => {
-                self.handle_join_conversation(*conversation_id);
+                self.handle_join_conversation(*conversation_id, ctx);
             }
 
             ClientMessage::LeaveConversation { conversation_id } => {
@@ -153,10 +265,43 @@ impl WebSocketSession {
                 self.handle_typing_stop(*conversation_id);
             }
 
+            ClientMessage::Ack { message_id } => {
+                self.handle_ack(*message_id, ctx);
+            }
+
+            ClientMessage::Sync { last_seq } => {
+                if let Some(user_id) = self.require_auth() {
+                    self.server.do_send(SyncEvents {
+                        session_id: self.id,
+                        user_id,
+                        last_seq: *last_seq,
+                    });
+                }
+            }
+
+            ClientMessage::SetStatus { status, text, emoji } => {
+                self.handle_set_status(*status, text.clone(), emoji.clone());
+            }
+
+            ClientMessage::CallOffer { .. }
+            | ClientMessage::CallAnswer { .. }
+            | ClientMessage::IceCandidate { .. }
+            | ClientMessage::CallEnd { .. } => {
+                self.handle_call_signal(msg.clone(), ctx);
+            }
+
+            ClientMessage::JoinGroupCall { conversation_id } => {
+                self.handle_join_group_call(*conversation_id, ctx);
+            }
+
+            ClientMessage::LeaveGroupCall { conversation_id } => {
+                self.handle_leave_group_call(*conversation_id);
+            }
+
             ClientMessage::Ping => {
                 // Cập nhật heartbeat timestamp và gửi pong response
                 self.last_heartbeat = Instant::now();
-                self.send_to_client(&ServerMessage::Pong);
+                self.send_to_client(ServerMessage::Pong);
             }
         }
     }
@@ -165,25 +310,26 @@ impl WebSocketSession {
     ///
     /// Flow (inspired by Messenger/Instagram):
     /// 1. Verify JWT token
-    /// 2. Register session với server (sync)
-    /// 3. Spawn async task:
+    /// 2. Kiểm tra token chưa bị revoke (Redis denylist)
+    /// 3. Register session với server (sync), resume session cũ nếu client yêu cầu
+    /// 4. Spawn async task:
     ///    a. Load friend IDs từ DB (for targeted notifications)
     ///    b. Set presence key trong Redis với TTL
     ///    c. Thông báo online friends về user mới online
     ///    d. Gửi initial online friends list cho user
-    fn handle_auth(&mut self, token: &str, ctx: &mut Context<Self>) {
+    fn handle_auth(&mut self, token: &str, resume: Option<ResumeRequest>, ctx: &mut Context<Self>) {
         // Kiểm tra đã auth chưa (tránh auth lại)
-        if self.user_id.is_some() {
-            self.send_error("Session đã được xác thực");
+        if self.user_id.is_some() || self.authenticating {
+            self.send_error(ErrorCode::AlreadyAuthenticated, "Session đã được xác thực");
             return;
         }
 
         // Decode và verify JWT token
-        let claims = match Claims::decode(token, ENV.jwt_secret.as_ref()) {
+        let claims = match Claims::decode(token) {
             Ok(claims) => claims,
             Err(e) => {
                 tracing::warn!("JWT verification thất bại (session {}): {}", self.id, e);
-                self.send_to_client(&ServerMessage::AuthFailed {
+                self.send_to_client(ServerMessage::AuthFailed {
                     reason: "Token không hợp lệ hoặc đã hết hạn".to_string(),
                 });
                 return;
@@ -192,32 +338,92 @@ impl WebSocketSession {
 
         // Kiểm tra token type phải là AccessToken
         if claims._type.as_ref() != Some(&TypeClaims::AccessToken) {
-            self.send_to_client(&ServerMessage::AuthFailed {
+            self.send_to_client(ServerMessage::AuthFailed {
                 reason: "Chỉ chấp nhận access token".to_string(),
             });
             return;
         }
 
-        let user_id = claims.sub;
+        // Token của guest bắt buộc mang conversation được mời
+        if claims.role == UserRole::Guest && claims.conversation_id.is_none() {
+            self.send_to_client(ServerMessage::AuthFailed {
+                reason: "Token không hợp lệ hoặc đã hết hạn".to_string(),
+            });
+            return;
+        }
+        self.guest_conversation = claims.conversation_id;
+
+        let Some(user_service) = self.user_service.clone() else {
+            self.complete_auth(claims.sub, resume, ctx);
+            return;
+        };
 
+        self.authenticating = true;
+
+        ctx.spawn(
+            async move {
+                let revoked = user_service.is_token_revoked(&claims).await;
+                (claims.sub, revoked)
+            }
+            .into_actor(self)
+            .map(move |(user_id, revoked), act, ctx| {
+                act.authenticating = false;
+                match revoked {
+                    Ok(false) => act.complete_auth(user_id, resume, ctx),
+                    Ok(true) => {
+                        act.send_to_client(ServerMessage::AuthFailed {
+                            reason: "Token đã bị thu hồi".to_string(),
+                        });
+                    }
+                    Err(e) => {
+                        tracing::error!("Lỗi kiểm tra token revoke (session {}): {}", act.id, e);
+                        act.send_to_client(ServerMessage::AuthFailed {
+                            reason: "Không thể xác thực token".to_string(),
+                        });
+                    }
+                }
+            }),
+        );
+    }
+
+    /// Hoàn tất authentication sau khi token đã được verify
+    fn complete_auth(
+        &mut self,
+        user_id: Uuid,
+        resume: Option<ResumeRequest>,
+        ctx: &mut Context<Self>,
+    ) {
         // Cập nhật state session
         self.user_id = Some(user_id);
 
-        // Thông báo server về user đã authenticate (đăng ký vào users map)
-        self.server.do_send(Authenticate { session_id: self.id, user_id });
+        // Gửi success response về client (trước các events replay khi resume)
+        self.send_to_client(ServerMessage::AuthSuccess { user_id, session_id: self.id });
 
-        // Gửi success response về client
-        self.send_to_client(&ServerMessage::AuthSuccess { user_id });
+        // Thông báo server về user đã authenticate (đăng ký vào users map)
+        self.server.do_send(Authenticate { session_id: self.id, user_id, resume });
 
         tracing::info!("User {} đã authenticate thành công trên session {}", user_id, self.id);
 
         // === Presence flow (async) ===
         let friend_repo = self.friend_repo.clone();
         let presence_service = self.presence_service.clone();
+        let user_service = self.user_service.clone();
+        let message_service = self.message_service.clone();
+        let client_metadata = self.client_metadata.clone();
+        let session_id = self.id;
         let server = self.server.clone();
 
         ctx.spawn(
             async move {
+                // 0. Ghi nhận client metadata của session (phục vụ debug)
+                if let (Some(service), Some(client)) = (&message_service, client_metadata) {
+                    if let Err(e) =
+                        service.record_session_metadata(user_id, session_id, client).await
+                    {
+                        tracing::error!("Lỗi lưu client metadata cho user {}: {}", user_id, e);
+                    }
+                }
+
                 // 1. Load friend IDs từ DB
                 let friend_ids = if let Some(repo) = &friend_repo {
                     match repo.find_friend_ids(&user_id).await {
@@ -235,27 +441,60 @@ impl WebSocketSession {
                     vec![]
                 };
 
-                // 2. Set online trong Redis
+                // 2. Đồng bộ presence visibility từ Postgres rồi set online trong Redis
+                let visibility = match &user_service {
+                    Some(service) => service.get_presence_visibility(user_id).await.ok(),
+                    None => None,
+                }
+                .unwrap_or_default();
                 if let Some(presence) = &presence_service {
+                    if let Err(e) = presence.set_visibility(user_id, visibility).await {
+                        tracing::error!("Lỗi set presence visibility cho user {}: {}", user_id, e);
+                    }
                     if let Err(e) = presence.set_online(user_id).await {
                         tracing::error!("Lỗi set Redis presence cho user {}: {}", user_id, e);
                     }
                 }
 
-                // 3. Notify online friends (friend-scoped, not broadcast)
                 if !friend_ids.is_empty() {
-                    server.do_send(UserPresenceChanged {
-                        user_id,
-                        is_online: true,
-                        friend_ids: friend_ids.clone(),
-                        last_seen: None,
-                    });
+                    // 3. Notify online friends (friend-scoped, not broadcast),
+                    // trừ khi user ẩn trạng thái với mọi người
+                    if visibility != PresenceVisibility::Nobody {
+                        server.do_send(UserPresenceChanged {
+                            user_id,
+                            is_online: true,
+                            friend_ids: friend_ids.clone(),
+                            last_seen: None,
+                        });
+                    }
 
                     // 4. Send initial presence (online friends) to this user
-                    server.do_send(SendInitialPresence {
-                        user_id,
-                        friend_ids: friend_ids.clone(),
-                    });
+                    // Ưu tiên Redis presence vì friends có thể kết nối tới instance khác
+                    let friend_set: HashSet<Uuid> = friend_ids.iter().copied().collect();
+                    let online_friends = match &presence_service {
+                        Some(presence) => presence
+                            .get_presence_for_viewer(user_id, &friend_set, &friend_ids)
+                            .await
+                            .ok(),
+                        None => None,
+                    };
+
+                    match online_friends {
+                        Some(statuses) => server.do_send(SendToUser {
+                            user_id,
+                            message: ServerMessage::OnlineUsers {
+                                user_ids: statuses
+                                    .into_iter()
+                                    .filter(|status| status.is_online)
+                                    .map(|status| status.user_id)
+                                    .collect(),
+                            },
+                        }),
+                        None => server.do_send(SendInitialPresence {
+                            user_id,
+                            friend_ids: friend_ids.clone(),
+                        }),
+                    }
                 }
 
                 friend_ids
@@ -269,7 +508,13 @@ impl WebSocketSession {
     }
 
     /// Xử lý gửi tin nhắn - lưu vào DB rồi broadcast tới room
-    fn handle_send_message(&self, conversation_id: Uuid, content: String, ctx: &mut Context<Self>) {
+    fn handle_send_message(
+        &self,
+        conversation_id: Uuid,
+        content: String,
+        client_message_id: Option<Uuid>,
+        ctx: &mut Context<Self>,
+    ) {
         let Some(user_id) = self.require_auth() else {
             return;
         };
@@ -278,60 +523,87 @@ impl WebSocketSession {
 
         // Kiểm tra message service khả dụng
         let Some(service) = self.message_service.clone() else {
-            self.send_error("Message service không khả dụng");
+            self.send_error(ErrorCode::ServiceUnavailable, "Message service không khả dụng");
             return;
         };
 
         // Clone các dependencies cần thiết cho async block
         let server = self.server.clone();
-        let tx = self.tx.clone();
         let session_id = self.id;
+        let client_metadata = self.client_metadata.clone();
+        let conversation_service = self.conversation_service.clone();
 
-        // Spawn async future trong actor context để gọi DB
-        // Sử dụng send_group_message vì WS luôn có conversation_id (đã tồn tại).
-        // send_group_message hoạt động đúng cho cả direct và group conversations:
-        // - increment_unread_count_for_others tăng unread cho tất cả participants trừ sender
-        // - Đây là hành vi chính xác cho cả 1-1 và group chat
+        // Spawn async future trong actor context để gọi DB. WS luôn có conversation_id nên
+        // dùng chung `send_message` với HTTP (membership, unread counts, broadcast, push).
+        // Phản hồi riêng cho session này (lỗi, command reply) được gửi qua `send_to_client`
         ctx.spawn(
             async move {
-                // Lưu message vào database
-                match service.send_group_message(user_id, content, conversation_id).await {
-                    Ok(msg_entity) => {
-                        // Serialize MessageEntity thành JSON value cho broadcast
-                        let message_value = serde_json::to_value(&msg_entity).unwrap_or_default();
-
-                        // Tạo last message info cho new-message event
-                        let last_message = LastMessageInfo {
-                            _id: msg_entity.id,
-                            content: msg_entity.content.clone(),
-                            created_at: msg_entity.created_at.to_rfc3339(),
-                            sender: SenderInfo {
-                                _id: msg_entity.sender_id,
-                                display_name: String::new(), // Will be populated by client
-                                avatar_url: None,
-                            },
-                        };
-
-                        // Broadcast tin nhắn mới với format tương thích Socket.IO
-                        let new_msg_event = ServerMessage::new_message(
-                            message_value,
+                // Từ chối sớm qua membership cache, lỗi Redis / DB thì để `send_message` kiểm tra lại
+                if let Some(conversations) = conversation_service {
+                    match conversations.is_member(conversation_id, user_id).await {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            return Some(ServerMessage::error(
+                                ErrorCode::NotAMember,
+                                NOT_A_MEMBER_MESSAGE,
+                            ));
+                        }
+                        Err(e) => tracing::warn!(
+                            "Không thể kiểm tra membership (session {}, conversation {}): {}",
+                            session_id,
                             conversation_id,
-                            last_message,
-                            msg_entity.created_at.to_rfc3339(),
-                            serde_json::json!({}), // unread_counts will be handled by client
-                        );
+                            e
+                        ),
+                    }
+                }
 
-                        server.do_send(BroadcastToRoom {
-                            conversation_id,
-                            message: new_msg_event,
-                            skip_user_id: None, // Gửi cả cho sender (confirm message đã gửi)
+                // Slash command được xử lý trước khi lưu, phản hồi ephemeral chỉ gửi về session này
+                let outcome =
+                    service.dispatch_command(user_id, Some(conversation_id), content).await;
+                let result = match outcome {
+                    Ok(CommandOutcome::Send(content)) => {
+                        // Lưu message vào database
+                        service
+                            .send_message(
+                                user_id,
+                                conversation_id,
+                                content,
+                                client_metadata,
+                                client_message_id,
+                            )
+                            .await
+                    }
+                    Ok(CommandOutcome::Reply(reply)) => {
+                        return Some(ServerMessage::CommandReply {
+                            conversation_id: reply.conversation_id,
+                            command: reply.command,
+                            text: reply.text,
                         });
+                    }
+                    Err(e) => Err(e),
+                };
+
+                match result {
+                    // Tin nhắn trùng lặp đã được gộp, service đã broadcast message-repeated
+                    Ok(msg_entity) if msg_entity.repeat_count > 1 => None,
+                    Ok(msg_entity) => {
+                        // `send_message` đã broadcast tới các thành viên khác, sender nhận
+                        // `new-message` trên mọi session để xác nhận tin nhắn đã gửi
+                        match service.new_message_event(&msg_entity).await {
+                            Ok(event) => server.do_send(SendToUser { user_id, message: event }),
+                            Err(e) => tracing::warn!(
+                                "Không thể xác nhận message {} cho sender: {}",
+                                msg_entity.id,
+                                e
+                            ),
+                        }
 
                         tracing::info!(
                             "Message {} saved và broadcast tới conversation {}",
                             msg_entity.id,
                             conversation_id
                         );
+                        None
                     }
                     Err(e) => {
                         tracing::error!(
@@ -342,37 +614,126 @@ impl WebSocketSession {
                         );
 
                         // Gửi error response về client
-                        let err_msg = ServerMessage::Error {
-                            message: "Không thể gửi tin nhắn. Vui lòng thử lại.".to_string(),
+                        let code = match e.code() {
+                            ErrorCode::InternalError => ErrorCode::SendFailed,
+                            code => code,
                         };
-                        if let Ok(json) = serde_json::to_string(&err_msg) {
-                            let _ = tx.send(json);
-                        }
+                        let message = match code {
+                            ErrorCode::NotAMember => NOT_A_MEMBER_MESSAGE,
+                            _ => "Không thể gửi tin nhắn. Vui lòng thử lại.",
+                        };
+                        Some(ServerMessage::error(code, message))
                     }
                 }
             }
+            .into_actor(self)
+            .map(|reply, act, _ctx| {
+                if let Some(reply) = reply {
+                    act.send_to_client(reply);
+                }
+            }),
+        );
+    }
+
+    /// Xử lý delivery ack - cập nhật delivered watermark, service relay event cho sender
+    fn handle_ack(&self, message_id: Uuid, ctx: &mut Context<Self>) {
+        let Some(user_id) = self.require_auth() else {
+            return;
+        };
+
+        let Some(service) = self.message_service.clone() else {
+            return;
+        };
+
+        ctx.spawn(
+            async move {
+                if let Err(e) = service.acknowledge_delivery(user_id, message_id).await {
+                    tracing::warn!(
+                        "Lỗi ghi nhận delivery của message {} cho user {}: {}",
+                        message_id,
+                        user_id,
+                        e
+                    );
+                }
+            }
             .into_actor(self),
         );
     }
 
-    /// Xử lý join conversation room
-    fn handle_join_conversation(&self, conversation_id: Uuid) {
+    /// Xử lý join conversation room, báo room danh sách users đang mở conversation
+    ///
+    /// Chỉ thành viên của conversation mới được join room (nhận events của conversation)
+    fn handle_join_conversation(&mut self, conversation_id: Uuid, ctx: &mut Context<Self>) {
         let Some(user_id) = self.require_auth() else {
             return;
         };
 
+        let Some(service) = self.conversation_service.clone() else {
+            self.join_conversation(user_id, conversation_id);
+            return;
+        };
+
+        ctx.spawn(
+            async move { service.is_member(conversation_id, user_id).await }.into_actor(self).map(
+                move |result, act, _| match result {
+                    // User có thể đã đăng xuất / đổi tài khoản trong lúc chờ kiểm tra
+                    Ok(true) if act.user_id == Some(user_id) => {
+                        act.join_conversation(user_id, conversation_id)
+                    }
+                    Ok(true) => {}
+                    Ok(false) => act.send_error(ErrorCode::NotAMember, NOT_A_MEMBER_MESSAGE),
+                    Err(e) => {
+                        tracing::error!(
+                            "Lỗi kiểm tra membership (session {}, conversation {}): {}",
+                            act.id,
+                            conversation_id,
+                            e
+                        );
+                        act.send_error(
+                            ErrorCode::InternalError,
+                            "Không thể tham gia conversation. Vui lòng thử lại.",
+                        );
+                    }
+                },
+            ),
+        );
+    }
+
+    fn join_conversation(&mut self, user_id: Uuid, conversation_id: Uuid) {
         self.server.do_send(JoinRoom { user_id, conversation_id });
+        self.open_conversations.insert(conversation_id);
         tracing::debug!("User {} joined conversation {}", user_id, conversation_id);
+
+        if let Some(presence) = self.presence_service.clone() {
+            actix_web::rt::spawn(update_conversation_presence(
+                presence,
+                self.server.clone(),
+                conversation_id,
+                user_id,
+                true,
+            ));
+        }
     }
 
     /// Xử lý leave conversation room
-    fn handle_leave_conversation(&self, conversation_id: Uuid) {
+    fn handle_leave_conversation(&mut self, conversation_id: Uuid) {
         let Some(user_id) = self.require_auth() else {
             return;
         };
 
         self.server.do_send(LeaveRoom { user_id, conversation_id });
+        self.open_conversations.remove(&conversation_id);
         tracing::debug!("User {} left conversation {}", user_id, conversation_id);
+
+        if let Some(presence) = self.presence_service.clone() {
+            actix_web::rt::spawn(update_conversation_presence(
+                presence,
+                self.server.clone(),
+                conversation_id,
+                user_id,
+                false,
+            ));
+        }
     }
 
     /// Xử lý typing start - broadcast tới room (trừ sender)
@@ -400,6 +761,231 @@ impl WebSocketSession {
             skip_user_id: Some(user_id),
         });
     }
+
+    /// Xử lý set custom status - lưu Redis rồi báo friends và các thiết bị khác của user
+    ///
+    /// User ẩn presence (`nobody`) chỉ đồng bộ status giữa các thiết bị của chính mình
+    fn handle_set_status(
+        &self,
+        status: PresenceStatus,
+        text: Option<String>,
+        emoji: Option<String>,
+    ) {
+        let Some(user_id) = self.require_auth() else {
+            return;
+        };
+
+        let text = text.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+        let emoji = emoji.map(|e| e.trim().to_string()).filter(|e| !e.is_empty());
+        if text.as_ref().is_some_and(|t| t.chars().count() > MAX_STATUS_TEXT_LEN) {
+            self.send_error(ErrorCode::InvalidStatus, "Status text quá dài");
+            return;
+        }
+        if emoji.as_ref().is_some_and(|e| e.chars().count() > MAX_STATUS_EMOJI_LEN) {
+            self.send_error(ErrorCode::InvalidStatus, "Status emoji không hợp lệ");
+            return;
+        }
+
+        let Some(presence) = self.presence_service.clone() else {
+            return;
+        };
+        let friend_ids = self.friend_ids.clone();
+        let server = self.server.clone();
+
+        actix_web::rt::spawn(async move {
+            let custom = CustomStatus { status, text, emoji };
+            if let Err(e) = presence.set_status(user_id, &custom).await {
+                tracing::error!("Lỗi set custom status cho user {}: {}", user_id, e);
+                return;
+            }
+
+            let hidden = is_hidden(&presence, user_id).await;
+
+            let mut user_ids = if hidden { vec![] } else { friend_ids };
+            user_ids.push(user_id);
+
+            server.do_send(SendToUsers {
+                user_ids,
+                message: ServerMessage::PresenceStatusChanged {
+                    user_id,
+                    status: custom.status,
+                    text: custom.text,
+                    emoji: custom.emoji,
+                },
+            });
+        });
+    }
+
+    /// Xử lý signaling cuộc gọi - CallService lưu trạng thái và relay tới user còn lại
+    ///
+    /// Caller nhận `call-ringing` (kèm call_id) khi offer đã được gửi tới callee,
+    /// lỗi (callee bận, không phải direct conversation...) trả về session này.
+    fn handle_call_signal(&self, msg: ClientMessage, ctx: &mut Context<Self>) {
+        let Some(user_id) = self.require_auth() else {
+            return;
+        };
+
+        let Some(service) = self.call_service.clone() else {
+            self.send_error(ErrorCode::ServiceUnavailable, "Call service không khả dụng");
+            return;
+        };
+
+        let session_id = self.id;
+
+        ctx.spawn(
+            async move {
+                let result = match msg {
+                    ClientMessage::CallOffer { conversation_id, callee_id, call_type, sdp } => {
+                        service
+                            .start_call(user_id, conversation_id, callee_id, call_type, sdp)
+                            .await
+                            .map(|call| {
+                                Some(ServerMessage::CallRinging {
+                                    call_id: call.id,
+                                    conversation_id: call.conversation_id,
+                                    callee_id: call.callee_id,
+                                })
+                            })
+                    }
+                    ClientMessage::CallAnswer { call_id, sdp } => {
+                        service.answer_call(user_id, session_id, call_id, sdp).await.map(|_| None)
+                    }
+                    ClientMessage::IceCandidate { call_id, candidate } => {
+                        service.relay_ice_candidate(user_id, call_id, candidate).await.map(|_| None)
+                    }
+                    ClientMessage::CallEnd { call_id } => {
+                        service.end_call(user_id, call_id).await.map(|_| None)
+                    }
+                    _ => Ok(None),
+                };
+
+                match result {
+                    Ok(reply) => reply,
+                    Err(e) => {
+                        tracing::warn!("Lỗi signaling cuộc gọi (session {}): {}", session_id, e);
+                        Some(ServerMessage::error(e.code(), "Không thể xử lý cuộc gọi"))
+                    }
+                }
+            }
+            .into_actor(self)
+            .map(|reply, act, _ctx| {
+                if let Some(reply) = reply {
+                    act.send_to_client(reply);
+                }
+            }),
+        );
+    }
+
+    /// Xử lý join cuộc gọi nhóm - mỗi session chỉ ở trong một cuộc gọi nhóm tại một thời điểm
+    fn handle_join_group_call(&mut self, conversation_id: Uuid, ctx: &mut Context<Self>) {
+        let Some(user_id) = self.require_auth() else {
+            return;
+        };
+
+        let Some(service) = self.call_service.clone() else {
+            self.send_error(ErrorCode::ServiceUnavailable, "Call service không khả dụng");
+            return;
+        };
+
+        if self.group_call.is_some_and(|current| current != conversation_id) {
+            self.send_error(ErrorCode::UserBusy, "Bạn đang ở trong một cuộc gọi nhóm khác");
+            return;
+        }
+        self.group_call = Some(conversation_id);
+
+        let session_id = self.id;
+
+        ctx.spawn(
+            async move { service.join_group_call(user_id, session_id, conversation_id).await }
+                .into_actor(self)
+                .map(move |result, act, _ctx| {
+                    let Err(e) = result else {
+                        return;
+                    };
+
+                    tracing::warn!(
+                        "Lỗi join cuộc gọi nhóm (session {}, conversation {}): {}",
+                        act.id,
+                        conversation_id,
+                        e
+                    );
+                    if act.group_call == Some(conversation_id) {
+                        act.group_call = None;
+                    }
+                    act.send_error(e.code(), "Không thể tham gia cuộc gọi");
+                }),
+        );
+    }
+
+    /// Xử lý rời cuộc gọi nhóm
+    fn handle_leave_group_call(&mut self, conversation_id: Uuid) {
+        let Some(user_id) = self.require_auth() else {
+            return;
+        };
+
+        if self.group_call == Some(conversation_id) {
+            self.group_call = None;
+        }
+
+        if let Some(service) = self.call_service.clone() {
+            actix_web::rt::spawn(leave_group_call(service, user_id, self.id, conversation_id));
+        }
+    }
+}
+
+/// Rời cuộc gọi nhóm, lỗi chỉ được log (session có thể đã đóng)
+async fn leave_group_call(
+    service: actix_web::web::Data<CallService>,
+    user_id: Uuid,
+    session_id: Uuid,
+    conversation_id: Uuid,
+) {
+    if let Err(e) = service.leave_group_call(user_id, session_id, conversation_id).await {
+        tracing::warn!(
+            "Lỗi rời cuộc gọi nhóm (session {}, conversation {}): {}",
+            session_id,
+            conversation_id,
+            e
+        );
+    }
+}
+
+/// User đặt presence visibility = nobody (lỗi Redis coi như không ẩn)
+async fn is_hidden(presence: &PresenceService, user_id: Uuid) -> bool {
+    presence
+        .get_visibility_batch(&[user_id])
+        .await
+        .is_ok_and(|v| v.first() == Some(&PresenceVisibility::Nobody))
+}
+
+/// Cập nhật "active now" của user trong conversation rồi broadcast danh sách mới tới room
+///
+/// User ẩn presence (`nobody`) không được đánh dấu active
+async fn update_conversation_presence(
+    presence: actix_web::web::Data<PresenceService>,
+    server: Addr<WebSocketServer>,
+    conversation_id: Uuid,
+    user_id: Uuid,
+    active: bool,
+) {
+    let result = if !active {
+        presence.mark_conversation_inactive(conversation_id, user_id).await
+    } else if is_hidden(&presence, user_id).await {
+        return;
+    } else {
+        presence.mark_conversation_active(conversation_id, user_id).await
+    };
+
+    match result {
+        Ok(active_user_ids) => server.do_send(BroadcastToRoom {
+            conversation_id,
+            message: ServerMessage::ConversationPresence { conversation_id, active_user_ids },
+            skip_user_id: None,
+        }),
+        Err(e) => {
+            tracing::warn!("Lỗi cập nhật active users cho conversation {}: {}", conversation_id, e)
+        }
+    }
 }
 
 impl Actor for WebSocketSession {
@@ -424,16 +1010,36 @@ impl Actor for WebSocketSession {
             }
 
             // Gửi ping tới client để kiểm tra connection
-            act.send_to_client(&ServerMessage::Pong);
+            act.send_to_client(ServerMessage::Pong);
+
+            // Ghi nhận hoạt động (service tự giới hạn tần suất ghi DB)
+            if let (Some(user_id), Some(user_service)) = (act.user_id, act.user_service.clone()) {
+                actix_web::rt::spawn(async move { user_service.record_activity(user_id).await });
+            }
 
             // Refresh Redis presence TTL (piggyback on heartbeat interval)
             if let (Some(user_id), Some(presence)) =
                 (act.user_id, act.presence_service.clone())
             {
+                let open_conversations: Vec<Uuid> =
+                    act.open_conversations.iter().copied().collect();
                 actix_web::rt::spawn(async move {
                     if let Err(e) = presence.refresh_presence(user_id).await {
                         tracing::warn!("Lỗi refresh Redis presence cho user {}: {}", user_id, e);
                     }
+
+                    if open_conversations.is_empty() || is_hidden(&presence, user_id).await {
+                        return;
+                    }
+                    if let Err(e) =
+                        presence.refresh_conversations_active(&open_conversations, user_id).await
+                    {
+                        tracing::warn!(
+                            "Lỗi refresh active conversations cho user {}: {}",
+                            user_id,
+                            e
+                        );
+                    }
                 });
             }
         });
@@ -445,8 +1051,27 @@ impl Actor for WebSocketSession {
         // Notify server về disconnect
         self.server.do_send(Disconnect { id: self.id });
 
+        // Rời cuộc gọi nhóm đang tham gia (kết thúc cuộc gọi nếu là người cuối cùng)
+        if let (Some(user_id), Some(conversation_id), Some(service)) =
+            (self.user_id, self.group_call, self.call_service.clone())
+        {
+            actix_web::rt::spawn(leave_group_call(service, user_id, self.id, conversation_id));
+        }
+
         // Presence cleanup: notify friends + set Redis offline
-        if let Some(user_id) = self.user_id {
+        if let Some(user_id) = self.user_id.filter(|_| !self.superseded) {
+            if let Some(presence) = &self.presence_service {
+                for &conversation_id in &self.open_conversations {
+                    actix_web::rt::spawn(update_conversation_presence(
+                        presence.clone(),
+                        self.server.clone(),
+                        conversation_id,
+                        user_id,
+                        false,
+                    ));
+                }
+            }
+
             let friend_ids = self.friend_ids.clone();
             let server = self.server.clone();
             let presence_service = self.presence_service.clone();
@@ -460,8 +1085,14 @@ impl Actor for WebSocketSession {
                     }
                 }
 
+                // User ẩn trạng thái với mọi người thì friends chưa từng thấy online
+                let hidden = match &presence_service {
+                    Some(presence) => is_hidden(presence, user_id).await,
+                    None => false,
+                };
+
                 // Notify friends about offline (with last_seen)
-                if !friend_ids.is_empty() {
+                if !friend_ids.is_empty() && !hidden {
                     let last_seen = Some(chrono::Utc::now().to_rfc3339());
                     server.do_send(UserPresenceChanged {
                         user_id,
@@ -489,11 +1120,32 @@ impl Handler<ClientMessage> for WebSocketSession {
     }
 }
 
-/// Handler: Nhận ServerMessage từ server actor → serialize → gửi tới client qua channel
+/// Handler: Nhận ServerMessage từ server actor → gửi tới client qua channel
 impl Handler<ServerMessage> for WebSocketSession {
     type Result = ();
 
     fn handle(&mut self, msg: ServerMessage, _ctx: &mut Context<Self>) {
-        self.send_to_client(&msg);
+        self.send_to_client(msg);
+    }
+}
+
+/// Handler: Nhận frame dùng chung (có thể kèm user_seq) từ server actor → gửi tới client
+impl Handler<OutboundFrame> for WebSocketSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: OutboundFrame, _ctx: &mut Context<Self>) {
+        self.send_outbound(Outbound::Frame(msg.frame));
+    }
+}
+
+/// Handler: Server shutdown / vượt giới hạn sessions / client đọc quá chậm → gửi Close frame
+/// (sau các messages đang chờ) và dừng session
+impl Handler<CloseSession> for WebSocketSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: CloseSession, ctx: &mut Context<Self>) {
+        self.superseded = msg.superseded;
+        let _ = self.tx.send(Outbound::Close(msg.reason));
+        ctx.stop();
     }
 }
@@ -0,0 +1,70 @@
+/// Graceful Shutdown
+///
+/// Thay cho signal handling mặc định của actix-web. Khi nhận SIGTERM / Ctrl-C:
+///
+/// 1. WebSocketServer chuyển sang draining (từ chối upgrade mới) và mỗi session
+///    gửi hết messages đang chờ rồi gửi Close frame "server restarting"
+/// 2. Set offline trong Redis cho các users đang kết nối với instance này
+/// 3. Dừng HTTP server (graceful, chờ connections đóng tối đa `shutdown_timeout`)
+use actix::Addr;
+use actix_web::dev::ServerHandle;
+use futures_util::future::join_all;
+
+use super::events::Shutdown;
+use super::presence::PresenceService;
+use super::server::{WebSocketServer, SHUTDOWN_REASON};
+
+/// Chờ shutdown signal rồi drain WebSocket connections và dừng HTTP server
+pub async fn shutdown_on_signal(
+    server_handle: ServerHandle,
+    ws_server: Addr<WebSocketServer>,
+    presence_service: PresenceService,
+) {
+    wait_for_signal().await;
+
+    tracing::info!("Shutdown signal received, draining WebSocket connections");
+
+    let shutdown = Shutdown { reason: SHUTDOWN_REASON.to_string() };
+    let online_users = match ws_server.send(shutdown).await {
+        Ok(user_ids) => user_ids,
+        Err(e) => {
+            tracing::error!("Failed to drain WebSocket server: {}", e);
+            vec![]
+        }
+    };
+
+    let results =
+        join_all(online_users.iter().map(|&user_id| presence_service.set_offline(user_id))).await;
+    for (user_id, result) in online_users.iter().zip(results) {
+        if let Err(e) = result {
+            tracing::warn!("Failed to set user {} offline during shutdown: {}", user_id, e);
+        }
+    }
+
+    tracing::info!("Marked {} users offline, stopping HTTP server", online_users.len());
+
+    server_handle.stop(true).await;
+}
+
+/// Chờ SIGTERM (unix) hoặc Ctrl-C
+async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use actix_web::rt::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = sigterm.recv() => {}
+                    _ = actix_web::rt::signal::ctrl_c() => {}
+                }
+                return;
+            }
+            Err(e) => tracing::warn!("Failed to listen for SIGTERM: {}", e),
+        }
+    }
+
+    if let Err(e) = actix_web::rt::signal::ctrl_c().await {
+        tracing::error!("Failed to listen for Ctrl-C: {}", e);
+    }
+}
@@ -0,0 +1,405 @@
+/// Socket.IO Transport Adapter
+///
+/// Endpoint tương thích Socket.IO v4 (Engine.IO v4, chỉ transport websocket, namespace
+/// mặc định) để các socket.io frontends có thể kết nối mà không cần thay đổi.
+/// Adapter chỉ chuyển đổi frame format, toàn bộ logic vẫn nằm trong WebSocketSession:
+///
+/// - `40{"token": ...}` (CONNECT kèm auth) → `ClientMessage::Auth`
+/// - `42["send_message", {...}]` → `ClientMessage` với `type` = tên event
+///   (chấp nhận cả kebab-case, ví dụ `join-conversation`)
+/// - Engine.IO pong `3` → `ClientMessage::Ping` (giữ session heartbeat)
+/// - `auth-success` → `40{"sid": ...}`, `auth-failed` → `44{"message": ...}`
+/// - ServerMessage khác `{"type": "new-message", ...}` → `42["new-message", {...}]`
+///
+/// Client: `io(url, { path: "/socket.io", transports: ["websocket"], auth: { token } })`
+use std::time::Duration;
+
+use actix::{Actor, Addr};
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use actix_ws::{CloseCode, CloseReason, Message};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::backpressure::{outbound_channel, SLOW_CLIENT_REASON};
+use super::events::{CloseSession, IsDraining};
+use super::message::ClientMessage;
+use super::server::WebSocketServer;
+use super::session::{Outbound, SessionServices, WebSocketSession};
+
+/// Engine.IO ping interval, nhỏ hơn CLIENT_TIMEOUT của session để pong của client
+/// (forward thành Ping) giữ session alive
+const PING_INTERVAL: Duration = Duration::from_secs(15);
+const PING_TIMEOUT: Duration = Duration::from_secs(20);
+const MAX_PAYLOAD: usize = 1_000_000;
+
+#[derive(Debug, Deserialize)]
+struct EngineIoQuery {
+    #[serde(rename = "EIO")]
+    eio: String,
+    transport: String,
+}
+
+/// Packet từ client sau khi decode
+#[derive(Debug)]
+enum Inbound {
+    /// Forward tới session actor
+    Client(ClientMessage),
+    /// Trả lời trực tiếp cho client (không qua session)
+    Reply(String),
+    /// Client đóng connection / disconnect namespace
+    Close,
+    /// Packet không cần xử lý
+    Ignore,
+}
+
+/// HTTP handler cho Socket.IO (Engine.IO v4 qua websocket)
+///
+/// Endpoint: GET /socket.io/?EIO=4&transport=websocket
+///
+/// Trả về 400 nếu client dùng Engine.IO version / transport khác (không hỗ trợ polling),
+/// 503 nếu server đang shutdown.
+pub async fn socketio_handler(
+    req: HttpRequest,
+    stream: web::Payload,
+    server: web::Data<Addr<WebSocketServer>>,
+    services: SessionServices,
+) -> Result<HttpResponse, Error> {
+    let supported = web::Query::<EngineIoQuery>::from_query(req.query_string())
+        .is_ok_and(|query| query.eio == "4" && query.transport == "websocket");
+    if !supported {
+        return Ok(HttpResponse::BadRequest().json(json!({
+            "code": 0,
+            "message": "Chỉ hỗ trợ Engine.IO v4 với transport websocket"
+        })));
+    }
+
+    if server.send(IsDraining).await.unwrap_or(true) {
+        return Ok(HttpResponse::ServiceUnavailable().finish());
+    }
+
+    let (response, mut ws_session, mut msg_stream) = actix_ws::handle(&req, stream)?;
+
+    let (tx, mut rx) = outbound_channel(Uuid::now_v7());
+
+    let ws_actor = WebSocketSession::new(server.get_ref().clone(), tx, services);
+    let sid = ws_actor.id;
+    let addr = ws_actor.start();
+
+    actix_web::rt::spawn(async move {
+        // Engine.IO OPEN packet
+        let open = json!({
+            "sid": sid,
+            "upgrades": [],
+            "pingInterval": PING_INTERVAL.as_millis() as u64,
+            "pingTimeout": PING_TIMEOUT.as_millis() as u64,
+            "maxPayload": MAX_PAYLOAD,
+        });
+        if ws_session.text(format!("0{open}")).await.is_err() {
+            return;
+        }
+
+        let start = actix_web::rt::time::Instant::now() + PING_INTERVAL;
+        let mut ping = actix_web::rt::time::interval_at(start, PING_INTERVAL);
+        let mut close_reason: Option<CloseReason> = None;
+
+        loop {
+            tokio::select! {
+                // === INBOUND: Client → Server ===
+                msg = msg_stream.recv() => {
+                    match msg {
+                        Some(Ok(Message::Text(text))) => match decode_packet(&text) {
+                            Inbound::Client(client_msg) => match client_msg.validate() {
+                                Ok(()) => addr.do_send(client_msg),
+                                Err(error) => {
+                                    let packet = serde_json::to_string(&error)
+                                        .ok()
+                                        .and_then(|json| encode_server_message(&json, sid));
+                                    if let Some(packet) = packet {
+                                        if ws_session.text(packet).await.is_err() {
+                                            break;
+                                        }
+                                    }
+                                }
+                            },
+                            Inbound::Reply(packet) => {
+                                if ws_session.text(packet).await.is_err() {
+                                    break;
+                                }
+                            }
+                            Inbound::Close => break,
+                            Inbound::Ignore => {}
+                        },
+
+                        Some(Ok(Message::Ping(data))) => {
+                            if ws_session.pong(&data).await.is_err() {
+                                break;
+                            }
+                        }
+
+                        Some(Ok(Message::Close(reason))) => {
+                            tracing::info!("Socket.IO close frame: {:?}", reason);
+                            break;
+                        }
+
+                        Some(Ok(Message::Binary(_))) => {
+                            tracing::warn!("Socket.IO binary attachments không được hỗ trợ");
+                        }
+
+                        Some(Ok(Message::Pong(_) | Message::Continuation(_) | Message::Nop)) => {}
+
+                        Some(Err(e)) => {
+                            tracing::error!("Socket.IO protocol error: {}", e);
+                            break;
+                        }
+
+                        None => break,
+                    }
+                }
+
+                // === OUTBOUND: Server → Client ===
+                outbound = rx.recv() => {
+                    match outbound {
+                        Some(Outbound::Frame(frame)) => {
+                            let Some(packet) =
+                                frame.json().and_then(|json| encode_server_message(json, sid))
+                            else {
+                                continue;
+                            };
+                            if ws_session.text(packet).await.is_err() {
+                                tracing::error!("Không thể gửi packet tới Socket.IO client");
+                                break;
+                            }
+                        }
+
+                        Some(Outbound::Close(reason)) => {
+                            // Socket.IO DISCONNECT rồi mới đóng websocket
+                            let _ = ws_session.text("41").await;
+                            close_reason = Some(CloseReason {
+                                code: CloseCode::Restart,
+                                description: Some(reason),
+                            });
+                            break;
+                        }
+
+                        // Queue đầy (client đọc quá chậm) hoặc session actor đã dừng
+                        None => {
+                            if rx.is_lagging() {
+                                let _ = ws_session.text("41").await;
+                                close_reason = Some(CloseReason {
+                                    code: CloseCode::Again,
+                                    description: Some(SLOW_CLIENT_REASON.to_string()),
+                                });
+                                addr.do_send(CloseSession {
+                                    reason: SLOW_CLIENT_REASON.to_string(),
+                                    superseded: false,
+                                });
+                            }
+                            break;
+                        }
+                    }
+                }
+
+                // === Engine.IO heartbeat ===
+                _ = ping.tick() => {
+                    if ws_session.text("2").await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let _ = ws_session.close(close_reason).await;
+        tracing::debug!("Socket.IO message loop kết thúc");
+    });
+
+    tracing::info!("Socket.IO connection established");
+    Ok(response)
+}
+
+/// Decode Engine.IO packet (và Socket.IO packet bên trong) từ client
+fn decode_packet(text: &str) -> Inbound {
+    let mut chars = text.chars();
+
+    match chars.next() {
+        // CLOSE
+        Some('1') => Inbound::Close,
+        // PING từ client → PONG
+        Some('2') => Inbound::Reply(format!("3{}", chars.as_str())),
+        // PONG → refresh session heartbeat
+        Some('3') => Inbound::Client(ClientMessage::Ping),
+        // MESSAGE → Socket.IO packet
+        Some('4') => decode_socketio_packet(chars.as_str()),
+        _ => Inbound::Ignore,
+    }
+}
+
+fn decode_socketio_packet(packet: &str) -> Inbound {
+    let mut chars = packet.chars();
+    let packet_type = chars.next();
+    let rest = chars.as_str();
+
+    // Chỉ hỗ trợ namespace mặc định "/"
+    if rest.starts_with('/') && !rest.starts_with("/,") {
+        return Inbound::Reply(connect_error("Namespace không được hỗ trợ"));
+    }
+    let rest = rest.strip_prefix("/,").unwrap_or(rest);
+
+    match packet_type {
+        // CONNECT (kèm auth payload)
+        Some('0') => {
+            let token = serde_json::from_str::<Value>(rest)
+                .ok()
+                .and_then(|auth| auth.get("token").and_then(Value::as_str).map(str::to_string));
+
+            match token {
+                Some(token) => {
+                    Inbound::Client(ClientMessage::Auth { token, client: None, resume: None })
+                }
+                None => Inbound::Reply(connect_error("Thiếu token trong auth payload")),
+            }
+        }
+        // DISCONNECT
+        Some('1') => Inbound::Close,
+        // EVENT (bỏ qua ack id nếu có)
+        Some('2') => {
+            let payload = rest.trim_start_matches(|c: char| c.is_ascii_digit());
+            decode_event(payload).map_or(Inbound::Ignore, Inbound::Client)
+        }
+        _ => Inbound::Ignore,
+    }
+}
+
+/// `["event-name", {...}]` → ClientMessage
+fn decode_event(payload: &str) -> Option<ClientMessage> {
+    let Ok(Value::Array(mut items)) = serde_json::from_str::<Value>(payload) else {
+        let preview: String = payload.chars().take(100).collect();
+        tracing::warn!("Socket.IO event không hợp lệ: {}", preview);
+        return None;
+    };
+
+    if items.is_empty() {
+        return None;
+    }
+
+    let name = items.remove(0).as_str()?.replace('-', "_");
+    let mut data = match items.into_iter().next() {
+        Some(Value::Object(data)) => data,
+        _ => serde_json::Map::new(),
+    };
+    data.insert("type".to_string(), Value::String(name));
+
+    match serde_json::from_value::<ClientMessage>(Value::Object(data)) {
+        Ok(client_msg) => Some(client_msg),
+        Err(e) => {
+            tracing::warn!("Không thể parse Socket.IO event: {}", e);
+            None
+        }
+    }
+}
+
+/// ServerMessage JSON `{"type": ..., ...}` → Socket.IO packet, `None` nếu không cần gửi
+fn encode_server_message(json: &str, sid: Uuid) -> Option<String> {
+    let Ok(Value::Object(mut data)) = serde_json::from_str::<Value>(json) else {
+        return None;
+    };
+    let Some(Value::String(event)) = data.remove("type") else {
+        return None;
+    };
+
+    match event.as_str() {
+        // Engine.IO có heartbeat riêng
+        "pong" => None,
+        "auth-success" => Some(format!("40{}", json!({ "sid": sid }))),
+        "auth-failed" => {
+            let reason = data.get("reason").and_then(Value::as_str).unwrap_or("Unauthorized");
+            Some(connect_error(reason))
+        }
+        _ => Some(format!("42{}", json!([event, data]))),
+    }
+}
+
+fn connect_error(message: &str) -> String {
+    format!("44{}", json!({ "message": message }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONVERSATION_ID: &str = "0190a5b4-0000-7000-8000-000000000001";
+
+    #[test]
+    fn decode_packet_handles_engine_io_control_packets() {
+        assert!(matches!(decode_packet("1"), Inbound::Close));
+        assert!(matches!(decode_packet("2probe"), Inbound::Reply(reply) if reply == "3probe"));
+        assert!(matches!(decode_packet("3"), Inbound::Client(ClientMessage::Ping)));
+        assert!(matches!(decode_packet("6"), Inbound::Ignore));
+    }
+
+    #[test]
+    fn decode_packet_maps_connect_to_auth() {
+        let inbound = decode_packet(r#"40{"token":"abc"}"#);
+        assert!(matches!(
+            inbound,
+            Inbound::Client(ClientMessage::Auth { token, .. }) if token == "abc"
+        ));
+
+        let inbound = decode_packet("40{}");
+        assert!(matches!(inbound, Inbound::Reply(reply) if reply.starts_with("44")));
+    }
+
+    #[test]
+    fn decode_packet_maps_event_with_and_without_ack_id() {
+        for packet in [
+            format!(r#"42["typing-start",{{"conversation_id":"{CONVERSATION_ID}"}}]"#),
+            format!(r#"4217["typing_start",{{"conversation_id":"{CONVERSATION_ID}"}}]"#),
+        ] {
+            let inbound = decode_packet(&packet);
+            assert!(
+                matches!(
+                    inbound,
+                    Inbound::Client(ClientMessage::TypingStart { conversation_id })
+                        if conversation_id.to_string() == CONVERSATION_ID
+                ),
+                "unexpected decode for {packet}: {inbound:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn decode_packet_handles_namespaces() {
+        let inbound = decode_packet(r#"40/,{"token":"abc"}"#);
+        assert!(matches!(inbound, Inbound::Client(ClientMessage::Auth { .. })));
+
+        let inbound = decode_packet(r#"40/admin,{"token":"abc"}"#);
+        assert!(matches!(inbound, Inbound::Reply(reply) if reply.starts_with("44")));
+    }
+
+    #[test]
+    fn decode_packet_ignores_invalid_multibyte_event() {
+        // 100 byte đầu cắt giữa ký tự 3 byte
+        let packet = format!("42{}", "ế".repeat(60));
+        assert!(matches!(decode_packet(&packet), Inbound::Ignore));
+    }
+
+    #[test]
+    fn encode_server_message_maps_events() {
+        let sid = Uuid::now_v7();
+
+        assert_eq!(
+            encode_server_message(r#"{"type":"auth-success","user_id":"x"}"#, sid),
+            Some(format!("40{}", json!({ "sid": sid })))
+        );
+        assert_eq!(
+            encode_server_message(r#"{"type":"auth-failed","reason":"expired"}"#, sid),
+            Some(connect_error("expired"))
+        );
+        assert_eq!(encode_server_message(r#"{"type":"pong"}"#, sid), None);
+        assert_eq!(encode_server_message("not json", sid), None);
+
+        let packet = encode_server_message(r#"{"type":"new-message","id":1}"#, sid).unwrap();
+        let payload: Value = serde_json::from_str(packet.strip_prefix("42").unwrap()).unwrap();
+        assert_eq!(payload, json!(["new-message", { "id": 1 }]));
+    }
+}
@@ -1,19 +1,35 @@
-use actix_web::{web, FromRequest};
+use actix_web::{web, FromRequest, HttpRequest};
 use argon2::{
     password_hash::{Error as PasswordHashError, PasswordHash, PasswordHasher, SaltString},
-    Argon2, PasswordVerifier,
+    Argon2, Params, PasswordVerifier, Version,
 };
 use futures_util::future::LocalBoxFuture;
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
-use rand::rngs::OsRng;
+use jsonwebtoken::{decode, decode_header, encode, Header, Validation};
+use rand::{distributions::Alphanumeric, rngs::OsRng, Rng};
 use serde::{de::Deserializer, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use validator::Validate;
 
 use std::sync::LazyLock;
 
-use crate::{api::error, modules::user::schema::UserRole};
+use crate::{
+    api::error,
+    configs::jwt_keys::{self, JwtKey},
+    modules::{bot::schema::BotScope, user::schema::UserRole},
+    ENV,
+};
+
+/// Argon2id với tham số từ `ARGON2_MEMORY_KIB` / `ARGON2_ITERATIONS` / `ARGON2_PARALLELISM`
+static ARGON2: LazyLock<Argon2<'static>> = LazyLock::new(|| {
+    let params =
+        Params::new(ENV.argon2_memory_kib, ENV.argon2_iterations, ENV.argon2_parallelism, None)
+            .expect("Invalid Argon2 parameters");
+    Argon2::new(argon2::Algorithm::Argon2id, Version::V0x13, params)
+});
 
-static ARGON2: LazyLock<Argon2<'static>> = LazyLock::new(Argon2::default);
+/// Giá trị `hash_password` của tài khoản không đăng nhập bằng mật khẩu (bot, guest).
+/// Không phải PHC string nên không mật khẩu nào khớp
+pub const UNUSABLE_PASSWORD_HASH: &str = "!";
 
 pub fn hash_password(password: &str) -> Result<String, error::SystemError> {
     let salt = SaltString::generate(&mut OsRng);
@@ -22,6 +38,10 @@ pub fn hash_password(password: &str) -> Result<String, error::SystemError> {
 }
 
 pub fn verify_password(hash: &str, password: &str) -> Result<bool, error::SystemError> {
+    if hash == UNUSABLE_PASSWORD_HASH {
+        return Ok(false);
+    }
+
     let parsed_hash = PasswordHash::new(hash)?;
     match ARGON2.verify_password(password.as_bytes(), &parsed_hash) {
         Ok(_) => Ok(true),
@@ -30,10 +50,50 @@ pub fn verify_password(hash: &str, password: &str) -> Result<bool, error::System
     }
 }
 
+/// Chuỗi chữ + số ngẫu nhiên dùng cho API key, invite token, webhook secret, ...
+pub fn random_token(len: usize) -> String {
+    rand::thread_rng().sample_iter(&Alphanumeric).take(len).map(char::from).collect()
+}
+
+/// SHA-256 (hex) của token bí mật, DB chỉ lưu và so sánh digest này
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// Hash được tạo bằng thuật toán / tham số khác cấu hình hiện tại (vd. hash cũ với tham số
+/// yếu hơn) và nên được hash lại khi user đăng nhập thành công
+pub fn needs_rehash(hash: &str) -> Result<bool, error::SystemError> {
+    let parsed_hash = PasswordHash::new(hash)?;
+    if parsed_hash.algorithm != argon2::Algorithm::Argon2id.ident()
+        || parsed_hash.version != Some(Version::V0x13.into())
+    {
+        return Ok(true);
+    }
+
+    let params = Params::try_from(&parsed_hash)?;
+    let current = ARGON2.params();
+    Ok(params.m_cost() != current.m_cost()
+        || params.t_cost() != current.t_cost()
+        || params.p_cost() != current.p_cost())
+}
+
+/// IP của client. Header của proxy (`Forwarded` / `X-Forwarded-For`) chỉ được tin khi
+/// bật `TRUST_PROXY_HEADERS`, nếu không client có thể tự khai IP bất kỳ
+pub fn client_ip(req: &HttpRequest) -> Option<String> {
+    if ENV.trust_proxy_headers {
+        req.connection_info().realip_remote_addr().map(str::to_string)
+    } else {
+        req.peer_addr().map(|addr| addr.ip().to_string())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum TypeClaims {
     RefreshToken,
     AccessToken,
+    PasswordReset,
+    EmailVerification,
+    SignInConfirmation,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,12 +104,27 @@ pub struct Claims {
     pub jti: Option<uuid::Uuid>,
     pub role: UserRole,
     pub _type: Option<TypeClaims>,
+    /// Quyền của API key, chỉ có ở bot principal (không bao giờ nằm trong JWT)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<BotScope>>,
+    /// Conversation duy nhất mà guest được truy cập, chỉ có ở token của guest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<uuid::Uuid>,
 }
 
 impl Claims {
     pub fn new(sub: &uuid::Uuid, role: &UserRole, exp: u64) -> Self {
         let now = chrono::Utc::now().timestamp() as u64;
-        Claims { sub: *sub, iat: now, exp: now + exp, role: role.clone(), jti: None, _type: None }
+        Claims {
+            sub: *sub,
+            iat: now,
+            exp: now + exp,
+            role: role.clone(),
+            jti: None,
+            _type: None,
+            scopes: None,
+            conversation_id: None,
+        }
     }
 
     pub fn with_jti(mut self, jti: uuid::Uuid) -> Self {
@@ -62,19 +137,54 @@ impl Claims {
         self
     }
 
-    pub fn encode(&self, secret: &[u8]) -> Result<String, error::SystemError> {
-        let header = Header::new(Algorithm::HS256);
-        let token = encode(&header, self, &EncodingKey::from_secret(secret))?;
+    pub fn with_scopes(mut self, scopes: Vec<BotScope>) -> Self {
+        self.scopes = Some(scopes);
+        self
+    }
+
+    /// Principal có quyền `scope` hay không; token của user không bị giới hạn scope
+    pub fn has_scope(&self, scope: BotScope) -> bool {
+        self.scopes.as_ref().is_none_or(|scopes| scopes.contains(&scope))
+    }
+
+    pub fn with_conversation(mut self, conversation_id: uuid::Uuid) -> Self {
+        self.conversation_id = Some(conversation_id);
+        self
+    }
+
+    /// Ký bằng key hiện tại, `kid` của key nằm trong header
+    pub fn encode(&self) -> Result<String, error::SystemError> {
+        let keyring = jwt_keys::keyring();
+        let key = keyring.current();
+
+        let mut header = Header::new(key.algorithm);
+        header.kid = Some(key.kid.clone());
+        let token = encode(&header, self, &key.encoding)?;
         Ok(token)
     }
 
-    #[allow(unused)]
-    pub fn decode(token: &str, secret: &[u8]) -> Result<Self, error::SystemError> {
-        let mut validation = Validation::new(Algorithm::HS256);
-        validation.validate_exp = true;
-        validation.validate_nbf = false;
-        let token_data = decode::<Self>(token, &DecodingKey::from_secret(secret), &validation)?;
-        Ok(token_data.claims)
+    /// Verify bằng key có `kid` trong header. Token ký trước khi có `kid` được thử với
+    /// lần lượt mọi key
+    pub fn decode(token: &str) -> Result<Self, error::SystemError> {
+        let keyring = jwt_keys::keyring();
+        let candidates: Vec<&JwtKey> = match decode_header(token)?.kid {
+            Some(kid) => keyring.find(&kid).into_iter().collect(),
+            None => keyring.keys().iter().collect(),
+        };
+
+        let mut last_error = jsonwebtoken::errors::ErrorKind::InvalidSignature.into();
+        for key in candidates {
+            // Chỉ chấp nhận đúng thuật toán của key, tránh nhầm public key thành HMAC secret
+            let mut validation = Validation::new(key.algorithm);
+            validation.validate_exp = true;
+            validation.validate_nbf = false;
+
+            match decode::<Self>(token, &key.decoding, &validation) {
+                Ok(token_data) => return Ok(token_data.claims),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(error::SystemError::JwtError(last_error))
     }
 }
 
@@ -91,7 +201,8 @@ impl<T> FromRequest for ValidatedJson<T>
 where
     T: Validate + serde::de::DeserializeOwned + 'static,
 {
-    type Error = error::Error;
+    /// Lỗi parse đã được `JsonConfig` của app chuyển sang `ErrorBody` (413 / 400)
+    type Error = actix_web::Error;
     type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
 
     fn from_request(
@@ -101,9 +212,8 @@ where
         let fut = web::Json::<T>::from_request(req, payload);
 
         Box::pin(async move {
-            let json = fut.await.map_err(|e| error::Error::BadRequest(e.to_string().into()))?;
-            let model = json.into_inner();
-            model.validate().map_err(|e| error::Error::BadRequest(e.to_string().into()))?;
+            let model = fut.await?.into_inner();
+            model.validate().map_err(error::Error::from)?;
             Ok(ValidatedJson(model))
         })
     }
@@ -125,8 +235,10 @@ where
         let fut = web::Query::<T>::from_request(req, payload);
 
         Box::pin(async move {
-            let query = fut.await.map_err(|e| error::Error::BadRequest(e.to_string().into()))?;
-            query.validate().map_err(|e| error::Error::BadRequest(e.to_string().into()))?;
+            let query = fut
+                .await
+                .map_err(|e| error::Error::bad_request(format!("Invalid query string: {e}")))?;
+            query.validate().map_err(error::Error::from)?;
             Ok(ValidatedQuery(query.into_inner()))
         })
     }
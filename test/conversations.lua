@@ -0,0 +1,7 @@
+-- Benchmark danh sách conversations (một query, participants gom bằng json_agg)
+--   TOKEN=<access_token> wrk -t4 -c64 -d30s -s test/conversations.lua http://localhost:8080
+wrk.method = "GET"
+
+wrk.path = "/api/v1/conversations?limit=30"
+
+wrk.headers["Authorization"] = "Bearer " .. (os.getenv("TOKEN") or "")